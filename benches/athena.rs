@@ -0,0 +1,85 @@
+//! Benchmarks for the hot paths of the crate.
+//!
+//! See the "Performance targets" section of the README for the budgets these are held to.
+
+use std::collections::HashMap;
+
+use athena::agent::{AgentManager, NpcAgent};
+use athena::emotional_response::Emotion;
+use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+use athena::prompt::{PersonaCard, PromptBuilder, PromptContext};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/// Builds a graph with `edges` relationships spread over `edges / 10` entities.
+fn build_graph(edges: usize) -> KnowledgeGraph {
+    let entity_count = (edges / 10).max(1);
+    let mut graph = KnowledgeGraph::new();
+    for i in 0..entity_count {
+        let mut properties = HashMap::new();
+        properties.insert("name".to_string(), format!("Entity {}", i));
+        graph.add_entity(Entity::new(i.to_string(), properties));
+    }
+    for i in 0..edges {
+        let source = (i % entity_count).to_string();
+        let target = ((i * 7 + 1) % entity_count).to_string();
+        graph.add_relationship(Relationship::new(source, target, "knows".to_string(), HashMap::new()));
+    }
+    graph
+}
+
+fn knowledge_graph_queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("knowledge_graph");
+    for edges in [10_000, 100_000] {
+        let graph = build_graph(edges);
+        group.bench_with_input(BenchmarkId::new("get_entity", edges), &graph, |b, graph| {
+            b.iter(|| graph.get_entity(black_box("42")))
+        });
+        group.bench_with_input(BenchmarkId::new("get_relationships", edges), &graph, |b, graph| {
+            b.iter(|| graph.get_relationships(black_box("42")).len())
+        });
+        group.bench_with_input(BenchmarkId::new("relationships_of", edges), &graph, |b, graph| {
+            b.iter(|| graph.relationships_of(black_box("42")).count())
+        });
+    }
+    group.finish();
+}
+
+fn agent_ticking(c: &mut Criterion) {
+    let mut manager = AgentManager::new(0.1);
+    for i in 0..1_000 {
+        let mut agent = NpcAgent::new(&format!("npc_{}", i), vec![]);
+        agent.needs.add_need("hunger", 0.001);
+        agent.needs.add_need("rest", 0.0005);
+        agent.emotions.set_emotion(Emotion::Joy);
+        manager.add_agent(agent);
+    }
+    c.bench_function("agent_manager/advance_1k_agents", |b| {
+        b.iter(|| manager.advance(black_box(0.1)))
+    });
+    c.bench_function("agent_manager/decide_all_1k_agents", |b| b.iter(|| manager.decide_all()));
+
+    let mut agent = NpcAgent::new("decider", vec![]);
+    agent.emotions.set_emotion(Emotion::Fear);
+    agent.emotions.set_intensity(Emotion::Surprise, 0.6);
+    c.bench_function("agent/decide", |b| b.iter(|| agent.decide()));
+}
+
+fn prompt_assembly(c: &mut Criterion) {
+    let mut persona = PersonaCard::new("Brom", "The village blacksmith, gruff but fair.");
+    persona.speaking_style = "Short sentences, smithing metaphors.".to_string();
+    persona.facts = (0..20).map(|i| format!("Fact number {} about the village.", i)).collect();
+    let mut builder = PromptBuilder::new(persona);
+    builder.set_summary("The player has bought two swords and haggled hard both times.");
+    let context = PromptContext {
+        mood: vec!["joy".to_string(), "optimism".to_string()],
+        directives: vec!["Respond in your usual manner.".to_string()],
+        history: (0..10)
+            .map(|i| ("Player".to_string(), format!("Line {} of the conversation.", i)))
+            .collect(),
+        player_input: "Do you have any shields?".to_string(),
+    };
+    c.bench_function("prompt/build", |b| b.iter(|| builder.build(black_box(&context))));
+}
+
+criterion_group!(benches, knowledge_graph_queries, agent_ticking, prompt_assembly);
+criterion_main!(benches);
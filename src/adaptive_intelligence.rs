@@ -6,17 +6,30 @@
 
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
+use crate::symbol::Symbol;
+
 /// Represents a generic state for an NPC. The actual states will be defined externally.
-pub type State = String;
+pub type State = Symbol;
+
+/// The built-in states and the action each calls for.
+pub const STATE_ACTIONS: [(&str, &str); 4] = [
+    ("Idle", "Rest"),
+    ("Alert", "Investigate"),
+    ("Engaged", "Talk"),
+    ("Fleeing", "Run"),
+];
 
 /// Represents an action that the NPC can take.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Action {
     pub name: String,
     pub description: String,
 }
 
 /// Represents the adaptive intelligence of an NPC.
+#[derive(Clone)]
 pub struct AdaptiveIntelligence {
     /// The current state of the NPC.
     current_state: State,
@@ -47,7 +60,7 @@ impl AdaptiveIntelligence {
     /// ```
     pub fn new(actions: Vec<Action>) -> Self {
         AdaptiveIntelligence {
-            current_state: "Idle".to_string(), // Default state
+            current_state: Symbol::new("Idle"), // Default state
             memory: HashMap::new(),
             actions,
         }
@@ -73,7 +86,7 @@ impl AdaptiveIntelligence {
     /// ai.update_state("Alert");
     /// ```
     pub fn update_state(&mut self, new_state: &str) {
-        self.current_state = new_state.to_string();
+        self.current_state = Symbol::new(new_state);
     }
 
     /// Chooses an action based on the current state of the NPC and psychological principles.
@@ -101,12 +114,42 @@ impl AdaptiveIntelligence {
     /// ```
     pub fn choose_action(&self) -> String {
         // Decision-making based on the current state
-        match self.current_state.as_str() {
-            "Idle" => self.select_action("Rest"),
-            "Alert" => self.select_action("Investigate"),
-            "Engaged" => self.select_action("Talk"),
-            "Fleeing" => self.select_action("Run"),
-            _ => "No action available".to_string(),
+        match self.preferred_action() {
+            Some(action_name) => self.select_action(action_name),
+            None => "No action available".to_string(),
+        }
+    }
+
+    /// Gets the actions available to the NPC.
+    pub fn get_actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    /// Returns the name of the action the current state calls for, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::adaptive_intelligence::AdaptiveIntelligence;
+    /// let mut ai = AdaptiveIntelligence::new(vec![]);
+    /// ai.update_state("Fleeing");
+    /// assert_eq!(ai.preferred_action(), Some("Run"));
+    /// ```
+    pub fn preferred_action(&self) -> Option<&'static str> {
+        STATE_ACTIONS
+            .iter()
+            .find(|(state, _)| *state == self.current_state.as_str())
+            .map(|(_, action)| *action)
+    }
+
+    /// Computes the utility multiplier the current state applies to an action.
+    ///
+    /// The action the state calls for is favoured; all other actions are unaffected.
+    pub fn action_modifier(&self, action_name: &str) -> f64 {
+        if self.preferred_action() == Some(action_name) {
+            1.5
+        } else {
+            1.0
         }
     }
 
@@ -182,11 +225,34 @@ impl AdaptiveIntelligence {
         self.memory.get(key)
     }
 
+    /// Iterates over all recorded memories as `(key, value)` pairs.
+    pub fn memories(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.memory.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Iterates over the memories whose key starts with the given prefix (e.g. `"belief:"`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::adaptive_intelligence::AdaptiveIntelligence;
+    /// let mut ai = AdaptiveIntelligence::new(vec![]);
+    /// ai.record_memory("belief:guards", "I no longer trust the guards");
+    /// ai.record_memory("last_interaction", "spoke to player");
+    /// assert_eq!(ai.memories_with_prefix("belief:").count(), 1);
+    /// ```
+    pub fn memories_with_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.memories().filter(move |(key, _)| key.starts_with(prefix))
+    }
+
     /// Gets the current state of the NPC.
     ///
     /// # Returns
     ///
-    /// The current state as a string.
+    /// The current state as an interned `State` symbol.
     ///
     /// # Examples
     ///
@@ -203,7 +269,7 @@ impl AdaptiveIntelligence {
     /// let state = ai.get_current_state();
     /// println!("Current State: {}", state);
     /// ```
-    pub fn get_current_state(&self) -> &String {
+    pub fn get_current_state(&self) -> &State {
         &self.current_state
     }
 }
\ No newline at end of file
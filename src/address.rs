@@ -0,0 +1,170 @@
+//! # Address Module
+//!
+//! This module decides how an NPC addresses someone. A stranger is addressed by title ("Captain
+//! Vance", or "Ser Vance" in a culture that uses honorifics), a friend by first name, a confidant
+//! or partner by nickname, and an enemy by a hostile epithet. Formal cultures keep using titles
+//! with friends, and good standing with the NPC's faction earns a stranger a title even where
+//! titles are not customary.
+//!
+//! The result can be injected into prompts as a directive or filled into authored templates
+//! through the `{address}` placeholder.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::companionship::BondStage;
+use crate::culture::Culture;
+
+/// The standing (personal or with the NPC's faction) at or below which someone is an enemy.
+const HOSTILE_STANDING: f64 = -0.5;
+
+/// The standing at or above which a stranger is addressed by title in any culture.
+const RESPECTED_STANDING: f64 = 0.5;
+
+/// Represents the kind of address used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AddressForm {
+    Title,
+    FirstName,
+    Nickname,
+    Epithet,
+}
+
+/// Represents the person being addressed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Addressee {
+    pub given_name: String,
+    pub family_name: Option<String>,
+    /// Their own title (e.g. "Captain").
+    pub title: Option<String>,
+    /// What the people close to them call them.
+    pub nickname: Option<String>,
+}
+
+impl Addressee {
+    /// Creates a new addressee with only a given name.
+    pub fn new(given_name: &str) -> Self {
+        Addressee {
+            given_name: given_name.to_string(),
+            ..Addressee::default()
+        }
+    }
+}
+
+/// Represents how to address someone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Address {
+    pub form: AddressForm,
+    /// The words to address them with (e.g. "Captain Vance").
+    pub text: String,
+}
+
+impl Address {
+    /// Builds a prompt directive telling the NPC how to address the listener.
+    pub fn directive(&self) -> String {
+        match self.form {
+            AddressForm::Epithet => format!(
+                "You despise the listener; if you address them, call them \"{}\".",
+                self.text
+            ),
+            _ => format!("If you address the listener, call them \"{}\".", self.text),
+        }
+    }
+
+    /// Returns the template placeholders for this address (`{address}`).
+    pub fn placeholders(&self) -> HashMap<String, String> {
+        HashMap::from([("address".to_string(), self.text.clone())])
+    }
+
+    /// Fills the `{address}` placeholder of an authored line.
+    pub fn fill(&self, template: &str) -> String {
+        template.replace("{address}", &self.text)
+    }
+}
+
+/// Computes how an NPC should address someone.
+///
+/// # Arguments
+///
+/// * `addressee` - Who is being addressed.
+/// * `stage` - The relationship stage with them, or `None` if there is no relationship.
+/// * `culture` - The NPC's culture, if any. Its "formality" value (0.7 or more keeps titles
+///   among friends), `honorific`, and `epithets` are used.
+/// * `standing` - How the NPC (or its faction) regards them, between -1.0 and 1.0. At -0.5 or
+///   below they are an enemy whatever the relationship stage.
+///
+/// # Examples
+///
+/// ```
+/// use athena::address::{address, AddressForm, Addressee};
+/// use athena::companionship::BondStage;
+/// use athena::culture::Culture;
+///
+/// let vance = Addressee {
+///     given_name: "Mira".to_string(),
+///     family_name: Some("Vance".to_string()),
+///     title: Some("Captain".to_string()),
+///     nickname: Some("Mouse".to_string()),
+/// };
+/// assert_eq!(address(&vance, None, None, 0.0).text, "Captain Vance");
+/// assert_eq!(address(&vance, Some(BondStage::Friend), None, 0.0).text, "Mira");
+/// assert_eq!(address(&vance, Some(BondStage::Confidant), None, 0.0).text, "Mouse");
+///
+/// let mut culture = Culture::new("Northmen");
+/// culture.epithets.push("oathbreaker".to_string());
+/// let enemy = address(&vance, Some(BondStage::Friend), Some(&culture), -0.8);
+/// assert_eq!(enemy.form, AddressForm::Epithet);
+/// assert_eq!(enemy.fill("Begone, {address}!"), "Begone, oathbreaker!");
+/// ```
+pub fn address(
+    addressee: &Addressee,
+    stage: Option<BondStage>,
+    culture: Option<&Culture>,
+    standing: f64,
+) -> Address {
+    if standing <= HOSTILE_STANDING {
+        let epithet = culture
+            .and_then(|culture| culture.epithets.first())
+            .map_or("wretch", |epithet| epithet.as_str());
+        return Address {
+            form: AddressForm::Epithet,
+            text: epithet.to_string(),
+        };
+    }
+    let formal = culture.is_some_and(|culture| culture.get_value("formality") >= 0.7);
+    match stage {
+        Some(BondStage::Confidant | BondStage::Partner) if addressee.nickname.is_some() => Address {
+            form: AddressForm::Nickname,
+            text: addressee.nickname.clone().unwrap_or_default(),
+        },
+        Some(BondStage::Confidant | BondStage::Partner) => first_name(addressee),
+        Some(BondStage::Friend) if !formal => first_name(addressee),
+        _ => {
+            let honorific = culture.and_then(|culture| culture.honorific.as_ref());
+            let respected = standing >= RESPECTED_STANDING || formal;
+            let title = addressee
+                .title
+                .as_ref()
+                .or(honorific)
+                .filter(|_| addressee.title.is_some() || respected);
+            let name = addressee.family_name.as_ref().unwrap_or(&addressee.given_name);
+            let text = match title {
+                Some(title) => format!("{} {}", title, name),
+                None => "traveler".to_string(),
+            };
+            Address {
+                form: AddressForm::Title,
+                text,
+            }
+        }
+    }
+}
+
+/// Addresses someone by their given name.
+fn first_name(addressee: &Addressee) -> Address {
+    Address {
+        form: AddressForm::FirstName,
+        text: addressee.given_name.clone(),
+    }
+}
@@ -0,0 +1,2663 @@
+//! # Agent Module
+//!
+//! This module brings the individual NPC subsystems together. An `NpcAgent` owns an NPC's
+//! personality, emotions, adaptive intelligence, knowledge, needs, schedule, and reflections,
+//! while the `AgentManager` advances a population of agents with game time.
+//!
+//! The manager runs a fixed internal timestep so simulation results do not depend on frame rate.
+//! Long frames are caught up with several steps (up to a configurable cap), and within each step
+//! every agent runs the update phases in the same order: needs, emotions, schedule, and finally
+//! memory consolidation. Each frame is profiled per phase, and with a frame budget set,
+//! consolidation waits for a later frame once the budget is spent.
+//!
+//! Agents far from the player run at reduced fidelity: instead of every step, they are advanced
+//! in larger abstract chunks that only keep needs, emotions, and schedules roughly up to date;
+//! their consolidation still waits for a frame with budget to spare. When the player approaches,
+//! the pending time is flushed so the agent rehydrates to full fidelity seamlessly.
+//!
+//! For debug overlays and bug reports, `NpcAgent::debug_report` captures a serializable snapshot
+//! of an agent's internals, including its recent decisions and their utility scores. Every
+//! decision carries an `Explanation` listing the emotion, personality, state, and need factors
+//! that produced its score.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::adaptive_intelligence::{Action, AdaptiveIntelligence};
+use crate::address::{self, Address, Addressee};
+use crate::annoyance::AnnoyancePolicy;
+use crate::appraisal::{AppraisalAccumulator, AppraisalEvent, AppraisalRegistry};
+use crate::cancellation::CancellationToken;
+use crate::checkpoint::{CheckpointPolicy, ConversationOutcome, SessionNotes};
+use crate::companionship::{self, BondStage, Companionship};
+use crate::crime::LawSystem;
+use crate::coping::Coping;
+use crate::conversation_goal::{ConversationGoal, GoalStatus};
+use crate::culture::Culture;
+use crate::emotion_timeline::{EmotionSample, EmotionTimeline, SessionTrace};
+use crate::emotional_response::{Emotion, EmotionalResponse};
+use crate::ending::{self, ConversationEnd, EndReason, EndingPolicy};
+use crate::gifts::{self, GiftReaction};
+use crate::interaction::{self, InteractionSummary};
+use crate::interlocutor::InterlocutorContext;
+use crate::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+use crate::ledger::{Ledger, LedgerEntry, LedgerKind};
+use crate::morality::MoralValues;
+use crate::needs::Needs;
+use crate::norms::{Conduct, NormSet, NormViolation};
+use crate::perception::{PerceptionPolicy, PlayerAction};
+use crate::personality::{Personality, Trait};
+use crate::physiology::BodyState;
+use crate::postprocess::Filter;
+use crate::profiling::{FrameProfile, FrameProfiler, WorkPriority};
+use crate::promises::{self, Promise, PromiseBook, PromiseStatus};
+use crate::quota::{QuotaStatus, QuotaTracker};
+use crate::reflection::Reflector;
+use crate::relationship::{self, with_article, RelationshipLabels, RelationshipMetrics};
+use crate::reputation::{DeedCategory, Disposition};
+use crate::schedule::{Replan, Schedule, SECONDS_PER_DAY};
+use crate::simulation::SplitMix;
+use crate::symbol::Symbol;
+use crate::quest::{self, Quest, QuestHint, QuestOutcome};
+use crate::knowledge_query::{KnowledgeAnswer, SharingPolicy};
+use crate::topic::Topic;
+use crate::trading::{self, Haggle};
+use crate::utility::{self, Explanation, ScoreBatch, UtilityCandidate};
+use crate::variation::RepetitionTracker;
+
+/// The number of recent decisions kept for debugging.
+const MAX_RECENT_DECISIONS: usize = 16;
+
+/// The number of memories of each kind included in a debug report.
+const REPORT_MEMORY_COUNT: usize = 5;
+
+/// The number of emotion timeline samples included in a debug report.
+const REPORT_EMOTION_COUNT: usize = 10;
+
+/// The strength below which a memory counts as minor, and may differ between variants.
+const MINOR_MEMORY_STRENGTH: f64 = 0.5;
+
+/// The base score of the action a goal proposes.
+const GOAL_URGENCY: f64 = 0.8;
+
+/// What proposed a candidate action.
+#[derive(Debug, Clone, Copy)]
+enum Proposer<'a> {
+    /// The action registry, for the current emotion.
+    Emotion,
+    /// An unsatisfied need.
+    Need(&'a str),
+    /// A goal the agent pursues.
+    Goal(&'a str),
+}
+
+/// Represents one phase of an agent update. Phases always run in `UpdatePhase::ORDER`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdatePhase {
+    /// Needs decay and the body recovers.
+    Needs,
+    /// Emotion intensities decay and the previous tick's appraisals are cleared.
+    Emotions,
+    /// The schedule clock advances.
+    Schedule,
+    /// Pending experiences are consolidated into beliefs via reflection.
+    Consolidation,
+}
+
+impl UpdatePhase {
+    /// The guaranteed order in which phases are run within a step.
+    pub const ORDER: [UpdatePhase; 4] = [
+        UpdatePhase::Needs,
+        UpdatePhase::Emotions,
+        UpdatePhase::Schedule,
+        UpdatePhase::Consolidation,
+    ];
+
+    /// Gets the name the phase is profiled under.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdatePhase::Needs => "needs",
+            UpdatePhase::Emotions => "emotions",
+            UpdatePhase::Schedule => "schedule",
+            UpdatePhase::Consolidation => "consolidation",
+        }
+    }
+
+    /// Gets the priority of the phase. Consolidation can wait for a frame with time to spare.
+    pub fn priority(&self) -> WorkPriority {
+        match self {
+            UpdatePhase::Consolidation => WorkPriority::Low,
+            _ => WorkPriority::Essential,
+        }
+    }
+}
+
+/// Represents the level of detail at which an agent is simulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fidelity {
+    /// Every step runs every phase.
+    Full,
+    /// The agent is advanced in infrequent, coarse chunks.
+    Reduced,
+}
+
+/// Represents one action decision made by an agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    /// The game time at which the decision was made.
+    pub game_time: f64,
+    /// The chosen action.
+    pub action: String,
+    /// The utility score of the chosen action.
+    pub score: f64,
+    /// All candidates that were considered, with their scores.
+    pub candidates: Vec<UtilityCandidate>,
+    /// Why the chosen action scored the way it did.
+    pub explanation: Explanation,
+}
+
+/// Represents the result of an agent processing one player turn.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentOutput {
+    /// False if the agent refused to engage because it is still on cooldown for this partner.
+    pub engaged: bool,
+    /// The action the agent chose, if it engaged.
+    pub action: Option<String>,
+    /// A line the agent says right away (e.g. a farewell), if any.
+    pub dialogue: Option<String>,
+    /// Set when the agent ends the conversation.
+    pub conversation_end: Option<ConversationEnd>,
+    /// The social norms the line violated.
+    pub violations: Vec<NormViolation>,
+    /// The agent's conversation goals with the speaker, as assessed after the line.
+    pub goals: Vec<ConversationGoal>,
+    /// The promises the speaker made in the line.
+    pub promises: Vec<Promise>,
+    /// The outcome of the conversation, if the agent ended it and it was significant.
+    pub outcome: Option<ConversationOutcome>,
+}
+
+/// Represents a serializable snapshot of an agent's internals for debug overlays and bug reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugReport {
+    pub id: Symbol,
+    pub game_time: f64,
+    pub fidelity: Fidelity,
+    pub state: Symbol,
+    pub emotion: Emotion,
+    /// The intensity of each active emotion, strongest first.
+    pub emotion_vector: Vec<(Emotion, f64)>,
+    /// Labels of the active dyads, strongest first.
+    pub dyads: Vec<String>,
+    /// The satisfaction of each need.
+    pub needs: Vec<(String, f64)>,
+    /// The activity of the current schedule block, if any.
+    pub activity: Option<String>,
+    /// The strongest emotional memories as `(trigger, emotion, strength)`.
+    pub top_emotional_memories: Vec<(String, Emotion, f64)>,
+    /// A sample of the agent's recorded memories as `(key, value)`, in key order.
+    pub top_memories: Vec<(String, String)>,
+    pub goals: Vec<String>,
+    pub pending_dialogue: Vec<String>,
+    /// The most recent decisions, oldest first.
+    pub recent_decisions: Vec<Decision>,
+    /// The most recent emotion timeline samples, oldest first.
+    pub recent_emotions: Vec<EmotionSample>,
+}
+
+/// Represents a complete NPC, combining all of its subsystems.
+pub struct NpcAgent {
+    pub id: Symbol,
+    /// The position of the NPC in the game world, used for level-of-detail decisions.
+    pub position: (f64, f64),
+    pub personality: Personality,
+    /// How much the agent cares about care, fairness, loyalty, authority, and liberty.
+    pub morals: MoralValues,
+    /// The pain, fatigue, and intoxication the game has inflicted on the agent.
+    pub body: BodyState,
+    pub emotions: EmotionalResponse,
+    /// The emotional appraisals of this tick's events, combined into one reaction.
+    pub appraisals: AppraisalAccumulator,
+    pub intelligence: AdaptiveIntelligence,
+    pub knowledge: KnowledgeGraph,
+    pub needs: Needs,
+    pub schedule: Schedule,
+    pub reflector: Reflector,
+    /// The history of the agent's emotional states.
+    pub timeline: EmotionTimeline,
+    /// How the agent copes with sustained negative emotion.
+    pub coping: Coping,
+    /// The goals the NPC is currently pursuing.
+    pub goals: Vec<String>,
+    /// The action each goal has the NPC take, for goals that call for one (e.g. "Patrol" to
+    /// keep watch).
+    pub goal_actions: HashMap<String, String>,
+    /// The multiplier the NPC's faction applies to its prices (see `Faction::price_factor`).
+    pub faction_price_factor: f64,
+    /// The favors the agent is owed, the debts it owes, and the grudges it holds.
+    pub ledger: Ledger,
+    /// The promises made to the agent.
+    pub promises: PromiseBook,
+    /// When a finished conversation is significant enough to produce an outcome.
+    pub checkpoint_policy: CheckpointPolicy,
+    /// Recently said lines, used to avoid repetition.
+    pub repetition: RepetitionTracker,
+    /// When the agent ends conversations.
+    pub ending_policy: EndingPolicy,
+    /// How the agent grows annoyed with partners who pester it.
+    pub annoyance_policy: AnnoyancePolicy,
+    /// The social norms the agent expects others to respect.
+    pub norms: NormSet,
+    /// The cultural background of the agent, if any.
+    pub culture: Option<Culture>,
+    /// How the agent's relationships are labelled.
+    pub relationship_labels: RelationshipLabels,
+    /// How the agent appraises the actions it perceives.
+    pub perception_policy: PerceptionPolicy,
+    /// The kind of NPC the agent is (e.g. "merchant"), which selects its custom appraisal rules.
+    pub archetype: Option<String>,
+    /// The game's custom appraisal rules, usually shared by every agent.
+    pub appraisal_rules: AppraisalRegistry,
+    /// Per-partner conversation history, relationship scores, and memories.
+    interlocutors: HashMap<Symbol, InterlocutorContext>,
+    /// The partner the agent is currently attending to, if any.
+    focus: Option<Symbol>,
+    /// Dialogue lines waiting to be delivered by the game.
+    pending_dialogue: VecDeque<String>,
+    /// The most recent decisions, oldest first.
+    recent_decisions: VecDeque<Decision>,
+    /// The trace of the session being recorded, if any.
+    trace: Option<SessionTrace>,
+    /// The game time this agent has been simulated up to, in seconds.
+    game_time: f64,
+    /// The level of detail the agent is currently simulated at.
+    fidelity: Fidelity,
+    /// Game time accumulated while at reduced fidelity that has not been simulated yet.
+    pending_dt: f64,
+    /// Whether a coarse tick skipped the low-priority phases, which then run when the
+    /// manager's frame budget allows.
+    consolidation_due: bool,
+    /// The token of the scene the agent is in; cancelling it cancels the agent's work too.
+    scene: CancellationToken,
+    /// The token the agent's in-flight work runs under, a child of the scene token.
+    work: CancellationToken,
+}
+
+impl Clone for NpcAgent {
+    /// Clones the agent into the same scene. The clone's work runs under its own token, so
+    /// cancelling one agent's work leaves the other's alone.
+    fn clone(&self) -> Self {
+        NpcAgent {
+            id: self.id.clone(),
+            position: self.position,
+            personality: self.personality.clone(),
+            morals: self.morals,
+            body: self.body.clone(),
+            emotions: self.emotions.clone(),
+            appraisals: self.appraisals.clone(),
+            intelligence: self.intelligence.clone(),
+            knowledge: self.knowledge.clone(),
+            needs: self.needs.clone(),
+            schedule: self.schedule.clone(),
+            reflector: self.reflector.clone(),
+            timeline: self.timeline.clone(),
+            coping: self.coping.clone(),
+            goals: self.goals.clone(),
+            goal_actions: self.goal_actions.clone(),
+            faction_price_factor: self.faction_price_factor,
+            ledger: self.ledger.clone(),
+            promises: self.promises.clone(),
+            checkpoint_policy: self.checkpoint_policy,
+            repetition: self.repetition.clone(),
+            ending_policy: self.ending_policy.clone(),
+            annoyance_policy: self.annoyance_policy.clone(),
+            norms: self.norms.clone(),
+            culture: self.culture.clone(),
+            relationship_labels: self.relationship_labels.clone(),
+            perception_policy: self.perception_policy.clone(),
+            archetype: self.archetype.clone(),
+            appraisal_rules: self.appraisal_rules.clone(),
+            interlocutors: self.interlocutors.clone(),
+            focus: self.focus.clone(),
+            pending_dialogue: self.pending_dialogue.clone(),
+            recent_decisions: self.recent_decisions.clone(),
+            trace: self.trace.clone(),
+            game_time: self.game_time,
+            fidelity: self.fidelity,
+            pending_dt: self.pending_dt,
+            consolidation_due: self.consolidation_due,
+            scene: self.scene.clone(),
+            work: self.scene.child_token(),
+        }
+    }
+}
+
+impl NpcAgent {
+    /// Creates a new agent with default subsystems and the given actions.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique identifier for the agent.
+    /// * `actions` - A vector of actions that the NPC can take.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::adaptive_intelligence::Action;
+    ///
+    /// let actions = vec![
+    ///     Action { name: "Talk".to_string(), description: "Engage in conversation.".to_string() },
+    /// ];
+    /// let agent = NpcAgent::new("blacksmith", actions);
+    /// assert_eq!(agent.id, "blacksmith");
+    /// ```
+    pub fn new(id: &str, actions: Vec<Action>) -> Self {
+        let scene = CancellationToken::new();
+        NpcAgent {
+            id: Symbol::new(id),
+            position: (0.0, 0.0),
+            personality: Personality::new(),
+            morals: MoralValues::default(),
+            body: BodyState::default(),
+            emotions: EmotionalResponse::new(),
+            appraisals: AppraisalAccumulator::default(),
+            intelligence: AdaptiveIntelligence::new(actions),
+            knowledge: KnowledgeGraph::new(),
+            needs: Needs::new(),
+            schedule: Schedule::new(),
+            reflector: Reflector::new(10),
+            timeline: EmotionTimeline::default(),
+            coping: Coping::default(),
+            goals: Vec::new(),
+            goal_actions: HashMap::new(),
+            faction_price_factor: 1.0,
+            ledger: Ledger::new(),
+            promises: PromiseBook::new(),
+            checkpoint_policy: CheckpointPolicy::default(),
+            repetition: RepetitionTracker::default(),
+            ending_policy: EndingPolicy::default(),
+            annoyance_policy: AnnoyancePolicy::default(),
+            norms: NormSet::default(),
+            culture: None,
+            relationship_labels: RelationshipLabels::default(),
+            perception_policy: PerceptionPolicy::default(),
+            archetype: None,
+            appraisal_rules: AppraisalRegistry::new(),
+            interlocutors: HashMap::new(),
+            focus: None,
+            pending_dialogue: VecDeque::new(),
+            recent_decisions: VecDeque::new(),
+            trace: None,
+            game_time: 0.0,
+            fidelity: Fidelity::Full,
+            pending_dt: 0.0,
+            consolidation_due: false,
+            scene: scene.clone(),
+            work: scene.child_token(),
+        }
+    }
+
+    /// Spawns a variant of the agent, for populating crowds of guards or villagers that are not
+    /// identical.
+    ///
+    /// The variant is a copy of the agent with:
+    ///
+    /// - a new identity, `<id>_<seed>`, under which its knowledge about itself is renamed;
+    /// - each personality trait moved randomly by up to a quarter of `variation_strength`;
+    /// - its minor memories (the triggers its emotional response remembers that have faded
+    ///   below half strength) varied: each takes part with probability `variation_strength`,
+    ///   and is then either forgotten or made stronger or weaker by up to a quarter of
+    ///   `variation_strength`, so variants remember slightly different things. Every memory
+    ///   keeps its own emotion;
+    /// - no decision history, session trace, or acquaintances.
+    ///
+    /// The same seed always gives the same variant.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Picks the variation.
+    /// * `variation_strength` - How different the variant is (between 0.0 and 1.0).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut guard = NpcAgent::new("guard", vec![]);
+    /// guard.personality.set_conscientiousness(0.8);
+    /// guard.learn_preferences("I love ale.");
+    /// guard.emotions.record_memory("ambush", Emotion::Fear);
+    /// guard.interlocutor("alice").adjust_trust(0.5);
+    ///
+    /// let first = guard.spawn_variant(1, 0.5);
+    /// assert_eq!(first.id, "guard_1");
+    /// assert!((first.personality.conscientiousness - 0.8).abs() <= 0.125);
+    /// assert!(first.knowledge.get_entity("guard").is_none());
+    /// assert_eq!(first.knowledge.get_relationships("guard_1").len(), 1);
+    /// assert_eq!(first.emotions.get_memory("ambush"), Some(&Emotion::Fear));
+    /// assert_eq!(first.emotions.get_memory_strength("ambush"), 1.0);
+    /// assert!(first.get_interlocutor("alice").is_none());
+    ///
+    /// let second = guard.spawn_variant(2, 0.5);
+    /// assert_ne!(first.personality.openness, second.personality.openness);
+    /// assert_eq!(guard.spawn_variant(1, 0.5).personality.openness, first.personality.openness);
+    /// ```
+    pub fn spawn_variant(&self, seed: u64, variation_strength: f64) -> NpcAgent {
+        let strength = variation_strength.clamp(0.0, 1.0);
+        let mut rng = SplitMix(seed);
+        let mut variant = self.clone();
+        let id = format!("{}_{}", self.id, seed);
+        variant.knowledge.rename_entity(self.id.as_str(), &id);
+        variant.id = Symbol::new(&id);
+        variant.recent_decisions.clear();
+        variant.trace = None;
+        variant.interlocutors.clear();
+        variant.focus = None;
+
+        for personality_trait in Trait::ALL {
+            let jitter = (2.0 * rng.fraction() - 1.0) * 0.25 * strength;
+            let value = variant.personality.get_trait(personality_trait) + jitter;
+            variant.personality.set_trait(personality_trait, value);
+        }
+
+        let mut minor: Vec<(&str, f64)> = self
+            .emotions
+            .memories()
+            .map(|(trigger, _)| (trigger, self.emotions.get_memory_strength(trigger)))
+            .filter(|(_, memory_strength)| *memory_strength < MINOR_MEMORY_STRENGTH)
+            .collect();
+        minor.sort_by(|a, b| a.0.cmp(b.0));
+        for (trigger, memory_strength) in minor {
+            if rng.fraction() >= strength {
+                continue;
+            }
+            if rng.fraction() < 0.5 {
+                variant.emotions.forget_memory(trigger);
+            } else {
+                let jitter = (2.0 * rng.fraction() - 1.0) * 0.25 * strength;
+                variant.emotions.set_memory_strength(trigger, memory_strength + jitter);
+            }
+        }
+        variant
+    }
+
+    /// Runs a single update phase for this agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `phase` - The phase to run.
+    /// * `dt` - The elapsed game time in seconds.
+    pub fn run_phase(&mut self, phase: UpdatePhase, dt: f64) {
+        match phase {
+            UpdatePhase::Needs => {
+                self.needs.update(dt);
+                self.body.update(dt);
+            }
+            UpdatePhase::Emotions => {
+                self.emotions.update(dt);
+                self.appraisals.clear();
+            }
+            UpdatePhase::Schedule => self.schedule.update(dt),
+            UpdatePhase::Consolidation => {
+                self.consolidation_due = false;
+                if self.reflector.should_reflect() {
+                    self.note_mood();
+                    self.reflector.reflect(&mut self.intelligence);
+                }
+            }
+        }
+    }
+
+    /// Advances this agent by the given amount of game time, running every phase in order.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The elapsed game time in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// let mut agent = NpcAgent::new("baker", vec![]);
+    /// agent.needs.add_need("rest", 0.01);
+    /// agent.tick(10.0);
+    /// assert_eq!(agent.needs.get_satisfaction("rest"), Some(0.9));
+    /// ```
+    pub fn tick(&mut self, dt: f64) {
+        for phase in UpdatePhase::ORDER {
+            self.run_phase(phase, dt);
+        }
+        self.advance_clock(dt);
+    }
+
+    /// Moves the agent's game time forward, samples its emotions into the timeline, and
+    /// re-evaluates how it copes.
+    fn advance_clock(&mut self, dt: f64) {
+        self.game_time += dt;
+        self.timeline.observe(self.game_time, &self.emotions);
+        if let Some(trace) = &mut self.trace {
+            let state = self.intelligence.get_current_state();
+            trace.record(self.game_time, state.as_str(), &self.emotions);
+        }
+        self.ledger.expire(self.game_time);
+        for promise in self.promises.expire(self.game_time) {
+            self.react_to_broken_promise(&promise);
+        }
+        let previous = self.coping.get_strategy();
+        let strategy = self.coping.evaluate(&self.personality, &self.timeline, self.game_time);
+        if strategy != previous {
+            if let Some(previous) = previous {
+                self.goals.retain(|goal| goal != previous.goal());
+            }
+            if let Some(strategy) = strategy {
+                self.goals.push(strategy.goal().to_string());
+            }
+        }
+    }
+
+    /// Describes how the agent has felt over the last day, for dialogue and its journal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut agent = NpcAgent::new("guard", vec![]);
+    /// agent.emotions.set_decay_rate(0.0);
+    /// agent.emotions.set_emotion(Emotion::Fear);
+    /// for _ in 0..12 {
+    ///     agent.tick(3_600.0);
+    /// }
+    /// assert_eq!(agent.recent_mood().as_deref(), Some("I've been on edge lately."));
+    /// ```
+    pub fn recent_mood(&self) -> Option<String> {
+        self.timeline.mood_remark(self.game_time, SECONDS_PER_DAY)
+    }
+
+    /// Starts recording every emotion and the agent's state on every tick, replacing any trace
+    /// being recorded. The current state is the first frame.
+    pub fn start_trace(&mut self) {
+        let mut trace = SessionTrace::new(self.id.as_str());
+        let state = self.intelligence.get_current_state();
+        trace.record(self.game_time, state.as_str(), &self.emotions);
+        self.trace = Some(trace);
+    }
+
+    /// Gets the trace being recorded, if any.
+    pub fn get_trace(&self) -> Option<&SessionTrace> {
+        self.trace.as_ref()
+    }
+
+    /// Stops recording and returns the trace, for export.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut agent = NpcAgent::new("guard", vec![]);
+    /// agent.start_trace();
+    /// agent.emotions.set_intensity(Emotion::Fear, 0.8);
+    /// agent.tick(60.0);
+    /// agent.tick(60.0);
+    ///
+    /// let trace = agent.finish_trace().unwrap();
+    /// assert_eq!(trace.len(), 3);
+    /// assert_eq!(trace.frames[1].emotion, Emotion::Fear);
+    /// assert!(trace.frames[2].intensities["Fear"] < 0.8);
+    /// assert!(trace.to_json().unwrap().contains("\"npc\": \"guard\""));
+    /// assert!(agent.get_trace().is_none());
+    /// ```
+    pub fn finish_trace(&mut self) -> Option<SessionTrace> {
+        self.trace.take()
+    }
+
+    /// Builds the prompt directives for the agent's current state of mind and body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut agent = NpcAgent::new("widow", vec![]);
+    /// agent.personality.set_extraversion(0.9);
+    /// agent.emotions.set_decay_rate(0.0);
+    /// agent.emotions.set_emotion(Emotion::Sadness);
+    /// for _ in 0..3 {
+    ///     agent.tick(1_800.0);
+    /// }
+    /// assert_eq!(agent.goals, vec!["Find someone to confide in".to_string()]);
+    /// assert!(agent.mood_directives()[0].starts_with("You are looking for support"));
+    /// ```
+    pub fn mood_directives(&self) -> Vec<String> {
+        let mut directives = Vec::new();
+        if let Some(strategy) = self.coping.get_strategy() {
+            directives.push(strategy.directive().to_string());
+        }
+        if let Some(mood) = self.recent_mood() {
+            directives.push(format!("If it comes up, you might say: \"{}\"", mood));
+        }
+        directives.extend(self.body.directives());
+        directives
+    }
+
+    /// Tells the reflector how the agent has felt lately, for its next journal entry.
+    pub(crate) fn note_mood(&mut self) {
+        let mood = self.recent_mood();
+        self.reflector.set_mood(mood);
+    }
+
+    /// Gets the game time this agent has been simulated up to, in seconds.
+    pub fn get_game_time(&self) -> f64 {
+        self.game_time
+    }
+
+    /// Gets the context for a conversation partner, creating it on first contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `partner_id` - The ID of the player (or NPC) the agent is interacting with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// let mut agent = NpcAgent::new("innkeeper", vec![]);
+    /// agent.interlocutor("alice").adjust_affinity(0.5);
+    /// agent.interlocutor("bob").adjust_affinity(-0.5);
+    /// assert_eq!(agent.get_interlocutor("alice").unwrap().affinity, 0.5);
+    /// assert_eq!(agent.get_interlocutor("bob").unwrap().affinity, -0.5);
+    /// ```
+    pub fn interlocutor(&mut self, partner_id: &str) -> &mut InterlocutorContext {
+        let npc_id = self.id.clone();
+        self.interlocutors
+            .entry(Symbol::new(partner_id))
+            .or_insert_with(|| InterlocutorContext::new(&npc_id, partner_id))
+    }
+
+    /// Gets the context for a conversation partner, if the agent has met them.
+    pub fn get_interlocutor(&self, partner_id: &str) -> Option<&InterlocutorContext> {
+        self.interlocutors.get(partner_id)
+    }
+
+    /// Iterates over all known conversation partners and their contexts.
+    pub fn interlocutors(&self) -> impl Iterator<Item = (&Symbol, &InterlocutorContext)> {
+        self.interlocutors.iter()
+    }
+
+    /// Records a deed a partner was seen doing, at the agent's current game time.
+    ///
+    /// The magnitude is weighted by the agent's moral values and, if the agent has a culture, by
+    /// how much the culture cares about the kind of deed.
+    ///
+    /// # Arguments
+    ///
+    /// * `partner_id` - Who did the deed.
+    /// * `category` - The kind of deed.
+    /// * `magnitude` - How significant the deed was.
+    /// * `description` - A short description of the deed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::reputation::DeedCategory;
+    ///
+    /// let mut agent = NpcAgent::new("merchant", vec![]);
+    /// agent.record_deed("alice", DeedCategory::Theft, 2.0, "stole an apple");
+    /// assert!(agent.disposition_towards("alice").suspicion > 0.5);
+    /// ```
+    pub fn record_deed(
+        &mut self,
+        partner_id: &str,
+        category: DeedCategory,
+        magnitude: f64,
+        description: &str,
+    ) {
+        let game_time = self.game_time;
+        let weight = self
+            .culture
+            .as_ref()
+            .map_or(1.0, |culture| culture.appraisal_weight(category));
+        let magnitude = magnitude * weight * self.morals.deed_weight(category);
+        self.interlocutor(partner_id)
+            .reputation
+            .record(category, magnitude, game_time, description);
+        let event = AppraisalEvent::new(&format!("deed.{:?}", category), description)
+            .by(partner_id)
+            .with_magnitude(magnitude);
+        self.appraise_event(&event);
+    }
+
+    /// Records a favor, debt, or grudge with a partner in the agent's ledger, stamped with the
+    /// current game time.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - What kind of entry it is.
+    /// * `partner_id` - Who it is about.
+    /// * `what` - What happened (e.g. "that potion").
+    /// * `magnitude` - How much it matters.
+    /// * `lifetime` - How long until it is forgotten, in seconds of game time, or `None` if never.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::ledger::LedgerKind;
+    ///
+    /// let mut herbalist = NpcAgent::new("herbalist", vec![]);
+    /// herbalist.record_ledger(LedgerKind::Favor, "alice", "that potion", 0.5, Some(3_600.0));
+    /// assert_eq!(
+    ///     herbalist.ledger_directives("alice"),
+    ///     vec!["They owe you for that potion; you may remind them.".to_string()]
+    /// );
+    /// herbalist.tick(7_200.0);
+    /// assert!(herbalist.ledger.is_empty());
+    /// ```
+    pub fn record_ledger(
+        &mut self,
+        kind: LedgerKind,
+        partner_id: &str,
+        what: &str,
+        magnitude: f64,
+        lifetime: Option<f64>,
+    ) {
+        self.ledger.record(LedgerEntry {
+            kind,
+            counterpart: partner_id.to_string(),
+            what: what.to_string(),
+            magnitude,
+            recorded_at: self.game_time,
+            expires_at: lifetime.map(|lifetime| self.game_time + lifetime),
+        });
+    }
+
+    /// Builds the prompt directives for the agent's active ledger entries with a partner.
+    pub fn ledger_directives(&self, partner_id: &str) -> Vec<String> {
+        self.ledger.directives(partner_id, self.game_time)
+    }
+
+    /// Records a promise a partner made to the agent, stamped with the current game time.
+    ///
+    /// # Arguments
+    ///
+    /// * `partner_id` - Who made the promise.
+    /// * `what` - What they promised (e.g. "bring back the lantern").
+    /// * `within` - How long they have to keep it, in seconds of game time, or `None` if there
+    ///   is no deadline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut innkeeper = NpcAgent::new("innkeeper", vec![]);
+    /// innkeeper.record_promise("alice", "bring back the lantern", Some(3_600.0));
+    /// innkeeper.record_promise("alice", "pay for the room", Some(60.0));
+    /// assert!(innkeeper.keep_promise("alice", "the lantern"));
+    /// assert_eq!(innkeeper.emotions.get_emotion(), &Emotion::Joy);
+    ///
+    /// innkeeper.tick(120.0);
+    /// assert!(innkeeper.get_interlocutor("alice").unwrap().trust < 0.1);
+    /// assert_eq!(
+    ///     innkeeper.take_dialogue().unwrap(),
+    ///     "You promised to bring back the lantern, and you did. Thank you."
+    /// );
+    /// assert_eq!(
+    ///     innkeeper.take_dialogue().unwrap(),
+    ///     "You promised to pay for the room. I trusted you."
+    /// );
+    /// ```
+    pub fn record_promise(&mut self, partner_id: &str, what: &str, within: Option<f64>) {
+        self.promises.record(Promise {
+            promiser: partner_id.to_string(),
+            what: what.to_string(),
+            made_at: self.game_time,
+            deadline: within.map(|within| self.game_time + within),
+            status: PromiseStatus::Pending,
+            reminded: false,
+        });
+    }
+
+    /// Records the promises a partner makes in a line, found with `promises::detect_promises`.
+    ///
+    /// # Returns
+    ///
+    /// The promises recorded.
+    pub fn note_promises(&mut self, partner_id: &str, line: &str) -> Vec<Promise> {
+        let found = promises::detect_promises(line);
+        for promise in &found {
+            let within = promise.within_hours.map(|hours| hours * 3_600.0);
+            self.record_promise(partner_id, &promise.what, within);
+        }
+        let pending = self.promises.pending_with(partner_id);
+        found
+            .iter()
+            .filter_map(|promise| pending.iter().find(|p| p.what == promise.what))
+            .map(|promise| (*promise).clone())
+            .collect()
+    }
+
+    /// Marks a partner's pending promise as kept. The agent is grateful: its affinity and trust
+    /// towards the partner grow, and it thanks them.
+    ///
+    /// # Returns
+    ///
+    /// True if a pending promise about the subject was found.
+    pub fn keep_promise(&mut self, partner_id: &str, what: &str) -> bool {
+        let Some(promise) = self.promises.keep(partner_id, what) else {
+            return false;
+        };
+        let context = self.interlocutor(partner_id);
+        context.adjust_affinity(0.1);
+        context.adjust_trust(0.15);
+        self.appraise(Emotion::Joy, 0.5, &format!("{} kept a promise", partner_id));
+        self.queue_dialogue(&promise.follow_up());
+        true
+    }
+
+    /// Reacts to a broken promise with disappointment, lost affinity and trust, and a reproach.
+    fn react_to_broken_promise(&mut self, promise: &Promise) {
+        let context = self.interlocutor(&promise.promiser);
+        context.adjust_affinity(-0.15);
+        context.adjust_trust(-0.25);
+        let description = format!("broke a promise to {}", promise.what);
+        self.record_deed(&promise.promiser, DeedCategory::Deception, 0.5, &description);
+        let source = format!("{} {}", promise.promiser, description);
+        self.appraise(Emotion::Sadness, 0.5, &source);
+        self.queue_dialogue(&promise.follow_up());
+    }
+
+    /// Builds the prompt directives for the promises a partner made to the agent.
+    pub fn promise_directives(&self, partner_id: &str) -> Vec<String> {
+        self.promises.directives(partner_id, self.game_time)
+    }
+
+    /// Gets the agent's current disposition towards a partner based on their deeds.
+    pub fn disposition_towards(&self, partner_id: &str) -> Disposition {
+        self.get_interlocutor(partner_id)
+            .map(|context| context.reputation.disposition(self.game_time))
+            .unwrap_or_default()
+    }
+
+    /// Reacts emotionally to an event.
+    ///
+    /// The appraisal is combined with every other appraisal of the current tick, so an NPC who
+    /// is ambushed and insulted at once reacts to both instead of only to the last event. Its
+    /// intensity is scaled by the agent's body state.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion the event provokes.
+    /// * `intensity` - How strongly it provokes it (between 0.0 and 1.0).
+    /// * `source` - What caused the appraisal.
+    ///
+    /// # Returns
+    ///
+    /// The agent's new current emotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut agent = NpcAgent::new("merchant", vec![]);
+    /// agent.appraise(Emotion::Fear, 0.8, "ambushed by bandits");
+    /// agent.appraise(Emotion::Anger, 0.9, "insulted by a bandit");
+    /// assert_eq!(agent.emotions.get_emotion(), &Emotion::Fear);
+    ///
+    /// // The next tick starts a new set of appraisals.
+    /// agent.tick(1.0);
+    /// agent.appraise(Emotion::Anger, 0.9, "insulted again");
+    /// assert_eq!(agent.emotions.get_emotion(), &Emotion::Anger);
+    /// ```
+    pub fn appraise(&mut self, emotion: Emotion, intensity: f64, source: &str) -> Emotion {
+        let intensity = intensity * self.body.emotion_gain(&emotion);
+        self.appraisals.add(emotion, intensity, source);
+        self.appraisals.apply(&mut self.emotions);
+        self.emotions.get_emotion().clone()
+    }
+
+    /// Reacts emotionally to an event through the custom appraisal rules enabled for the agent's
+    /// archetype. Perceived actions and recorded deeds are appraised this way on top of the
+    /// built-in reactions, as `perceived.<ActionKind>` and `deed.<DeedCategory>` events.
+    ///
+    /// # Returns
+    ///
+    /// The agent's new current emotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::appraisal::{AppraisalEvent, AppraisalRegistry, AppraisalRule};
+    /// use athena::emotional_response::Emotion;
+    /// use athena::perception::PlayerAction;
+    ///
+    /// let rules = AppraisalRegistry::new();
+    /// let mut merchant = NpcAgent::new("merchant", vec![]);
+    /// merchant.archetype = Some("merchant".to_string());
+    /// merchant.appraisal_rules = rules.clone();
+    ///
+    /// // Registered after the agent got the registry, and still reaching it.
+    /// rules.register(AppraisalRule::new("storm-dread", "weather.storm", |event| {
+    ///     vec![(Emotion::Sadness, 0.5 * event.magnitude)]
+    /// }));
+    /// let storm = AppraisalEvent::new("weather.storm", "a storm rolled in");
+    /// assert_eq!(merchant.appraise_event(&storm), Emotion::Sadness);
+    ///
+    /// // A bow only earns a little trust, but a merchant smells a sale.
+    /// let sale = |_: &AppraisalEvent| vec![(Emotion::Anticipation, 0.6)];
+    /// let rule = AppraisalRule::new("sale", "perceived.Bowed", sale).for_archetypes(["merchant"]);
+    /// rules.register(rule);
+    /// assert_eq!(merchant.perceive("alice", &PlayerAction::Bowed), Emotion::Anticipation);
+    /// let mut guard = NpcAgent::new("guard", vec![]);
+    /// guard.appraisal_rules = rules.clone();
+    /// assert_eq!(guard.perceive("alice", &PlayerAction::Bowed), Emotion::Trust);
+    /// ```
+    pub fn appraise_event(&mut self, event: &AppraisalEvent) -> Emotion {
+        let archetype = self.archetype.as_deref();
+        for appraisal in self.appraisal_rules.evaluate(event, archetype) {
+            self.appraise(appraisal.emotion, appraisal.intensity, &appraisal.source);
+        }
+        self.emotions.get_emotion().clone()
+    }
+
+    /// Checks a partner's conduct against the agent's norms and reacts to any violations.
+    ///
+    /// Each violation provokes its emotion (more intensely the more severe it is), lowers the
+    /// agent's affinity towards the partner, and is remembered as a disrespectful deed.
+    ///
+    /// # Arguments
+    ///
+    /// * `partner_id` - Who the conduct came from.
+    /// * `conduct` - What the partner said and did.
+    ///
+    /// # Returns
+    ///
+    /// The violated norms.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    /// use athena::norms::Conduct;
+    ///
+    /// let mut agent = NpcAgent::new("duchess", vec![]);
+    /// let conduct = Conduct { distance: Some(0.1), ..Conduct::default() };
+    /// assert_eq!(agent.observe_conduct("alice", &conduct).len(), 1);
+    /// assert_eq!(agent.emotions.get_emotion(), &Emotion::Disgust);
+    /// assert!(agent.disposition_towards("alice").warmth < 0.0);
+    /// ```
+    pub fn observe_conduct(&mut self, partner_id: &str, conduct: &Conduct) -> Vec<NormViolation> {
+        let violations = self.norms.check(conduct);
+        for violation in &violations {
+            let intensity = (0.3 + 0.3 * violation.severity).min(1.0);
+            self.appraise(violation.emotion.clone(), intensity, &violation.description);
+            self.interlocutor(partner_id)
+                .adjust_affinity(-0.05 * violation.severity);
+            self.record_deed(
+                partner_id,
+                DeedCategory::Disrespect,
+                violation.severity,
+                &violation.description,
+            );
+        }
+        violations
+    }
+
+    /// Perceives an action someone did, such as drawing a weapon or handing over a gift, and
+    /// reacts to it without any dialogue.
+    ///
+    /// The agent's perception policy decides the reaction: the action provokes an emotion, moves
+    /// the agent's affinity and trust towards the actor, and may be remembered as a deed. The
+    /// agent also learns the action as a fact in its knowledge graph.
+    ///
+    /// Gifts are judged by the agent's preferences, through `receive_gift` with an item of
+    /// middling worth (0.5), unless the policy overrides their appraisal.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor_id` - Who did it.
+    /// * `action` - What they did.
+    ///
+    /// # Returns
+    ///
+    /// The agent's new current emotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    /// use athena::perception::PlayerAction;
+    ///
+    /// let mut agent = NpcAgent::new("merchant", vec![]);
+    /// assert_eq!(agent.perceive("alice", &PlayerAction::DrewWeapon), Emotion::Fear);
+    /// assert!(agent.get_interlocutor("alice").unwrap().trust < 0.0);
+    ///
+    /// let theft = PlayerAction::Stole { item: "apple".to_string() };
+    /// agent.perceive("alice", &theft);
+    /// assert!(agent.disposition_towards("alice").suspicion > 0.0);
+    /// assert_eq!(agent.knowledge.get_relationships("alice")[0].relation_type, "drew_weapon_on");
+    ///
+    /// agent.learn_preferences("I can't stand turnips.");
+    /// let turnip = PlayerAction::GaveGift { item: "turnip".to_string() };
+    /// assert_eq!(agent.perceive("bob", &turnip), Emotion::Disgust);
+    /// ```
+    pub fn perceive(&mut self, actor_id: &str, action: &PlayerAction) -> Emotion {
+        let description = action.description();
+        let source = format!("{} {}", actor_id, description);
+        let kind = format!("perceived.{:?}", action.kind());
+        if let PlayerAction::GaveGift { item } = action {
+            if !self.perception_policy.overrides.contains_key(&action.kind()) {
+                self.receive_gift(actor_id, item, 0.5);
+                return self.appraise_event(&AppraisalEvent::new(&kind, &source).by(actor_id));
+            }
+        }
+        let appraisal = self.perception_policy.appraisal(action.kind());
+        let context = self.interlocutor(actor_id);
+        context.adjust_affinity(appraisal.affinity);
+        context.adjust_trust(appraisal.trust);
+        if let Some((category, magnitude)) = appraisal.deed {
+            self.record_deed(actor_id, category, magnitude, &description);
+        }
+        let (relation, target) = action.fact();
+        let target = target.unwrap_or(self.id.as_str()).to_string();
+        self.learn_fact(actor_id, &target, relation);
+        if let Some((emotion, intensity)) = appraisal.emotion {
+            self.appraise(emotion, intensity, &source);
+        }
+        self.appraise_event(&AppraisalEvent::new(&kind, &source).by(actor_id))
+    }
+
+    /// Adds a fact to the agent's knowledge graph, with entities for both ends.
+    pub(crate) fn learn_fact(&mut self, source: &str, target: &str, relation: &str) {
+        self.learn_weighted_fact(source, target, relation, 1.0);
+    }
+
+    /// Adds a fact of a given weight to the agent's knowledge graph, like `learn_fact`.
+    fn learn_weighted_fact(&mut self, source: &str, target: &str, relation: &str, weight: f64) {
+        for id in [source, target] {
+            if self.knowledge.get_entity(id).is_none() {
+                self.knowledge.add_entity(Entity::new(id, HashMap::new()));
+            }
+        }
+        let fact = Relationship::new(source, target, relation, HashMap::new()).with_weight(weight);
+        self.knowledge.add_relationship(fact);
+    }
+
+    /// Receives a gift and reacts to it according to the agent's preferences.
+    ///
+    /// The gift is scored against the likes and dislikes in the agent's knowledge graph. The
+    /// score moves the agent's affinity towards the giver and provokes an emotion; a gift the
+    /// agent does not dislike is also remembered as a generous deed. The agent learns who gave
+    /// it what.
+    ///
+    /// # Arguments
+    ///
+    /// * `giver_id` - Who gave the gift.
+    /// * `item` - The item given.
+    /// * `value` - What the item is worth to the agent (between 0.0 and 1.0).
+    ///
+    /// # Returns
+    ///
+    /// The reaction, with an authored line and a directive for generating one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut agent = NpcAgent::new("mira", vec![]);
+    /// agent.learn_preferences("I adore roses, but I can't stand turnips.");
+    ///
+    /// let reaction = agent.receive_gift("alice", "rose", 0.3);
+    /// assert_eq!(reaction.emotion, Emotion::Joy);
+    /// assert!(agent.get_interlocutor("alice").unwrap().affinity > 0.1);
+    ///
+    /// let reaction = agent.receive_gift("bob", "turnip", 0.3);
+    /// assert_eq!(reaction.line, "Get that turnip away from me!");
+    /// assert!(agent.get_interlocutor("bob").unwrap().affinity < 0.0);
+    /// ```
+    pub fn receive_gift(&mut self, giver_id: &str, item: &str, value: f64) -> GiftReaction {
+        let reaction = gifts::evaluate(&self.knowledge, self.id.as_str(), item, value);
+        self.interlocutor(giver_id).adjust_affinity(reaction.affinity);
+        let description = format!("gave me {}", with_article(item));
+        if reaction.preference > -0.2 {
+            let magnitude = 0.25 + 0.5 * value.clamp(0.0, 1.0);
+            self.record_deed(giver_id, DeedCategory::Generosity, magnitude, &description);
+        }
+        self.learn_fact(giver_id, item, "gave");
+        let source = format!("{} {}", giver_id, description);
+        self.appraise(reaction.emotion.clone(), reaction.intensity, &source);
+        reaction
+    }
+
+    /// Learns the likes and dislikes the agent states in a line (e.g. "I love roses"), so later
+    /// gifts are judged by them.
+    ///
+    /// # Returns
+    ///
+    /// The number of preferences learned.
+    pub fn learn_preferences(&mut self, line: &str) -> usize {
+        let stated = gifts::extract_preferences(line);
+        for (subject, strength) in &stated {
+            gifts::learn_preference(&mut self.knowledge, self.id.as_str(), subject, *strength);
+        }
+        stated.len()
+    }
+
+    /// Gathers what the agent is willing to tell an asker about a topic, trusting them as much
+    /// as its context for them says (see `KnowledgeAnswer::gather`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::agent::NpcAgent;
+    /// use athena::knowledge_graph::Relationship;
+    /// use athena::knowledge_query::SharingPolicy;
+    ///
+    /// let mut agent = NpcAgent::new("mira", vec![]);
+    /// agent.knowledge.add_relationship(Relationship::new("brom", "forge", "owns", HashMap::new()));
+    /// let answer = agent.knowledge_about("player", "brom", &SharingPolicy::default());
+    /// agent.record_shared(&answer);
+    ///
+    /// let again = agent.knowledge_about("player", "brom", &SharingPolicy::default());
+    /// assert!(again.facts[0].already_told);
+    /// assert!(again.directive(&agent.knowledge).contains("You have told them all this before."));
+    /// ```
+    pub fn knowledge_about(
+        &self,
+        asker: &str,
+        topic: &str,
+        policy: &SharingPolicy,
+    ) -> KnowledgeAnswer {
+        let trust = self.get_interlocutor(asker).map_or(0.0, |context| context.trust);
+        KnowledgeAnswer::gather(&self.knowledge, topic, asker, trust, policy)
+    }
+
+    /// Records in the agent's knowledge that an answer was given, at its current game time.
+    pub fn record_shared(&mut self, answer: &KnowledgeAnswer) {
+        let game_time = self.get_game_time();
+        answer.record(&mut self.knowledge, game_time);
+    }
+
+    /// Gathers the hints the agent can give an asker about a quest, trusting them as much as
+    /// its context for them says (see `quest::hints`).
+    pub fn quest_hints(
+        &self,
+        asker: &str,
+        quest: &Quest,
+        policy: &SharingPolicy,
+    ) -> Vec<QuestHint> {
+        let trust = self.get_interlocutor(asker).map_or(0.0, |context| context.trust);
+        quest::hints(&self.knowledge, &self.id, quest, trust, policy)
+    }
+
+    /// Gathers how the quests the agent knows of were resolved (see `quest::outcomes`).
+    pub fn quest_outcomes(&self) -> Vec<QuestOutcome> {
+        quest::outcomes(&self.knowledge)
+    }
+
+    /// Computes how the agent should address a partner.
+    ///
+    /// The relationship stage and the agent's culture pick the form of address. The agent's
+    /// personal warmth towards the partner is combined with the partner's standing with the
+    /// agent's faction: if either is negative the worse one counts, otherwise the better one.
+    ///
+    /// # Arguments
+    ///
+    /// * `partner_id` - The partner being addressed.
+    /// * `addressee` - The partner's names and title.
+    /// * `faction_standing` - The partner's standing with the agent's faction (-1.0 to 1.0).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::address::{AddressForm, Addressee};
+    /// use athena::agent::NpcAgent;
+    ///
+    /// let mut agent = NpcAgent::new("guard", vec![]);
+    /// let ayla = Addressee::new("Ayla");
+    /// assert_eq!(agent.address_for("player", &ayla, 0.0).text, "traveler");
+    /// agent.enable_companionship("player");
+    /// assert_eq!(agent.address_for("player", &ayla, 0.0).text, "traveler");
+    /// assert_eq!(agent.address_for("player", &ayla, -0.9).form, AddressForm::Epithet);
+    /// ```
+    pub fn address_for(
+        &self,
+        partner_id: &str,
+        addressee: &Addressee,
+        faction_standing: f64,
+    ) -> Address {
+        let warmth = self.disposition_towards(partner_id).warmth;
+        let standing = if warmth < 0.0 || faction_standing < 0.0 {
+            warmth.min(faction_standing)
+        } else {
+            warmth.max(faction_standing)
+        };
+        let stage = self.bond_stage(partner_id);
+        address::address(addressee, stage, self.culture.as_ref(), standing)
+    }
+
+    /// Gets the label of the agent's relationship with a partner, derived from its current
+    /// affinity, trust, and familiarity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    ///
+    /// let mut agent = NpcAgent::new("guard", vec![]);
+    /// assert_eq!(agent.relationship_label("alice"), "stranger");
+    /// agent.interlocutor("alice").adjust_affinity(-0.3);
+    /// assert_eq!(agent.relationship_label("alice"), "rival");
+    /// assert_eq!(agent.relationship_directive("alice"), "To you, the listener is a rival.");
+    /// ```
+    pub fn relationship_label(&self, partner_id: &str) -> String {
+        let metrics = self
+            .get_interlocutor(partner_id)
+            .map(RelationshipMetrics::of)
+            .unwrap_or_default();
+        self.relationship_labels.label(&metrics).to_string()
+    }
+
+    /// Builds the prompt directive stating the agent's relationship with a partner.
+    pub fn relationship_directive(&self, partner_id: &str) -> String {
+        let label = self.relationship_label(partner_id);
+        format!("To you, the listener is {}.", relationship::with_article(&label))
+    }
+
+    /// Enables relationship progression with a partner, starting at the stranger stage.
+    pub fn enable_companionship(&mut self, partner_id: &str) {
+        self.interlocutor(partner_id)
+            .companionship
+            .get_or_insert_with(Companionship::new);
+    }
+
+    /// Re-evaluates the bond with a partner whose personality is known.
+    ///
+    /// # Returns
+    ///
+    /// The new stage if it changed, or `None` if it did not or companionship is not enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::companionship::BondStage;
+    /// use athena::personality::Personality;
+    ///
+    /// let mut agent = NpcAgent::new("ranger", vec![]);
+    /// agent.enable_companionship("alice");
+    /// agent.interlocutor("alice").adjust_affinity(0.4);
+    /// agent.interlocutor("alice").adjust_trust(0.2);
+    /// agent.evaluate_bond("alice", &Personality::new());
+    /// assert_eq!(agent.bond_stage("alice"), Some(BondStage::Friend));
+    /// ```
+    pub fn evaluate_bond(&mut self, partner_id: &str, partner: &Personality) -> Option<BondStage> {
+        let compatibility = companionship::compatibility(&self.personality, partner);
+        self.interlocutor(partner_id).evaluate_bond(compatibility)
+    }
+
+    /// Gets the stage of the bond with a partner, if companionship is enabled.
+    pub fn bond_stage(&self, partner_id: &str) -> Option<BondStage> {
+        self.get_interlocutor(partner_id)?
+            .companionship
+            .as_ref()
+            .map(|bond| bond.get_stage())
+    }
+
+    /// Sets the partner the agent is attending to; their reputation then affects action scores.
+    pub fn set_focus(&mut self, partner_id: Option<&str>) {
+        self.focus = partner_id.map(Symbol::new);
+    }
+
+    /// Gets the partner the agent is attending to, if any.
+    pub fn get_focus(&self) -> Option<&Symbol> {
+        self.focus.as_ref()
+    }
+
+    /// Returns true if the agent is willing to talk to a partner (i.e. it is not on cooldown).
+    pub fn can_engage(&self, partner_id: &str) -> bool {
+        self.get_interlocutor(partner_id)
+            .is_none_or(|context| self.game_time >= context.cooldown_until)
+    }
+
+    /// Processes one line said by a conversation partner.
+    ///
+    /// The line is recorded, tagged with topics, and checked for rudeness; the agent then
+    /// chooses an action and decides whether it wants to end the conversation. When it does,
+    /// the output carries the farewell line and the cooldown before it will engage again.
+    ///
+    /// # Arguments
+    ///
+    /// * `partner_id` - The ID of the speaker.
+    /// * `line` - What the speaker said.
+    /// * `topics` - The known conversation topics.
+    ///
+    /// # Returns
+    ///
+    /// An `AgentOutput` describing the agent's reaction.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::ending::EndReason;
+    /// use athena::topic::{Topic, TopicKind};
+    ///
+    /// let mut agent = NpcAgent::new("innkeeper", vec![]);
+    /// agent.process_turn("alice", "You idiot.", &[]);
+    /// agent.process_turn("alice", "Stupid inn.", &[]);
+    /// let output = agent.process_turn("alice", "Shut up!", &[]);
+    /// assert_eq!(output.conversation_end.unwrap().reason, EndReason::Rudeness);
+    /// assert!(!agent.can_engage("alice"));
+    /// assert!(!agent.process_turn("alice", "Sorry!", &[]).engaged);
+    ///
+    /// // Topics are tracked per partner, and each conversation starts afresh.
+    /// let topics = vec![Topic::new("weather", TopicKind::Other, &["rain"])];
+    /// agent.process_turn("bob", "Rain again?", &topics);
+    /// assert_eq!(agent.get_interlocutor("bob").unwrap().topics.get_active().len(), 1);
+    /// agent.end_conversation("bob");
+    /// agent.process_turn("bob", "Hello.", &topics);
+    /// assert!(agent.get_interlocutor("bob").unwrap().topics.get_active().is_empty());
+    /// ```
+    pub fn process_turn(&mut self, partner_id: &str, line: &str, topics: &[Topic]) -> AgentOutput {
+        if !self.can_engage(partner_id) {
+            return AgentOutput::default();
+        }
+        self.set_focus(Some(partner_id));
+        let opening = self.get_interlocutor(partner_id).is_none_or(|c| c.session_turns == 0);
+        if opening {
+            let game_time = self.game_time;
+            let context = self.interlocutor(partner_id);
+            context.notes = SessionNotes::new(game_time, context.affinity, context.trust);
+            context.topics.reset();
+        }
+        let conduct = Conduct {
+            line: line.to_string(),
+            distance: None,
+            opening,
+        };
+        let violations = self.observe_conduct(partner_id, &conduct);
+        let rude = ending::is_rude(line);
+        let (policy, game_time) = (self.annoyance_policy.clone(), self.game_time);
+        let context = self.interlocutor(partner_id);
+        context.record_turn(partner_id, line);
+        context.session_turns += 1;
+        context.annoyance.record(game_time, conduct.opening, &policy);
+        for goal in context.conversation_goals.iter_mut() {
+            goal.assess(line);
+        }
+        let goals = context.conversation_goals.clone();
+        if rude {
+            context.rudeness += 1;
+            context.adjust_affinity(-0.1);
+        }
+        let discussed: Vec<Symbol> = context.topics.observe(line, topics).to_vec();
+        // Remind the partner of earlier promises, not of those made in this line.
+        let reminder = self.promises.remind(partner_id);
+        let promises = self.note_promises(partner_id, line);
+        let notes = &mut self.interlocutor(partner_id).notes;
+        notes.note_topics(discussed.iter().map(|id| id.as_str()));
+        notes.commitments.extend(promises.iter().cloned());
+        let action = self.decide();
+
+        let mut output = AgentOutput {
+            engaged: true,
+            action: Some(action),
+            violations,
+            goals,
+            promises,
+            ..AgentOutput::default()
+        };
+        if let Some(reason) = self.end_reason(partner_id, topics) {
+            let farewell = ending::farewell(reason, &self.personality, &mut self.repetition);
+            let cooldown = self.ending_policy.cooldown_for(reason);
+            let (npc_id, game_time) = (self.id.clone(), self.game_time);
+            let context = self.interlocutor(partner_id);
+            context.conversation.add_turn(&npc_id, &farewell);
+            context.cooldown_until = game_time + cooldown;
+            output.outcome = self.end_conversation(partner_id);
+            // Goals only last for one conversation; the ones still pursued have failed.
+            for goal in output.goals.iter_mut().filter(|goal| goal.is_active()) {
+                goal.status = GoalStatus::Failed;
+            }
+            output.dialogue = Some(farewell.clone());
+            output.conversation_end = Some(ConversationEnd {
+                reason,
+                farewell,
+                cooldown,
+            });
+        } else if let Some(reminder) = reminder {
+            self.queue_dialogue(&reminder);
+        }
+        output
+    }
+
+    /// Ends the current conversation with a partner, e.g. when the player walks away.
+    ///
+    /// The next line from the partner starts a new conversation. Conversation goals and the
+    /// rudeness count are forgotten; the history, relationship, and annoyance are kept.
+    ///
+    /// # Returns
+    ///
+    /// The outcome of the conversation if one was under way and it was significant under the
+    /// agent's checkpoint policy. Its facts are added to the agent's knowledge graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::ledger::HookKind;
+    ///
+    /// let mut innkeeper = NpcAgent::new("innkeeper", vec![]);
+    /// innkeeper.process_turn("alice", "Lovely weather today.", &[]);
+    /// assert!(innkeeper.end_conversation("alice").is_none());
+    ///
+    /// innkeeper.process_turn("alice", "I promise to pay for the room by tomorrow.", &[]);
+    /// let outcome = innkeeper.end_conversation("alice").unwrap();
+    /// assert_eq!(outcome.commitments[0].what, "pay for the room");
+    /// assert_eq!(outcome.quest_hooks()[0].kind, HookKind::FollowUp);
+    /// assert_eq!(innkeeper.knowledge.get_relationships("alice")[0].relation_type, "promised");
+    ///
+    /// // Without a conversation under way, there is nothing to end.
+    /// assert!(innkeeper.end_conversation("alice").is_none());
+    /// assert!(innkeeper.end_conversation("bob").is_none());
+    /// ```
+    pub fn end_conversation(&mut self, partner_id: &str) -> Option<ConversationOutcome> {
+        if self.get_interlocutor(partner_id).is_none_or(|context| context.session_turns == 0) {
+            return None;
+        }
+        let (npc_id, game_time) = (self.id.clone(), self.game_time);
+        let context = self.interlocutor(partner_id);
+        let outcome = ConversationOutcome::from_notes(
+            npc_id.as_str(),
+            partner_id,
+            std::mem::take(&mut context.notes),
+            std::mem::take(&mut context.conversation_goals),
+            context.session_turns,
+            game_time,
+            (context.affinity, context.trust),
+        );
+        context.session_turns = 0;
+        context.rudeness = 0;
+        if self.focus.as_ref().is_some_and(|focus| focus.as_str() == partner_id) {
+            self.focus = None;
+        }
+        if !outcome.is_significant(&self.checkpoint_policy) {
+            return None;
+        }
+        outcome.record(&mut self.knowledge);
+        Some(outcome)
+    }
+
+    /// Gives the agent a goal for its conversation with a partner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::conversation_goal::{ConversationGoal, GoalKind, GoalStatus};
+    ///
+    /// let mut agent = NpcAgent::new("spy", vec![]);
+    /// let subjects = vec!["tunnel".to_string()];
+    /// agent.add_conversation_goal("alice", ConversationGoal::new(GoalKind::ExtractInformation(subjects)));
+    /// assert!(agent.conversation_directives("alice")[0].contains("about tunnel"));
+    /// let output = agent.process_turn("alice", "The tunnel starts under the mill.", &[]);
+    /// assert_eq!(output.goals[0].status, GoalStatus::Achieved);
+    /// assert!(agent.conversation_directives("alice").is_empty());
+    /// ```
+    pub fn add_conversation_goal(&mut self, partner_id: &str, goal: ConversationGoal) {
+        self.interlocutor(partner_id).conversation_goals.push(goal);
+    }
+
+    /// Builds the prompt directives for the agent's active conversation goals with a partner.
+    pub fn conversation_directives(&self, partner_id: &str) -> Vec<String> {
+        self.get_interlocutor(partner_id)
+            .map(|context| {
+                context.conversation_goals.iter().filter_map(|goal| goal.directive()).collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Gets how annoyed the agent is with a partner right now (between 0.0 and 1.0).
+    pub fn annoyance_towards(&self, partner_id: &str) -> f64 {
+        self.get_interlocutor(partner_id).map_or(0.0, |context| {
+            context.annoyance.level_at(self.game_time, &self.annoyance_policy)
+        })
+    }
+
+    /// Returns the multiplier the agent applies to its prices for a partner, which rises as the
+    /// partner annoys it, scaled by its faction's price factor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    ///
+    /// let mut merchant = NpcAgent::new("merchant", vec![]);
+    /// assert_eq!(merchant.price_factor("alice"), 1.0);
+    /// for _ in 0..3 {
+    ///     merchant.process_turn("alice", "What's the price of this?", &[]);
+    ///     merchant.end_conversation("alice");
+    /// }
+    /// assert!(merchant.price_factor("alice") > 1.0);
+    /// merchant.faction_price_factor = 2.0;
+    /// assert!(merchant.price_factor("alice") > 2.0);
+    /// ```
+    pub fn price_factor(&self, partner_id: &str) -> f64 {
+        let annoyance = self.annoyance_towards(partner_id);
+        let personal = self.annoyance_policy.price_factor(annoyance, &self.personality);
+        personal * self.faction_price_factor
+    }
+
+    /// Starts haggling with a partner over an item the agent has a price belief for, at prices
+    /// scaled by `price_factor`.
+    ///
+    /// # Returns
+    ///
+    /// The haggling session, or `None` if the agent knows no price for the item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::trading::{self, PriceBelief};
+    ///
+    /// let mut merchant = NpcAgent::new("merchant", vec![]);
+    /// trading::set_price_belief(&mut merchant.knowledge, &PriceBelief::new("iron", 10.0));
+    /// let asking = merchant.haggle("alice", "iron").unwrap().get_asking();
+    /// merchant.faction_price_factor = 1.5;
+    /// assert_eq!(merchant.haggle("alice", "iron").unwrap().get_asking(), asking * 1.5);
+    /// assert!(merchant.haggle("alice", "silk").is_none());
+    /// ```
+    pub fn haggle(&self, partner_id: &str, item: &str) -> Option<Haggle> {
+        let belief = trading::price_belief(&self.knowledge, item)?;
+        let haggle = Haggle::new(belief, &self.personality);
+        Some(haggle.with_surcharge(self.price_factor(partner_id)))
+    }
+
+    /// Builds the prompt directive describing the agent's annoyance with a partner, if it shows.
+    pub fn annoyance_directive(&self, partner_id: &str) -> Option<String> {
+        let annoyance = self.annoyance_towards(partner_id);
+        self.annoyance_policy.directive(annoyance, &self.personality)
+    }
+
+    /// Builds the post-processing filters for the agent's replies to a partner: those of its
+    /// body state, and a single sentence when the partner annoys it.
+    pub fn speech_filters(&self, partner_id: &str) -> Vec<Filter> {
+        let mut filters = self.body.filters();
+        let annoyance = self.annoyance_towards(partner_id);
+        if self.annoyance_policy.is_terse(annoyance, &self.personality) {
+            filters.push(Filter::MaxSentences(1));
+        }
+        filters
+    }
+
+    /// Decides whether the agent wants to end the conversation with a partner, and why.
+    fn end_reason(&self, partner_id: &str, topics: &[Topic]) -> Option<EndReason> {
+        let context = self.get_interlocutor(partner_id)?;
+        let policy = &self.ending_policy;
+        let busy = self
+            .schedule
+            .current_block()
+            .is_some_and(|block| !policy.idle_activities.contains(&block.activity));
+        let annoyance = context.annoyance.level_at(self.game_time, &self.annoyance_policy);
+        if context.rudeness >= policy.max_rudeness {
+            Some(EndReason::Rudeness)
+        } else if self.annoyance_policy.walks_away(annoyance, &self.personality) {
+            Some(EndReason::Annoyance)
+        } else if context.affinity < policy.min_affinity {
+            Some(EndReason::LowAffinity)
+        } else if busy && context.session_turns >= policy.busy_turn_limit {
+            Some(EndReason::SchedulePressure)
+        } else if context.topics.should_end(topics, &self.personality, &self.knowledge) {
+            Some(EndReason::Boredom)
+        } else {
+            None
+        }
+    }
+
+    /// Disrupts the agent's routine at a location (e.g. its shop burned down) and re-plans it.
+    ///
+    /// Every moved block is remembered and noted as an experience to reflect on; the disruption
+    /// saddens the agent, and it queues a remark about how its routine changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - The location that cannot be used.
+    /// * `reason` - Why (e.g. "the smithy burned down").
+    ///
+    /// # Returns
+    ///
+    /// The blocks that were moved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::schedule::ScheduleBlock;
+    ///
+    /// let mut smith = NpcAgent::new("smith", vec![]);
+    /// smith.schedule.add_block(ScheduleBlock::new(8.0, 18.0, "work", "smithy"));
+    /// smith.schedule.add_alternative("work", "work", "market stall");
+    /// let replans = smith.disrupt_routine("smithy", "the smithy burned down");
+    /// assert_eq!(replans.len(), 1);
+    /// assert_eq!(
+    ///     smith.take_dialogue().unwrap(),
+    ///     "Ever since the smithy burned down, I've had to work at the market stall."
+    /// );
+    /// assert!(smith.intelligence.get_memory("disruption:smithy:work").is_some());
+    /// ```
+    pub fn disrupt_routine(&mut self, location: &str, reason: &str) -> Vec<Replan> {
+        let replans = self.schedule.disrupt(location, reason);
+        for replan in &replans {
+            let key = format!("disruption:{}:{}", location, replan.original.activity);
+            self.intelligence.record_memory(&key, &replan.memory());
+            self.reflector.record_experience(location, &replan.memory(), Emotion::Sadness);
+        }
+        if let Some(replan) = replans.first() {
+            self.appraise(Emotion::Sadness, 0.3 + 0.1 * replans.len() as f64, reason);
+            self.queue_dialogue(&replan.remark());
+        }
+        replans
+    }
+
+    /// Queues a dialogue line for the game to deliver.
+    pub fn queue_dialogue(&mut self, line: &str) {
+        self.pending_dialogue.push_back(line.to_string());
+    }
+
+    /// Takes the next pending dialogue line, if any.
+    pub fn take_dialogue(&mut self) -> Option<String> {
+        self.pending_dialogue.pop_front()
+    }
+
+    /// Scores every candidate action, explaining each score.
+    ///
+    /// Candidates come from the action registry for the current emotion, from unsatisfied needs,
+    /// and from the goals that call for an action (see `goal_actions`). Each is then modified by
+    /// the active dyads, the personality, the current state, and the reputation of the partner
+    /// in focus.
+    ///
+    /// # Returns
+    ///
+    /// An `Explanation` per candidate, in proposal order.
+    pub fn score_actions(&self) -> Vec<Explanation> {
+        let dyads = self.emotions.get_dyad_labels(0.0).join("+");
+        self.proposals()
+            .into_iter()
+            .map(|(action, base, proposer)| self.explain_with(action, base, proposer, &dyads))
+            .collect()
+    }
+
+    /// Proposes candidate actions as `(action, base score, proposer)`: those of the action
+    /// registry for the current emotion, then those of unsatisfied needs, then those of goals.
+    fn proposals(&self) -> Vec<(&str, f64, Proposer<'_>)> {
+        let emotion = self.emotions.get_emotion();
+        let mut proposals: Vec<(&str, f64, Proposer<'_>)> = self
+            .emotions
+            .get_action_registry()
+            .get_candidates(emotion)
+            .iter()
+            .map(|(action, weight)| (action.as_str(), *weight, Proposer::Emotion))
+            .collect();
+        for (need, action, urgency) in self.needs.proposals() {
+            proposals.push((action, urgency, Proposer::Need(need)));
+        }
+        for goal in &self.goals {
+            if let Some(action) = self.goal_actions.get(goal) {
+                proposals.push((action, GOAL_URGENCY, Proposer::Goal(goal)));
+            }
+        }
+        proposals
+    }
+
+    /// Explains the score of a proposed action, applying every modifier as a factor.
+    fn explain_with(
+        &self,
+        action: &str,
+        base: f64,
+        proposer: Proposer<'_>,
+        dyads: &str,
+    ) -> Explanation {
+        let source = match proposer {
+            Proposer::Emotion => format!("emotion {:?}", self.emotions.get_emotion()),
+            Proposer::Need(need) => format!("need {} at {:.2}", need, 1.0 - base),
+            Proposer::Goal(goal) => format!("goal {}", goal),
+        };
+        let mut explanation = Explanation::new(action, base, &source);
+        for (modifier, multiplier) in self.modifiers(action) {
+            let description = match modifier {
+                "emotion" => format!("dyads {}", dyads),
+                "personality" => "Big Five traits".to_string(),
+                "temperament" => "risk tolerance and novelty seeking".to_string(),
+                "body" => "pain, fatigue, and intoxication".to_string(),
+                "morals" => "moral values".to_string(),
+                "state" => format!("state {}", self.intelligence.get_current_state()),
+                "coping" => self.coping.get_strategy().map_or(String::new(), |strategy| {
+                    format!("coping by {:?}", strategy)
+                }),
+                "reputation" => self.focus.as_ref().map_or(String::new(), |partner| {
+                    format!("disposition towards {}", partner)
+                }),
+                _ => String::new(),
+            };
+            explanation.add_factor(modifier, &description, multiplier);
+        }
+        explanation
+    }
+
+    /// Lists the modifiers that apply to the score of an action, as `(source, multiplier)`
+    /// pairs, so scoring and explaining apply the same factors in the same order.
+    fn modifiers<'a>(&'a self, action: &'a str) -> impl Iterator<Item = (&'static str, f64)> + 'a {
+        let coping = self.coping.get_strategy();
+        [
+            ("emotion", self.emotions.action_modifier(action)),
+            ("personality", self.personality.action_modifier(action)),
+            ("temperament", self.temperament_modifier(action)),
+            ("body", self.body.action_modifier(action)),
+            ("morals", self.morals.action_modifier(action)),
+            ("state", self.intelligence.action_modifier(action)),
+        ]
+        .into_iter()
+        .chain(coping.map(|strategy| ("coping", strategy.action_modifier(action))))
+        .chain(self.focus.as_ref().map(|partner| {
+            ("reputation", self.disposition_towards(partner).action_modifier(action))
+        }))
+    }
+
+    /// Computes the multiplier the agent's decision parameters apply to an action.
+    fn temperament_modifier(&self, action: &str) -> f64 {
+        let last = self.recent_decisions.back().map(|decision| decision.action.as_str());
+        self.personality.decision_parameters().action_modifier(action, last)
+    }
+
+    /// Applies every modifier to the score of a proposed action, without building the
+    /// explanation.
+    fn modified_score(&self, action: &str, base: f64) -> f64 {
+        self.modifiers(action).fold(base, |score, (_, multiplier)| score * multiplier).max(0.0)
+    }
+
+    /// Chooses an action using the shared utility selection system and records the decision.
+    ///
+    /// # Returns
+    ///
+    /// The name of the chosen action, or "Observe" if no candidates are available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    /// let mut agent = NpcAgent::new("guard", vec![]);
+    /// agent.emotions.set_emotion(Emotion::Fear);
+    /// assert_eq!(agent.decide(), "Hide");
+    /// assert_eq!(agent.get_recent_decisions().count(), 1);
+    /// ```
+    pub fn decide(&mut self) -> String {
+        let explanations = self.score_actions();
+        let candidates: Vec<UtilityCandidate> =
+            explanations.iter().map(|e| e.to_candidate()).collect();
+        // The same action may be proposed more than once, so the pick is found by index.
+        let explanation = utility::select_best_index(&candidates)
+            .map(|index| explanations[index].clone())
+            .unwrap_or_else(|| Explanation::new("Observe", 0.0, "no candidates"));
+        self.record_decision(explanation, candidates)
+    }
+
+    /// Records a decision among scored candidates.
+    ///
+    /// # Returns
+    ///
+    /// The name of the chosen action.
+    fn record_decision(
+        &mut self,
+        explanation: Explanation,
+        candidates: Vec<UtilityCandidate>,
+    ) -> String {
+        let action = explanation.action.clone();
+        if self.recent_decisions.len() == MAX_RECENT_DECISIONS {
+            self.recent_decisions.pop_front();
+        }
+        self.recent_decisions.push_back(Decision {
+            game_time: self.game_time,
+            action: action.clone(),
+            score: explanation.score(),
+            candidates,
+            explanation,
+        });
+        action
+    }
+
+    /// Explains the most recent decision.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<&Explanation>` for the last chosen action, or `None` if no decision was made yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    /// let mut agent = NpcAgent::new("guard", vec![]);
+    /// agent.personality.set_neuroticism(0.9);
+    /// agent.emotions.set_emotion(Emotion::Fear);
+    /// agent.decide();
+    /// let explanation = agent.explain_last().unwrap();
+    /// assert_eq!(explanation.factors[0].source, "personality");
+    /// println!("{}", explanation.summary());
+    /// ```
+    pub fn explain_last(&self) -> Option<&Explanation> {
+        self.recent_decisions.back().map(|decision| &decision.explanation)
+    }
+
+    /// Iterates over the most recent decisions, oldest first.
+    pub fn get_recent_decisions(&self) -> impl Iterator<Item = &Decision> {
+        self.recent_decisions.iter()
+    }
+
+    /// Captures a serializable snapshot of the agent's internals.
+    ///
+    /// # Returns
+    ///
+    /// A `DebugReport` suitable for in-game debug overlays or attaching to bug reports.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::emotional_response::Emotion;
+    /// let mut agent = NpcAgent::new("innkeeper", vec![]);
+    /// agent.emotions.set_emotion(Emotion::Joy);
+    /// agent.goals.push("Serve every guest".to_string());
+    /// agent.decide();
+    /// let report = agent.debug_report();
+    /// assert_eq!(report.recent_decisions[0].action, "Dance");
+    /// let json = serde_json::to_string(&report).unwrap();
+    /// assert!(json.contains("Serve every guest"));
+    /// ```
+    pub fn debug_report(&self) -> DebugReport {
+        let mut emotional_memories: Vec<(String, Emotion, f64)> = self
+            .emotions
+            .memories()
+            .map(|(trigger, emotion)| {
+                let strength = self.emotions.get_memory_strength(trigger);
+                (trigger.to_string(), emotion.clone(), strength)
+            })
+            .collect();
+        emotional_memories.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        emotional_memories.truncate(REPORT_MEMORY_COUNT);
+
+        let mut memories: Vec<(String, String)> = self
+            .intelligence
+            .memories()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        memories.sort();
+        memories.truncate(REPORT_MEMORY_COUNT);
+        let skipped = self.timeline.len().saturating_sub(REPORT_EMOTION_COUNT);
+
+        DebugReport {
+            id: self.id.clone(),
+            game_time: self.game_time,
+            fidelity: self.fidelity,
+            state: self.intelligence.get_current_state().clone(),
+            emotion: self.emotions.get_emotion().clone(),
+            emotion_vector: self.emotions.get_intensities(),
+            dyads: self.emotions.get_dyad_labels(0.0),
+            needs: self
+                .needs
+                .iter()
+                .map(|(name, need)| (name.to_string(), need.satisfaction))
+                .collect(),
+            activity: self.schedule.current_block().map(|block| block.activity.clone()),
+            top_emotional_memories: emotional_memories,
+            top_memories: memories,
+            goals: self.goals.clone(),
+            pending_dialogue: self.pending_dialogue.iter().cloned().collect(),
+            recent_decisions: self.recent_decisions.iter().cloned().collect(),
+            recent_emotions: self.timeline.samples().skip(skipped).cloned().collect(),
+        }
+    }
+
+    /// Gets the token the agent's in-flight work should run under, e.g. dialogue generation
+    /// with `CancellationToken::run`. It is cancelled by `cancel_work` and when the agent's scene
+    /// is cancelled.
+    pub fn get_cancellation_token(&self) -> &CancellationToken {
+        &self.work
+    }
+
+    /// Cancels the agent's in-flight work. Work started afterwards runs under a fresh token.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    ///
+    /// let mut agent = NpcAgent::new("brom", vec![]);
+    /// let token = agent.get_cancellation_token().clone();
+    /// agent.cancel_work();
+    /// assert!(token.is_cancelled());
+    /// assert!(!agent.get_cancellation_token().is_cancelled());
+    /// ```
+    pub fn cancel_work(&mut self) {
+        self.work.cancel();
+        self.work = self.scene.child_token();
+    }
+
+    /// Moves the agent into a scene: its work from now on is cancelled along with the scene.
+    /// Work already in flight keeps its old token.
+    pub fn set_scene_token(&mut self, scene: &CancellationToken) {
+        self.scene = scene.clone();
+        self.work = scene.child_token();
+    }
+
+    /// Gets the level of detail the agent is currently simulated at.
+    pub fn get_fidelity(&self) -> Fidelity {
+        self.fidelity
+    }
+
+    /// Switches the agent's level of detail.
+    ///
+    /// Switching to `Fidelity::Full` first simulates any pending reduced-fidelity time, so the
+    /// agent's state is current before it is simulated in detail again.
+    ///
+    /// # Arguments
+    ///
+    /// * `fidelity` - The new level of detail.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{Fidelity, NpcAgent};
+    /// let mut agent = NpcAgent::new("farmer", vec![]);
+    /// agent.set_fidelity(Fidelity::Reduced);
+    /// agent.set_fidelity(Fidelity::Full);
+    /// assert_eq!(agent.get_fidelity(), Fidelity::Full);
+    /// ```
+    pub fn set_fidelity(&mut self, fidelity: Fidelity) {
+        if fidelity == Fidelity::Full {
+            self.flush_pending();
+        }
+        self.fidelity = fidelity;
+    }
+
+    /// Accumulates reduced-fidelity time, simulating it once it reaches `chunk` seconds.
+    fn advance_reduced(&mut self, dt: f64, chunk: f64) {
+        self.pending_dt += dt;
+        if self.pending_dt >= chunk {
+            self.flush_pending();
+        }
+    }
+
+    /// Simulates all pending reduced-fidelity time as a single coarse tick. Low-priority phases
+    /// are left to the manager, which runs them within its frame budget.
+    fn flush_pending(&mut self) {
+        if self.pending_dt > 0.0 {
+            let dt = self.pending_dt;
+            self.pending_dt = 0.0;
+            for phase in UpdatePhase::ORDER {
+                if phase.priority() == WorkPriority::Low {
+                    self.consolidation_due = true;
+                } else {
+                    self.run_phase(phase, dt);
+                }
+            }
+            self.advance_clock(dt);
+        }
+    }
+}
+
+/// Manages a population of agents and advances them with game time.
+pub struct AgentManager {
+    /// The managed agents, keyed and iterated by ID for deterministic update order.
+    agents: BTreeMap<Symbol, NpcAgent>,
+    /// The fixed internal timestep in seconds.
+    timestep: f64,
+    /// The maximum number of steps run by a single call to `advance`.
+    max_steps: usize,
+    /// Game time that has elapsed but not yet been simulated.
+    accumulator: f64,
+    /// The total simulated game time in seconds.
+    game_time: f64,
+    /// The player's position, if known. Without it, every agent runs at full fidelity.
+    player_position: Option<(f64, f64)>,
+    /// Agents farther than this from the player run at reduced fidelity.
+    lod_radius: f64,
+    /// The number of steps reduced-fidelity agents accumulate before being simulated.
+    reduced_interval: usize,
+    /// The token of the current scene, parent of every agent's token.
+    scene: CancellationToken,
+    /// How long the simulation may take per frame, or `None` for no limit.
+    frame_budget: Option<Duration>,
+    /// The profiler of the current (or last) frame.
+    frame: FrameProfiler,
+    /// The agents whose low-priority phases were deferred, run first on the next step.
+    deferred: Vec<Symbol>,
+    /// The language model quotas of the agents.
+    quotas: QuotaTracker,
+    /// When the manager was created, the starting point of quota time.
+    created: Instant,
+}
+
+impl AgentManager {
+    /// Creates a new manager with the given fixed timestep.
+    ///
+    /// # Arguments
+    ///
+    /// * `timestep` - The fixed internal timestep in seconds of game time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::AgentManager;
+    /// let manager = AgentManager::new(0.5);
+    /// ```
+    pub fn new(timestep: f64) -> Self {
+        AgentManager {
+            agents: BTreeMap::new(),
+            timestep: timestep.max(f64::EPSILON),
+            max_steps: 120,
+            accumulator: 0.0,
+            game_time: 0.0,
+            player_position: None,
+            lod_radius: 100.0,
+            reduced_interval: 10,
+            scene: CancellationToken::new(),
+            frame_budget: None,
+            frame: FrameProfiler::start(None),
+            deferred: Vec::new(),
+            quotas: QuotaTracker::default(),
+            created: Instant::now(),
+        }
+    }
+
+    /// Sets how long the simulation may take per frame. Once a frame's budget is spent,
+    /// low-priority phases are deferred to later frames.
+    pub fn set_frame_budget(&mut self, budget: Option<Duration>) {
+        self.frame_budget = budget;
+    }
+
+    /// Gets the profile of the current frame, started by the last call to `advance`: the time
+    /// each module took and the work that was deferred.
+    pub fn get_frame_profile(&self) -> &FrameProfile {
+        self.frame.get_profile()
+    }
+
+    /// Runs low-priority work of the game (e.g. ambient chatter generation) as part of the
+    /// current frame, if its budget is not spent yet. The time is profiled under the module.
+    ///
+    /// # Returns
+    ///
+    /// The result of the work, or `None` if it was deferred. Deferred work is dropped without
+    /// running, not queued, so the game should call again with it on a later frame.
+    pub fn run_low_priority<T>(&mut self, module: &str, work: impl FnOnce() -> T) -> Option<T> {
+        self.frame.run(module, None, WorkPriority::Low, work)
+    }
+
+    /// Sets the player's position, used to decide which agents run at full fidelity.
+    pub fn set_player_position(&mut self, position: (f64, f64)) {
+        self.player_position = Some(position);
+    }
+
+    /// Configures the level-of-detail system.
+    ///
+    /// # Arguments
+    ///
+    /// * `radius` - Agents farther than this from the player run at reduced fidelity. Defaults to 100.0.
+    /// * `reduced_interval` - How many steps reduced-fidelity agents accumulate before being simulated. Defaults to 10.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, Fidelity, NpcAgent};
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.set_lod(50.0, 5);
+    /// let mut far = NpcAgent::new("hermit", vec![]);
+    /// far.position = (500.0, 0.0);
+    /// far.needs.add_need("rest", 0.01);
+    /// manager.add_agent(far);
+    /// manager.set_player_position((0.0, 0.0));
+    ///
+    /// manager.advance(3.0);
+    /// let hermit = manager.get_agent("hermit").unwrap();
+    /// assert_eq!(hermit.get_fidelity(), Fidelity::Reduced);
+    /// assert_eq!(hermit.needs.get_satisfaction("rest"), Some(1.0));
+    ///
+    /// manager.get_agent_mut("hermit").unwrap().position = (10.0, 0.0);
+    /// manager.advance(1.0);
+    /// let hermit = manager.get_agent("hermit").unwrap();
+    /// assert_eq!(hermit.get_fidelity(), Fidelity::Full);
+    /// assert!((hermit.needs.get_satisfaction("rest").unwrap() - 0.96).abs() < 1e-9);
+    /// ```
+    pub fn set_lod(&mut self, radius: f64, reduced_interval: usize) {
+        self.lod_radius = radius.max(0.0);
+        self.reduced_interval = reduced_interval.max(1);
+    }
+
+    /// Updates every agent's fidelity from its distance to the player.
+    fn update_fidelity(&mut self) {
+        let Some((px, py)) = self.player_position else {
+            return;
+        };
+        for agent in self.agents.values_mut() {
+            let fidelity = if distance(agent.position, (px, py)) > self.lod_radius {
+                Fidelity::Reduced
+            } else {
+                Fidelity::Full
+            };
+            if fidelity != agent.fidelity {
+                agent.set_fidelity(fidelity);
+            }
+        }
+    }
+
+    /// Sets the maximum number of catch-up steps run by a single call to `advance`.
+    ///
+    /// Time beyond the cap is dropped so that a long stall cannot cause a spiral of ever longer
+    /// frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_steps` - The maximum number of steps per call. Defaults to 120.
+    pub fn set_max_catch_up_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps.max(1);
+    }
+
+    /// Adds an agent, replacing any existing agent with the same ID. The agent joins the
+    /// current scene, so its work is cancelled by `cancel_scene`.
+    pub fn add_agent(&mut self, mut agent: NpcAgent) {
+        agent.set_scene_token(&self.scene);
+        self.agents.insert(agent.id.clone(), agent);
+    }
+
+    /// Gets the token of the current scene, for work that belongs to the scene but no agent.
+    pub fn get_scene_token(&self) -> &CancellationToken {
+        &self.scene
+    }
+
+    /// Cancels all in-flight work of the current scene and its agents, e.g. on a scene
+    /// transition, and starts a new scene.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(NpcAgent::new("brom", vec![]));
+    /// let token = manager.get_agent("brom").unwrap().get_cancellation_token().clone();
+    /// manager.cancel_scene();
+    /// assert!(token.is_cancelled());
+    /// assert!(!manager.get_agent("brom").unwrap().get_cancellation_token().is_cancelled());
+    /// ```
+    pub fn cancel_scene(&mut self) {
+        self.scene.cancel();
+        self.scene = CancellationToken::new();
+        for agent in self.agents.values_mut() {
+            agent.set_scene_token(&self.scene);
+        }
+    }
+
+    /// Removes an agent and returns it, if present.
+    pub fn remove_agent(&mut self, id: &str) -> Option<NpcAgent> {
+        self.agents.remove(id)
+    }
+
+    /// Retrieves an agent by its ID.
+    pub fn get_agent(&self, id: &str) -> Option<&NpcAgent> {
+        self.agents.get(id)
+    }
+
+    /// Retrieves a mutable reference to an agent by its ID.
+    pub fn get_agent_mut(&mut self, id: &str) -> Option<&mut NpcAgent> {
+        self.agents.get_mut(id)
+    }
+
+    /// Iterates over all agents in ID order.
+    pub fn agents(&self) -> impl Iterator<Item = &NpcAgent> {
+        self.agents.values()
+    }
+
+    /// Gets the language model quotas of the agents.
+    pub fn get_quotas(&self) -> &QuotaTracker {
+        &self.quotas
+    }
+
+    /// Gets the language model quotas of the agents, e.g. to set them or start a new session.
+    pub fn get_quotas_mut(&mut self) -> &mut QuotaTracker {
+        &mut self.quotas
+    }
+
+    /// Produces a line for an agent with the language model if its quota allows it, and with
+    /// fallback dialogue otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the agent.
+    /// * `generate` - Produces the line with the language model, e.g. by starting a request.
+    /// * `fallback` - Produces the line from authored dialogue.
+    ///
+    /// # Returns
+    ///
+    /// The line and whether the quota allowed generating it, or `None` if there is no such
+    /// agent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::quota::{Quota, QuotaStatus};
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(NpcAgent::new("brom", vec![]));
+    /// manager.get_quotas_mut().default = Quota::unlimited().with_per_session(2);
+    ///
+    /// let mut lines = Vec::new();
+    /// for _ in 0..3 {
+    ///     let (line, status) = manager
+    ///         .generate_or_fallback(
+    ///             "brom",
+    ///             |agent| format!("{} thinks hard about it.", agent.id),
+    ///             |_| "Hmm.".to_string(),
+    ///         )
+    ///         .unwrap();
+    ///     lines.push(line);
+    ///     if !status.is_allowed() {
+    ///         assert_eq!(status, QuotaStatus::SessionExhausted);
+    ///     }
+    /// }
+    /// assert_eq!(lines, ["brom thinks hard about it.", "brom thinks hard about it.", "Hmm."]);
+    ///
+    /// manager.get_quotas_mut().reset_session("brom");
+    /// let (_, status) = manager.generate_or_fallback("brom", |_| (), |_| ()).unwrap();
+    /// assert!(status.is_allowed());
+    /// ```
+    pub fn generate_or_fallback<T>(
+        &mut self,
+        id: &str,
+        generate: impl FnOnce(&mut NpcAgent) -> T,
+        fallback: impl FnOnce(&mut NpcAgent) -> T,
+    ) -> Option<(T, QuotaStatus)> {
+        let agent = self.agents.get_mut(id)?;
+        let now = self.created.elapsed().as_secs_f64();
+        let status = self.quotas.try_acquire(id, now);
+        let line = if status.is_allowed() {
+            generate(agent)
+        } else {
+            fallback(agent)
+        };
+        Some((line, status))
+    }
+
+    /// Gets the total simulated game time in seconds.
+    pub fn get_game_time(&self) -> f64 {
+        self.game_time
+    }
+
+    /// Lets nearby agents interact with each other without the player present.
+    ///
+    /// Each agent that is not attending to a partner is paired with the closest other free agent
+    /// within `radius` that both are willing to engage with. Every agent takes part in at most one
+    /// interaction per call.
+    ///
+    /// # Arguments
+    ///
+    /// * `radius` - How close two agents must be to interact.
+    /// * `law` - The law system used to decide which gossip is about crimes.
+    ///
+    /// # Returns
+    ///
+    /// A summary of each interaction, in the order they happened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::crime::LawSystem;
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(NpcAgent::new("baker", vec![]));
+    /// manager.add_agent(NpcAgent::new("miller", vec![]));
+    /// let mut hermit = NpcAgent::new("hermit", vec![]);
+    /// hermit.position = (500.0, 0.0);
+    /// manager.add_agent(hermit);
+    ///
+    /// let interactions = manager.simulate_interactions(10.0, &LawSystem::default());
+    /// assert_eq!(interactions.len(), 1);
+    /// assert_eq!(interactions[0].initiator, "baker");
+    /// assert_eq!(interactions[0].partner, "miller");
+    /// assert!(manager.simulate_interactions(10.0, &LawSystem::default()).is_empty());
+    /// ```
+    pub fn simulate_interactions(
+        &mut self,
+        radius: f64,
+        law: &LawSystem,
+    ) -> Vec<InteractionSummary> {
+        let started = Instant::now();
+        let summaries = self.pair_interactions(radius, law);
+        self.frame.record("interactions", started.elapsed());
+        summaries
+    }
+
+    /// Seeds initial relationships between agents from their personalities, so procedurally
+    /// placed NPCs start with plausible friendships and rivalries.
+    ///
+    /// Every pair of agents within `radius` of each other whose personality compatibility (see
+    /// `companionship::compatibility`, rescaled from -1.0 for rivals to 1.0 for friends) is at
+    /// least `threshold` away from zero is tied: each agent's affinity towards the other moves
+    /// by half the compatibility and its trust by a quarter, and each learns the other as a
+    /// "friend" or "rival", weighted by the strength of the compatibility. Other pairs stay
+    /// strangers. Pairs that have already met are left alone, so seeding again changes nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `radius` - How close two agents must be to know each other (`f64::INFINITY` for all).
+    /// * `threshold` - How compatible or incompatible a pair must be to be tied (0.0 to 1.0).
+    ///
+    /// # Returns
+    ///
+    /// The tied pairs with their compatibility, in ID order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// // (ID, openness, extraversion, agreeableness, neuroticism)
+    /// let people = [
+    ///     ("ada", 0.5, 0.5, 0.9, 0.1),
+    ///     ("bo", 0.5, 0.5, 0.8, 0.2),
+    ///     ("cy", 0.0, 1.0, 0.0, 1.0),
+    ///     ("dan", 1.0, 0.0, 0.1, 0.9),
+    /// ];
+    /// for (id, openness, extraversion, agreeableness, neuroticism) in people {
+    ///     let mut agent = NpcAgent::new(id, vec![]);
+    ///     agent.personality.set_openness(openness);
+    ///     agent.personality.set_extraversion(extraversion);
+    ///     agent.personality.set_agreeableness(agreeableness);
+    ///     agent.personality.set_neuroticism(neuroticism);
+    ///     manager.add_agent(agent);
+    /// }
+    ///
+    /// let ties = manager.seed_relationships(f64::INFINITY, 0.3);
+    /// assert_eq!(ties.len(), 2);
+    /// let ada = manager.get_agent("ada").unwrap();
+    /// assert!(ada.get_interlocutor("bo").unwrap().affinity > 0.3);
+    /// assert_eq!(ada.knowledge.get_relationships("ada")[0].relation_type, "friend");
+    /// let cy = manager.get_agent("cy").unwrap();
+    /// assert_eq!(cy.knowledge.get_relationships("cy")[0].relation_type, "rival");
+    /// assert!(cy.get_interlocutor("ada").is_none());
+    ///
+    /// assert!(manager.seed_relationships(f64::INFINITY, 0.3).is_empty());
+    /// let ada = manager.get_agent("ada").unwrap();
+    /// assert_eq!(ada.knowledge.get_relationships("ada").len(), 1);
+    /// ```
+    pub fn seed_relationships(
+        &mut self,
+        radius: f64,
+        threshold: f64,
+    ) -> Vec<(Symbol, Symbol, f64)> {
+        let ids: Vec<Symbol> = self.agents.keys().cloned().collect();
+        let mut ties = Vec::new();
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                let (first, second) = (&self.agents[a], &self.agents[b]);
+                let met = first.get_interlocutor(b).is_some()
+                    || second.get_interlocutor(a).is_some();
+                if met || distance(first.position, second.position) > radius {
+                    continue;
+                }
+                let compatibility = first.personality.compatibility(&second.personality);
+                if compatibility.abs() < threshold {
+                    continue;
+                }
+                let relation = if compatibility > 0.0 { "friend" } else { "rival" };
+                for (own, other) in [(a, b), (b, a)] {
+                    let Some(agent) = self.agents.get_mut(own) else {
+                        continue;
+                    };
+                    let context = agent.interlocutor(other);
+                    context.adjust_affinity(0.5 * compatibility);
+                    context.adjust_trust(0.25 * compatibility);
+                    agent.learn_weighted_fact(own, other, relation, compatibility.abs());
+                }
+                ties.push((a.clone(), b.clone(), compatibility));
+            }
+        }
+        ties
+    }
+
+    /// Pairs up free agents and has them interact.
+    fn pair_interactions(&mut self, radius: f64, law: &LawSystem) -> Vec<InteractionSummary> {
+        let ids: Vec<Symbol> = self.agents.keys().cloned().collect();
+        let mut busy: Vec<Symbol> = Vec::new();
+        let mut summaries = Vec::new();
+        for id in &ids {
+            if busy.contains(id) || self.agents[id].focus.is_some() {
+                continue;
+            }
+            let agent = &self.agents[id];
+            let partner_id = ids
+                .iter()
+                .filter(|other| *other != id && !busy.contains(other))
+                .filter_map(|other| {
+                    let partner = &self.agents[other];
+                    let distance = distance(agent.position, partner.position);
+                    let willing = partner.focus.is_none()
+                        && agent.can_engage(other)
+                        && partner.can_engage(id);
+                    (willing && distance <= radius).then_some((other, distance))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(other, _)| other.clone());
+            let Some(partner_id) = partner_id else {
+                continue;
+            };
+            let Some(mut partner) = self.agents.remove(&partner_id) else {
+                continue;
+            };
+            if let Some(agent) = self.agents.get_mut(id) {
+                summaries.push(interaction::interact(agent, &mut partner, law));
+            }
+            self.agents.insert(partner_id.clone(), partner);
+            busy.push(id.clone());
+            busy.push(partner_id);
+        }
+        summaries
+    }
+
+    /// Has every agent choose an action, like `NpcAgent::decide`, scoring all of their
+    /// candidates in one batch.
+    ///
+    /// The candidates of all agents are laid out in a `ScoreBatch` and scored without building
+    /// explanations; only the chosen actions are explained. With the `parallel` feature,
+    /// proposing, scoring, and selecting run in parallel across agents, so large crowds stay
+    /// within their frame budget.
+    ///
+    /// # Returns
+    ///
+    /// The action each agent chose, in update order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let frightened = || {
+    ///     let mut guard = NpcAgent::new("guard", vec![]);
+    ///     guard.emotions.set_emotion(Emotion::Fear);
+    ///     guard
+    /// };
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(frightened());
+    /// manager.add_agent(NpcAgent::new("miller", vec![]));
+    ///
+    /// let decisions = manager.decide_all();
+    /// assert_eq!(decisions.len(), 2);
+    /// assert_eq!(decisions[0], ("guard".into(), frightened().decide()));
+    /// ```
+    pub fn decide_all(&mut self) -> Vec<(Symbol, String)> {
+        let agents: Vec<&NpcAgent> = self.agents.values().collect();
+        #[cfg(feature = "parallel")]
+        let proposals: Vec<Vec<(&str, f64, Proposer<'_>)>> = {
+            use rayon::prelude::*;
+            agents.par_iter().map(|agent| agent.proposals()).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let proposals: Vec<Vec<(&str, f64, Proposer<'_>)>> =
+            agents.iter().map(|agent| agent.proposals()).collect();
+        let mut batch = ScoreBatch::new();
+        for candidates in &proposals {
+            batch.push_agent(candidates.iter().map(|(action, base, _)| (*action, *base)));
+        }
+        batch.apply(|agent, action, score| agents[agent].modified_score(action, score));
+        let chosen: Vec<(Explanation, Vec<UtilityCandidate>)> = batch
+            .select_best()
+            .into_iter()
+            .enumerate()
+            .map(|(index, chosen)| {
+                let explanation = match chosen {
+                    Some(chosen) => {
+                        let (action, base, proposer) = proposals[index][chosen];
+                        let dyads = agents[index].emotions.get_dyad_labels(0.0).join("+");
+                        agents[index].explain_with(action, base, proposer, &dyads)
+                    }
+                    None => Explanation::new("Observe", 0.0, "no candidates"),
+                };
+                (explanation, batch.candidates(index))
+            })
+            .collect();
+
+        self.agents
+            .values_mut()
+            .zip(chosen)
+            .map(|(agent, (explanation, candidates))| {
+                let action = agent.record_decision(explanation, candidates);
+                (agent.id.clone(), action)
+            })
+            .collect()
+    }
+
+    /// Advances all agents by the given amount of game time.
+    ///
+    /// The delta is accumulated and simulated in fixed timesteps. Within each step, each phase
+    /// runs for every full-fidelity agent before the next phase starts, so cross-agent effects
+    /// observe a consistent world. Reduced-fidelity agents are advanced after the phases.
+    ///
+    /// Each call starts a new frame for profiling. Once the frame budget is spent, agents skip
+    /// low-priority phases; they run them first on a later step.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_time_delta` - The elapsed game time in seconds since the last call.
+    ///
+    /// # Returns
+    ///
+    /// The number of fixed steps that were simulated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(NpcAgent::new("guard", vec![]));
+    /// assert_eq!(manager.advance(0.5), 0);
+    /// assert_eq!(manager.advance(2.75), 3);
+    /// assert_eq!(manager.get_game_time(), 3.0);
+    ///
+    /// // With no time to spare, reflection waits for a later frame.
+    /// manager.set_frame_budget(Some(Duration::ZERO));
+    /// manager.advance(1.0);
+    /// assert_eq!(manager.get_frame_profile().deferred[0].module, "consolidation");
+    /// ```
+    pub fn advance(&mut self, game_time_delta: f64) -> usize {
+        self.frame = FrameProfiler::start(self.frame_budget);
+        self.accumulator += game_time_delta.max(0.0);
+        self.update_fidelity();
+        let chunk = self.timestep * self.reduced_interval as f64;
+        let timestep = self.timestep;
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < self.max_steps {
+            for phase in UpdatePhase::ORDER {
+                if phase.priority() == WorkPriority::Low {
+                    self.run_deferrable_phase(phase);
+                    continue;
+                }
+                let agents = &mut self.agents;
+                self.frame.time(phase.name(), || {
+                    for agent in agents.values_mut() {
+                        if agent.fidelity == Fidelity::Full {
+                            agent.run_phase(phase, timestep);
+                        }
+                    }
+                });
+            }
+            let agents = &mut self.agents;
+            self.frame.time("clock", || {
+                for agent in agents.values_mut() {
+                    match agent.fidelity {
+                        Fidelity::Full => agent.advance_clock(timestep),
+                        Fidelity::Reduced => agent.advance_reduced(timestep, chunk),
+                    }
+                }
+            });
+            self.accumulator -= self.timestep;
+            self.game_time += self.timestep;
+            steps += 1;
+        }
+        if steps == self.max_steps {
+            self.accumulator = self.accumulator.min(self.timestep);
+        }
+        steps
+    }
+
+    /// Runs a low-priority phase for each full-fidelity agent, and each agent whose coarse tick
+    /// left it out, while the frame budget allows, starting with the agents deferred before, and
+    /// defers it for the rest.
+    fn run_deferrable_phase(&mut self, phase: UpdatePhase) {
+        let mut order: Vec<Symbol> = self.deferred.drain(..).collect();
+        let deferred: HashSet<Symbol> = order.iter().cloned().collect();
+        order.extend(self.agents.keys().filter(|id| !deferred.contains(*id)).cloned());
+        for id in order {
+            let Some(agent) = self.agents.get_mut(&id) else {
+                continue;
+            };
+            if agent.fidelity != Fidelity::Full && !agent.consolidation_due {
+                continue;
+            }
+            let ran = self.frame.run(phase.name(), Some(id.as_str()), phase.priority(), || {
+                agent.run_phase(phase, self.timestep)
+            });
+            if ran.is_none() {
+                self.deferred.push(id);
+            }
+        }
+    }
+}
+
+/// Computes the distance between two positions.
+pub(crate) fn distance((ax, ay): (f64, f64), (bx, by): (f64, f64)) -> f64 {
+    ((ax - bx).powi(2) + (ay - by).powi(2)).sqrt()
+}
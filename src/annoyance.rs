@@ -0,0 +1,175 @@
+//! # Annoyance Module
+//!
+//! This module models how an NPC grows tired of being pestered. Every line the player says adds
+//! a little annoyance, and coming back to talk again shortly after the last conversation adds a
+//! lot; annoyance fades over game time. As it grows, the NPC first keeps its answers short, then
+//! charges more for its goods, and finally walks away.
+//!
+//! How much pestering an NPC tolerates depends on its personality: agreeable, even-tempered NPCs
+//! are patient, while disagreeable, anxious ones reach every threshold sooner.
+
+use serde::{Deserialize, Serialize};
+
+use crate::personality::Personality;
+
+/// Configures how annoyance grows, fades, and takes effect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnoyancePolicy {
+    /// The annoyance added by every line.
+    pub per_line: f64,
+    /// The annoyance added by starting a conversation within `pester_window` of the last contact.
+    pub per_return: f64,
+    /// How soon after the last contact, in seconds of game time, a new conversation is pestering.
+    pub pester_window: f64,
+    /// How long it takes annoyance to halve, in seconds of game time.
+    pub half_life: f64,
+    /// The annoyance at which an NPC of average patience starts answering tersely.
+    pub terse_threshold: f64,
+    /// The annoyance at which an NPC of average patience starts raising its prices.
+    pub surcharge_threshold: f64,
+    /// The annoyance at which an NPC of average patience walks away.
+    pub walk_away_threshold: f64,
+    /// The price increase at full annoyance, as a fraction of the price.
+    pub max_surcharge: f64,
+}
+
+impl Default for AnnoyancePolicy {
+    /// Annoyance halves every half hour of game time; returning within ten minutes counts as
+    /// pestering.
+    fn default() -> Self {
+        AnnoyancePolicy {
+            per_line: 0.03,
+            per_return: 0.25,
+            pester_window: 600.0,
+            half_life: 1_800.0,
+            terse_threshold: 0.3,
+            surcharge_threshold: 0.5,
+            walk_away_threshold: 0.8,
+            max_surcharge: 0.5,
+        }
+    }
+}
+
+impl AnnoyancePolicy {
+    /// Returns how patient a personality is, as a multiplier for the thresholds. An average
+    /// personality has a patience of 1.0.
+    pub fn patience(personality: &Personality) -> f64 {
+        0.6 + 0.5 * personality.agreeableness + 0.3 * (1.0 - personality.neuroticism)
+    }
+
+    /// Scales a threshold by the patience of a personality.
+    fn threshold(&self, base: f64, personality: &Personality) -> f64 {
+        (base * Self::patience(personality)).min(1.0)
+    }
+
+    /// Returns true if the NPC is annoyed enough to answer tersely.
+    pub fn is_terse(&self, level: f64, personality: &Personality) -> bool {
+        level >= self.threshold(self.terse_threshold, personality)
+    }
+
+    /// Returns true if the NPC is annoyed enough to walk away.
+    pub fn walks_away(&self, level: f64, personality: &Personality) -> bool {
+        level >= self.threshold(self.walk_away_threshold, personality)
+    }
+
+    /// Returns the multiplier the NPC applies to its prices.
+    ///
+    /// Prices are unchanged below the surcharge threshold, and rise linearly above it up to
+    /// `1.0 + max_surcharge` at full annoyance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::annoyance::AnnoyancePolicy;
+    /// use athena::personality::Personality;
+    ///
+    /// let policy = AnnoyancePolicy::default();
+    /// let personality = Personality::new();
+    /// assert_eq!(policy.price_factor(0.4, &personality), 1.0);
+    /// assert!((policy.price_factor(0.75, &personality) - 1.25).abs() < 1e-9);
+    /// assert_eq!(policy.price_factor(1.0, &personality), 1.5);
+    /// ```
+    pub fn price_factor(&self, level: f64, personality: &Personality) -> f64 {
+        let threshold = self.threshold(self.surcharge_threshold, personality);
+        if level < threshold || threshold >= 1.0 {
+            return 1.0;
+        }
+        1.0 + self.max_surcharge * (level - threshold) / (1.0 - threshold)
+    }
+
+    /// Builds the prompt directive describing the NPC's annoyance, or `None` if it is not
+    /// annoyed enough to show it.
+    pub fn directive(&self, level: f64, personality: &Personality) -> Option<String> {
+        if !self.is_terse(level, personality) {
+            return None;
+        }
+        let directive = if level >= self.threshold(self.surcharge_threshold, personality) {
+            "You are fed up with this person pestering you: be curt and show your impatience."
+        } else {
+            "This person keeps bothering you: keep your answers short."
+        };
+        Some(directive.to_string())
+    }
+}
+
+/// Represents how annoyed an NPC is with one partner.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Annoyance {
+    /// The annoyance at the last contact (between 0.0 and 1.0).
+    level: f64,
+    /// The game time of the last contact, if any.
+    last_contact: Option<f64>,
+}
+
+impl Annoyance {
+    /// Returns the annoyance at a game time, after fading since the last contact.
+    pub fn level_at(&self, now: f64, policy: &AnnoyancePolicy) -> f64 {
+        match self.last_contact {
+            Some(last) if policy.half_life > 0.0 => {
+                self.level * 0.5_f64.powf((now - last).max(0.0) / policy.half_life)
+            }
+            _ => self.level,
+        }
+    }
+
+    /// Records a line from the partner.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current game time.
+    /// * `opening` - Whether the line starts a new conversation.
+    /// * `policy` - How annoyance grows and fades.
+    ///
+    /// # Returns
+    ///
+    /// The annoyance after the line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::annoyance::{Annoyance, AnnoyancePolicy};
+    ///
+    /// let policy = AnnoyancePolicy::default();
+    /// let mut annoyance = Annoyance::default();
+    /// annoyance.record(0.0, true, &policy);
+    /// let pestered = annoyance.record(60.0, true, &policy);
+    /// assert!(pestered > 0.25);
+    /// assert!(annoyance.level_at(60.0 + 1_800.0, &policy) < pestered / 2.0 + 1e-9);
+    /// ```
+    pub fn record(&mut self, now: f64, opening: bool, policy: &AnnoyancePolicy) -> f64 {
+        let returning =
+            opening && self.last_contact.is_some_and(|last| now - last < policy.pester_window);
+        let mut level = self.level_at(now, policy) + policy.per_line;
+        if returning {
+            level += policy.per_return;
+        }
+        self.level = level.clamp(0.0, 1.0);
+        self.last_contact = Some(now);
+        self.level
+    }
+
+    /// Forgets all annoyance.
+    pub fn clear(&mut self) {
+        self.level = 0.0;
+    }
+}
@@ -0,0 +1,527 @@
+//! # Appraisal Module
+//!
+//! This module combines the emotional appraisals of events that happen at the same time. When an
+//! NPC is ambushed and insulted in the same tick, the reaction should reflect both events instead
+//! of whichever was handled last. An `AppraisalAccumulator` collects the tick's appraisals and
+//! resolves them into one emotional state:
+//!
+//! - Appraisals of the same emotion reinforce each other without exceeding full intensity
+//!   (two appraisals of 0.5 give 0.75).
+//! - Dominance rules let some emotions suppress others: fear of an ambush drowns out the anger
+//!   at an insult, and threats crowd out joy and trust. A suppressed emotion keeps only the part
+//!   of its intensity the dominant one leaves (`1.0 - dominant`).
+//!
+//! The strongest emotion left becomes the NPC's current emotion.
+//!
+//! Games can add their own emotional logic through an `AppraisalRegistry`: each registered rule
+//! matches a pattern of event kinds (e.g. "perceived.DrewWeapon" or "deed.*") and computes the
+//! emotions a matching event provokes. Rules run by priority, an exclusive rule stops the rules
+//! below it, and each rule can be enabled or disabled per NPC archetype. Clones of a registry
+//! share their rules, so rules registered at runtime reach every NPC holding the registry.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::emotional_response::{Emotion, EmotionalResponse};
+
+/// Represents the emotional appraisal of a single event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Appraisal {
+    /// The emotion the event provokes.
+    pub emotion: Emotion,
+    /// How strongly it provokes it (between 0.0 and 1.0).
+    pub intensity: f64,
+    /// What caused the appraisal (e.g. "ambushed by bandits"), for debugging.
+    pub source: String,
+}
+
+/// Represents the appraisals collected during one tick.
+#[derive(Debug, Clone)]
+pub struct AppraisalAccumulator {
+    /// The appraisals collected so far, in arrival order.
+    pending: Vec<Appraisal>,
+    /// The dominance rules as `(dominant, suppressed)` pairs.
+    dominance: Vec<(Emotion, Emotion)>,
+    /// The intensities the emotions touched this tick had before its first appraisal.
+    baseline: HashMap<Emotion, f64>,
+}
+
+impl Default for AppraisalAccumulator {
+    /// An accumulator where fear suppresses anger, joy, trust, and anticipation, anger suppresses
+    /// joy and trust, sadness suppresses joy, and disgust suppresses trust.
+    fn default() -> Self {
+        AppraisalAccumulator {
+            pending: Vec::new(),
+            baseline: HashMap::new(),
+            dominance: vec![
+                (Emotion::Fear, Emotion::Anger),
+                (Emotion::Fear, Emotion::Joy),
+                (Emotion::Fear, Emotion::Trust),
+                (Emotion::Fear, Emotion::Anticipation),
+                (Emotion::Anger, Emotion::Joy),
+                (Emotion::Anger, Emotion::Trust),
+                (Emotion::Sadness, Emotion::Joy),
+                (Emotion::Disgust, Emotion::Trust),
+            ],
+        }
+    }
+}
+
+impl AppraisalAccumulator {
+    /// Creates a new accumulator without dominance rules.
+    pub fn new() -> Self {
+        AppraisalAccumulator {
+            pending: Vec::new(),
+            dominance: Vec::new(),
+            baseline: HashMap::new(),
+        }
+    }
+
+    /// Adds a dominance rule: when both emotions are appraised, `dominant` suppresses
+    /// `suppressed`.
+    pub fn add_dominance(&mut self, dominant: Emotion, suppressed: Emotion) {
+        if !self.dominance.contains(&(dominant.clone(), suppressed.clone())) {
+            self.dominance.push((dominant, suppressed));
+        }
+    }
+
+    /// Gets the dominance rules as `(dominant, suppressed)` pairs.
+    pub fn get_dominance(&self) -> &[(Emotion, Emotion)] {
+        &self.dominance
+    }
+
+    /// Adds the appraisal of an event.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion the event provokes. `Neutral` appraisals are ignored.
+    /// * `intensity` - How strongly it provokes it (between 0.0 and 1.0).
+    /// * `source` - What caused the appraisal.
+    pub fn add(&mut self, emotion: Emotion, intensity: f64, source: &str) {
+        if emotion == Emotion::Neutral {
+            return;
+        }
+        self.pending.push(Appraisal {
+            emotion,
+            intensity: intensity.clamp(0.0, 1.0),
+            source: source.to_string(),
+        });
+    }
+
+    /// Iterates over the collected appraisals, in arrival order.
+    pub fn appraisals(&self) -> impl Iterator<Item = &Appraisal> {
+        self.pending.iter()
+    }
+
+    /// Returns the number of collected appraisals.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns true if nothing has been appraised.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Removes all collected appraisals, e.g. at the end of a tick.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.baseline.clear();
+    }
+
+    /// Combines the collected appraisals into one emotional state.
+    ///
+    /// # Returns
+    ///
+    /// The resulting `(emotion, intensity)` pairs, strongest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::appraisal::AppraisalAccumulator;
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let mut appraisals = AppraisalAccumulator::default();
+    /// appraisals.add(Emotion::Fear, 0.5, "ambushed by bandits");
+    /// appraisals.add(Emotion::Anger, 0.8, "insulted by a bandit");
+    /// appraisals.add(Emotion::Fear, 0.5, "saw a crossbow");
+    /// assert_eq!(appraisals.resolve(), vec![(Emotion::Fear, 0.75), (Emotion::Anger, 0.2)]);
+    /// ```
+    pub fn resolve(&self) -> Vec<(Emotion, f64)> {
+        let combined = self.combine();
+        let mut resolved: Vec<(Emotion, f64)> = combined
+            .iter()
+            .map(|(emotion, intensity)| {
+                (emotion.clone(), intensity * self.kept(&combined, emotion))
+            })
+            .filter(|(_, intensity)| *intensity > 0.0)
+            .collect();
+        resolved.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| a.0.name().cmp(b.0.name()))
+        });
+        resolved
+    }
+
+    /// Applies the combined appraisals to an emotional response.
+    ///
+    /// Every appraised emotion is raised to at least its combined intensity, then the tick's
+    /// dominant appraisals suppress the emotions they dominate, including what the NPC already
+    /// felt. The strongest resolved emotion becomes the current emotion. Everything is computed
+    /// from the intensities the emotions had before the tick's first appraisal, so applying
+    /// again after another event arrives in the same tick gives the same result whatever order
+    /// the events came in.
+    ///
+    /// # Returns
+    ///
+    /// The new current emotion, or `None` if nothing was appraised.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::appraisal::AppraisalAccumulator;
+    /// use athena::emotional_response::{Emotion, EmotionalResponse};
+    ///
+    /// let mut emotions = EmotionalResponse::new();
+    /// let mut appraisals = AppraisalAccumulator::default();
+    /// appraisals.add(Emotion::Fear, 0.9, "ambushed by bandits");
+    /// appraisals.add(Emotion::Anger, 0.6, "insulted by a bandit");
+    /// assert_eq!(appraisals.apply(&mut emotions), Some(Emotion::Fear));
+    /// assert_eq!(emotions.get_emotion(), &Emotion::Fear);
+    /// assert_eq!(emotions.get_intensity(&Emotion::Fear), 0.9);
+    ///
+    /// // The insult arriving first does not shield the anger from the fear.
+    /// let mut reversed = EmotionalResponse::new();
+    /// let mut appraisals = AppraisalAccumulator::default();
+    /// appraisals.add(Emotion::Anger, 0.6, "insulted by a bandit");
+    /// appraisals.apply(&mut reversed);
+    /// appraisals.add(Emotion::Fear, 0.9, "ambushed by bandits");
+    /// appraisals.apply(&mut reversed);
+    /// assert_eq!(reversed.get_emotion(), &Emotion::Fear);
+    /// let anger = reversed.get_intensity(&Emotion::Anger);
+    /// assert!((anger - emotions.get_intensity(&Emotion::Anger)).abs() < 1e-9);
+    /// ```
+    pub fn apply(&mut self, emotions: &mut EmotionalResponse) -> Option<Emotion> {
+        let combined = self.combine();
+        let touched = combined.keys().chain(
+            self.dominance
+                .iter()
+                .filter(|(dominant, _)| combined.contains_key(dominant))
+                .map(|(_, suppressed)| suppressed),
+        );
+        for emotion in touched {
+            if !self.baseline.contains_key(emotion) {
+                self.baseline.insert(emotion.clone(), emotions.get_intensity(emotion));
+            }
+        }
+        let dominant = self.resolve().into_iter().next().map(|(dominant, _)| dominant);
+        if let Some(dominant) = &dominant {
+            emotions.set_emotion(dominant.clone());
+        }
+        for (emotion, baseline) in &self.baseline {
+            let appraised = combined.get(emotion).copied().unwrap_or(0.0);
+            let intensity = baseline.max(appraised) * self.kept(&combined, emotion);
+            emotions.set_intensity(emotion.clone(), intensity);
+        }
+        dominant
+    }
+
+    /// Combines the collected appraisals of each emotion, without dominance.
+    fn combine(&self) -> HashMap<Emotion, f64> {
+        let mut combined: HashMap<Emotion, f64> = HashMap::new();
+        for appraisal in &self.pending {
+            let intensity = combined.entry(appraisal.emotion.clone()).or_insert(0.0);
+            *intensity = 1.0 - (1.0 - *intensity) * (1.0 - appraisal.intensity);
+        }
+        combined
+    }
+
+    /// Computes the share of an emotion's intensity the dominant appraised emotions leave.
+    fn kept(&self, combined: &HashMap<Emotion, f64>, emotion: &Emotion) -> f64 {
+        self.dominance
+            .iter()
+            .filter(|(_, suppressed)| suppressed == emotion)
+            .filter_map(|(dominant, _)| combined.get(dominant))
+            .fold(1.0, |kept, dominant| kept * (1.0 - dominant))
+    }
+}
+
+/// Represents an event an NPC can appraise with custom rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppraisalEvent {
+    /// What happened, as a dotted kind (e.g. "perceived.DrewWeapon", "deed.Theft", or a kind of
+    /// the game's own such as "weather.storm").
+    pub kind: String,
+    /// Who caused the event, if anyone.
+    pub actor: Option<String>,
+    /// How significant the event is (e.g. the weighted magnitude of a deed), 1.0 by default.
+    pub magnitude: f64,
+    /// What happened, in words (e.g. "alice drew a weapon").
+    pub description: String,
+}
+
+impl AppraisalEvent {
+    /// Creates an event without an actor, of magnitude 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - What happened, as a dotted kind.
+    /// * `description` - What happened, in words.
+    pub fn new(kind: &str, description: &str) -> Self {
+        AppraisalEvent {
+            kind: kind.to_string(),
+            actor: None,
+            magnitude: 1.0,
+            description: description.to_string(),
+        }
+    }
+
+    /// Sets who caused the event.
+    pub fn by(mut self, actor: &str) -> Self {
+        self.actor = Some(actor.to_string());
+        self
+    }
+
+    /// Sets how significant the event is.
+    pub fn with_magnitude(mut self, magnitude: f64) -> Self {
+        self.magnitude = magnitude;
+        self
+    }
+}
+
+/// Returns true if an event kind matches a pattern: the kind itself, "*" for any kind, or a
+/// prefix followed by ".*" (e.g. "deed.*" matches "deed.Theft").
+pub(crate) fn kind_matches(pattern: &str, kind: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some("") => true,
+        Some(prefix) if prefix.ends_with('.') => kind.starts_with(prefix),
+        _ => pattern == kind,
+    }
+}
+
+/// A function computing the emotions an event provokes, as `(emotion, intensity)` pairs.
+type DeltaFn = Arc<dyn Fn(&AppraisalEvent) -> Vec<(Emotion, f64)> + Send + Sync>;
+
+/// Represents a custom appraisal rule registered by the game.
+#[derive(Clone)]
+pub struct AppraisalRule {
+    /// A unique ID for the rule; registering another rule with the same ID replaces it.
+    pub id: String,
+    /// The event kinds the rule matches (e.g. "deed.Theft", "deed.*", or "*").
+    pub pattern: String,
+    /// Rules with a higher priority run first.
+    pub priority: i32,
+    /// Whether a matching event skips the rules of lower priority.
+    pub exclusive: bool,
+    /// The archetypes the rule is enabled for, or empty for every NPC.
+    pub archetypes: HashSet<String>,
+    delta: DeltaFn,
+}
+
+impl AppraisalRule {
+    /// Creates a non-exclusive rule of priority 0, enabled for every NPC.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique ID for the rule.
+    /// * `pattern` - The event kinds the rule matches.
+    /// * `delta` - Computes the emotions a matching event provokes.
+    pub fn new(
+        id: &str,
+        pattern: &str,
+        delta: impl Fn(&AppraisalEvent) -> Vec<(Emotion, f64)> + Send + Sync + 'static,
+    ) -> Self {
+        AppraisalRule {
+            id: id.to_string(),
+            pattern: pattern.to_string(),
+            priority: 0,
+            exclusive: false,
+            archetypes: HashSet::new(),
+            delta: Arc::new(delta),
+        }
+    }
+
+    /// Sets the priority of the rule.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Makes a matching event skip the rules of lower priority.
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+
+    /// Enables the rule only for the given archetypes.
+    pub fn for_archetypes<'a>(mut self, archetypes: impl IntoIterator<Item = &'a str>) -> Self {
+        self.archetypes = archetypes.into_iter().map(str::to_string).collect();
+        self
+    }
+
+    /// Computes the emotions an event provokes.
+    pub fn appraise(&self, event: &AppraisalEvent) -> Vec<(Emotion, f64)> {
+        (self.delta)(event)
+    }
+}
+
+/// Represents the rules of a registry and their per-archetype overrides.
+#[derive(Default)]
+struct RegistryState {
+    /// The rules, sorted by descending priority and then in registration order.
+    rules: Vec<AppraisalRule>,
+    /// Rules enabled (`true`) or disabled (`false`) for an archetype, by `(rule, archetype)`.
+    overrides: HashMap<(String, String), bool>,
+}
+
+impl RegistryState {
+    /// Returns true if a rule is enabled for an archetype.
+    fn allows(&self, rule: &AppraisalRule, archetype: Option<&str>) -> bool {
+        let Some(archetype) = archetype else {
+            return rule.archetypes.is_empty();
+        };
+        let key = (rule.id.clone(), archetype.to_string());
+        match self.overrides.get(&key) {
+            Some(enabled) => *enabled,
+            None => rule.archetypes.is_empty() || rule.archetypes.contains(archetype),
+        }
+    }
+}
+
+/// Holds the custom appraisal rules of a game.
+///
+/// The registry is a shared handle: clones see the same rules, so a registry can be given to
+/// every NPC and extended at runtime.
+#[derive(Clone, Default)]
+pub struct AppraisalRegistry {
+    state: Arc<RwLock<RegistryState>>,
+}
+
+impl AppraisalRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        AppraisalRegistry::default()
+    }
+
+    /// Registers a rule, replacing any rule with the same ID.
+    pub fn register(&self, rule: AppraisalRule) {
+        let mut state = self.state.write().unwrap();
+        state.rules.retain(|existing| existing.id != rule.id);
+        let position = state
+            .rules
+            .iter()
+            .position(|existing| existing.priority < rule.priority)
+            .unwrap_or(state.rules.len());
+        state.rules.insert(position, rule);
+    }
+
+    /// Removes a rule.
+    ///
+    /// # Returns
+    ///
+    /// True if the rule was registered.
+    pub fn unregister(&self, id: &str) -> bool {
+        let mut state = self.state.write().unwrap();
+        let before = state.rules.len();
+        state.rules.retain(|rule| rule.id != id);
+        state.overrides.retain(|(rule, _), _| rule != id);
+        state.rules.len() < before
+    }
+
+    /// Gets the IDs of the registered rules, in the order they run.
+    pub fn rule_ids(&self) -> Vec<String> {
+        let state = self.state.read().unwrap();
+        state.rules.iter().map(|rule| rule.id.clone()).collect()
+    }
+
+    /// Enables a rule for an archetype, whatever archetypes the rule was registered for.
+    pub fn enable(&self, id: &str, archetype: &str) {
+        let key = (id.to_string(), archetype.to_string());
+        self.state.write().unwrap().overrides.insert(key, true);
+    }
+
+    /// Disables a rule for an archetype.
+    pub fn disable(&self, id: &str, archetype: &str) {
+        let key = (id.to_string(), archetype.to_string());
+        self.state.write().unwrap().overrides.insert(key, false);
+    }
+
+    /// Returns true if a rule is enabled for an archetype (`None` for NPCs without one).
+    pub fn is_enabled(&self, id: &str, archetype: Option<&str>) -> bool {
+        let state = self.state.read().unwrap();
+        state
+            .rules
+            .iter()
+            .find(|rule| rule.id == id)
+            .is_some_and(|rule| state.allows(rule, archetype))
+    }
+
+    /// Appraises an event with the rules enabled for an archetype.
+    ///
+    /// Matching rules run by descending priority; an exclusive one stops the rules below it.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to appraise.
+    /// * `archetype` - The archetype of the NPC appraising it, if any.
+    ///
+    /// # Returns
+    ///
+    /// The appraisals the rules produced, each sourced from the event and the rule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::appraisal::{AppraisalEvent, AppraisalRegistry, AppraisalRule};
+    /// use athena::emotional_response::Emotion;
+    ///
+    /// let registry = AppraisalRegistry::new();
+    /// registry.register(AppraisalRule::new("theft-outrage", "deed.Theft", |event| {
+    ///     vec![(Emotion::Anger, 0.4 * event.magnitude)]
+    /// }));
+    /// registry.register(
+    ///     AppraisalRule::new("greedy-envy", "deed.*", |_| vec![(Emotion::Anticipation, 0.3)])
+    ///         .with_priority(10)
+    ///         .exclusive()
+    ///         .for_archetypes(["merchant"]),
+    /// );
+    ///
+    /// let theft = AppraisalEvent::new("deed.Theft", "alice stole an apple").by("alice");
+    /// let guard = registry.evaluate(&theft, Some("guard"));
+    /// assert_eq!(guard[0].emotion, Emotion::Anger);
+    /// assert_eq!(guard[0].source, "alice stole an apple (theft-outrage)");
+    ///
+    /// // The exclusive merchant rule runs first and skips the outrage.
+    /// let merchant = registry.evaluate(&theft, Some("merchant"));
+    /// assert_eq!(merchant.len(), 1);
+    /// assert_eq!(merchant[0].emotion, Emotion::Anticipation);
+    ///
+    /// registry.disable("greedy-envy", "merchant");
+    /// assert_eq!(registry.evaluate(&theft, Some("merchant"))[0].emotion, Emotion::Anger);
+    /// ```
+    pub fn evaluate(&self, event: &AppraisalEvent, archetype: Option<&str>) -> Vec<Appraisal> {
+        let state = self.state.read().unwrap();
+        let mut appraisals = Vec::new();
+        let matching = state
+            .rules
+            .iter()
+            .filter(|rule| kind_matches(&rule.pattern, &event.kind))
+            .filter(|rule| state.allows(rule, archetype));
+        for rule in matching {
+            let source = format!("{} ({})", event.description, rule.id);
+            for (emotion, intensity) in rule.appraise(event) {
+                appraisals.push(Appraisal {
+                    emotion,
+                    intensity: intensity.clamp(0.0, 1.0),
+                    source: source.clone(),
+                });
+            }
+            if rule.exclusive {
+                break;
+            }
+        }
+        appraisals
+    }
+}
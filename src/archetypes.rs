@@ -0,0 +1,73 @@
+//! # Archetypes Module
+//!
+//! This module ships a small library of stock NPC definitions that designers can start from
+//! instead of a blank file: an innkeeper, a guard captain, a street urchin, a merchant, and a
+//! blacksmith. Each comes with a persona card, a personality sketch, a set of actions, goals, a
+//! daily schedule, and a little starting knowledge.
+//!
+//! The definitions are JSON files in the crate's `archetypes` directory, embedded in the binary
+//! at compile time. A loaded archetype is an ordinary `NpcDefinition`, so it can be renamed and
+//! tweaked before it is built, or written out with serde as the start of a new file.
+
+use crate::definition::NpcDefinition;
+
+/// The stock archetypes and their definitions, as JSON.
+const LIBRARY: [(&str, &str); 5] = [
+    ("blacksmith", include_str!("../archetypes/blacksmith.json")),
+    ("guard_captain", include_str!("../archetypes/guard_captain.json")),
+    ("innkeeper", include_str!("../archetypes/innkeeper.json")),
+    ("merchant", include_str!("../archetypes/merchant.json")),
+    ("street_urchin", include_str!("../archetypes/street_urchin.json")),
+];
+
+/// Gets the names of the stock archetypes, in alphabetical order.
+pub fn names() -> Vec<&'static str> {
+    LIBRARY.iter().map(|(name, _)| *name).collect()
+}
+
+/// Loads a stock archetype by name.
+///
+/// # Arguments
+///
+/// * `name` - The name of the archetype (e.g. "innkeeper"). Spaces and hyphens are read as
+///   underscores, and case is ignored.
+///
+/// # Returns
+///
+/// * `Result<NpcDefinition, Box<dyn std::error::Error>>` - The archetype's definition, or an
+///   error listing the available archetypes.
+///
+/// # Examples
+///
+/// ```
+/// use athena::archetypes;
+///
+/// let mut definition = archetypes::load("Guard Captain").unwrap();
+/// definition.id = "captain_hale".to_string();
+/// definition.persona.name = "Captain Hale".to_string();
+/// let agent = definition.build();
+/// assert_eq!(agent.archetype.as_deref(), Some("guard_captain"));
+/// assert!(agent.personality.conscientiousness > 0.5);
+///
+/// // Every stock archetype is clean and builds.
+/// for name in archetypes::names() {
+///     let definition = archetypes::load(name).unwrap();
+///     let report = definition.validate();
+///     assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+///     assert!(!definition.build().schedule.get_blocks().is_empty());
+/// }
+///
+/// assert!(archetypes::load("dragon").is_err());
+/// ```
+pub fn load(name: &str) -> Result<NpcDefinition, Box<dyn std::error::Error>> {
+    let name = name.trim().to_lowercase().replace([' ', '-'], "_");
+    match LIBRARY.iter().find(|(stock, _)| *stock == name) {
+        Some((_, json)) => NpcDefinition::from_json(json),
+        None => Err(format!(
+            "there is no \"{}\" archetype (available: {})",
+            name,
+            names().join(", ")
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,118 @@
+//! # Arena Module
+//!
+//! This module provides a generational arena: values are stored contiguously in a `Vec` and
+//! addressed by an `Index` made of a slot number and a generation. Removing a value frees its
+//! slot for reuse and bumps the generation, so stale indices are detected instead of silently
+//! pointing at a new value.
+
+/// Represents a generational index into an `Arena`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Index {
+    slot: u32,
+    generation: u32,
+}
+
+/// Represents one slot of an arena.
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    /// The generation of the value currently (or last) stored in the slot.
+    generation: u32,
+    /// The stored value, or `None` if the slot is free.
+    value: Option<T>,
+}
+
+/// Represents a generational arena of values of type `T`.
+#[derive(Debug, Clone)]
+pub struct Arena<T> {
+    /// The slots, occupied or free.
+    slots: Vec<Slot<T>>,
+    /// The numbers of the free slots, reused last-in first-out.
+    free: Vec<u32>,
+    /// The number of occupied slots.
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Arena<T> {
+    /// Creates a new, empty arena.
+    pub fn new() -> Self {
+        Arena {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Inserts a value, returning its index.
+    pub fn insert(&mut self, value: T) -> Index {
+        self.len += 1;
+        if let Some(slot) = self.free.pop() {
+            let entry = &mut self.slots[slot as usize];
+            entry.value = Some(value);
+            return Index {
+                slot,
+                generation: entry.generation,
+            };
+        }
+        let slot = self.slots.len() as u32;
+        self.slots.push(Slot {
+            generation: 0,
+            value: Some(value),
+        });
+        Index { slot, generation: 0 }
+    }
+
+    /// Removes the value at an index, returning it if the index was still valid.
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        let entry = self.slots.get_mut(index.slot as usize)?;
+        if entry.generation != index.generation {
+            return None;
+        }
+        let value = entry.value.take()?;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free.push(index.slot);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Gets the value at an index, if the index is still valid.
+    pub fn get(&self, index: Index) -> Option<&T> {
+        self.slots
+            .get(index.slot as usize)
+            .filter(|entry| entry.generation == index.generation)
+            .and_then(|entry| entry.value.as_ref())
+    }
+
+    /// Gets a mutable reference to the value at an index, if the index is still valid.
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut T> {
+        self.slots
+            .get_mut(index.slot as usize)
+            .filter(|entry| entry.generation == index.generation)
+            .and_then(|entry| entry.value.as_mut())
+    }
+
+    /// Returns the number of values in the arena.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Iterates over all values and their indices, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (Index, &T)> {
+        self.slots.iter().enumerate().filter_map(|(slot, entry)| {
+            entry.value.as_ref().map(|value| {
+                (
+                    Index {
+                        slot: slot as u32,
+                        generation: entry.generation,
+                    },
+                    value,
+                )
+            })
+        })
+    }
+}
@@ -0,0 +1,465 @@
+//! # Audit Module
+//!
+//! This module keeps a structured log of dialogue requests for narrative QA. Each record holds
+//! what went into a request and what came out of it: the prompt, the raw response, the reply
+//! that was shown, the fallback line used instead (if any), and the validation decisions made
+//! along the way. Logs are written as JSON lines, so they can be kept per build, replayed through
+//! a newer build, and diffed to catch regressions in tone or lore.
+//!
+//! Logging every request of a live game is rarely wanted, so the log samples requests (always
+//! keeping rejected ones and fallbacks, unless told otherwise) and redacts configured terms such
+//! as player names before anything is stored. Sampling is derived from the speaker and input, so
+//! the same requests are sampled in every build. Each redacted term gets its own numbered
+//! placeholder, so a replay can put the terms back (see `AuditConfig::restore`).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_generation::RequestType;
+use crate::experiment::stable_fraction;
+
+/// Represents a validation decision made about a generated line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationDecision {
+    /// What made the decision (e.g. "lore", "profanity").
+    pub check: String,
+    pub passed: bool,
+    /// Why, if the check gave a reason.
+    pub detail: String,
+}
+
+/// Represents one audited dialogue request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// The position of the record in its log.
+    pub sequence: u64,
+    /// Who the line was generated for (e.g. the NPC's ID).
+    pub speaker: String,
+    pub request_type: RequestType,
+    /// The player's line or other input that triggered the request.
+    pub input: String,
+    pub prompt: String,
+    /// The raw response of the model, if it answered.
+    pub response: Option<String>,
+    /// The line that was finally shown, after post-processing.
+    pub reply: Option<String>,
+    /// The fallback line used instead of a generated one, if any.
+    pub fallback: Option<String>,
+    pub validation: Vec<ValidationDecision>,
+    /// The values the request carried (e.g. the NPC's and player's IDs), so it can be replayed
+    /// as it ran.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl AuditRecord {
+    /// Creates a new record for a request, with nothing generated yet.
+    pub fn new(speaker: &str, request_type: RequestType, input: &str) -> Self {
+        AuditRecord {
+            sequence: 0,
+            speaker: speaker.to_string(),
+            request_type,
+            input: input.to_string(),
+            prompt: String::new(),
+            response: None,
+            reply: None,
+            fallback: None,
+            validation: Vec::new(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the prompt.
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = prompt.to_string();
+        self
+    }
+
+    /// Sets the raw response of the model.
+    pub fn with_response(mut self, response: &str) -> Self {
+        self.response = Some(response.to_string());
+        self
+    }
+
+    /// Sets the line that was finally shown.
+    pub fn with_reply(mut self, reply: &str) -> Self {
+        self.reply = Some(reply.to_string());
+        self
+    }
+
+    /// Sets the fallback line used instead of a generated one.
+    pub fn with_fallback(mut self, fallback: &str) -> Self {
+        self.fallback = Some(fallback.to_string());
+        self
+    }
+
+    /// Adds a validation decision.
+    pub fn with_validation(mut self, check: &str, passed: bool, detail: &str) -> Self {
+        self.validation.push(ValidationDecision {
+            check: check.to_string(),
+            passed,
+            detail: detail.to_string(),
+        });
+        self
+    }
+
+    /// Sets the values the request carried.
+    pub fn with_metadata(mut self, metadata: &HashMap<String, String>) -> Self {
+        self.metadata = metadata.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        self
+    }
+
+    /// Returns true if a validation check rejected the line.
+    pub fn is_rejected(&self) -> bool {
+        self.validation.iter().any(|decision| !decision.passed)
+    }
+
+    /// Returns true if the record should be kept whatever the sampling says.
+    fn is_notable(&self) -> bool {
+        self.is_rejected() || self.fallback.is_some()
+    }
+
+    /// Replaces the redacted terms in every text field.
+    fn redact(&mut self, config: &AuditConfig) {
+        let redact = |text: &mut String| *text = config.redact(text);
+        redact(&mut self.speaker);
+        redact(&mut self.input);
+        redact(&mut self.prompt);
+        let optional = [&mut self.response, &mut self.reply, &mut self.fallback];
+        for text in optional.into_iter().flatten() {
+            redact(text);
+        }
+        for decision in &mut self.validation {
+            redact(&mut decision.detail);
+        }
+        for value in self.metadata.values_mut() {
+            redact(value);
+        }
+    }
+}
+
+/// Represents the sampling and redaction settings of an audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// The fraction of requests to log (between 0.0 and 1.0).
+    pub sample_rate: f64,
+    /// Whether rejected lines and fallbacks are logged even when not sampled.
+    pub keep_notable: bool,
+    /// Terms replaced before anything is stored (e.g. the player's name).
+    pub redacted_terms: Vec<String>,
+    /// What a redacted term is replaced with. A `{}` in it is replaced with the term's number
+    /// (its position in `redacted_terms`, starting at 1), which lets `restore` undo redaction.
+    pub replacement: String,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        AuditConfig {
+            sample_rate: 1.0,
+            keep_notable: true,
+            redacted_terms: Vec::new(),
+            replacement: "[redacted {}]".to_string(),
+        }
+    }
+}
+
+impl AuditConfig {
+    /// Returns true if a request is sampled. The decision depends only on the speaker and
+    /// input, so it is the same in every build.
+    pub fn samples(&self, speaker: &str, input: &str) -> bool {
+        self.sample_rate >= 1.0 || stable_fraction(&[speaker, input]) < self.sample_rate
+    }
+
+    /// Replaces the redacted terms in a text with their placeholders.
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        for (index, term) in self.redacted_terms.iter().enumerate() {
+            if !term.is_empty() {
+                redacted = redacted.replace(term.as_str(), &self.placeholder(index));
+            }
+        }
+        redacted
+    }
+
+    /// Puts the redacted terms back into a redacted text, e.g. to replay a logged request as
+    /// it ran. Only numbered placeholders can be told apart; if the replacement has no `{}`,
+    /// every placeholder is restored as the first term.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::audit::AuditConfig;
+    ///
+    /// let config = AuditConfig {
+    ///     redacted_terms: vec!["Alice".to_string(), "Bob".to_string()],
+    ///     ..AuditConfig::default()
+    /// };
+    /// let redacted = config.redact("Alice owes Bob.");
+    /// assert_eq!(redacted, "[redacted 1] owes [redacted 2].");
+    /// assert_eq!(config.restore(&redacted), "Alice owes Bob.");
+    /// ```
+    pub fn restore(&self, text: &str) -> String {
+        let mut restored = text.to_string();
+        for (index, term) in self.redacted_terms.iter().enumerate() {
+            if !term.is_empty() {
+                restored = restored.replace(&self.placeholder(index), term);
+            }
+        }
+        restored
+    }
+
+    /// Gets the placeholder of the redacted term at an index.
+    fn placeholder(&self, index: usize) -> String {
+        self.replacement.replace("{}", &(index + 1).to_string())
+    }
+}
+
+/// Represents which part of a record differs between two logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AuditField {
+    /// The request is in only one of the logs.
+    Presence,
+    Prompt,
+    Response,
+    Reply,
+    Fallback,
+    Validation,
+}
+
+/// Represents a difference between a baseline record and its replay.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditDiff {
+    pub speaker: String,
+    pub input: String,
+    pub field: AuditField,
+    pub baseline: Option<String>,
+    pub candidate: Option<String>,
+}
+
+/// Identifies a record across logs: its speaker, input, and occurrence of that pair.
+type RecordKey = (String, String, usize);
+
+/// Represents a log of audited dialogue requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditLog {
+    pub config: AuditConfig,
+    records: Vec<AuditRecord>,
+    next_sequence: u64,
+}
+
+impl AuditLog {
+    /// Creates a new, empty log with the given settings.
+    pub fn new(config: AuditConfig) -> Self {
+        AuditLog {
+            config,
+            records: Vec::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Records a request, unless it is not sampled. The record is numbered and redacted first.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the record was kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::audit::{AuditConfig, AuditLog, AuditRecord};
+    /// use athena::dialogue_generation::RequestType;
+    ///
+    /// let config = AuditConfig {
+    ///     redacted_terms: vec!["Alice".to_string()],
+    ///     ..AuditConfig::default()
+    /// };
+    /// let mut log = AuditLog::new(config);
+    /// let record = AuditRecord::new("brom", RequestType::Conversation, "I'm Alice.")
+    ///     .with_prompt("Player: I'm Alice.")
+    ///     .with_response("Welcome, Alice!")
+    ///     .with_validation("lore", true, "");
+    /// assert!(log.record(record));
+    /// assert_eq!(log.records()[0].input, "I'm [redacted 1].");
+    /// assert_eq!(log.records()[0].response.as_deref(), Some("Welcome, [redacted 1]!"));
+    /// ```
+    pub fn record(&mut self, mut record: AuditRecord) -> bool {
+        let keep = self.config.samples(&record.speaker, &record.input)
+            || (self.config.keep_notable && record.is_notable());
+        if !keep {
+            return false;
+        }
+        record.sequence = self.next_sequence;
+        self.next_sequence += 1;
+        record.redact(&self.config);
+        self.records.push(record);
+        true
+    }
+
+    /// Gets the records, in the order they were logged.
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+
+    /// Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns true if the log has no records.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Writes the records as JSON lines, one record per line.
+    pub fn to_jsonl(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut jsonl = String::new();
+        for record in &self.records {
+            jsonl.push_str(&serde_json::to_string(record)?);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+
+    /// Reads records written by `to_jsonl` into a log with default settings. Blank lines are
+    /// skipped.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut log = AuditLog::default();
+        for line in jsonl.lines().filter(|line| !line.trim().is_empty()) {
+            let record: AuditRecord = serde_json::from_str(line)?;
+            log.next_sequence = log.next_sequence.max(record.sequence + 1);
+            log.records.push(record);
+        }
+        Ok(log)
+    }
+
+    /// Saves the records to a JSON lines file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.to_jsonl()?)?;
+        Ok(())
+    }
+
+    /// Loads the records from a JSON lines file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_jsonl(&fs::read_to_string(path)?)
+    }
+
+    /// Replays the logged requests, e.g. through a newer build, into a new log with the same
+    /// redaction and no sampling.
+    ///
+    /// # Arguments
+    ///
+    /// * `regenerate` - Runs a logged request again and returns the new record. Records are
+    ///   passed as logged, so redacted terms stay redacted unless `config.restore` puts them
+    ///   back.
+    pub fn replay(&self, mut regenerate: impl FnMut(&AuditRecord) -> AuditRecord) -> AuditLog {
+        let mut replayed = AuditLog::new(AuditConfig {
+            sample_rate: 1.0,
+            ..self.config.clone()
+        });
+        for record in &self.records {
+            replayed.record(regenerate(record));
+        }
+        replayed
+    }
+
+    /// Lists the differences between this log and a replay of it. Records are matched by
+    /// speaker and input (the n-th occurrence of a request with the n-th in the other log), so
+    /// differences in unrelated requests do not shift the comparison.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::audit::{AuditField, AuditLog, AuditRecord};
+    /// use athena::dialogue_generation::RequestType;
+    ///
+    /// let mut baseline = AuditLog::default();
+    /// baseline.record(
+    ///     AuditRecord::new("brom", RequestType::Conversation, "Hello!").with_reply("Well met."),
+    /// );
+    /// let candidate = baseline.replay(|record| {
+    ///     AuditRecord::new(&record.speaker, record.request_type, &record.input)
+    ///         .with_reply("What do you want?")
+    /// });
+    /// let diffs = baseline.diff(&candidate);
+    /// assert_eq!(diffs.len(), 1);
+    /// assert_eq!(diffs[0].field, AuditField::Reply);
+    /// assert_eq!(diffs[0].candidate.as_deref(), Some("What do you want?"));
+    /// ```
+    pub fn diff(&self, candidate: &AuditLog) -> Vec<AuditDiff> {
+        let candidates: HashMap<_, _> = keyed(candidate).into_iter().collect();
+        let mut matched = HashSet::new();
+        let mut diffs = Vec::new();
+        for (key, baseline) in keyed(self) {
+            let Some(replayed) = candidates.get(&key) else {
+                diffs.push(presence(baseline, true));
+                continue;
+            };
+            matched.insert(key);
+            let validation = |record: &AuditRecord| {
+                let decisions: Vec<String> = record
+                    .validation
+                    .iter()
+                    .map(|decision| {
+                        let verdict = if decision.passed { "passed" } else { "failed" };
+                        format!("{} {}: {}", decision.check, verdict, decision.detail)
+                    })
+                    .collect();
+                Some(decisions.join("; "))
+            };
+            let fields = [
+                (AuditField::Prompt, Some(baseline.prompt.clone()), Some(replayed.prompt.clone())),
+                (AuditField::Response, baseline.response.clone(), replayed.response.clone()),
+                (AuditField::Reply, baseline.reply.clone(), replayed.reply.clone()),
+                (AuditField::Fallback, baseline.fallback.clone(), replayed.fallback.clone()),
+                (AuditField::Validation, validation(baseline), validation(replayed)),
+            ];
+            for (field, before, after) in fields {
+                if before != after {
+                    diffs.push(AuditDiff {
+                        speaker: baseline.speaker.clone(),
+                        input: baseline.input.clone(),
+                        field,
+                        baseline: before,
+                        candidate: after,
+                    });
+                }
+            }
+        }
+        for (key, record) in keyed(candidate) {
+            if !matched.contains(&key) {
+                diffs.push(presence(record, false));
+            }
+        }
+        diffs
+    }
+}
+
+/// Keys the records of a log by speaker, input, and occurrence of that pair.
+fn keyed(log: &AuditLog) -> Vec<(RecordKey, &AuditRecord)> {
+    let mut occurrences: HashMap<(String, String), usize> = HashMap::new();
+    log.records
+        .iter()
+        .map(|record| {
+            let key = (record.speaker.clone(), record.input.clone());
+            let occurrence = occurrences.entry(key.clone()).or_insert(0);
+            *occurrence += 1;
+            ((key.0, key.1, *occurrence), record)
+        })
+        .collect()
+}
+
+/// Describes a request found in only one of two logs.
+fn presence(record: &AuditRecord, in_baseline: bool) -> AuditDiff {
+    let present = Some(format!("{:?}", record.request_type));
+    AuditDiff {
+        speaker: record.speaker.clone(),
+        input: record.input.clone(),
+        field: AuditField::Presence,
+        baseline: if in_baseline { present.clone() } else { None },
+        candidate: if in_baseline { None } else { present },
+    }
+}
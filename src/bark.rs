@@ -0,0 +1,319 @@
+//! # Bark Module
+//!
+//! This module generates barks: short ambient one-liners NPCs say to nobody in particular while
+//! going about their day. Barks are cheap by design. Prompts are a single sentence, results are
+//! cached per situation and reused in rotation, lines that are too long or contain banned
+//! phrases are rejected, and caches can be filled in bulk ahead of time (e.g. during a loading
+//! screen) so that no request has to be made during gameplay.
+
+#[cfg(feature = "network")]
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "network")]
+use crate::dialogue_generation::{DialogueClient, RequestType};
+
+/// Represents the situation an NPC barks in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BarkContext {
+    /// Who is barking (e.g. "a tired guard").
+    pub speaker: String,
+    /// What the NPC is doing (e.g. "patrolling").
+    pub activity: String,
+    /// Where the NPC is (e.g. "the city gate").
+    pub location: String,
+    /// The NPC's mood (e.g. "bored").
+    pub mood: String,
+    /// Something that just happened, if anything (e.g. "it started raining").
+    pub event: Option<String>,
+}
+
+impl BarkContext {
+    /// Creates a new bark context for a speaker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::bark::BarkContext;
+    /// let mut context = BarkContext::new("a tired guard");
+    /// context.activity = "patrolling".to_string();
+    /// ```
+    pub fn new(speaker: &str) -> Self {
+        BarkContext {
+            speaker: speaker.to_string(),
+            ..BarkContext::default()
+        }
+    }
+
+    /// Renders the tiny prompt sent to the language model for this context.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_words` - The maximum number of words the line may have.
+    pub fn prompt(&self, max_words: usize) -> String {
+        let mut text = format!("You are {}", self.speaker);
+        if !self.activity.is_empty() {
+            text.push_str(&format!(", {}", self.activity));
+        }
+        if !self.location.is_empty() {
+            text.push_str(&format!(" at {}", self.location));
+        }
+        if !self.mood.is_empty() {
+            text.push_str(&format!(", feeling {}", self.mood));
+        }
+        text.push('.');
+        if let Some(event) = &self.event {
+            text.push_str(&format!(" {}.", event.trim_end_matches('.')));
+        }
+        text.push_str(&format!(
+            " Say one line to nobody in particular, at most {} words. Reply with the line only.",
+            max_words
+        ));
+        text
+    }
+}
+
+/// Represents the limits generated barks must respect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BarkConfig {
+    /// The maximum number of characters in a bark.
+    pub max_chars: usize,
+    /// The maximum number of words in a bark.
+    pub max_words: usize,
+    /// Phrases that must never appear in a bark (matched case-insensitively).
+    pub banned_phrases: Vec<String>,
+    /// How many different lines are kept per context.
+    pub variants_per_context: usize,
+}
+
+impl Default for BarkConfig {
+    fn default() -> Self {
+        BarkConfig {
+            max_chars: 80,
+            max_words: 12,
+            banned_phrases: ["as an ai", "language model", "i cannot", "i'm sorry"]
+                .iter()
+                .map(|phrase| phrase.to_string())
+                .collect(),
+            variants_per_context: 3,
+        }
+    }
+}
+
+impl BarkConfig {
+    /// Cleans up a raw model reply and checks it against the limits.
+    ///
+    /// Only the first line is kept and surrounding quotes are stripped. A reply that is too
+    /// long is cut back to its last complete sentence within the limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `raw` - The text returned by the language model.
+    ///
+    /// # Returns
+    ///
+    /// The usable bark, or `None` if the reply is empty, too long, or contains a banned phrase.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::bark::BarkConfig;
+    /// let config = BarkConfig::default();
+    /// assert_eq!(config.sanitize("\"Quiet night.\"\n(The guard yawns)"), Some("Quiet night.".to_string()));
+    /// assert_eq!(config.sanitize("As an AI, I have no opinion."), None);
+    /// let rambling = "Rain again. I swear this town has not seen the sun since the old king died.";
+    /// assert_eq!(config.sanitize(rambling), Some("Rain again.".to_string()));
+    /// ```
+    pub fn sanitize(&self, raw: &str) -> Option<String> {
+        let line = raw
+            .lines()
+            .map(|line| line.trim().trim_matches(|c| c == '"' || c == '\'').trim())
+            .find(|line| !line.is_empty())?;
+        let line = if self.fits(line) {
+            line
+        } else {
+            // Cut back to the last sentence that still fits.
+            line.rmatch_indices(['.', '!', '?'])
+                .map(|(index, mark)| &line[..index + mark.len()])
+                .find(|sentence| self.fits(sentence))?
+        };
+        let lowercase = line.to_lowercase();
+        if self
+            .banned_phrases
+            .iter()
+            .any(|phrase| lowercase.contains(&phrase.to_lowercase()))
+        {
+            return None;
+        }
+        Some(line.to_string())
+    }
+
+    /// Returns true if a line is within the length limits.
+    fn fits(&self, line: &str) -> bool {
+        line.chars().count() <= self.max_chars && line.split_whitespace().count() <= self.max_words
+    }
+}
+
+/// Generates barks and caches them per context.
+#[cfg(feature = "network")]
+pub struct BarkService {
+    client: DialogueClient,
+    config: BarkConfig,
+    /// Cached lines per context.
+    cache: HashMap<BarkContext, Vec<String>>,
+    /// The index of the next cached line to serve per context.
+    cursors: HashMap<BarkContext, usize>,
+}
+
+#[cfg(feature = "network")]
+impl BarkService {
+    /// Creates a new bark service using the given client and the default limits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::bark::BarkService;
+    /// use athena::dialogue_generation::DialogueClient;
+    /// let service = BarkService::new(DialogueClient::new("my-api-key"));
+    /// ```
+    pub fn new(client: DialogueClient) -> Self {
+        BarkService {
+            client,
+            config: BarkConfig::default(),
+            cache: HashMap::new(),
+            cursors: HashMap::new(),
+        }
+    }
+
+    /// Replaces the limits.
+    pub fn set_config(&mut self, config: BarkConfig) {
+        self.config = config;
+    }
+
+    /// Gets the limits.
+    pub fn get_config(&self) -> &BarkConfig {
+        &self.config
+    }
+
+    /// Adds a line to the cache for a context, e.g. an authored fallback bark.
+    ///
+    /// The line is sanitized first; lines that fail the limits are dropped.
+    ///
+    /// # Returns
+    ///
+    /// True if the line was added.
+    pub fn insert(&mut self, context: &BarkContext, line: &str) -> bool {
+        let Some(line) = self.config.sanitize(line) else {
+            return false;
+        };
+        let lines = self.cache.entry(context.clone()).or_default();
+        if lines.contains(&line) {
+            return false;
+        }
+        lines.push(line);
+        true
+    }
+
+    /// Returns how many lines are cached for a context.
+    pub fn cached_count(&self, context: &BarkContext) -> usize {
+        self.cache.get(context).map_or(0, |lines| lines.len())
+    }
+
+    /// Returns the next cached line for a context, rotating through the cached variants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::bark::{BarkContext, BarkService};
+    /// use athena::dialogue_generation::DialogueClient;
+    ///
+    /// let mut service = BarkService::new(DialogueClient::new("my-api-key"));
+    /// let context = BarkContext::new("a tired guard");
+    /// service.insert(&context, "Quiet night.");
+    /// service.insert(&context, "My feet hurt.");
+    /// assert_eq!(service.cached(&context).as_deref(), Some("Quiet night."));
+    /// assert_eq!(service.cached(&context).as_deref(), Some("My feet hurt."));
+    /// assert_eq!(service.cached(&context).as_deref(), Some("Quiet night."));
+    /// ```
+    pub fn cached(&mut self, context: &BarkContext) -> Option<String> {
+        let lines = self.cache.get(context).filter(|lines| !lines.is_empty())?;
+        let cursor = self.cursors.entry(context.clone()).or_insert(0);
+        let line = lines[*cursor % lines.len()].clone();
+        *cursor += 1;
+        Some(line)
+    }
+
+    /// Generates a bark for a context.
+    ///
+    /// A cached line is served whenever one exists, so the language model is only called the
+    /// first time a context is seen (or not at all if it was pre-generated).
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The situation the NPC barks in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, Box<dyn std::error::Error>>` - The bark, or an error if the request
+    ///   failed or the reply could not be used.
+    pub async fn generate_bark(
+        &mut self,
+        context: &BarkContext,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        if let Some(line) = self.cached(context) {
+            return Ok(line);
+        }
+        let line = self.request(context).await?;
+        self.insert(context, &line);
+        self.cached(context).ok_or_else(|| "Bark was rejected".into())
+    }
+
+    /// Fills the cache for each context up to `variants_per_context` lines.
+    ///
+    /// Meant to be called during loading screens. Failed requests and rejected replies are
+    /// skipped rather than retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `contexts` - The situations to pre-generate barks for.
+    ///
+    /// # Returns
+    ///
+    /// The number of lines added to the cache.
+    pub async fn pregenerate(&mut self, contexts: &[BarkContext]) -> usize {
+        let mut added = 0;
+        for context in contexts {
+            let missing = self
+                .config
+                .variants_per_context
+                .saturating_sub(self.cached_count(context));
+            for _ in 0..missing {
+                if let Ok(line) = self.request(context).await {
+                    if self.insert(context, &line) {
+                        added += 1;
+                    }
+                }
+            }
+        }
+        added
+    }
+
+    /// Requests a new line from the language model and sanitizes it.
+    async fn request(&self, context: &BarkContext) -> Result<String, Box<dyn std::error::Error>> {
+        let prompt = context.prompt(self.config.max_words);
+        let response = self
+            .client
+            .send(RequestType::Bark, &prompt)
+            .await
+            .map_err(|error| error as Box<dyn std::error::Error>)?;
+        let raw = response
+            .choices
+            .first()
+            .map(|choice| choice.message.content.as_str())
+            .unwrap_or_default();
+        self.config
+            .sanitize(raw)
+            .ok_or_else(|| format!("Bark rejected: {}", raw).into())
+    }
+}
@@ -0,0 +1,262 @@
+//! # Athena CLI
+//!
+//! Loads an NPC definition and talks to it, for prototyping and testing NPCs without a game.
+//! Every line typed is said to the NPC by the player; the NPC's reaction and the changes to its
+//! emotions, relationship, and memories are printed after each turn. Lines starting with `:` are
+//! commands:
+//!
+//! - `:state` prints the NPC's debug report.
+//! - `:quit` exits.
+//! - Every scenario command (`:tick`, `:deed`, `:expect`, ...; see `athena::scenario`).
+//!
+//! With `--script <file>` the lines of a scenario file are run instead of reading from the
+//! terminal, and the process exits with a failure if any expectation failed. Replies come from
+//! the scenario mock provider unless `--generate` is given, in which case they are generated by
+//! the language model using `GROQ_API_KEY`.
+//!
+//! Usage: `athena <npc.json> [--script <file>] [--generate]`
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use athena::definition::NpcDefinition;
+use athena::dialogue_generation::{DialogueClient, GenerationParams, RequestType};
+use athena::emotional_response::Emotion;
+use athena::prompt::PromptBuilder;
+use athena::scenario::{MockProvider, ReplyProvider, ScenarioRunner, Step, PLAYER_ID};
+
+/// Generates replies with the language model.
+struct ModelProvider {
+    client: DialogueClient,
+    runtime: tokio::runtime::Runtime,
+    /// The NPC's generation overrides for conversations.
+    params: GenerationParams,
+}
+
+impl ReplyProvider for ModelProvider {
+    fn reply(&mut self, prompt: &str) -> Option<String> {
+        let request = self.client.send_with_params(RequestType::Conversation, prompt, &self.params);
+        match self.runtime.block_on(request) {
+            Ok(response) => response.choices.into_iter().next().map(|c| c.message.content),
+            Err(error) => {
+                eprintln!("  generation failed: {}", error);
+                None
+            }
+        }
+    }
+}
+
+/// Represents the session with the loaded NPC.
+struct Session {
+    runner: ScenarioRunner,
+    failures: usize,
+}
+
+/// Represents what the NPC looked like before a step, to print what changed.
+struct Snapshot {
+    emotions: Vec<(Emotion, f64)>,
+    affinity: f64,
+    trust: f64,
+    memories: usize,
+}
+
+impl Session {
+    fn snapshot(&self) -> Snapshot {
+        let agent = &self.runner.agent;
+        let context = agent.get_interlocutor(PLAYER_ID);
+        Snapshot {
+            emotions: agent.emotions.get_intensities(),
+            affinity: context.map_or(0.0, |c| c.affinity),
+            trust: context.map_or(0.0, |c| c.trust),
+            memories: agent.intelligence.memories().count()
+                + context.map_or(0, |c| c.memories().count()),
+        }
+    }
+
+    /// Prints the NPC's reaction to the line the player just said.
+    fn print_reaction(&self) {
+        let id = &self.runner.agent.id;
+        let output = self.runner.get_last_output();
+        if !output.engaged {
+            println!("  ({} ignores you)", id);
+        }
+        if let Some(action) = &output.action {
+            println!("  action: {}", action);
+        }
+        for violation in &output.violations {
+            println!("  norm violated: {} ({})", violation.norm_id, violation.description);
+        }
+        if let Some(reply) = self.runner.get_last_reply() {
+            println!("{}: {}", id, reply);
+        }
+        if let Some(end) = &output.conversation_end {
+            println!("  conversation ended: {:?} (cooldown {}s)", end.reason, end.cooldown);
+        }
+    }
+
+    fn print_changes(&self, before: &Snapshot) {
+        let after = self.snapshot();
+        for (emotion, intensity) in &after.emotions {
+            let previous = before
+                .emotions
+                .iter()
+                .find(|(e, _)| e == emotion)
+                .map_or(0.0, |(_, i)| *i);
+            if (intensity - previous).abs() > 1e-9 {
+                println!("  {:?}: {:.2} -> {:.2}", emotion, previous, intensity);
+            }
+        }
+        for (name, previous, current) in [
+            ("affinity", before.affinity, after.affinity),
+            ("trust", before.trust, after.trust),
+        ] {
+            if (current - previous).abs() > 1e-9 {
+                println!("  {}: {:.2} -> {:.2}", name, previous, current);
+            }
+        }
+        if after.memories != before.memories {
+            println!("  memories: {} -> {}", before.memories, after.memories);
+        }
+    }
+
+    /// Runs one input line. Returns false when the session should end.
+    fn run_line(&mut self, line: &str) -> bool {
+        match line.trim() {
+            ":quit" => return false,
+            ":state" => {
+                match serde_json::to_string_pretty(&self.runner.agent.debug_report()) {
+                    Ok(report) => println!("{}", report),
+                    Err(error) => eprintln!("{}", error),
+                }
+                return true;
+            }
+            _ => {}
+        }
+        let step = match Step::parse(line) {
+            Ok(Some(step)) => step,
+            Ok(None) => return true,
+            Err(error) => {
+                eprintln!("{}", error);
+                return true;
+            }
+        };
+        let before = self.snapshot();
+        match (self.runner.apply(&step), &step) {
+            (Err(message), _) => {
+                println!("  FAILED: {} ({})", line.trim(), message);
+                self.failures += 1;
+            }
+            (Ok(()), Step::Expect(_)) => println!("  ok: {}", line.trim()),
+            (Ok(()), Step::Say(_)) => self.print_reaction(),
+            (Ok(()), _) => {}
+        }
+        self.print_changes(&before);
+        true
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut definition_path = None;
+    let mut script_path = None;
+    let mut generate = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--script" => script_path = iter.next(),
+            "--generate" => generate = true,
+            _ => definition_path = Some(arg),
+        }
+    }
+    let Some(definition_path) = definition_path else {
+        eprintln!("usage: athena <npc.json> [--script <file>] [--generate]");
+        return ExitCode::FAILURE;
+    };
+    let definition = match NpcDefinition::load(definition_path) {
+        Ok(definition) => definition,
+        Err(error) => {
+            eprintln!("could not load {}: {}", definition_path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let report = definition.validate();
+    for diagnostic in &report.diagnostics {
+        eprintln!("{}: {}", definition_path, diagnostic.describe());
+    }
+    if !report.is_valid() {
+        return ExitCode::FAILURE;
+    }
+
+    let provider: Box<dyn ReplyProvider> = if generate {
+        let client = match DialogueClient::from_env() {
+            Ok(client) => client,
+            Err(_) => {
+                eprintln!("--generate needs GROQ_API_KEY to be set");
+                return ExitCode::FAILURE;
+            }
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build();
+        match runtime {
+            Ok(runtime) => {
+                let params = definition.persona.generation.params_for(RequestType::Conversation);
+                Box::new(ModelProvider {
+                    client,
+                    runtime,
+                    params,
+                })
+            }
+            Err(error) => {
+                eprintln!("could not start the runtime: {}", error);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        Box::new(MockProvider::new())
+    };
+    let mut prompt = PromptBuilder::new(definition.persona.clone());
+    prompt.set_culture(definition.culture.clone());
+    let mut session = Session {
+        runner: ScenarioRunner::new(definition.build(), prompt, provider),
+        failures: 0,
+    };
+
+    if let Some(script_path) = script_path {
+        let script = match std::fs::read_to_string(script_path) {
+            Ok(script) => script,
+            Err(error) => {
+                eprintln!("could not read {}: {}", script_path, error);
+                return ExitCode::FAILURE;
+            }
+        };
+        for line in script.lines() {
+            if !line.trim().is_empty() && !line.trim_start().starts_with('#') {
+                println!("> {}", line.trim());
+            }
+            if !session.run_line(line) {
+                break;
+            }
+        }
+        if session.failures > 0 {
+            println!("{} expectation(s) failed", session.failures);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    println!("Talking to {}. Type :quit to exit.", session.runner.agent.id);
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if !session.run_line(&line) {
+                    break;
+                }
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,130 @@
+//! # Blocking Module
+//!
+//! This module offers blocking variants of the asynchronous APIs, for game engines and tools that
+//! do not run an async runtime. Each call is driven to completion on a small runtime owned by the
+//! `BlockingClient`, so callers never see a future.
+//!
+//! The module is only built with the `blocking` feature. Blocking calls must not be made from
+//! inside an async runtime; use the asynchronous APIs there instead.
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::agent::NpcAgent;
+use crate::dialogue_generation::{self, ApiResponse, DialogueClient, RequestType};
+use crate::pipeline::{Pipeline, PipelineState, StageError};
+
+/// Sends a message to the API and waits for the response.
+///
+/// This is the blocking variant of `dialogue_generation::send_message`.
+///
+/// # Arguments
+///
+/// * `input` - A string slice that holds the user message.
+///
+/// # Returns
+///
+/// * `Result<ApiResponse, Box<dyn std::error::Error>>` - A result containing the API response or an error.
+pub fn send_message_blocking(input: &str) -> Result<ApiResponse, Box<dyn std::error::Error>> {
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(dialogue_generation::send_message(input))
+}
+
+/// Represents a dialogue client whose calls block until they complete.
+pub struct BlockingClient {
+    runtime: Runtime,
+    client: DialogueClient,
+}
+
+impl BlockingClient {
+    /// Creates a new blocking client around a dialogue client.
+    ///
+    /// # Returns
+    ///
+    /// * `std::io::Result<BlockingClient>` - The client, or an error if its runtime could not be
+    ///   started.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::blocking::BlockingClient;
+    /// use athena::dialogue_generation::DialogueClient;
+    /// let client = BlockingClient::new(DialogueClient::new("my-api-key")).unwrap();
+    /// ```
+    pub fn new(client: DialogueClient) -> std::io::Result<Self> {
+        Ok(BlockingClient {
+            runtime: Builder::new_current_thread().enable_all().build()?,
+            client,
+        })
+    }
+
+    /// Gets the wrapped dialogue client.
+    pub fn get_client(&self) -> &DialogueClient {
+        &self.client
+    }
+
+    /// Sends a message and waits for the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_type` - The kind of request, used to pick the model.
+    /// * `input` - A string slice that holds the user message.
+    pub fn send(
+        &self,
+        request_type: RequestType,
+        input: &str,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.runtime.block_on(self.client.send(request_type, input))
+    }
+
+    /// Runs a request through a pipeline and waits for the final state.
+    ///
+    /// The pipeline generates with its own client; this client only provides the runtime.
+    pub fn run_pipeline(
+        &self,
+        pipeline: &mut Pipeline,
+        request_type: RequestType,
+        input: &str,
+    ) -> Result<PipelineState, StageError> {
+        self.runtime.block_on(pipeline.run(request_type, input))
+    }
+
+    /// Advances an agent by the given amount of game time, writing its reflection with the
+    /// language model when one is due.
+    ///
+    /// Without a due reflection this is the same as `NpcAgent::tick`, which would otherwise write
+    /// the journal entry with the heuristic summarizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - The agent to advance.
+    /// * `dt` - The elapsed game time in seconds.
+    /// * `persona` - A short description of the NPC, used to set the voice of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::blocking::BlockingClient;
+    /// use athena::dialogue_generation::DialogueClient;
+    ///
+    /// let client = BlockingClient::new(DialogueClient::new("my-api-key")).unwrap();
+    /// let mut agent = NpcAgent::new("baker", vec![]);
+    /// agent.needs.add_need("rest", 0.01);
+    /// client.tick(&mut agent, 10.0, "A cheerful baker.").unwrap();
+    /// assert_eq!(agent.needs.get_satisfaction("rest"), Some(0.9));
+    /// ```
+    pub fn tick(
+        &self,
+        agent: &mut NpcAgent,
+        dt: f64,
+        persona: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if agent.reflector.should_reflect() {
+            agent.note_mood();
+            let reflection = agent.reflector.reflect_with_llm(&mut agent.intelligence, persona);
+            self.runtime.block_on(reflection)?;
+        }
+        agent.tick(dt);
+        Ok(())
+    }
+}
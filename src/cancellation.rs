@@ -0,0 +1,217 @@
+//! # Cancellation Module
+//!
+//! This module lets games abort NPC work that is still in flight, e.g. when a scene ends. A
+//! `CancellationToken` is shared between the game and the work it started; cancelling it makes
+//! every future run under it finish at once with an `Interrupted::Cancelled` error, dropping the
+//! work so no request or task outlives the scene. Work can also be given a time limit.
+//!
+//! Tokens form a tree: cancelling a token cancels its children, but not its parent. The agent
+//! manager holds a token for the current scene, and every agent a child of it, so one agent's
+//! work or the whole scene's can be cancelled. Futures are polled as is, so tokens work with any
+//! async runtime.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+#[cfg(feature = "network")]
+use std::time::Duration;
+
+/// The error of work that was stopped before it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Interrupted {
+    /// Its token was cancelled.
+    Cancelled,
+    /// It ran out of time.
+    TimedOut,
+}
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interrupted::Cancelled => write!(f, "The operation was cancelled"),
+            Interrupted::TimedOut => write!(f, "The operation timed out"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// The shared state of a token.
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    /// The tasks waiting on the token, by the ID of the future they are running.
+    wakers: Mutex<HashMap<u64, Waker>>,
+    next_id: AtomicU64,
+    /// The child tokens, dropped from the list once they are gone.
+    children: Mutex<Vec<Weak<TokenState>>>,
+}
+
+impl TokenState {
+    /// Cancels the token and its children, and wakes the tasks waiting on them.
+    fn cancel(&self) {
+        if self.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        for waker in std::mem::take(&mut *self.wakers.lock().unwrap()).into_values() {
+            waker.wake();
+        }
+        for child in std::mem::take(&mut *self.children.lock().unwrap()) {
+            if let Some(child) = child.upgrade() {
+                child.cancel();
+            }
+        }
+    }
+}
+
+/// A handle to cancel in-flight work. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    state: Arc<TokenState>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that is not cancelled.
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Creates a token that is cancelled along with this one, but can also be cancelled alone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::cancellation::CancellationToken;
+    ///
+    /// let scene = CancellationToken::new();
+    /// let npc = scene.child_token();
+    /// npc.cancel();
+    /// assert!(!scene.is_cancelled());
+    ///
+    /// let other = scene.child_token();
+    /// scene.cancel();
+    /// assert!(other.is_cancelled());
+    /// ```
+    pub fn child_token(&self) -> CancellationToken {
+        let child = CancellationToken::new();
+        let mut children = self.state.children.lock().unwrap();
+        if self.is_cancelled() {
+            child.cancel();
+        } else {
+            children.retain(|existing| existing.strong_count() > 0);
+            children.push(Arc::downgrade(&child.state));
+        }
+        child
+    }
+
+    /// Cancels the token and its children.
+    pub fn cancel(&self) {
+        self.state.cancel();
+    }
+
+    /// Returns true if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns `Err(Interrupted::Cancelled)` if the token has been cancelled, for checks
+    /// between steps of synchronous work.
+    pub fn check(&self) -> Result<(), Interrupted> {
+        if self.is_cancelled() {
+            Err(Interrupted::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs a future until it finishes or the token is cancelled, whichever comes first. On
+    /// cancellation the future is dropped, aborting the work it was doing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::cancellation::{CancellationToken, Interrupted};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = CancellationToken::new();
+    ///     assert_eq!(token.run(async { 42 }).await, Ok(42));
+    ///
+    ///     token.cancel();
+    ///     let pending = std::future::pending::<()>();
+    ///     assert_eq!(token.run(pending).await, Err(Interrupted::Cancelled));
+    /// }
+    /// ```
+    pub fn run<F: Future>(&self, future: F) -> Cancellable<F> {
+        Cancellable {
+            token: self.clone(),
+            id: self.state.next_id.fetch_add(1, Ordering::Relaxed),
+            future: Box::pin(future),
+        }
+    }
+
+    /// Runs a future until it finishes, the token is cancelled, or the timeout passes,
+    /// whichever comes first. Needs a tokio runtime for the timer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use athena::cancellation::{CancellationToken, Interrupted};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let token = CancellationToken::new();
+    ///     let slow = tokio::time::sleep(Duration::from_secs(60));
+    ///     let result = token.run_with_timeout(Duration::from_millis(10), slow).await;
+    ///     assert_eq!(result, Err(Interrupted::TimedOut));
+    /// }
+    /// ```
+    #[cfg(feature = "network")]
+    pub async fn run_with_timeout<F: Future>(
+        &self,
+        timeout: Duration,
+        future: F,
+    ) -> Result<F::Output, Interrupted> {
+        tokio::time::timeout(timeout, self.run(future))
+            .await
+            .unwrap_or(Err(Interrupted::TimedOut))
+    }
+}
+
+/// A future that finishes with `Err(Interrupted::Cancelled)` as soon as its token is cancelled.
+pub struct Cancellable<F: Future> {
+    token: CancellationToken,
+    /// The ID its waker is registered under.
+    id: u64,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Cancellable<F> {
+    type Output = Result<F::Output, Interrupted>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(Err(Interrupted::Cancelled));
+        }
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Ok(output));
+        }
+        let mut wakers = self.token.state.wakers.lock().unwrap();
+        // The token may have been cancelled while the future was polled.
+        if self.token.is_cancelled() {
+            return Poll::Ready(Err(Interrupted::Cancelled));
+        }
+        wakers.insert(self.id, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<F: Future> Drop for Cancellable<F> {
+    fn drop(&mut self) {
+        self.token.state.wakers.lock().unwrap().remove(&self.id);
+    }
+}
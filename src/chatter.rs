@@ -0,0 +1,415 @@
+//! # Chatter Module
+//!
+//! This module generates ambient chatter: short exchanges between NPCs that the player can
+//! overhear while walking past. Given a location and the NPCs present, NPCs are paired up by
+//! how well they know each other, and each pair's exchange is seeded from their relationship
+//! label, their moods, and what is going on in the world, so friends gossip about the latest
+//! news while rivals bicker over it.
+//!
+//! Like barks, chatter is cheap by design: a single request generates several exchanges for a
+//! pair at once, exchanges are cached per situation and reused in rotation, and caches can be
+//! filled ahead of time so no request has to be made during gameplay.
+
+#[cfg(feature = "network")]
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::NpcAgent;
+use crate::bark::BarkConfig;
+use crate::relationship;
+#[cfg(feature = "network")]
+use crate::dialogue_generation::{DialogueClient, RequestType};
+
+/// The line that separates exchanges in a model reply.
+const SEPARATOR: &str = "---";
+
+/// Represents an NPC taking part in an exchange.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ChatterSpeaker {
+    /// The name the NPC's lines are attributed to.
+    pub name: String,
+    /// The NPC's mood (e.g. "joy").
+    pub mood: String,
+}
+
+/// Represents the situation two NPCs chat in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ChatterContext {
+    pub speakers: [ChatterSpeaker; 2],
+    /// How the first speaker regards the second (e.g. "rival").
+    pub relationship: String,
+    /// Where the exchange takes place (e.g. "the market square").
+    pub location: String,
+    /// What is going on in the world, which the speakers may talk about.
+    pub events: Vec<String>,
+}
+
+impl ChatterContext {
+    /// Creates the context for two agents chatting, seeded from how the first regards the
+    /// second and from their current emotions.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The agent who speaks first.
+    /// * `second` - The agent they talk to.
+    /// * `location` - Where they are.
+    /// * `events` - What is going on in the world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::chatter::ChatterContext;
+    ///
+    /// let mut mira = NpcAgent::new("Mira", vec![]);
+    /// let tom = NpcAgent::new("Tom", vec![]);
+    /// mira.interlocutor("Tom").adjust_affinity(-0.6);
+    /// let context = ChatterContext::between(&mira, &tom, "the docks", &[]);
+    /// assert_eq!(context.relationship, "enemy");
+    /// ```
+    pub fn between(
+        first: &NpcAgent,
+        second: &NpcAgent,
+        location: &str,
+        events: &[String],
+    ) -> Self {
+        ChatterContext {
+            speakers: [speaker(first), speaker(second)],
+            relationship: first.relationship_label(second.id.as_str()),
+            location: location.to_string(),
+            events: events.to_vec(),
+        }
+    }
+
+    /// Pairs up the agents present at a location and creates a context for each pair.
+    ///
+    /// Pairs that know each other best are formed first, and every agent takes part in at most
+    /// one exchange; with an odd number of agents, one is left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::chatter::ChatterContext;
+    ///
+    /// let mut baker = NpcAgent::new("baker", vec![]);
+    /// let guard = NpcAgent::new("guard", vec![]);
+    /// let miller = NpcAgent::new("miller", vec![]);
+    /// baker.interlocutor("miller").familiarity = 0.8;
+    /// let contexts = ChatterContext::for_crowd(&[&baker, &guard, &miller], "the square", &[]);
+    /// assert_eq!(contexts.len(), 1);
+    /// assert_eq!(contexts[0].speakers[1].name, "miller");
+    /// ```
+    pub fn for_crowd(agents: &[&NpcAgent], location: &str, events: &[String]) -> Vec<Self> {
+        let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+        for i in 0..agents.len() {
+            for j in i + 1..agents.len() {
+                let closeness = closeness(agents[i], agents[j]) + closeness(agents[j], agents[i]);
+                pairs.push((closeness, i, j));
+            }
+        }
+        pairs.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut paired = vec![false; agents.len()];
+        let mut contexts = Vec::new();
+        for (_, i, j) in pairs {
+            if paired[i] || paired[j] {
+                continue;
+            }
+            paired[i] = true;
+            paired[j] = true;
+            contexts.push(ChatterContext::between(agents[i], agents[j], location, events));
+        }
+        contexts
+    }
+
+    /// Renders the prompt asking the language model for several exchanges in this context.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - How many exchanges to ask for.
+    /// * `config` - The limits the exchanges must respect.
+    pub fn prompt(&self, count: usize, config: &ChatterConfig) -> String {
+        let [first, second] = &self.speakers;
+        let mut text = format!(
+            "{} (feeling {}) and {} (feeling {}) are talking",
+            first.name, first.mood, second.name, second.mood
+        );
+        if !self.location.is_empty() {
+            text.push_str(&format!(" at {}", self.location));
+        }
+        text.push_str(&format!(
+            ". {} regards {} as {}.",
+            first.name,
+            second.name,
+            relationship::with_article(&self.relationship)
+        ));
+        if !self.events.is_empty() {
+            text.push_str(&format!(" News going around: {}.", self.events.join("; ")));
+        }
+        text.push_str(&format!(
+            " Write {} different short exchanges between them that a passer-by could overhear, \
+             each 2 to {} lines of at most {} words, one line per turn formatted as \"Name: \
+             line\". Separate exchanges with a line containing only {}. Reply with the exchanges \
+             only.",
+            count, config.max_lines, config.line.max_words, SEPARATOR
+        ));
+        text
+    }
+}
+
+/// Describes an agent as a speaker.
+fn speaker(agent: &NpcAgent) -> ChatterSpeaker {
+    ChatterSpeaker {
+        name: agent.id.to_string(),
+        mood: format!("{:?}", agent.emotions.get_emotion()).to_lowercase(),
+    }
+}
+
+/// Returns how well one agent knows another.
+fn closeness(agent: &NpcAgent, other: &NpcAgent) -> f64 {
+    agent
+        .get_interlocutor(other.id.as_str())
+        .map_or(0.0, |context| context.familiarity + context.affinity.abs())
+}
+
+/// Represents one overheard exchange.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatterSnippet {
+    /// The lines of the exchange as `(speaker, line)`, in order.
+    pub lines: Vec<(String, String)>,
+}
+
+/// Represents the limits generated chatter must respect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatterConfig {
+    /// The maximum number of lines in an exchange.
+    pub max_lines: usize,
+    /// The limits every line must respect.
+    pub line: BarkConfig,
+    /// How many different exchanges are kept per context.
+    pub variants_per_context: usize,
+}
+
+impl Default for ChatterConfig {
+    fn default() -> Self {
+        ChatterConfig {
+            max_lines: 4,
+            line: BarkConfig {
+                max_chars: 100,
+                max_words: 15,
+                ..BarkConfig::default()
+            },
+            variants_per_context: 3,
+        }
+    }
+}
+
+impl ChatterConfig {
+    /// Parses a model reply into exchanges.
+    ///
+    /// Lines must be attributed to one of the context's speakers and pass the line limits;
+    /// anything else is dropped. Exchanges are cut to `max_lines`, and exchanges left with fewer
+    /// than two lines are dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::chatter::{ChatterConfig, ChatterContext};
+    ///
+    /// let mut context = ChatterContext::default();
+    /// context.speakers[0].name = "Mira".to_string();
+    /// context.speakers[1].name = "Tom".to_string();
+    /// let raw = "Mira: Did you hear about the fire?\nTom: Aye, terrible.\n---\nTom: Hm.\n---\n\
+    ///            Narrator: They sigh.\nMira: Rain again.\nTom: Always rain.";
+    /// let snippets = ChatterConfig::default().parse(raw, &context);
+    /// assert_eq!(snippets.len(), 2);
+    /// assert_eq!(snippets[1].lines[0], ("Mira".to_string(), "Rain again.".to_string()));
+    /// ```
+    pub fn parse(&self, raw: &str, context: &ChatterContext) -> Vec<ChatterSnippet> {
+        let mut snippets = Vec::new();
+        let mut lines: Vec<(String, String)> = Vec::new();
+        for row in raw.lines().map(str::trim).chain(std::iter::once(SEPARATOR)) {
+            if row.starts_with(SEPARATOR) {
+                lines.truncate(self.max_lines);
+                if lines.len() >= 2 {
+                    snippets.push(ChatterSnippet {
+                        lines: std::mem::take(&mut lines),
+                    });
+                }
+                lines.clear();
+                continue;
+            }
+            let Some((name, text)) = row.split_once(':') else {
+                continue;
+            };
+            let name = name.trim().trim_matches('*');
+            let known = context.speakers.iter().find(|speaker| speaker.name == name);
+            if let (Some(speaker), Some(text)) = (known, self.line.sanitize(text)) {
+                lines.push((speaker.name.clone(), text));
+            }
+        }
+        snippets
+    }
+}
+
+/// Generates chatter and caches it per context.
+#[cfg(feature = "network")]
+pub struct ChatterService {
+    client: DialogueClient,
+    config: ChatterConfig,
+    /// Cached exchanges per context.
+    cache: HashMap<ChatterContext, Vec<ChatterSnippet>>,
+    /// The index of the next cached exchange to serve per context.
+    cursors: HashMap<ChatterContext, usize>,
+}
+
+#[cfg(feature = "network")]
+impl ChatterService {
+    /// Creates a new chatter service using the given client and the default limits.
+    pub fn new(client: DialogueClient) -> Self {
+        ChatterService {
+            client,
+            config: ChatterConfig::default(),
+            cache: HashMap::new(),
+            cursors: HashMap::new(),
+        }
+    }
+
+    /// Replaces the limits.
+    pub fn set_config(&mut self, config: ChatterConfig) {
+        self.config = config;
+    }
+
+    /// Gets the limits.
+    pub fn get_config(&self) -> &ChatterConfig {
+        &self.config
+    }
+
+    /// Adds an exchange to the cache for a context, e.g. an authored fallback.
+    ///
+    /// # Returns
+    ///
+    /// True if the exchange was added.
+    pub fn insert(&mut self, context: &ChatterContext, snippet: ChatterSnippet) -> bool {
+        let snippets = self.cache.entry(context.clone()).or_default();
+        if snippet.lines.is_empty() || snippets.contains(&snippet) {
+            return false;
+        }
+        snippets.push(snippet);
+        true
+    }
+
+    /// Returns how many exchanges are cached for a context.
+    pub fn cached_count(&self, context: &ChatterContext) -> usize {
+        self.cache.get(context).map_or(0, |snippets| snippets.len())
+    }
+
+    /// Returns the next cached exchange for a context, rotating through the cached variants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::chatter::{ChatterContext, ChatterService, ChatterSnippet};
+    /// use athena::dialogue_generation::DialogueClient;
+    ///
+    /// let mut service = ChatterService::new(DialogueClient::new("my-api-key"));
+    /// let context = ChatterContext::default();
+    /// let snippet = ChatterSnippet {
+    ///     lines: vec![
+    ///         ("Mira".to_string(), "Cold today.".to_string()),
+    ///         ("Tom".to_string(), "Colder tomorrow.".to_string()),
+    ///     ],
+    /// };
+    /// service.insert(&context, snippet.clone());
+    /// assert_eq!(service.cached(&context), Some(snippet.clone()));
+    /// assert_eq!(service.cached(&context), Some(snippet));
+    /// ```
+    pub fn cached(&mut self, context: &ChatterContext) -> Option<ChatterSnippet> {
+        let snippets = self.cache.get(context).filter(|snippets| !snippets.is_empty())?;
+        let cursor = self.cursors.entry(context.clone()).or_insert(0);
+        let snippet = snippets[*cursor % snippets.len()].clone();
+        *cursor += 1;
+        Some(snippet)
+    }
+
+    /// Gets an exchange for a context.
+    ///
+    /// A cached exchange is served whenever one exists; otherwise a single request generates
+    /// `variants_per_context` exchanges at once and caches them all.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The situation the NPCs chat in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ChatterSnippet, Box<dyn std::error::Error>>` - The exchange, or an error if the
+    ///   request failed or no usable exchange came back.
+    pub async fn generate(
+        &mut self,
+        context: &ChatterContext,
+    ) -> Result<ChatterSnippet, Box<dyn std::error::Error>> {
+        if let Some(snippet) = self.cached(context) {
+            return Ok(snippet);
+        }
+        for snippet in self.request(context, self.config.variants_per_context).await? {
+            self.insert(context, snippet);
+        }
+        self.cached(context).ok_or_else(|| "Chatter was rejected".into())
+    }
+
+    /// Fills the cache for each context up to `variants_per_context` exchanges, with one request
+    /// per context.
+    ///
+    /// Meant to be called during loading screens. Failed requests and rejected replies are
+    /// skipped rather than retried.
+    ///
+    /// # Returns
+    ///
+    /// The number of exchanges added to the cache.
+    pub async fn pregenerate(&mut self, contexts: &[ChatterContext]) -> usize {
+        let mut added = 0;
+        for context in contexts {
+            let missing = self
+                .config
+                .variants_per_context
+                .saturating_sub(self.cached_count(context));
+            if missing == 0 {
+                continue;
+            }
+            if let Ok(snippets) = self.request(context, missing).await {
+                for snippet in snippets {
+                    if self.insert(context, snippet) {
+                        added += 1;
+                    }
+                }
+            }
+        }
+        added
+    }
+
+    /// Requests several exchanges from the language model and parses them.
+    async fn request(
+        &self,
+        context: &ChatterContext,
+        count: usize,
+    ) -> Result<Vec<ChatterSnippet>, Box<dyn std::error::Error>> {
+        let prompt = context.prompt(count, &self.config);
+        let response = self
+            .client
+            .send(RequestType::Bark, &prompt)
+            .await
+            .map_err(|error| error as Box<dyn std::error::Error>)?;
+        let raw = response
+            .choices
+            .first()
+            .map(|choice| choice.message.content.as_str())
+            .unwrap_or_default();
+        let snippets = self.config.parse(raw, context);
+        if snippets.is_empty() {
+            return Err(format!("Chatter rejected: {}", raw).into());
+        }
+        Ok(snippets)
+    }
+}
@@ -0,0 +1,241 @@
+//! # Checkpoint Module
+//!
+//! This module turns finished conversations into structured outcome records, so what was said
+//! can drive the rest of the game. While a conversation runs, the NPC takes notes: the topics
+//! discussed, the promises made, and where the relationship stood when it started. When the
+//! conversation ends, the notes and the NPC's conversation goals become a `ConversationOutcome`
+//! listing the topics, the commitments, the information the partner revealed, and how the
+//! relationship changed.
+//!
+//! Only significant conversations are kept. Their outcomes are written into the NPC's knowledge
+//! graph as facts about the partner, and offer quest hooks, so quest generation can follow up
+//! on a promise or a lead.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::conversation_goal::{ConversationGoal, GoalKind, GoalStatus};
+use crate::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+use crate::ledger::{HookKind, QuestHook};
+use crate::promises::Promise;
+
+/// Represents when a conversation is significant enough to produce an outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointPolicy {
+    /// The number of partner lines that makes a conversation significant on its own.
+    pub min_turns: usize,
+    /// The change in affinity or trust that makes a conversation significant on its own.
+    pub min_relationship_change: f64,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        CheckpointPolicy {
+            min_turns: 6,
+            min_relationship_change: 0.1,
+        }
+    }
+}
+
+/// Represents the notes an NPC takes during a conversation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionNotes {
+    /// When the conversation started, in seconds of game time.
+    pub started_at: f64,
+    /// The affinity towards the partner when it started.
+    pub affinity: f64,
+    /// The trust in the partner when it started.
+    pub trust: f64,
+    /// The IDs of the topics discussed, in the order they came up.
+    pub topics: Vec<String>,
+    /// The promises the partner made.
+    pub commitments: Vec<Promise>,
+}
+
+impl SessionNotes {
+    /// Starts the notes of a conversation.
+    pub fn new(started_at: f64, affinity: f64, trust: f64) -> Self {
+        SessionNotes {
+            started_at,
+            affinity,
+            trust,
+            ..SessionNotes::default()
+        }
+    }
+
+    /// Notes topics that came up, skipping those already noted.
+    pub fn note_topics<'a>(&mut self, topics: impl IntoIterator<Item = &'a str>) {
+        for topic in topics {
+            if !self.topics.iter().any(|noted| noted == topic) {
+                self.topics.push(topic.to_string());
+            }
+        }
+    }
+}
+
+/// Represents the outcome of a finished conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationOutcome {
+    pub npc: String,
+    pub partner: String,
+    /// When the conversation started and ended, in seconds of game time.
+    pub started_at: f64,
+    pub ended_at: f64,
+    /// The number of lines the partner said.
+    pub turns: usize,
+    /// The IDs of the topics discussed.
+    pub topics: Vec<String>,
+    /// The promises the partner made.
+    pub commitments: Vec<Promise>,
+    /// The subjects the partner revealed information about.
+    pub revealed: Vec<String>,
+    /// The NPC's conversation goals and how they ended.
+    pub goals: Vec<ConversationGoal>,
+    pub affinity_change: f64,
+    pub trust_change: f64,
+}
+
+impl ConversationOutcome {
+    /// Builds the outcome of a conversation from the notes taken during it.
+    ///
+    /// # Arguments
+    ///
+    /// * `npc` - The NPC who took the notes.
+    /// * `partner` - Who it talked to.
+    /// * `notes` - The notes.
+    /// * `goals` - The NPC's conversation goals, as assessed at the end.
+    /// * `turns` - The number of lines the partner said.
+    /// * `now` - The current game time.
+    /// * `relationship` - The affinity and trust towards the partner at the end.
+    pub fn from_notes(
+        npc: &str,
+        partner: &str,
+        notes: SessionNotes,
+        goals: Vec<ConversationGoal>,
+        turns: usize,
+        now: f64,
+        relationship: (f64, f64),
+    ) -> Self {
+        let mut revealed: Vec<String> = Vec::new();
+        for subject in goals.iter().flat_map(|goal| goal.get_learned()) {
+            if !revealed.contains(subject) {
+                revealed.push(subject.clone());
+            }
+        }
+        ConversationOutcome {
+            npc: npc.to_string(),
+            partner: partner.to_string(),
+            started_at: notes.started_at,
+            ended_at: now,
+            turns,
+            topics: notes.topics,
+            commitments: notes.commitments,
+            revealed,
+            goals,
+            affinity_change: relationship.0 - notes.affinity,
+            trust_change: relationship.1 - notes.trust,
+        }
+    }
+
+    /// Returns true if the conversation mattered: a promise was made, information was revealed,
+    /// a goal was achieved, or it was long or moved the relationship enough.
+    pub fn is_significant(&self, policy: &CheckpointPolicy) -> bool {
+        !self.commitments.is_empty()
+            || !self.revealed.is_empty()
+            || self.goals.iter().any(|goal| goal.status == GoalStatus::Achieved)
+            || self.turns >= policy.min_turns
+            || self.affinity_change.abs() >= policy.min_relationship_change
+            || self.trust_change.abs() >= policy.min_relationship_change
+    }
+
+    /// Describes the outcome in one line.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{} talked with {}", self.partner, self.npc)];
+        if !self.topics.is_empty() {
+            parts.push(format!("about {}", self.topics.join(", ")));
+        }
+        let mut summary = parts.join(" ");
+        for promise in &self.commitments {
+            summary.push_str(&format!("; promised to {}", promise.what));
+        }
+        if !self.revealed.is_empty() {
+            summary.push_str(&format!("; revealed {}", self.revealed.join(", ")));
+        }
+        summary.push('.');
+        summary
+    }
+
+    /// Lists the quests the outcome could motivate: following up on each promise and each
+    /// proposal the partner agreed to, and pursuing each revealed subject.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::checkpoint::{ConversationOutcome, SessionNotes};
+    /// use athena::conversation_goal::{ConversationGoal, GoalKind};
+    /// use athena::ledger::HookKind;
+    ///
+    /// let mut goal = ConversationGoal::new(GoalKind::ExtractInformation(vec!["tunnel".to_string()]));
+    /// goal.assess("The tunnel starts under the mill.");
+    /// let outcome = ConversationOutcome::from_notes(
+    ///     "spy", "alice", SessionNotes::new(0.0, 0.0, 0.0), vec![goal], 3, 60.0, (0.0, 0.0),
+    /// );
+    /// assert_eq!(outcome.summary(), "alice talked with spy; revealed tunnel.");
+    /// assert_eq!(outcome.quest_hooks()[0].kind, HookKind::Lead);
+    /// ```
+    pub fn quest_hooks(&self) -> Vec<QuestHook> {
+        let commitments = self.commitments.iter().map(|promise| QuestHook {
+            kind: HookKind::FollowUp,
+            counterpart: self.partner.clone(),
+            reason: promise.what.clone(),
+            magnitude: 1.0,
+        });
+        let leads = self.revealed.iter().map(|subject| QuestHook {
+            kind: HookKind::Lead,
+            counterpart: self.partner.clone(),
+            reason: subject.clone(),
+            magnitude: 0.5,
+        });
+        let agreements = self.goals.iter().filter_map(|goal| match &goal.kind {
+            GoalKind::Persuade(proposal) if goal.status == GoalStatus::Achieved => {
+                Some(QuestHook {
+                    kind: HookKind::FollowUp,
+                    counterpart: self.partner.clone(),
+                    reason: proposal.clone(),
+                    magnitude: 0.8,
+                })
+            }
+            _ => None,
+        });
+        commitments.chain(agreements).chain(leads).collect()
+    }
+
+    /// Writes the outcome into a knowledge graph as facts about the partner: the topics they
+    /// discussed, what they promised, and what they revealed, each stamped with the time.
+    ///
+    /// # Returns
+    ///
+    /// The number of facts added.
+    pub fn record(&self, knowledge: &mut KnowledgeGraph) -> usize {
+        let facts = self
+            .topics
+            .iter()
+            .map(|topic| ("discussed", topic.as_str()))
+            .chain(self.commitments.iter().map(|promise| ("promised", promise.what.as_str())))
+            .chain(self.revealed.iter().map(|subject| ("revealed", subject.as_str())));
+        let mut count = 0;
+        for (relation, target) in facts {
+            for id in [self.partner.as_str(), target] {
+                if knowledge.get_entity(id).is_none() {
+                    knowledge.add_entity(Entity::new(id, HashMap::new()));
+                }
+            }
+            let properties = HashMap::from([("at".to_string(), self.ended_at.to_string())]);
+            let fact = Relationship::new(&self.partner, target, relation, properties);
+            knowledge.add_relationship(fact);
+            count += 1;
+        }
+        count
+    }
+}
@@ -0,0 +1,209 @@
+//! # Companionship Module
+//!
+//! This module implements an opt-in relationship progression for companions and romance
+//! options. A bond moves through stages (stranger, friend, confidant, partner); each step
+//! requires enough affinity and trust, a number of memorable shared events, and for the final
+//! stage a compatible personality. A bond can also slip back a stage if affinity collapses.
+//!
+//! The current stage is exposed as a prompt directive for dialogue and as a plain value that
+//! quest generation can gate content on.
+
+use serde::{Deserialize, Serialize};
+
+use crate::personality::Personality;
+
+/// How far affinity may fall below a stage's requirement before the bond slips back.
+const REGRESSION_MARGIN: f64 = 0.3;
+
+/// Represents a stage of a bond, from first meeting to partnership.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum BondStage {
+    Stranger,
+    Friend,
+    Confidant,
+    Partner,
+}
+
+impl BondStage {
+    /// Returns the stage after this one, or `None` at the final stage.
+    pub fn next(&self) -> Option<BondStage> {
+        match self {
+            BondStage::Stranger => Some(BondStage::Friend),
+            BondStage::Friend => Some(BondStage::Confidant),
+            BondStage::Confidant => Some(BondStage::Partner),
+            BondStage::Partner => None,
+        }
+    }
+
+    /// Returns the stage before this one, or `None` at the first stage.
+    pub fn previous(&self) -> Option<BondStage> {
+        match self {
+            BondStage::Stranger => None,
+            BondStage::Friend => Some(BondStage::Stranger),
+            BondStage::Confidant => Some(BondStage::Friend),
+            BondStage::Partner => Some(BondStage::Confidant),
+        }
+    }
+
+    /// Renders the stage as a prompt directive.
+    pub fn directive(&self) -> &'static str {
+        match self {
+            BondStage::Stranger => "The player is a stranger to you; be polite but reserved.",
+            BondStage::Friend => "The player is your friend; be warm and relaxed.",
+            BondStage::Confidant => "The player is your confidant; you share secrets and worries.",
+            BondStage::Partner => "The player is your partner; speak with affection and devotion.",
+        }
+    }
+}
+
+/// Represents what it takes to reach a stage.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StageRequirement {
+    pub min_affinity: f64,
+    pub min_trust: f64,
+    /// The number of memorable events that must have been shared.
+    pub min_events: usize,
+    /// The personality compatibility required, if any (between 0.0 and 1.0).
+    pub min_compatibility: Option<f64>,
+}
+
+/// Computes how compatible two personalities are, between 0.0 and 1.0. This is
+/// `Personality::compatibility` rescaled for bond requirements.
+///
+/// # Examples
+///
+/// ```
+/// use athena::companionship::compatibility;
+/// use athena::personality::Personality;
+///
+/// let mut calm = Personality::new();
+/// calm.set_neuroticism(0.1);
+/// let mut volatile = Personality::new();
+/// volatile.set_neuroticism(0.9);
+/// volatile.set_agreeableness(0.1);
+/// assert!(compatibility(&calm, &calm) > compatibility(&calm, &volatile));
+/// ```
+pub fn compatibility(a: &Personality, b: &Personality) -> f64 {
+    (a.compatibility(b) + 1.0) / 2.0
+}
+
+/// Tracks the progression of one bond.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Companionship {
+    stage: BondStage,
+    /// Memorable events shared with the partner, oldest first.
+    events: Vec<String>,
+    /// The requirements for reaching friend, confidant, and partner, in that order.
+    requirements: [StageRequirement; 3],
+}
+
+impl Default for Companionship {
+    fn default() -> Self {
+        Companionship {
+            stage: BondStage::Stranger,
+            events: Vec::new(),
+            requirements: [
+                StageRequirement {
+                    min_affinity: 0.3,
+                    min_trust: 0.1,
+                    min_events: 0,
+                    min_compatibility: None,
+                },
+                StageRequirement {
+                    min_affinity: 0.5,
+                    min_trust: 0.4,
+                    min_events: 1,
+                    min_compatibility: None,
+                },
+                StageRequirement {
+                    min_affinity: 0.75,
+                    min_trust: 0.6,
+                    min_events: 3,
+                    min_compatibility: Some(0.6),
+                },
+            ],
+        }
+    }
+}
+
+impl Companionship {
+    /// Creates a new bond at the stranger stage with the default requirements.
+    pub fn new() -> Self {
+        Companionship::default()
+    }
+
+    /// Gets the current stage.
+    pub fn get_stage(&self) -> BondStage {
+        self.stage
+    }
+
+    /// Gets the requirement for reaching a stage, or `None` for the stranger stage.
+    pub fn get_requirement(&self, stage: BondStage) -> Option<&StageRequirement> {
+        let index = (stage as usize).checked_sub(1)?;
+        self.requirements.get(index)
+    }
+
+    /// Sets the requirement for reaching a stage. The stranger stage has no requirement.
+    pub fn set_requirement(&mut self, stage: BondStage, requirement: StageRequirement) {
+        if let Some(index) = (stage as usize).checked_sub(1) {
+            self.requirements[index] = requirement;
+        }
+    }
+
+    /// Records a memorable event shared with the partner (e.g. "survived the flood together").
+    pub fn record_event(&mut self, description: &str) {
+        self.events.push(description.to_string());
+    }
+
+    /// Iterates over the memorable events, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &str> {
+        self.events.iter().map(|event| event.as_str())
+    }
+
+    /// Re-evaluates the stage, advancing or slipping back at most one stage.
+    ///
+    /// # Arguments
+    ///
+    /// * `affinity` - The NPC's affinity towards the partner.
+    /// * `trust` - The NPC's trust in the partner.
+    /// * `compatibility` - How compatible their personalities are (see `compatibility`).
+    ///
+    /// # Returns
+    ///
+    /// The new stage if it changed, otherwise `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::companionship::{BondStage, Companionship};
+    ///
+    /// let mut bond = Companionship::new();
+    /// assert_eq!(bond.evaluate(0.6, 0.5, 0.8), Some(BondStage::Friend));
+    /// assert_eq!(bond.evaluate(0.6, 0.5, 0.8), None); // Confidants need a shared event.
+    /// bond.record_event("Fought off the wolves together");
+    /// assert_eq!(bond.evaluate(0.6, 0.5, 0.8), Some(BondStage::Confidant));
+    /// assert_eq!(bond.evaluate(-0.1, 0.5, 0.8), Some(BondStage::Friend));
+    /// ```
+    pub fn evaluate(&mut self, affinity: f64, trust: f64, compatibility: f64) -> Option<BondStage> {
+        if let Some(current) = self.get_requirement(self.stage) {
+            if affinity < current.min_affinity - REGRESSION_MARGIN {
+                self.stage = self.stage.previous()?;
+                return Some(self.stage);
+            }
+        }
+        let next = self.stage.next()?;
+        let requirement = self.get_requirement(next)?;
+        let compatible = requirement
+            .min_compatibility
+            .is_none_or(|minimum| compatibility >= minimum);
+        if affinity >= requirement.min_affinity
+            && trust >= requirement.min_trust
+            && self.events.len() >= requirement.min_events
+            && compatible
+        {
+            self.stage = next;
+            return Some(next);
+        }
+        None
+    }
+}
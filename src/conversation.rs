@@ -0,0 +1,410 @@
+//! # Conversation Module
+//!
+//! This module keeps the history of conversations between NPCs and players, keyed by
+//! `(npc_id, player_id)`. Conversations are persisted through the `ConversationStore` trait so
+//! they survive process restarts, letting an NPC pick up "where were we?" across play sessions.
+//!
+//! Three stores are provided: an in-memory store for tests and short-lived sessions, a
+//! file-backed store writing one snapshot file per conversation (JSON by default), and (with the
+//! `sqlite` feature) a SQLite-backed store.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::snapshot::{Encoding, Persist, SnapshotOptions};
+
+/// Represents a single line spoken in a conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    /// Who spoke the line (an NPC ID, a player ID, or a display name).
+    pub speaker: String,
+    pub text: String,
+}
+
+/// Represents the full history of a conversation between an NPC and a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    pub npc_id: String,
+    pub player_id: String,
+    pub turns: Vec<Turn>,
+}
+
+impl Conversation {
+    /// Creates a new, empty conversation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::Conversation;
+    /// let conversation = Conversation::new("blacksmith", "player_1");
+    /// assert!(conversation.turns.is_empty());
+    /// ```
+    pub fn new(npc_id: &str, player_id: &str) -> Self {
+        Conversation {
+            npc_id: npc_id.to_string(),
+            player_id: player_id.to_string(),
+            turns: Vec::new(),
+        }
+    }
+
+    /// Appends a line to the conversation.
+    pub fn add_turn(&mut self, speaker: &str, text: &str) {
+        self.turns.push(Turn {
+            speaker: speaker.to_string(),
+            text: text.to_string(),
+        });
+    }
+
+    /// Returns the last `count` turns, oldest first.
+    pub fn recent_turns(&self, count: usize) -> &[Turn] {
+        &self.turns[self.turns.len().saturating_sub(count)..]
+    }
+
+    /// Renders a short recap of where the conversation left off, for resuming it in a prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of recent turns to include.
+    ///
+    /// # Returns
+    ///
+    /// The recap text, or `None` if the conversation has no turns yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::Conversation;
+    /// let mut conversation = Conversation::new("blacksmith", "player_1");
+    /// conversation.add_turn("player_1", "Can you fix my sword?");
+    /// conversation.add_turn("blacksmith", "Come back tomorrow.");
+    /// let recap = conversation.resume_recap(1).unwrap();
+    /// assert!(recap.contains("Come back tomorrow."));
+    /// ```
+    pub fn resume_recap(&self, count: usize) -> Option<String> {
+        if self.turns.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = self
+            .recent_turns(count)
+            .iter()
+            .map(|turn| format!("{}: {}", turn.speaker, turn.text))
+            .collect();
+        Some(format!("Last time you spoke:\n{}", lines.join("\n")))
+    }
+}
+
+/// Persists conversations keyed by `(npc_id, player_id)`.
+pub trait ConversationStore {
+    /// Loads a conversation, returning `None` if none was saved.
+    fn load(
+        &self,
+        npc_id: &str,
+        player_id: &str,
+    ) -> Result<Option<Conversation>, Box<dyn std::error::Error>>;
+
+    /// Saves a conversation, replacing any previously saved version.
+    fn save(&mut self, conversation: &Conversation) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Appends a line to a conversation, creating the conversation if needed.
+    ///
+    /// The default implementation loads, modifies, and saves the whole conversation.
+    fn append(
+        &mut self,
+        npc_id: &str,
+        player_id: &str,
+        speaker: &str,
+        text: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut conversation = self
+            .load(npc_id, player_id)?
+            .unwrap_or_else(|| Conversation::new(npc_id, player_id));
+        conversation.add_turn(speaker, text);
+        self.save(&conversation)
+    }
+}
+
+/// A conversation store that keeps everything in memory.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryConversationStore {
+    conversations: HashMap<(String, String), Conversation>,
+}
+
+impl InMemoryConversationStore {
+    /// Creates a new, empty in-memory store.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::{ConversationStore, InMemoryConversationStore};
+    /// let mut store = InMemoryConversationStore::new();
+    /// store.append("blacksmith", "player_1", "player_1", "Hello!").unwrap();
+    /// let conversation = store.load("blacksmith", "player_1").unwrap().unwrap();
+    /// assert_eq!(conversation.turns.len(), 1);
+    /// ```
+    pub fn new() -> Self {
+        InMemoryConversationStore {
+            conversations: HashMap::new(),
+        }
+    }
+}
+
+impl ConversationStore for InMemoryConversationStore {
+    fn load(
+        &self,
+        npc_id: &str,
+        player_id: &str,
+    ) -> Result<Option<Conversation>, Box<dyn std::error::Error>> {
+        Ok(self
+            .conversations
+            .get(&(npc_id.to_string(), player_id.to_string()))
+            .cloned())
+    }
+
+    fn save(&mut self, conversation: &Conversation) -> Result<(), Box<dyn std::error::Error>> {
+        self.conversations.insert(
+            (conversation.npc_id.clone(), conversation.player_id.clone()),
+            conversation.clone(),
+        );
+        Ok(())
+    }
+}
+
+/// A conversation store that writes one snapshot file per conversation into a directory.
+#[derive(Debug, Clone)]
+pub struct FileConversationStore {
+    directory: PathBuf,
+    options: SnapshotOptions,
+}
+
+impl FileConversationStore {
+    /// Creates a store in the given directory, creating the directory if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The directory holding the conversation files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::{ConversationStore, FileConversationStore};
+    /// let directory = std::env::temp_dir().join("athena_conversation_doctest");
+    /// let mut store = FileConversationStore::new(&directory).unwrap();
+    /// store.append("blacksmith", "player_1", "player_1", "Hello!").unwrap();
+    ///
+    /// let reopened = FileConversationStore::new(&directory).unwrap();
+    /// assert!(reopened.load("blacksmith", "player_1").unwrap().is_some());
+    ///
+    /// // IDs that differ only in punctuation or case never share a file.
+    /// store.append("guard_7", "a_", "a_", "Halt!").unwrap();
+    /// assert!(store.load("guard 7", "a_").unwrap().is_none());
+    /// assert!(store.load("Guard_7", "a_").unwrap().is_none());
+    /// assert!(store.load("guard", "7__a").unwrap().is_none());
+    /// # std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self, Box<dyn std::error::Error>> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(FileConversationStore {
+            directory,
+            options: SnapshotOptions::json(),
+        })
+    }
+
+    /// Sets how conversations are written. Conversations saved in another format still load.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::{ConversationStore, FileConversationStore};
+    /// use athena::snapshot::SnapshotOptions;
+    /// let directory = std::env::temp_dir().join("athena_binary_conversation_doctest");
+    /// let mut store = FileConversationStore::new(&directory).unwrap();
+    /// store.append("blacksmith", "player_1", "player_1", "Hello!").unwrap();
+    ///
+    /// let mut store = store.with_options(SnapshotOptions::binary());
+    /// store.append("blacksmith", "player_1", "blacksmith", "Welcome.").unwrap();
+    /// assert_eq!(store.load("blacksmith", "player_1").unwrap().unwrap().turns.len(), 2);
+    /// # std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn with_options(mut self, options: SnapshotOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the path of the file for a conversation written with an encoding.
+    fn path(&self, npc_id: &str, player_id: &str, encoding: Encoding) -> PathBuf {
+        self.directory
+            .join(format!("{}__{}.{}", escape(npc_id), escape(player_id), extension(encoding)))
+    }
+
+    /// Returns the path older versions wrote a conversation to, which several pairs of IDs can
+    /// share; files found there are only used if they hold the conversation asked for.
+    fn legacy_path(&self, npc_id: &str, player_id: &str, encoding: Encoding) -> PathBuf {
+        let sanitize = |id: &str| -> String {
+            id.chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+                .collect()
+        };
+        self.directory.join(format!(
+            "{}__{}.{}",
+            sanitize(npc_id),
+            sanitize(player_id),
+            extension(encoding)
+        ))
+    }
+}
+
+/// Gets the file extension of an encoding.
+fn extension(encoding: Encoding) -> &'static str {
+    match encoding {
+        Encoding::Json => "json",
+        Encoding::Binary => "snap",
+    }
+}
+
+/// Escapes an ID for use in a file name: lowercase ASCII letters, digits, and '-' are kept, and
+/// every other byte is written as `%XX`. Distinct IDs give distinct names, even on file systems
+/// that ignore case, and escaped IDs never contain the "__" separator.
+fn escape(id: &str) -> String {
+    let mut escaped = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-' {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+impl ConversationStore for FileConversationStore {
+    fn load(
+        &self,
+        npc_id: &str,
+        player_id: &str,
+    ) -> Result<Option<Conversation>, Box<dyn std::error::Error>> {
+        let encoding = self.options.encoding;
+        let other = match encoding {
+            Encoding::Json => Encoding::Binary,
+            Encoding::Binary => Encoding::Json,
+        };
+        let paths = [encoding, other]
+            .map(|encoding| self.path(npc_id, player_id, encoding))
+            .into_iter()
+            .chain([encoding, other].map(|encoding| self.legacy_path(npc_id, player_id, encoding)));
+        for path in paths {
+            if !path.exists() {
+                continue;
+            }
+            let conversation = Conversation::from_snapshot(&fs::read(path)?)?;
+            if conversation.npc_id == npc_id && conversation.player_id == player_id {
+                return Ok(Some(conversation));
+            }
+        }
+        Ok(None)
+    }
+
+    fn save(&mut self, conversation: &Conversation) -> Result<(), Box<dyn std::error::Error>> {
+        let (npc_id, player_id) = (&conversation.npc_id, &conversation.player_id);
+        fs::write(
+            self.path(npc_id, player_id, self.options.encoding),
+            conversation.to_snapshot(self.options)?,
+        )?;
+        for encoding in [Encoding::Json, Encoding::Binary] {
+            let stale = self.path(npc_id, player_id, encoding);
+            if encoding != self.options.encoding && stale.exists() {
+                fs::remove_file(stale)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A conversation store backed by a SQLite database.
+#[cfg(feature = "sqlite")]
+pub struct SqliteConversationStore {
+    connection: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteConversationStore {
+    /// Opens (or creates) a SQLite database at the given path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the database file.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_connection(rusqlite::Connection::open(path)?)
+    }
+
+    /// Opens a SQLite database that lives only in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::{ConversationStore, SqliteConversationStore};
+    /// let mut store = SqliteConversationStore::open_in_memory().unwrap();
+    /// store.append("blacksmith", "player_1", "player_1", "Hello!").unwrap();
+    /// assert!(store.load("blacksmith", "player_1").unwrap().is_some());
+    /// ```
+    pub fn open_in_memory() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_connection(rusqlite::Connection::open_in_memory()?)
+    }
+
+    /// Creates the conversations table if needed.
+    fn from_connection(connection: rusqlite::Connection) -> Result<Self, Box<dyn std::error::Error>> {
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                npc_id TEXT NOT NULL,
+                player_id TEXT NOT NULL,
+                turns TEXT NOT NULL,
+                PRIMARY KEY (npc_id, player_id)
+            )",
+            [],
+        )?;
+        Ok(SqliteConversationStore { connection })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ConversationStore for SqliteConversationStore {
+    fn load(
+        &self,
+        npc_id: &str,
+        player_id: &str,
+    ) -> Result<Option<Conversation>, Box<dyn std::error::Error>> {
+        use rusqlite::OptionalExtension;
+
+        let turns: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT turns FROM conversations WHERE npc_id = ?1 AND player_id = ?2",
+                [npc_id, player_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match turns {
+            Some(turns) => Ok(Some(Conversation {
+                npc_id: npc_id.to_string(),
+                player_id: player_id.to_string(),
+                turns: serde_json::from_str(&turns)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&mut self, conversation: &Conversation) -> Result<(), Box<dyn std::error::Error>> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO conversations (npc_id, player_id, turns) VALUES (?1, ?2, ?3)",
+            [
+                conversation.npc_id.as_str(),
+                conversation.player_id.as_str(),
+                serde_json::to_string(&conversation.turns)?.as_str(),
+            ],
+        )?;
+        Ok(())
+    }
+}
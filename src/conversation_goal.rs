@@ -0,0 +1,226 @@
+//! # Conversation Goal Module
+//!
+//! This module gives NPCs something to want from a conversation. An NPC can enter a
+//! conversation with explicit goals (finding something out, talking the partner into something,
+//! keeping them talking, or comforting them), which are assessed after every line the partner
+//! says. Each goal turns into a prompt directive that steers the NPC's lines towards it, growing
+//! more direct as turns pass without progress, so its dialogue has direction instead of only
+//! reacting.
+//!
+//! Progress is assessed with keyword heuristics on the partner's lines, so it works without the
+//! language model.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_act::normalize;
+
+/// Phrases that signal the partner agrees.
+const AGREEMENT_PHRASES: &[&str] = &[
+    "yes", "agreed", "deal", "fine", "okay", "ok", "all right", "very well", "you're right",
+    "i'll do it", "you have a point",
+];
+
+/// Phrases that signal the partner refuses.
+const REFUSAL_PHRASES: &[&str] = &["no", "never", "not a chance", "forget it", "i refuse", "won't"];
+
+/// Phrases that signal the partner feels better.
+const RELIEF_PHRASES: &[&str] = &[
+    "thank", "thanks", "thank you", "better", "calmer", "relieved", "you're right", "kind of you",
+];
+
+/// Phrases that signal the partner is still distressed.
+const DISTRESS_PHRASES: &[&str] = &[
+    "afraid", "scared", "sad", "alone", "hopeless", "crying", "hurts", "can't go on",
+];
+
+/// How many refusals make a persuasion attempt fail.
+const MAX_REFUSALS: usize = 3;
+
+/// Returns true if a normalized line contains any of the phrases as whole words.
+fn mentions(normalized: &str, phrases: &[&str]) -> bool {
+    phrases.iter().any(|phrase| normalized.contains(&format!(" {} ", phrase)))
+}
+
+/// Represents what an NPC wants from a conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GoalKind {
+    /// Get the partner to talk about these subjects (e.g. "smuggler", "tunnel").
+    ExtractInformation(Vec<String>),
+    /// Get the partner to agree to a proposal (e.g. "escort the caravan").
+    Persuade(String),
+    /// Keep the partner talking for this many of their lines.
+    Stall(usize),
+    /// Make the partner feel better.
+    Comfort,
+}
+
+/// Represents where a conversation goal stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GoalStatus {
+    Active,
+    Achieved,
+    Failed,
+}
+
+/// Represents a conversation goal and its progress.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationGoal {
+    pub kind: GoalKind,
+    /// How close the goal is to being achieved (between 0.0 and 1.0).
+    pub progress: f64,
+    pub status: GoalStatus,
+    /// How many of the partner's lines have been assessed.
+    pub turns: usize,
+    /// After how many of the partner's lines an unachieved goal fails.
+    pub max_turns: usize,
+    /// The subjects the partner has talked about, for `ExtractInformation`.
+    learned: Vec<String>,
+    /// How often the partner refused, for `Persuade`.
+    refusals: usize,
+}
+
+impl ConversationGoal {
+    /// Creates a new active goal that fails after 10 of the partner's lines.
+    pub fn new(kind: GoalKind) -> Self {
+        ConversationGoal {
+            kind,
+            progress: 0.0,
+            status: GoalStatus::Active,
+            turns: 0,
+            max_turns: 10,
+            learned: Vec::new(),
+            refusals: 0,
+        }
+    }
+
+    /// Sets after how many of the partner's lines an unachieved goal fails.
+    pub fn with_max_turns(mut self, max_turns: usize) -> Self {
+        self.max_turns = max_turns.max(1);
+        self
+    }
+
+    /// Returns true while the goal is being pursued.
+    pub fn is_active(&self) -> bool {
+        self.status == GoalStatus::Active
+    }
+
+    /// Gets the subjects the partner has talked about, for `ExtractInformation`.
+    pub fn get_learned(&self) -> &[String] {
+        &self.learned
+    }
+
+    /// Assesses one of the partner's lines and updates the goal's progress.
+    ///
+    /// # Returns
+    ///
+    /// The goal's status after the assessment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation_goal::{ConversationGoal, GoalKind, GoalStatus};
+    ///
+    /// let subjects = vec!["smuggler".to_string(), "tunnel".to_string()];
+    /// let mut goal = ConversationGoal::new(GoalKind::ExtractInformation(subjects));
+    /// goal.assess("I saw a smuggler by the docks.");
+    /// assert_eq!(goal.progress, 0.5);
+    /// assert_eq!(goal.assess("He came out of the old tunnel."), GoalStatus::Achieved);
+    ///
+    /// let mut goal = ConversationGoal::new(GoalKind::Persuade("escort the caravan".to_string()));
+    /// goal.assess("Why would I do that?");
+    /// assert_eq!(goal.assess("Fine, I'll do it."), GoalStatus::Achieved);
+    /// ```
+    pub fn assess(&mut self, partner_line: &str) -> GoalStatus {
+        if !self.is_active() {
+            return self.status;
+        }
+        self.turns += 1;
+        let normalized = normalize(partner_line);
+        match &self.kind {
+            GoalKind::ExtractInformation(subjects) => {
+                for subject in subjects {
+                    let mentioned = normalized.contains(&subject.to_lowercase());
+                    if mentioned && !self.learned.contains(subject) {
+                        self.learned.push(subject.clone());
+                    }
+                }
+                self.progress = self.learned.len() as f64 / subjects.len().max(1) as f64;
+            }
+            GoalKind::Persuade(_) => {
+                if mentions(&normalized, REFUSAL_PHRASES) {
+                    self.refusals += 1;
+                    self.progress = (self.progress - 0.2).max(0.0);
+                    if self.refusals >= MAX_REFUSALS {
+                        self.status = GoalStatus::Failed;
+                    }
+                } else if mentions(&normalized, AGREEMENT_PHRASES) {
+                    self.progress = 1.0;
+                } else {
+                    self.progress = (self.progress + 0.1).min(0.9);
+                }
+            }
+            GoalKind::Stall(lines) => {
+                self.progress = (self.turns as f64 / (*lines).max(1) as f64).min(1.0);
+            }
+            GoalKind::Comfort => {
+                if mentions(&normalized, RELIEF_PHRASES) {
+                    self.progress = (self.progress + 0.35).min(1.0);
+                } else if mentions(&normalized, DISTRESS_PHRASES) {
+                    self.progress = (self.progress - 0.1).max(0.0);
+                }
+            }
+        }
+        if self.progress >= 1.0 {
+            self.status = GoalStatus::Achieved;
+        } else if self.turns >= self.max_turns {
+            self.status = GoalStatus::Failed;
+        }
+        self.status
+    }
+
+    /// Builds the prompt directive that steers the NPC towards the goal, or `None` once the
+    /// goal is no longer active.
+    ///
+    /// Once half of the allowed turns have passed with less than half of the progress, the
+    /// directive asks the NPC to be more direct.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation_goal::{ConversationGoal, GoalKind};
+    ///
+    /// let goal = ConversationGoal::new(GoalKind::Stall(4)).with_max_turns(6);
+    /// assert_eq!(
+    ///     goal.directive().unwrap(),
+    ///     "Your aim in this conversation: keep them talking; ask questions and draw things out."
+    /// );
+    /// ```
+    pub fn directive(&self) -> Option<String> {
+        if !self.is_active() {
+            return None;
+        }
+        let aim = match &self.kind {
+            GoalKind::ExtractInformation(subjects) => {
+                let missing: Vec<&str> = subjects
+                    .iter()
+                    .filter(|subject| !self.learned.contains(subject))
+                    .map(|subject| subject.as_str())
+                    .collect();
+                format!(
+                    "find out what they know about {}, without being obvious",
+                    missing.join(", ")
+                )
+            }
+            GoalKind::Persuade(proposal) => format!("convince them to {}", proposal),
+            GoalKind::Stall(_) => {
+                "keep them talking; ask questions and draw things out".to_string()
+            }
+            GoalKind::Comfort => "comfort them and ease their worries".to_string(),
+        };
+        let mut directive = format!("Your aim in this conversation: {}.", aim);
+        if self.turns * 2 >= self.max_turns && self.progress < 0.5 {
+            directive.push_str(" Time is running out: be more direct.");
+        }
+        Some(directive)
+    }
+}
@@ -0,0 +1,220 @@
+//! # Coping Module
+//!
+//! This module decides how an NPC copes with sustained negative emotion. When fear, anger,
+//! sadness, or disgust has dominated an NPC's recent emotional history, it picks one of four
+//! coping strategies: confronting the problem, avoiding it, seeking support from others, or
+//! suppressing its feelings. Which strategy it picks depends on its personality (a calm,
+//! disagreeable NPC confronts, an anxious introvert avoids, a sociable one seeks support, a
+//! disciplined one suppresses) and on the emotion it is coping with.
+//!
+//! The chosen strategy biases the NPC's action scores, adds a goal, and provides a prompt
+//! directive that sets the tone of its dialogue.
+
+use serde::{Deserialize, Serialize};
+
+use crate::emotion_timeline::EmotionTimeline;
+use crate::emotional_response::Emotion;
+use crate::personality::Personality;
+
+/// The negative emotions an NPC copes with.
+const NEGATIVE_EMOTIONS: [Emotion; 4] =
+    [Emotion::Fear, Emotion::Anger, Emotion::Sadness, Emotion::Disgust];
+
+/// Represents a way of coping with negative emotion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CopingStrategy {
+    /// Dealing with the cause head-on.
+    Confront,
+    /// Staying away from the cause.
+    Avoid,
+    /// Turning to others for comfort and help.
+    SeekSupport,
+    /// Hiding the feelings and carrying on.
+    Suppress,
+}
+
+impl CopingStrategy {
+    /// Every strategy, in tie-breaking order.
+    pub const ALL: [CopingStrategy; 4] = [
+        CopingStrategy::Confront,
+        CopingStrategy::Avoid,
+        CopingStrategy::SeekSupport,
+        CopingStrategy::Suppress,
+    ];
+
+    /// Scores how likely a personality is to cope with an emotion using this strategy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::coping::CopingStrategy;
+    /// use athena::emotional_response::Emotion;
+    /// use athena::personality::Personality;
+    ///
+    /// let mut personality = Personality::new();
+    /// personality.set_extraversion(0.9);
+    /// let support = CopingStrategy::SeekSupport.weight(&personality, &Emotion::Sadness);
+    /// let avoid = CopingStrategy::Avoid.weight(&personality, &Emotion::Sadness);
+    /// assert!(support > avoid);
+    /// ```
+    pub fn weight(&self, personality: &Personality, emotion: &Emotion) -> f64 {
+        let (traits, fitting) = match self {
+            CopingStrategy::Confront => (
+                0.5 * (1.0 - personality.neuroticism) + 0.5 * (1.0 - personality.agreeableness),
+                Emotion::Anger,
+            ),
+            CopingStrategy::Avoid => (
+                0.6 * personality.neuroticism + 0.4 * (1.0 - personality.extraversion),
+                Emotion::Fear,
+            ),
+            CopingStrategy::SeekSupport => (
+                0.5 * personality.extraversion + 0.5 * personality.agreeableness,
+                Emotion::Sadness,
+            ),
+            CopingStrategy::Suppress => (
+                0.6 * personality.conscientiousness + 0.4 * (1.0 - personality.openness),
+                Emotion::Disgust,
+            ),
+        };
+        if *emotion == fitting {
+            traits + 0.25
+        } else {
+            traits
+        }
+    }
+
+    /// Chooses the strategy a personality is most likely to use for an emotion.
+    pub fn select(personality: &Personality, emotion: &Emotion) -> CopingStrategy {
+        // Reversed, so ties go to the strategy listed first.
+        CopingStrategy::ALL
+            .into_iter()
+            .rev()
+            .max_by(|a, b| {
+                a.weight(personality, emotion).total_cmp(&b.weight(personality, emotion))
+            })
+            .unwrap_or(CopingStrategy::Suppress)
+    }
+
+    /// Returns a multiplier for an action's utility while coping this way.
+    pub fn action_modifier(&self, action_name: &str) -> f64 {
+        match (self, action_name) {
+            (CopingStrategy::Confront, "Shout" | "Reject" | "Investigate") => 1.5,
+            (CopingStrategy::Confront, "Hide" | "Run") => 0.5,
+            (CopingStrategy::Avoid, "Hide" | "Run" | "Rest") => 1.5,
+            (CopingStrategy::Avoid, "Talk" | "Collaborate" | "Shout") => 0.6,
+            (CopingStrategy::SeekSupport, "Talk" | "Collaborate") => 1.5,
+            (CopingStrategy::SeekSupport, "Hide") => 0.7,
+            (CopingStrategy::Suppress, "Work") => 1.5,
+            (CopingStrategy::Suppress, "Cry" | "Shout") => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns the goal the NPC pursues while coping this way.
+    pub fn goal(&self) -> &'static str {
+        match self {
+            CopingStrategy::Confront => "Confront the cause of my distress",
+            CopingStrategy::Avoid => "Stay away from trouble",
+            CopingStrategy::SeekSupport => "Find someone to confide in",
+            CopingStrategy::Suppress => "Keep busy and carry on",
+        }
+    }
+
+    /// Returns a prompt directive that sets the tone of the NPC's dialogue.
+    pub fn directive(&self) -> &'static str {
+        match self {
+            CopingStrategy::Confront => {
+                "You are facing what troubles you head-on: speak bluntly and directly."
+            }
+            CopingStrategy::Avoid => {
+                "You are avoiding what troubles you: keep answers short and change the subject."
+            }
+            CopingStrategy::SeekSupport => {
+                "You are looking for support: open up and confide in whoever listens."
+            }
+            CopingStrategy::Suppress => {
+                "You are bottling up your feelings: stay composed and insist nothing is wrong."
+            }
+        }
+    }
+}
+
+/// Represents an NPC's coping layer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Coping {
+    /// How far back negative emotion is considered, in seconds of game time.
+    pub window: f64,
+    /// The fraction of the window negative emotion must fill before the NPC starts coping.
+    pub threshold: f64,
+    /// The strategy in use, if the NPC is coping.
+    strategy: Option<CopingStrategy>,
+}
+
+impl Default for Coping {
+    /// Coping starts once negative emotion filled half of the last two hours.
+    fn default() -> Self {
+        Coping {
+            window: 7_200.0,
+            threshold: 0.5,
+            strategy: None,
+        }
+    }
+}
+
+impl Coping {
+    /// Gets the strategy in use, or `None` if the NPC is not coping.
+    pub fn get_strategy(&self) -> Option<CopingStrategy> {
+        self.strategy
+    }
+
+    /// Re-evaluates whether and how the NPC copes, from its recent emotional history.
+    ///
+    /// The NPC copes with the negative emotion it felt longest, as long as negative emotions
+    /// together filled at least `threshold` of the window. A strategy is kept while coping
+    /// continues, so the NPC does not flip between strategies.
+    ///
+    /// # Returns
+    ///
+    /// The strategy in use after the evaluation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::coping::{Coping, CopingStrategy};
+    /// use athena::emotion_timeline::EmotionTimeline;
+    /// use athena::emotional_response::Emotion;
+    /// use athena::personality::Personality;
+    ///
+    /// let mut personality = Personality::new();
+    /// personality.set_neuroticism(0.9);
+    /// let mut timeline = EmotionTimeline::default();
+    /// timeline.record(0.0, Emotion::Fear, 0.8);
+    /// let mut coping = Coping::default();
+    /// assert_eq!(coping.evaluate(&personality, &timeline, 1_800.0), None);
+    /// assert_eq!(coping.evaluate(&personality, &timeline, 5_400.0), Some(CopingStrategy::Avoid));
+    /// ```
+    pub fn evaluate(
+        &mut self,
+        personality: &Personality,
+        timeline: &EmotionTimeline,
+        now: f64,
+    ) -> Option<CopingStrategy> {
+        let times = timeline.times_in(now, self.window);
+        let durations: Vec<(&Emotion, f64)> = NEGATIVE_EMOTIONS
+            .iter()
+            .map(|emotion| (emotion, times.get(emotion).copied().unwrap_or(0.0)))
+            .collect();
+        let total: f64 = durations.iter().map(|(_, duration)| duration).sum();
+        if total < self.window * self.threshold {
+            self.strategy = None;
+        } else if self.strategy.is_none() {
+            let longest = durations
+                .iter()
+                .rev()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map_or(&Emotion::Fear, |(emotion, _)| *emotion);
+            self.strategy = Some(CopingStrategy::select(personality, longest));
+        }
+        self.strategy
+    }
+}
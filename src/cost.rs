@@ -0,0 +1,367 @@
+//! # Cost Module
+//!
+//! This module shows where the language model budget goes. Each response's token usage is
+//! recorded with the NPC, scene, and session it was for, and priced with a table of per-model
+//! token prices. The records can then be totalled and grouped by NPC, scene, or session, read
+//! programmatically, or exported as CSV for producers' spreadsheets.
+//!
+//! A report keeps a capped number of individual records. Older records are folded into totals
+//! per NPC, scene, session, and model, so reports stay exact over a long session while memory
+//! stays bounded.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_generation::Usage;
+
+/// Represents the price of a model's tokens, per million tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+impl ModelPrice {
+    /// Creates a new price.
+    pub fn new(prompt_per_million: f64, completion_per_million: f64) -> Self {
+        ModelPrice {
+            prompt_per_million,
+            completion_per_million,
+        }
+    }
+}
+
+/// Represents the token prices of models.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPrice>,
+    /// The price of models without a price of their own.
+    pub default: ModelPrice,
+}
+
+impl PriceTable {
+    /// Creates a new table in which every model is free.
+    pub fn new() -> Self {
+        PriceTable::default()
+    }
+
+    /// Sets the price of a model.
+    pub fn set_price(&mut self, model: &str, price: ModelPrice) {
+        self.prices.insert(model.to_string(), price);
+    }
+
+    /// Gets the price of a model.
+    pub fn get_price(&self, model: &str) -> ModelPrice {
+        self.prices.get(model).copied().unwrap_or(self.default)
+    }
+
+    /// Estimates the cost of a request.
+    pub fn cost(&self, model: &str, prompt_tokens: usize, completion_tokens: usize) -> f64 {
+        let price = self.get_price(model);
+        (prompt_tokens as f64 * price.prompt_per_million
+            + completion_tokens as f64 * price.completion_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Represents the token usage of one request and what it was for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub npc_id: String,
+    /// A tag for the scene or quest the request was made in (e.g. "tavern_intro").
+    pub scene: String,
+    pub session: String,
+    pub model: String,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl UsageRecord {
+    /// Creates a record from a response's usage.
+    pub fn new(npc_id: &str, scene: &str, session: &str, model: &str, usage: &Usage) -> Self {
+        UsageRecord {
+            npc_id: npc_id.to_string(),
+            scene: scene.to_string(),
+            session: session.to_string(),
+            model: model.to_string(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+/// Represents what records are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CostGrouping {
+    Npc,
+    Scene,
+    Session,
+    Model,
+}
+
+impl CostGrouping {
+    /// Gets the key of a record in the grouping.
+    fn key<'a>(&self, record: &'a UsageRecord) -> &'a str {
+        match self {
+            CostGrouping::Npc => &record.npc_id,
+            CostGrouping::Scene => &record.scene,
+            CostGrouping::Session => &record.session,
+            CostGrouping::Model => &record.model,
+        }
+    }
+
+    /// Gets the name of the grouping's CSV column.
+    fn column(&self) -> &'static str {
+        match self {
+            CostGrouping::Npc => "npc",
+            CostGrouping::Scene => "scene",
+            CostGrouping::Session => "session",
+            CostGrouping::Model => "model",
+        }
+    }
+}
+
+/// The number of individual records a report keeps by default.
+const DEFAULT_MAX_RECORDS: usize = 10_000;
+
+/// Represents the totals of a set of requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostTotals {
+    pub requests: usize,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    /// The estimated cost, in the currency of the price table.
+    pub cost: f64,
+}
+
+impl CostTotals {
+    /// Returns the number of prompt and completion tokens.
+    pub fn total_tokens(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Adds other totals to these.
+    fn add(&mut self, other: &CostTotals) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Gets the maximum number of records of a report loaded without one.
+fn default_max_records() -> usize {
+    DEFAULT_MAX_RECORDS
+}
+
+/// Collects token usage and reports it grouped by NPC, scene, session, or model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostReport {
+    pub prices: PriceTable,
+    records: Vec<UsageRecord>,
+    /// The totals of records beyond the cap, with the record whose NPC, scene, session, and
+    /// model they were for. Their tokens are in the totals only.
+    #[serde(default)]
+    folded: Vec<(UsageRecord, CostTotals)>,
+    /// How many individual records are kept.
+    #[serde(default = "default_max_records")]
+    max_records: usize,
+}
+
+impl Default for CostReport {
+    fn default() -> Self {
+        CostReport::new(PriceTable::default())
+    }
+}
+
+impl CostReport {
+    /// Creates a new, empty report priced with a table.
+    pub fn new(prices: PriceTable) -> Self {
+        CostReport {
+            prices,
+            records: Vec::new(),
+            folded: Vec::new(),
+            max_records: DEFAULT_MAX_RECORDS,
+        }
+    }
+
+    /// Sets how many individual records the report keeps; older ones are folded into totals.
+    /// Defaults to 10,000.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::cost::{CostGrouping, CostReport, PriceTable, UsageRecord};
+    /// use athena::dialogue_generation::Usage;
+    ///
+    /// let mut report = CostReport::new(PriceTable::new());
+    /// report.set_max_records(2);
+    /// let usage = Usage { prompt_tokens: 100, completion_tokens: 20, ..Usage::default() };
+    /// for npc in ["brom", "brom", "mira", "brom"] {
+    ///     report.record(UsageRecord::new(npc, "forge", "s1", "small", &usage));
+    /// }
+    /// assert_eq!(report.records().len(), 2);
+    /// assert_eq!(report.totals().requests, 4);
+    /// assert_eq!(report.grouped(CostGrouping::Npc)["brom"].prompt_tokens, 300);
+    /// ```
+    pub fn set_max_records(&mut self, max_records: usize) {
+        self.max_records = max_records;
+        self.fold_excess();
+    }
+
+    /// Records the usage of a request.
+    pub fn record(&mut self, record: UsageRecord) {
+        self.records.push(record);
+        self.fold_excess();
+    }
+
+    /// Folds the oldest records beyond the cap into the totals of their NPC, scene, session,
+    /// and model. They are priced as they are folded.
+    fn fold_excess(&mut self) {
+        let excess = self.records.len().saturating_sub(self.max_records);
+        for record in self.records.drain(..excess).collect::<Vec<_>>() {
+            let totals = self.price(&record);
+            let same = self.folded.iter_mut().find(|(folded, _)| {
+                folded.npc_id == record.npc_id
+                    && folded.scene == record.scene
+                    && folded.session == record.session
+                    && folded.model == record.model
+            });
+            match same {
+                Some((_, folded)) => folded.add(&totals),
+                None => {
+                    let key = UsageRecord {
+                        prompt_tokens: 0,
+                        completion_tokens: 0,
+                        ..record
+                    };
+                    self.folded.push((key, totals));
+                }
+            }
+        }
+    }
+
+    /// Gets the individual records kept, in the order they were recorded. Records beyond the
+    /// cap count towards the totals, but are not listed.
+    pub fn records(&self) -> &[UsageRecord] {
+        &self.records
+    }
+
+    /// Clears the recorded usage, e.g. at the start of a new reporting period.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.folded.clear();
+    }
+
+    /// Totals a single record.
+    fn price(&self, record: &UsageRecord) -> CostTotals {
+        CostTotals {
+            requests: 1,
+            prompt_tokens: record.prompt_tokens,
+            completion_tokens: record.completion_tokens,
+            cost: self.prices.cost(&record.model, record.prompt_tokens, record.completion_tokens),
+        }
+    }
+
+    /// Iterates over the totals of every record, kept or folded, with the record they are for.
+    fn entries(&self) -> impl Iterator<Item = (&UsageRecord, CostTotals)> {
+        let folded = self.folded.iter().map(|(record, totals)| (record, *totals));
+        folded.chain(self.records.iter().map(|record| (record, self.price(record))))
+    }
+
+    /// Totals every record.
+    pub fn totals(&self) -> CostTotals {
+        self.entries().fold(CostTotals::default(), |mut totals, (_, entry)| {
+            totals.add(&entry);
+            totals
+        })
+    }
+
+    /// Totals the records of each group, keyed and sorted by the group.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::cost::{CostGrouping, CostReport, ModelPrice, PriceTable, UsageRecord};
+    /// use athena::dialogue_generation::Usage;
+    ///
+    /// let mut prices = PriceTable::new();
+    /// prices.set_price("large", ModelPrice::new(0.5, 1.5));
+    /// let mut report = CostReport::new(prices);
+    /// let usage = Usage { prompt_tokens: 1_000_000, completion_tokens: 1_000_000, ..Usage::default() };
+    /// report.record(UsageRecord::new("brom", "forge", "s1", "large", &usage));
+    /// report.record(UsageRecord::new("brom", "tavern", "s1", "large", &usage));
+    /// report.record(UsageRecord::new("mira", "tavern", "s1", "small", &usage));
+    ///
+    /// let by_npc = report.grouped(CostGrouping::Npc);
+    /// assert_eq!(by_npc["brom"].requests, 2);
+    /// assert_eq!(by_npc["brom"].cost, 4.0);
+    /// assert_eq!(by_npc["mira"].cost, 0.0);
+    /// assert_eq!(report.grouped(CostGrouping::Scene)["tavern"].total_tokens(), 4_000_000);
+    /// ```
+    pub fn grouped(&self, grouping: CostGrouping) -> BTreeMap<String, CostTotals> {
+        let mut groups: BTreeMap<String, CostTotals> = BTreeMap::new();
+        for (record, totals) in self.entries() {
+            groups.entry(grouping.key(record).to_string()).or_default().add(&totals);
+        }
+        groups
+    }
+
+    /// Writes the totals of each group as CSV, with a header row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::cost::{CostGrouping, CostReport, PriceTable, UsageRecord};
+    /// use athena::dialogue_generation::Usage;
+    ///
+    /// let mut report = CostReport::new(PriceTable::new());
+    /// let usage = Usage { prompt_tokens: 120, completion_tokens: 30, ..Usage::default() };
+    /// report.record(UsageRecord::new("brom", "forge, night", "s1", "small", &usage));
+    /// assert_eq!(
+    ///     report.to_csv(CostGrouping::Scene),
+    ///     "scene,requests,prompt_tokens,completion_tokens,total_tokens,cost\n\
+    ///      \"forge, night\",1,120,30,150,0.000000\n"
+    /// );
+    /// ```
+    pub fn to_csv(&self, grouping: CostGrouping) -> String {
+        let mut csv = format!(
+            "{},requests,prompt_tokens,completion_tokens,total_tokens,cost\n",
+            grouping.column()
+        );
+        for (key, totals) in self.grouped(grouping) {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{:.6}\n",
+                csv_field(&key),
+                totals.requests,
+                totals.prompt_tokens,
+                totals.completion_tokens,
+                totals.total_tokens(),
+                totals.cost
+            ));
+        }
+        csv
+    }
+
+    /// Saves the totals of each group to a CSV file.
+    pub fn save_csv(
+        &self,
+        path: impl AsRef<Path>,
+        grouping: CostGrouping,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.to_csv(grouping))?;
+        Ok(())
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote, or line break.
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,408 @@
+//! # Crime Module
+//!
+//! This module implements the flow from a crime being committed to an NPC reacting to it. A
+//! witness records the crime in its knowledge graph along with how it learned about it (its
+//! provenance), appraises it (anger at the culprit, or fear if the crime was violent and the
+//! witness is anxious), remembers it against the culprit's reputation, and answers with an
+//! accusation line and, if the law calls for it, an action to alert the guards. Witnesses then
+//! spread what they know through gossip, with confidence dropping at every retelling.
+//!
+//! Which acts are crimes, how severe they are, and whether guards get involved are defined by
+//! a `LawSystem`, so each game can plug in its own laws.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::NpcAgent;
+use crate::emotional_response::Emotion;
+use crate::knowledge_graph::{KnowledgeGraph, Relationship};
+use crate::reputation::DeedCategory;
+
+/// The prefix of the knowledge graph relation types used for crimes (e.g. "crime:theft").
+const CRIME_RELATION_PREFIX: &str = "crime:";
+
+/// How much confidence is kept each time a crime is retold through gossip.
+const GOSSIP_CONFIDENCE: f64 = 0.7;
+
+/// Gossip below this confidence is not passed on.
+const MIN_GOSSIP_CONFIDENCE: f64 = 0.3;
+
+/// The action witnesses take to alert the guards.
+pub const ALERT_GUARDS_ACTION: &str = "AlertGuards";
+
+/// Represents a crime that was committed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrimeEvent {
+    /// A unique ID for the event.
+    pub id: String,
+    /// The kind of crime, as named by the law system (e.g. "theft").
+    pub crime: String,
+    /// Who committed the crime.
+    pub perpetrator: String,
+    /// Who the crime was committed against, if anyone.
+    pub victim: Option<String>,
+    pub location: String,
+    /// When the crime was committed, in seconds of game time.
+    pub game_time: f64,
+}
+
+/// Represents how an NPC learned about a crime.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Provenance {
+    /// The NPC saw the crime happen.
+    Witnessed,
+    /// Another NPC told the NPC about the crime.
+    Gossip { from: String },
+}
+
+/// Represents a crime an NPC knows about.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownCrime {
+    pub event: CrimeEvent,
+    pub provenance: Provenance,
+    /// How sure the NPC is that the crime happened as it heard (between 0.0 and 1.0).
+    pub confidence: f64,
+}
+
+/// Represents how one kind of crime is treated by the law.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrimeRule {
+    /// How serious the crime is (e.g. 1.0 for a theft, 5.0 for a murder).
+    pub severity: f64,
+    /// The deed the crime counts as for the perpetrator's reputation.
+    pub deed: DeedCategory,
+    /// Whether witnesses alert the guards.
+    pub report_to_guards: bool,
+    /// The accusation witnesses shout; `{perpetrator}`, `{victim}` and `{location}` are filled in.
+    pub accusation: String,
+}
+
+impl CrimeRule {
+    /// Creates a new crime rule.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::crime::CrimeRule;
+    /// use athena::reputation::DeedCategory;
+    /// let rule = CrimeRule::new(0.5, DeedCategory::Deception, false, "You're not allowed here!");
+    /// ```
+    pub fn new(
+        severity: f64,
+        deed: DeedCategory,
+        report_to_guards: bool,
+        accusation: &str,
+    ) -> Self {
+        CrimeRule {
+            severity,
+            deed,
+            report_to_guards,
+            accusation: accusation.to_string(),
+        }
+    }
+}
+
+/// Defines which acts are crimes and how they are treated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LawSystem {
+    rules: HashMap<String, CrimeRule>,
+}
+
+impl Default for LawSystem {
+    fn default() -> Self {
+        let mut law = LawSystem::new();
+        law.set_rule(
+            "theft",
+            CrimeRule::new(1.0, DeedCategory::Theft, true, "Thief! Stop, thief!"),
+        );
+        law.set_rule(
+            "assault",
+            CrimeRule::new(
+                2.0,
+                DeedCategory::Violence,
+                true,
+                "Help! {perpetrator} attacked {victim}!",
+            ),
+        );
+        law.set_rule(
+            "murder",
+            CrimeRule::new(5.0, DeedCategory::Violence, true, "Murderer! Guards, guards!"),
+        );
+        law.set_rule(
+            "vandalism",
+            CrimeRule::new(0.5, DeedCategory::Violence, false, "Hey! Leave that alone!"),
+        );
+        law
+    }
+}
+
+impl LawSystem {
+    /// Creates a law system in which nothing is a crime.
+    pub fn new() -> Self {
+        LawSystem {
+            rules: HashMap::new(),
+        }
+    }
+
+    /// Sets the rule for a kind of crime.
+    pub fn set_rule(&mut self, crime: &str, rule: CrimeRule) {
+        self.rules.insert(crime.to_string(), rule);
+    }
+
+    /// Gets the rule for a kind of crime, or `None` if the act is not a crime.
+    pub fn get_rule(&self, crime: &str) -> Option<&CrimeRule> {
+        self.rules.get(crime)
+    }
+}
+
+/// Represents a witness's reaction to a crime.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WitnessReaction {
+    /// The emotion the crime provoked.
+    pub emotion: Emotion,
+    /// The accusation the witness shouts.
+    pub accusation: String,
+    /// Actions the witness takes (e.g. alerting the guards).
+    pub actions: Vec<String>,
+}
+
+/// Records a crime in a knowledge graph, replacing any earlier record of the same event.
+fn record_crime(knowledge: &mut KnowledgeGraph, known: &KnownCrime) {
+    let event = &known.event;
+    let relation_type = format!("{}{}", CRIME_RELATION_PREFIX, event.crime);
+    knowledge.remove_relationship(&event.perpetrator, &event.id, &relation_type);
+    let mut properties = HashMap::new();
+    if let Some(victim) = &event.victim {
+        properties.insert("victim".to_string(), victim.clone());
+    }
+    properties.insert("location".to_string(), event.location.clone());
+    properties.insert("game_time".to_string(), event.game_time.to_string());
+    properties.insert("confidence".to_string(), known.confidence.to_string());
+    let source = match &known.provenance {
+        Provenance::Witnessed => "witnessed".to_string(),
+        Provenance::Gossip { from } => format!("gossip:{}", from),
+    };
+    properties.insert("source".to_string(), source);
+    knowledge.add_relationship(Relationship::new(
+        event.perpetrator.as_str(),
+        event.id.as_str(),
+        relation_type,
+        properties,
+    ));
+}
+
+/// Lists the crimes recorded in a knowledge graph.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::crime::{self, CrimeEvent, LawSystem, Provenance};
+///
+/// let mut witness = NpcAgent::new("baker", vec![]);
+/// let event = CrimeEvent {
+///     id: "crime_1".to_string(),
+///     crime: "theft".to_string(),
+///     perpetrator: "alice".to_string(),
+///     victim: Some("baker".to_string()),
+///     location: "market".to_string(),
+///     game_time: 0.0,
+/// };
+/// crime::witness(&mut witness, &event, &LawSystem::default());
+/// let known = crime::known_crimes(&witness.knowledge);
+/// assert_eq!(known[0].event, event);
+/// assert_eq!(known[0].provenance, Provenance::Witnessed);
+/// ```
+pub fn known_crimes(knowledge: &KnowledgeGraph) -> Vec<KnownCrime> {
+    let mut crimes: Vec<KnownCrime> = knowledge
+        .relationships()
+        .filter_map(|relationship| {
+            let crime = relationship.relation_type.strip_prefix(CRIME_RELATION_PREFIX)?;
+            let property = |key: &str| relationship.properties.get(key);
+            let provenance = match property("source")?.strip_prefix("gossip:") {
+                Some(from) => Provenance::Gossip { from: from.to_string() },
+                None => Provenance::Witnessed,
+            };
+            Some(KnownCrime {
+                event: CrimeEvent {
+                    id: relationship.target.to_string(),
+                    crime: crime.to_string(),
+                    perpetrator: relationship.source.to_string(),
+                    victim: property("victim").cloned(),
+                    location: property("location").cloned().unwrap_or_default(),
+                    game_time: property("game_time")?.parse().ok()?,
+                },
+                provenance,
+                confidence: property("confidence")?.parse().ok()?,
+            })
+        })
+        .collect();
+    crimes.sort_by(|a, b| a.event.id.cmp(&b.event.id));
+    crimes
+}
+
+/// Has an NPC witness a crime.
+///
+/// The crime is recorded in the witness's knowledge, counted against the perpetrator's
+/// reputation, and appraised: violent crimes frighten anxious witnesses (neuroticism of 0.5
+/// or more), everything else angers them. The accusation is queued as dialogue.
+///
+/// # Arguments
+///
+/// * `witness` - The NPC who saw the crime.
+/// * `event` - The crime.
+/// * `law` - The law system that defines the crime.
+///
+/// # Returns
+///
+/// The witness's reaction, or `None` if the act is not a crime under the law system.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::crime::{self, CrimeEvent, LawSystem, ALERT_GUARDS_ACTION};
+/// use athena::emotional_response::Emotion;
+///
+/// let mut witness = NpcAgent::new("baker", vec![]);
+/// witness.personality.set_neuroticism(0.8);
+/// let event = CrimeEvent {
+///     id: "crime_2".to_string(),
+///     crime: "assault".to_string(),
+///     perpetrator: "alice".to_string(),
+///     victim: Some("bob".to_string()),
+///     location: "market".to_string(),
+///     game_time: 0.0,
+/// };
+/// let reaction = crime::witness(&mut witness, &event, &LawSystem::default()).unwrap();
+/// assert_eq!(reaction.emotion, Emotion::Fear);
+/// assert_eq!(reaction.accusation, "Help! alice attacked bob!");
+/// assert_eq!(reaction.actions, vec![ALERT_GUARDS_ACTION.to_string()]);
+/// assert!(witness.disposition_towards("alice").fear > 0.5);
+/// ```
+pub fn witness(
+    witness: &mut NpcAgent,
+    event: &CrimeEvent,
+    law: &LawSystem,
+) -> Option<WitnessReaction> {
+    let rule = law.get_rule(&event.crime)?;
+    record_crime(
+        &mut witness.knowledge,
+        &KnownCrime {
+            event: event.clone(),
+            provenance: Provenance::Witnessed,
+            confidence: 1.0,
+        },
+    );
+    witness.record_deed(
+        &event.perpetrator,
+        rule.deed,
+        rule.severity,
+        &format!("{} at {}", event.crime, event.location),
+    );
+
+    let frightened = rule.deed == DeedCategory::Violence && witness.personality.neuroticism >= 0.5;
+    let emotion = if frightened {
+        Emotion::Fear
+    } else {
+        Emotion::Anger
+    };
+    let source = format!("saw {} commit {}", event.perpetrator, event.crime);
+    witness.appraise(emotion.clone(), (rule.severity / 2.0).clamp(0.3, 1.0), &source);
+    witness.emotions.record_memory(&event.perpetrator, emotion.clone());
+
+    let accusation = rule
+        .accusation
+        .replace("{perpetrator}", &event.perpetrator)
+        .replace("{victim}", event.victim.as_deref().unwrap_or("someone"))
+        .replace("{location}", &event.location);
+    witness.queue_dialogue(&accusation);
+    let actions = if rule.report_to_guards {
+        vec![ALERT_GUARDS_ACTION.to_string()]
+    } else {
+        Vec::new()
+    };
+    Some(WitnessReaction {
+        emotion,
+        accusation,
+        actions,
+    })
+}
+
+/// Has one NPC tell another about the crimes it knows.
+///
+/// The listener records each crime it did not already know about, attributed to the teller and
+/// with reduced confidence, and counts it against the perpetrator's reputation in proportion to
+/// that confidence. Crimes the teller is too unsure about are not passed on.
+///
+/// # Arguments
+///
+/// * `teller` - The NPC spreading the news.
+/// * `listener` - The NPC hearing it.
+/// * `law` - The law system that defines the crimes.
+///
+/// # Returns
+///
+/// The number of crimes the listener learned about.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::crime::{self, CrimeEvent, LawSystem, Provenance};
+///
+/// let law = LawSystem::default();
+/// let mut baker = NpcAgent::new("baker", vec![]);
+/// let mut miller = NpcAgent::new("miller", vec![]);
+/// let event = CrimeEvent {
+///     id: "crime_3".to_string(),
+///     crime: "theft".to_string(),
+///     perpetrator: "alice".to_string(),
+///     victim: None,
+///     location: "market".to_string(),
+///     game_time: 0.0,
+/// };
+/// crime::witness(&mut baker, &event, &law);
+/// assert_eq!(crime::gossip(&baker, &mut miller, &law), 1);
+/// assert_eq!(crime::gossip(&baker, &mut miller, &law), 0);
+///
+/// let heard = &crime::known_crimes(&miller.knowledge)[0];
+/// assert_eq!(heard.provenance, Provenance::Gossip { from: "baker".to_string() });
+/// assert!(heard.confidence < 1.0);
+/// ```
+pub fn gossip(teller: &NpcAgent, listener: &mut NpcAgent, law: &LawSystem) -> usize {
+    let known_by_listener: Vec<String> = known_crimes(&listener.knowledge)
+        .into_iter()
+        .map(|known| known.event.id)
+        .collect();
+    let mut learned = 0;
+    for known in known_crimes(&teller.knowledge) {
+        let confidence = known.confidence * GOSSIP_CONFIDENCE;
+        if confidence < MIN_GOSSIP_CONFIDENCE || known_by_listener.contains(&known.event.id) {
+            continue;
+        }
+        let Some(rule) = law.get_rule(&known.event.crime) else {
+            continue;
+        };
+        let event = known.event;
+        listener.record_deed(
+            &event.perpetrator,
+            rule.deed,
+            rule.severity * confidence,
+            &format!("heard about {} at {}", event.crime, event.location),
+        );
+        record_crime(
+            &mut listener.knowledge,
+            &KnownCrime {
+                event,
+                provenance: Provenance::Gossip {
+                    from: teller.id.to_string(),
+                },
+                confidence,
+            },
+        );
+        learned += 1;
+    }
+    learned
+}
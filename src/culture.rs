@@ -0,0 +1,178 @@
+//! # Culture Module
+//!
+//! This module describes the cultural background of an NPC: what its people value, how they
+//! speak, how they name their children, how they greet each other, and what their stories are
+//! about. A `Culture` is shared by every NPC from the same region. It is rendered into prompts,
+//! weighs how strongly NPCs react to deeds, and flavours quests, so different parts of the world
+//! feel distinct.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::morality::MoralValues;
+use crate::reputation::DeedCategory;
+
+/// Represents how a culture composes names.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamingConvention {
+    pub given_names: Vec<String>,
+    pub family_names: Vec<String>,
+    /// How a full name is composed; `{given}` and `{family}` are filled in.
+    pub pattern: String,
+}
+
+impl NamingConvention {
+    /// Generates a name deterministically from a seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::culture::NamingConvention;
+    /// let naming = NamingConvention {
+    ///     given_names: vec!["Sigrun".to_string(), "Bjorn".to_string()],
+    ///     family_names: vec!["Ironside".to_string()],
+    ///     pattern: "{given} {family}".to_string(),
+    /// };
+    /// assert_eq!(naming.name(1), "Bjorn Ironside");
+    /// ```
+    pub fn name(&self, seed: usize) -> String {
+        let pick = |names: &[String], seed: usize| {
+            names.get(seed % names.len().max(1)).cloned().unwrap_or_default()
+        };
+        self.pattern
+            .replace("{given}", &pick(&self.given_names, seed))
+            .replace("{family}", &pick(&self.family_names, seed / self.given_names.len().max(1)))
+            .trim()
+            .to_string()
+    }
+}
+
+/// Represents the cultural background shared by NPCs of a region.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Culture {
+    pub name: String,
+    /// How much the culture cares about each value (between 0.0 and 1.0). Values that weigh
+    /// deeds are "peace", "honesty", "charity", and "honor".
+    pub values: BTreeMap<String, f64>,
+    /// Hints about how its people speak (e.g. "uses nautical slang").
+    pub dialect_hints: Vec<String>,
+    pub naming: NamingConvention,
+    /// Customary greetings.
+    pub greetings: Vec<String>,
+    /// Themes that colour quests from this culture (e.g. "ancestral honor").
+    pub quest_motifs: Vec<String>,
+    /// The title used for respected strangers without a title of their own (e.g. "Ser").
+    #[serde(default)]
+    pub honorific: Option<String>,
+    /// Insults used to address enemies (e.g. "oathbreaker").
+    #[serde(default)]
+    pub epithets: Vec<String>,
+    /// The moral values its people are raised with, if the culture specifies them.
+    #[serde(default)]
+    pub morals: Option<MoralValues>,
+}
+
+impl Culture {
+    /// Creates a new culture with a name and nothing else.
+    pub fn new(name: &str) -> Self {
+        Culture {
+            name: name.to_string(),
+            ..Culture::default()
+        }
+    }
+
+    /// Sets how much the culture cares about a value.
+    pub fn set_value(&mut self, value: &str, weight: f64) {
+        self.values.insert(value.to_string(), weight.clamp(0.0, 1.0));
+    }
+
+    /// Gets how much the culture cares about a value, or 0.5 if it is not specified.
+    pub fn get_value(&self, value: &str) -> f64 {
+        self.values.get(value).copied().unwrap_or(0.5)
+    }
+
+    /// Computes how strongly NPCs of this culture react to a kind of deed.
+    ///
+    /// # Returns
+    ///
+    /// A multiplier for the deed's magnitude: 1.0 for a culture that holds the related value at
+    /// 0.5, up to 1.5 for one that holds it at 1.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::culture::Culture;
+    /// use athena::reputation::DeedCategory;
+    ///
+    /// let mut culture = Culture::new("Northmen");
+    /// culture.set_value("honor", 1.0);
+    /// assert_eq!(culture.appraisal_weight(DeedCategory::Disrespect), 1.5);
+    /// assert_eq!(culture.appraisal_weight(DeedCategory::Theft), 1.0);
+    /// ```
+    pub fn appraisal_weight(&self, category: DeedCategory) -> f64 {
+        let value = match category {
+            DeedCategory::Violence => "peace",
+            DeedCategory::Theft | DeedCategory::Deception => "honesty",
+            DeedCategory::Generosity | DeedCategory::Helpfulness => "charity",
+            DeedCategory::Disrespect => "honor",
+        };
+        0.5 + self.get_value(value)
+    }
+
+    /// Renders the culture as a prompt segment.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::culture::Culture;
+    ///
+    /// let mut culture = Culture::new("Northmen");
+    /// culture.set_value("honor", 0.9);
+    /// culture.dialect_hints.push("short, blunt sentences".to_string());
+    /// let text = culture.render();
+    /// assert!(text.contains("You are of the Northmen."));
+    /// assert!(text.contains("honor"));
+    /// ```
+    pub fn render(&self) -> String {
+        let mut text = format!("You are of the {}.", self.name);
+        let cherished: Vec<&str> = self
+            .values
+            .iter()
+            .filter(|(_, weight)| **weight >= 0.7)
+            .map(|(value, _)| value.as_str())
+            .collect();
+        if !cherished.is_empty() {
+            text.push_str(&format!("\nYour people cherish: {}.", cherished.join(", ")));
+        }
+        if !self.dialect_hints.is_empty() {
+            text.push_str(&format!("\nDialect: {}.", self.dialect_hints.join("; ")));
+        }
+        if !self.greetings.is_empty() {
+            text.push_str(&format!("\nCustomary greetings: {}.", self.greetings.join(" / ")));
+        }
+        text
+    }
+
+    /// Adds the culture's motifs to a quest description.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::culture::Culture;
+    ///
+    /// let mut culture = Culture::new("Northmen");
+    /// culture.quest_motifs.push("ancestral honor".to_string());
+    /// assert_eq!(
+    ///     culture.flavor_quest("Recover the stolen axe."),
+    ///     "Recover the stolen axe. Themes: ancestral honor."
+    /// );
+    /// ```
+    pub fn flavor_quest(&self, description: &str) -> String {
+        if self.quest_motifs.is_empty() {
+            description.to_string()
+        } else {
+            format!("{} Themes: {}.", description, self.quest_motifs.join(", "))
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! # Definition Module
+//!
+//! This module describes NPCs as data, so designers can author them in JSON files instead of
+//! code. An `NpcDefinition` lists the persona, personality, actions, goals, schedule, starting
+//! knowledge, culture, and emotion palette of an NPC, and builds a ready-to-run `NpcAgent` from
+//! them. Definitions can be linted with `validate` before they are built (see the `validation`
+//! module), and stock ones can be loaded from the `archetypes` library (with the `archetypes`
+//! feature).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::adaptive_intelligence::Action;
+use crate::agent::NpcAgent;
+use crate::culture::Culture;
+use crate::emotional_response::EmotionPalette;
+use crate::knowledge_graph::{Entity, Relationship};
+use crate::morality::MoralValues;
+use crate::personality::Personality;
+use crate::prompt::PersonaCard;
+use crate::questionnaire::PersonalitySketch;
+use crate::schedule::ScheduleBlock;
+use crate::validation::{self, ValidationReport, ValidationRules};
+
+/// Represents an authored NPC.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NpcDefinition {
+    /// A unique ID for the NPC.
+    pub id: String,
+    /// The kind of NPC (e.g. "innkeeper"), which selects its custom appraisal rules.
+    #[serde(default)]
+    pub archetype: Option<String>,
+    #[serde(default)]
+    pub persona: PersonaCard,
+    #[serde(default)]
+    pub personality: Personality,
+    /// Questionnaire answers and tags describing the personality; if present, the personality
+    /// is derived from them instead.
+    #[serde(default)]
+    pub personality_sketch: Option<PersonalitySketch>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    #[serde(default)]
+    pub goals: Vec<String>,
+    /// The daily routine.
+    #[serde(default)]
+    pub schedule: Vec<ScheduleBlock>,
+    /// Starting knowledge as `(source, relation, target)` triples.
+    #[serde(default)]
+    pub facts: Vec<(String, String, String)>,
+    #[serde(default)]
+    pub culture: Option<Culture>,
+    /// The NPC's own moral values; if missing, those of its culture are used.
+    #[serde(default)]
+    pub morals: Option<MoralValues>,
+    /// The emotions the NPC can feel; if missing, Plutchik's primaries.
+    #[serde(default)]
+    pub palette: Option<EmotionPalette>,
+}
+
+impl NpcDefinition {
+    /// Parses a definition from JSON.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::definition::NpcDefinition;
+    ///
+    /// let definition = NpcDefinition::from_json(r#"{
+    ///     "id": "brom",
+    ///     "persona": { "name": "Brom", "description": "The village blacksmith.",
+    ///                  "speaking_style": "gruff", "facts": [] },
+    ///     "personality": { "openness": 0.3, "conscientiousness": 0.8, "extraversion": 0.4,
+    ///                      "agreeableness": 0.6, "neuroticism": 0.2 },
+    ///     "facts": [["brom", "lives_in", "riverside"]]
+    /// }"#).unwrap();
+    /// let agent = definition.build();
+    /// assert_eq!(agent.id, "brom");
+    /// assert_eq!(agent.personality.conscientiousness, 0.8);
+    /// assert_eq!(agent.knowledge.relationship_count(), 1);
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Loads a definition from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Validates the definition against the default rules (see `validation::validate`).
+    pub fn validate(&self) -> ValidationReport {
+        validation::validate(self, &ValidationRules::default())
+    }
+
+    /// Builds an agent from the definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::definition::NpcDefinition;
+    ///
+    /// let definition = NpcDefinition::from_json(r#"{
+    ///     "id": "orla",
+    ///     "personality_sketch": { "tags": ["bookish", "hot-tempered"] }
+    /// }"#).unwrap();
+    /// let agent = definition.build();
+    /// assert_eq!(agent.personality.openness, 0.75);
+    /// assert_eq!(agent.personality.neuroticism, 0.8);
+    /// ```
+    pub fn build(&self) -> NpcAgent {
+        let mut agent = NpcAgent::new(&self.id, self.actions.clone());
+        match &self.personality_sketch {
+            Some(sketch) => agent.personality = sketch.personality(),
+            None => {
+                let personality = &mut agent.personality;
+                personality.set_openness(self.personality.openness);
+                personality.set_conscientiousness(self.personality.conscientiousness);
+                personality.set_extraversion(self.personality.extraversion);
+                personality.set_agreeableness(self.personality.agreeableness);
+                personality.set_neuroticism(self.personality.neuroticism);
+            }
+        }
+        agent.goals = self.goals.clone();
+        agent.archetype = self.archetype.clone();
+        for block in &self.schedule {
+            agent.schedule.add_block(block.clone());
+        }
+        agent.culture = self.culture.clone();
+        let culture_morals = self.culture.as_ref().and_then(|culture| culture.morals);
+        if let Some(morals) = self.morals.or(culture_morals) {
+            agent.morals = morals;
+        }
+        if let Some(palette) = &self.palette {
+            agent.emotions.set_palette(palette.clone());
+        }
+        for (source, relation, target) in &self.facts {
+            for id in [source, target] {
+                if agent.knowledge.get_entity(id).is_none() {
+                    agent.knowledge.add_entity(Entity::new(id, HashMap::new()));
+                }
+            }
+            let relationship = Relationship::new(source, target, relation, HashMap::new());
+            agent.knowledge.add_relationship(relationship);
+        }
+        agent
+    }
+}
@@ -0,0 +1,291 @@
+//! # Dialogue Act Module
+//!
+//! This module lets the game declare what NPCs may and must do in a scene: which dialogue acts
+//! (greeting, offering a quest, threatening, ...) are forbidden or required, and which topics
+//! must or must not be mentioned. For example, a combat scene can forbid quest offers, and the
+//! festival eve can require mentioning the festival.
+//!
+//! Constraints are enforced twice: as instructions in the prompt, and by validating the
+//! generated line, which is regenerated with the violations spelled out if it breaks them. Both
+//! happen in a pipeline, with the scene added as a stage to the context gathering and the
+//! validation phases.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "network")]
+use crate::pipeline::{Phase, PipelineState, Regenerate, Stage, StageError};
+
+/// Represents what a line of dialogue does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DialogueAct {
+    Greeting,
+    Farewell,
+    Question,
+    QuestOffer,
+    TradeOffer,
+    Threat,
+    Insult,
+    Refusal,
+}
+
+/// Phrases that mark a line as performing a dialogue act. Questions are detected by their
+/// question mark instead.
+const ACT_PHRASES: [(DialogueAct, &[&str]); 7] = [
+    (
+        DialogueAct::Greeting,
+        &["hello", "greetings", "good morning", "good evening", "welcome", "well met", "hail"],
+    ),
+    (DialogueAct::Farewell, &["goodbye", "farewell", "see you", "safe travels", "bye"]),
+    (
+        DialogueAct::QuestOffer,
+        &["quest", "task for you", "job for you", "need your help", "reward", "bounty"],
+    ),
+    (DialogueAct::TradeOffer, &["buy", "sell", "wares", "price", "trade", "coin", "coins"]),
+    (
+        DialogueAct::Threat,
+        &["or else", "i'll kill", "you'll regret", "watch your back", "last warning"],
+    ),
+    (DialogueAct::Insult, &["fool", "idiot", "coward", "scum", "worthless", "half-wit"]),
+    (DialogueAct::Refusal, &["no", "never", "i refuse", "not a chance", "i won't"]),
+];
+
+impl DialogueAct {
+    /// Describes the act as an instruction verb phrase (e.g. "offer a quest or task").
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DialogueAct::Greeting => "greet the player",
+            DialogueAct::Farewell => "say goodbye",
+            DialogueAct::Question => "ask a question",
+            DialogueAct::QuestOffer => "offer a quest or task",
+            DialogueAct::TradeOffer => "offer to trade",
+            DialogueAct::Threat => "make threats",
+            DialogueAct::Insult => "insult anyone",
+            DialogueAct::Refusal => "refuse",
+        }
+    }
+}
+
+/// Lowercases a line and pads every word with spaces, so phrases match whole words only.
+pub(crate) fn normalize(line: &str) -> String {
+    let words: String = line
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '\'' || c == '-' { c } else { ' ' })
+        .collect();
+    format!(" {} ", words.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Detects the dialogue acts a line performs.
+///
+/// # Arguments
+///
+/// * `line` - The line of dialogue.
+///
+/// # Returns
+///
+/// The acts found, in declaration order.
+///
+/// # Examples
+///
+/// ```
+/// use athena::dialogue_act::{detect_acts, DialogueAct};
+///
+/// let acts = detect_acts("Well met! Wolves took my sheep; there's a reward if you hunt them.");
+/// assert_eq!(acts, vec![DialogueAct::Greeting, DialogueAct::QuestOffer]);
+/// assert_eq!(detect_acts("Need anything?"), vec![DialogueAct::Question]);
+/// ```
+pub fn detect_acts(line: &str) -> Vec<DialogueAct> {
+    let normalized = normalize(line);
+    let mut acts: Vec<DialogueAct> = ACT_PHRASES
+        .iter()
+        .filter(|(_, phrases)| {
+            phrases.iter().any(|phrase| normalized.contains(&format!(" {} ", phrase)))
+        })
+        .map(|(act, _)| *act)
+        .collect();
+    if line.contains('?') {
+        let position = acts.iter().position(|act| *act > DialogueAct::Question);
+        acts.insert(position.unwrap_or(acts.len()), DialogueAct::Question);
+    }
+    acts
+}
+
+/// Represents one rule of a scene.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Constraint {
+    /// The line must not perform the act.
+    Forbid(DialogueAct),
+    /// The line must perform the act.
+    Require(DialogueAct),
+    /// The line must mention the topic.
+    Mention(String),
+    /// The line must not mention the topic.
+    Avoid(String),
+}
+
+impl Constraint {
+    /// Renders the constraint as an instruction.
+    fn instruction(&self) -> String {
+        match self {
+            Constraint::Forbid(act) => format!("do not {}", act.describe()),
+            Constraint::Require(act) => format!("you must {}", act.describe()),
+            Constraint::Mention(topic) => format!("mention {}", topic),
+            Constraint::Avoid(topic) => format!("do not mention {}", topic),
+        }
+    }
+
+    /// Checks a line against the constraint.
+    fn holds(&self, line: &str, acts: &[DialogueAct]) -> bool {
+        match self {
+            Constraint::Forbid(act) => !acts.contains(act),
+            Constraint::Require(act) => acts.contains(act),
+            Constraint::Mention(topic) => normalize(line).contains(&normalize(topic)),
+            Constraint::Avoid(topic) => !normalize(line).contains(&normalize(topic)),
+        }
+    }
+}
+
+/// Represents the dialogue rules of a scene, declared by the game.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SceneConstraints {
+    pub constraints: Vec<Constraint>,
+}
+
+impl SceneConstraints {
+    /// Creates a new scene without constraints.
+    pub fn new() -> Self {
+        SceneConstraints::default()
+    }
+
+    /// Forbids a dialogue act.
+    pub fn forbid(mut self, act: DialogueAct) -> Self {
+        self.constraints.push(Constraint::Forbid(act));
+        self
+    }
+
+    /// Requires a dialogue act.
+    pub fn require(mut self, act: DialogueAct) -> Self {
+        self.constraints.push(Constraint::Require(act));
+        self
+    }
+
+    /// Requires mentioning a topic.
+    pub fn mention(mut self, topic: &str) -> Self {
+        self.constraints.push(Constraint::Mention(topic.to_string()));
+        self
+    }
+
+    /// Forbids mentioning a topic.
+    pub fn avoid(mut self, topic: &str) -> Self {
+        self.constraints.push(Constraint::Avoid(topic.to_string()));
+        self
+    }
+
+    /// Builds the prompt directive stating the scene's rules, or `None` if there are none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_act::{DialogueAct, SceneConstraints};
+    ///
+    /// let scene = SceneConstraints::new().forbid(DialogueAct::QuestOffer).mention("the festival");
+    /// assert_eq!(
+    ///     scene.directive().unwrap(),
+    ///     "In this scene: do not offer a quest or task; mention the festival."
+    /// );
+    /// ```
+    pub fn directive(&self) -> Option<String> {
+        if self.constraints.is_empty() {
+            return None;
+        }
+        let rules: Vec<String> = self.constraints.iter().map(Constraint::instruction).collect();
+        Some(format!("In this scene: {}.", rules.join("; ")))
+    }
+
+    /// Validates a generated line.
+    ///
+    /// # Returns
+    ///
+    /// The constraints the line breaks, in declaration order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_act::{Constraint, DialogueAct, SceneConstraints};
+    ///
+    /// let combat = SceneConstraints::new().forbid(DialogueAct::QuestOffer);
+    /// let broken = combat.validate("Behind you! And after this, I have a job for you.");
+    /// assert_eq!(broken, vec![&Constraint::Forbid(DialogueAct::QuestOffer)]);
+    /// assert!(combat.validate("Behind you!").is_empty());
+    /// ```
+    pub fn validate(&self, line: &str) -> Vec<&Constraint> {
+        let acts = detect_acts(line);
+        self.constraints.iter().filter(|constraint| !constraint.holds(line, &acts)).collect()
+    }
+
+    /// Builds a directive pointing out the broken constraints, for regeneration.
+    pub fn correction_directive(&self, broken: &[&Constraint]) -> Option<String> {
+        if broken.is_empty() {
+            return None;
+        }
+        let rules: Vec<String> = broken.iter().map(|constraint| constraint.instruction()).collect();
+        Some(format!("Your last reply broke the scene rules. This time, {}.", rules.join("; ")))
+    }
+}
+
+/// A stage that keeps lines to the scene's rules. Added to the context gathering phase, it
+/// states the rules as a directive for the prompt; added to the validation phase, it has a line
+/// that breaks them regenerated with the broken rules spelled out, and fails the request once
+/// the attempts run out.
+///
+/// # Examples
+///
+/// ```
+/// use athena::dialogue_act::{DialogueAct, SceneConstraints};
+/// use athena::dialogue_generation::{DialogueClient, RequestType};
+/// use athena::pipeline::{Phase, Pipeline, PipelineState, Regenerate};
+///
+/// let combat = SceneConstraints::new().forbid(DialogueAct::QuestOffer);
+/// let mut pipeline = Pipeline::new(DialogueClient::new("my-api-key"));
+/// pipeline.add_stage(Phase::ContextGathering, combat.clone());
+/// pipeline.add_stage(Phase::Validation, combat);
+///
+/// let mut state = PipelineState::new(RequestType::Conversation, "Any work for me?");
+/// pipeline.prepare(&mut state).unwrap();
+/// assert_eq!(state.context.directives, ["In this scene: do not offer a quest or task."]);
+///
+/// state.reply = "Later! But I have a job for you.".to_string();
+/// state.attempts_left = 1;
+/// let error = pipeline.finish(&mut state).unwrap_err();
+/// assert!(error.downcast_ref::<Regenerate>().is_some());
+/// state.attempts_left = 0;
+/// let error = pipeline.finish(&mut state).unwrap_err();
+/// assert!(error.to_string().starts_with("Line still breaks the scene rules"));
+/// ```
+#[cfg(feature = "network")]
+impl Stage for SceneConstraints {
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        match state.phase {
+            Phase::ContextGathering => {
+                state.context.directives.extend(self.directive());
+                Ok(())
+            }
+            Phase::Validation => {
+                let broken = self.validate(&state.reply);
+                let Some(directive) = self.correction_directive(&broken) else {
+                    return Ok(());
+                };
+                if state.attempts_left > 0 {
+                    return Err(Regenerate::new(&directive).into());
+                }
+                let rules: Vec<String> = broken.iter().map(|rule| rule.instruction()).collect();
+                Err(format!("Line still breaks the scene rules: {}", rules.join("; ")).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "scene_constraints"
+    }
+}
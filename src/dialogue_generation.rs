@@ -1,25 +1,93 @@
+//! # Dialogue Generation Module
+//!
+//! This module talks to the language model API that generates NPC dialogue. The
+//! `DialogueClient` routes each request to a model suited to its type: a cheap, fast model for
+//! ambient barks and classification, and a premium model for key story conversations. Every
+//! route can list fallback models that are tried in order when the preferred one fails.
+//!
+//! The client speaks the OpenAI chat completions API. It defaults to Groq, but any
+//! OpenAI-compatible server (vLLM, LM Studio, text-generation-webui, ...) works by pointing it at
+//! another base URL; `DialogueClient::autodetect` finds the API root, probes what the server
+//! supports, and routes to a served model. Responses are parsed leniently, since such servers
+//! omit or null fields that Groq always sends.
+//!
+//! A client can also fail over to backup providers, each a client of its own with its own routing
+//! (e.g. Groq, then OpenAI, then a local model). Providers that keep failing are skipped for a
+//! while by a circuit breaker (see the `failover` module).
+//!
+//! Studios that route calls through an internal gateway can configure a proxy, certificates, and
+//! mutual TLS, and add middleware that signs or rewrites requests (see the `transport` module).
+
+#[cfg(feature = "network")]
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "network")]
 use std::env;
+#[cfg(feature = "network")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "network")]
+use std::time::Instant;
+
+#[cfg(feature = "network")]
+use crate::failover::{BreakerPolicy, CircuitBreaker, ProviderHealth};
+#[cfg(feature = "network")]
+use crate::transport::{HttpConfig, Middleware};
+
+/// Deserializes a field that may be missing or null as its default value.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
 
 /// Represents a message in the choices array from the API response.
 #[derive(Deserialize, Debug)]
 pub struct ChoiceMessage {
+    #[serde(default, deserialize_with = "null_as_default")]
     pub role: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub content: String,
+    /// The tools the model asked to call (see the `tools` module).
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub tool_calls: Vec<ApiToolCall>,
+}
+
+/// Represents a tool call in the API response. Convert it into a `tools::ToolCall` to check and
+/// run it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiToolCall {
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub id: String,
+    pub function: ApiFunctionCall,
+}
+
+/// Represents the function a tool call names, with its arguments as a JSON string.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiFunctionCall {
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub arguments: String,
 }
 
 /// Represents a choice from the API response.
 #[derive(Deserialize, Debug)]
 pub struct Choice {
+    #[serde(default)]
     pub index: usize,
     pub message: ChoiceMessage,
+    #[serde(default)]
     pub logprobs: Option<serde_json::Value>, // Log probabilities can be null
+    #[serde(default, deserialize_with = "null_as_default")]
     pub finish_reason: String,
 }
 
-/// Represents the usage statistics from the API response.
-#[derive(Deserialize, Debug)]
+/// Represents the usage statistics from the API response. Timings are Groq extensions and are
+/// zero for other servers.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct Usage {
     pub queue_time: f64,
     pub prompt_tokens: usize,
@@ -31,59 +99,907 @@ pub struct Usage {
 }
 
 /// Represents the complete response from the API.
+///
+/// Only `choices` is required; servers that omit or null the other fields get defaults.
+///
+/// # Examples
+///
+/// ```
+/// use athena::dialogue_generation::ApiResponse;
+///
+/// // A minimal response, as some self-hosted servers send it.
+/// let response: ApiResponse = serde_json::from_str(r#"{
+///     "model": "local",
+///     "choices": [{ "message": { "role": "assistant", "content": "Aye." }, "finish_reason": null }]
+/// }"#).unwrap();
+/// assert_eq!(response.choices[0].message.content, "Aye.");
+/// assert_eq!(response.usage.total_tokens, 0);
+/// ```
 #[derive(Deserialize, Debug)]
 pub struct ApiResponse {
+    #[serde(default, deserialize_with = "null_as_default")]
     pub id: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub object: String,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub created: usize,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub model: String,
     pub choices: Vec<Choice>,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub usage: Usage,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub system_fingerprint: String,
+    #[serde(default)]
     pub x_groq: serde_json::Value, // Assuming this can vary, so use Value
 }
 
+/// The error of a request the server answered with an unsuccessful status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusError {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The body of the response.
+    pub message: String,
+}
+
+impl StatusError {
+    /// Returns true if the status means the provider is unavailable rather than the request is
+    /// wrong: a server error or rate limiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::StatusError;
+    ///
+    /// let error = |status| StatusError { status, message: String::new() };
+    /// assert!(error(503).is_outage());
+    /// assert!(error(429).is_outage());
+    /// assert!(!error(400).is_outage());
+    /// assert!(!error(401).is_outage());
+    /// ```
+    pub fn is_outage(&self) -> bool {
+        self.status >= 500 || self.status == 429
+    }
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request failed with status: {} - {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Returns true if a request error means the provider is unavailable: the request could not be
+/// sent or answered, or the server answered with a server error or rate limiting. Other errors,
+/// such as a rejected request, say nothing about the provider's health.
+#[cfg(feature = "network")]
+fn is_outage(error: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    if let Some(error) = error.downcast_ref::<StatusError>() {
+        return error.is_outage();
+    }
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|error| error.is_connect() || error.is_timeout() || error.is_request())
+}
+
+/// The root of the default OpenAI-compatible API.
+pub const DEFAULT_BASE_URL: &str = "https://api.groq.com/openai/v1";
+
+/// Represents what an OpenAI-compatible server supports, as found by probing it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// The models the server lists, in its order.
+    pub models: Vec<String>,
+    /// Whether the server streams responses as server-sent events.
+    pub streaming: bool,
+    /// Whether the server accepts `response_format: {"type": "json_object"}`.
+    pub json_mode: bool,
+    /// Whether the server accepts `response_format: {"type": "json_schema", ...}`, which
+    /// constrains generation to a schema.
+    #[serde(default)]
+    pub json_schema: bool,
+}
+
+/// Represents the format the model is asked to respond in.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ResponseFormat {
+    /// Free text.
+    #[default]
+    Text,
+    /// Any valid JSON object.
+    JsonObject,
+    /// JSON constrained to a schema.
+    JsonSchema {
+        name: String,
+        schema: serde_json::Value,
+    },
+}
+
+impl ResponseFormat {
+    /// Builds the `response_format` request field, or `None` for free text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::ResponseFormat;
+    /// assert_eq!(ResponseFormat::Text.to_request_field(), None);
+    /// assert_eq!(
+    ///     ResponseFormat::JsonObject.to_request_field().unwrap()["type"],
+    ///     "json_object"
+    /// );
+    /// ```
+    pub fn to_request_field(&self) -> Option<serde_json::Value> {
+        match self {
+            ResponseFormat::Text => None,
+            ResponseFormat::JsonObject => Some(serde_json::json!({ "type": "json_object" })),
+            ResponseFormat::JsonSchema { name, schema } => Some(serde_json::json!({
+                "type": "json_schema",
+                "json_schema": { "name": name, "schema": schema, "strict": true }
+            })),
+        }
+    }
+}
+
+/// Represents sampling parameters for a request. Unset parameters are left to the next layer:
+/// per-NPC overrides fall back to the client defaults, which fall back to the server defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationParams {
+    /// The model to use instead of the routed one. The route's fallbacks still apply.
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<usize>,
+    /// Sequences that end generation.
+    pub stop: Option<Vec<String>>,
+    /// The tools the model may call, in the chat completions API's format (e.g. an NPC's
+    /// `ToolSandbox::request_tools`).
+    pub tools: Option<Vec<serde_json::Value>>,
+}
+
+impl GenerationParams {
+    /// Merges overrides into these parameters: every parameter set in `overrides` wins.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::GenerationParams;
+    ///
+    /// let defaults = GenerationParams { temperature: Some(0.7), max_tokens: Some(256), ..Default::default() };
+    /// let overrides = GenerationParams { temperature: Some(1.1), ..Default::default() };
+    /// let merged = defaults.merge(&overrides);
+    /// assert_eq!(merged.temperature, Some(1.1));
+    /// assert_eq!(merged.max_tokens, Some(256));
+    /// ```
+    pub fn merge(&self, overrides: &GenerationParams) -> GenerationParams {
+        GenerationParams {
+            model: overrides.model.clone().or_else(|| self.model.clone()),
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            max_tokens: overrides.max_tokens.or(self.max_tokens),
+            stop: overrides.stop.clone().or_else(|| self.stop.clone()),
+            tools: overrides.tools.clone().or_else(|| self.tools.clone()),
+        }
+    }
+
+    /// Adds the set sampling parameters to a request body. The model is handled by routing.
+    #[cfg(feature = "network")]
+    fn apply_to(&self, body: &mut serde_json::Value) {
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            body["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            body["max_tokens"] = serde_json::json!(max_tokens);
+        }
+        if let Some(stop) = &self.stop {
+            body["stop"] = serde_json::json!(stop);
+        }
+        if let Some(tools) = self.tools.as_ref().filter(|tools| !tools.is_empty()) {
+            body["tools"] = serde_json::json!(tools);
+        }
+    }
+}
+
+/// Represents an NPC's generation parameter overrides, for all requests and per request type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationOverrides {
+    /// Overrides for every request type.
+    pub all: GenerationParams,
+    /// Overrides for specific request types, applied on top of `all`.
+    pub per_request: HashMap<RequestType, GenerationParams>,
+}
+
+impl GenerationOverrides {
+    /// Gets the overrides for a request type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::{GenerationOverrides, GenerationParams, RequestType};
+    ///
+    /// let mut overrides = GenerationOverrides::default();
+    /// overrides.all.temperature = Some(0.9);
+    /// overrides.per_request.insert(
+    ///     RequestType::Bark,
+    ///     GenerationParams { max_tokens: Some(24), ..Default::default() },
+    /// );
+    /// let bark = overrides.params_for(RequestType::Bark);
+    /// assert_eq!((bark.temperature, bark.max_tokens), (Some(0.9), Some(24)));
+    /// assert_eq!(overrides.params_for(RequestType::Story).max_tokens, None);
+    /// ```
+    pub fn params_for(&self, request_type: RequestType) -> GenerationParams {
+        match self.per_request.get(&request_type) {
+            Some(params) => self.all.merge(params),
+            None => self.all.clone(),
+        }
+    }
+}
+
+/// Represents the kind of generation request, used to pick a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RequestType {
+    /// Short ambient one-liners.
+    Bark,
+    /// Classification of player input or events.
+    Classification,
+    /// Regular conversation with the player.
+    Conversation,
+    /// Key story conversations where quality matters most.
+    Story,
+}
+
+/// Represents the model to use for a request type, with ordered fallbacks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelRoute {
+    pub model: String,
+    /// Models tried in order if the preferred model fails.
+    pub fallbacks: Vec<String>,
+}
+
+impl ModelRoute {
+    /// Creates a new route with the given model and fallbacks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::ModelRoute;
+    /// let route = ModelRoute::new("llama-3.3-70b-versatile", &["llama3-8b-8192"]);
+    /// assert_eq!(route.models().count(), 2);
+    /// ```
+    pub fn new(model: &str, fallbacks: &[&str]) -> Self {
+        ModelRoute {
+            model: model.to_string(),
+            fallbacks: fallbacks.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Iterates over the preferred model followed by its fallbacks.
+    pub fn models(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.model.as_str()).chain(self.fallbacks.iter().map(|f| f.as_str()))
+    }
+}
+
+/// Maps request types to model routes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingPolicy {
+    /// Routes for specific request types.
+    routes: HashMap<RequestType, ModelRoute>,
+    /// The route used for request types without a specific route.
+    default_route: ModelRoute,
+}
+
+impl Default for RoutingPolicy {
+    fn default() -> Self {
+        let mut policy = RoutingPolicy::new(ModelRoute::new("llama3-8b-8192", &[]));
+        let fast = ModelRoute::new("llama-3.1-8b-instant", &["llama3-8b-8192"]);
+        policy.set_route(RequestType::Bark, fast.clone());
+        policy.set_route(RequestType::Classification, fast);
+        policy.set_route(
+            RequestType::Story,
+            ModelRoute::new("llama-3.3-70b-versatile", &["llama3-8b-8192"]),
+        );
+        policy
+    }
+}
+
+impl RoutingPolicy {
+    /// Creates a policy that sends every request type to the given route.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_route` - The route for request types without a specific route.
+    pub fn new(default_route: ModelRoute) -> Self {
+        RoutingPolicy {
+            routes: HashMap::new(),
+            default_route,
+        }
+    }
+
+    /// Sets the route for a request type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::{ModelRoute, RequestType, RoutingPolicy};
+    /// let mut policy = RoutingPolicy::default();
+    /// policy.set_route(RequestType::Story, ModelRoute::new("my-premium-model", &[]));
+    /// assert_eq!(policy.route(RequestType::Story).model, "my-premium-model");
+    /// ```
+    pub fn set_route(&mut self, request_type: RequestType, route: ModelRoute) {
+        self.routes.insert(request_type, route);
+    }
+
+    /// Gets the route for a request type.
+    pub fn route(&self, request_type: RequestType) -> &ModelRoute {
+        self.routes.get(&request_type).unwrap_or(&self.default_route)
+    }
+}
+
+/// Represents a client for the dialogue generation API.
+#[cfg(feature = "network")]
+pub struct DialogueClient {
+    client: Client,
+    /// The API key, or empty for servers without authentication.
+    api_key: String,
+    /// The API root, e.g. "http://localhost:8000/v1".
+    base_url: String,
+    routing: RoutingPolicy,
+    /// The generation parameters of every request, unless overridden.
+    defaults: GenerationParams,
+    /// What the server supports, once probed.
+    capabilities: Option<Capabilities>,
+    /// The providers tried in order when this one fails.
+    backups: Vec<DialogueClient>,
+    breaker_policy: BreakerPolicy,
+    /// The circuit breakers of this provider followed by those of the backups.
+    breakers: Mutex<Vec<CircuitBreaker>>,
+    /// The hooks run on every HTTP request and response, in order.
+    middleware: Vec<Arc<dyn Middleware>>,
+}
+
+#[cfg(feature = "network")]
+impl DialogueClient {
+    /// Creates a new client with the given API key and the default routing policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::DialogueClient;
+    /// let client = DialogueClient::new("my-api-key");
+    /// ```
+    pub fn new(api_key: &str) -> Self {
+        DialogueClient {
+            client: Client::new(),
+            api_key: api_key.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            routing: RoutingPolicy::default(),
+            defaults: GenerationParams::default(),
+            capabilities: None,
+            backups: Vec::new(),
+            breaker_policy: BreakerPolicy::default(),
+            breakers: Mutex::new(vec![CircuitBreaker::new()]),
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Creates a new client for an OpenAI-compatible server.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The API root, e.g. "http://localhost:1234/v1".
+    /// * `api_key` - The API key, or empty if the server does not need one.
+    /// * `model` - The model every request type is routed to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::{DialogueClient, RequestType};
+    /// let client = DialogueClient::openai_compatible("http://localhost:1234/v1/", "", "mistral");
+    /// assert_eq!(client.get_base_url(), "http://localhost:1234/v1");
+    /// assert_eq!(client.get_routing().route(RequestType::Story).model, "mistral");
+    /// ```
+    pub fn openai_compatible(base_url: &str, api_key: &str, model: &str) -> Self {
+        let mut client = DialogueClient::new(api_key);
+        client.set_base_url(base_url);
+        client.set_routing(RoutingPolicy::new(ModelRoute::new(model, &[])));
+        client
+    }
+
+    /// Creates a new client from environment variables.
+    ///
+    /// The API key is read from `GROQ_API_KEY`, or `ATHENA_API_KEY` if that is not set.
+    /// `ATHENA_BASE_URL` points the client at another OpenAI-compatible server, and
+    /// `ATHENA_MODEL` routes every request type to one model. With a base URL set, the key is
+    /// optional and read from `ATHENA_API_KEY` only, so the Groq key is never sent to another
+    /// server.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DialogueClient, env::VarError>` - The client, or an error if no key is set for
+    ///   the default server.
+    pub fn from_env() -> Result<Self, env::VarError> {
+        let base_url = env::var("ATHENA_BASE_URL").ok();
+        let api_key = match &base_url {
+            Some(_) => env::var("ATHENA_API_KEY").unwrap_or_default(),
+            None => env::var("GROQ_API_KEY").or_else(|_| env::var("ATHENA_API_KEY"))?,
+        };
+        let mut client = DialogueClient::new(&api_key);
+        if let Some(base_url) = base_url {
+            client.set_base_url(&base_url);
+        }
+        if let Ok(model) = env::var("ATHENA_MODEL") {
+            client.set_routing(RoutingPolicy::new(ModelRoute::new(&model, &[])));
+        }
+        Ok(client)
+    }
+
+    /// Creates a client for an OpenAI-compatible server, detecting how to talk to it.
+    ///
+    /// The API root is found by trying the URL as given and with `/v1` appended. The server is
+    /// then probed for its models and capabilities, and if it does not serve the models of the
+    /// default routing policy, every request type is routed to the first model it lists.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The server URL, with or without the `/v1` suffix.
+    /// * `api_key` - The API key, or empty if the server does not need one.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<DialogueClient, Box<dyn std::error::Error + Send + Sync>>` - The configured
+    ///   client, or an error if no model list was found at either root.
+    pub async fn autodetect(
+        url: &str,
+        api_key: &str,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut client = DialogueClient::new(api_key);
+        let trimmed = url.trim_end_matches('/');
+        let mut candidates = vec![trimmed.to_string()];
+        if !trimmed.ends_with("/v1") {
+            candidates.push(format!("{}/v1", trimmed));
+        }
+        let mut last_error: Box<dyn std::error::Error + Send + Sync> = "No URL given".into();
+        for candidate in candidates {
+            client.set_base_url(&candidate);
+            match client.list_models().await {
+                Ok(models) => {
+                    let served = |model: &str| models.iter().any(|m| m == model);
+                    let routing = client.get_routing();
+                    let request_types = [
+                        RequestType::Bark,
+                        RequestType::Classification,
+                        RequestType::Conversation,
+                        RequestType::Story,
+                    ];
+                    let all_served = request_types
+                        .into_iter()
+                        .all(|request_type| routing.route(request_type).models().any(served));
+                    if !all_served {
+                        if let Some(model) = models.first() {
+                            client.set_routing(RoutingPolicy::new(ModelRoute::new(model, &[])));
+                        }
+                    }
+                    client.probe().await?;
+                    return Ok(client);
+                }
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sets the API root, e.g. "http://localhost:8000/v1".
+    pub fn set_base_url(&mut self, base_url: &str) {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self.capabilities = None;
+    }
+
+    /// Gets the API root.
+    pub fn get_base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Gets what the server supports, if it has been probed.
+    pub fn get_capabilities(&self) -> Option<&Capabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Replaces the HTTP client with one built from HTTP settings (proxy, timeouts, TLS).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Box<dyn std::error::Error>>` - An error if the settings are invalid, in which
+    ///   case the client is left unchanged.
+    pub fn set_http_config(
+        &mut self,
+        config: &HttpConfig,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.client = config.build()?;
+        Ok(())
+    }
+
+    /// Adds middleware, run on every request and response after the middleware added before it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::DialogueClient;
+    /// use athena::transport::MiddlewareError;
+    ///
+    /// let mut client = DialogueClient::openai_compatible("https://gateway.internal/v1", "", "npc");
+    /// client.add_middleware(|request: &mut reqwest::Request| -> Result<(), MiddlewareError> {
+    ///     request.headers_mut().insert("X-Studio-Signature", "signed".parse()?);
+    ///     Ok(())
+    /// });
+    /// assert_eq!(client.middleware_count(), 1);
+    /// ```
+    pub fn add_middleware(&mut self, middleware: impl Middleware + 'static) {
+        self.middleware.push(Arc::new(middleware));
+    }
+
+    /// Returns the number of middleware hooks.
+    pub fn middleware_count(&self) -> usize {
+        self.middleware.len()
+    }
+
+    /// Sends a request, adding the authorization header (unless the client has no key) and
+    /// running the middleware on the request and response.
+    async fn execute(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, Box<dyn std::error::Error + Send + Sync>> {
+        let request = if self.api_key.is_empty() {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", self.api_key))
+        };
+        let mut request = request.build()?;
+        for middleware in &self.middleware {
+            middleware.on_request(&mut request)?;
+        }
+        let response = self.client.execute(request).await?;
+        for middleware in &self.middleware {
+            middleware.on_response(&response)?;
+        }
+        Ok(response)
+    }
+
+    /// Lists the models the server serves.
+    pub async fn list_models(
+        &self,
+    ) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.execute(self.client.get(format!("{}/models", self.base_url))).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(format!("Listing models failed with status: {}", status).into());
+        }
+        let body: serde_json::Value = response.json().await?;
+        let models = body["data"]
+            .as_array()
+            .ok_or("The model list has no data array")?
+            .iter()
+            .filter_map(|model| model["id"].as_str().map(|id| id.to_string()))
+            .collect();
+        Ok(models)
+    }
+
+    /// Probes the server for its models and capabilities, and remembers them.
+    ///
+    /// Streaming, JSON mode, and schema-constrained JSON are each probed with a one-token
+    /// request to the conversation model; a server that rejects the request is taken not to
+    /// support the feature.
+    pub async fn probe(
+        &mut self,
+    ) -> Result<&Capabilities, Box<dyn std::error::Error + Send + Sync>> {
+        let models = self.list_models().await?;
+        let model = self.routing.route(RequestType::Conversation).model.clone();
+        let streaming = self
+            .post_probe(serde_json::json!({
+                "messages": [{ "role": "user", "content": "Say hi." }],
+                "model": model,
+                "max_tokens": 1,
+                "stream": true
+            }))
+            .await
+            .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+        let json_mode = self
+            .post_probe(serde_json::json!({
+                "messages": [{ "role": "user", "content": "Reply with the JSON object {}." }],
+                "model": model,
+                "max_tokens": 1,
+                "response_format": { "type": "json_object" }
+            }))
+            .await
+            .is_some();
+        let schema = ResponseFormat::JsonSchema {
+            name: "probe".to_string(),
+            schema: serde_json::json!({ "type": "object", "properties": {} }),
+        };
+        let json_schema = self
+            .post_probe(serde_json::json!({
+                "messages": [{ "role": "user", "content": "Reply with the JSON object {}." }],
+                "model": model,
+                "max_tokens": 1,
+                "response_format": schema.to_request_field()
+            }))
+            .await
+            .is_some();
+        Ok(self.capabilities.insert(Capabilities {
+            models,
+            streaming,
+            json_mode,
+            json_schema,
+        }))
+    }
+
+    /// Sends a probe request, returning the response content type if the server accepted it.
+    async fn post_probe(&self, body: serde_json::Value) -> Option<String> {
+        let url = format!("{}/chat/completions", self.base_url);
+        let response = self.execute(self.client.post(url).json(&body)).await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let content_type = response.headers().get("content-type")?.to_str().ok()?;
+        Some(content_type.to_string())
+    }
+
+    /// Replaces the routing policy.
+    pub fn set_routing(&mut self, routing: RoutingPolicy) {
+        self.routing = routing;
+    }
+
+    /// Gets the routing policy.
+    pub fn get_routing(&self) -> &RoutingPolicy {
+        &self.routing
+    }
+
+    /// Sets the generation parameters used when a request does not override them.
+    pub fn set_defaults(&mut self, defaults: GenerationParams) {
+        self.defaults = defaults;
+    }
+
+    /// Gets the default generation parameters.
+    pub fn get_defaults(&self) -> &GenerationParams {
+        &self.defaults
+    }
+
+    /// Adds a backup provider, tried after this one and the backups added before it. The backup
+    /// uses its own routing and defaults; its own backups are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::DialogueClient;
+    ///
+    /// let mut client = DialogueClient::new("groq-key");
+    /// client.add_backup(DialogueClient::openai_compatible(
+    ///     "https://api.openai.com/v1",
+    ///     "openai-key",
+    ///     "gpt-4o-mini",
+    /// ));
+    /// client.add_backup(DialogueClient::openai_compatible("http://localhost:1234/v1", "", "mistral"));
+    /// let health = client.provider_health();
+    /// assert_eq!(health.len(), 3);
+    /// assert_eq!(health[2].base_url, "http://localhost:1234/v1");
+    /// ```
+    pub fn add_backup(&mut self, backup: DialogueClient) {
+        self.backups.push(backup);
+        self.breakers.lock().unwrap().push(CircuitBreaker::new());
+    }
+
+    /// Gets the backup providers.
+    pub fn get_backups(&self) -> &[DialogueClient] {
+        &self.backups
+    }
+
+    /// Sets when providers are skipped after failing.
+    pub fn set_breaker_policy(&mut self, policy: BreakerPolicy) {
+        self.breaker_policy = policy;
+    }
+
+    /// Gets the health of this provider followed by that of the backups.
+    pub fn provider_health(&self) -> Vec<ProviderHealth> {
+        let now = Instant::now();
+        let breakers = self.breakers.lock().unwrap();
+        std::iter::once(self)
+            .chain(&self.backups)
+            .zip(breakers.iter())
+            .map(|(provider, breaker)| {
+                ProviderHealth::of(&provider.base_url, breaker, now, &self.breaker_policy)
+            })
+            .collect()
+    }
+
+    /// Sends a message, routed to the model configured for the request type.
+    ///
+    /// If the preferred model fails, each fallback is tried in order. If every model of the
+    /// provider fails, the backup providers are tried in turn, skipping those whose circuit
+    /// breaker is open, and the last error is returned if all of them fail. Only outages (see
+    /// `StatusError::is_outage`) count against a provider's breaker.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_type` - The kind of request, used to pick the model.
+    /// * `input` - A string slice that holds the user message.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>>` - A result containing the
+    ///   API response or an error. The error is `Send` so requests can run on background tasks.
+    pub async fn send(
+        &self,
+        request_type: RequestType,
+        input: &str,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.send_with_format(request_type, input, &ResponseFormat::Text).await
+    }
+
+    /// Sends a message asking for a response in the given format.
+    ///
+    /// The format is passed on as is; whether the server supports it is up to the caller (see
+    /// `get_capabilities` and the `structured` module, which falls back when it does not).
+    pub async fn send_with_format(
+        &self,
+        request_type: RequestType,
+        input: &str,
+        format: &ResponseFormat,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.send_request(request_type, input, format, &GenerationParams::default()).await
+    }
+
+    /// Sends a message with generation parameter overrides, e.g. an NPC's
+    /// `GenerationOverrides::params_for` the request type.
+    ///
+    /// The overrides are merged over the client defaults. An overridden model is tried first,
+    /// followed by the fallbacks of the request type's route. The overridden model names a model
+    /// of this provider, so backups use their own routes instead.
+    pub async fn send_with_params(
+        &self,
+        request_type: RequestType,
+        input: &str,
+        params: &GenerationParams,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        self.send_request(request_type, input, &ResponseFormat::Text, params).await
+    }
+
+    /// Sends a message in a format with parameter overrides, trying each healthy provider in
+    /// turn.
+    pub(crate) async fn send_request(
+        &self,
+        request_type: RequestType,
+        input: &str,
+        format: &ResponseFormat,
+        params: &GenerationParams,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let mut last_error: Box<dyn std::error::Error + Send + Sync> =
+            "Every provider is unavailable".into();
+        let backup_params = GenerationParams {
+            model: None,
+            ..params.clone()
+        };
+        for (index, provider) in std::iter::once(self).chain(&self.backups).enumerate() {
+            if !self.breakers.lock().unwrap()[index].acquire(Instant::now(), &self.breaker_policy) {
+                continue;
+            }
+            let params = if index == 0 { params } else { &backup_params };
+            let result = provider.send_to_provider(request_type, input, format, params).await;
+            let mut breakers = self.breakers.lock().unwrap();
+            match result {
+                Ok(response) => {
+                    breakers[index].record_success();
+                    return Ok(response);
+                }
+                Err(error) => {
+                    if is_outage(error.as_ref()) {
+                        breakers[index].record_failure(Instant::now(), &self.breaker_policy);
+                    } else {
+                        breakers[index].release();
+                    }
+                    last_error = error;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sends a message in a format with parameter overrides to this provider, trying each
+    /// routed model in turn.
+    async fn send_to_provider(
+        &self,
+        request_type: RequestType,
+        input: &str,
+        format: &ResponseFormat,
+        params: &GenerationParams,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let params = self.defaults.merge(params);
+        let route = self.routing.route(request_type);
+        let models: Vec<&str> = match &params.model {
+            Some(model) => std::iter::once(model.as_str())
+                .chain(route.fallbacks.iter().map(|f| f.as_str()))
+                .collect(),
+            None => route.models().collect(),
+        };
+        let mut last_error: Box<dyn std::error::Error + Send + Sync> = "No model configured".into();
+        for model in models {
+            match self.send_to_model(model, input, format, &params).await {
+                Ok(response) => return Ok(response),
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Sends a message to a specific model.
+    async fn send_to_model(
+        &self,
+        model: &str,
+        input: &str,
+        format: &ResponseFormat,
+        params: &GenerationParams,
+    ) -> Result<ApiResponse, Box<dyn std::error::Error + Send + Sync>> {
+        // Create request body
+        let mut request_body = serde_json::json!({
+            "messages": [
+                {
+                    "role": "user",
+                    "content": input
+                }
+            ],
+            "model": model
+        });
+        if let Some(response_format) = format.to_request_field() {
+            request_body["response_format"] = response_format;
+        }
+        params.apply_to(&mut request_body);
+
+        // Send request to the API
+        let request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Content-Type", "application/json")
+            .json(&request_body);
+        let response = self.execute(request).await?;
+
+        // Check if the response is successful
+        if response.status().is_success() {
+            let json_response: ApiResponse = response.json().await?;
+            Ok(json_response)
+        } else {
+            // Handle non-successful responses
+            let status = response.status().as_u16();
+            let message = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Failed to read error message".to_string());
+            Err(StatusError { status, message }.into())
+        }
+    }
+}
+
 /// Sends a message to the API and returns the JSON response.
 ///
+/// The message is sent as a `RequestType::Conversation` request using the default routing policy.
+///
 /// # Arguments
 ///
 /// * `input` - A string slice that holds the user message.
 ///
 /// # Returns
 ///
-/// * `Result<ApiResponse, Box<dyn std::error::Error>>` - A result containing the API response or an error.
+/// * `Result<ApiResponse, Box<dyn std::error::Error>>` - A result containing the API response or an error,
+///   including when no API key is set.
+#[cfg(feature = "network")]
 pub async fn send_message(input: &str) -> Result<ApiResponse, Box<dyn std::error::Error>> {
-    let client = Client::new();
-    let api_key = env::var("GROQ_API_KEY").expect("GROQ_API_KEY not set");
-
-    // Create request body
-    let request_body = serde_json::json!({
-        "messages": [
-            {
-                "role": "user",
-                "content": input
-            }
-        ],
-        "model": "llama3-8b-8192"
-    });
-
-    // Send request to the API
-    let response = client
-        .post("https://api.groq.com/openai/v1/chat/completions")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await?;
-
-    // Check if the response is successful
-    if response.status().is_success() {
-        let json_response: ApiResponse = response.json().await?;
-        Ok(json_response)
-    } else {
-        // Handle non-successful responses
-        let status = response.status();
-        let error_message = response.text().await.unwrap_or_else(|_| "Failed to read error message".to_string());
-        Err(format!("Request failed with status: {} - {}", status, error_message).into())
-    }
+    let client =
+        DialogueClient::from_env().map_err(|_| "neither GROQ_API_KEY nor ATHENA_API_KEY is set")?;
+    client
+        .send(RequestType::Conversation, input)
+        .await
+        .map_err(|error| error as Box<dyn std::error::Error>)
 }
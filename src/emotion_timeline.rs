@@ -0,0 +1,453 @@
+//! # Emotion Timeline Module
+//!
+//! This module keeps a history of an NPC's emotional states. An `EmotionTimeline` is a ring
+//! buffer of timestamped samples, recorded whenever the current emotion changes and otherwise at
+//! a fixed interval, so the oldest samples are dropped once it is full.
+//!
+//! The timeline answers questions such as "what has the NPC mostly felt in the last hour" or
+//! "how many times was it frightened this week". Reflection uses it to note the NPC's mood in its
+//! journal, dialogue uses it for remarks like "I've been on edge lately", and designers can render
+//! it to see how an NPC's mood developed.
+//!
+//! For tuning decay rates and appraisal weights, a `SessionTrace` records every emotion's
+//! intensity and the NPC's state on every tick of a play session, and exports them as JSON or
+//! CSV for plotting in external tools.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cost::csv_field;
+use crate::emotional_response::{Emotion, EmotionalResponse};
+use crate::schedule::SECONDS_PER_DAY;
+
+/// One hour of game time, in seconds.
+pub const HOUR: f64 = 3_600.0;
+
+/// One week of game time, in seconds.
+pub const WEEK: f64 = 7.0 * SECONDS_PER_DAY;
+
+/// Represents the emotional state of an NPC at one point in game time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmotionSample {
+    /// The game time of the sample, in seconds.
+    pub game_time: f64,
+    pub emotion: Emotion,
+    /// The intensity of the emotion (0.0 for `Neutral`).
+    pub intensity: f64,
+}
+
+/// Represents a bounded history of an NPC's emotional states.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmotionTimeline {
+    /// The samples, oldest first.
+    samples: VecDeque<EmotionSample>,
+    /// The maximum number of samples kept.
+    capacity: usize,
+    /// How often an unchanged emotion is sampled again, in seconds of game time.
+    sample_interval: f64,
+}
+
+impl Default for EmotionTimeline {
+    /// A timeline of 1024 samples taken at least every 15 minutes, which covers over a week of
+    /// game time.
+    fn default() -> Self {
+        Self::new(1024, 900.0)
+    }
+}
+
+impl EmotionTimeline {
+    /// Creates a new, empty timeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum number of samples kept.
+    /// * `sample_interval` - How often an unchanged emotion is sampled again, in seconds.
+    pub fn new(capacity: usize, sample_interval: f64) -> Self {
+        EmotionTimeline {
+            samples: VecDeque::new(),
+            capacity: capacity.max(1),
+            sample_interval: sample_interval.max(0.0),
+        }
+    }
+
+    /// Records a sample, dropping the oldest one if the timeline is full.
+    pub fn record(&mut self, game_time: f64, emotion: Emotion, intensity: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(EmotionSample {
+            game_time,
+            emotion,
+            intensity: intensity.clamp(0.0, 1.0),
+        });
+    }
+
+    /// Samples an emotional response if its emotion changed or the sample interval has passed.
+    ///
+    /// # Returns
+    ///
+    /// True if a sample was recorded.
+    pub fn observe(&mut self, game_time: f64, emotions: &EmotionalResponse) -> bool {
+        let emotion = emotions.get_emotion();
+        let due = self.samples.back().is_none_or(|last| {
+            last.emotion != *emotion || game_time - last.game_time >= self.sample_interval
+        });
+        if due {
+            self.record(game_time, emotion.clone(), emotions.get_intensity(emotion));
+        }
+        due
+    }
+
+    /// Iterates over the samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &EmotionSample> {
+        self.samples.iter()
+    }
+
+    /// Returns the number of samples kept.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns true if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Gets the maximum number of samples kept.
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Removes all samples.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Sums how long each emotion lasted within a window, weighted by its intensity.
+    ///
+    /// Every sample lasts until the next one (the last one until `now`), so a sample taken before
+    /// the window still counts for the part of it inside the window.
+    fn weighted_durations(&self, now: f64, window: f64) -> HashMap<Emotion, (f64, f64)> {
+        let start = now - window;
+        let mut durations: HashMap<Emotion, (f64, f64)> = HashMap::new();
+        let ends = self.samples.iter().skip(1).map(|s| s.game_time).chain([now]);
+        for (sample, end) in self.samples.iter().zip(ends) {
+            let duration = end.min(now) - sample.game_time.max(start);
+            if duration > 0.0 {
+                let entry = durations.entry(sample.emotion.clone()).or_default();
+                entry.0 += duration;
+                entry.1 += duration * sample.intensity;
+            }
+        }
+        durations
+    }
+
+    /// Gets how long the NPC felt an emotion within the window ending at `now`, in seconds.
+    pub fn time_in(&self, emotion: &Emotion, now: f64, window: f64) -> f64 {
+        self.weighted_durations(now, window).get(emotion).map_or(0.0, |(duration, _)| *duration)
+    }
+
+    /// Gets how long the NPC felt each emotion within the window ending at `now`, in seconds.
+    /// This reads the timeline once, where calling `time_in` for every emotion reads it again
+    /// for each.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::emotion_timeline::{EmotionTimeline, HOUR};
+    ///
+    /// let mut timeline = EmotionTimeline::default();
+    /// timeline.record(0.0, Emotion::Fear, 0.8);
+    /// timeline.record(1_800.0, Emotion::Anger, 0.5);
+    /// let times = timeline.times_in(3_600.0, HOUR);
+    /// assert_eq!(times[&Emotion::Fear], 1_800.0);
+    /// assert_eq!(times[&Emotion::Anger], timeline.time_in(&Emotion::Anger, 3_600.0, HOUR));
+    /// ```
+    pub fn times_in(&self, now: f64, window: f64) -> HashMap<Emotion, f64> {
+        self.weighted_durations(now, window)
+            .into_iter()
+            .map(|(emotion, (duration, _))| (emotion, duration))
+            .collect()
+    }
+
+    /// Finds the dominant emotion within the window ending at `now`.
+    ///
+    /// Emotions are compared by how long they lasted times how intense they were. `Neutral` is
+    /// never dominant.
+    ///
+    /// # Returns
+    ///
+    /// The dominant emotion, or `None` if the NPC felt nothing in the window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::emotion_timeline::{EmotionTimeline, HOUR};
+    ///
+    /// let mut timeline = EmotionTimeline::default();
+    /// timeline.record(0.0, Emotion::Joy, 1.0);
+    /// timeline.record(4_000.0, Emotion::Fear, 0.9);
+    /// timeline.record(6_000.0, Emotion::Anger, 0.3);
+    /// timeline.record(6_600.0, Emotion::Neutral, 0.0);
+    /// assert_eq!(timeline.dominant_emotion(7_200.0, HOUR), Some(Emotion::Fear));
+    /// assert_eq!(timeline.dominant_emotion(7_200.0, 2.0 * HOUR), Some(Emotion::Joy));
+    /// ```
+    pub fn dominant_emotion(&self, now: f64, window: f64) -> Option<Emotion> {
+        dominant(&self.weighted_durations(now, window))
+    }
+
+    /// Counts how many times the NPC started feeling an emotion within the window ending at `now`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::emotion_timeline::{EmotionTimeline, WEEK};
+    ///
+    /// let mut timeline = EmotionTimeline::default();
+    /// timeline.record(0.0, Emotion::Fear, 1.0);
+    /// timeline.record(900.0, Emotion::Fear, 0.6);
+    /// timeline.record(1_800.0, Emotion::Neutral, 0.0);
+    /// timeline.record(90_000.0, Emotion::Fear, 1.0);
+    /// assert_eq!(timeline.count_onsets(&Emotion::Fear, 100_000.0, WEEK), 2);
+    /// ```
+    pub fn count_onsets(&self, emotion: &Emotion, now: f64, window: f64) -> usize {
+        let start = now - window;
+        let mut previous: Option<&Emotion> = None;
+        let mut count = 0;
+        for sample in &self.samples {
+            let onset = sample.emotion == *emotion && previous != Some(emotion);
+            if onset && sample.game_time >= start && sample.game_time <= now {
+                count += 1;
+            }
+            previous = Some(&sample.emotion);
+        }
+        count
+    }
+
+    /// Describes the NPC's recent mood in its own words, for dialogue and journal entries.
+    ///
+    /// A remark is only made if the dominant emotion lasted at least a quarter of the window.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::emotion_timeline::EmotionTimeline;
+    ///
+    /// let mut timeline = EmotionTimeline::default();
+    /// timeline.record(0.0, Emotion::Fear, 0.8);
+    /// assert_eq!(timeline.mood_remark(3_600.0, 86_400.0), None);
+    /// assert_eq!(
+    ///     timeline.mood_remark(43_200.0, 86_400.0).as_deref(),
+    ///     Some("I've been on edge lately.")
+    /// );
+    /// ```
+    pub fn mood_remark(&self, now: f64, window: f64) -> Option<String> {
+        let durations = self.weighted_durations(now, window);
+        let emotion = dominant(&durations)?;
+        if durations.get(&emotion).map_or(0.0, |(duration, _)| *duration) < window * 0.25 {
+            return None;
+        }
+        let remark = match emotion {
+            Emotion::Joy => "I've been in good spirits lately.",
+            Emotion::Trust => "I've been feeling at ease with people lately.",
+            Emotion::Fear => "I've been on edge lately.",
+            Emotion::Surprise => "Things keep catching me off guard lately.",
+            Emotion::Sadness => "I've been feeling low lately.",
+            Emotion::Disgust => "I've been fed up with everything lately.",
+            Emotion::Anger => "I've been short-tempered lately.",
+            Emotion::Anticipation => "I've been restless lately, waiting for what comes next.",
+            Emotion::Neutral => return None,
+            Emotion::Custom(id) => {
+                return Some(format!("I've been full of {} lately.", id.to_lowercase()));
+            }
+        };
+        Some(remark.to_string())
+    }
+
+    /// Renders the timeline for debugging, one line per sample.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::emotion_timeline::EmotionTimeline;
+    ///
+    /// let mut timeline = EmotionTimeline::default();
+    /// timeline.record(90.0, Emotion::Anger, 0.75);
+    /// assert_eq!(timeline.render(), "0d 00:01:30  Anger (0.75)");
+    /// ```
+    pub fn render(&self) -> String {
+        self.samples
+            .iter()
+            .map(|sample| {
+                let seconds = sample.game_time.max(0.0) as u64;
+                format!(
+                    "{}d {:02}:{:02}:{:02}  {:?} ({:.2})",
+                    seconds / 86_400,
+                    seconds % 86_400 / 3_600,
+                    seconds % 3_600 / 60,
+                    seconds % 60,
+                    sample.emotion,
+                    sample.intensity
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Finds the emotion that lasted longest times how intense it was, other than `Neutral`. Ties
+/// are broken by name.
+fn dominant(durations: &HashMap<Emotion, (f64, f64)>) -> Option<Emotion> {
+    let mut durations: Vec<(&Emotion, f64)> = durations
+        .iter()
+        .filter(|(emotion, (_, weight))| **emotion != Emotion::Neutral && *weight > 0.0)
+        .map(|(emotion, (_, weight))| (emotion, *weight))
+        .collect();
+    durations.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0)))
+    });
+    durations.into_iter().next().map(|(emotion, _)| emotion.clone())
+}
+
+/// Represents the full emotional state of an NPC at one tick of a recorded session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceFrame {
+    /// The game time of the frame, in seconds.
+    pub game_time: f64,
+    /// The NPC's state (e.g. "Idle").
+    pub state: String,
+    /// The dominant emotion.
+    pub emotion: Emotion,
+    /// The intensity of every emotion, keyed by name.
+    pub intensities: BTreeMap<String, f64>,
+    /// The labels of the active dyads, strongest first.
+    pub dyads: Vec<String>,
+}
+
+/// Represents the emotions and states of an NPC over a play session, for plotting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionTrace {
+    /// The ID of the NPC traced.
+    pub npc: String,
+    /// The frames, oldest first.
+    pub frames: Vec<TraceFrame>,
+}
+
+impl SessionTrace {
+    /// Starts an empty trace of an NPC.
+    pub fn new(npc: &str) -> Self {
+        SessionTrace {
+            npc: npc.to_string(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Records a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_time` - The current game time.
+    /// * `state` - The NPC's state.
+    /// * `emotions` - The NPC's emotional response.
+    pub fn record(&mut self, game_time: f64, state: &str, emotions: &EmotionalResponse) {
+        let palette = emotions.get_palette().emotions();
+        let custom = palette.filter(|emotion| matches!(emotion, Emotion::Custom(_)));
+        let intensities = Emotion::ALL
+            .iter()
+            .chain(custom)
+            .map(|emotion| (emotion.name().to_string(), emotions.get_intensity(emotion)))
+            .collect();
+        self.frames.push(TraceFrame {
+            game_time,
+            state: state.to_string(),
+            emotion: emotions.get_emotion().clone(),
+            intensities,
+            dyads: emotions.get_dyad_labels(0.0),
+        });
+    }
+
+    /// Returns the number of frames recorded.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns true if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Exports the trace as JSON.
+    pub fn to_json(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Exports the trace as CSV, one row per frame with a column per emotion intensity. Custom
+    /// emotions of the agent's palette follow the built-in ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{Emotion, EmotionalResponse};
+    /// use athena::emotion_timeline::SessionTrace;
+    ///
+    /// let mut emotions = EmotionalResponse::new();
+    /// emotions.set_emotion(Emotion::Fear);
+    /// emotions.set_intensity(Emotion::Fear, 0.5);
+    /// let mut trace = SessionTrace::new("guard");
+    /// trace.record(60.0, "Alert", &emotions);
+    ///
+    /// let csv = trace.to_csv();
+    /// let mut lines = csv.lines();
+    /// assert_eq!(
+    ///     lines.next(),
+    ///     Some("game_time,state,emotion,Joy,Trust,Fear,Surprise,Sadness,Disgust,Anger,Anticipation,Neutral")
+    /// );
+    /// assert_eq!(lines.next(), Some("60,Alert,Fear,0,0,0.5,0,0,0,0,0,0"));
+    /// ```
+    pub fn to_csv(&self) -> String {
+        let mut names: Vec<String> = Emotion::ALL.iter().map(|e| e.name().to_string()).collect();
+        for frame in &self.frames {
+            for name in frame.intensities.keys() {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+        }
+        let mut csv = format!("game_time,state,emotion,{}\n", names.join(","));
+        for frame in &self.frames {
+            let intensities: Vec<String> = names
+                .iter()
+                .map(|name| frame.intensities.get(name).copied().unwrap_or(0.0).to_string())
+                .collect();
+            csv.push_str(&format!(
+                "{},{},{:?},{}\n",
+                frame.game_time,
+                csv_field(&frame.state),
+                frame.emotion,
+                intensities.join(",")
+            ));
+        }
+        csv
+    }
+
+    /// Saves the trace to a JSON file.
+    pub fn save_json(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Saves the trace to a CSV file.
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.to_csv())?;
+        Ok(())
+    }
+}
@@ -3,11 +3,26 @@
 //! This module manages the emotional responses of NPCs, allowing them to experience and react
 //! to various emotional states. It provides methods to set, get, and modify emotional states,
 //! as well as to choose actions based on these emotions.
+//!
+//! Alongside the discrete current emotion, every NPC carries an intensity vector over the
+//! primary emotions. Pairs of co-active primaries combine into Plutchik dyads (e.g. Joy + Trust
+//! = Love), which can be rendered as labels for prompts or used as modifiers for action utility.
+//!
+//! Which emotions exist is configured by an `EmotionPalette`, which gives each emotion a baseline
+//! it settles back to, a decay rate, and an opposite. The default palette is Plutchik's eight
+//! primaries; games with bespoke emotions (Corruption, Zeal) add them as `Emotion::Custom`.
 
 use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_act::normalize;
+use crate::symbol::Symbol;
+use crate::utility::{self, UtilityCandidate};
 
 /// Represents the emotional states an NPC can experience.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Emotion {
     Joy,
     Trust,
@@ -18,14 +33,425 @@ pub enum Emotion {
     Anger,
     Anticipation,
     Neutral,
+    /// An emotion of a custom palette, by ID (e.g. "Corruption").
+    Custom(Symbol),
+}
+
+impl Emotion {
+    /// All built-in emotions, the eight primary ones in Plutchik wheel order followed by
+    /// `Neutral`.
+    pub const ALL: [Emotion; 9] = [
+        Emotion::Joy,
+        Emotion::Trust,
+        Emotion::Fear,
+        Emotion::Surprise,
+        Emotion::Sadness,
+        Emotion::Disgust,
+        Emotion::Anger,
+        Emotion::Anticipation,
+        Emotion::Neutral,
+    ];
+
+    /// Creates a custom emotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// let zeal = Emotion::custom("Zeal");
+    /// assert_eq!(zeal.name(), "Zeal");
+    /// assert_eq!(format!("{:?}", zeal), "Zeal");
+    /// ```
+    pub fn custom(id: &str) -> Self {
+        Emotion::Custom(Symbol::new(id))
+    }
+
+    /// Gets the name of the emotion (e.g. "Joy").
+    pub fn name(&self) -> &str {
+        match self {
+            Emotion::Joy => "Joy",
+            Emotion::Trust => "Trust",
+            Emotion::Fear => "Fear",
+            Emotion::Surprise => "Surprise",
+            Emotion::Sadness => "Sadness",
+            Emotion::Disgust => "Disgust",
+            Emotion::Anger => "Anger",
+            Emotion::Anticipation => "Anticipation",
+            Emotion::Neutral => "Neutral",
+            Emotion::Custom(id) => id.as_str(),
+        }
+    }
+}
+
+impl fmt::Debug for Emotion {
+    /// Formats the emotion as its name, so custom emotions read like built-in ones.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Represents how one emotion of a palette behaves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmotionSpec {
+    pub emotion: Emotion,
+    /// The intensity the emotion settles back to over time (between 0.0 and 1.0).
+    pub baseline: f64,
+    /// The fraction of intensity lost per second of game time, or `None` for the response's
+    /// own decay rate.
+    pub decay_rate: Option<f64>,
+    /// The emotion it opposes, if any.
+    pub opposite: Option<Emotion>,
+}
+
+/// Represents the set of emotions an NPC can feel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmotionPalette {
+    specs: Vec<EmotionSpec>,
+    /// How much raising an emotion lowers its opposite, as a fraction of the rise.
+    pub inhibition: f64,
+}
+
+impl Default for EmotionPalette {
+    fn default() -> Self {
+        Self::plutchik()
+    }
+}
+
+impl EmotionPalette {
+    /// Creates an empty palette.
+    pub fn new() -> Self {
+        EmotionPalette {
+            specs: Vec::new(),
+            inhibition: 0.0,
+        }
+    }
+
+    /// Creates the default palette: Plutchik's eight primary emotions with a baseline of 0.0 and
+    /// his four pairs of opposites, which name each other without inhibiting each other.
+    pub fn plutchik() -> Self {
+        let pairs = [
+            (Emotion::Joy, Emotion::Sadness),
+            (Emotion::Trust, Emotion::Disgust),
+            (Emotion::Fear, Emotion::Anger),
+            (Emotion::Surprise, Emotion::Anticipation),
+        ];
+        let mut palette = EmotionPalette::new();
+        for emotion in &Emotion::ALL[..8] {
+            palette = palette.with_emotion(emotion.clone(), 0.0, None);
+        }
+        for (a, b) in pairs {
+            palette = palette.with_opposites(a, b);
+        }
+        palette
+    }
+
+    /// Adds an emotion, or replaces its baseline and decay rate if it is already in the palette.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion.
+    /// * `baseline` - The intensity it settles back to (between 0.0 and 1.0).
+    /// * `decay_rate` - The fraction of intensity lost per second, or `None` for the default.
+    pub fn with_emotion(
+        mut self,
+        emotion: Emotion,
+        baseline: f64,
+        decay_rate: Option<f64>,
+    ) -> Self {
+        let baseline = baseline.clamp(0.0, 1.0);
+        let decay_rate = decay_rate.map(|rate| rate.max(0.0));
+        match self.specs.iter_mut().find(|spec| spec.emotion == emotion) {
+            Some(spec) => {
+                spec.baseline = baseline;
+                spec.decay_rate = decay_rate;
+            }
+            None => self.specs.push(EmotionSpec {
+                emotion,
+                baseline,
+                decay_rate,
+                opposite: None,
+            }),
+        }
+        self
+    }
+
+    /// Makes two emotions of the palette opposites of each other.
+    pub fn with_opposites(mut self, a: Emotion, b: Emotion) -> Self {
+        for spec in &mut self.specs {
+            if spec.emotion == a {
+                spec.opposite = Some(b.clone());
+            } else if spec.emotion == b {
+                spec.opposite = Some(a.clone());
+            }
+        }
+        self
+    }
+
+    /// Sets how much raising an emotion lowers its opposite (between 0.0 and 1.0).
+    pub fn with_inhibition(mut self, inhibition: f64) -> Self {
+        self.inhibition = inhibition.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Gets how an emotion behaves, or `None` if it is not in the palette.
+    pub fn get(&self, emotion: &Emotion) -> Option<&EmotionSpec> {
+        self.specs.iter().find(|spec| spec.emotion == *emotion)
+    }
+
+    /// Returns true if the palette has an emotion.
+    pub fn contains(&self, emotion: &Emotion) -> bool {
+        self.get(emotion).is_some()
+    }
+
+    /// Iterates over the emotions of the palette, in the order they were added.
+    pub fn emotions(&self) -> impl Iterator<Item = &Emotion> {
+        self.specs.iter().map(|spec| &spec.emotion)
+    }
+
+    /// Gets the opposite of an emotion, if it has one.
+    pub fn get_opposite(&self, emotion: &Emotion) -> Option<&Emotion> {
+        self.get(emotion).and_then(|spec| spec.opposite.as_ref())
+    }
+
+    /// Gets the baseline of an emotion, or 0.0 if it is not in the palette.
+    pub fn get_baseline(&self, emotion: &Emotion) -> f64 {
+        self.get(emotion).map_or(0.0, |spec| spec.baseline)
+    }
+}
+
+/// Intensities below this level are considered faded and are dropped.
+const RESIDUAL_INTENSITY: f64 = 0.05;
+
+/// Represents a compound emotion formed by two adjacent primary emotions (Plutchik's primary dyads).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dyad {
+    /// Joy + Trust.
+    Love,
+    /// Trust + Fear.
+    Submission,
+    /// Fear + Surprise.
+    Awe,
+    /// Surprise + Sadness.
+    Disapproval,
+    /// Sadness + Disgust.
+    Remorse,
+    /// Disgust + Anger.
+    Contempt,
+    /// Anger + Anticipation.
+    Aggressiveness,
+    /// Anticipation + Joy.
+    Optimism,
+}
+
+impl Dyad {
+    /// All primary dyads, in Plutchik wheel order.
+    pub const ALL: [Dyad; 8] = [
+        Dyad::Love,
+        Dyad::Submission,
+        Dyad::Awe,
+        Dyad::Disapproval,
+        Dyad::Remorse,
+        Dyad::Contempt,
+        Dyad::Aggressiveness,
+        Dyad::Optimism,
+    ];
+
+    /// Returns the two primary emotions that make up this dyad.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{Dyad, Emotion};
+    /// assert_eq!(Dyad::Awe.components(), (Emotion::Fear, Emotion::Surprise));
+    /// ```
+    pub fn components(&self) -> (Emotion, Emotion) {
+        match self {
+            Dyad::Love => (Emotion::Joy, Emotion::Trust),
+            Dyad::Submission => (Emotion::Trust, Emotion::Fear),
+            Dyad::Awe => (Emotion::Fear, Emotion::Surprise),
+            Dyad::Disapproval => (Emotion::Surprise, Emotion::Sadness),
+            Dyad::Remorse => (Emotion::Sadness, Emotion::Disgust),
+            Dyad::Contempt => (Emotion::Disgust, Emotion::Anger),
+            Dyad::Aggressiveness => (Emotion::Anger, Emotion::Anticipation),
+            Dyad::Optimism => (Emotion::Anticipation, Emotion::Joy),
+        }
+    }
+
+    /// Returns a lowercase label for the dyad, suitable for inclusion in prompts.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Dyad::Love => "love",
+            Dyad::Submission => "submission",
+            Dyad::Awe => "awe",
+            Dyad::Disapproval => "disapproval",
+            Dyad::Remorse => "remorse",
+            Dyad::Contempt => "contempt",
+            Dyad::Aggressiveness => "aggressiveness",
+            Dyad::Optimism => "optimism",
+        }
+    }
+
+    /// Returns the utility multipliers this dyad applies to actions when fully active.
+    ///
+    /// Values above 1.0 make an action more attractive, values below 1.0 less so.
+    pub fn action_modifiers(&self) -> &'static [(&'static str, f64)] {
+        match self {
+            Dyad::Love => &[("Collaborate", 1.5), ("Dance", 1.2), ("Shout", 0.5)],
+            Dyad::Submission => &[("Collaborate", 1.3), ("Reject", 0.6)],
+            Dyad::Awe => &[("Investigate", 1.4), ("Observe", 1.3)],
+            Dyad::Disapproval => &[("Reject", 1.3), ("Collaborate", 0.8)],
+            Dyad::Remorse => &[("Cry", 1.3), ("Collaborate", 1.1), ("Shout", 0.7)],
+            Dyad::Contempt => &[("Reject", 1.5), ("Collaborate", 0.5)],
+            Dyad::Aggressiveness => &[("Shout", 1.5), ("Hide", 0.5)],
+            Dyad::Optimism => &[("Prepare", 1.4), ("Investigate", 1.2), ("Hide", 0.7)],
+        }
+    }
+}
+
+/// A data-driven mapping from emotions to weighted action candidates.
+///
+/// The default registry reproduces the classic one-action-per-emotion behaviour. Archetypes can
+/// override individual emotions to give, say, a soldier "Charge" instead of "Hide" when afraid.
+#[derive(Debug, Clone)]
+pub struct ActionRegistry {
+    /// Weighted action candidates for each emotion.
+    mappings: HashMap<Emotion, Vec<(String, f64)>>,
+}
+
+impl Default for ActionRegistry {
+    fn default() -> Self {
+        let defaults = [
+            (Emotion::Joy, "Dance"),
+            (Emotion::Trust, "Collaborate"),
+            (Emotion::Fear, "Hide"),
+            (Emotion::Surprise, "Investigate"),
+            (Emotion::Sadness, "Cry"),
+            (Emotion::Disgust, "Reject"),
+            (Emotion::Anger, "Shout"),
+            (Emotion::Anticipation, "Prepare"),
+            (Emotion::Neutral, "Observe"),
+        ];
+        let mut registry = ActionRegistry::new();
+        for (emotion, action) in defaults {
+            registry.set_candidates(emotion, vec![(action.to_string(), 1.0)]);
+        }
+        registry
+    }
+}
+
+impl ActionRegistry {
+    /// Creates an empty registry with no mappings.
+    ///
+    /// Use `ActionRegistry::default()` for the built-in mapping.
+    pub fn new() -> Self {
+        ActionRegistry {
+            mappings: HashMap::new(),
+        }
+    }
+
+    /// Replaces the action candidates for an emotion.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion to configure.
+    /// * `candidates` - A vector of `(action, weight)` pairs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{ActionRegistry, Emotion};
+    /// let mut registry = ActionRegistry::default();
+    /// registry.set_candidates(Emotion::Fear, vec![("Charge".to_string(), 1.0)]);
+    /// ```
+    pub fn set_candidates(&mut self, emotion: Emotion, candidates: Vec<(String, f64)>) {
+        self.mappings.insert(emotion, candidates);
+    }
+
+    /// Adds a single weighted action candidate for an emotion.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion to configure.
+    /// * `action` - The name of the action.
+    /// * `weight` - The base utility of the action for this emotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{ActionRegistry, Emotion};
+    /// let mut registry = ActionRegistry::default();
+    /// registry.add_candidate(Emotion::Joy, "Sing", 1.2);
+    /// assert_eq!(registry.get_candidates(&Emotion::Joy).len(), 2);
+    /// ```
+    pub fn add_candidate(&mut self, emotion: Emotion, action: &str, weight: f64) {
+        self.mappings
+            .entry(emotion)
+            .or_default()
+            .push((action.to_string(), weight));
+    }
+
+    /// Iterates over the names of every action the registry proposes, for any emotion.
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.mappings.values().flatten().map(|(action, _)| action.as_str())
+    }
+
+    /// Retrieves the action candidates for an emotion.
+    pub fn get_candidates(&self, emotion: &Emotion) -> &[(String, f64)] {
+        self.mappings.get(emotion).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    /// Returns a copy of this registry with the mappings from `overrides` applied on top.
+    ///
+    /// Emotions mapped in `overrides` replace the base candidates; all other emotions keep theirs.
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - An archetype-specific registry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{ActionRegistry, Emotion};
+    /// let mut soldier = ActionRegistry::new();
+    /// soldier.set_candidates(Emotion::Fear, vec![("Charge".to_string(), 1.0)]);
+    /// let registry = ActionRegistry::default().with_overrides(&soldier);
+    /// assert_eq!(registry.get_candidates(&Emotion::Fear)[0].0, "Charge");
+    /// assert_eq!(registry.get_candidates(&Emotion::Joy)[0].0, "Dance");
+    /// ```
+    pub fn with_overrides(&self, overrides: &ActionRegistry) -> ActionRegistry {
+        let mut merged = self.clone();
+        for (emotion, candidates) in &overrides.mappings {
+            merged.mappings.insert(emotion.clone(), candidates.clone());
+        }
+        merged
+    }
 }
 
 /// Represents the emotional response system of an NPC.
+#[derive(Clone)]
 pub struct EmotionalResponse {
     /// The current emotional state of the NPC.
     current_emotion: Emotion,
+    /// The intensity (between 0.0 and 1.0) of each primary emotion.
+    intensities: HashMap<Emotion, f64>,
     /// A memory store for past emotional states and their triggers.
     memory: HashMap<String, Emotion>,
+    /// The fraction of intensity lost per second of game time.
+    decay_rate: f64,
+    /// How strongly each memory re-activates when recalled; weakens with every recall.
+    memory_strength: HashMap<String, f64>,
+    /// The fraction of a memory's strength that re-activates on recall.
+    recall_factor: f64,
+    /// The emotion-to-action mapping used by `choose_action`.
+    action_registry: ActionRegistry,
+    /// The emotions the NPC can feel and how they behave.
+    palette: EmotionPalette,
+}
+
+impl Default for EmotionalResponse {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EmotionalResponse {
@@ -40,12 +466,66 @@ impl EmotionalResponse {
     pub fn new() -> Self {
         EmotionalResponse {
             current_emotion: Emotion::Neutral, // Default emotional state
+            intensities: HashMap::new(),
+            decay_rate: 0.01,
             memory: HashMap::new(),
+            memory_strength: HashMap::new(),
+            recall_factor: 0.5,
+            action_registry: ActionRegistry::default(),
+            palette: EmotionPalette::default(),
         }
     }
 
+    /// Sets the emotions the NPC can feel. Emotions with a baseline start at it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{Emotion, EmotionPalette, EmotionalResponse};
+    ///
+    /// let corruption = Emotion::custom("Corruption");
+    /// let zeal = Emotion::custom("Zeal");
+    /// let palette = EmotionPalette::plutchik()
+    ///     .with_emotion(corruption.clone(), 0.0, Some(0.0))
+    ///     .with_emotion(zeal.clone(), 0.2, Some(0.05))
+    ///     .with_opposites(corruption.clone(), zeal.clone())
+    ///     .with_inhibition(0.5);
+    ///
+    /// let mut cultist = EmotionalResponse::new();
+    /// cultist.set_palette(palette);
+    /// assert_eq!(cultist.get_intensity(&zeal), 0.2);
+    /// assert_eq!(cultist.get_emotion(), &zeal);
+    ///
+    /// // Corruption pushes zeal down as it grows, and never fades.
+    /// cultist.set_emotion(corruption.clone());
+    /// assert_eq!(cultist.get_intensity(&zeal), 0.0);
+    /// cultist.update(600.0);
+    /// assert_eq!(cultist.get_intensity(&corruption), 1.0);
+    ///
+    /// // Zeal settles back to its baseline.
+    /// assert!((cultist.get_intensity(&zeal) - 0.2).abs() < 1e-9);
+    /// ```
+    pub fn set_palette(&mut self, palette: EmotionPalette) {
+        for spec in &palette.specs {
+            if spec.baseline > self.get_intensity(&spec.emotion) {
+                self.intensities.insert(spec.emotion.clone(), spec.baseline);
+            }
+        }
+        self.palette = palette;
+        if self.current_emotion == Emotion::Neutral {
+            self.refresh_current_emotion();
+        }
+    }
+
+    /// Gets the emotions the NPC can feel.
+    pub fn get_palette(&self) -> &EmotionPalette {
+        &self.palette
+    }
+
     /// Sets the current emotional state of the NPC.
     ///
+    /// The emotion is set to full intensity, from which it decays over game time.
+    ///
     /// # Arguments
     ///
     /// * `emotion` - The new emotional state to set for the NPC.
@@ -58,6 +538,9 @@ impl EmotionalResponse {
     /// emotional_response.set_emotion(Emotion::Joy);
     /// ```
     pub fn set_emotion(&mut self, emotion: Emotion) {
+        if emotion != Emotion::Neutral {
+            self.set_intensity(emotion.clone(), 1.0);
+        }
         self.current_emotion = emotion;
     }
 
@@ -80,6 +563,215 @@ impl EmotionalResponse {
         &self.current_emotion
     }
 
+    /// Sets the intensity of a primary emotion. If the palette inhibits opposites, raising an
+    /// emotion lowers its opposite.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion whose intensity to set.
+    /// * `value` - The new intensity (between 0.0 and 1.0).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_intensity(Emotion::Joy, 0.7);
+    /// assert_eq!(emotional_response.get_intensity(&Emotion::Joy), 0.7);
+    /// ```
+    pub fn set_intensity(&mut self, emotion: Emotion, value: f64) {
+        let value = value.clamp(0.0, 1.0);
+        let rise = value - self.get_intensity(&emotion);
+        if rise > 0.0 && self.palette.inhibition > 0.0 {
+            if let Some(opposite) = self.palette.get_opposite(&emotion).cloned() {
+                let lowered = self.get_intensity(&opposite) - rise * self.palette.inhibition;
+                self.intensities.insert(opposite, lowered.max(0.0));
+            }
+        }
+        self.intensities.insert(emotion, value);
+    }
+
+    /// Gets the intensity of a primary emotion, or 0.0 if it has never been set.
+    pub fn get_intensity(&self, emotion: &Emotion) -> f64 {
+        self.intensities.get(emotion).copied().unwrap_or(0.0)
+    }
+
+    /// Returns the intensity vector as `(emotion, intensity)` pairs, strongest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_intensity(Emotion::Fear, 0.3);
+    /// emotional_response.set_intensity(Emotion::Anger, 0.6);
+    /// assert_eq!(emotional_response.get_intensities()[0], (Emotion::Anger, 0.6));
+    /// ```
+    pub fn get_intensities(&self) -> Vec<(Emotion, f64)> {
+        let mut intensities: Vec<(Emotion, f64)> = self
+            .intensities
+            .iter()
+            .map(|(emotion, intensity)| (emotion.clone(), *intensity))
+            .collect();
+        intensities.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| a.0.name().cmp(b.0.name()))
+        });
+        intensities
+    }
+
+    /// Sets the fraction of intensity every emotion loses per second of game time.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - The decay rate. Defaults to 0.01.
+    pub fn set_decay_rate(&mut self, rate: f64) {
+        self.decay_rate = rate.max(0.0);
+    }
+
+    /// Decays all emotion intensities by the given amount of game time.
+    ///
+    /// Intensities decay exponentially towards their baseline in the palette, at the palette's
+    /// decay rate for the emotion if it has one. Emotions without a baseline are forgotten once
+    /// they fade below a residual level, while emotions with one always recover towards it, even
+    /// from nothing. Once the current emotion fades below the residual level the NPC settles on
+    /// the strongest remaining emotion, or back to `Neutral`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The elapsed game time in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionPalette, EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_emotion(Emotion::Anger);
+    /// emotional_response.update(600.0);
+    /// assert_eq!(emotional_response.get_emotion(), &Emotion::Neutral);
+    ///
+    /// // Small steps climb back to a baseline too.
+    /// let zeal = Emotion::custom("Zeal");
+    /// let palette = EmotionPalette::plutchik().with_emotion(zeal.clone(), 0.2, Some(0.05));
+    /// let mut cultist = EmotionalResponse::new();
+    /// cultist.set_palette(palette);
+    /// cultist.set_intensity(zeal.clone(), 0.0);
+    /// for _ in 0..12000 {
+    ///     cultist.update(0.1);
+    /// }
+    /// assert!((cultist.get_intensity(&zeal) - 0.2).abs() < 1e-9);
+    /// ```
+    pub fn update(&mut self, dt: f64) {
+        for spec in self.palette.specs.iter().filter(|spec| spec.baseline > 0.0) {
+            self.intensities.entry(spec.emotion.clone()).or_insert(0.0);
+        }
+        for (emotion, intensity) in self.intensities.iter_mut() {
+            let (baseline, rate) = match self.palette.get(emotion) {
+                Some(spec) => (spec.baseline, spec.decay_rate.unwrap_or(self.decay_rate)),
+                None => (0.0, self.decay_rate),
+            };
+            let factor = (-rate * dt).exp();
+            *intensity = baseline + (*intensity - baseline) * factor;
+        }
+        let palette = &self.palette;
+        self.intensities.retain(|emotion, intensity| {
+            *intensity >= RESIDUAL_INTENSITY || palette.get_baseline(emotion) > 0.0
+        });
+        if self.get_intensity(&self.current_emotion) < RESIDUAL_INTENSITY {
+            self.current_emotion = Emotion::Neutral;
+            self.refresh_current_emotion();
+        }
+    }
+
+    /// Computes the active dyads from the intensity vector.
+    ///
+    /// A dyad's intensity is the weaker of its two components, so both primaries must be present
+    /// for the compound emotion to emerge.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `(Dyad, intensity)` pairs with non-zero intensity, strongest first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion, Dyad};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_intensity(Emotion::Fear, 0.8);
+    /// emotional_response.set_intensity(Emotion::Surprise, 0.6);
+    /// let dyads = emotional_response.get_dyads();
+    /// assert_eq!(dyads[0], (Dyad::Awe, 0.6));
+    /// ```
+    pub fn get_dyads(&self) -> Vec<(Dyad, f64)> {
+        let mut dyads: Vec<(Dyad, f64)> = Dyad::ALL
+            .iter()
+            .map(|dyad| {
+                let (a, b) = dyad.components();
+                (*dyad, self.get_intensity(&a).min(self.get_intensity(&b)))
+            })
+            .filter(|(_, intensity)| *intensity > 0.0)
+            .collect();
+        dyads.sort_by(|a, b| b.1.total_cmp(&a.1));
+        dyads
+    }
+
+    /// Returns the labels of all dyads at or above the given intensity, strongest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The minimum dyad intensity to include.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_intensity(Emotion::Joy, 0.9);
+    /// emotional_response.set_intensity(Emotion::Trust, 0.5);
+    /// assert_eq!(emotional_response.get_dyad_labels(0.3), vec!["love".to_string()]);
+    /// ```
+    pub fn get_dyad_labels(&self, threshold: f64) -> Vec<String> {
+        self.get_dyads()
+            .into_iter()
+            .filter(|(_, intensity)| *intensity >= threshold)
+            .map(|(dyad, _)| dyad.label().to_string())
+            .collect()
+    }
+
+    /// Computes the utility multiplier the active dyads apply to an action.
+    ///
+    /// Each dyad's modifier is scaled by its intensity, and the results are multiplied together.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_name` - The name of the action being scored.
+    ///
+    /// # Returns
+    ///
+    /// A multiplier, where 1.0 means the dyads have no effect on the action.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_intensity(Emotion::Disgust, 1.0);
+    /// emotional_response.set_intensity(Emotion::Anger, 1.0);
+    /// assert!(emotional_response.action_modifier("Reject") > 1.0);
+    /// assert!(emotional_response.action_modifier("Collaborate") < 1.0);
+    /// ```
+    pub fn action_modifier(&self, action_name: &str) -> f64 {
+        self.get_dyads()
+            .iter()
+            .flat_map(|(dyad, intensity)| {
+                dyad.action_modifiers()
+                    .iter()
+                    .filter(|(name, _)| *name == action_name)
+                    .map(move |(_, modifier)| 1.0 + (modifier - 1.0) * intensity)
+            })
+            .product()
+    }
+
     /// Records an emotional memory with a trigger.
     ///
     /// # Arguments
@@ -96,6 +788,7 @@ impl EmotionalResponse {
     /// ```
     pub fn record_memory(&mut self, trigger: &str, emotion: Emotion) {
         self.memory.insert(trigger.to_string(), emotion);
+        self.memory_strength.insert(trigger.to_string(), 1.0);
     }
 
     /// Retrieves an emotional memory based on the trigger.
@@ -122,8 +815,196 @@ impl EmotionalResponse {
         self.memory.get(trigger)
     }
 
+    /// Iterates over all emotional memories as `(trigger, emotion)` pairs.
+    pub fn memories(&self) -> impl Iterator<Item = (&str, &Emotion)> {
+        self.memory.iter().map(|(trigger, emotion)| (trigger.as_str(), emotion))
+    }
+
+    /// Iterates over the emotional memories whose trigger mentions the given entity. Triggers
+    /// and IDs are compared word by word, ignoring case (words being separated by anything but
+    /// letters, digits, apostrophes, and hyphens), so `"guard_7"` is mentioned in `"attacked_by_guard_7"` but not in
+    /// `"attacked_by_guard_70"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.record_memory("attacked_by_guard_7", Emotion::Anger);
+    /// emotional_response.record_memory("lost_game", Emotion::Sadness);
+    /// emotional_response.record_memory("attacked_by_guard_70", Emotion::Fear);
+    /// assert_eq!(emotional_response.memories_about("guard_7").count(), 1);
+    /// ```
+    pub fn memories_about<'a>(
+        &'a self,
+        entity_id: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a Emotion)> + 'a {
+        let entity = normalize(entity_id);
+        self.memories().filter(move |(trigger, _)| {
+            !entity.trim().is_empty() && normalize(trigger).contains(&entity)
+        })
+    }
+
+    /// Gets how strongly a memory would currently re-activate, or 0.0 if it does not exist.
+    pub fn get_memory_strength(&self, trigger: &str) -> f64 {
+        self.memory_strength.get(trigger).copied().unwrap_or(0.0)
+    }
+
+    /// Sets how strongly a memory re-activates (between 0.0 and 1.0), if it exists.
+    pub fn set_memory_strength(&mut self, trigger: &str, strength: f64) {
+        if let Some(current) = self.memory_strength.get_mut(trigger) {
+            *current = strength.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Forgets an emotional memory.
+    pub fn forget_memory(&mut self, trigger: &str) {
+        self.memory.remove(trigger);
+        self.memory_strength.remove(trigger);
+    }
+
+    /// Sets the fraction of a memory's strength that re-activates when it is recalled.
+    ///
+    /// # Arguments
+    ///
+    /// * `factor` - The recall factor (between 0.0 and 1.0). Defaults to 0.5.
+    pub fn set_recall_factor(&mut self, factor: f64) {
+        self.recall_factor = factor.clamp(0.0, 1.0);
+    }
+
+    /// Recalls the emotional memory associated with a recurring trigger.
+    ///
+    /// The remembered emotion partially re-activates: its intensity rises to at least
+    /// `strength * recall_factor`, and the current emotion is updated to the strongest one.
+    /// Each recall weakens the memory, so repeated triggers habituate over time.
+    ///
+    /// # Arguments
+    ///
+    /// * `trigger` - The event or situation that recurred.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<Emotion>` containing the re-activated emotion, or `None` if nothing is remembered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.record_memory("dog_bite", Emotion::Fear);
+    /// emotional_response.recall("dog_bite");
+    /// assert_eq!(emotional_response.get_intensity(&Emotion::Fear), 0.5);
+    /// assert_eq!(emotional_response.get_emotion(), &Emotion::Fear);
+    /// ```
+    pub fn recall(&mut self, trigger: &str) -> Option<Emotion> {
+        let emotion = self.memory.get(trigger)?.clone();
+        let strength = self.memory_strength.entry(trigger.to_string()).or_insert(1.0);
+        let activation = *strength * self.recall_factor;
+        *strength *= 1.0 - self.recall_factor / 2.0;
+
+        if activation > self.get_intensity(&emotion) {
+            self.set_intensity(emotion.clone(), activation);
+        }
+        self.refresh_current_emotion();
+        Some(emotion)
+    }
+
+    /// Recalls every emotional memory whose trigger mentions the given entity.
+    ///
+    /// This lets perceiving a related entity (e.g. the guard who attacked the NPC) re-activate
+    /// memories recorded under triggers such as `"attacked_by_guard_7"`.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The ID of the perceived entity.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the re-activated emotions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{EmotionalResponse, Emotion};
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.record_memory("attacked_by_guard_7", Emotion::Anger);
+    /// let recalled = emotional_response.recall_related("guard_7");
+    /// assert_eq!(recalled, vec![Emotion::Anger]);
+    /// ```
+    pub fn recall_related(&mut self, entity_id: &str) -> Vec<Emotion> {
+        let mut triggers: Vec<String> = self
+            .memories_about(entity_id)
+            .map(|(trigger, _)| trigger.to_string())
+            .collect();
+        triggers.sort();
+        triggers
+            .iter()
+            .filter_map(|trigger| self.recall(trigger))
+            .collect()
+    }
+
+    /// Updates the current emotion to the most intense primary emotion, if any is active.
+    fn refresh_current_emotion(&mut self) {
+        let strongest = self
+            .intensities
+            .iter()
+            .filter(|(_, intensity)| **intensity > 0.0)
+            // Ties go to the emotion named first, so the result does not depend on hash order.
+            .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.name().cmp(a.0.name())));
+        if let Some((emotion, _)) = strongest {
+            self.current_emotion = emotion.clone();
+        }
+    }
+
+    /// Replaces the emotion-to-action mapping, e.g. with an archetype-specific registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The new action registry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::{ActionRegistry, EmotionalResponse, Emotion};
+    /// let mut registry = ActionRegistry::default();
+    /// registry.set_candidates(Emotion::Fear, vec![("Charge".to_string(), 1.0)]);
+    /// let mut emotional_response = EmotionalResponse::new();
+    /// emotional_response.set_action_registry(registry);
+    /// emotional_response.set_emotion(Emotion::Fear);
+    /// assert_eq!(emotional_response.choose_action(), "Charge");
+    /// ```
+    pub fn set_action_registry(&mut self, registry: ActionRegistry) {
+        self.action_registry = registry;
+    }
+
+    /// Gets the emotion-to-action mapping in use.
+    pub fn get_action_registry(&self) -> &ActionRegistry {
+        &self.action_registry
+    }
+
+    /// Builds the scored action candidates for the current emotional state.
+    ///
+    /// Candidate weights come from the action registry and are modified by the active dyads.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `UtilityCandidate`s for the shared utility selection system.
+    pub fn action_candidates(&self) -> Vec<UtilityCandidate> {
+        let mut candidates: Vec<UtilityCandidate> = self
+            .action_registry
+            .get_candidates(&self.current_emotion)
+            .iter()
+            .map(|(action, weight)| UtilityCandidate::new(action, *weight))
+            .collect();
+        utility::apply_modifier(&mut candidates, |action| self.action_modifier(action));
+        candidates
+    }
+
     /// Chooses an action based on the current emotional state of the NPC.
     ///
+    /// The candidates registered for the current emotion are scored and the best one is selected.
+    /// If no candidates are registered, the NPC falls back to "Observe".
+    ///
     /// # Returns
     ///
     /// A string describing the action chosen based on the current emotion.
@@ -138,16 +1019,9 @@ impl EmotionalResponse {
     /// println!("Chosen Action: {}", action);
     /// ```
     pub fn choose_action(&self) -> String {
-        match self.current_emotion {
-            Emotion::Joy => "Dance".to_string(),
-            Emotion::Trust => "Collaborate".to_string(),
-            Emotion::Fear => "Hide".to_string(),
-            Emotion::Surprise => "Investigate".to_string(),
-            Emotion::Sadness => "Cry".to_string(),
-            Emotion::Disgust => "Reject".to_string(),
-            Emotion::Anger => "Shout".to_string(),
-            Emotion::Anticipation => "Prepare".to_string(),
-            Emotion::Neutral => "Observe".to_string(),
-        }
-    }
-}
\ No newline at end of file
+        let candidates = self.action_candidates();
+        utility::select_best(&candidates)
+            .map(|candidate| candidate.action.clone())
+            .unwrap_or_else(|| "Observe".to_string())
+    }
+}
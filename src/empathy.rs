@@ -0,0 +1,247 @@
+//! # Empathy Module
+//!
+//! This module lets NPCs estimate the player's emotional state from what the player says and
+//! what happens to them, and decide how to respond to it. How much an NPC cares is modulated by
+//! its agreeableness: agreeable NPCs respond supportively to a distressed player, while
+//! disagreeable ones brush them off. The resulting stance influences both action selection and
+//! the style directive given to dialogue generation. Agreeableness is read from the NPC's
+//! personality whenever the stance is needed, so personality changes take effect at once.
+
+use crate::dialogue_act::normalize;
+use crate::emotional_response::Emotion;
+use crate::personality::Personality;
+
+/// Keywords used to classify player input into an emotion. They match whole words only.
+const EMOTION_KEYWORDS: [(Emotion, &[&str]); 8] = [
+    (Emotion::Joy, &["happy", "glad", "great", "wonderful", "yay", "love it", "thank", "thanks"]),
+    (Emotion::Trust, &["trust", "believe you", "count on", "rely"]),
+    (Emotion::Fear, &["scared", "afraid", "terrified", "help me", "frightened", "worried"]),
+    (Emotion::Surprise, &["wow", "can't believe", "unbelievable", "incredible", "no way"]),
+    (Emotion::Sadness, &["sad", "lost", "miss", "died", "alone", "crying", "sorry"]),
+    (Emotion::Disgust, &["gross", "disgusting", "vile", "sick of"]),
+    (Emotion::Anger, &["angry", "hate", "furious", "damn", "kill", "idiot"]),
+    (Emotion::Anticipation, &["can't wait", "soon", "ready", "looking forward"]),
+];
+
+/// Classifies a line of player input into the emotion it most likely expresses.
+///
+/// # Arguments
+///
+/// * `input` - The player's message.
+///
+/// # Returns
+///
+/// The emotion with the most keyword matches, or `Emotion::Neutral` if none match.
+///
+/// # Examples
+///
+/// ```
+/// use athena::empathy::classify_input;
+/// use athena::emotional_response::Emotion;
+/// assert_eq!(classify_input("I'm so scared, please help me"), Emotion::Fear);
+/// assert_eq!(classify_input("Good morning"), Emotion::Neutral);
+/// assert_eq!(classify_input("The missionary is ready"), Emotion::Anticipation);
+/// ```
+pub fn classify_input(input: &str) -> Emotion {
+    let normalized = normalize(input);
+    EMOTION_KEYWORDS
+        .iter()
+        .map(|(emotion, keywords)| {
+            let hits = keywords
+                .iter()
+                .filter(|keyword| normalized.contains(&format!(" {} ", keyword)))
+                .count();
+            (emotion, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        .fold(None, |best: Option<(&Emotion, usize)>, current| match best {
+            Some(b) if b.1 >= current.1 => Some(b),
+            _ => Some(current),
+        })
+        .map(|(emotion, _)| emotion.clone())
+        .unwrap_or(Emotion::Neutral)
+}
+
+/// Represents how an NPC chooses to respond to the player's emotional state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmpathicStance {
+    Supportive,
+    Neutral,
+    Dismissive,
+}
+
+/// Represents an NPC's model of the player's emotional state.
+pub struct Empathy {
+    /// The emotion the NPC believes the player is feeling.
+    estimated_emotion: Emotion,
+    /// How confident the NPC is in its estimate (between 0.0 and 1.0).
+    confidence: f64,
+}
+
+impl Default for Empathy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Empathy {
+    /// Creates a new Empathy instance with no estimate of the player's emotion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::empathy::Empathy;
+    /// let empathy = Empathy::new();
+    /// assert_eq!(empathy.get_confidence(), 0.0);
+    /// ```
+    pub fn new() -> Self {
+        Empathy {
+            estimated_emotion: Emotion::Neutral,
+            confidence: 0.0,
+        }
+    }
+
+    /// Updates the estimate of the player's emotion from a line of player input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The player's message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::empathy::Empathy;
+    /// use athena::emotional_response::Emotion;
+    /// let mut empathy = Empathy::new();
+    /// empathy.observe_input("My brother died last winter");
+    /// assert_eq!(empathy.get_estimated_emotion(), &Emotion::Sadness);
+    /// ```
+    pub fn observe_input(&mut self, input: &str) {
+        let emotion = classify_input(input);
+        if emotion != Emotion::Neutral {
+            self.observe_event(emotion, 0.6);
+        }
+    }
+
+    /// Updates the estimate of the player's emotion from an event that happened to the player.
+    ///
+    /// A stronger observation of a different emotion replaces the estimate; a repeated emotion
+    /// reinforces the NPC's confidence.
+    ///
+    /// # Arguments
+    ///
+    /// * `emotion` - The emotion the event is likely to cause in the player.
+    /// * `weight` - How strongly the event implies that emotion (between 0.0 and 1.0).
+    pub fn observe_event(&mut self, emotion: Emotion, weight: f64) {
+        let weight = weight.clamp(0.0, 1.0);
+        if emotion == self.estimated_emotion {
+            self.confidence = (self.confidence + weight * (1.0 - self.confidence)).min(1.0);
+        } else if weight >= self.confidence * 0.5 {
+            self.estimated_emotion = emotion;
+            self.confidence = weight;
+        }
+    }
+
+    /// Gets the emotion the NPC believes the player is feeling.
+    pub fn get_estimated_emotion(&self) -> &Emotion {
+        &self.estimated_emotion
+    }
+
+    /// Gets the NPC's confidence in its estimate.
+    pub fn get_confidence(&self) -> f64 {
+        self.confidence
+    }
+
+    /// Determines how the NPC responds to the player's estimated emotional state.
+    ///
+    /// # Arguments
+    ///
+    /// * `personality` - The NPC's personality, whose agreeableness modulates the response.
+    ///
+    /// # Returns
+    ///
+    /// `Supportive` when an agreeable NPC perceives distress (or any NPC perceives joy it shares),
+    /// `Dismissive` when a disagreeable NPC perceives distress, and `Neutral` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::empathy::{Empathy, EmpathicStance};
+    /// use athena::personality::Personality;
+    /// let mut personality = Personality::new();
+    /// personality.set_agreeableness(0.9);
+    /// let mut empathy = Empathy::new();
+    /// empathy.observe_input("I'm terrified of the dark");
+    /// assert_eq!(empathy.stance(&personality), EmpathicStance::Supportive);
+    /// personality.set_agreeableness(0.1);
+    /// assert_eq!(empathy.stance(&personality), EmpathicStance::Dismissive);
+    /// ```
+    pub fn stance(&self, personality: &Personality) -> EmpathicStance {
+        if self.confidence < 0.3 {
+            return EmpathicStance::Neutral;
+        }
+        let agreeableness = personality.agreeableness;
+        match self.estimated_emotion {
+            Emotion::Fear | Emotion::Sadness | Emotion::Anger | Emotion::Disgust => {
+                if agreeableness >= 0.6 {
+                    EmpathicStance::Supportive
+                } else if agreeableness <= 0.4 {
+                    EmpathicStance::Dismissive
+                } else {
+                    EmpathicStance::Neutral
+                }
+            }
+            Emotion::Joy | Emotion::Trust | Emotion::Anticipation if agreeableness >= 0.6 => {
+                EmpathicStance::Supportive
+            }
+            _ => EmpathicStance::Neutral,
+        }
+    }
+
+    /// Computes the utility multiplier the empathic stance applies to an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `personality` - The NPC's personality.
+    /// * `action_name` - The name of the action being scored.
+    ///
+    /// # Returns
+    ///
+    /// A multiplier, where 1.0 means empathy has no effect on the action.
+    pub fn action_modifier(&self, personality: &Personality, action_name: &str) -> f64 {
+        match (self.stance(personality), action_name) {
+            (EmpathicStance::Supportive, "Comfort" | "Collaborate" | "Talk") => {
+                1.0 + self.confidence
+            }
+            (EmpathicStance::Supportive, "Reject" | "Shout") => 1.0 - self.confidence * 0.5,
+            (EmpathicStance::Dismissive, "Reject" | "Observe") => 1.0 + self.confidence * 0.5,
+            (EmpathicStance::Dismissive, "Comfort") => 1.0 - self.confidence * 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Returns a dialogue style directive describing how the NPC should address the player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::empathy::Empathy;
+    /// use athena::personality::Personality;
+    /// let empathy = Empathy::new();
+    /// assert!(!empathy.dialogue_style(&Personality::new()).is_empty());
+    /// ```
+    pub fn dialogue_style(&self, personality: &Personality) -> String {
+        let feeling = format!("{:?}", self.estimated_emotion).to_lowercase();
+        match self.stance(personality) {
+            EmpathicStance::Supportive => format!(
+                "The player seems to feel {}. Respond warmly and acknowledge their feelings.",
+                feeling
+            ),
+            EmpathicStance::Dismissive => format!(
+                "The player seems to feel {}. You don't care much; respond curtly and move on.",
+                feeling
+            ),
+            EmpathicStance::Neutral => "Respond in your usual manner.".to_string(),
+        }
+    }
+}
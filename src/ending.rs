@@ -0,0 +1,190 @@
+//! # Ending Module
+//!
+//! This module decides when an NPC wants to end a conversation and how it says goodbye. An NPC
+//! may leave because it is bored (every topic is exhausted), because it dislikes the player,
+//! because its schedule calls it elsewhere, because the player keeps being rude, or because the
+//! player keeps pestering it. Each reason comes with fitting farewell lines and a cooldown before
+//! the NPC will engage again.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_act::normalize;
+use crate::personality::Personality;
+use crate::variation::RepetitionTracker;
+
+/// Words that mark a line as rude.
+const RUDE_WORDS: [&str; 10] = [
+    "idiot", "stupid", "shut up", "fool", "moron", "ugly", "useless", "pathetic", "loser", "dumb",
+];
+
+/// Returns true if a line contains rude language, as whole words.
+///
+/// # Examples
+///
+/// ```
+/// use athena::ending::is_rude;
+/// assert!(is_rude("Shut up, old man."));
+/// assert!(!is_rude("Good morning!"));
+/// assert!(!is_rude("A foolproof plan, and a useful one."));
+/// ```
+pub fn is_rude(line: &str) -> bool {
+    let normalized = normalize(line);
+    RUDE_WORDS.iter().any(|word| normalized.contains(&format!(" {} ", word)))
+}
+
+/// Represents why an NPC ends a conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EndReason {
+    /// Every topic is exhausted and nothing interesting is left to talk about.
+    Boredom,
+    /// The NPC dislikes the player.
+    LowAffinity,
+    /// The NPC's schedule requires it to be doing something else.
+    SchedulePressure,
+    /// The player has been rude too many times.
+    Rudeness,
+    /// The player keeps pestering the NPC.
+    Annoyance,
+}
+
+/// Represents the NPC's decision to end a conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationEnd {
+    pub reason: EndReason,
+    /// The farewell line the NPC says.
+    pub farewell: String,
+    /// How long, in seconds of game time, before the NPC will engage again.
+    pub cooldown: f64,
+}
+
+/// Configures when NPCs end conversations.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EndingPolicy {
+    /// The number of rude lines after which the NPC leaves.
+    pub max_rudeness: u32,
+    /// The affinity below which the NPC leaves.
+    pub min_affinity: f64,
+    /// The number of turns a busy NPC tolerates before leaving.
+    pub busy_turn_limit: usize,
+    /// Schedule activities during which the NPC is not considered busy.
+    pub idle_activities: Vec<String>,
+    /// The base cooldown in seconds; scaled per reason.
+    pub base_cooldown: f64,
+}
+
+impl Default for EndingPolicy {
+    fn default() -> Self {
+        EndingPolicy {
+            max_rudeness: 3,
+            min_affinity: -0.5,
+            busy_turn_limit: 6,
+            idle_activities: vec!["idle".to_string(), "leisure".to_string(), "socialize".to_string()],
+            base_cooldown: 60.0,
+        }
+    }
+}
+
+impl EndingPolicy {
+    /// Returns the cooldown for a reason: rudeness, pestering, and dislike keep the NPC away
+    /// longer.
+    pub fn cooldown_for(&self, reason: EndReason) -> f64 {
+        match reason {
+            EndReason::Boredom => self.base_cooldown,
+            EndReason::SchedulePressure => self.base_cooldown * 0.5,
+            EndReason::LowAffinity => self.base_cooldown * 3.0,
+            EndReason::Rudeness => self.base_cooldown * 5.0,
+            EndReason::Annoyance => self.base_cooldown * 4.0,
+        }
+    }
+}
+
+/// Returns the authored farewell variants for a reason, in the NPC's register.
+fn farewell_variants(reason: EndReason, personality: &Personality) -> &'static [&'static str] {
+    let polite = personality.agreeableness >= 0.5;
+    match (reason, polite) {
+        (EndReason::Boredom, true) => &[
+            "Well, I shouldn't keep you. Take care.",
+            "It was good talking. Until next time.",
+            "I think that's all I have to say. Farewell.",
+        ],
+        (EndReason::Boredom, false) => &[
+            "I'm done talking.",
+            "We're finished here.",
+            "Nothing more to say. Go on.",
+        ],
+        (EndReason::LowAffinity, true) => &[
+            "I'd rather not continue this. Goodbye.",
+            "I think we're done here.",
+        ],
+        (EndReason::LowAffinity, false) => &["Get out of my sight.", "Leave me be."],
+        (EndReason::SchedulePressure, true) => &[
+            "Sorry, I really must get going.",
+            "Forgive me, duty calls.",
+        ],
+        (EndReason::SchedulePressure, false) => &["I've got work to do.", "No time for this."],
+        (EndReason::Rudeness, true) => &[
+            "I won't be spoken to like that. Good day.",
+            "There's no need for that. I'm leaving.",
+        ],
+        (EndReason::Rudeness, false) => &[
+            "Watch your tongue. We're done.",
+            "Say that again and you'll regret it.",
+        ],
+        (EndReason::Annoyance, true) => &[
+            "Please, I need some peace. Come back another time.",
+            "I've had enough talking for one day, friend.",
+        ],
+        (EndReason::Annoyance, false) => &[
+            "Enough! Stop pestering me.",
+            "You again? Leave me alone.",
+        ],
+    }
+}
+
+/// Picks a farewell line for a reason, avoiding lines the NPC said recently.
+///
+/// # Arguments
+///
+/// * `reason` - Why the conversation ends.
+/// * `personality` - The personality of the NPC, which sets the register of the line.
+/// * `repetition` - The NPC's repetition tracker.
+///
+/// # Examples
+///
+/// ```
+/// use athena::ending::{farewell, EndReason};
+/// use athena::personality::Personality;
+/// use athena::variation::RepetitionTracker;
+///
+/// let mut repetition = RepetitionTracker::default();
+/// let first = farewell(EndReason::Boredom, &Personality::new(), &mut repetition);
+/// let second = farewell(EndReason::Boredom, &Personality::new(), &mut repetition);
+/// assert_ne!(first, second);
+/// ```
+pub fn farewell(
+    reason: EndReason,
+    personality: &Personality,
+    repetition: &mut RepetitionTracker,
+) -> String {
+    repetition
+        .pick_variant("farewell", farewell_variants(reason, personality))
+        .unwrap_or("Goodbye.")
+        .to_string()
+}
+
+/// Builds a prompt asking the language model to generate a farewell for a reason.
+///
+/// Use this instead of `farewell` when generated, rather than authored, farewells are wanted.
+pub fn farewell_prompt(reason: EndReason) -> String {
+    let why = match reason {
+        EndReason::Boredom => "you have nothing more to talk about",
+        EndReason::LowAffinity => "you dislike the person you are talking to",
+        EndReason::SchedulePressure => "you have somewhere else to be",
+        EndReason::Rudeness => "the other person has been rude to you",
+        EndReason::Annoyance => "the other person keeps pestering you",
+    };
+    format!(
+        "End the conversation in one short sentence, in character, because {}.",
+        why
+    )
+}
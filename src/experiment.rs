@@ -0,0 +1,270 @@
+//! # Experiment Module
+//!
+//! This module runs A/B tests on dialogue generation. An experiment defines variants, each a
+//! prompt style (extra directives) and generation parameters (such as another model), and
+//! assigns every NPC-player pair to one of them by a stable hash. The same pair always gets the
+//! same variant, in every session and build, so players see a consistent NPC and results are
+//! not muddled by players switching between variants.
+//!
+//! Outcomes (how many turns a conversation lasted, whether the player came back) are reported to
+//! a metrics sink tagged with the experiment and variant, so studios can compare variants with
+//! their own analytics, or with the in-memory sink provided here.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_generation::GenerationParams;
+use crate::prompt::PromptContext;
+
+/// Maps parts of a key to a fraction between 0.0 (inclusive) and 1.0 (exclusive).
+///
+/// Uses FNV-1a, which unlike the standard hasher is stable across builds and platforms.
+pub(crate) fn stable_fraction(parts: &[&str]) -> f64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (index, part) in parts.iter().enumerate() {
+        let separator = (index > 0).then_some(0);
+        for byte in separator.into_iter().chain(part.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Represents a variant of an experiment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    /// How often the variant is assigned relative to the others.
+    pub weight: f64,
+    /// Directives added to the prompt (e.g. "Answer in one short sentence.").
+    #[serde(default)]
+    pub directives: Vec<String>,
+    /// Generation parameter overrides (e.g. another model).
+    #[serde(default)]
+    pub params: GenerationParams,
+}
+
+impl Variant {
+    /// Creates a new variant with a weight of 1.0 that changes nothing.
+    pub fn new(name: &str) -> Self {
+        Variant {
+            name: name.to_string(),
+            weight: 1.0,
+            directives: Vec::new(),
+            params: GenerationParams::default(),
+        }
+    }
+
+    /// Sets the weight.
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight.max(0.0);
+        self
+    }
+
+    /// Adds a prompt directive.
+    pub fn with_directive(mut self, directive: &str) -> Self {
+        self.directives.push(directive.to_string());
+        self
+    }
+
+    /// Sets the generation parameter overrides.
+    pub fn with_params(mut self, params: GenerationParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Applies the variant to a request: adds its directives to the prompt context and merges
+    /// its parameters over the request's.
+    pub fn apply(&self, context: &mut PromptContext, params: &mut GenerationParams) {
+        context.directives.extend(self.directives.iter().cloned());
+        *params = params.merge(&self.params);
+    }
+}
+
+/// Represents a measured outcome of an interaction, tagged with the variant that produced it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metric {
+    pub experiment: String,
+    pub variant: String,
+    /// What was measured (e.g. "turns", "returned").
+    pub name: String,
+    pub value: f64,
+}
+
+/// Receives metrics, e.g. to forward them to a studio's analytics.
+///
+/// Closures taking `&Metric` are sinks too.
+pub trait MetricsSink {
+    /// Records a metric.
+    fn record(&mut self, metric: &Metric);
+}
+
+impl<F> MetricsSink for F
+where
+    F: FnMut(&Metric),
+{
+    fn record(&mut self, metric: &Metric) {
+        self(metric)
+    }
+}
+
+/// Represents the summary of a metric for one variant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricSummary {
+    pub count: usize,
+    pub total: f64,
+}
+
+impl MetricSummary {
+    /// Returns the mean value, or 0.0 if nothing was recorded.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total / self.count as f64
+        }
+    }
+}
+
+/// A sink that keeps a running summary of every metric per experiment and variant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InMemorySink {
+    /// Summaries keyed by experiment, variant, and metric name.
+    summaries: BTreeMap<(String, String, String), MetricSummary>,
+}
+
+impl InMemorySink {
+    /// Creates a new, empty sink.
+    pub fn new() -> Self {
+        InMemorySink::default()
+    }
+
+    /// Gets the summary of a metric for a variant, if it was recorded.
+    pub fn get_summary(
+        &self,
+        experiment: &str,
+        variant: &str,
+        metric: &str,
+    ) -> Option<MetricSummary> {
+        let key = (experiment.to_string(), variant.to_string(), metric.to_string());
+        self.summaries.get(&key).copied()
+    }
+
+    /// Picks the variant of an experiment with the highest mean for a metric, if any recorded it.
+    pub fn best_variant(&self, experiment: &str, metric: &str) -> Option<&str> {
+        self.summaries
+            .iter()
+            .filter(|((e, _, m), _)| e == experiment && m == metric)
+            .max_by(|(_, a), (_, b)| a.mean().total_cmp(&b.mean()))
+            .map(|((_, variant, _), _)| variant.as_str())
+    }
+}
+
+impl MetricsSink for InMemorySink {
+    fn record(&mut self, metric: &Metric) {
+        let key = (metric.experiment.clone(), metric.variant.clone(), metric.name.clone());
+        let summary = self.summaries.entry(key).or_default();
+        summary.count += 1;
+        summary.total += metric.value;
+    }
+}
+
+/// Represents an A/B test over dialogue generation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Experiment {
+    pub name: String,
+    pub variants: Vec<Variant>,
+    /// Whether the experiment is running. A stopped experiment assigns the first variant.
+    pub active: bool,
+}
+
+impl Experiment {
+    /// Creates a new, running experiment without variants.
+    pub fn new(name: &str) -> Self {
+        Experiment {
+            name: name.to_string(),
+            variants: Vec::new(),
+            active: true,
+        }
+    }
+
+    /// Adds a variant.
+    pub fn add_variant(&mut self, variant: Variant) {
+        self.variants.push(variant);
+    }
+
+    /// Assigns an NPC-player pair to a variant. The assignment depends only on the experiment
+    /// name, the pair, and the variants, so it is the same in every session and build.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Variant>` - The variant, or `None` if the experiment has no variants.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::experiment::{Experiment, Variant};
+    ///
+    /// let mut experiment = Experiment::new("terse_greetings");
+    /// experiment.add_variant(Variant::new("control"));
+    /// experiment.add_variant(Variant::new("terse").with_directive("Answer in one sentence."));
+    /// let variant = experiment.assign("brom", "player").unwrap().name.clone();
+    /// assert_eq!(experiment.assign("brom", "player").unwrap().name, variant);
+    ///
+    /// experiment.active = false;
+    /// assert_eq!(experiment.assign("brom", "player").unwrap().name, "control");
+    /// ```
+    pub fn assign(&self, npc_id: &str, player_id: &str) -> Option<&Variant> {
+        let total: f64 = self.variants.iter().map(|variant| variant.weight).sum();
+        if !self.active || total <= 0.0 {
+            return self.variants.first();
+        }
+        let mut point = stable_fraction(&[&self.name, npc_id, player_id]) * total;
+        for variant in &self.variants {
+            if point < variant.weight {
+                return Some(variant);
+            }
+            point -= variant.weight;
+        }
+        self.variants.last()
+    }
+
+    /// Reports an outcome of an NPC-player pair's interaction to a sink, tagged with the
+    /// variant the pair is assigned to. Nothing is reported for stopped experiments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::experiment::{Experiment, InMemorySink, Variant};
+    ///
+    /// let mut experiment = Experiment::new("terse_greetings");
+    /// experiment.add_variant(Variant::new("control"));
+    /// let mut sink = InMemorySink::new();
+    /// experiment.report(&mut sink, "brom", "player", "turns", 6.0);
+    /// experiment.report(&mut sink, "brom", "player", "turns", 4.0);
+    /// let summary = sink.get_summary("terse_greetings", "control", "turns").unwrap();
+    /// assert_eq!(summary.mean(), 5.0);
+    /// ```
+    pub fn report(
+        &self,
+        sink: &mut impl MetricsSink,
+        npc_id: &str,
+        player_id: &str,
+        metric: &str,
+        value: f64,
+    ) {
+        if !self.active {
+            return;
+        }
+        if let Some(variant) = self.assign(npc_id, player_id) {
+            sink.record(&Metric {
+                experiment: self.name.clone(),
+                variant: variant.name.clone(),
+                name: metric.to_string(),
+                value,
+            });
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! # Fact Check Module
+//!
+//! This module checks generated dialogue against what the NPC actually knows. Claims are
+//! extracted from each sentence as (subject, relation, object) triples: a sentence that mentions
+//! two known entities (as whole words) with a known relation type between them (e.g. "Brom lives
+//! in Riverside" for a `lives_in` relationship) makes a claim. Each claim is looked up in the
+//! knowledge graph and found supported, contradicted, or unverified. Only functional relations
+//! (see `FUNCTIONAL_RELATIONS`) can be contradicted: Brom lives in one place, so living in
+//! Hilltown contradicts living in Riverside, but he may know many people, so knowing someone new
+//! is merely unverified.
+//!
+//! A `FactCheckStage` in a pipeline's validation phase has lines with contradictions regenerated
+//! with the correct facts spelled out, or hedged so the NPC does not state hallucinated lore with
+//! confidence.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_act::normalize;
+use crate::knowledge_graph::KnowledgeGraph;
+#[cfg(feature = "network")]
+use crate::pipeline::{PipelineState, Regenerate, Stage, StageError};
+
+/// The relations a subject has to at most one object at a time, so a claim naming another
+/// object contradicts the known one.
+pub const FUNCTIONAL_RELATIONS: [&str; 8] = [
+    "lives_in", "located_in", "born_in", "died_in", "works_at", "married_to", "ruled_by",
+    "owned_by",
+];
+
+/// Represents the outcome of checking one claim.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Verdict {
+    /// The knowledge graph contains the claimed relationship.
+    Supported,
+    /// The subject has the claimed functional relation, but to the listed objects instead.
+    Contradicted { known: Vec<String> },
+    /// The knowledge graph says nothing about the claim.
+    Unverified,
+}
+
+/// Represents a factual claim made by a line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    /// The sentence the claim was extracted from.
+    pub sentence: String,
+    pub subject: String,
+    pub relation: String,
+    pub object: String,
+    pub verdict: Verdict,
+}
+
+/// Represents what to do with lines that contradict known facts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FactCheckPolicy {
+    /// Return lines unchanged.
+    Off,
+    /// Hedge contradicted sentences.
+    Hedge,
+    /// Regenerate up to `max_attempts` times, then hedge whatever contradictions remain.
+    Regenerate { max_attempts: usize },
+}
+
+/// Finds the known entities mentioned as whole words in a normalized sentence, as `(entity id,
+/// position)` pairs.
+fn mentions(normalized: &str, knowledge: &KnowledgeGraph) -> Vec<(String, usize)> {
+    let mut found: Vec<(String, usize)> = knowledge
+        .entities()
+        .filter_map(|entity| {
+            let names = std::iter::once(entity.id.as_str())
+                .chain(entity.properties.get("name").map(|name| name.as_str()));
+            names
+                .map(normalize)
+                .filter(|name| !name.trim().is_empty())
+                .filter_map(|name| normalized.find(&name))
+                .min()
+                .map(|position| (entity.id.to_string(), position))
+        })
+        .collect();
+    found.sort_by_key(|(_, position)| *position);
+    found
+}
+
+/// Splits a line into sentences, keeping their terminators.
+fn sentences(line: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut start = 0;
+    for (index, c) in line.char_indices() {
+        if matches!(c, '.' | '!' | '?') {
+            result.push(line[start..=index].trim());
+            start = index + 1;
+        }
+    }
+    if !line[start..].trim().is_empty() {
+        result.push(line[start..].trim());
+    }
+    result
+}
+
+/// Extracts the claims a line makes and checks them against a knowledge graph.
+///
+/// # Arguments
+///
+/// * `line` - The generated line.
+/// * `knowledge` - The speaking NPC's knowledge graph.
+///
+/// # Returns
+///
+/// The claims found, in the order they appear.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use athena::fact_check::{self, Verdict};
+/// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// for id in ["Brom", "Riverside", "Hilltown"] {
+///     knowledge.add_entity(Entity::new(id, HashMap::new()));
+/// }
+/// knowledge.add_relationship(Relationship::new("Brom", "Riverside", "lives_in", HashMap::new()));
+/// knowledge.add_relationship(Relationship::new("Brom", "Riverside", "knows", HashMap::new()));
+///
+/// let claims = fact_check::check("Brom lives in Hilltown, you know.", &knowledge);
+/// assert_eq!(claims[0].verdict, Verdict::Contradicted { known: vec!["Riverside".to_string()] });
+/// let claims = fact_check::check("Aye, Brom lives in Riverside.", &knowledge);
+/// assert_eq!(claims[0].verdict, Verdict::Supported);
+///
+/// // Brom may know more than one place, and "Bromley" is not Brom.
+/// let claims = fact_check::check("Brom knows Hilltown.", &knowledge);
+/// assert_eq!(claims[0].verdict, Verdict::Unverified);
+/// assert!(fact_check::check("Bromley lives in Hilltown.", &knowledge).is_empty());
+/// ```
+pub fn check(line: &str, knowledge: &KnowledgeGraph) -> Vec<Claim> {
+    let mut relations: Vec<String> = knowledge
+        .relationships()
+        .map(|relationship| relationship.relation_type.to_string())
+        .collect();
+    relations.sort();
+    relations.dedup();
+
+    let mut claims = Vec::new();
+    for sentence in sentences(line) {
+        let normalized = normalize(sentence);
+        let mentioned = mentions(&normalized, knowledge);
+        for (i, (subject, subject_at)) in mentioned.iter().enumerate() {
+            for (object, object_at) in &mentioned[i + 1..] {
+                // Both positions are at the space before a name, so the slice keeps both spaces.
+                let between = &normalized[*subject_at..=*object_at];
+                let Some(relation) =
+                    relations.iter().find(|relation| between.contains(&normalize(relation)))
+                else {
+                    continue;
+                };
+                let known: Vec<String> = knowledge
+                    .outgoing(subject)
+                    .filter(|relationship| relationship.relation_type == relation.as_str())
+                    .map(|relationship| relationship.target.to_string())
+                    .collect();
+                let verdict = if known.iter().any(|target| target == object) {
+                    Verdict::Supported
+                } else if known.is_empty() || !FUNCTIONAL_RELATIONS.contains(&relation.as_str()) {
+                    Verdict::Unverified
+                } else {
+                    Verdict::Contradicted { known }
+                };
+                claims.push(Claim {
+                    sentence: sentence.to_string(),
+                    subject: subject.clone(),
+                    relation: relation.clone(),
+                    object: object.clone(),
+                    verdict,
+                });
+            }
+        }
+    }
+    claims
+}
+
+/// Hedges the sentences of a line that make contradicted claims.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use athena::fact_check;
+/// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// for id in ["Brom", "Riverside", "Hilltown"] {
+///     knowledge.add_entity(Entity::new(id, HashMap::new()));
+/// }
+/// knowledge.add_relationship(Relationship::new("Brom", "Riverside", "lives_in", HashMap::new()));
+/// let claims = fact_check::check("Hello! Brom lives in Hilltown.", &knowledge);
+/// assert_eq!(
+///     fact_check::hedge("Hello! Brom lives in Hilltown.", &claims),
+///     "Hello! Brom lives in Hilltown, if I remember right."
+/// );
+/// ```
+pub fn hedge(line: &str, claims: &[Claim]) -> String {
+    sentences(line)
+        .into_iter()
+        .map(|sentence| {
+            let contradicted = claims.iter().any(|claim| {
+                claim.sentence == sentence && matches!(claim.verdict, Verdict::Contradicted { .. })
+            });
+            if contradicted {
+                let stem = sentence.trim_end_matches(['.', '!', '?']);
+                format!("{}, if I remember right.", stem)
+            } else {
+                sentence.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds a directive spelling out the correct facts for contradicted claims.
+pub fn correction_directive(claims: &[Claim]) -> Option<String> {
+    let corrections: Vec<String> = claims
+        .iter()
+        .filter_map(|claim| match &claim.verdict {
+            Verdict::Contradicted { known } => Some(format!(
+                "{} {} {} (not {})",
+                claim.subject,
+                claim.relation.replace('_', " "),
+                known.join(" and "),
+                claim.object
+            )),
+            _ => None,
+        })
+        .collect();
+    if corrections.is_empty() {
+        None
+    } else {
+        Some(format!("Stick to these facts: {}.", corrections.join("; ")))
+    }
+}
+
+/// A validation stage that applies a fact-check policy to the reply. With
+/// `FactCheckPolicy::Regenerate`, a reply with contradictions is regenerated with the correct
+/// facts spelled out, and hedged once the attempts run out.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use athena::dialogue_generation::RequestType;
+/// use athena::fact_check::{FactCheckPolicy, FactCheckStage};
+/// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+/// use athena::pipeline::{PipelineState, Regenerate, Stage};
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// for id in ["Brom", "Riverside", "Hilltown"] {
+///     knowledge.add_entity(Entity::new(id, HashMap::new()));
+/// }
+/// knowledge.add_relationship(Relationship::new("Brom", "Riverside", "lives_in", HashMap::new()));
+///
+/// let policy = FactCheckPolicy::Regenerate { max_attempts: 2 };
+/// let mut stage = FactCheckStage::new(knowledge, policy);
+/// let mut state = PipelineState::new(RequestType::Conversation, "Where does Brom live?");
+/// state.reply = "Brom lives in Hilltown.".to_string();
+/// state.attempts_left = 2;
+/// let error = stage.process(&mut state).unwrap_err();
+/// assert!(error.downcast_ref::<Regenerate>().unwrap().directive.contains("Riverside"));
+///
+/// // The last attempt allowed is hedged instead.
+/// state.attempt = 1;
+/// stage.process(&mut state).unwrap();
+/// assert_eq!(state.reply, "Brom lives in Hilltown, if I remember right.");
+/// ```
+#[cfg(feature = "network")]
+#[derive(Clone)]
+pub struct FactCheckStage {
+    /// The speaking NPC's knowledge, e.g. a `KnowledgeGraph::snapshot` of it.
+    pub knowledge: KnowledgeGraph,
+    pub policy: FactCheckPolicy,
+}
+
+#[cfg(feature = "network")]
+impl FactCheckStage {
+    /// Creates a stage checking replies against the given knowledge.
+    pub fn new(knowledge: KnowledgeGraph, policy: FactCheckPolicy) -> Self {
+        FactCheckStage { knowledge, policy }
+    }
+}
+
+#[cfg(feature = "network")]
+impl Stage for FactCheckStage {
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        if self.policy == FactCheckPolicy::Off {
+            return Ok(());
+        }
+        let claims = check(&state.reply, &self.knowledge);
+        let Some(directive) = correction_directive(&claims) else {
+            return Ok(());
+        };
+        let regenerate = match self.policy {
+            FactCheckPolicy::Regenerate { max_attempts } => {
+                state.attempt + 1 < max_attempts && state.attempts_left > 0
+            }
+            _ => false,
+        };
+        if regenerate {
+            return Err(Regenerate::new(&directive).into());
+        }
+        state.reply = hedge(&state.reply, &claims);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "fact_check"
+    }
+}
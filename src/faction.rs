@@ -0,0 +1,433 @@
+//! # Faction Module
+//!
+//! This module lets groups of NPCs (a guild, a village, a noble house) make decisions together.
+//! A faction looks at its members as a whole: how frightened, angry, and sad they are on
+//! average, how they regard outsiders, and what threats the game has reported to it. From that it
+//! adopts or revokes group-level decisions (declaring a rivalry, raising prices, posting guards),
+//! and pushes each decision down to its members as a goal, which their individual decision
+//! making then acts on.
+//!
+//! Decisions are revoked only once their cause has clearly faded, so a faction does not flip
+//! back and forth around a threshold.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{AgentManager, NpcAgent};
+use crate::emotional_response::Emotion;
+use crate::morality::MoralValues;
+use crate::symbol::Symbol;
+
+/// Represents a group-level decision.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FactionDecision {
+    /// Treat an outsider (a player or another faction) as a rival.
+    DeclareRivalry(String),
+    /// Charge more for goods while times are hard.
+    RaisePrices,
+    /// Keep watch over the faction's territory.
+    PostGuards,
+}
+
+impl FactionDecision {
+    /// Returns the goal members pursue while the decision stands.
+    pub fn goal(&self) -> String {
+        match self {
+            FactionDecision::DeclareRivalry(target) => format!("Stand against {}", target),
+            FactionDecision::RaisePrices => "Charge more while times are hard".to_string(),
+            FactionDecision::PostGuards => "Keep watch for trouble".to_string(),
+        }
+    }
+
+    /// Returns the action members take for the decision's goal, if it calls for one.
+    pub fn action(&self) -> Option<&'static str> {
+        match self {
+            FactionDecision::DeclareRivalry(_) => Some("Confront"),
+            FactionDecision::RaisePrices => None,
+            FactionDecision::PostGuards => Some("Patrol"),
+        }
+    }
+}
+
+/// Represents the aggregated state of a faction's members.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FactionMood {
+    /// The mean intensity of each emotion across members.
+    pub fear: f64,
+    pub anger: f64,
+    pub sadness: f64,
+    pub joy: f64,
+    /// How the members regard each assessed outsider on average (between -1.0 and 1.0).
+    pub standings: BTreeMap<String, f64>,
+}
+
+/// Configures when a faction adopts its decisions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactionPolicy {
+    /// The pressure (threat plus mean fear and half the mean anger) at which guards are posted.
+    pub guard_threshold: f64,
+    /// The threat at which prices are raised.
+    pub price_threshold: f64,
+    /// The standing with an outsider (lowered by grievances and anger) at or below which the
+    /// faction declares a rivalry.
+    pub rivalry_threshold: f64,
+    /// How long it takes threat and grievances to halve, in seconds of game time.
+    pub half_life: f64,
+    /// The price multiplier at full threat while prices are raised.
+    pub max_price_factor: f64,
+}
+
+impl Default for FactionPolicy {
+    /// Threat and grievances halve every game day.
+    fn default() -> Self {
+        FactionPolicy {
+            guard_threshold: 0.5,
+            price_threshold: 0.4,
+            rivalry_threshold: -0.4,
+            half_life: 86_400.0,
+            max_price_factor: 1.5,
+        }
+    }
+}
+
+/// Represents the decisions a faction adopted and revoked in one deliberation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FactionChanges {
+    pub adopted: Vec<FactionDecision>,
+    pub revoked: Vec<FactionDecision>,
+}
+
+/// Represents a faction and the decisions it currently stands by.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Faction {
+    pub id: Symbol,
+    /// The IDs of the member agents.
+    pub members: Vec<Symbol>,
+    pub policy: FactionPolicy,
+    /// How threatened the faction feels by recent events (between 0.0 and 1.0).
+    threat: f64,
+    /// How much the faction blames each outsider for recent events (between 0.0 and 1.0).
+    grievances: HashMap<String, f64>,
+    /// The decisions in force, in the order they were adopted.
+    decisions: Vec<FactionDecision>,
+    /// The moral values the faction expects of its members, if any.
+    #[serde(default)]
+    pub morals: Option<MoralValues>,
+}
+
+impl Faction {
+    /// Creates a new faction without members.
+    pub fn new(id: &str) -> Self {
+        Faction {
+            id: Symbol::new(id),
+            members: Vec::new(),
+            policy: FactionPolicy::default(),
+            threat: 0.0,
+            grievances: HashMap::new(),
+            decisions: Vec::new(),
+            morals: None,
+        }
+    }
+
+    /// Adds a member, unless it already is one.
+    pub fn add_member(&mut self, agent_id: &str) {
+        if !self.members.iter().any(|member| member == agent_id) {
+            self.members.push(Symbol::new(agent_id));
+        }
+    }
+
+    /// Gets how threatened the faction feels.
+    pub fn get_threat(&self) -> f64 {
+        self.threat
+    }
+
+    /// Gets how much the faction blames an outsider.
+    pub fn get_grievance(&self, target: &str) -> f64 {
+        self.grievances.get(target).copied().unwrap_or(0.0)
+    }
+
+    /// Gets the decisions in force, in the order they were adopted.
+    pub fn get_decisions(&self) -> &[FactionDecision] {
+        &self.decisions
+    }
+
+    /// Reports an event that concerns the faction (e.g. a raid on its caravans).
+    ///
+    /// # Arguments
+    ///
+    /// * `threat` - How threatening the event is (between 0.0 and 1.0).
+    /// * `blamed` - Who the faction blames for it, if anyone.
+    pub fn record_event(&mut self, threat: f64, blamed: Option<&str>) {
+        let threat = threat.clamp(0.0, 1.0);
+        self.threat = 1.0 - (1.0 - self.threat) * (1.0 - threat);
+        if let Some(target) = blamed {
+            let grievance = self.grievances.entry(target.to_string()).or_default();
+            *grievance = 1.0 - (1.0 - *grievance) * (1.0 - threat);
+        }
+    }
+
+    /// Lets threat and grievances fade over the given amount of game time.
+    pub fn update(&mut self, dt: f64) {
+        if self.policy.half_life <= 0.0 {
+            return;
+        }
+        let factor = 0.5_f64.powf(dt.max(0.0) / self.policy.half_life);
+        self.threat *= factor;
+        for grievance in self.grievances.values_mut() {
+            *grievance *= factor;
+        }
+        self.grievances.retain(|_, grievance| *grievance > 1e-3);
+    }
+
+    /// Aggregates the emotions of the members present in a manager and their standing with
+    /// outsiders.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - The manager holding the members.
+    /// * `outsiders` - The outsiders (players or other factions' members) to assess standing with.
+    pub fn assess(&self, manager: &AgentManager, outsiders: &[&str]) -> FactionMood {
+        let members: Vec<&NpcAgent> = self
+            .members
+            .iter()
+            .filter_map(|member| manager.get_agent(member.as_str()))
+            .collect();
+        let mut mood = FactionMood::default();
+        if members.is_empty() {
+            return mood;
+        }
+        let count = members.len() as f64;
+        let mean = |emotion: Emotion| {
+            members.iter().map(|agent| agent.emotions.get_intensity(&emotion)).sum::<f64>() / count
+        };
+        mood.fear = mean(Emotion::Fear);
+        mood.anger = mean(Emotion::Anger);
+        mood.sadness = mean(Emotion::Sadness);
+        mood.joy = mean(Emotion::Joy);
+        for outsider in outsiders {
+            let standing = members
+                .iter()
+                .map(|agent| {
+                    let affinity = agent.get_interlocutor(outsider).map_or(0.0, |c| c.affinity);
+                    (affinity + agent.disposition_towards(outsider).warmth).clamp(-1.0, 1.0)
+                })
+                .sum::<f64>()
+                / count;
+            mood.standings.insert(outsider.to_string(), standing);
+        }
+        mood
+    }
+
+    /// Deliberates on the members' mood and the recorded events, adopting and revoking
+    /// decisions.
+    ///
+    /// Guards are posted under pressure from threat and the members' fear and anger, prices
+    /// are raised under threat, and a rivalry is declared with an outsider the members dislike,
+    /// blame, or are angry at. Each decision is revoked once its cause falls to half its
+    /// threshold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::faction::{Faction, FactionDecision, FactionMood};
+    ///
+    /// let mut guild = Faction::new("merchants_guild");
+    /// guild.record_event(0.6, Some("bandits"));
+    /// let mut mood = FactionMood::default();
+    /// mood.standings.insert("bandits".to_string(), -0.2);
+    /// let changes = guild.decide(&mood);
+    /// assert!(changes.adopted.contains(&FactionDecision::PostGuards));
+    /// assert!(changes.adopted.contains(&FactionDecision::DeclareRivalry("bandits".to_string())));
+    ///
+    /// guild.update(3.0 * 86_400.0);
+    /// let changes = guild.decide(&FactionMood::default());
+    /// assert!(changes.revoked.contains(&FactionDecision::RaisePrices));
+    /// assert!(guild.get_decisions().is_empty());
+    /// ```
+    pub fn decide(&mut self, mood: &FactionMood) -> FactionChanges {
+        let policy = &self.policy;
+        let pressure = self.threat + mood.fear + 0.5 * mood.anger;
+        let mut wanted: Vec<(FactionDecision, bool, bool)> = vec![
+            (
+                FactionDecision::PostGuards,
+                pressure >= policy.guard_threshold,
+                pressure >= policy.guard_threshold / 2.0,
+            ),
+            (
+                FactionDecision::RaisePrices,
+                self.threat >= policy.price_threshold,
+                self.threat >= policy.price_threshold / 2.0,
+            ),
+        ];
+        let mut targets: Vec<&String> =
+            mood.standings.keys().chain(self.grievances.keys()).collect();
+        targets.sort();
+        targets.dedup();
+        for target in targets {
+            let standing = mood.standings.get(target).copied().unwrap_or(0.0);
+            let hostility = standing - self.get_grievance(target) - 0.5 * mood.anger;
+            wanted.push((
+                FactionDecision::DeclareRivalry(target.clone()),
+                hostility <= policy.rivalry_threshold,
+                hostility <= policy.rivalry_threshold / 2.0,
+            ));
+        }
+        // Rivalries with outsiders that are no longer assessed or blamed lapse.
+        for decision in &self.decisions {
+            if !wanted.iter().any(|(wanted, _, _)| wanted == decision) {
+                wanted.push((decision.clone(), false, false));
+            }
+        }
+
+        let mut changes = FactionChanges::default();
+        for (decision, adopt, keep) in wanted {
+            let active = self.decisions.contains(&decision);
+            if !active && adopt {
+                self.decisions.push(decision.clone());
+                changes.adopted.push(decision);
+            } else if active && !keep {
+                self.decisions.retain(|active| *active != decision);
+                changes.revoked.push(decision);
+            }
+        }
+        changes
+    }
+
+    /// Returns the multiplier members apply to their prices: rising with the threat up to
+    /// `max_price_factor` while prices are raised, 1.0 otherwise.
+    pub fn price_factor(&self) -> f64 {
+        if self.decisions.contains(&FactionDecision::RaisePrices) {
+            1.0 + (self.policy.max_price_factor - 1.0) * self.threat
+        } else {
+            1.0
+        }
+    }
+
+    /// Pushes decisions down to the members: every active decision becomes a goal (with the
+    /// action it calls for), so members who joined since the decision was adopted take it up
+    /// too, and the goals of revoked decisions are dropped. Members' prices follow the
+    /// faction's `price_factor`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::faction::{Faction, FactionChanges};
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(NpcAgent::new("watchman", vec![]));
+    /// manager.add_agent(NpcAgent::new("recruit", vec![]));
+    /// let mut village = Faction::new("village");
+    /// village.add_member("watchman");
+    /// village.record_event(1.0, None);
+    /// village.deliberate(&mut manager, &[], 0.0);
+    ///
+    /// village.add_member("recruit");
+    /// village.apply(&FactionChanges::default(), &mut manager);
+    /// let recruit = manager.get_agent_mut("recruit").unwrap();
+    /// assert!(recruit.goals.contains(&"Keep watch for trouble".to_string()));
+    /// assert!(recruit.price_factor("alice") > 1.0);
+    /// assert!(recruit.score_actions().iter().any(|explanation| explanation.action == "Patrol"));
+    /// ```
+    pub fn apply(&self, changes: &FactionChanges, manager: &mut AgentManager) {
+        let price_factor = self.price_factor();
+        for member in &self.members {
+            let Some(agent) = manager.get_agent_mut(member.as_str()) else {
+                continue;
+            };
+            for decision in &changes.revoked {
+                let goal = decision.goal();
+                agent.goals.retain(|existing| *existing != goal);
+                agent.goal_actions.remove(&goal);
+            }
+            for decision in &self.decisions {
+                let goal = decision.goal();
+                if let Some(action) = decision.action() {
+                    agent.goal_actions.insert(goal.clone(), action.to_string());
+                }
+                if !agent.goals.contains(&goal) {
+                    agent.goals.push(goal);
+                }
+            }
+            agent.faction_price_factor = price_factor;
+        }
+    }
+
+    /// Moves the moral values of the members towards those of the faction, if it has any.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - The manager holding the members.
+    /// * `weight` - How far to move (0.0 changes nothing, 1.0 adopts the faction's values).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::faction::Faction;
+    /// use athena::morality::MoralValues;
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// manager.add_agent(NpcAgent::new("guard", vec![]));
+    /// let mut watch = Faction::new("city_watch");
+    /// watch.add_member("guard");
+    /// watch.morals = Some(MoralValues { authority: 0.9, ..MoralValues::default() });
+    /// watch.instill_morals(&mut manager, 0.5);
+    /// assert!((manager.get_agent("guard").unwrap().morals.authority - 0.7).abs() < 1e-9);
+    /// ```
+    pub fn instill_morals(&self, manager: &mut AgentManager, weight: f64) {
+        let Some(morals) = &self.morals else {
+            return;
+        };
+        for member in &self.members {
+            if let Some(agent) = manager.get_agent_mut(member.as_str()) {
+                agent.morals = agent.morals.blend(morals, weight);
+            }
+        }
+    }
+
+    /// Runs one full deliberation: lets events fade, assesses the members, decides, and pushes
+    /// the changes down to the members.
+    ///
+    /// # Arguments
+    ///
+    /// * `manager` - The manager holding the members.
+    /// * `outsiders` - The outsiders to assess standing with.
+    /// * `dt` - The game time since the last deliberation, in seconds.
+    ///
+    /// # Returns
+    ///
+    /// The decisions adopted and revoked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::emotional_response::Emotion;
+    /// use athena::faction::Faction;
+    ///
+    /// let mut manager = AgentManager::new(1.0);
+    /// let mut watchman = NpcAgent::new("watchman", vec![]);
+    /// watchman.emotions.set_intensity(Emotion::Fear, 0.7);
+    /// manager.add_agent(watchman);
+    ///
+    /// let mut village = Faction::new("village");
+    /// village.add_member("watchman");
+    /// village.deliberate(&mut manager, &[], 0.0);
+    /// let goals = &manager.get_agent("watchman").unwrap().goals;
+    /// assert!(goals.contains(&"Keep watch for trouble".to_string()));
+    /// ```
+    pub fn deliberate(
+        &mut self,
+        manager: &mut AgentManager,
+        outsiders: &[&str],
+        dt: f64,
+    ) -> FactionChanges {
+        self.update(dt);
+        let mood = self.assess(manager, outsiders);
+        let changes = self.decide(&mood);
+        self.apply(&changes, manager);
+        changes
+    }
+}
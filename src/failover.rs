@@ -0,0 +1,213 @@
+//! # Failover Module
+//!
+//! This module tracks the health of language model providers, so a client can fail over from
+//! one provider to the next (e.g. Groq, then OpenAI, then a local model) without an outage
+//! silencing every NPC. Each provider has a circuit breaker: after a number of consecutive
+//! failures it opens, and the provider is skipped until a cooldown has passed. A single request
+//! is then let through as a trial while the others keep skipping the provider; a success closes
+//! the breaker again, a failure reopens it.
+//!
+//! Skipping a provider that is known to be down keeps requests from waiting on its timeouts
+//! before reaching a healthy one.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Represents when circuit breakers open and how long they stay open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakerPolicy {
+    /// The number of consecutive failures that opens a breaker.
+    pub failure_threshold: u32,
+    /// How long an open breaker skips its provider before letting a trial request through.
+    pub cooldown: Duration,
+}
+
+impl Default for BreakerPolicy {
+    fn default() -> Self {
+        BreakerPolicy {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Represents the state of a circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CircuitState {
+    /// The provider is healthy and receives requests.
+    Closed,
+    /// The provider failed repeatedly and is skipped.
+    Open,
+    /// The cooldown has passed; the next request is a trial, and the provider is skipped while
+    /// it runs.
+    HalfOpen,
+}
+
+/// Tracks the health of one provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    consecutive_failures: u32,
+    /// When the breaker last opened, if it is open.
+    opened_at: Option<Instant>,
+    /// When the running trial request was let through, if one is running.
+    trial_started: Option<Instant>,
+    successes: u64,
+    failures: u64,
+}
+
+impl CircuitBreaker {
+    /// Creates a new, closed breaker.
+    pub fn new() -> Self {
+        CircuitBreaker::default()
+    }
+
+    /// Gets the state of the breaker at a time.
+    pub fn state(&self, now: Instant, policy: &BreakerPolicy) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if now.duration_since(opened_at) >= policy.cooldown => {
+                CircuitState::HalfOpen
+            }
+            Some(_) => CircuitState::Open,
+        }
+    }
+
+    /// Returns true if the breaker is not open at a time. A half-open breaker may still be
+    /// running its trial; see `acquire`.
+    pub fn allows(&self, now: Instant, policy: &BreakerPolicy) -> bool {
+        self.state(now, policy) != CircuitState::Open
+    }
+
+    /// Claims the right to send a request to the provider at a time. A closed breaker lets every
+    /// request through, and a half-open one only a single trial until its outcome is recorded
+    /// (or a cooldown has passed, in case the trial was dropped).
+    ///
+    /// # Returns
+    ///
+    /// True if the request may be sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use athena::failover::{BreakerPolicy, CircuitBreaker};
+    ///
+    /// let policy = BreakerPolicy { failure_threshold: 1, cooldown: Duration::from_secs(30) };
+    /// let mut breaker = CircuitBreaker::new();
+    /// let start = Instant::now();
+    /// breaker.record_failure(start, &policy);
+    /// assert!(!breaker.acquire(start, &policy));
+    ///
+    /// let later = start + Duration::from_secs(31);
+    /// assert!(breaker.acquire(later, &policy));
+    /// assert!(!breaker.acquire(later, &policy));
+    /// breaker.record_success();
+    /// assert!(breaker.acquire(later, &policy));
+    /// assert!(breaker.acquire(later, &policy));
+    /// ```
+    pub fn acquire(&mut self, now: Instant, policy: &BreakerPolicy) -> bool {
+        match self.state(now, policy) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                let running = self
+                    .trial_started
+                    .is_some_and(|started| now.duration_since(started) < policy.cooldown);
+                if !running {
+                    self.trial_started = Some(now);
+                }
+                !running
+            }
+        }
+    }
+
+    /// Ends a request that says nothing about the provider's health (e.g. one it rejected as
+    /// malformed), letting another trial through if it was one.
+    pub fn release(&mut self) {
+        self.trial_started = None;
+    }
+
+    /// Records a successful request, closing the breaker.
+    pub fn record_success(&mut self) {
+        self.successes += 1;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.trial_started = None;
+    }
+
+    /// Records a failed request, opening the breaker once the failures reach the threshold. A
+    /// failed trial reopens it at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use athena::failover::{BreakerPolicy, CircuitBreaker, CircuitState};
+    ///
+    /// let policy = BreakerPolicy { failure_threshold: 2, cooldown: Duration::from_secs(30) };
+    /// let mut breaker = CircuitBreaker::new();
+    /// let start = Instant::now();
+    /// breaker.record_failure(start, &policy);
+    /// assert_eq!(breaker.state(start, &policy), CircuitState::Closed);
+    /// breaker.record_failure(start, &policy);
+    /// assert!(!breaker.allows(start, &policy));
+    ///
+    /// let later = start + Duration::from_secs(31);
+    /// assert_eq!(breaker.state(later, &policy), CircuitState::HalfOpen);
+    /// breaker.record_success();
+    /// assert_eq!(breaker.state(later, &policy), CircuitState::Closed);
+    /// ```
+    pub fn record_failure(&mut self, now: Instant, policy: &BreakerPolicy) {
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= policy.failure_threshold || self.opened_at.is_some() {
+            self.opened_at = Some(now);
+        }
+        self.trial_started = None;
+    }
+
+    /// Gets the number of failures since the last success.
+    pub fn get_consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Gets the number of successful requests.
+    pub fn get_successes(&self) -> u64 {
+        self.successes
+    }
+
+    /// Gets the number of failed requests.
+    pub fn get_failures(&self) -> u64 {
+        self.failures
+    }
+}
+
+/// Represents a snapshot of a provider's health.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderHealth {
+    /// The provider's API root.
+    pub base_url: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub successes: u64,
+    pub failures: u64,
+}
+
+impl ProviderHealth {
+    /// Takes a snapshot of a provider's breaker at a time.
+    pub fn of(
+        base_url: &str,
+        breaker: &CircuitBreaker,
+        now: Instant,
+        policy: &BreakerPolicy,
+    ) -> Self {
+        ProviderHealth {
+            base_url: base_url.to_string(),
+            state: breaker.state(now, policy),
+            consecutive_failures: breaker.consecutive_failures,
+            successes: breaker.successes,
+            failures: breaker.failures,
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! # Gifts Module
+//!
+//! This module decides how an NPC reacts to a gift. Preferences live in the NPC's knowledge
+//! graph as "likes" and "dislikes" relationships from the NPC to an item or a category of items
+//! (the `category` property of the item's entity), weighted by how strongly they are held. A gift
+//! is scored against them, and the score sets the change in affinity, the emotion it provokes,
+//! and the reaction: an authored line, or a directive for generating one.
+//!
+//! Preferences are learned from what NPCs say ("I love roses", "I can't stand turnips"), so a
+//! preference mentioned in generated dialogue is honoured when a gift arrives later.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::emotional_response::Emotion;
+use crate::knowledge_graph::{KnowledgeGraph, Relationship};
+use crate::relationship::with_article;
+
+/// Phrases that state a preference, with the strength they state, strongest first.
+const PREFERENCE_PHRASES: [(&str, f64); 11] = [
+    ("i can't stand ", -1.0),
+    ("i cannot stand ", -1.0),
+    ("i despise ", -1.0),
+    ("i hate ", -1.0),
+    ("i don't like ", -0.6),
+    ("i dislike ", -0.6),
+    ("i adore ", 1.0),
+    ("i love ", 1.0),
+    ("i'm fond of ", 0.6),
+    ("i enjoy ", 0.6),
+    ("i like ", 0.6),
+];
+
+/// Words that start what follows a preference phrase when it is not about an item, as in
+/// "I love you", "I hate to say", or "I like it when".
+const NOT_ITEMS: [&str; 14] = [
+    "you", "your", "him", "her", "them", "it", "me", "us", "this", "that", "to", "when", "how",
+    "being",
+];
+
+/// Normalizes an item or category name so "Roses" and "rose" match.
+fn normalize(subject: &str) -> String {
+    let subject = subject.trim().to_lowercase();
+    let subject = ["a ", "an ", "the ", "some ", "good "]
+        .iter()
+        .fold(subject, |subject, article| {
+            subject.strip_prefix(article).map(str::to_string).unwrap_or(subject)
+        });
+    match subject.strip_suffix('s') {
+        Some(singular) if singular.len() > 2 && !singular.ends_with('s') => singular.to_string(),
+        _ => subject,
+    }
+}
+
+/// Gets the preference an NPC has stated for a subject, if any.
+fn stated(knowledge: &KnowledgeGraph, npc_id: &str, subject: &str) -> Option<f64> {
+    let subject = normalize(subject);
+    knowledge
+        .outgoing(npc_id)
+        .filter(|relationship| normalize(relationship.target.as_str()) == subject)
+        .find_map(|relationship| match relationship.relation_type.as_str() {
+            "likes" => Some(relationship.weight),
+            "dislikes" => Some(-relationship.weight),
+            _ => None,
+        })
+}
+
+/// Gets how much an NPC likes an item (between -1.0 and 1.0). A preference for the item itself
+/// wins over one for its category; without either, the NPC does not mind it (0.0).
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use athena::gifts::{learn_preference, preference};
+/// use athena::knowledge_graph::{Entity, KnowledgeGraph};
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// let properties = HashMap::from([("category".to_string(), "flowers".to_string())]);
+/// knowledge.add_entity(Entity::new("rose", properties));
+/// learn_preference(&mut knowledge, "mira", "flowers", 0.6);
+/// assert_eq!(preference(&knowledge, "mira", "rose"), 0.6);
+/// learn_preference(&mut knowledge, "mira", "roses", -1.0);
+/// assert_eq!(preference(&knowledge, "mira", "rose"), -1.0);
+/// assert_eq!(preference(&knowledge, "mira", "turnip"), 0.0);
+/// ```
+pub fn preference(knowledge: &KnowledgeGraph, npc_id: &str, item: &str) -> f64 {
+    stated(knowledge, npc_id, item)
+        .or_else(|| {
+            let category = knowledge.get_entity(item)?.properties.get("category")?;
+            stated(knowledge, npc_id, category)
+        })
+        .unwrap_or(0.0)
+}
+
+/// Stores a preference in the NPC's knowledge graph, replacing any earlier one for the same
+/// subject.
+///
+/// # Arguments
+///
+/// * `subject` - An item or a category of items.
+/// * `strength` - From -1.0 (hates it) to 1.0 (loves it).
+pub fn learn_preference(
+    knowledge: &mut KnowledgeGraph,
+    npc_id: &str,
+    subject: &str,
+    strength: f64,
+) {
+    let normalized = normalize(subject);
+    let existing: Vec<(String, String)> = knowledge
+        .outgoing(npc_id)
+        .filter(|relationship| normalize(relationship.target.as_str()) == normalized)
+        .filter(|relationship| ["likes", "dislikes"].contains(&relationship.relation_type.as_str()))
+        .map(|relationship| {
+            (relationship.target.to_string(), relationship.relation_type.to_string())
+        })
+        .collect();
+    for (target, relation) in existing {
+        knowledge.remove_relationship(npc_id, &target, &relation);
+    }
+    let strength = strength.clamp(-1.0, 1.0);
+    let relation = if strength < 0.0 { "dislikes" } else { "likes" };
+    let relationship = Relationship::new(npc_id, normalized, relation, HashMap::new());
+    knowledge.add_relationship(relationship.with_weight(strength.abs()));
+}
+
+/// Finds the preferences stated in a line, as `(subject, strength)` pairs.
+///
+/// # Examples
+///
+/// ```
+/// use athena::gifts::extract_preferences;
+///
+/// let stated = extract_preferences("Ah, I really love roses. But I can't stand turnips!");
+/// assert_eq!(stated, vec![("roses".to_string(), 1.0), ("turnips".to_string(), -1.0)]);
+/// assert!(extract_preferences("I love you. I hate to say it, but I like it when it rains.")
+///     .is_empty());
+/// ```
+pub fn extract_preferences(line: &str) -> Vec<(String, f64)> {
+    let lower = line.to_lowercase().replace(" really ", " ").replace('’', "'");
+    let mut found = Vec::new();
+    for sentence in lower.split(['.', '!', '?', ';', ',']) {
+        let sentence = format!("{} ", sentence.trim());
+        let states = |phrase: &str| {
+            sentence.starts_with(phrase) || sentence.contains(&format!(" {}", phrase))
+        };
+        let Some((phrase, strength)) = PREFERENCE_PHRASES.iter().find(|(phrase, _)| states(phrase))
+        else {
+            continue;
+        };
+        let start = sentence.find(phrase).unwrap_or(0) + phrase.len();
+        let subject = sentence[start..].split(" and ").next().unwrap_or("").trim();
+        let first = subject.split_whitespace().next().unwrap_or("");
+        if !subject.is_empty() && !NOT_ITEMS.contains(&first) {
+            found.push((subject.to_string(), *strength));
+        }
+    }
+    found
+}
+
+/// Represents how an NPC reacts to a gift.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GiftReaction {
+    pub item: String,
+    /// How much the NPC likes the item (between -1.0 and 1.0).
+    pub preference: f64,
+    /// The change in affinity towards the giver.
+    pub affinity: f64,
+    pub emotion: Emotion,
+    pub intensity: f64,
+    /// The authored reaction line.
+    pub line: String,
+}
+
+/// Represents how an NPC feels about an item, from its preference for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Feeling {
+    Love,
+    Like,
+    Indifferent,
+    Dislike,
+    Hate,
+}
+
+impl Feeling {
+    /// Gets the feeling a preference (between -1.0 and 1.0) amounts to.
+    pub fn from_preference(preference: f64) -> Self {
+        match preference {
+            p if p >= 0.6 => Feeling::Love,
+            p if p > 0.2 => Feeling::Like,
+            p if p > -0.2 => Feeling::Indifferent,
+            p if p > -0.6 => Feeling::Dislike,
+            _ => Feeling::Hate,
+        }
+    }
+
+    /// Describes the feeling as a verb ("love", "don't mind", ...).
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Feeling::Love => "love",
+            Feeling::Like => "like",
+            Feeling::Indifferent => "don't mind",
+            Feeling::Dislike => "dislike",
+            Feeling::Hate => "hate",
+        }
+    }
+}
+
+impl GiftReaction {
+    /// Gets how the NPC feels about the item.
+    pub fn feeling(&self) -> Feeling {
+        Feeling::from_preference(self.preference)
+    }
+
+    /// Builds a prompt directive for generating the reaction line instead of using the
+    /// authored one.
+    pub fn directive(&self) -> String {
+        format!(
+            "You have just been given {} as a gift. You {} it; react in one short line.",
+            with_article(&self.item),
+            self.feeling().verb()
+        )
+    }
+}
+
+/// Evaluates a gift against an NPC's preferences.
+///
+/// # Arguments
+///
+/// * `knowledge` - The NPC's knowledge graph, holding its preferences.
+/// * `npc_id` - The NPC receiving the gift.
+/// * `item` - The item given.
+/// * `value` - What the item is worth to the NPC (between 0.0 and 1.0); a valuable gift counts
+///   for more, unless the NPC dislikes it.
+///
+/// # Examples
+///
+/// ```
+/// use athena::emotional_response::Emotion;
+/// use athena::gifts::{evaluate, learn_preference};
+/// use athena::knowledge_graph::KnowledgeGraph;
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// learn_preference(&mut knowledge, "mira", "rose", 1.0);
+/// let reaction = evaluate(&knowledge, "mira", "rose", 0.5);
+/// assert_eq!(reaction.emotion, Emotion::Joy);
+/// assert!(reaction.affinity > 0.1);
+/// assert_eq!(reaction.line, "A rose! How did you know? I love these!");
+/// ```
+pub fn evaluate(knowledge: &KnowledgeGraph, npc_id: &str, item: &str, value: f64) -> GiftReaction {
+    let preference = preference(knowledge, npc_id, item);
+    let worth = value.clamp(0.0, 1.0);
+    let affinity = 0.03 + 0.15 * preference + if preference >= 0.0 { 0.05 * worth } else { 0.0 };
+    let mut reaction = GiftReaction {
+        item: item.to_string(),
+        preference,
+        affinity,
+        emotion: Emotion::Joy,
+        intensity: 0.0,
+        line: String::new(),
+    };
+    let (emotion, intensity, line) = match reaction.feeling() {
+        Feeling::Love => (
+            Emotion::Joy,
+            0.8,
+            format!("{}! How did you know? I love these!", with_article(item)),
+        ),
+        Feeling::Like => (Emotion::Joy, 0.5, format!("Thank you, I do like {}.", with_article(item))),
+        Feeling::Indifferent => (Emotion::Joy, 0.2, format!("Oh... thank you for the {}.", item)),
+        Feeling::Dislike => (
+            Emotion::Disgust,
+            0.3,
+            format!("{}? ...I'll find a use for it, I suppose.", with_article(item)),
+        ),
+        Feeling::Hate => {
+            let line = format!("Get that {} away from me!", item);
+            (Emotion::Disgust, 0.6, line)
+        }
+    };
+    reaction.emotion = emotion;
+    reaction.intensity = intensity;
+    reaction.line = capitalize(&line);
+    reaction
+}
+
+/// Capitalizes the first letter of a line.
+fn capitalize(line: &str) -> String {
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
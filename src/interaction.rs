@@ -0,0 +1,127 @@
+//! # Interaction Module
+//!
+//! This module lets NPCs interact with each other while the player is elsewhere. When two agents
+//! meet they swap gossip, their relationship drifts according to how compatible they are, and
+//! each remembers a short summary of the encounter. The summaries are kept as partner memories,
+//! so the player can later discover who met whom and what was said.
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::NpcAgent;
+use crate::companionship;
+use crate::crime::{self, LawSystem};
+
+/// How long two agents wait before interacting with each other again, in seconds of game time.
+pub const INTERACTION_COOLDOWN: f64 = 600.0;
+
+/// How much familiarity each interaction adds.
+const FAMILIARITY_PER_INTERACTION: f64 = 0.02;
+
+/// Represents what happened when two NPCs met.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InteractionSummary {
+    /// The agent who started the interaction.
+    pub initiator: String,
+    /// The agent who was approached.
+    pub partner: String,
+    pub game_time: f64,
+    /// The number of crimes each side told the other about, initiator first.
+    pub gossip_shared: (usize, usize),
+    /// How much their affinity towards each other changed.
+    pub affinity_change: f64,
+    /// A one-line description of the encounter.
+    pub summary: String,
+}
+
+/// Has two agents interact.
+///
+/// Both share the crimes they know about, adjust their affinity towards each other by their
+/// personality compatibility, and store the summary as a memory about the other under the key
+/// `interaction:<game time>`. Neither agent will interact with the other again until
+/// `INTERACTION_COOLDOWN` has passed.
+///
+/// # Arguments
+///
+/// * `initiator` - The agent who starts the interaction.
+/// * `partner` - The agent who is approached.
+/// * `law` - The law system that defines which gossip is about crimes.
+///
+/// # Returns
+///
+/// A summary of the interaction.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::crime::{self, CrimeEvent, LawSystem};
+/// use athena::interaction;
+///
+/// let law = LawSystem::default();
+/// let mut baker = NpcAgent::new("baker", vec![]);
+/// let mut miller = NpcAgent::new("miller", vec![]);
+/// let event = CrimeEvent {
+///     id: "crime_1".to_string(),
+///     crime: "theft".to_string(),
+///     perpetrator: "alice".to_string(),
+///     victim: None,
+///     location: "market".to_string(),
+///     game_time: 0.0,
+/// };
+/// crime::witness(&mut baker, &event, &law);
+///
+/// let summary = interaction::interact(&mut baker, &mut miller, &law);
+/// assert_eq!(summary.gossip_shared, (1, 0));
+/// assert!(!miller.can_engage("baker"));
+/// let memory = miller.get_interlocutor("baker").unwrap().get_memory("interaction:0").unwrap();
+/// assert!(memory.contains("told miller about 1 crime"));
+/// ```
+pub fn interact(
+    initiator: &mut NpcAgent,
+    partner: &mut NpcAgent,
+    law: &LawSystem,
+) -> InteractionSummary {
+    let game_time = initiator.get_game_time();
+    let told = crime::gossip(initiator, partner, law);
+    let heard = crime::gossip(partner, initiator, law);
+    let compatibility = companionship::compatibility(&initiator.personality, &partner.personality);
+    let affinity_change = 0.1 * (compatibility - 0.5);
+
+    let (initiator_id, partner_id) = (initiator.id.to_string(), partner.id.to_string());
+    let mut summary = match initiator.schedule.current_block() {
+        Some(block) => format!(
+            "{} chatted with {} while {} at {}",
+            initiator_id, partner_id, block.activity, block.location
+        ),
+        None => format!("{} chatted with {}", initiator_id, partner_id),
+    };
+    let exchanges = [
+        (&initiator_id, &partner_id, told),
+        (&partner_id, &initiator_id, heard),
+    ];
+    for (teller, listener, count) in exchanges {
+        if count > 0 {
+            let crimes = if count == 1 { "crime" } else { "crimes" };
+            let clause = format!("; {} told {} about {} {}", teller, listener, count, crimes);
+            summary.push_str(&clause);
+        }
+    }
+    summary.push('.');
+
+    for (agent, other_id) in [(initiator, &partner_id), (partner, &initiator_id)] {
+        let context = agent.interlocutor(other_id);
+        context.adjust_affinity(affinity_change);
+        context.familiarity = (context.familiarity + FAMILIARITY_PER_INTERACTION).min(1.0);
+        context.cooldown_until = game_time + INTERACTION_COOLDOWN;
+        context.record_memory(&format!("interaction:{}", game_time), &summary);
+    }
+
+    InteractionSummary {
+        initiator: initiator_id,
+        partner: partner_id,
+        game_time,
+        gossip_shared: (told, heard),
+        affinity_change,
+        summary,
+    }
+}
@@ -0,0 +1,160 @@
+//! # Interlocutor Module
+//!
+//! This module holds everything an NPC keeps about one specific conversation partner: the
+//! conversation history, relationship scores, and partner-specific memories. An NPC in a
+//! multiplayer or co-op game keeps one `InterlocutorContext` per player, so it can treat each
+//! player distinctly without needing separate agent instances.
+
+use std::collections::HashMap;
+
+use crate::annoyance::Annoyance;
+use crate::checkpoint::SessionNotes;
+use crate::companionship::{BondStage, Companionship};
+use crate::conversation::Conversation;
+use crate::conversation_goal::ConversationGoal;
+use crate::reputation::Reputation;
+use crate::topic::TopicTracker;
+
+/// Represents what an NPC knows about and feels towards one conversation partner.
+#[derive(Debug, Clone)]
+pub struct InterlocutorContext {
+    /// The conversation history with this partner.
+    pub conversation: Conversation,
+    /// How much the NPC likes the partner (between -1.0 and 1.0).
+    pub affinity: f64,
+    /// How much the NPC trusts the partner (between -1.0 and 1.0).
+    pub trust: f64,
+    /// How well the NPC knows the partner (between 0.0 and 1.0).
+    pub familiarity: f64,
+    /// The partner's deeds as remembered by the NPC.
+    pub reputation: Reputation,
+    /// The progression of the bond with the partner; `None` unless companionship is enabled.
+    pub companionship: Option<Companionship>,
+    /// How many lines the partner has said in the current conversation.
+    pub session_turns: usize,
+    /// How many rude lines the partner has said in the current conversation.
+    pub rudeness: u32,
+    /// The game time before which the NPC will not engage with the partner again.
+    pub cooldown_until: f64,
+    /// What the NPC wants from the current conversation.
+    pub conversation_goals: Vec<ConversationGoal>,
+    /// How much the partner's pestering annoys the NPC.
+    pub annoyance: Annoyance,
+    /// The notes the NPC takes during the current conversation.
+    pub notes: SessionNotes,
+    /// The topics of the current conversation.
+    pub topics: TopicTracker,
+    /// Partner-specific memories, keyed like `AdaptiveIntelligence` memories.
+    memories: HashMap<String, String>,
+}
+
+impl InterlocutorContext {
+    /// Creates a new context for a partner the NPC has never met.
+    ///
+    /// # Arguments
+    ///
+    /// * `npc_id` - The ID of the NPC.
+    /// * `partner_id` - The ID of the conversation partner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::interlocutor::InterlocutorContext;
+    /// let context = InterlocutorContext::new("blacksmith", "player_1");
+    /// assert_eq!(context.affinity, 0.0);
+    /// ```
+    pub fn new(npc_id: &str, partner_id: &str) -> Self {
+        InterlocutorContext {
+            conversation: Conversation::new(npc_id, partner_id),
+            affinity: 0.0,
+            trust: 0.0,
+            familiarity: 0.0,
+            reputation: Reputation::default(),
+            companionship: None,
+            session_turns: 0,
+            rudeness: 0,
+            cooldown_until: 0.0,
+            conversation_goals: Vec::new(),
+            annoyance: Annoyance::default(),
+            notes: SessionNotes::default(),
+            topics: TopicTracker::default(),
+            memories: HashMap::new(),
+        }
+    }
+
+    /// Adjusts the affinity towards the partner, clamped to -1.0..=1.0.
+    pub fn adjust_affinity(&mut self, delta: f64) {
+        self.affinity = (self.affinity + delta).clamp(-1.0, 1.0);
+    }
+
+    /// Adjusts the trust towards the partner, clamped to -1.0..=1.0.
+    pub fn adjust_trust(&mut self, delta: f64) {
+        self.trust = (self.trust + delta).clamp(-1.0, 1.0);
+    }
+
+    /// Records a line in the conversation. Every exchange makes the partner a bit more familiar.
+    ///
+    /// # Arguments
+    ///
+    /// * `speaker` - Who spoke the line.
+    /// * `text` - The line itself.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::interlocutor::InterlocutorContext;
+    /// let mut context = InterlocutorContext::new("blacksmith", "player_1");
+    /// context.record_turn("player_1", "Nice hammer.");
+    /// assert_eq!(context.conversation.turns.len(), 1);
+    /// assert!(context.familiarity > 0.0);
+    /// ```
+    pub fn record_turn(&mut self, speaker: &str, text: &str) {
+        self.conversation.add_turn(speaker, text);
+        self.familiarity = (self.familiarity + 0.01).min(1.0);
+    }
+
+    /// Re-evaluates the bond with the partner from the current affinity and trust.
+    ///
+    /// # Arguments
+    ///
+    /// * `compatibility` - How compatible the NPC's and partner's personalities are.
+    ///
+    /// # Returns
+    ///
+    /// The new stage if it changed, or `None` if it did not or companionship is not enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::companionship::{BondStage, Companionship};
+    /// use athena::interlocutor::InterlocutorContext;
+    ///
+    /// let mut context = InterlocutorContext::new("ranger", "player_1");
+    /// context.adjust_affinity(0.4);
+    /// context.adjust_trust(0.2);
+    /// assert_eq!(context.evaluate_bond(0.5), None);
+    /// context.companionship = Some(Companionship::new());
+    /// assert_eq!(context.evaluate_bond(0.5), Some(BondStage::Friend));
+    /// ```
+    pub fn evaluate_bond(&mut self, compatibility: f64) -> Option<BondStage> {
+        let (affinity, trust) = (self.affinity, self.trust);
+        self.companionship
+            .as_mut()?
+            .evaluate(affinity, trust, compatibility)
+    }
+
+    /// Records a memory about the partner.
+    pub fn record_memory(&mut self, key: &str, value: &str) {
+        self.memories.insert(key.to_string(), value.to_string());
+    }
+
+    /// Retrieves a memory about the partner.
+    pub fn get_memory(&self, key: &str) -> Option<&String> {
+        self.memories.get(key)
+    }
+
+    /// Iterates over all memories about the partner as `(key, value)` pairs.
+    pub fn memories(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.memories.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
@@ -3,13 +3,34 @@
 //! This module implements a knowledge graph for NPCs, allowing them to store and manage knowledge
 //! about entities, relationships, and properties. The knowledge graph enables NPCs to make informed
 //! decisions based on the information available.
+//!
+//! Internally, nodes and relationships live in generational arenas and reference each other by
+//! index, so traversals walk compact adjacency lists instead of scanning every relationship.
+//! String IDs are only used at the API boundary, where they are resolved through a single map.
+//!
+//! The storage is shared copy-on-write: cloning a graph or taking a `snapshot` only increments
+//! reference counts. The storage is split into four parts (the ID map, the nodes, the
+//! relationships, and the property indexes), and the first mutation after a clone copies every
+//! part it touches in full, so it costs time proportional to the size of the graph. Later
+//! mutations are cheap again until the next clone. Worker threads can read a snapshot while the
+//! owner keeps mutating the graph, at the price of one such copy per snapshot taken.
+//!
+//! Graphs serialize as plain lists of entities and relationships (`GraphData`), so saved graphs
+//! do not depend on the arena layout and can be upgraded by the `migration` module.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
 
-use std::collections::HashMap;
+use crate::arena::{Arena, Index};
+use crate::symbol::Symbol;
 
 /// Represents an entity in the knowledge graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entity {
-    pub id: String,
+    pub id: Symbol,
     pub properties: HashMap<String, String>,
 }
 
@@ -18,7 +39,7 @@ impl Entity {
     ///
     /// # Arguments
     ///
-    /// * `id` - A unique identifier for the entity.
+    /// * `id` - A unique identifier for the entity (a `String`, `&str`, or `Symbol`).
     /// * `properties` - A HashMap of properties associated with the entity.
     ///
     /// # Examples
@@ -30,18 +51,30 @@ impl Entity {
     /// properties.insert("name".to_string(), "Alice".to_string());
     /// let entity = Entity::new("1".to_string(), properties);
     /// ```
-    pub fn new(id: String, properties: HashMap<String, String>) -> Self {
-        Entity { id, properties }
+    pub fn new(id: impl Into<Symbol>, properties: HashMap<String, String>) -> Self {
+        Entity {
+            id: id.into(),
+            properties,
+        }
     }
 }
 
 /// Represents a relationship between two entities in the knowledge graph.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Relationship {
-    pub source: String,
-    pub target: String,
-    pub relation_type: String,
+    pub source: Symbol,
+    pub target: Symbol,
+    pub relation_type: Symbol,
     pub properties: HashMap<String, String>,
+    /// How strong the relationship is (e.g. how close two friends are). Defaults to 1.0,
+    /// including in data saved before relationships had weights.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+/// Gets the weight of a relationship that does not set one.
+fn default_weight() -> f64 {
+    1.0
 }
 
 impl Relationship {
@@ -62,23 +95,83 @@ impl Relationship {
     /// let mut properties = HashMap::new();
     /// properties.insert("since".to_string(), "2021".to_string());
     /// let relationship = Relationship::new("1".to_string(), "2".to_string(), "friend".to_string(), properties);
+    /// assert_eq!(relationship.weight, 1.0);
+    ///
+    /// // Relationships saved without a weight load with the default one.
+    /// let saved = r#"{"source": "1", "target": "2", "relation_type": "friend", "properties": {}}"#;
+    /// let loaded: Relationship = serde_json::from_str(saved).unwrap();
+    /// assert_eq!(loaded.weight, 1.0);
     /// ```
-    pub fn new(source: String, target: String, relation_type: String, properties: HashMap<String, String>) -> Self {
+    pub fn new(
+        source: impl Into<Symbol>,
+        target: impl Into<Symbol>,
+        relation_type: impl Into<Symbol>,
+        properties: HashMap<String, String>,
+    ) -> Self {
         Relationship {
-            source,
-            target,
-            relation_type,
+            source: source.into(),
+            target: target.into(),
+            relation_type: relation_type.into(),
             properties,
+            weight: 1.0,
         }
     }
+
+    /// Sets the weight of the relationship.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::Relationship;
+    /// let relationship = Relationship::new("1", "2", "friend", HashMap::new()).with_weight(0.8);
+    /// assert_eq!(relationship.weight, 0.8);
+    /// ```
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+/// Represents a node of the graph: an ID that is an entity, a relationship endpoint, or both.
+#[derive(Debug, Clone)]
+struct Node {
+    /// The entity stored under this ID, if one was added.
+    entity: Option<Entity>,
+    /// The relationships whose source is this node.
+    outgoing: Vec<Index>,
+    /// The relationships whose target is this node.
+    incoming: Vec<Index>,
+}
+
+/// Represents a relationship together with the resolved indices of its endpoints.
+#[derive(Debug, Clone)]
+struct Edge {
+    relationship: Relationship,
+    source: Index,
+    target: Index,
 }
 
 /// Represents the knowledge graph for NPCs.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "GraphData", into = "GraphData")]
 pub struct KnowledgeGraph {
-    /// A collection of entities in the knowledge graph.
-    entities: HashMap<String, Entity>,
-    /// A collection of relationships in the knowledge graph.
-    relationships: Vec<Relationship>,
+    /// Maps boundary IDs to node indices.
+    ids: Arc<HashMap<Symbol, Index>>,
+    /// The nodes of the graph.
+    nodes: Arc<Arena<Node>>,
+    /// The relationships of the graph.
+    edges: Arc<Arena<Edge>>,
+    /// Secondary indexes from property name to value to the nodes whose entity has it.
+    indexes: Arc<HashMap<String, HashMap<String, HashSet<Index>>>>,
+    /// Change events from committed transactions that have not been taken yet.
+    changes: Vec<GraphChange>,
+}
+
+impl Default for KnowledgeGraph {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl KnowledgeGraph {
@@ -92,8 +185,40 @@ impl KnowledgeGraph {
     /// ```
     pub fn new() -> Self {
         KnowledgeGraph {
-            entities: HashMap::new(),
-            relationships: Vec::new(),
+            ids: Arc::new(HashMap::new()),
+            nodes: Arc::new(Arena::new()),
+            edges: Arc::new(Arena::new()),
+            indexes: Arc::new(HashMap::new()),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Resolves an ID to its node index, creating an empty node if needed.
+    fn node_index(&mut self, id: &Symbol) -> Index {
+        if let Some(index) = self.ids.get(id) {
+            return *index;
+        }
+        let index = Arc::make_mut(&mut self.nodes).insert(Node {
+            entity: None,
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
+        });
+        Arc::make_mut(&mut self.ids).insert(id.clone(), index);
+        index
+    }
+
+    /// Removes a node if it holds no entity and no relationships.
+    fn prune_node(&mut self, id: &str) {
+        let Some(index) = self.ids.get(id).copied() else {
+            return;
+        };
+        let empty = self
+            .nodes
+            .get(index)
+            .is_some_and(|n| n.entity.is_none() && n.outgoing.is_empty() && n.incoming.is_empty());
+        if empty {
+            Arc::make_mut(&mut self.nodes).remove(index);
+            Arc::make_mut(&mut self.ids).remove(id);
         }
     }
 
@@ -115,7 +240,187 @@ impl KnowledgeGraph {
     /// knowledge_graph.add_entity(entity);
     /// ```
     pub fn add_entity(&mut self, entity: Entity) {
-        self.entities.insert(entity.id.clone(), entity);
+        let index = self.node_index(&entity.id);
+        let Some(node) = Arc::make_mut(&mut self.nodes).get_mut(index) else {
+            return;
+        };
+        if let Some(previous) = node.entity.take() {
+            Self::update_indexes(Arc::make_mut(&mut self.indexes), index, &previous, false);
+        }
+        Self::update_indexes(Arc::make_mut(&mut self.indexes), index, &entity, true);
+        node.entity = Some(entity);
+    }
+
+    /// Adds an entity's indexed properties to the indexes, or removes them.
+    fn update_indexes(
+        indexes: &mut HashMap<String, HashMap<String, HashSet<Index>>>,
+        index: Index,
+        entity: &Entity,
+        add: bool,
+    ) {
+        for (property, values) in indexes.iter_mut() {
+            let Some(value) = entity.properties.get(property) else {
+                continue;
+            };
+            if add {
+                values.entry(value.clone()).or_default().insert(index);
+            } else if let Some(nodes) = values.get_mut(value) {
+                nodes.remove(&index);
+                if nodes.is_empty() {
+                    values.remove(value);
+                }
+            }
+        }
+    }
+
+    /// Removes an entity and every relationship touching it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the entity to remove.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<Entity>` containing the removed entity, or `None` if it did not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Entity, Relationship};
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_entity(Entity::new("1", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("1", "2", "friend", HashMap::new()));
+    /// assert!(knowledge_graph.remove_entity("1").is_some());
+    /// assert!(knowledge_graph.get_entity("1").is_none());
+    /// assert_eq!(knowledge_graph.relationships_of("2").count(), 0);
+    /// ```
+    pub fn remove_entity(&mut self, id: &str) -> Option<Entity> {
+        let index = *self.ids.get(id)?;
+        let entity = self.take_entity(index)?;
+        for edge in self.touching_edges(index) {
+            self.remove_edge(edge);
+        }
+        self.prune_node(id);
+        Some(entity)
+    }
+
+    /// Collects the edges touching a node, listing self-loops once.
+    fn touching_edges(&self, index: Index) -> Vec<Index> {
+        let Some(node) = self.nodes.get(index) else {
+            return Vec::new();
+        };
+        let mut touching: Vec<Index> = node.outgoing.iter().chain(&node.incoming).copied().collect();
+        touching.sort();
+        touching.dedup();
+        touching
+    }
+
+    /// Takes the entity out of a node and drops it from the indexes, leaving its relationships.
+    fn take_entity(&mut self, index: Index) -> Option<Entity> {
+        let entity = Arc::make_mut(&mut self.nodes).get_mut(index)?.entity.take()?;
+        Self::update_indexes(Arc::make_mut(&mut self.indexes), index, &entity, false);
+        Some(entity)
+    }
+
+    /// Renames an entity, moving its properties and every relationship touching it to the new ID.
+    ///
+    /// # Returns
+    ///
+    /// True if the graph knew anything about the entity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Entity, Relationship};
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_entity(Entity::new("guard", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("guard", "gate", "works_at", HashMap::new()));
+    /// assert!(knowledge_graph.rename_entity("guard", "guard_2"));
+    /// assert!(knowledge_graph.get_entity("guard").is_none());
+    /// assert_eq!(knowledge_graph.get_relationships("guard_2")[0].target, "gate");
+    /// ```
+    pub fn rename_entity(&mut self, id: &str, new_id: &str) -> bool {
+        let relationships: Vec<Relationship> = self.relationships_of(id).cloned().collect();
+        let entity = self.remove_entity(id);
+        if entity.is_none() && relationships.is_empty() {
+            return false;
+        }
+        if entity.is_none() {
+            for r in &relationships {
+                self.remove_relationship(&r.source, &r.target, &r.relation_type);
+            }
+        }
+        if let Some(entity) = entity {
+            self.add_entity(Entity::new(new_id, entity.properties));
+        }
+        for mut relationship in relationships {
+            if relationship.source == id {
+                relationship.source = Symbol::new(new_id);
+            }
+            if relationship.target == id {
+                relationship.target = Symbol::new(new_id);
+            }
+            self.add_relationship(relationship);
+        }
+        true
+    }
+
+    /// Removes the first relationship matching the given source, target, and type.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<Relationship>` containing the removed relationship, or `None` if none matched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_relationship(Relationship::new("1", "2", "friend", HashMap::new()));
+    /// assert!(knowledge_graph.remove_relationship("1", "2", "friend").is_some());
+    /// assert_eq!(knowledge_graph.relationships().count(), 0);
+    /// ```
+    pub fn remove_relationship(
+        &mut self,
+        source: &str,
+        target: &str,
+        relation_type: &str,
+    ) -> Option<Relationship> {
+        let index = self.find_edge(source, target, relation_type)?;
+        self.unlink_edge(index)
+    }
+
+    /// Finds the first edge matching the given source, target, and type.
+    fn find_edge(&self, source: &str, target: &str, relation_type: &str) -> Option<Index> {
+        let node = self.nodes.get(*self.ids.get(source)?)?;
+        node.outgoing.iter().copied().find(|index| {
+            self.edges.get(*index).is_some_and(|edge| {
+                edge.relationship.target == target && edge.relationship.relation_type == relation_type
+            })
+        })
+    }
+
+    /// Removes an edge and prunes its endpoints if nothing else refers to them.
+    fn unlink_edge(&mut self, index: Index) -> Option<Relationship> {
+        let relationship = self.remove_edge(index)?;
+        self.prune_node(&relationship.source);
+        self.prune_node(&relationship.target);
+        Some(relationship)
+    }
+
+    /// Removes an edge and unlinks it from its endpoints' adjacency lists.
+    fn remove_edge(&mut self, index: Index) -> Option<Relationship> {
+        let edge = Arc::make_mut(&mut self.edges).remove(index)?;
+        if let Some(source) = Arc::make_mut(&mut self.nodes).get_mut(edge.source) {
+            source.outgoing.retain(|i| *i != index);
+        }
+        if let Some(target) = Arc::make_mut(&mut self.nodes).get_mut(edge.target) {
+            target.incoming.retain(|i| *i != index);
+        }
+        Some(edge.relationship)
     }
 
     /// Adds a new relationship to the knowledge graph.
@@ -136,7 +441,25 @@ impl KnowledgeGraph {
     /// knowledge_graph.add_relationship(relationship);
     /// ```
     pub fn add_relationship(&mut self, relationship: Relationship) {
-        self.relationships.push(relationship);
+        self.insert_edge(relationship);
+    }
+
+    /// Inserts a relationship's edge, linking it into its endpoints' adjacency lists.
+    fn insert_edge(&mut self, relationship: Relationship) -> Index {
+        let source = self.node_index(&relationship.source);
+        let target = self.node_index(&relationship.target);
+        let index = Arc::make_mut(&mut self.edges).insert(Edge {
+            relationship,
+            source,
+            target,
+        });
+        if let Some(node) = Arc::make_mut(&mut self.nodes).get_mut(source) {
+            node.outgoing.push(index);
+        }
+        if let Some(node) = Arc::make_mut(&mut self.nodes).get_mut(target) {
+            node.incoming.push(index);
+        }
+        index
     }
 
     /// Retrieves an entity by its ID.
@@ -163,7 +486,8 @@ impl KnowledgeGraph {
     /// assert!(retrieved_entity.is_some());
     /// ```
     pub fn get_entity(&self, id: &str) -> Option<&Entity> {
-        self.entities.get(id)
+        let index = self.ids.get(id)?;
+        self.nodes.get(*index)?.entity.as_ref()
     }
 
     /// Retrieves all relationships for a given entity ID.
@@ -190,9 +514,744 @@ impl KnowledgeGraph {
     /// assert_eq!(relationships.len(), 1);
     /// ```
     pub fn get_relationships(&self, entity_id: &str) -> Vec<&Relationship> {
-        self.relationships
-            .iter()
-            .filter(|r| r.source == entity_id || r.target == entity_id)
-            .collect()
+        self.relationships_of(entity_id).collect()
+    }
+
+    /// Iterates over all relationships for a given entity ID without allocating.
+    ///
+    /// Prefer this over `get_relationships` in per-frame queries.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The ID of the entity for which to iterate relationships.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_relationship(Relationship::new("1", "2", "friend", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("3", "1", "rival", HashMap::new()));
+    /// assert_eq!(knowledge_graph.relationships_of("1").count(), 2);
+    /// ```
+    pub fn relationships_of<'a, 'b>(
+        &'a self,
+        entity_id: &'b str,
+    ) -> impl Iterator<Item = &'a Relationship> + use<'a, 'b> {
+        let node = self.ids.get(entity_id).and_then(|index| self.nodes.get(*index));
+        // Self-loops appear in both lists; yield them only from the outgoing side.
+        let incoming = node
+            .into_iter()
+            .flat_map(|n| n.incoming.iter())
+            .filter_map(|index| self.edges.get(*index))
+            .filter(|edge| edge.source != edge.target);
+        self.outgoing(entity_id)
+            .chain(incoming.map(|edge| &edge.relationship))
+    }
+
+    /// Iterates over the relationships whose source is the given entity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_relationship(Relationship::new("1", "2", "friend", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("3", "1", "rival", HashMap::new()));
+    /// let targets: Vec<&str> = knowledge_graph.outgoing("1").map(|r| r.target.as_str()).collect();
+    /// assert_eq!(targets, vec!["2"]);
+    /// ```
+    pub fn outgoing<'a, 'b>(
+        &'a self,
+        entity_id: &'b str,
+    ) -> impl Iterator<Item = &'a Relationship> + use<'a, 'b> {
+        self.adjacent(entity_id, |node| &node.outgoing)
+    }
+
+    /// Iterates over the relationships whose target is the given entity.
+    pub fn incoming<'a, 'b>(
+        &'a self,
+        entity_id: &'b str,
+    ) -> impl Iterator<Item = &'a Relationship> + use<'a, 'b> {
+        self.adjacent(entity_id, |node| &node.incoming)
+    }
+
+    /// Iterates over the relationships in one of an entity's adjacency lists.
+    fn adjacent<'a>(
+        &'a self,
+        entity_id: &str,
+        list: fn(&Node) -> &Vec<Index>,
+    ) -> impl Iterator<Item = &'a Relationship> + use<'a> {
+        self.ids
+            .get(entity_id)
+            .and_then(|index| self.nodes.get(*index))
+            .into_iter()
+            .flat_map(move |node| list(node).iter())
+            .filter_map(|index| self.edges.get(*index))
+            .map(|edge| &edge.relationship)
+    }
+
+    /// Iterates over all entities in the knowledge graph, in no particular order.
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.nodes.iter().filter_map(|(_, node)| node.entity.as_ref())
+    }
+
+    /// Iterates over all relationships in the knowledge graph, in storage order.
+    ///
+    /// Storage order matches insertion order until relationships are removed, after which
+    /// freed slots are reused.
+    pub fn relationships(&self) -> impl Iterator<Item = &Relationship> {
+        self.edges.iter().map(|(_, edge)| &edge.relationship)
+    }
+
+    /// Returns the number of entities in the knowledge graph.
+    pub fn entity_count(&self) -> usize {
+        self.entities().count()
+    }
+
+    /// Returns the number of relationships in the knowledge graph.
+    pub fn relationship_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Creates a secondary index on an entity property, so `find_by_property` no longer scans
+    /// every entity. The index is kept up to date as entities are added and removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{Entity, KnowledgeGraph};
+    ///
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// let guard = HashMap::from([("role".to_string(), "guard".to_string())]);
+    /// knowledge_graph.add_entity(Entity::new("hald", guard.clone()));
+    /// knowledge_graph.create_index("role");
+    /// knowledge_graph.add_entity(Entity::new("orla", guard.clone()));
+    /// knowledge_graph.add_entity(Entity::new("orla", guard));
+    /// assert_eq!(knowledge_graph.find_by_property("role", "guard").len(), 2);
+    ///
+    /// let baker = HashMap::from([("role".to_string(), "baker".to_string())]);
+    /// knowledge_graph.add_entity(Entity::new("orla", baker));
+    /// knowledge_graph.remove_entity("hald");
+    /// assert!(knowledge_graph.find_by_property("role", "guard").is_empty());
+    /// assert_eq!(knowledge_graph.find_by_property("role", "baker")[0].id, "orla");
+    /// ```
+    pub fn create_index(&mut self, property: &str) {
+        let mut values: HashMap<String, HashSet<Index>> = HashMap::new();
+        for (index, node) in self.nodes.iter() {
+            if let Some(value) = node.entity.as_ref().and_then(|e| e.properties.get(property)) {
+                values.entry(value.clone()).or_default().insert(index);
+            }
+        }
+        Arc::make_mut(&mut self.indexes).insert(property.to_string(), values);
+    }
+
+    /// Drops the index on a property. Returns false if there was none.
+    pub fn drop_index(&mut self, property: &str) -> bool {
+        Arc::make_mut(&mut self.indexes).remove(property).is_some()
+    }
+
+    /// Returns whether a property is indexed.
+    pub fn has_index(&self, property: &str) -> bool {
+        self.indexes.contains_key(property)
+    }
+
+    /// Finds the entities whose property has the given value, in ID order.
+    ///
+    /// Indexed properties are looked up directly; other properties scan every entity.
+    pub fn find_by_property(&self, property: &str, value: &str) -> Vec<&Entity> {
+        let mut found: Vec<&Entity> = match self.indexes.get(property) {
+            Some(values) => values
+                .get(value)
+                .into_iter()
+                .flatten()
+                .filter_map(|index| self.nodes.get(*index)?.entity.as_ref())
+                .collect(),
+            None => self
+                .entities()
+                .filter(|entity| entity.properties.get(property).is_some_and(|v| v == value))
+                .collect(),
+        };
+        found.sort_by(|a, b| a.id.cmp(&b.id));
+        found
+    }
+
+    /// Starts a transaction: a batch of adds and removes applied all at once by
+    /// `Transaction::commit`, or not at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+    ///
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_entity(Entity::new("brom", HashMap::new()));
+    ///
+    /// // A story beat: Brom leaves the village and Orla takes over the forge.
+    /// let mut transaction = knowledge_graph.transaction();
+    /// transaction.remove_entity("brom");
+    /// transaction.add_entity(Entity::new("orla", HashMap::new()));
+    /// transaction.add_relationship(Relationship::new("orla", "forge", "works_at", HashMap::new()));
+    /// let change = transaction.commit().unwrap();
+    /// assert_eq!(change.removed_entities, vec!["brom".to_string()]);
+    /// assert_eq!(knowledge_graph.take_changes().len(), 1);
+    ///
+    /// // Removing something that does not exist fails the whole batch.
+    /// let mut transaction = knowledge_graph.transaction();
+    /// transaction.add_entity(Entity::new("hald", HashMap::new()));
+    /// transaction.remove_entity("brom");
+    /// assert!(transaction.commit().is_err());
+    /// assert!(knowledge_graph.get_entity("hald").is_none());
+    ///
+    /// // Custom checks run against the graph as it would be after the batch.
+    /// let mut transaction = knowledge_graph.transaction();
+    /// transaction.add_relationship(Relationship::new("hald", "forge", "works_at", HashMap::new()));
+    /// transaction.require(|graph| match graph.get_entity("hald") {
+    ///     Some(_) => Ok(()),
+    ///     None => Err("hald is not an entity".to_string()),
+    /// });
+    /// assert!(transaction.commit().is_err());
+    /// assert_eq!(knowledge_graph.relationship_count(), 1);
+    /// assert!(knowledge_graph.take_changes().is_empty());
+    ///
+    /// // A failed commit also puts back what the batch had already removed.
+    /// let mut transaction = knowledge_graph.transaction();
+    /// transaction.add_relationship(Relationship::new("orla", "mill", "visits", HashMap::new()));
+    /// transaction.remove_entity("orla");
+    /// transaction.require(|_| Err("the mill is closed".to_string()));
+    /// assert!(transaction.commit().is_err());
+    /// assert!(knowledge_graph.get_entity("orla").is_some());
+    /// assert_eq!(knowledge_graph.relationships_of("orla").count(), 1);
+    /// assert!(knowledge_graph.get_relationships("mill").is_empty());
+    /// ```
+    pub fn transaction(&mut self) -> Transaction<'_> {
+        Transaction {
+            graph: self,
+            operations: Vec::new(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Takes the change events of the transactions committed since the last call, oldest first.
+    pub fn take_changes(&mut self) -> Vec<GraphChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Takes a read-only snapshot of the graph as it is now.
+    ///
+    /// Taking a snapshot is cheap: it shares the graph's storage until the graph is next mutated.
+    /// That mutation then copies the storage it touches in full, so taking a snapshot every tick
+    /// of a large, busy graph costs a full copy per tick. Later changes to the graph are not
+    /// visible through the snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+    ///
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_relationship(Relationship::new("brom", "forge", "owns", HashMap::new()));
+    /// let snapshot = knowledge_graph.snapshot();
+    ///
+    /// let worker = std::thread::spawn(move || snapshot.get_relationships("brom").len());
+    /// knowledge_graph.add_relationship(Relationship::new("brom", "orla", "knows", HashMap::new()));
+    ///
+    /// assert_eq!(worker.join().unwrap(), 1);
+    /// assert_eq!(knowledge_graph.get_relationships("brom").len(), 2);
+    /// ```
+    pub fn snapshot(&self) -> GraphSnapshot {
+        GraphSnapshot {
+            graph: KnowledgeGraph {
+                ids: Arc::clone(&self.ids),
+                nodes: Arc::clone(&self.nodes),
+                edges: Arc::clone(&self.edges),
+                indexes: Arc::clone(&self.indexes),
+                changes: Vec::new(),
+            },
+        }
+    }
+
+    /// Retrieves the facts most relevant to a conversation.
+    ///
+    /// Facts within `max_distance` relationships of a focus entity are scored by recency (from
+    /// their `game_time` property; undated facts count as half recent), confidence (from their
+    /// `confidence` property, 1.0 if absent), proximity to the focus, and the emotional salience
+    /// of their endpoints.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - What the retrieval is about.
+    /// * `limit` - The maximum number of facts to return.
+    ///
+    /// # Returns
+    ///
+    /// The most relevant facts, best first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, RelevanceQuery, Relationship};
+    ///
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// knowledge_graph.add_relationship(Relationship::new("brom", "anvil", "owns", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("brom", "mira", "married_to", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("mira", "mill", "works_at", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("mill", "river", "near", HashMap::new()));
+    /// knowledge_graph.add_relationship(Relationship::new("river", "sea", "flows_into", HashMap::new()));
+    ///
+    /// let mut query = RelevanceQuery { focus: vec!["brom".to_string()], ..Default::default() };
+    /// query.salience.insert("mira".to_string(), 1.0);
+    /// let facts = knowledge_graph.ranked_facts(&query, 2);
+    /// assert_eq!(facts.len(), 2);
+    /// assert_eq!(facts[0].relationship.relation_type, "married_to");
+    /// // The mill is two relationships away from Brom, so what is near it is still relevant,
+    /// // but where the river flows is three away.
+    /// let facts = knowledge_graph.ranked_facts(&query, 10);
+    /// assert_eq!(facts.len(), 4);
+    /// assert!(facts.iter().all(|fact| fact.relationship.relation_type != "flows_into"));
+    /// ```
+    pub fn ranked_facts(&self, query: &RelevanceQuery, limit: usize) -> Vec<ScoredFact<'_>> {
+        // Breadth-first from the focus entities; each edge is first reached from its closer end.
+        let mut distances: HashMap<Index, usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for id in &query.focus {
+            if let Some(index) = self.ids.get(id.as_str()) {
+                if distances.insert(*index, 0).is_none() {
+                    queue.push_back(*index);
+                }
+            }
+        }
+        let mut reached: Vec<(Index, usize)> = Vec::new();
+        let mut seen = HashSet::new();
+        while let Some(index) = queue.pop_front() {
+            let distance = distances[&index];
+            let Some(node) = self.nodes.get(index) else {
+                continue;
+            };
+            for edge_index in node.outgoing.iter().chain(&node.incoming) {
+                let Some(edge) = self.edges.get(*edge_index) else {
+                    continue;
+                };
+                if seen.insert(*edge_index) {
+                    reached.push((*edge_index, distance));
+                }
+                let other = if edge.source == index { edge.target } else { edge.source };
+                if distance < query.max_distance && !distances.contains_key(&other) {
+                    distances.insert(other, distance + 1);
+                    queue.push_back(other);
+                }
+            }
+        }
+
+        let weights = &query.weights;
+        let mut facts: Vec<ScoredFact> = reached
+            .into_iter()
+            .filter_map(|(index, distance)| {
+                let relationship = &self.edges.get(index)?.relationship;
+                let number = |key: &str| relationship.properties.get(key)?.parse::<f64>().ok();
+                let recency = number("game_time").map_or(0.5, |time| {
+                    let age = (query.game_time - time).max(0.0);
+                    0.5_f64.powf(age / query.half_life.max(f64::EPSILON))
+                });
+                let confidence = number("confidence").unwrap_or(1.0);
+                let salience = [&relationship.source, &relationship.target]
+                    .iter()
+                    .filter_map(|id| query.salience.get(id.as_str()))
+                    .fold(0.0_f64, |max, value| max.max(*value));
+                let score = weights.recency * recency
+                    + weights.confidence * confidence
+                    + weights.proximity / (1.0 + distance as f64)
+                    + weights.salience * salience;
+                Some(ScoredFact {
+                    relationship,
+                    score,
+                    distance,
+                })
+            })
+            .collect();
+        facts.sort_by(|a, b| b.score.total_cmp(&a.score));
+        facts.truncate(limit);
+        facts
+    }
+}
+
+/// Represents the serialized form of a knowledge graph.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphData {
+    pub entities: Vec<Entity>,
+    /// The relationships, in storage order.
+    pub relationships: Vec<Relationship>,
+    /// The properties with a secondary index.
+    #[serde(default)]
+    pub indexes: Vec<String>,
+}
+
+impl From<KnowledgeGraph> for GraphData {
+    fn from(graph: KnowledgeGraph) -> Self {
+        let mut indexes: Vec<String> = graph.indexes.keys().cloned().collect();
+        indexes.sort();
+        GraphData {
+            entities: graph.entities().cloned().collect(),
+            relationships: graph.relationships().cloned().collect(),
+            indexes,
+        }
+    }
+}
+
+impl From<GraphData> for KnowledgeGraph {
+    /// Rebuilds a graph from its serialized form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+    ///
+    /// let mut knowledge_graph = KnowledgeGraph::new();
+    /// let guard = HashMap::from([("role".to_string(), "guard".to_string())]);
+    /// knowledge_graph.add_entity(Entity::new("hald", guard));
+    /// knowledge_graph.create_index("role");
+    /// knowledge_graph.add_relationship(Relationship::new("hald", "orla", "knows", HashMap::new()));
+    ///
+    /// let json = serde_json::to_string(&knowledge_graph).unwrap();
+    /// let loaded: KnowledgeGraph = serde_json::from_str(&json).unwrap();
+    /// assert!(loaded.has_index("role"));
+    /// assert_eq!(loaded.find_by_property("role", "guard")[0].id, "hald");
+    /// assert_eq!(loaded.get_relationships("orla").len(), 1);
+    /// ```
+    fn from(data: GraphData) -> Self {
+        let mut graph = KnowledgeGraph::new();
+        for entity in data.entities {
+            graph.add_entity(entity);
+        }
+        for relationship in data.relationships {
+            graph.add_relationship(relationship);
+        }
+        for property in &data.indexes {
+            graph.create_index(property);
+        }
+        graph
+    }
+}
+
+/// Represents a read-only view of a knowledge graph at one point in time.
+///
+/// Snapshots dereference to `KnowledgeGraph`, so every query is available, and can be cloned and
+/// sent between threads freely.
+#[derive(Clone)]
+pub struct GraphSnapshot {
+    graph: KnowledgeGraph,
+}
+
+impl Deref for GraphSnapshot {
+    type Target = KnowledgeGraph;
+
+    fn deref(&self) -> &KnowledgeGraph {
+        &self.graph
+    }
+}
+
+/// Represents the changes made by one committed transaction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphChange {
+    /// The IDs of the entities added or replaced.
+    pub added_entities: Vec<String>,
+    /// The IDs of the entities removed.
+    pub removed_entities: Vec<String>,
+    /// The relationships added, as `(source, relation, target)` triples.
+    pub added_relationships: Vec<(String, String, String)>,
+    /// The relationships removed explicitly, as `(source, relation, target)` triples.
+    /// Relationships removed along with an entity are not listed.
+    pub removed_relationships: Vec<(String, String, String)>,
+}
+
+impl GraphChange {
+    /// Returns whether the change touched an entity, directly or through a relationship.
+    pub fn touches(&self, id: &str) -> bool {
+        let in_triple =
+            |(source, _, target): &(String, String, String)| source == id || target == id;
+        self.added_entities.iter().chain(&self.removed_entities).any(|e| e == id)
+            || self.added_relationships.iter().chain(&self.removed_relationships).any(in_triple)
+    }
+}
+
+/// Represents one staged mutation of a transaction.
+enum Operation {
+    AddEntity(Entity),
+    RemoveEntity(String),
+    AddRelationship(Relationship),
+    RemoveRelationship(String, String, String),
+}
+
+/// Represents how to undo one mutation a transaction applied.
+enum Undo {
+    /// Puts back the entity an add replaced, or clears the entity an add created.
+    Entity(Symbol, Option<Entity>),
+    /// Removes an added edge.
+    Edge(Index),
+    /// Puts back a removed relationship, which was stored under the given edge index.
+    Relationship(Index, Relationship),
+}
+
+/// A validation run against the graph as it would be after a transaction.
+type Check = Box<dyn Fn(&KnowledgeGraph) -> Result<(), String>>;
+
+/// Represents a batch of mutations to a knowledge graph.
+///
+/// Nothing changes until `commit` is called. Dropping a transaction discards it.
+pub struct Transaction<'a> {
+    graph: &'a mut KnowledgeGraph,
+    operations: Vec<Operation>,
+    checks: Vec<Check>,
+}
+
+impl Transaction<'_> {
+    /// Stages adding an entity, replacing any entity with the same ID.
+    pub fn add_entity(&mut self, entity: Entity) -> &mut Self {
+        self.operations.push(Operation::AddEntity(entity));
+        self
+    }
+
+    /// Stages removing an entity and every relationship touching it. The commit fails if the
+    /// entity does not exist at that point of the batch.
+    pub fn remove_entity(&mut self, id: &str) -> &mut Self {
+        self.operations.push(Operation::RemoveEntity(id.to_string()));
+        self
+    }
+
+    /// Stages adding a relationship. The commit fails if its weight is not finite.
+    pub fn add_relationship(&mut self, relationship: Relationship) -> &mut Self {
+        self.operations.push(Operation::AddRelationship(relationship));
+        self
+    }
+
+    /// Stages removing a relationship. The commit fails if no relationship matches at that
+    /// point of the batch.
+    pub fn remove_relationship(
+        &mut self,
+        source: &str,
+        target: &str,
+        relation_type: &str,
+    ) -> &mut Self {
+        let operation =
+            Operation::RemoveRelationship(source.into(), target.into(), relation_type.into());
+        self.operations.push(operation);
+        self
+    }
+
+    /// Adds a check the graph must pass once the batch is applied, or the commit fails.
+    pub fn require(
+        &mut self,
+        check: impl Fn(&KnowledgeGraph) -> Result<(), String> + 'static,
+    ) -> &mut Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    /// Returns the number of staged mutations.
+    pub fn len(&self) -> usize {
+        self.operations.len()
     }
-}
\ No newline at end of file
+
+    /// Returns whether no mutations are staged.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Applies the batch.
+    ///
+    /// Mutations are applied to the graph in place while an undo log records how to revert
+    /// each one. If a mutation or check fails, the log is replayed backwards, so a failed commit
+    /// leaves the graph as it was (though relationships it put back may move in storage order).
+    /// A commit costs time proportional to the size of the batch, not of the graph.
+    ///
+    /// # Returns
+    ///
+    /// The change event, which is also queued for `KnowledgeGraph::take_changes`, or an error
+    /// describing the first mutation or check that failed.
+    pub fn commit(self) -> Result<GraphChange, Box<dyn std::error::Error>> {
+        let mut undo = Vec::new();
+        let applied = Self::apply(self.graph, self.operations, &mut undo).and_then(|change| {
+            for check in &self.checks {
+                check(self.graph)?;
+            }
+            Ok(change)
+        });
+        match applied {
+            Ok(change) => {
+                self.graph.changes.push(change.clone());
+                Ok(change)
+            }
+            Err(error) => {
+                Self::rollback(self.graph, undo);
+                Err(error)
+            }
+        }
+    }
+
+    /// Applies the staged mutations in order, logging how to undo each one that succeeded.
+    fn apply(
+        graph: &mut KnowledgeGraph,
+        operations: Vec<Operation>,
+        undo: &mut Vec<Undo>,
+    ) -> Result<GraphChange, Box<dyn std::error::Error>> {
+        let mut change = GraphChange::default();
+        for operation in operations {
+            match operation {
+                Operation::AddEntity(entity) => {
+                    change.added_entities.push(entity.id.to_string());
+                    let previous = graph.get_entity(&entity.id).cloned();
+                    undo.push(Undo::Entity(entity.id.clone(), previous));
+                    graph.add_entity(entity);
+                }
+                Operation::RemoveEntity(id) => {
+                    let touching: Vec<Undo> = graph
+                        .ids
+                        .get(id.as_str())
+                        .map(|index| graph.touching_edges(*index))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|index| {
+                            let relationship = graph.edges.get(index)?.relationship.clone();
+                            Some(Undo::Relationship(index, relationship))
+                        })
+                        .collect();
+                    let Some(entity) = graph.remove_entity(&id) else {
+                        return Err(format!("No entity '{}' to remove", id).into());
+                    };
+                    undo.push(Undo::Entity(entity.id.clone(), Some(entity)));
+                    undo.extend(touching);
+                    change.removed_entities.push(id);
+                }
+                Operation::AddRelationship(relationship) => {
+                    if !relationship.weight.is_finite() {
+                        return Err(format!(
+                            "Relationship '{}' from '{}' to '{}' has weight {}",
+                            relationship.relation_type,
+                            relationship.source,
+                            relationship.target,
+                            relationship.weight
+                        )
+                        .into());
+                    }
+                    change.added_relationships.push((
+                        relationship.source.to_string(),
+                        relationship.relation_type.to_string(),
+                        relationship.target.to_string(),
+                    ));
+                    undo.push(Undo::Edge(graph.insert_edge(relationship)));
+                }
+                Operation::RemoveRelationship(source, target, relation_type) => {
+                    let found = graph.find_edge(&source, &target, &relation_type);
+                    let Some((index, removed)) =
+                        found.and_then(|index| Some((index, graph.unlink_edge(index)?)))
+                    else {
+                        return Err(format!(
+                            "No relationship '{}' from '{}' to '{}' to remove",
+                            relation_type, source, target
+                        )
+                        .into());
+                    };
+                    undo.push(Undo::Relationship(index, removed));
+                    change.removed_relationships.push((source, relation_type, target));
+                }
+            }
+        }
+        Ok(change)
+    }
+
+    /// Reverts applied mutations by replaying their undo log backwards.
+    ///
+    /// A relationship put back gets a new edge index, so earlier steps referring to its old
+    /// index are redirected to the new one.
+    fn rollback(graph: &mut KnowledgeGraph, undo: Vec<Undo>) {
+        let mut moved: HashMap<Index, Index> = HashMap::new();
+        for step in undo.into_iter().rev() {
+            match step {
+                Undo::Entity(_, Some(entity)) => graph.add_entity(entity),
+                Undo::Entity(id, None) => {
+                    if let Some(index) = graph.ids.get(&id).copied() {
+                        graph.take_entity(index);
+                        graph.prune_node(&id);
+                    }
+                }
+                Undo::Edge(mut index) => {
+                    while let Some(next) = moved.get(&index) {
+                        index = *next;
+                    }
+                    graph.unlink_edge(index);
+                }
+                Undo::Relationship(index, relationship) => {
+                    moved.insert(index, graph.insert_edge(relationship));
+                }
+            }
+        }
+    }
+}
+
+/// Represents how much each signal counts towards a fact's relevance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelevanceWeights {
+    pub recency: f64,
+    pub confidence: f64,
+    /// How much closeness to the conversation's entities counts.
+    pub proximity: f64,
+    pub salience: f64,
+}
+
+impl Default for RelevanceWeights {
+    fn default() -> Self {
+        RelevanceWeights {
+            recency: 1.0,
+            confidence: 1.0,
+            proximity: 2.0,
+            salience: 1.0,
+        }
+    }
+}
+
+/// Represents what a fact retrieval is about.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelevanceQuery {
+    /// The entities the conversation is about.
+    pub focus: Vec<String>,
+    /// The current game time, used for recency.
+    pub game_time: f64,
+    /// How long it takes a fact's recency to halve, in seconds of game time.
+    pub half_life: f64,
+    /// How many relationships away from a focus entity a fact may be. Facts about a focus
+    /// entity itself are 0 away.
+    pub max_distance: usize,
+    /// How emotionally salient entities are (between 0.0 and 1.0), e.g. from the strength of
+    /// the NPC's emotional memories about them.
+    pub salience: HashMap<String, f64>,
+    pub weights: RelevanceWeights,
+}
+
+impl Default for RelevanceQuery {
+    fn default() -> Self {
+        RelevanceQuery {
+            focus: Vec::new(),
+            game_time: 0.0,
+            half_life: 86_400.0,
+            max_distance: 2,
+            salience: HashMap::new(),
+            weights: RelevanceWeights::default(),
+        }
+    }
+}
+
+/// Represents a fact together with how relevant it is.
+#[derive(Debug, Clone)]
+pub struct ScoredFact<'a> {
+    pub relationship: &'a Relationship,
+    pub score: f64,
+    /// How many relationships away from a focus entity the fact is.
+    pub distance: usize,
+}
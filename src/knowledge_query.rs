@@ -0,0 +1,331 @@
+//! # Knowledge Query Module
+//!
+//! This module answers the player's "What do you know about X?". The NPC's facts about the
+//! topic are gathered from its knowledge graph and filtered: facts it is not confident enough
+//! in are left out, and so are secrets the NPC does not trust the asker with. What remains is
+//! turned into a prompt directive so the model tells it in the NPC's own voice, adding nothing.
+//!
+//! Facts are filtered by two relationship properties, both between 0.0 and 1.0:
+//!
+//! - `confidence` (1.0 if absent): facts below the policy's minimum are not shared, and
+//!   uncertain ones are passed on as hearsay.
+//! - `secrecy` (0.0 if absent): a fact is only shared with an asker the NPC trusts at least as
+//!   much, so common knowledge is shared with anyone and a secret of 0.8 only with a confidant.
+//!
+//! Once the answer has been given, recording it marks each shared fact with a `shared_with`
+//! property listing who was told, and adds a "discussed" fact from the asker to the topic. With
+//! the `network` feature, `answer` does all of this through a dialogue pipeline.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::knowledge_graph::{Entity, KnowledgeGraph, RelevanceQuery, Relationship};
+use crate::knowledge_summary::{display_name, render_sentence};
+
+#[cfg(feature = "network")]
+use crate::agent::NpcAgent;
+#[cfg(feature = "network")]
+use crate::dialogue_generation::RequestType;
+#[cfg(feature = "network")]
+use crate::pipeline::{Pipeline, PipelineState, StageError};
+
+/// Configures which facts an NPC shares when asked about a topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharingPolicy {
+    /// The confidence below which facts are not shared.
+    pub min_confidence: f64,
+    /// The confidence below which facts are shared as hearsay.
+    pub hearsay_below: f64,
+    /// The most facts shared in one answer, the most relevant first.
+    pub max_facts: usize,
+    /// Relation types that are bookkeeping rather than knowledge, never shared.
+    pub hidden_relations: Vec<String>,
+}
+
+impl Default for SharingPolicy {
+    fn default() -> Self {
+        SharingPolicy {
+            min_confidence: 0.3,
+            hearsay_below: 0.7,
+            max_facts: 6,
+            hidden_relations: ["discussed", "promised", "revealed"]
+                .iter()
+                .map(|relation| relation.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Represents whether an NPC shares a fact with an asker.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Disclosure {
+    /// The fact is shared, as hearsay if the NPC is unsure of it.
+    Shared { confidence: f64, hearsay: bool },
+    /// The NPC is not confident enough in the fact to pass it on.
+    Doubtful,
+    /// The fact is a secret the NPC does not trust the asker with.
+    Secret,
+}
+
+impl SharingPolicy {
+    /// Decides whether a fact is shared with an asker, from its `confidence` and `secrecy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `fact` - The fact.
+    /// * `trust` - How much the NPC trusts the asker (between -1.0 and 1.0).
+    pub fn disclosure(&self, fact: &Relationship, trust: f64) -> Disclosure {
+        let number = |key: &str| fact.properties.get(key)?.parse::<f64>().ok();
+        let confidence = number("confidence").unwrap_or(1.0);
+        if confidence < self.min_confidence {
+            Disclosure::Doubtful
+        } else if number("secrecy").unwrap_or(0.0) > trust.max(0.0) {
+            Disclosure::Secret
+        } else {
+            let hearsay = confidence < self.hearsay_below;
+            Disclosure::Shared { confidence, hearsay }
+        }
+    }
+}
+
+/// Represents a fact an NPC is willing to share.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedFact {
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+    pub confidence: f64,
+    /// Whether the NPC is unsure of the fact and passes it on as hearsay.
+    pub hearsay: bool,
+    /// Whether the asker was told this before.
+    pub already_told: bool,
+}
+
+/// Represents what an NPC is willing to tell an asker about a topic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeAnswer {
+    /// The ID of the entity asked about.
+    pub topic: String,
+    /// The ID of who asked.
+    pub asker: String,
+    /// The facts to share, the most relevant first.
+    pub facts: Vec<SharedFact>,
+    /// How many known facts were kept secret from the asker.
+    pub withheld: usize,
+}
+
+impl KnowledgeAnswer {
+    /// Gathers what a knowledge graph's owner is willing to tell an asker about a topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The knowledge graph of the NPC asked.
+    /// * `topic` - The ID of the entity asked about.
+    /// * `asker` - The ID of who asked.
+    /// * `trust` - How much the NPC trusts the asker (between -1.0 and 1.0).
+    /// * `policy` - Which facts are shared.
+    pub fn gather(
+        graph: &KnowledgeGraph,
+        topic: &str,
+        asker: &str,
+        trust: f64,
+        policy: &SharingPolicy,
+    ) -> Self {
+        let query = RelevanceQuery {
+            focus: vec![topic.to_string()],
+            max_distance: 0,
+            ..RelevanceQuery::default()
+        };
+        let mut answer = KnowledgeAnswer {
+            topic: topic.to_string(),
+            asker: asker.to_string(),
+            facts: Vec::new(),
+            withheld: 0,
+        };
+        for fact in graph.ranked_facts(&query, usize::MAX) {
+            let relationship = fact.relationship;
+            let relation = relationship.relation_type.as_str();
+            if policy.hidden_relations.iter().any(|hidden| hidden == relation) {
+                continue;
+            }
+            let (confidence, hearsay) = match policy.disclosure(relationship, trust) {
+                Disclosure::Shared { confidence, hearsay } => (confidence, hearsay),
+                Disclosure::Doubtful => continue,
+                Disclosure::Secret => {
+                    answer.withheld += 1;
+                    continue;
+                }
+            };
+            if answer.facts.len() < policy.max_facts {
+                let told = relationship.properties.get("shared_with");
+                answer.facts.push(SharedFact {
+                    source: relationship.source.to_string(),
+                    relation: relationship.relation_type.to_string(),
+                    target: relationship.target.to_string(),
+                    confidence,
+                    hearsay,
+                    already_told: told.is_some_and(|told| told.split(',').any(|id| id == asker)),
+                });
+            }
+        }
+        answer
+    }
+
+    /// Builds the prompt directive telling the model what to say about the topic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// use athena::knowledge_query::{KnowledgeAnswer, SharingPolicy};
+    ///
+    /// let mut graph = KnowledgeGraph::new();
+    /// graph.add_relationship(Relationship::new("mira", "old_mill", "works_at", HashMap::new()));
+    /// let rumor = HashMap::from([("confidence".to_string(), "0.5".to_string())]);
+    /// graph.add_relationship(Relationship::new("old_mill", "ghost", "haunted_by", rumor));
+    /// let secret = HashMap::from([("secrecy".to_string(), "0.8".to_string())]);
+    /// graph.add_relationship(Relationship::new("old_mill", "smugglers", "hides", secret));
+    ///
+    /// let policy = SharingPolicy::default();
+    /// let answer = KnowledgeAnswer::gather(&graph, "old_mill", "player", 0.2, &policy);
+    /// assert_eq!(answer.facts.len(), 2);
+    /// assert_eq!(answer.withheld, 1);
+    /// assert_eq!(
+    ///     answer.directive(&graph),
+    ///     "You are asked what you know about old mill. Tell what you know, in your own words \
+    ///      and voice, adding nothing: Mira works at old mill. You are not sure, but you have \
+    ///      heard: Old mill haunted by ghost. There is more you know, but you keep it to \
+    ///      yourself."
+    /// );
+    ///
+    /// // A confidant is told the secret as well.
+    /// let answer = KnowledgeAnswer::gather(&graph, "old_mill", "player", 0.9, &policy);
+    /// assert_eq!((answer.facts.len(), answer.withheld), (3, 0));
+    /// ```
+    pub fn directive(&self, graph: &KnowledgeGraph) -> String {
+        let topic = display_name(graph, &self.topic);
+        if self.facts.is_empty() {
+            let reason = if self.withheld > 0 {
+                "You will not share what you know about it"
+            } else {
+                "You know nothing about it"
+            };
+            return format!(
+                "You are asked what you know about {}. {}; say so in character.",
+                topic, reason
+            );
+        }
+        let (hearsay, certain): (Vec<&SharedFact>, Vec<&SharedFact>) =
+            self.facts.iter().partition(|fact| fact.hearsay);
+        let mut directive = format!(
+            "You are asked what you know about {}. Tell what you know, in your own words and \
+             voice, adding nothing:",
+            topic
+        );
+        if !certain.is_empty() {
+            directive.push(' ');
+            directive.push_str(&render_facts(graph, &certain));
+        }
+        if !hearsay.is_empty() {
+            directive.push_str(" You are not sure, but you have heard: ");
+            directive.push_str(&render_facts(graph, &hearsay));
+        }
+        if self.facts.iter().all(|fact| fact.already_told) {
+            directive.push_str(" You have told them all this before.");
+        }
+        if self.withheld > 0 {
+            directive.push_str(" There is more you know, but you keep it to yourself.");
+        }
+        directive
+    }
+
+    /// Records in a knowledge graph that the facts were shared: each is marked as told to the
+    /// asker, and the asker is noted to have discussed the topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The knowledge graph of the NPC that answered.
+    /// * `game_time` - When the answer was given, in seconds of game time.
+    pub fn record(&self, graph: &mut KnowledgeGraph, game_time: f64) {
+        for fact in self.facts.iter().filter(|fact| !fact.already_told) {
+            let Some(mut relationship) =
+                graph.remove_relationship(&fact.source, &fact.target, &fact.relation)
+            else {
+                continue;
+            };
+            let told = relationship.properties.entry("shared_with".to_string()).or_default();
+            if !told.is_empty() {
+                told.push(',');
+            }
+            told.push_str(&self.asker);
+            graph.add_relationship(relationship);
+        }
+        if graph.get_entity(&self.asker).is_none() {
+            graph.add_entity(Entity::new(self.asker.as_str(), HashMap::new()));
+        }
+        let properties = HashMap::from([("at".to_string(), game_time.to_string())]);
+        graph.add_relationship(Relationship::new(
+            self.asker.as_str(),
+            self.topic.as_str(),
+            "discussed",
+            properties,
+        ));
+    }
+}
+
+/// Renders facts as sentences, grouped by subject.
+fn render_facts(graph: &KnowledgeGraph, facts: &[&SharedFact]) -> String {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for fact in facts {
+        let subject = display_name(graph, &fact.source);
+        let predicate =
+            format!("{} {}", fact.relation.replace('_', " "), display_name(graph, &fact.target));
+        match groups.iter_mut().find(|(s, _)| *s == subject) {
+            Some((_, predicates)) => predicates.push(predicate),
+            None => groups.push((subject, vec![predicate])),
+        }
+    }
+    let sentences: Vec<String> = groups
+        .iter()
+        .map(|(subject, predicates)| render_sentence(subject, predicates))
+        .collect();
+    sentences.join(" ")
+}
+
+/// Answers "What do you know about X?" in character through a dialogue pipeline, and records
+/// what was shared.
+///
+/// The answer's directive is added to the prompt context, so the pipeline's prompt stage must
+/// render it (a `PromptStage` does). If the request fails, nothing is recorded.
+///
+/// # Arguments
+///
+/// * `pipeline` - The pipeline generating the NPC's lines.
+/// * `agent` - The NPC asked.
+/// * `asker` - The ID of who asked.
+/// * `topic` - The ID of the entity asked about.
+/// * `policy` - Which facts are shared.
+///
+/// # Returns
+///
+/// * `Result<String, StageError>` - The NPC's reply, or the error of the stage or request that
+///   failed.
+#[cfg(feature = "network")]
+pub async fn answer(
+    pipeline: &mut Pipeline,
+    agent: &mut NpcAgent,
+    asker: &str,
+    topic: &str,
+    policy: &SharingPolicy,
+) -> Result<String, StageError> {
+    let answer = agent.knowledge_about(asker, topic, policy);
+    let input = format!("What do you know about {}?", display_name(&agent.knowledge, topic));
+    let mut state = PipelineState::new(RequestType::Conversation, &input);
+    state.context.directives.push(answer.directive(&agent.knowledge));
+    state.metadata.insert("npc_id".to_string(), agent.id.to_string());
+    let state = pipeline.run_state(state).await?;
+    agent.record_shared(&answer);
+    Ok(state.reply)
+}
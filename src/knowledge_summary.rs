@@ -0,0 +1,269 @@
+//! # Knowledge Summary Module
+//!
+//! This module turns part of a knowledge graph into a few natural-language sentences for a
+//! prompt, answering "what would this NPC say they know?" without dumping raw triples. The most
+//! relevant facts around the conversation's focus are grouped by subject into sentences such as
+//! "Brom owns the forge and is married to Mira.", and sentences are added until a token budget
+//! is spent.
+//!
+//! The `KnowledgeSummarizer` caches summaries per focus and drops a cached summary when a graph
+//! change touches any entity it was built from.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::knowledge_graph::{GraphChange, KnowledgeGraph, RelevanceQuery};
+
+/// Estimates how many tokens a text takes, at roughly four characters per token.
+///
+/// # Examples
+///
+/// ```
+/// use athena::knowledge_summary::estimate_tokens;
+/// assert_eq!(estimate_tokens("Brom owns the forge."), 5);
+/// ```
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Gets the display name of an entity: its "name" property, or its ID with underscores as
+/// spaces.
+pub(crate) fn display_name(graph: &KnowledgeGraph, id: &str) -> String {
+    graph
+        .get_entity(id)
+        .and_then(|entity| entity.properties.get("name").cloned())
+        .unwrap_or_else(|| id.replace('_', " "))
+}
+
+/// Renders one subject's facts as a sentence.
+pub(crate) fn render_sentence(subject: &str, predicates: &[String]) -> String {
+    let mut sentence = subject.to_string();
+    if let Some(first) = sentence.get_mut(0..1) {
+        first.make_ascii_uppercase();
+    }
+    for (i, predicate) in predicates.iter().enumerate() {
+        let separator = match i {
+            0 => " ",
+            _ if i + 1 == predicates.len() => " and ",
+            _ => ", ",
+        };
+        sentence.push_str(separator);
+        sentence.push_str(predicate);
+    }
+    sentence.push('.');
+    sentence
+}
+
+/// Represents a summary together with what it was built from.
+#[derive(Debug, Clone)]
+struct CachedSummary {
+    text: String,
+    /// Every entity within reach of the focus when the summary was built.
+    entities: HashSet<String>,
+}
+
+/// Summarizes a knowledge graph around a query's focus, within a token budget.
+///
+/// # Arguments
+///
+/// * `graph` - The NPC's knowledge graph.
+/// * `query` - What the conversation is about. Facts are chosen in `ranked_facts` order.
+/// * `token_budget` - The maximum estimated tokens of the summary.
+///
+/// Relation types are phrased as their words (e.g. "works_at" reads "works at"); use a
+/// `KnowledgeSummarizer` to phrase them differently.
+///
+/// # Returns
+///
+/// The summary, or an empty string if no fact is relevant or none fits the budget.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use athena::knowledge_graph::{Entity, KnowledgeGraph, RelevanceQuery, Relationship};
+/// use athena::knowledge_summary::summarize;
+///
+/// let mut graph = KnowledgeGraph::new();
+/// let name = HashMap::from([("name".to_string(), "Mira".to_string())]);
+/// graph.add_entity(Entity::new("mira_01", name));
+/// graph.add_relationship(Relationship::new("brom", "the_forge", "owns", HashMap::new()));
+/// graph.add_relationship(Relationship::new("brom", "mira_01", "trusts", HashMap::new()));
+/// graph.add_relationship(Relationship::new("mira_01", "the_mill", "works_at", HashMap::new()));
+///
+/// let query = RelevanceQuery { focus: vec!["brom".to_string()], ..Default::default() };
+/// assert_eq!(
+///     summarize(&graph, &query, 100),
+///     "Brom owns the forge and trusts Mira. Mira works at the mill."
+/// );
+/// assert_eq!(summarize(&graph, &query, 10), "Brom owns the forge and trusts Mira.");
+/// ```
+pub fn summarize(graph: &KnowledgeGraph, query: &RelevanceQuery, token_budget: usize) -> String {
+    summarize_with_reach(graph, query, token_budget, &HashMap::new()).0
+}
+
+/// Summarizes a graph, also returning every entity the candidate facts touch.
+fn summarize_with_reach(
+    graph: &KnowledgeGraph,
+    query: &RelevanceQuery,
+    token_budget: usize,
+    phrases: &HashMap<String, String>,
+) -> (String, HashSet<String>) {
+    let facts = graph.ranked_facts(query, usize::MAX);
+    let mut reach: HashSet<String> = query.focus.iter().cloned().collect();
+    // Subjects in the order their first fact was chosen, each with its predicates.
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    let mut tokens = 0;
+    for fact in &facts {
+        let relationship = fact.relationship;
+        reach.insert(relationship.source.to_string());
+        reach.insert(relationship.target.to_string());
+
+        let subject = display_name(graph, &relationship.source);
+        let phrase = match phrases.get(relationship.relation_type.as_str()) {
+            Some(phrase) => phrase.clone(),
+            None => relationship.relation_type.replace('_', " "),
+        };
+        let predicate = format!("{} {}", phrase, display_name(graph, &relationship.target));
+        let position = groups.iter().position(|(s, _)| *s == subject);
+        let before = position.map_or(0, |i| {
+            estimate_tokens(&render_sentence(&groups[i].0, &groups[i].1))
+        });
+        let mut predicates = position.map_or_else(Vec::new, |i| groups[i].1.clone());
+        predicates.push(predicate);
+        let after = estimate_tokens(&render_sentence(&subject, &predicates));
+        if tokens - before + after > token_budget {
+            continue;
+        }
+        tokens = tokens - before + after;
+        match position {
+            Some(i) => groups[i].1 = predicates,
+            None => groups.push((subject, predicates)),
+        }
+    }
+    let sentences: Vec<String> = groups
+        .iter()
+        .map(|(subject, predicates)| render_sentence(subject, predicates))
+        .collect();
+    (sentences.join(" "), reach)
+}
+
+/// Summarizes knowledge for prompts, caching summaries between requests.
+#[derive(Debug, Clone)]
+pub struct KnowledgeSummarizer {
+    /// The maximum estimated tokens of a summary.
+    token_budget: usize,
+    /// How to phrase relation types, keyed by type (e.g. "married_to" as "is married to").
+    phrases: HashMap<String, String>,
+    /// Cached summaries keyed by their sorted focus.
+    cache: HashMap<Vec<String>, CachedSummary>,
+}
+
+impl KnowledgeSummarizer {
+    /// Creates a new summarizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_budget` - The maximum estimated tokens of a summary.
+    pub fn new(token_budget: usize) -> Self {
+        KnowledgeSummarizer {
+            token_budget,
+            phrases: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Sets how a relation type is phrased, dropping every cached summary.
+    ///
+    /// # Arguments
+    ///
+    /// * `relation_type` - The relation type (e.g. "married_to").
+    /// * `phrase` - What to say between the subject and object (e.g. "is married to").
+    pub fn set_phrase(&mut self, relation_type: &str, phrase: &str) {
+        self.phrases.insert(relation_type.to_string(), phrase.to_string());
+        self.cache.clear();
+    }
+
+    pub fn get_token_budget(&self) -> usize {
+        self.token_budget
+    }
+
+    /// Sets the token budget, dropping every cached summary.
+    pub fn set_token_budget(&mut self, token_budget: usize) {
+        self.token_budget = token_budget;
+        self.cache.clear();
+    }
+
+    /// Summarizes a graph around a query's focus, reusing the cached summary for the same focus.
+    ///
+    /// The cache is keyed by focus only: a cached summary is reused even if the query's time or
+    /// salience changed, until it is invalidated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{Entity, KnowledgeGraph, RelevanceQuery, Relationship};
+    /// use athena::knowledge_summary::KnowledgeSummarizer;
+    ///
+    /// let mut graph = KnowledgeGraph::new();
+    /// graph.add_relationship(Relationship::new("brom", "forge", "owns", HashMap::new()));
+    /// let mut summarizer = KnowledgeSummarizer::new(50);
+    /// summarizer.set_phrase("married_to", "is married to");
+    /// let query = RelevanceQuery { focus: vec!["brom".to_string()], ..Default::default() };
+    /// assert_eq!(summarizer.summarize(&graph, &query), "Brom owns forge.");
+    ///
+    /// let mut transaction = graph.transaction();
+    /// transaction.add_relationship(Relationship::new("brom", "mira", "married_to", HashMap::new()));
+    /// transaction.commit().unwrap();
+    /// // Until the change is reported, the cached summary is served.
+    /// assert_eq!(summarizer.summarize(&graph, &query), "Brom owns forge.");
+    /// for change in graph.take_changes() {
+    ///     summarizer.invalidate(&change);
+    /// }
+    /// assert_eq!(summarizer.summarize(&graph, &query), "Brom owns forge and is married to mira.");
+    /// ```
+    pub fn summarize(&mut self, graph: &KnowledgeGraph, query: &RelevanceQuery) -> String {
+        let mut key = query.focus.clone();
+        key.sort();
+        if let Some(cached) = self.cache.get(&key) {
+            return cached.text.clone();
+        }
+        let (text, entities) =
+            summarize_with_reach(graph, query, self.token_budget, &self.phrases);
+        self.cache.insert(
+            key,
+            CachedSummary {
+                text: text.clone(),
+                entities,
+            },
+        );
+        text
+    }
+
+    /// Builds a prompt directive from the summary, or `None` if there is nothing to say.
+    pub fn directive(&mut self, graph: &KnowledgeGraph, query: &RelevanceQuery) -> Option<String> {
+        let summary = self.summarize(graph, query);
+        (!summary.is_empty()).then(|| format!("What you know: {}", summary))
+    }
+
+    /// Drops the cached summaries a committed transaction may have made stale: those built from
+    /// an entity the transaction touched.
+    pub fn invalidate(&mut self, change: &GraphChange) {
+        self.cache.retain(|_, cached| !cached.entities.iter().any(|id| change.touches(id)));
+    }
+
+    /// Drops the cached summaries built from an entity, after changing it directly.
+    pub fn invalidate_entity(&mut self, id: &str) {
+        self.cache.retain(|_, cached| !cached.entities.contains(id));
+    }
+
+    /// Drops every cached summary.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Returns the number of cached summaries.
+    pub fn cached_count(&self) -> usize {
+        self.cache.len()
+    }
+}
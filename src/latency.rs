@@ -0,0 +1,199 @@
+//! # Latency Module
+//!
+//! This module puts a time limit on dialogue generation. Each request type has a latency budget;
+//! when the language model does not answer within it, the caller immediately gets a fallback line
+//! (e.g. a cached or authored template line) while the request keeps running in the background.
+//! The finished line is kept and served the next time the same line is asked for, so a slow
+//! request is never wasted and the game never blocks on the network.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cancellation::CancellationToken;
+use crate::dialogue_generation::{DialogueClient, RequestType};
+
+/// Represents where a budgeted reply came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReplySource {
+    /// Generated within the latency budget.
+    Generated,
+    /// Generated in the background after an earlier request ran over budget.
+    Background,
+    /// The fallback line supplied by the caller.
+    Fallback,
+}
+
+/// Represents a line returned within the latency budget.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetedReply {
+    pub text: String,
+    pub source: ReplySource,
+}
+
+/// Generates dialogue within per-request-type latency budgets.
+pub struct BudgetedGenerator {
+    /// The token background requests run under; cancelling it aborts them.
+    cancellation: CancellationToken,
+    client: Arc<DialogueClient>,
+    /// Budgets for specific request types.
+    budgets: HashMap<RequestType, Duration>,
+    /// The budget for request types without a specific budget.
+    default_budget: Duration,
+    /// Lines finished in the background, by key.
+    completed: Arc<Mutex<HashMap<String, String>>>,
+    /// Keys with a request still running in the background.
+    pending: Arc<Mutex<HashSet<String>>>,
+}
+
+impl BudgetedGenerator {
+    /// Creates a new generator with the default budgets: 150 ms for barks and classification,
+    /// 1.5 s for story conversations, and 800 ms for everything else.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::DialogueClient;
+    /// use athena::latency::BudgetedGenerator;
+    /// let generator = BudgetedGenerator::new(DialogueClient::new("my-api-key"));
+    /// ```
+    pub fn new(client: DialogueClient) -> Self {
+        let mut generator = BudgetedGenerator {
+            cancellation: CancellationToken::new(),
+            client: Arc::new(client),
+            budgets: HashMap::new(),
+            default_budget: Duration::from_millis(800),
+            completed: Arc::new(Mutex::new(HashMap::new())),
+            pending: Arc::new(Mutex::new(HashSet::new())),
+        };
+        generator.set_budget(RequestType::Bark, Duration::from_millis(150));
+        generator.set_budget(RequestType::Classification, Duration::from_millis(150));
+        generator.set_budget(RequestType::Story, Duration::from_millis(1500));
+        generator
+    }
+
+    /// Sets the latency budget for a request type.
+    pub fn set_budget(&mut self, request_type: RequestType, budget: Duration) {
+        self.budgets.insert(request_type, budget);
+    }
+
+    /// Gets the latency budget for a request type.
+    pub fn get_budget(&self, request_type: RequestType) -> Duration {
+        self.budgets
+            .get(&request_type)
+            .copied()
+            .unwrap_or(self.default_budget)
+    }
+
+    /// Sets the token background requests run under, e.g. a scene or agent token, so they are
+    /// aborted instead of finishing for a scene that is gone.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Aborts the requests running in the background. Later requests run under a fresh token.
+    pub fn cancel_pending(&mut self) {
+        self.cancellation.cancel();
+        self.cancellation = CancellationToken::new();
+        self.pending.lock().unwrap().clear();
+    }
+
+    /// Returns true if a request for the key is still running in the background.
+    pub fn is_pending(&self, key: &str) -> bool {
+        self.pending.lock().unwrap().contains(key)
+    }
+
+    /// Generates a line, returning the fallback if the budget runs out first.
+    ///
+    /// A line finished in the background for the same key is served first (once). If a request
+    /// for the key is already running, the fallback is returned without starting another one.
+    /// Must be called from within a Tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Identifies the line being asked for (e.g. `"brom:greeting"`).
+    /// * `request_type` - The kind of request, used to pick the budget and the model.
+    /// * `prompt` - The prompt to send.
+    /// * `fallback` - The line to use if generation does not finish in time.
+    ///
+    /// # Returns
+    ///
+    /// A `BudgetedReply` with the line and where it came from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use athena::dialogue_generation::{DialogueClient, RequestType};
+    /// use athena::latency::{BudgetedGenerator, ReplySource};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut generator = BudgetedGenerator::new(DialogueClient::new("my-api-key"));
+    ///     generator.set_budget(RequestType::Bark, Duration::ZERO);
+    ///     let reply = generator
+    ///         .generate("guard:bark", RequestType::Bark, "Say something.", "Move along.")
+    ///         .await;
+    ///     assert_eq!(reply.text, "Move along.");
+    ///     assert_eq!(reply.source, ReplySource::Fallback);
+    /// }
+    /// ```
+    pub async fn generate(
+        &self,
+        key: &str,
+        request_type: RequestType,
+        prompt: &str,
+        fallback: &str,
+    ) -> BudgetedReply {
+        if let Some(text) = self.completed.lock().unwrap().remove(key) {
+            return BudgetedReply {
+                text,
+                source: ReplySource::Background,
+            };
+        }
+        if !self.pending.lock().unwrap().insert(key.to_string()) {
+            return fallback_reply(fallback);
+        }
+
+        let client = Arc::clone(&self.client);
+        let completed = Arc::clone(&self.completed);
+        let pending = Arc::clone(&self.pending);
+        let (task_key, prompt) = (key.to_string(), prompt.to_string());
+        let cancellation = self.cancellation.clone();
+        let task = tokio::spawn(async move {
+            let line = cancellation
+                .run(client.send(request_type, &prompt))
+                .await
+                .ok()
+                .and_then(|response| response.ok())
+                .and_then(|response| response.choices.into_iter().next())
+                .map(|choice| choice.message.content.trim().to_string());
+            // Keep the line for next time in case the caller has given up waiting.
+            if let Some(line) = line {
+                completed.lock().unwrap().insert(task_key.clone(), line);
+            }
+            pending.lock().unwrap().remove(&task_key);
+        });
+
+        match tokio::time::timeout(self.get_budget(request_type), task).await {
+            Ok(_) => match self.completed.lock().unwrap().remove(key) {
+                Some(text) => BudgetedReply {
+                    text,
+                    source: ReplySource::Generated,
+                },
+                None => fallback_reply(fallback),
+            },
+            Err(_) => fallback_reply(fallback),
+        }
+    }
+}
+
+/// Wraps the caller's fallback line in a reply.
+fn fallback_reply(fallback: &str) -> BudgetedReply {
+    BudgetedReply {
+        text: fallback.to_string(),
+        source: ReplySource::Fallback,
+    }
+}
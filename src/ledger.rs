@@ -0,0 +1,293 @@
+//! # Ledger Module
+//!
+//! This module keeps an explicit ledger of what an NPC is owed, what it owes, and whom it holds
+//! a grudge against. Each entry records what happened, with whom, how much it matters, and
+//! optionally when it expires. Unlike reputation, which fades into a general disposition, the
+//! ledger remembers specifics, so the NPC can call them back in dialogue ("You still owe me for
+//! that potion.") and the game can turn them into quests (revenge, calling in a favor).
+//!
+//! The ledger is serializable, so it can be saved and loaded with the rest of the game state.
+
+use serde::{Deserialize, Serialize};
+
+/// Represents the kind of a ledger entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LedgerKind {
+    /// The counterpart owes the NPC.
+    Favor,
+    /// The NPC owes the counterpart.
+    Debt,
+    /// The NPC holds a grudge against the counterpart.
+    Grudge,
+}
+
+/// Represents one entry in the ledger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub kind: LedgerKind,
+    /// Who the entry is about.
+    pub counterpart: String,
+    /// What happened (e.g. "that potion", "burning my barn").
+    pub what: String,
+    /// How much it matters (0.0 or more; 1.0 is a considerable favor or grudge).
+    pub magnitude: f64,
+    /// When it was recorded, in seconds of game time.
+    pub recorded_at: f64,
+    /// When it is forgotten, in seconds of game time, or `None` if never.
+    pub expires_at: Option<f64>,
+}
+
+impl LedgerEntry {
+    /// Returns a line the NPC can say to the counterpart about the entry.
+    pub fn callback(&self) -> String {
+        match self.kind {
+            LedgerKind::Favor => format!("You still owe me for {}.", self.what),
+            LedgerKind::Debt => format!("I haven't forgotten I owe you for {}.", self.what),
+            LedgerKind::Grudge => format!("Don't think I've forgotten {}.", self.what),
+        }
+    }
+
+    /// Returns true if the entry has not expired by a game time.
+    pub fn is_active(&self, now: f64) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// Represents the kind of quest a ledger entry can motivate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HookKind {
+    /// The NPC wants to get back at the counterpart.
+    Revenge,
+    /// The NPC wants the counterpart to repay a favor.
+    CallInFavor,
+    /// The NPC wants to repay the counterpart.
+    Repay,
+    /// The NPC expects the counterpart to do what they committed to in conversation.
+    FollowUp,
+    /// The counterpart revealed something the NPC wants pursued.
+    Lead,
+}
+
+/// Represents a quest opportunity arising from a ledger entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuestHook {
+    pub kind: HookKind,
+    /// Who the quest is about.
+    pub counterpart: String,
+    /// What motivates it.
+    pub reason: String,
+    pub magnitude: f64,
+}
+
+/// Represents an NPC's ledger of favors, debts, and grudges.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ledger {
+    entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    /// Creates a new, empty ledger.
+    pub fn new() -> Self {
+        Ledger::default()
+    }
+
+    /// Records an entry. An existing entry of the same kind, counterpart, and subject is
+    /// reinforced instead: the magnitudes add up and the later expiry is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::ledger::{Ledger, LedgerEntry, LedgerKind};
+    ///
+    /// let mut ledger = Ledger::new();
+    /// let potion = LedgerEntry {
+    ///     kind: LedgerKind::Favor,
+    ///     counterpart: "alice".to_string(),
+    ///     what: "that potion".to_string(),
+    ///     magnitude: 0.5,
+    ///     recorded_at: 0.0,
+    ///     expires_at: None,
+    /// };
+    /// ledger.record(potion.clone());
+    /// ledger.record(potion);
+    /// assert_eq!(ledger.len(), 1);
+    /// assert_eq!(ledger.balance("alice", 0.0), 1.0);
+    /// ```
+    pub fn record(&mut self, entry: LedgerEntry) {
+        let existing = self.entries.iter_mut().find(|existing| {
+            existing.kind == entry.kind
+                && existing.counterpart == entry.counterpart
+                && existing.what == entry.what
+        });
+        match existing {
+            Some(existing) => {
+                existing.magnitude += entry.magnitude.max(0.0);
+                existing.expires_at = match (existing.expires_at, entry.expires_at) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+            }
+            None => self.entries.push(LedgerEntry {
+                magnitude: entry.magnitude.max(0.0),
+                ..entry
+            }),
+        }
+    }
+
+    /// Settles entries of a kind with a counterpart by an amount, largest first. Entries paid
+    /// off in full are removed.
+    ///
+    /// # Returns
+    ///
+    /// The part of the amount that was not needed.
+    pub fn settle(&mut self, kind: LedgerKind, counterpart: &str, amount: f64) -> f64 {
+        let mut remaining = amount.max(0.0);
+        self.entries.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+        for entry in self
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.kind == kind && entry.counterpart == counterpart)
+        {
+            let paid = remaining.min(entry.magnitude);
+            entry.magnitude -= paid;
+            remaining -= paid;
+        }
+        self.entries.retain(|entry| entry.magnitude > 1e-9);
+        remaining
+    }
+
+    /// Removes the entries that have expired by a game time.
+    ///
+    /// # Returns
+    ///
+    /// The expired entries.
+    pub fn expire(&mut self, now: f64) -> Vec<LedgerEntry> {
+        let (active, expired) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|entry| entry.is_active(now));
+        self.entries = active;
+        expired
+    }
+
+    /// Iterates over all entries.
+    pub fn entries(&self) -> impl Iterator<Item = &LedgerEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the ledger has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets the active entries about a counterpart, largest first.
+    pub fn entries_with(&self, counterpart: &str, now: f64) -> Vec<&LedgerEntry> {
+        let mut entries: Vec<&LedgerEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.counterpart == counterpart && entry.is_active(now))
+            .collect();
+        entries.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+        entries
+    }
+
+    /// Returns what a counterpart owes the NPC minus what the NPC owes them.
+    pub fn balance(&self, counterpart: &str, now: f64) -> f64 {
+        self.entries_with(counterpart, now)
+            .iter()
+            .map(|entry| match entry.kind {
+                LedgerKind::Favor => entry.magnitude,
+                LedgerKind::Debt => -entry.magnitude,
+                LedgerKind::Grudge => 0.0,
+            })
+            .sum()
+    }
+
+    /// Picks the line the NPC says about the largest active entry with a counterpart, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::ledger::{Ledger, LedgerEntry, LedgerKind};
+    ///
+    /// let mut ledger = Ledger::new();
+    /// ledger.record(LedgerEntry {
+    ///     kind: LedgerKind::Favor,
+    ///     counterpart: "alice".to_string(),
+    ///     what: "that potion".to_string(),
+    ///     magnitude: 0.5,
+    ///     recorded_at: 0.0,
+    ///     expires_at: Some(3_600.0),
+    /// });
+    /// assert_eq!(ledger.callback("alice", 60.0).unwrap(), "You still owe me for that potion.");
+    /// assert!(ledger.callback("alice", 7_200.0).is_none());
+    /// ```
+    pub fn callback(&self, counterpart: &str, now: f64) -> Option<String> {
+        self.entries_with(counterpart, now).first().map(|entry| entry.callback())
+    }
+
+    /// Builds the prompt directives reminding the NPC of its active entries with a counterpart.
+    pub fn directives(&self, counterpart: &str, now: f64) -> Vec<String> {
+        self.entries_with(counterpart, now)
+            .into_iter()
+            .map(|entry| match entry.kind {
+                LedgerKind::Favor => {
+                    format!("They owe you for {}; you may remind them.", entry.what)
+                }
+                LedgerKind::Debt => format!("You owe them for {}.", entry.what),
+                LedgerKind::Grudge => {
+                    format!("You hold a grudge against them for {}.", entry.what)
+                }
+            })
+            .collect()
+    }
+
+    /// Lists the quests the NPC's entries could motivate, largest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current game time; expired entries are skipped.
+    /// * `min_magnitude` - How much an entry must matter to motivate a quest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::ledger::{HookKind, Ledger, LedgerEntry, LedgerKind};
+    ///
+    /// let mut ledger = Ledger::new();
+    /// ledger.record(LedgerEntry {
+    ///     kind: LedgerKind::Grudge,
+    ///     counterpart: "bandit_chief".to_string(),
+    ///     what: "burning my barn".to_string(),
+    ///     magnitude: 2.0,
+    ///     recorded_at: 0.0,
+    ///     expires_at: None,
+    /// });
+    /// let hooks = ledger.quest_hooks(0.0, 1.0);
+    /// assert_eq!(hooks[0].kind, HookKind::Revenge);
+    /// assert_eq!(hooks[0].counterpart, "bandit_chief");
+    /// ```
+    pub fn quest_hooks(&self, now: f64, min_magnitude: f64) -> Vec<QuestHook> {
+        let mut hooks: Vec<QuestHook> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_active(now) && entry.magnitude >= min_magnitude)
+            .map(|entry| QuestHook {
+                kind: match entry.kind {
+                    LedgerKind::Favor => HookKind::CallInFavor,
+                    LedgerKind::Debt => HookKind::Repay,
+                    LedgerKind::Grudge => HookKind::Revenge,
+                },
+                counterpart: entry.counterpart.clone(),
+                reason: entry.what.clone(),
+                magnitude: entry.magnitude,
+            })
+            .collect();
+        hooks.sort_by(|a, b| b.magnitude.total_cmp(&a.magnitude));
+        hooks
+    }
+}
@@ -1,5 +1,87 @@
 pub mod adaptive_intelligence;
+pub mod address;
+pub mod agent;
+pub mod annoyance;
+pub mod appraisal;
+mod arena;
+#[cfg(feature = "archetypes")]
+pub mod archetypes;
+pub mod audit;
+pub mod bark;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cancellation;
+pub mod chatter;
+pub mod checkpoint;
+pub mod companionship;
+pub mod conversation;
+pub mod conversation_goal;
+pub mod coping;
+pub mod cost;
+pub mod crime;
+pub mod culture;
+pub mod definition;
+pub mod dialogue_act;
 pub mod dialogue_generation;
+pub mod emotion_timeline;
 pub mod emotional_response;
+pub mod empathy;
+pub mod ending;
+pub mod experiment;
+pub mod fact_check;
+pub mod faction;
+pub mod failover;
+pub mod gifts;
+pub mod injection;
+pub mod interaction;
+pub mod interlocutor;
 pub mod knowledge_graph;
-pub mod personality;
\ No newline at end of file
+pub mod knowledge_query;
+pub mod knowledge_summary;
+#[cfg(feature = "network")]
+pub mod latency;
+pub mod ledger;
+pub mod localization;
+pub mod lore;
+pub mod migration;
+pub mod morality;
+pub mod needs;
+pub mod network;
+pub mod news;
+pub mod norms;
+pub mod perception;
+pub mod personality;
+pub mod persuasion;
+pub mod physiology;
+#[cfg(feature = "network")]
+pub mod pipeline;
+pub mod postprocess;
+pub mod prewarm;
+pub mod profiling;
+pub mod promises;
+pub mod prompt;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quest;
+pub mod quest_giver;
+pub mod questionnaire;
+pub mod quota;
+pub mod recall;
+pub mod reflection;
+pub mod relationship;
+pub mod reputation;
+pub mod scenario;
+pub mod schedule;
+pub mod simulation;
+pub mod snapshot;
+pub mod structured;
+pub mod symbol;
+pub mod tools;
+pub mod topic;
+pub mod trading;
+#[cfg(feature = "network")]
+pub mod transport;
+pub mod utility;
+pub mod validation;
+pub mod variation;
+pub mod voice;
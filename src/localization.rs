@@ -0,0 +1,320 @@
+//! # Localization Module
+//!
+//! This module checks that an NPC stays the same person in every language it speaks. A line
+//! generated or translated for several locales is compared with its source: names and numbers
+//! must carry over (a blacksmith called Brom who charges 30 gold must not become someone else
+//! charging 40), an exclamation or question must stay one, and when the game supplies
+//! embeddings from a multilingual model, each version must mean nearly the same thing as the
+//! source. A language model can also act as judge, comparing each version with the persona.
+//!
+//! Divergences are collected in a report and flagged for review rather than fixed, since only a
+//! translator can tell a bad translation from a good one that reads differently.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "network")]
+use serde_json::{json, Value};
+
+#[cfg(feature = "network")]
+use crate::dialogue_generation::{DialogueClient, RequestType};
+use crate::lore::cosine_similarity;
+use crate::prompt::PersonaCard;
+#[cfg(feature = "network")]
+use crate::structured;
+
+/// Represents one version of a line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalizedLine {
+    /// The locale of the line (e.g. "en", "de-DE").
+    pub locale: String,
+    pub text: String,
+    /// An embedding of the text from a multilingual model, if the game computes them.
+    pub embedding: Option<Vec<f64>>,
+}
+
+impl LocalizedLine {
+    /// Creates a line without an embedding.
+    pub fn new(locale: &str, text: &str) -> Self {
+        LocalizedLine {
+            locale: locale.to_string(),
+            text: text.to_string(),
+            embedding: None,
+        }
+    }
+
+    /// Sets the embedding of the line.
+    pub fn with_embedding(mut self, embedding: Vec<f64>) -> Self {
+        self.embedding = Some(embedding);
+        self
+    }
+}
+
+/// Represents how a version of a line diverges from its source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DivergenceKind {
+    /// A name or number in the source is missing.
+    MissingTerm(String),
+    /// The source exclaims or asks, and the version does not, or the other way round.
+    Tone,
+    /// The embeddings are less similar than the policy allows.
+    Meaning { similarity: f64 },
+    /// The language model judge found the version inconsistent with the persona.
+    Judged { reason: String },
+}
+
+/// Represents a divergence flagged for review.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    pub locale: String,
+    pub kind: DivergenceKind,
+}
+
+impl Divergence {
+    /// Describes the divergence for a reviewer.
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            DivergenceKind::MissingTerm(term) => {
+                format!("[{}] \"{}\" is missing", self.locale, term)
+            }
+            DivergenceKind::Tone => format!("[{}] the tone differs from the source", self.locale),
+            DivergenceKind::Meaning { similarity } => format!(
+                "[{}] the meaning drifts from the source (similarity {:.2})",
+                self.locale, similarity
+            ),
+            DivergenceKind::Judged { reason } => format!("[{}] {}", self.locale, reason),
+        }
+    }
+}
+
+/// Represents how strict consistency checks are.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyPolicy {
+    /// The lowest embedding similarity a version may have to its source.
+    pub min_similarity: f64,
+    /// Whether exclamations and questions must be kept.
+    pub check_tone: bool,
+}
+
+impl Default for ConsistencyPolicy {
+    fn default() -> Self {
+        ConsistencyPolicy {
+            min_similarity: 0.85,
+            check_tone: true,
+        }
+    }
+}
+
+/// Represents the result of checking the versions of a line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    /// The locale of the source line.
+    pub source_locale: String,
+    /// The embedding similarity of each version to the source, where both have embeddings.
+    pub similarities: Vec<(String, f64)>,
+    pub divergences: Vec<Divergence>,
+}
+
+impl ConsistencyReport {
+    /// Returns true if no version diverges.
+    pub fn is_consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// Gets the locales flagged for review, in the order they were checked.
+    pub fn flagged_locales(&self) -> Vec<&str> {
+        let mut locales: Vec<&str> = Vec::new();
+        for divergence in &self.divergences {
+            if !locales.contains(&divergence.locale.as_str()) {
+                locales.push(&divergence.locale);
+            }
+        }
+        locales
+    }
+
+    /// Describes every divergence for a reviewer, one per line.
+    pub fn review_notes(&self) -> Vec<String> {
+        self.divergences.iter().map(Divergence::describe).collect()
+    }
+}
+
+/// Finds the terms of a line that must carry over to every version: the numbers, the names
+/// (capitalized words not starting a sentence, except the English "I"), and the persona's name.
+/// Names are only told apart this way in languages that capitalize little else, so sources
+/// should be written in one of those.
+fn preserved_terms(text: &str, persona: &PersonaCard) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    let mut sentence_start = true;
+    for word in text.split_whitespace() {
+        let trimmed = word.trim_matches(|c: char| !c.is_alphanumeric());
+        let is_number = trimmed.chars().any(|c| c.is_ascii_digit());
+        let pronoun = trimmed == "I" || trimmed.starts_with("I'");
+        let capitalized = trimmed.chars().next().is_some_and(char::is_uppercase);
+        let is_name = !sentence_start && capitalized && !pronoun;
+        if (is_number || is_name) && !terms.iter().any(|term| term == trimmed) {
+            terms.push(trimmed.to_string());
+        }
+        sentence_start = word.ends_with(['.', '!', '?']);
+    }
+    if !persona.name.is_empty() && text.contains(&persona.name) && !terms.contains(&persona.name) {
+        terms.push(persona.name.clone());
+    }
+    terms
+}
+
+/// Gets the sentence-final mark of a line that sets its tone.
+fn final_mark(text: &str) -> Option<char> {
+    text.trim_end_matches(|c: char| c.is_whitespace() || c == '"' || c == '»' || c == '”')
+        .chars()
+        .last()
+        .filter(|c| matches!(c, '!' | '?' | '！' | '？'))
+        .map(|c| match c {
+            '！' => '!',
+            '？' => '?',
+            c => c,
+        })
+}
+
+/// Checks the versions of a line against its source without a language model.
+///
+/// # Arguments
+///
+/// * `persona` - The persona of the NPC speaking the line.
+/// * `source` - The line in the locale it was written or generated in.
+/// * `versions` - The line in the other locales.
+/// * `policy` - How strict the checks are.
+///
+/// # Examples
+///
+/// ```
+/// use athena::localization::{check, ConsistencyPolicy, LocalizedLine};
+/// use athena::prompt::PersonaCard;
+///
+/// let persona = PersonaCard::new("Brom", "The village blacksmith.");
+/// let source = LocalizedLine::new("en", "I'm Brom, and that sword costs 30 gold!");
+/// let versions = vec![
+///     LocalizedLine::new("de", "Ich bin Brom, und das Schwert kostet 30 Gold!"),
+///     LocalizedLine::new("fr", "Je suis Bram, et cette épée coûte 40 pièces d'or."),
+/// ];
+/// let report = check(&persona, &source, &versions, &ConsistencyPolicy::default());
+/// assert_eq!(report.flagged_locales(), vec!["fr"]);
+/// assert_eq!(report.review_notes().len(), 3);
+/// ```
+pub fn check(
+    persona: &PersonaCard,
+    source: &LocalizedLine,
+    versions: &[LocalizedLine],
+    policy: &ConsistencyPolicy,
+) -> ConsistencyReport {
+    let terms = preserved_terms(&source.text, persona);
+    let mut report = ConsistencyReport {
+        source_locale: source.locale.clone(),
+        ..ConsistencyReport::default()
+    };
+    for version in versions {
+        let mut flag = |kind| {
+            report.divergences.push(Divergence {
+                locale: version.locale.clone(),
+                kind,
+            })
+        };
+        for term in terms.iter().filter(|term| !version.text.contains(term.as_str())) {
+            flag(DivergenceKind::MissingTerm(term.clone()));
+        }
+        if policy.check_tone && final_mark(&source.text) != final_mark(&version.text) {
+            flag(DivergenceKind::Tone);
+        }
+        if let (Some(a), Some(b)) = (&source.embedding, &version.embedding) {
+            let similarity = cosine_similarity(a, b);
+            if similarity < policy.min_similarity {
+                flag(DivergenceKind::Meaning { similarity });
+            }
+            report.similarities.push((version.locale.clone(), similarity));
+        }
+    }
+    report
+}
+
+/// Represents the verdict of a language model judge on one version of a line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JudgeVerdict {
+    /// Whether the version keeps the source's facts about the persona and its world.
+    pub facts_preserved: bool,
+    /// Whether the version keeps the persona's tone and speaking style.
+    pub tone_preserved: bool,
+    /// Why the version is inconsistent, if it is.
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// Builds the prompt asking a language model to judge a version of a line.
+pub fn judge_prompt(
+    persona: &PersonaCard,
+    source: &LocalizedLine,
+    version: &LocalizedLine,
+) -> String {
+    format!(
+        "{}\n\nCompare a line this character says in {} with its {} version. Judge whether the \
+         version keeps every fact and the character's tone and speaking style.\n\n{}: {}\n{}: {}",
+        persona.render(),
+        source.locale,
+        version.locale,
+        source.locale,
+        source.text,
+        version.locale,
+        version.text
+    )
+}
+
+/// Gets the JSON schema of a judge's verdict.
+#[cfg(feature = "network")]
+fn verdict_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "facts_preserved": { "type": "boolean" },
+            "tone_preserved": { "type": "boolean" },
+            "reason": { "type": "string" }
+        },
+        "required": ["facts_preserved", "tone_preserved"]
+    })
+}
+
+/// Checks the versions of a line like `check`, and also has a language model judge each one.
+///
+/// # Returns
+///
+/// * `Result<ConsistencyReport, Box<dyn std::error::Error>>` - The report, or an error if the
+///   judge did not produce a valid verdict.
+#[cfg(feature = "network")]
+pub async fn check_with_judge(
+    client: &DialogueClient,
+    persona: &PersonaCard,
+    source: &LocalizedLine,
+    versions: &[LocalizedLine],
+    policy: &ConsistencyPolicy,
+) -> Result<ConsistencyReport, Box<dyn std::error::Error>> {
+    let mut report = check(persona, source, versions, policy);
+    let schema = verdict_schema();
+    for version in versions {
+        let prompt = judge_prompt(persona, source, version);
+        let verdict: JudgeVerdict = structured::generate_structured(
+            client,
+            RequestType::Classification,
+            &prompt,
+            Some(&schema),
+            2,
+        )
+        .await?;
+        if !verdict.facts_preserved || !verdict.tone_preserved {
+            let reason = if verdict.reason.is_empty() {
+                "the judge found the version inconsistent with the persona".to_string()
+            } else {
+                verdict.reason
+            };
+            report.divergences.push(Divergence {
+                locale: version.locale.clone(),
+                kind: DivergenceKind::Judged { reason },
+            });
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,218 @@
+//! # Lore Module
+//!
+//! This module holds the canonical world lore: the history, geography, and institutions every NPC
+//! should agree on. Unlike an NPC's knowledge graph, which holds what that one NPC has seen and
+//! heard, the lore database is shared by the whole world and is treated as true.
+//!
+//! Each entry carries tags and, optionally, an embedding computed by the game. Entries relevant to
+//! a request are retrieved by tag, keyword overlap, and embedding similarity, and rendered as a
+//! prompt directive, so every NPC describes the same history in the same way.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_act::normalize;
+
+/// Represents one piece of canonical lore.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoreEntry {
+    /// A unique ID for the entry (e.g. "fall_of_varn").
+    pub id: String,
+    pub title: String,
+    pub text: String,
+    /// Tags used for retrieval (e.g. "history", "riverside").
+    pub tags: Vec<String>,
+    /// An embedding of the text, if the game computes them.
+    pub embedding: Option<Vec<f64>>,
+}
+
+impl LoreEntry {
+    /// Creates a new lore entry without tags or an embedding.
+    pub fn new(id: &str, title: &str, text: &str) -> Self {
+        LoreEntry {
+            id: id.to_string(),
+            title: title.to_string(),
+            text: text.to_string(),
+            tags: Vec::new(),
+            embedding: None,
+        }
+    }
+
+    /// Adds a tag to the entry.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_lowercase());
+        self
+    }
+
+    /// Sets the embedding of the entry.
+    pub fn with_embedding(mut self, embedding: Vec<f64>) -> Self {
+        self.embedding = Some(embedding);
+        self
+    }
+}
+
+/// Represents what a request needs lore about.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoreQuery {
+    /// Free text, usually the player's line or the current topic.
+    pub text: String,
+    /// Tags that should be preferred (e.g. the NPC's location).
+    pub tags: Vec<String>,
+    /// An embedding of the text, if the game computes them.
+    pub embedding: Option<Vec<f64>>,
+}
+
+/// Computes the cosine similarity of two vectors, or 0.0 if either is empty or zero.
+pub(crate) fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Splits text into lowercase words of at least four letters, which skips most filler words.
+/// Possessives and hyphenated words are split too, so "Varn's" matches "Varn".
+pub(crate) fn keywords(text: &str) -> Vec<String> {
+    normalize(text)
+        .split([' ', '\'', '-'])
+        .filter(|word| word.chars().count() >= 4)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Stores the canonical world lore.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoreDatabase {
+    entries: Vec<LoreEntry>,
+}
+
+impl LoreDatabase {
+    /// Creates a new, empty lore database.
+    pub fn new() -> Self {
+        LoreDatabase::default()
+    }
+
+    /// Adds an entry, replacing any entry with the same ID.
+    pub fn add_entry(&mut self, entry: LoreEntry) {
+        self.entries.retain(|existing| existing.id != entry.id);
+        self.entries.push(entry);
+    }
+
+    /// Removes an entry by ID.
+    pub fn remove_entry(&mut self, id: &str) -> Option<LoreEntry> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Gets an entry by ID.
+    pub fn get_entry(&self, id: &str) -> Option<&LoreEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Iterates over the entries.
+    pub fn entries(&self) -> impl Iterator<Item = &LoreEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the database is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Scores how relevant an entry is to a query.
+    ///
+    /// Each matching tag adds 1.0, each query keyword found in the title, text, or tags adds 0.5,
+    /// and embedding similarity adds up to 2.0 when both sides have an embedding.
+    fn relevance(entry: &LoreEntry, query: &LoreQuery, words: &[String]) -> f64 {
+        let tags = query
+            .tags
+            .iter()
+            .filter(|tag| entry.tags.contains(&tag.to_lowercase()))
+            .count() as f64;
+        let haystack = format!("{} {} {}", entry.title, entry.text, entry.tags.join(" "));
+        let entry_words = keywords(&haystack);
+        let overlap = words.iter().filter(|word| entry_words.contains(word)).count() as f64;
+        let similarity = match (&entry.embedding, &query.embedding) {
+            (Some(a), Some(b)) => cosine_similarity(a, b).max(0.0),
+            _ => 0.0,
+        };
+        tags + 0.5 * overlap + 2.0 * similarity
+    }
+
+    /// Retrieves the entries most relevant to a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - What the request needs lore about.
+    /// * `limit` - The maximum number of entries to return.
+    ///
+    /// # Returns
+    ///
+    /// The relevant entries, most relevant first. Entries with no relevance are never returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::lore::{LoreDatabase, LoreEntry, LoreQuery};
+    ///
+    /// let mut lore = LoreDatabase::new();
+    /// lore.add_entry(
+    ///     LoreEntry::new("fall_of_varn", "The Fall of Varn", "Varn burned in the Dragon War.")
+    ///         .with_tag("history"),
+    /// );
+    /// lore.add_entry(LoreEntry::new("riverside", "Riverside", "A mill town on the Tamber."));
+    ///
+    /// let query = LoreQuery { text: "What happened to Varn?".to_string(), ..Default::default() };
+    /// let found = lore.retrieve(&query, 3);
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].id, "fall_of_varn");
+    /// ```
+    pub fn retrieve(&self, query: &LoreQuery, limit: usize) -> Vec<&LoreEntry> {
+        let words = keywords(&query.text);
+        let mut scored: Vec<(f64, &LoreEntry)> = self
+            .entries
+            .iter()
+            .map(|entry| (Self::relevance(entry, query, &words), entry))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(limit).map(|(_, entry)| entry).collect()
+    }
+
+    /// Retrieves the lore relevant to a query and renders it as a prompt directive.
+    ///
+    /// # Returns
+    ///
+    /// The directive, or `None` if no lore is relevant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::lore::{LoreDatabase, LoreEntry, LoreQuery};
+    ///
+    /// let mut lore = LoreDatabase::new();
+    /// lore.add_entry(LoreEntry::new("riverside", "Riverside", "A mill town on the Tamber."));
+    /// let query = LoreQuery { text: "Tell me about Riverside.".to_string(), ..Default::default() };
+    /// let directive = lore.directive(&query, 3).unwrap();
+    /// assert!(directive.contains("- Riverside: A mill town on the Tamber."));
+    /// ```
+    pub fn directive(&self, query: &LoreQuery, limit: usize) -> Option<String> {
+        let entries = self.retrieve(query, limit);
+        if entries.is_empty() {
+            return None;
+        }
+        let mut text = "Established world lore (never contradict it):".to_string();
+        for entry in entries {
+            text.push_str(&format!("\n- {}: {}", entry.title, entry.text));
+        }
+        Some(text)
+    }
+}
@@ -0,0 +1,299 @@
+//! # Migration Module
+//!
+//! This module upgrades saved NPC state when a new version of the game changes the shape of
+//! Athena's data. Each persisted type gets a `Migrator` holding its migrations in order (see
+//! `snapshot::Persist::migrator`); the number of migrations is the current schema version,
+//! which is stamped into every snapshot the migrator writes. Loading an older snapshot runs the
+//! missing migrations on its JSON form before deserializing it, so player saves are upgraded
+//! instead of failing to load or, worse, loading with fields silently reset.
+//!
+//! Migrations edit the data as JSON, so only JSON snapshots (and plain JSON saves without a
+//! header, which count as version 0) can be migrated; a binary snapshot can only be read by the
+//! type definitions that wrote it. Saves that must outlive a game patch should be written as
+//! JSON, compressed if size matters. Snapshots stamped `UNVERSIONED` are read as they are, since
+//! there is no telling which migrations they lack.
+//!
+//! A failed migration leaves the save untouched: `upgrade_file` only replaces a file once the
+//! upgraded data has deserialized, and keeps the old file next to it as a backup.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::snapshot::{self, Encoding, SnapshotOptions, UNVERSIONED};
+
+/// A function upgrading data from one schema version to the next.
+type MigrationFn = Box<dyn Fn(&mut Value) -> Result<(), Box<dyn std::error::Error>> + Send + Sync>;
+
+/// Represents one step upgrading saved data from one schema version to the next.
+pub struct Migration {
+    /// The schema version the migration upgrades from.
+    pub from: u32,
+    /// What the migration changes, for logs.
+    pub description: String,
+    apply: MigrationFn,
+}
+
+/// Upgrades the saved data of one type through its schema versions.
+#[derive(Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Creates a migrator without migrations, at schema version 0.
+    pub fn new() -> Self {
+        Migrator::default()
+    }
+
+    /// Adds a migration upgrading the data from the current schema version to the next.
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - What the migration changes.
+    /// * `apply` - Edits the JSON form of the data in place.
+    pub fn with_migration(
+        mut self,
+        description: &str,
+        apply: impl Fn(&mut Value) -> Result<(), Box<dyn std::error::Error>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.migrations.push(Migration {
+            from: self.current_version(),
+            description: description.to_string(),
+            apply: Box::new(apply),
+        });
+        self
+    }
+
+    /// Gets the schema version data is upgraded to.
+    pub fn current_version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// Gets the migrations, oldest first.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Upgrades the JSON form of data to the current schema version.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The data, edited in place.
+    /// * `version` - The schema version the data was saved with.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<&str>, Box<dyn std::error::Error>>` - The descriptions of the migrations
+    ///   applied, or an error if one failed or the data is newer than the current version.
+    pub fn migrate(
+        &self,
+        value: &mut Value,
+        version: u32,
+    ) -> Result<Vec<&str>, Box<dyn std::error::Error>> {
+        if version > self.current_version() {
+            return Err(format!(
+                "schema version {} is newer than the supported version {}",
+                version,
+                self.current_version()
+            )
+            .into());
+        }
+        let mut applied = Vec::new();
+        for migration in &self.migrations[version as usize..] {
+            (migration.apply)(value).map_err(|error| {
+                format!(
+                    "migration from schema version {} ({}) failed: {}",
+                    migration.from, migration.description, error
+                )
+            })?;
+            applied.push(migration.description.as_str());
+        }
+        Ok(applied)
+    }
+
+    /// Serializes a value into a snapshot stamped with the current schema version.
+    pub fn encode<T: Serialize + ?Sized>(
+        &self,
+        value: &T,
+        options: SnapshotOptions,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        snapshot::encode_versioned(value, options, self.current_version())
+    }
+
+    /// Deserializes a value from a snapshot or plain JSON, upgrading it first if it was saved
+    /// with an older schema version.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<T, Box<dyn std::error::Error>>` - The value, or an error if a migration failed,
+    ///   the data is newer than the current version, or it is an older binary snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::conversation::Conversation;
+    /// use athena::migration::{rename_field, set_default, Migrator};
+    /// use athena::snapshot::{encode, SnapshotHeader, SnapshotOptions, UNVERSIONED};
+    ///
+    /// // Version 1 renamed `npc` to `npc_id`; version 2 added `player_id`.
+    /// let migrator = Migrator::new()
+    ///     .with_migration("rename npc to npc_id", |data| rename_field(data, "", "npc", "npc_id"))
+    ///     .with_migration("add player_id", |data| {
+    ///         set_default(data, "", "player_id", "player".into())
+    ///     });
+    ///
+    /// let old_save = br#"{"npc": "blacksmith", "turns": [{"speaker": "player", "text": "Hi"}]}"#;
+    /// let conversation: Conversation = migrator.decode(old_save).unwrap();
+    /// assert_eq!(conversation.npc_id, "blacksmith");
+    /// assert_eq!(conversation.player_id, "player");
+    /// assert_eq!(conversation.turns.len(), 1);
+    ///
+    /// // Snapshots written without a schema version are read as they are.
+    /// let unversioned = encode(&conversation, SnapshotOptions::json()).unwrap();
+    /// assert_eq!(SnapshotHeader::read(&unversioned).unwrap().unwrap().schema, UNVERSIONED);
+    /// assert_eq!(migrator.decode::<Conversation>(&unversioned).unwrap(), conversation);
+    /// ```
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        let (header, payload) = snapshot::unpack(bytes)?;
+        let version = header.map_or(0, |header| header.schema);
+        if version == self.current_version() || version == UNVERSIONED {
+            return snapshot::decode(bytes);
+        }
+        if header.is_some_and(|header| header.encoding == Encoding::Binary) {
+            return Err(format!(
+                "binary snapshot of schema version {} cannot be read at schema version {}",
+                version,
+                self.current_version()
+            )
+            .into());
+        }
+        let mut value: Value = serde_json::from_slice(&payload)?;
+        self.migrate(&mut value, version)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Upgrades a save file to the current schema version in place, keeping the original next
+    /// to it with a `.bak` extension added. Files already at the current version are left alone,
+    /// and `UNVERSIONED` files are stamped with it without being migrated.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u32, Box<dyn std::error::Error>>` - The schema version the file had, or an error
+    ///   if it could not be upgraded, in which case it is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::knowledge_graph::KnowledgeGraph;
+    /// use athena::migration::{set_default, Migrator};
+    /// use athena::snapshot::{SnapshotHeader, SnapshotOptions};
+    ///
+    /// let migrator = Migrator::new().with_migration("weigh relationships", |data| {
+    ///     set_default(data, "/relationships", "weight", 1.0.into())
+    /// });
+    /// let path = std::env::temp_dir().join("athena_migration_doctest.json");
+    /// let old_save = r#"{"entities": [], "relationships": [
+    ///     {"source": "hald", "target": "orla", "relation_type": "knows", "properties": {}}
+    /// ]}"#;
+    /// std::fs::write(&path, old_save).unwrap();
+    ///
+    /// let version = migrator.upgrade_file::<KnowledgeGraph>(&path, SnapshotOptions::json());
+    /// assert_eq!(version.unwrap(), 0);
+    /// let upgraded = std::fs::read(&path).unwrap();
+    /// assert_eq!(SnapshotHeader::read(&upgraded).unwrap().unwrap().schema, 1);
+    /// let graph: KnowledgeGraph = migrator.decode(&upgraded).unwrap();
+    /// assert_eq!(graph.get_relationships("hald")[0].weight, 1.0);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # std::fs::remove_file(path.with_extension("json.bak")).unwrap();
+    /// ```
+    pub fn upgrade_file<T: Serialize + DeserializeOwned>(
+        &self,
+        path: impl AsRef<Path>,
+        options: SnapshotOptions,
+    ) -> Result<u32, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let version = snapshot::SnapshotHeader::read(&bytes)?.map_or(0, |header| header.schema);
+        if version == self.current_version() {
+            return Ok(version);
+        }
+        let value: T = self.decode(&bytes)?;
+        let upgraded = self.encode(&value, options)?;
+        fs::write(backup_path(path), &bytes)?;
+        fs::write(path, upgraded)?;
+        Ok(version)
+    }
+}
+
+/// Gets the path a file is backed up to before it is upgraded.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// Gets the objects a JSON pointer designates: the object itself, or every object in an array.
+fn objects_at<'a>(
+    value: &'a mut Value,
+    pointer: &str,
+) -> Result<Vec<&'a mut Map<String, Value>>, Box<dyn std::error::Error>> {
+    match value.pointer_mut(pointer) {
+        Some(Value::Object(object)) => Ok(vec![object]),
+        Some(Value::Array(items)) => {
+            Ok(items.iter_mut().filter_map(Value::as_object_mut).collect())
+        }
+        Some(_) => Err(format!("\"{}\" is neither an object nor an array", pointer).into()),
+        None => Err(format!("\"{}\" is missing", pointer).into()),
+    }
+}
+
+/// Renames a field of the objects at a JSON pointer ("" for the root; an array applies to each
+/// of its objects). Objects without the field are left alone.
+pub fn rename_field(
+    value: &mut Value,
+    pointer: &str,
+    from: &str,
+    to: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for object in objects_at(value, pointer)? {
+        if let Some(field) = object.remove(from) {
+            object.insert(to.to_string(), field);
+        }
+    }
+    Ok(())
+}
+
+/// Adds a field to the objects at a JSON pointer that lack it.
+pub fn set_default(
+    value: &mut Value,
+    pointer: &str,
+    field: &str,
+    default: Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for object in objects_at(value, pointer)? {
+        object.entry(field).or_insert_with(|| default.clone());
+    }
+    Ok(())
+}
+
+/// Removes a field from the objects at a JSON pointer.
+pub fn remove_field(
+    value: &mut Value,
+    pointer: &str,
+    field: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for object in objects_at(value, pointer)? {
+        object.remove(field);
+    }
+    Ok(())
+}
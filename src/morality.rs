@@ -0,0 +1,179 @@
+//! # Morality Module
+//!
+//! This module gives NPCs moral values: how much they care about care (not harming others),
+//! fairness, loyalty, authority, and liberty. Values bias how strongly the NPC appraises what
+//! others do (a fairness-minded NPC is outraged by theft, an authority-minded one by disrespect)
+//! and which actions it prefers itself (a caring NPC helps, an authority-minded one alerts the
+//! guards, a liberty-minded one goes its own way).
+//!
+//! Every value defaults to 0.5, which has no effect. Values can be set per NPC, or given to a
+//! culture or faction and adopted by its members.
+
+use serde::{Deserialize, Serialize};
+
+use crate::reputation::DeedCategory;
+
+/// Represents a moral foundation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Foundation {
+    Care,
+    Fairness,
+    Loyalty,
+    Authority,
+    Liberty,
+}
+
+impl Foundation {
+    /// Every foundation.
+    pub const ALL: [Foundation; 5] = [
+        Foundation::Care,
+        Foundation::Fairness,
+        Foundation::Loyalty,
+        Foundation::Authority,
+        Foundation::Liberty,
+    ];
+}
+
+/// Represents how much an NPC cares about each moral foundation (between 0.0 and 1.0).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoralValues {
+    pub care: f64,
+    pub fairness: f64,
+    pub loyalty: f64,
+    pub authority: f64,
+    pub liberty: f64,
+}
+
+impl Default for MoralValues {
+    fn default() -> Self {
+        MoralValues {
+            care: 0.5,
+            fairness: 0.5,
+            loyalty: 0.5,
+            authority: 0.5,
+            liberty: 0.5,
+        }
+    }
+}
+
+impl MoralValues {
+    /// Gets how much the NPC cares about a foundation.
+    pub fn get(&self, foundation: Foundation) -> f64 {
+        match foundation {
+            Foundation::Care => self.care,
+            Foundation::Fairness => self.fairness,
+            Foundation::Loyalty => self.loyalty,
+            Foundation::Authority => self.authority,
+            Foundation::Liberty => self.liberty,
+        }
+    }
+
+    /// Sets how much the NPC cares about a foundation.
+    pub fn set(&mut self, foundation: Foundation, value: f64) {
+        let value = value.clamp(0.0, 1.0);
+        match foundation {
+            Foundation::Care => self.care = value,
+            Foundation::Fairness => self.fairness = value,
+            Foundation::Loyalty => self.loyalty = value,
+            Foundation::Authority => self.authority = value,
+            Foundation::Liberty => self.liberty = value,
+        }
+    }
+
+    /// Moves these values towards others, e.g. those of the NPC's culture or faction.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The values to move towards.
+    /// * `weight` - How far to move (0.0 keeps these values, 1.0 adopts the others).
+    pub fn blend(&self, other: &MoralValues, weight: f64) -> MoralValues {
+        let weight = weight.clamp(0.0, 1.0);
+        let mut blended = *self;
+        for foundation in Foundation::ALL {
+            let value = self.get(foundation) * (1.0 - weight) + other.get(foundation) * weight;
+            blended.set(foundation, value);
+        }
+        blended
+    }
+
+    /// Returns a multiplier for how strongly the NPC appraises a deed.
+    ///
+    /// Violence offends care, theft offends fairness, deception offends fairness and loyalty,
+    /// disrespect offends authority, and kindness appeals to care.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::morality::MoralValues;
+    /// use athena::reputation::DeedCategory;
+    ///
+    /// let mut values = MoralValues::default();
+    /// assert_eq!(values.deed_weight(DeedCategory::Theft), 1.0);
+    /// values.fairness = 1.0;
+    /// assert_eq!(values.deed_weight(DeedCategory::Theft), 1.5);
+    /// ```
+    pub fn deed_weight(&self, category: DeedCategory) -> f64 {
+        let value = match category {
+            DeedCategory::Violence => self.care,
+            DeedCategory::Theft => self.fairness,
+            DeedCategory::Deception => (self.fairness + self.loyalty) / 2.0,
+            DeedCategory::Disrespect => self.authority,
+            DeedCategory::Generosity | DeedCategory::Helpfulness => self.care,
+        };
+        0.5 + value
+    }
+
+    /// Returns a multiplier for an action's utility given the NPC's values.
+    pub fn action_modifier(&self, action_name: &str) -> f64 {
+        let modifier = match action_name {
+            "Help" | "Comfort" => 1.0 + (self.care - 0.5),
+            "Collaborate" => 1.0 + 0.5 * (self.care - 0.5) + 0.5 * (self.loyalty - 0.5),
+            "Shout" | "Attack" => 1.0 - 0.8 * (self.care - 0.5),
+            "AlertGuards" => 1.0 + (self.authority - 0.5),
+            "Work" => 1.0 + 0.5 * (self.authority - 0.5),
+            "Explore" => 1.0 + 0.6 * (self.liberty - 0.5),
+            "Reject" => 1.0 + 0.6 * (self.liberty - 0.5) - 0.4 * (self.authority - 0.5),
+            "Trade" => 1.0 + 0.5 * (self.fairness - 0.5),
+            "Steal" | "Lie" => 1.0 - (self.fairness - 0.5),
+            _ => 1.0,
+        };
+        modifier.max(0.0)
+    }
+
+    /// Builds prompt directives for the values the NPC holds strongly (0.75 or more).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::morality::MoralValues;
+    ///
+    /// let values = MoralValues { loyalty: 0.9, ..MoralValues::default() };
+    /// assert_eq!(
+    ///     values.directives(),
+    ///     vec!["You value loyalty: stand by your own and despise traitors.".to_string()]
+    /// );
+    /// ```
+    pub fn directives(&self) -> Vec<String> {
+        Foundation::ALL
+            .into_iter()
+            .filter(|foundation| self.get(*foundation) >= 0.75)
+            .map(|foundation| {
+                let text = match foundation {
+                    Foundation::Care => "You value kindness: you cannot bear to see others hurt.",
+                    Foundation::Fairness => "You value fairness: cheating and theft outrage you.",
+                    Foundation::Loyalty => {
+                        "You value loyalty: stand by your own and despise traitors."
+                    }
+                    Foundation::Authority => {
+                        "You value order: respect rank and expect others to do the same."
+                    }
+                    Foundation::Liberty => {
+                        "You value freedom: you resent anyone telling you what to do."
+                    }
+                };
+                text.to_string()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,175 @@
+//! # Needs Module
+//!
+//! This module tracks the basic needs of an NPC (hunger, rest, social contact, ...). Each need
+//! is a satisfaction level between 0.0 (desperate) and 1.0 (fully satisfied) that decays over
+//! game time at its own rate, and is restored when the game reports the need being met.
+
+use std::collections::BTreeMap;
+
+/// Represents a single need of an NPC.
+#[derive(Debug, Clone)]
+pub struct Need {
+    /// The current satisfaction level (between 0.0 and 1.0).
+    pub satisfaction: f64,
+    /// How much satisfaction is lost per second of game time.
+    pub decay_rate: f64,
+    /// The action that satisfies this need, if any.
+    pub action: Option<String>,
+}
+
+/// Represents the collection of needs of an NPC.
+#[derive(Debug, Clone, Default)]
+pub struct Needs {
+    /// The needs, keyed by name.
+    needs: BTreeMap<String, Need>,
+}
+
+impl Needs {
+    /// Creates a new, empty set of needs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::needs::Needs;
+    /// let needs = Needs::new();
+    /// ```
+    pub fn new() -> Self {
+        Needs {
+            needs: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a fully satisfied need that decays at the given rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the need (e.g. "hunger").
+    /// * `decay_rate` - Satisfaction lost per second of game time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::needs::Needs;
+    /// let mut needs = Needs::new();
+    /// needs.add_need("hunger", 0.001);
+    /// assert_eq!(needs.get_satisfaction("hunger"), Some(1.0));
+    /// ```
+    pub fn add_need(&mut self, name: &str, decay_rate: f64) {
+        self.needs.insert(
+            name.to_string(),
+            Need {
+                satisfaction: 1.0,
+                decay_rate: decay_rate.max(0.0),
+                action: None,
+            },
+        );
+    }
+
+    /// Binds the action that satisfies a need, so the need can propose it during decisions.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the need.
+    /// * `action` - The name of the action that satisfies it (e.g. "Eat").
+    pub fn bind_action(&mut self, name: &str, action: &str) {
+        if let Some(need) = self.needs.get_mut(name) {
+            need.action = Some(action.to_string());
+        }
+    }
+
+    /// Proposes the bound actions of all unsatisfied needs.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `(need, action, urgency)` tuples, where urgency is `1.0 - satisfaction`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::needs::Needs;
+    /// let mut needs = Needs::new();
+    /// needs.add_need("hunger", 0.1);
+    /// needs.bind_action("hunger", "Eat");
+    /// needs.update(4.0);
+    /// let proposals = needs.proposals();
+    /// assert_eq!(proposals[0].1, "Eat");
+    /// ```
+    pub fn proposals(&self) -> Vec<(&str, &str, f64)> {
+        self.needs
+            .iter()
+            .filter(|(_, need)| need.satisfaction < 1.0)
+            .filter_map(|(name, need)| {
+                need.action
+                    .as_deref()
+                    .map(|action| (name.as_str(), action, 1.0 - need.satisfaction))
+            })
+            .collect()
+    }
+
+    /// Gets the satisfaction level of a need, or `None` if the NPC has no such need.
+    pub fn get_satisfaction(&self, name: &str) -> Option<f64> {
+        self.needs.get(name).map(|need| need.satisfaction)
+    }
+
+    /// Restores satisfaction of a need.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the need.
+    /// * `amount` - The amount of satisfaction to restore.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::needs::Needs;
+    /// let mut needs = Needs::new();
+    /// needs.add_need("hunger", 0.1);
+    /// needs.update(5.0);
+    /// needs.satisfy("hunger", 0.3);
+    /// assert_eq!(needs.get_satisfaction("hunger"), Some(0.8));
+    /// ```
+    pub fn satisfy(&mut self, name: &str, amount: f64) {
+        if let Some(need) = self.needs.get_mut(name) {
+            need.satisfaction = (need.satisfaction + amount).clamp(0.0, 1.0);
+        }
+    }
+
+    /// Decays all needs by the given amount of game time.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The elapsed game time in seconds.
+    pub fn update(&mut self, dt: f64) {
+        for need in self.needs.values_mut() {
+            need.satisfaction = (need.satisfaction - need.decay_rate * dt).clamp(0.0, 1.0);
+        }
+    }
+
+    /// Returns the least satisfied need, if any.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<(&str, f64)>` with the name and satisfaction of the most urgent need.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::needs::Needs;
+    /// let mut needs = Needs::new();
+    /// needs.add_need("hunger", 0.01);
+    /// needs.add_need("rest", 0.02);
+    /// needs.update(10.0);
+    /// assert_eq!(needs.most_urgent().unwrap().0, "rest");
+    /// ```
+    pub fn most_urgent(&self) -> Option<(&str, f64)> {
+        self.needs
+            .iter()
+            .min_by(|a, b| a.1.satisfaction.total_cmp(&b.1.satisfaction))
+            .map(|(name, need)| (name.as_str(), need.satisfaction))
+    }
+
+    /// Iterates over all needs in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Need)> {
+        self.needs.iter().map(|(name, need)| (name.as_str(), need))
+    }
+}
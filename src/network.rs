@@ -0,0 +1,331 @@
+//! # Network Module
+//!
+//! This module analyses the social network stored in a knowledge graph. Relationships of the
+//! chosen types (e.g. "friend", "knows", "family") are treated as undirected ties whose strength
+//! is the relationship's weight. From them it computes how connected each entity is (weighted
+//! degree), how much it bridges otherwise separate groups (betweenness), and which tight-knit
+//! groups exist (communities).
+//!
+//! Quest generation uses these to find socially central NPCs, and gossip can follow the
+//! strongest ties instead of spreading uniformly.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+
+use crate::knowledge_graph::KnowledgeGraph;
+
+/// Represents the undirected, weighted social network of a knowledge graph.
+#[derive(Debug, Clone, Default)]
+pub struct SocialNetwork {
+    /// The neighbours of each entity with the combined weight of their ties, in ID order.
+    ties: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+/// A Dijkstra queue entry, ordered so the closest entry is popped first.
+struct Candidate {
+    cost: f64,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal && self.node == other.node
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl SocialNetwork {
+    /// Builds the network from a knowledge graph.
+    ///
+    /// # Arguments
+    ///
+    /// * `graph` - The knowledge graph.
+    /// * `relation_types` - The relationship types that count as social ties. If empty, every
+    ///   relationship counts.
+    ///
+    /// Relationships with a weight of zero or less are ignored. Ties in both directions between
+    /// the same two entities are combined.
+    pub fn from_graph(graph: &KnowledgeGraph, relation_types: &[&str]) -> Self {
+        let mut network = SocialNetwork::default();
+        for relationship in graph.relationships() {
+            let counts = relation_types.is_empty()
+                || relation_types.contains(&relationship.relation_type.as_str());
+            if !counts || relationship.weight <= 0.0 || relationship.source == relationship.target {
+                continue;
+            }
+            let (a, b) = (relationship.source.to_string(), relationship.target.to_string());
+            *network.ties.entry(a.clone()).or_default().entry(b.clone()).or_default() +=
+                relationship.weight;
+            *network.ties.entry(b).or_default().entry(a).or_default() += relationship.weight;
+        }
+        network
+    }
+
+    /// Iterates over the entities in the network, in ID order.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.ties.keys().map(|id| id.as_str())
+    }
+
+    /// Computes the weighted degree of an entity: the summed strength of its ties.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// use athena::network::SocialNetwork;
+    ///
+    /// let mut graph = KnowledgeGraph::new();
+    /// graph.add_relationship(Relationship::new("ann", "bo", "friend", HashMap::new()).with_weight(0.5));
+    /// graph.add_relationship(Relationship::new("cy", "ann", "friend", HashMap::new()));
+    /// graph.add_relationship(Relationship::new("ann", "mill", "works_at", HashMap::new()));
+    /// let network = SocialNetwork::from_graph(&graph, &["friend"]);
+    /// assert_eq!(network.degree("ann"), 1.5);
+    /// ```
+    pub fn degree(&self, id: &str) -> f64 {
+        self.ties.get(id).map_or(0.0, |neighbours| neighbours.values().sum())
+    }
+
+    /// Gets an entity's strongest ties, strongest first.
+    ///
+    /// Gossip spreads along these first.
+    pub fn strongest_ties(&self, id: &str, limit: usize) -> Vec<(&str, f64)> {
+        let mut ties: Vec<(&str, f64)> = self
+            .ties
+            .get(id)
+            .into_iter()
+            .flatten()
+            .map(|(neighbour, weight)| (neighbour.as_str(), *weight))
+            .collect();
+        ties.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ties.truncate(limit);
+        ties
+    }
+
+    /// Counts how many retellings it takes news to reach every entity from a set of sources,
+    /// following ties of any strength.
+    ///
+    /// # Returns
+    ///
+    /// The number of hops to each reachable entity; the sources themselves are at zero hops.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// use athena::network::SocialNetwork;
+    ///
+    /// let mut graph = KnowledgeGraph::new();
+    /// for (a, b) in [("ann", "bo"), ("bo", "cy"), ("dee", "eli")] {
+    ///     graph.add_relationship(Relationship::new(a, b, "knows", HashMap::new()));
+    /// }
+    /// let hops = SocialNetwork::from_graph(&graph, &[]).hops(&["ann"]);
+    /// assert_eq!(hops["cy"], 2);
+    /// assert!(!hops.contains_key("dee"));
+    /// ```
+    pub fn hops(&self, sources: &[&str]) -> HashMap<String, usize> {
+        let mut hops: HashMap<String, usize> = HashMap::new();
+        let mut queue: VecDeque<&str> = VecDeque::new();
+        for source in sources {
+            if !hops.contains_key(*source) {
+                hops.insert(source.to_string(), 0);
+                queue.push_back(source);
+            }
+        }
+        while let Some(id) = queue.pop_front() {
+            let next = hops[id] + 1;
+            for neighbour in self.ties.get(id).into_iter().flat_map(|ties| ties.keys()) {
+                if !hops.contains_key(neighbour) {
+                    hops.insert(neighbour.clone(), next);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        hops
+    }
+
+    /// Computes the betweenness centrality of every entity: how many shortest paths between
+    /// other entities pass through it. Stronger ties are shorter (a tie's length is the inverse
+    /// of its weight).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// use athena::network::SocialNetwork;
+    ///
+    /// // Two pairs of friends, bridged by the innkeeper.
+    /// let mut graph = KnowledgeGraph::new();
+    /// for (a, b) in [("ann", "bo"), ("bo", "innkeeper"), ("innkeeper", "cy"), ("cy", "dee")] {
+    ///     graph.add_relationship(Relationship::new(a, b, "knows", HashMap::new()));
+    /// }
+    /// let network = SocialNetwork::from_graph(&graph, &[]);
+    /// let betweenness = network.betweenness();
+    /// assert_eq!(betweenness["innkeeper"], 4.0);
+    /// assert_eq!(betweenness["ann"], 0.0);
+    /// ```
+    pub fn betweenness(&self) -> HashMap<String, f64> {
+        let ids: Vec<&String> = self.ties.keys().collect();
+        let position: HashMap<&str, usize> =
+            ids.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect();
+        let neighbours: Vec<Vec<(usize, f64)>> = self
+            .ties
+            .values()
+            .map(|ties| {
+                ties.iter().map(|(id, weight)| (position[id.as_str()], 1.0 / weight)).collect()
+            })
+            .collect();
+        let count = ids.len();
+        let mut centrality = vec![0.0; count];
+
+        // Brandes' algorithm with Dijkstra for the weighted shortest paths.
+        for source in 0..count {
+            let mut order = Vec::with_capacity(count);
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); count];
+            let mut paths = vec![0.0_f64; count];
+            let mut distance = vec![f64::INFINITY; count];
+            paths[source] = 1.0;
+            distance[source] = 0.0;
+            let mut queue = BinaryHeap::new();
+            queue.push(Candidate {
+                cost: 0.0,
+                node: source,
+            });
+            let mut settled = vec![false; count];
+            while let Some(Candidate { cost, node }) = queue.pop() {
+                if settled[node] {
+                    continue;
+                }
+                settled[node] = true;
+                order.push(node);
+                for &(next, length) in &neighbours[node] {
+                    let candidate = cost + length;
+                    if candidate < distance[next] - 1e-12 {
+                        distance[next] = candidate;
+                        paths[next] = paths[node];
+                        predecessors[next] = vec![node];
+                        queue.push(Candidate {
+                            cost: candidate,
+                            node: next,
+                        });
+                    } else if (candidate - distance[next]).abs() <= 1e-12 {
+                        paths[next] += paths[node];
+                        predecessors[next].push(node);
+                    }
+                }
+            }
+            let mut dependency = vec![0.0; count];
+            for &node in order.iter().rev() {
+                for &predecessor in &predecessors[node] {
+                    dependency[predecessor] +=
+                        paths[predecessor] / paths[node] * (1.0 + dependency[node]);
+                }
+                if node != source {
+                    centrality[node] += dependency[node];
+                }
+            }
+        }
+
+        // Every undirected path was counted once from each end.
+        ids.into_iter()
+            .zip(centrality)
+            .map(|(id, value)| (id.clone(), value / 2.0))
+            .collect()
+    }
+
+    /// Ranks entities by betweenness, breaking ties by weighted degree.
+    ///
+    /// # Returns
+    ///
+    /// The `limit` most central entities with their betweenness, most central first.
+    pub fn most_central(&self, limit: usize) -> Vec<(String, f64)> {
+        let mut ranked: Vec<(String, f64)> = self.betweenness().into_iter().collect();
+        ranked.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| self.degree(&b.0).total_cmp(&self.degree(&a.0)))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        ranked.truncate(limit);
+        ranked
+    }
+
+    /// Detects communities by weighted label propagation: every entity repeatedly adopts the
+    /// community its ties are strongest to, until nothing changes.
+    ///
+    /// # Returns
+    ///
+    /// The community number of every entity. Communities are numbered from 0 in the order their
+    /// first member appears in ID order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::knowledge_graph::{KnowledgeGraph, Relationship};
+    /// use athena::network::SocialNetwork;
+    ///
+    /// let mut graph = KnowledgeGraph::new();
+    /// for (a, b) in [("ann", "bo"), ("bo", "cy"), ("cy", "ann"), ("dee", "eli"), ("eli", "fay"), ("fay", "dee")] {
+    ///     graph.add_relationship(Relationship::new(a, b, "friend", HashMap::new()));
+    /// }
+    /// graph.add_relationship(Relationship::new("cy", "dee", "friend", HashMap::new()).with_weight(0.1));
+    /// let communities = SocialNetwork::from_graph(&graph, &["friend"]).communities();
+    /// assert_eq!(communities["ann"], communities["cy"]);
+    /// assert_ne!(communities["cy"], communities["dee"]);
+    /// assert_eq!(communities["dee"], communities["fay"]);
+    /// ```
+    pub fn communities(&self) -> HashMap<String, usize> {
+        let mut labels: BTreeMap<&str, usize> =
+            self.ties.keys().enumerate().map(|(i, id)| (id.as_str(), i)).collect();
+        const MAX_ROUNDS: usize = 50;
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for (id, neighbours) in &self.ties {
+                let mut strength: BTreeMap<usize, f64> = BTreeMap::new();
+                for (neighbour, weight) in neighbours {
+                    *strength.entry(labels[neighbour.as_str()]).or_default() += weight;
+                }
+                // The strongest label wins; ties go to the smallest label, for determinism.
+                let best = strength
+                    .into_iter()
+                    .fold(None, |best: Option<(usize, f64)>, (label, total)| match best {
+                        Some((_, best_total)) if best_total >= total => best,
+                        _ => Some((label, total)),
+                    });
+                if let Some((label, _)) = best {
+                    if labels[id.as_str()] != label {
+                        labels.insert(id.as_str(), label);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut numbers: HashMap<usize, usize> = HashMap::new();
+        labels
+            .into_iter()
+            .map(|(id, label)| {
+                let next = numbers.len();
+                (id.to_string(), *numbers.entry(label).or_insert(next))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,331 @@
+//! # News Module
+//!
+//! This module spreads world events (a dragon attacking the mill, the king's death) through a
+//! population of NPCs, so that the whole town does not know everything the moment it happens.
+//! NPCs close to an event witness it right away; everyone else hears of it through a chain of
+//! retellings, each of which takes time and lowers how sure the listener is. The number of
+//! retellings is whichever is shorter: the gossip path through the social network, or the
+//! distance from the event. NPCs too many retellings away never hear of it at all.
+//!
+//! Retellings also distort the story. Events can carry authored distorted versions ("a dragon
+//! burned down half the town"), which NPCs further down the chain believe instead of what really
+//! happened.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{distance, AgentManager};
+use crate::emotional_response::Emotion;
+use crate::knowledge_graph::{Entity, KnowledgeGraph};
+use crate::network::SocialNetwork;
+use crate::symbol::Symbol;
+
+/// The prefix of the knowledge graph entity IDs used for news (e.g. "news:mill_attack").
+const NEWS_ENTITY_PREFIX: &str = "news:";
+
+/// Represents something that happened in the world.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldEvent {
+    /// A unique ID for the event.
+    pub id: String,
+    /// What happened (e.g. "a dragon attacked the mill").
+    pub description: String,
+    /// Where it happened.
+    pub position: (f64, f64),
+    /// When it happened, in seconds of game time.
+    pub game_time: f64,
+    /// Increasingly distorted retellings, believed from the second retelling on.
+    pub distortions: Vec<String>,
+    /// The emotion hearing of the event causes, and how intensely when witnessed.
+    pub reaction: Option<(Emotion, f64)>,
+}
+
+impl WorldEvent {
+    /// Creates a new event without distortions or reaction.
+    pub fn new(id: &str, description: &str, position: (f64, f64), game_time: f64) -> Self {
+        WorldEvent {
+            id: id.to_string(),
+            description: description.to_string(),
+            position,
+            game_time,
+            distortions: Vec::new(),
+            reaction: None,
+        }
+    }
+
+    /// Returns the version of the event believed after a number of retellings.
+    pub fn version_after(&self, hops: usize) -> &str {
+        match hops.checked_sub(2) {
+            Some(index) if !self.distortions.is_empty() => {
+                &self.distortions[index.min(self.distortions.len() - 1)]
+            }
+            _ => &self.description,
+        }
+    }
+}
+
+/// Represents what an NPC has heard about an event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsReport {
+    pub event_id: String,
+    /// The version of the event the NPC believes.
+    pub text: String,
+    /// How many retellings the news went through; 0 if the NPC witnessed the event.
+    pub hops: usize,
+    /// How sure the NPC is that it happened as it heard (between 0.0 and 1.0).
+    pub confidence: f64,
+    /// When the NPC learned of the event, in seconds of game time.
+    pub heard_at: f64,
+}
+
+impl NewsReport {
+    /// Returns a line the NPC can use to bring the news up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::news::NewsReport;
+    ///
+    /// let report = NewsReport {
+    ///     event_id: "mill_attack".to_string(),
+    ///     text: "a dragon attacked the mill".to_string(),
+    ///     hops: 1,
+    ///     confidence: 0.8,
+    ///     heard_at: 0.0,
+    /// };
+    /// assert_eq!(report.remark(), "I heard a dragon attacked the mill.");
+    /// ```
+    pub fn remark(&self) -> String {
+        match self.hops {
+            0 => format!("I saw it with my own eyes: {}.", self.text),
+            1 => format!("I heard {}.", self.text),
+            _ => format!("Rumor has it {}.", self.text),
+        }
+    }
+}
+
+/// Configures how news spreads.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsPolicy {
+    /// NPCs within this distance of an event witness it.
+    pub witness_radius: f64,
+    /// How far news travels with each retelling when it is not following the social network.
+    pub hop_distance: f64,
+    /// How long each retelling takes, in seconds of game time.
+    pub hop_delay: f64,
+    /// How much confidence is kept at each retelling.
+    pub confidence_per_hop: f64,
+    /// NPCs more retellings away than this never hear of an event.
+    pub max_hops: usize,
+}
+
+impl Default for NewsPolicy {
+    /// Each retelling carries news 150 units further and takes half an hour of game time.
+    fn default() -> Self {
+        NewsPolicy {
+            witness_radius: 30.0,
+            hop_distance: 150.0,
+            hop_delay: 1_800.0,
+            confidence_per_hop: 0.8,
+            max_hops: 5,
+        }
+    }
+}
+
+/// Represents news on its way to an NPC.
+#[derive(Debug, Clone)]
+struct Delivery {
+    agent: Symbol,
+    report: NewsReport,
+    reaction: Option<(Emotion, f64)>,
+}
+
+/// Publishes world events to a population of agents and delivers them over time.
+#[derive(Debug, Clone, Default)]
+pub struct NewsBroadcaster {
+    pub policy: NewsPolicy,
+    /// The deliveries not made yet.
+    pending: Vec<Delivery>,
+}
+
+impl NewsBroadcaster {
+    /// Creates a new broadcaster with the given policy.
+    pub fn new(policy: NewsPolicy) -> Self {
+        NewsBroadcaster {
+            policy,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns how many deliveries are still on their way.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Publishes an event, scheduling when and in which version each agent will hear of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - What happened.
+    /// * `manager` - The agents that may hear of it, at their current positions.
+    /// * `network` - The social network gossip follows, if any. Without one, news only spreads
+    ///   by distance.
+    ///
+    /// # Returns
+    ///
+    /// The number of agents that will hear of the event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::news::{self, NewsBroadcaster, WorldEvent};
+    ///
+    /// let mut manager = AgentManager::new(60.0);
+    /// for (id, x) in [("miller", 10.0), ("baker", 200.0), ("hermit", 5_000.0)] {
+    ///     let mut agent = NpcAgent::new(id, vec![]);
+    ///     agent.position = (x, 0.0);
+    ///     manager.add_agent(agent);
+    /// }
+    /// let mut event = WorldEvent::new("mill_attack", "a dragon attacked the mill", (0.0, 0.0), 0.0);
+    /// event.distortions.push("a dragon burned down half the town".to_string());
+    ///
+    /// let mut broadcaster = NewsBroadcaster::default();
+    /// assert_eq!(broadcaster.publish(&event, &manager, None), 2);
+    /// broadcaster.deliver(&mut manager);
+    /// let miller = news::report(&manager.get_agent("miller").unwrap().knowledge, "mill_attack");
+    /// assert_eq!(miller.unwrap().hops, 0);
+    /// assert!(news::report(&manager.get_agent("baker").unwrap().knowledge, "mill_attack").is_none());
+    ///
+    /// manager.advance(3_600.0);
+    /// assert_eq!(broadcaster.deliver(&mut manager).len(), 1);
+    /// let baker = news::report(&manager.get_agent("baker").unwrap().knowledge, "mill_attack");
+    /// assert_eq!(baker.unwrap().text, "a dragon burned down half the town");
+    /// ```
+    pub fn publish(
+        &mut self,
+        event: &WorldEvent,
+        manager: &AgentManager,
+        network: Option<&SocialNetwork>,
+    ) -> usize {
+        let policy = &self.policy;
+        let witnesses: Vec<&str> = manager
+            .agents()
+            .filter(|agent| distance(agent.position, event.position) <= policy.witness_radius)
+            .map(|agent| agent.id.as_str())
+            .collect();
+        let social_hops = network.map_or_else(HashMap::new, |network| network.hops(&witnesses));
+        let mut scheduled = 0;
+        for agent in manager.agents() {
+            let spatial = (distance(agent.position, event.position) / policy.hop_distance.max(1.0))
+                .ceil() as usize;
+            let spatial = if witnesses.contains(&agent.id.as_str()) {
+                0
+            } else {
+                spatial.max(1)
+            };
+            let social = social_hops.get(agent.id.as_str()).copied().unwrap_or(usize::MAX);
+            let hops = spatial.min(social);
+            if hops > policy.max_hops {
+                continue;
+            }
+            let confidence = policy.confidence_per_hop.powi(hops as i32);
+            let reaction = event
+                .reaction
+                .clone()
+                .map(|(emotion, intensity)| (emotion, intensity * confidence));
+            self.pending.push(Delivery {
+                agent: agent.id.clone(),
+                report: NewsReport {
+                    event_id: event.id.clone(),
+                    text: event.version_after(hops).to_string(),
+                    hops,
+                    confidence,
+                    heard_at: event.game_time + hops as f64 * policy.hop_delay,
+                },
+                reaction,
+            });
+            scheduled += 1;
+        }
+        scheduled
+    }
+
+    /// Delivers the news that has reached its listeners by the manager's game time.
+    ///
+    /// Each listener records the news in its knowledge (unless it already heard a better
+    /// version), notes it as an experience to reflect on, and reacts to it.
+    ///
+    /// # Returns
+    ///
+    /// The `(agent, event)` IDs of the deliveries made.
+    pub fn deliver(&mut self, manager: &mut AgentManager) -> Vec<(Symbol, String)> {
+        let now = manager.get_game_time();
+        let (due, pending): (Vec<Delivery>, Vec<Delivery>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|delivery| delivery.report.heard_at <= now);
+        self.pending = pending;
+        let mut delivered = Vec::new();
+        for delivery in due {
+            let Some(agent) = manager.get_agent_mut(delivery.agent.as_str()) else {
+                continue;
+            };
+            let known = report(&agent.knowledge, &delivery.report.event_id);
+            if known.is_some_and(|known| known.hops <= delivery.report.hops) {
+                continue;
+            }
+            record_report(&mut agent.knowledge, &delivery.report);
+            match delivery.reaction {
+                Some((emotion, intensity)) => {
+                    let text = &delivery.report.text;
+                    agent.reflector.record_experience("news", text, emotion.clone());
+                    agent.appraise(emotion, intensity, text);
+                }
+                None => {
+                    let emotion = agent.emotions.get_emotion().clone();
+                    agent.reflector.record_experience("news", &delivery.report.text, emotion);
+                }
+            }
+            delivered.push((delivery.agent, delivery.report.event_id));
+        }
+        delivered
+    }
+}
+
+/// Records a news report in a knowledge graph, replacing any earlier report of the same event.
+fn record_report(knowledge: &mut KnowledgeGraph, report: &NewsReport) {
+    let mut properties = HashMap::new();
+    properties.insert("text".to_string(), report.text.clone());
+    properties.insert("hops".to_string(), report.hops.to_string());
+    properties.insert("confidence".to_string(), report.confidence.to_string());
+    properties.insert("heard_at".to_string(), report.heard_at.to_string());
+    let id = format!("{}{}", NEWS_ENTITY_PREFIX, report.event_id);
+    knowledge.add_entity(Entity::new(id, properties));
+}
+
+/// Reads a news report from an entity, if it is one.
+fn parse_report(entity: &Entity) -> Option<NewsReport> {
+    let event_id = entity.id.as_str().strip_prefix(NEWS_ENTITY_PREFIX)?;
+    let property = |key: &str| entity.properties.get(key);
+    Some(NewsReport {
+        event_id: event_id.to_string(),
+        text: property("text")?.clone(),
+        hops: property("hops")?.parse().ok()?,
+        confidence: property("confidence")?.parse().ok()?,
+        heard_at: property("heard_at")?.parse().ok()?,
+    })
+}
+
+/// Gets what an NPC has heard about an event, if anything.
+pub fn report(knowledge: &KnowledgeGraph, event_id: &str) -> Option<NewsReport> {
+    parse_report(knowledge.get_entity(&format!("{}{}", NEWS_ENTITY_PREFIX, event_id))?)
+}
+
+/// Lists the news recorded in a knowledge graph, most recently heard first.
+pub fn reports(knowledge: &KnowledgeGraph) -> Vec<NewsReport> {
+    let mut reports: Vec<NewsReport> = knowledge.entities().filter_map(parse_report).collect();
+    reports.sort_by(|a, b| {
+        b.heard_at.total_cmp(&a.heard_at).then_with(|| a.event_id.cmp(&b.event_id))
+    });
+    reports
+}
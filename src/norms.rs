@@ -0,0 +1,199 @@
+//! # Norms Module
+//!
+//! This module defines the social norms an NPC expects others to respect: keeping a polite
+//! distance, addressing nobles by their title, and avoiding taboo topics. Each NPC carries its own
+//! `NormSet`, so a noble can insist on formal address while a dock worker does not care.
+//!
+//! Norms work in both directions. The player's conduct is checked against them, and violations
+//! provoke an emotional reaction and count against the player's reputation. The norms are also
+//! rendered as prompt directives, so generated dialogue respects them too.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dialogue_act::normalize;
+use crate::emotional_response::Emotion;
+
+/// Represents what a norm requires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NormKind {
+    /// Others must keep at least this distance.
+    PersonalSpace { min_distance: f64 },
+    /// The first line of a conversation must use one of these forms of address, matched as
+    /// whole words.
+    FormalAddress { titles: Vec<String> },
+    /// Lines must not mention any of these keywords, matched as whole words.
+    TabooTopic { topic: String, keywords: Vec<String> },
+}
+
+/// Represents a social norm.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Norm {
+    /// A unique ID for the norm (e.g. "address_nobles").
+    pub id: String,
+    pub kind: NormKind,
+    /// How much a violation matters (e.g. 0.5 for a faux pas, 2.0 for a grave insult).
+    pub severity: f64,
+}
+
+/// Represents a violation of a norm by someone else.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormViolation {
+    pub norm_id: String,
+    pub severity: f64,
+    /// The emotion the violation provokes.
+    pub emotion: Emotion,
+    pub description: String,
+}
+
+/// Represents the conduct of someone the NPC is dealing with.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conduct {
+    /// What they said, if anything.
+    pub line: String,
+    /// How close they are, if known.
+    pub distance: Option<f64>,
+    /// Whether the line opens the conversation.
+    pub opening: bool,
+}
+
+/// Represents the norms an NPC expects others to respect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NormSet {
+    norms: Vec<Norm>,
+}
+
+impl Default for NormSet {
+    fn default() -> Self {
+        let mut norms = NormSet::new();
+        norms.add_norm(Norm {
+            id: "personal_space".to_string(),
+            kind: NormKind::PersonalSpace { min_distance: 0.5 },
+            severity: 0.5,
+        });
+        norms
+    }
+}
+
+impl NormSet {
+    /// Creates a new, empty norm set.
+    pub fn new() -> Self {
+        NormSet { norms: Vec::new() }
+    }
+
+    /// Adds a norm, replacing any norm with the same ID.
+    pub fn add_norm(&mut self, norm: Norm) {
+        self.norms.retain(|existing| existing.id != norm.id);
+        self.norms.push(norm);
+    }
+
+    /// Removes a norm by ID.
+    pub fn remove_norm(&mut self, id: &str) -> Option<Norm> {
+        let index = self.norms.iter().position(|norm| norm.id == id)?;
+        Some(self.norms.remove(index))
+    }
+
+    /// Iterates over the norms.
+    pub fn norms(&self) -> impl Iterator<Item = &Norm> {
+        self.norms.iter()
+    }
+
+    /// Checks someone's conduct against the norms.
+    ///
+    /// Crowding and taboo topics provoke disgust; failing to use a proper title provokes anger.
+    ///
+    /// # Arguments
+    ///
+    /// * `conduct` - What the other person said and did.
+    ///
+    /// # Returns
+    ///
+    /// The violated norms, in the order they were added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::norms::{Conduct, Norm, NormKind, NormSet};
+    ///
+    /// let mut norms = NormSet::default();
+    /// norms.add_norm(Norm {
+    ///     id: "address_nobles".to_string(),
+    ///     kind: NormKind::FormalAddress { titles: vec!["my lord".to_string()] },
+    ///     severity: 1.0,
+    /// });
+    /// let rude = Conduct { line: "Hey, you.".to_string(), distance: Some(0.2), opening: true };
+    /// assert_eq!(norms.check(&rude).len(), 2);
+    /// let polite = Conduct { line: "Good day, my lord.".to_string(), distance: Some(2.0), ..rude };
+    /// assert!(norms.check(&polite).is_empty());
+    ///
+    /// // Titles and keywords only count as whole words.
+    /// let sly = Conduct { line: "My lordship's hound!".to_string(), ..polite };
+    /// assert_eq!(norms.check(&sly).len(), 1);
+    /// ```
+    pub fn check(&self, conduct: &Conduct) -> Vec<NormViolation> {
+        let line = normalize(&conduct.line);
+        let mentions = |phrase: &str| line.contains(&normalize(phrase));
+        self.norms
+            .iter()
+            .filter_map(|norm| {
+                let (emotion, description) = match &norm.kind {
+                    NormKind::PersonalSpace { min_distance } => {
+                        let distance = conduct.distance?;
+                        if distance >= *min_distance {
+                            return None;
+                        }
+                        (Emotion::Disgust, "stood uncomfortably close".to_string())
+                    }
+                    NormKind::FormalAddress { titles } => {
+                        let addressed = titles.iter().any(|title| mentions(title));
+                        if !conduct.opening || addressed {
+                            return None;
+                        }
+                        (Emotion::Anger, "failed to use a proper title".to_string())
+                    }
+                    NormKind::TabooTopic { topic, keywords } => {
+                        let keyword = keywords.iter().find(|keyword| mentions(keyword))?;
+                        (Emotion::Disgust, format!("brought up {} ({})", topic, keyword))
+                    }
+                };
+                Some(NormViolation {
+                    norm_id: norm.id.clone(),
+                    severity: norm.severity,
+                    emotion,
+                    description,
+                })
+            })
+            .collect()
+    }
+
+    /// Renders the norms as prompt directives, so generated dialogue respects them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::norms::{Norm, NormKind, NormSet};
+    ///
+    /// let mut norms = NormSet::new();
+    /// norms.add_norm(Norm {
+    ///     id: "no_plague".to_string(),
+    ///     kind: NormKind::TabooTopic {
+    ///         topic: "the plague".to_string(),
+    ///         keywords: vec!["plague".to_string()],
+    ///     },
+    ///     severity: 1.0,
+    /// });
+    /// assert_eq!(norms.directives(), vec!["Never bring up the plague.".to_string()]);
+    /// ```
+    pub fn directives(&self) -> Vec<String> {
+        self.norms
+            .iter()
+            .filter_map(|norm| match &norm.kind {
+                NormKind::PersonalSpace { .. } => None,
+                NormKind::FormalAddress { titles } => Some(format!(
+                    "You expect formal address (e.g. \"{}\") and speak formally yourself.",
+                    titles.first().map_or("sir", |title| title.as_str())
+                )),
+                NormKind::TabooTopic { topic, .. } => Some(format!("Never bring up {}.", topic)),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,196 @@
+//! # Perception Module
+//!
+//! This module lets NPCs perceive what the player does, not only what they say. Typed actions
+//! such as drawing a weapon, handing over a gift, or trespassing are first-class perceptions:
+//! each has a default appraisal (the emotion it provokes, the deed it counts as, and how it moves
+//! affinity and trust) and a fact the NPC learns about the actor. NPCs thus react sensibly
+//! even when no word is said, and can later talk about what they saw.
+//!
+//! Appraisals can be overridden per NPC, e.g. so a guard is not frightened by a drawn weapon but
+//! a child is.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::emotional_response::Emotion;
+use crate::reputation::DeedCategory;
+
+/// Represents something the player (or anyone else) does that an NPC can perceive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlayerAction {
+    DrewWeapon,
+    SheathedWeapon,
+    /// Threatened the NPC without words (e.g. a raised fist).
+    Threatened,
+    Attacked,
+    GaveGift {
+        item: String,
+    },
+    Stole {
+        item: String,
+    },
+    Trespassed {
+        location: String,
+    },
+    Helped {
+        what: String,
+    },
+    /// A respectful gesture, such as a bow.
+    Bowed,
+}
+
+/// Represents the kind of an action, without its details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionKind {
+    DrewWeapon,
+    SheathedWeapon,
+    Threatened,
+    Attacked,
+    GaveGift,
+    Stole,
+    Trespassed,
+    Helped,
+    Bowed,
+}
+
+impl PlayerAction {
+    /// Gets the kind of the action.
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            PlayerAction::DrewWeapon => ActionKind::DrewWeapon,
+            PlayerAction::SheathedWeapon => ActionKind::SheathedWeapon,
+            PlayerAction::Threatened => ActionKind::Threatened,
+            PlayerAction::Attacked => ActionKind::Attacked,
+            PlayerAction::GaveGift { .. } => ActionKind::GaveGift,
+            PlayerAction::Stole { .. } => ActionKind::Stole,
+            PlayerAction::Trespassed { .. } => ActionKind::Trespassed,
+            PlayerAction::Helped { .. } => ActionKind::Helped,
+            PlayerAction::Bowed => ActionKind::Bowed,
+        }
+    }
+
+    /// Describes the action (e.g. "gave me a rose").
+    pub fn description(&self) -> String {
+        match self {
+            PlayerAction::DrewWeapon => "drew a weapon on me".to_string(),
+            PlayerAction::SheathedWeapon => "put their weapon away".to_string(),
+            PlayerAction::Threatened => "threatened me".to_string(),
+            PlayerAction::Attacked => "attacked me".to_string(),
+            PlayerAction::GaveGift { item } => format!("gave me {}", item),
+            PlayerAction::Stole { item } => format!("stole {}", item),
+            PlayerAction::Trespassed { location } => format!("trespassed in {}", location),
+            PlayerAction::Helped { what } => format!("helped me with {}", what),
+            PlayerAction::Bowed => "bowed to me".to_string(),
+        }
+    }
+
+    /// The relation types of the facts observers learn (see `fact`).
+    pub const RELATIONS: [&'static str; 9] = [
+        "drew_weapon_on",
+        "sheathed_weapon_near",
+        "threatened",
+        "attacked",
+        "gave",
+        "stole",
+        "trespassed_in",
+        "helped",
+        "bowed_to",
+    ];
+
+    /// Gets the fact the observer learns about the actor, as `(relation, target)`. The target
+    /// `None` stands for the observer itself.
+    pub fn fact(&self) -> (&'static str, Option<&str>) {
+        match self {
+            PlayerAction::DrewWeapon => ("drew_weapon_on", None),
+            PlayerAction::SheathedWeapon => ("sheathed_weapon_near", None),
+            PlayerAction::Threatened => ("threatened", None),
+            PlayerAction::Attacked => ("attacked", None),
+            PlayerAction::GaveGift { item } => ("gave", Some(item)),
+            PlayerAction::Stole { item } => ("stole", Some(item)),
+            PlayerAction::Trespassed { location } => ("trespassed_in", Some(location)),
+            PlayerAction::Helped { .. } => ("helped", None),
+            PlayerAction::Bowed => ("bowed_to", None),
+        }
+    }
+}
+
+/// Represents how an NPC appraises an action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionAppraisal {
+    /// The emotion the action provokes and how strongly (between 0.0 and 1.0), if any.
+    pub emotion: Option<(Emotion, f64)>,
+    /// The deed the action counts as and how significant it is, if any.
+    pub deed: Option<(DeedCategory, f64)>,
+    pub affinity: f64,
+    pub trust: f64,
+}
+
+impl ActionAppraisal {
+    /// Creates an appraisal with an emotion and relationship changes, and no deed.
+    pub fn new(emotion: Option<(Emotion, f64)>, affinity: f64, trust: f64) -> Self {
+        ActionAppraisal {
+            emotion,
+            deed: None,
+            affinity,
+            trust,
+        }
+    }
+
+    /// Counts the action as a deed.
+    pub fn with_deed(mut self, category: DeedCategory, magnitude: f64) -> Self {
+        self.deed = Some((category, magnitude));
+        self
+    }
+}
+
+/// Represents how an NPC appraises the actions it perceives.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerceptionPolicy {
+    /// Appraisals replacing the defaults for some kinds of action.
+    pub overrides: HashMap<ActionKind, ActionAppraisal>,
+}
+
+impl PerceptionPolicy {
+    /// Replaces the default appraisal of a kind of action.
+    pub fn set_appraisal(&mut self, kind: ActionKind, appraisal: ActionAppraisal) {
+        self.overrides.insert(kind, appraisal);
+    }
+
+    /// Gets the appraisal of a kind of action.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::perception::{ActionAppraisal, ActionKind, PerceptionPolicy};
+    ///
+    /// let mut guard = PerceptionPolicy::default();
+    /// assert_eq!(guard.appraisal(ActionKind::DrewWeapon).emotion.unwrap().0, Emotion::Fear);
+    /// let calm = ActionAppraisal::new(Some((Emotion::Anticipation, 0.4)), -0.1, -0.2);
+    /// guard.set_appraisal(ActionKind::DrewWeapon, calm);
+    /// assert_eq!(guard.appraisal(ActionKind::DrewWeapon).emotion.unwrap().0, Emotion::Anticipation);
+    /// ```
+    pub fn appraisal(&self, kind: ActionKind) -> ActionAppraisal {
+        if let Some(appraisal) = self.overrides.get(&kind) {
+            return appraisal.clone();
+        }
+        match kind {
+            ActionKind::DrewWeapon => ActionAppraisal::new(Some((Emotion::Fear, 0.6)), -0.1, -0.2),
+            ActionKind::SheathedWeapon => ActionAppraisal::new(None, 0.0, 0.05),
+            ActionKind::Threatened => ActionAppraisal::new(Some((Emotion::Fear, 0.5)), -0.15, -0.2)
+                .with_deed(DeedCategory::Disrespect, 0.5),
+            ActionKind::Attacked => ActionAppraisal::new(Some((Emotion::Anger, 0.9)), -0.4, -0.5)
+                .with_deed(DeedCategory::Violence, 1.0),
+            ActionKind::GaveGift => ActionAppraisal::new(Some((Emotion::Joy, 0.4)), 0.1, 0.05)
+                .with_deed(DeedCategory::Generosity, 0.5),
+            ActionKind::Stole => ActionAppraisal::new(Some((Emotion::Anger, 0.6)), -0.2, -0.4)
+                .with_deed(DeedCategory::Theft, 1.0),
+            ActionKind::Trespassed => ActionAppraisal::new(Some((Emotion::Anger, 0.3)), -0.05, -0.1)
+                .with_deed(DeedCategory::Disrespect, 0.3),
+            ActionKind::Helped => ActionAppraisal::new(Some((Emotion::Joy, 0.5)), 0.15, 0.1)
+                .with_deed(DeedCategory::Helpfulness, 0.7),
+            ActionKind::Bowed => ActionAppraisal::new(Some((Emotion::Trust, 0.2)), 0.05, 0.0),
+        }
+    }
+}
@@ -7,11 +7,120 @@
 //! - **Extraversion**: Measures the extent to which a person is outgoing and sociable.
 //! - **Agreeableness**: Assesses how cooperative, compassionate, and friendly a person is.
 //! - **Neuroticism**: Evaluates emotional stability and the tendency to experience negative emotions.
+//!
+//! Writers who would rather not pick numbers can derive a personality from questionnaire answers
+//! and descriptive tags (see the `questionnaire` module).
+//!
+//! The traits also drive gameplay math through `DecisionParameters`: how much risk an NPC
+//! tolerates, how patient it is, and how much it seeks novelty. Action selection, haggling, and
+//! the choice of quest givers read them. The crate never weighs offers itself, but games can use
+//! `DecisionParameters::offer_value` to decide whether an NPC takes a job or a deal.
 
 use serde::{Deserialize, Serialize};
 
+/// Represents one of the Big Five traits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Trait {
+    Openness,
+    Conscientiousness,
+    Extraversion,
+    Agreeableness,
+    Neuroticism,
+}
+
+impl Trait {
+    /// All traits, in the order of the Big Five acronym OCEAN.
+    pub const ALL: [Trait; 5] = [
+        Trait::Openness,
+        Trait::Conscientiousness,
+        Trait::Extraversion,
+        Trait::Agreeableness,
+        Trait::Neuroticism,
+    ];
+}
+
+/// Actions that expose the NPC to danger or confrontation, favored by risk-tolerant NPCs.
+const RISKY_ACTIONS: [&str; 6] = ["Attack", "Confront", "Investigate", "Explore", "Steal", "Shout"];
+
+/// Represents the decision parameters a personality implies.
+///
+/// Every parameter is between 0.0 and 1.0, and a neutral personality yields 0.5 for each.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DecisionParameters {
+    /// How readily the NPC takes chances: high with extraversion and openness, low with
+    /// neuroticism and conscientiousness.
+    pub risk_tolerance: f64,
+    /// How willing the NPC is to wait for a reward: high with conscientiousness, low with
+    /// neuroticism and extraversion.
+    pub patience: f64,
+    /// How much the NPC prefers doing something new: high with openness and extraversion.
+    pub novelty_seeking: f64,
+}
+
+impl DecisionParameters {
+    /// Gets the fraction of its value a reward keeps per day of waiting: 0.5 for the most
+    /// impatient NPC, 1.0 for the most patient.
+    pub fn daily_discount(&self) -> f64 {
+        0.5 + 0.5 * self.patience
+    }
+
+    /// Computes what an offer is worth to the NPC, for games deciding whether it accepts a job
+    /// or a deal.
+    ///
+    /// The reward is discounted for every day it is delayed, and shrunk by the risk of failure
+    /// in proportion to how little risk the NPC tolerates.
+    ///
+    /// # Arguments
+    ///
+    /// * `reward` - What the offer pays.
+    /// * `risk` - The chance of failing or coming to harm (between 0.0 and 1.0).
+    /// * `delay` - How long until the reward arrives, in seconds of game time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::Personality;
+    ///
+    /// let mut daredevil = Personality::new();
+    /// daredevil.set_extraversion(1.0);
+    /// daredevil.set_neuroticism(0.0);
+    /// let mut coward = Personality::new();
+    /// coward.set_neuroticism(0.9);
+    ///
+    /// // A dangerous job paying 100 gold in two days.
+    /// let bold = daredevil.decision_parameters().offer_value(100.0, 0.6, 172_800.0);
+    /// let timid = coward.decision_parameters().offer_value(100.0, 0.6, 172_800.0);
+    /// assert!(bold > timid);
+    /// assert!(bold < 100.0);
+    /// ```
+    pub fn offer_value(&self, reward: f64, risk: f64, delay: f64) -> f64 {
+        let days = delay.max(0.0) / 86_400.0;
+        let discounted = reward * self.daily_discount().powf(days);
+        discounted * (1.0 - risk.clamp(0.0, 1.0) * (1.0 - self.risk_tolerance))
+    }
+
+    /// Computes the utility multiplier the parameters apply to an action: risky actions scale
+    /// with risk tolerance, and repeating the last action is discouraged for novelty seekers and
+    /// encouraged for creatures of habit.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_name` - The name of the action being scored.
+    /// * `last_action` - The action the NPC chose last, if any.
+    pub fn action_modifier(&self, action_name: &str, last_action: Option<&str>) -> f64 {
+        let mut modifier = 1.0;
+        if RISKY_ACTIONS.contains(&action_name) {
+            modifier *= 1.0 + 0.6 * (self.risk_tolerance - 0.5);
+        }
+        if last_action == Some(action_name) {
+            modifier *= 1.0 - 0.4 * (self.novelty_seeking - 0.5);
+        }
+        modifier
+    }
+}
+
 /// Represents the personality of an NPC.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Personality {
     pub openness: f64,
     pub conscientiousness: f64,
@@ -21,6 +130,12 @@ pub struct Personality {
 
 }
 
+impl Default for Personality {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Personality {
     /// Creates a new Personality instance.
     pub fn new() -> Self {
@@ -133,4 +248,135 @@ impl Personality {
         self.neuroticism = value.clamp(0.0, 1.0);
     }
 
-}
\ No newline at end of file
+    /// Gets the value of a trait.
+    pub fn get_trait(&self, personality_trait: Trait) -> f64 {
+        match personality_trait {
+            Trait::Openness => self.openness,
+            Trait::Conscientiousness => self.conscientiousness,
+            Trait::Extraversion => self.extraversion,
+            Trait::Agreeableness => self.agreeableness,
+            Trait::Neuroticism => self.neuroticism,
+        }
+    }
+
+    /// Sets the value of a trait (between 0.0 and 1.0).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::{Personality, Trait};
+    ///
+    /// let mut personality = Personality::new();
+    /// personality.set_trait(Trait::Agreeableness, 1.2);
+    /// assert_eq!(personality.get_trait(Trait::Agreeableness), 1.0);
+    /// ```
+    pub fn set_trait(&mut self, personality_trait: Trait, value: f64) {
+        match personality_trait {
+            Trait::Openness => self.set_openness(value),
+            Trait::Conscientiousness => self.set_conscientiousness(value),
+            Trait::Extraversion => self.set_extraversion(value),
+            Trait::Agreeableness => self.set_agreeableness(value),
+            Trait::Neuroticism => self.set_neuroticism(value),
+        }
+    }
+
+    /// Computes how well two personalities get along, from -1.0 (rivals) to 1.0 (friends).
+    ///
+    /// People are drawn to those similar in openness, conscientiousness, and extraversion;
+    /// agreeable, emotionally stable people get along with anyone. Similarity counts for half
+    /// of the score, and the pair's agreeableness and stability for a quarter each.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other personality.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::Personality;
+    ///
+    /// let mut scholar = Personality::new();
+    /// scholar.set_openness(1.0);
+    /// scholar.set_conscientiousness(1.0);
+    /// scholar.set_extraversion(0.0);
+    /// let mut brawler = Personality::new();
+    /// brawler.set_openness(0.0);
+    /// brawler.set_conscientiousness(0.0);
+    /// brawler.set_extraversion(1.0);
+    ///
+    /// assert_eq!(scholar.compatibility(&scholar), 0.5);
+    /// assert!(scholar.compatibility(&brawler) < 0.0);
+    /// assert_eq!(scholar.compatibility(&brawler), brawler.compatibility(&scholar));
+    /// ```
+    pub fn compatibility(&self, other: &Personality) -> f64 {
+        let similarity = 1.0
+            - ((self.openness - other.openness).abs()
+                + (self.conscientiousness - other.conscientiousness).abs()
+                + (self.extraversion - other.extraversion).abs())
+                / 3.0;
+        let warmth = (self.agreeableness + other.agreeableness) / 2.0;
+        let stability = 1.0 - (self.neuroticism + other.neuroticism) / 2.0;
+        let fit = 0.5 * similarity + 0.25 * warmth + 0.25 * stability;
+        (2.0 * fit - 1.0).clamp(-1.0, 1.0)
+    }
+
+    /// Derives the decision parameters the personality implies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::Personality;
+    ///
+    /// let mut scholar = Personality::new();
+    /// assert_eq!(scholar.decision_parameters().patience, 0.5);
+    /// scholar.set_conscientiousness(0.9);
+    /// scholar.set_openness(0.9);
+    /// let parameters = scholar.decision_parameters();
+    /// assert!(parameters.patience > 0.5);
+    /// assert!(parameters.novelty_seeking > 0.5);
+    /// ```
+    pub fn decision_parameters(&self) -> DecisionParameters {
+        let (o, c, e, n) = (
+            self.openness - 0.5,
+            self.conscientiousness - 0.5,
+            self.extraversion - 0.5,
+            self.neuroticism - 0.5,
+        );
+        DecisionParameters {
+            risk_tolerance: (0.5 + 0.4 * e + 0.3 * o - 0.6 * n - 0.3 * c).clamp(0.0, 1.0),
+            patience: (0.5 + 0.6 * c - 0.3 * n - 0.2 * e).clamp(0.0, 1.0),
+            novelty_seeking: (0.5 + 0.7 * o + 0.3 * e).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Computes the utility multiplier the personality applies to an action.
+    ///
+    /// Social actions scale with extraversion, avoidant ones with neuroticism, exploratory ones
+    /// with openness, and hostile ones inversely with agreeableness. At the neutral trait value
+    /// of 0.5 every multiplier is 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `action_name` - The name of the action being scored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::Personality;
+    ///
+    /// let mut personality = Personality::new();
+    /// assert_eq!(personality.action_modifier("Talk"), 1.0);
+    /// personality.set_extraversion(0.9);
+    /// assert!(personality.action_modifier("Talk") > 1.0);
+    /// ```
+    pub fn action_modifier(&self, action_name: &str) -> f64 {
+        match action_name {
+            "Talk" | "Collaborate" | "Dance" => 0.5 + self.extraversion,
+            "Hide" | "Cry" | "Run" => 0.5 + self.neuroticism,
+            "Investigate" | "Prepare" | "Explore" => 0.5 + self.openness,
+            "Work" | "Rest" => 0.5 + self.conscientiousness,
+            "Shout" | "Reject" => 1.5 - self.agreeableness,
+            _ => 1.0,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! # Persuasion Module
+//!
+//! This module resolves social skill checks against NPCs. When the player tries to persuade,
+//! intimidate, or deceive an NPC, the game asks how hard the NPC is to sway; the difficulty is
+//! computed from the NPC's personality, current emotion, relationship with the player, and what
+//! is at stake, and comes with the factors that produced it so it can be shown to the player.
+//!
+//! The game rolls however it likes (dice, a skill value, a minigame) and hands the roll back for
+//! resolution. Attempts leave their mark: the NPC reacts to being threatened or caught lying,
+//! and every failed attempt is remembered and makes the next one of the same kind harder.
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::NpcAgent;
+use crate::emotional_response::Emotion;
+use crate::reputation::DeedCategory;
+
+/// How much harder every earlier failed attempt of the same kind makes a check.
+const FAILURE_PENALTY: f64 = 0.1;
+
+/// Represents the kind of social pressure applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Approach {
+    Persuade,
+    Intimidate,
+    Deceive,
+}
+
+impl Approach {
+    /// Returns the verb used in memories (e.g. "intimidate").
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Approach::Persuade => "persuade",
+            Approach::Intimidate => "intimidate",
+            Approach::Deceive => "deceive",
+        }
+    }
+}
+
+/// Represents a social skill check requested by the game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SocialCheck {
+    pub approach: Approach,
+    /// How much the NPC has to lose by giving in (between 0.0 and 1.0).
+    pub stakes: f64,
+    /// What the check is about (e.g. "letting us through the gate").
+    pub subject: String,
+}
+
+/// Represents how hard an NPC is to sway, and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// The roll needed to succeed (between 0.05 and 0.95).
+    pub value: f64,
+    /// What raised or lowered the difficulty, as `(description, contribution)` pairs.
+    pub factors: Vec<(String, f64)>,
+}
+
+/// Represents the outcome of a resolved check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckOutcome {
+    pub success: bool,
+    pub difficulty: f64,
+    /// How far the roll was above (positive) or below (negative) the difficulty.
+    pub margin: f64,
+}
+
+/// Counts an NPC's remembered failed attempts of an approach by a partner.
+fn failed_attempts(agent: &NpcAgent, partner_id: &str, approach: Approach) -> usize {
+    let prefix = format!("check:{}:", approach.verb());
+    agent.get_interlocutor(partner_id).map_or(0, |context| {
+        context.memories().filter(|(key, _)| key.starts_with(&prefix)).count()
+    })
+}
+
+/// Computes how hard it is to sway an NPC.
+///
+/// Every check starts at 0.3 plus up to 0.4 for the stakes. Persuasion is easier for agreeable
+/// NPCs who like and trust the partner; intimidation is easier against anxious or already
+/// frightened NPCs but provokes angry ones; deception is easier against trusting NPCs in a good
+/// mood and harder against suspicious or conscientious ones. Earlier failures add 0.1 each.
+///
+/// # Arguments
+///
+/// * `agent` - The NPC being swayed.
+/// * `partner_id` - Who is trying.
+/// * `check` - What they are trying.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::persuasion::{difficulty, Approach, SocialCheck};
+///
+/// let mut guard = NpcAgent::new("guard", vec![]);
+/// let check = SocialCheck {
+///     approach: Approach::Intimidate,
+///     stakes: 0.5,
+///     subject: "letting us through the gate".to_string(),
+/// };
+/// let calm = difficulty(&guard, "player", &check).value;
+/// guard.personality.set_neuroticism(0.9);
+/// assert!(difficulty(&guard, "player", &check).value < calm);
+/// ```
+pub fn difficulty(agent: &NpcAgent, partner_id: &str, check: &SocialCheck) -> Difficulty {
+    let mut factors = vec![("base".to_string(), 0.3)];
+    factors.push(("stakes".to_string(), 0.4 * check.stakes.clamp(0.0, 1.0)));
+    let personality = &agent.personality;
+    let disposition = agent.disposition_towards(partner_id);
+    let (affinity, trust) = agent
+        .get_interlocutor(partner_id)
+        .map_or((0.0, 0.0), |context| (context.affinity, context.trust));
+    let emotion = agent.emotions.get_emotion();
+    match check.approach {
+        Approach::Persuade => {
+            factors.push(("agreeableness".to_string(), 0.3 * (0.5 - personality.agreeableness)));
+            factors.push(("openness".to_string(), 0.2 * (0.5 - personality.openness)));
+            factors.push(("affinity".to_string(), -0.2 * affinity));
+            factors.push(("trust".to_string(), -0.2 * trust));
+            factors.push(("warmth".to_string(), -0.1 * disposition.warmth));
+        }
+        Approach::Intimidate => {
+            factors.push(("neuroticism".to_string(), 0.4 * (0.5 - personality.neuroticism)));
+            factors.push(("fear of the partner".to_string(), -0.3 * disposition.fear));
+            match emotion {
+                Emotion::Fear => factors.push(("emotion Fear".to_string(), -0.2)),
+                Emotion::Anger => factors.push(("emotion Anger".to_string(), 0.15)),
+                _ => {}
+            }
+        }
+        Approach::Deceive => {
+            factors.push(("suspicion".to_string(), 0.3 * disposition.suspicion));
+            factors.push(("trust".to_string(), -0.2 * trust));
+            let care = 0.2 * (personality.conscientiousness - 0.5);
+            factors.push(("conscientiousness".to_string(), care));
+            if matches!(emotion, Emotion::Joy | Emotion::Trust) {
+                factors.push((format!("emotion {:?}", emotion), -0.1));
+            }
+        }
+    }
+    let failures = failed_attempts(agent, partner_id, check.approach);
+    factors.push(("earlier failures".to_string(), FAILURE_PENALTY * failures as f64));
+    factors.retain(|(_, contribution)| *contribution != 0.0);
+    let value = factors.iter().map(|(_, contribution)| contribution).sum::<f64>();
+    Difficulty {
+        value: value.clamp(0.05, 0.95),
+        factors,
+    }
+}
+
+/// Resolves a check with the game's roll and records the attempt.
+///
+/// Failures are remembered about the partner and make later attempts of the same kind harder.
+/// Intimidation is remembered as disrespect and frightens the NPC if it works or angers it if
+/// it does not; a failed deception is remembered as a lie and costs trust.
+///
+/// # Arguments
+///
+/// * `agent` - The NPC being swayed.
+/// * `partner_id` - Who is trying.
+/// * `check` - What they are trying.
+/// * `roll` - The game's roll, on the same 0.0 to 1.0 scale as the difficulty.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::persuasion::{difficulty, resolve, Approach, SocialCheck};
+///
+/// let mut merchant = NpcAgent::new("merchant", vec![]);
+/// let check = SocialCheck {
+///     approach: Approach::Deceive,
+///     stakes: 0.4,
+///     subject: "the price of the ring".to_string(),
+/// };
+/// let before = difficulty(&merchant, "player", &check).value;
+/// let outcome = resolve(&mut merchant, "player", &check, 0.1);
+/// assert!(!outcome.success);
+/// assert!(difficulty(&merchant, "player", &check).value > before);
+/// let memory = merchant.get_interlocutor("player").unwrap().get_memory("check:deceive:1");
+/// assert_eq!(memory.unwrap(), "Tried to deceive me about the price of the ring and failed.");
+/// ```
+pub fn resolve(
+    agent: &mut NpcAgent,
+    partner_id: &str,
+    check: &SocialCheck,
+    roll: f64,
+) -> CheckOutcome {
+    let difficulty = difficulty(agent, partner_id, check).value;
+    let success = roll >= difficulty;
+    let stakes = check.stakes.clamp(0.0, 1.0);
+    match (check.approach, success) {
+        (Approach::Intimidate, _) => {
+            let description = format!("threatened me over {}", check.subject);
+            agent.record_deed(partner_id, DeedCategory::Disrespect, 1.0 + stakes, &description);
+            let emotion = if success { Emotion::Fear } else { Emotion::Anger };
+            agent.appraise(emotion, 0.4 + 0.4 * stakes, &description);
+        }
+        (Approach::Deceive, false) => {
+            let description = format!("lied to me about {}", check.subject);
+            let magnitude = 1.0 + 2.0 * stakes;
+            agent.record_deed(partner_id, DeedCategory::Deception, magnitude, &description);
+            agent.interlocutor(partner_id).adjust_trust(-0.2);
+        }
+        _ => {}
+    }
+    if !success {
+        let failures = failed_attempts(agent, partner_id, check.approach);
+        let verb = check.approach.verb();
+        let memory = format!("Tried to {} me about {} and failed.", verb, check.subject);
+        agent
+            .interlocutor(partner_id)
+            .record_memory(&format!("check:{}:{}", verb, failures + 1), &memory);
+    }
+    CheckOutcome {
+        success,
+        difficulty,
+        margin: roll - difficulty,
+    }
+}
@@ -0,0 +1,194 @@
+//! # Physiology Module
+//!
+//! This module models the body state of an NPC: how much pain it is in, how tired it is, and how
+//! drunk it is. The game sets these levels (after a fight, a long shift, or a round at the
+//! tavern), and the crate lets them recover over game time, each with its own decay curve: pain
+//! fades quickly at first and then lingers, while alcohol is broken down at a steady rate.
+//!
+//! The body state modulates the rest of the NPC. It scales how strongly events are felt (pain
+//! makes an NPC quicker to anger, drink dulls fear), biases action utility (an exhausted NPC
+//! wants to rest), and changes how it talks through prompt directives and post-processing
+//! filters (terse when hurting or tired, slurred when drunk). All levels default to 0.0, which
+//! has no effect.
+
+use serde::{Deserialize, Serialize};
+
+use crate::emotional_response::Emotion;
+use crate::postprocess::Filter;
+
+/// Represents how a body state level recovers over game time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Decay {
+    /// The level drops by a fixed amount per second.
+    Linear(f64),
+    /// The level drops by a fixed fraction per second.
+    Exponential(f64),
+}
+
+impl Decay {
+    /// Applies the decay to a level over the given amount of game time.
+    pub fn apply(&self, level: f64, dt: f64) -> f64 {
+        match self {
+            Decay::Linear(rate) => (level - rate * dt).max(0.0),
+            Decay::Exponential(rate) => level * (-rate * dt).exp(),
+        }
+    }
+}
+
+/// Represents the body state of an NPC. Every level is between 0.0 and 1.0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BodyState {
+    pub pain: f64,
+    pub fatigue: f64,
+    pub intoxication: f64,
+    pub pain_decay: Decay,
+    pub fatigue_decay: Decay,
+    pub intoxication_decay: Decay,
+}
+
+impl Default for BodyState {
+    /// A healthy body. Pain halves about every 20 minutes, fatigue wears off over 8 hours of
+    /// game time, and full intoxication over 5 hours.
+    fn default() -> Self {
+        BodyState {
+            pain: 0.0,
+            fatigue: 0.0,
+            intoxication: 0.0,
+            pain_decay: Decay::Exponential(0.000_6),
+            fatigue_decay: Decay::Linear(1.0 / 28_800.0),
+            intoxication_decay: Decay::Linear(1.0 / 18_000.0),
+        }
+    }
+}
+
+impl BodyState {
+    /// Sets the pain level.
+    pub fn set_pain(&mut self, level: f64) {
+        self.pain = level.clamp(0.0, 1.0);
+    }
+
+    /// Sets the fatigue level.
+    pub fn set_fatigue(&mut self, level: f64) {
+        self.fatigue = level.clamp(0.0, 1.0);
+    }
+
+    /// Sets the intoxication level.
+    pub fn set_intoxication(&mut self, level: f64) {
+        self.intoxication = level.clamp(0.0, 1.0);
+    }
+
+    /// Lets the body recover over the given amount of game time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::physiology::BodyState;
+    ///
+    /// let mut body = BodyState::default();
+    /// body.set_intoxication(1.0);
+    /// body.update(9_000.0);
+    /// assert!((body.intoxication - 0.5).abs() < 1e-9);
+    /// ```
+    pub fn update(&mut self, dt: f64) {
+        self.pain = self.pain_decay.apply(self.pain, dt);
+        self.fatigue = self.fatigue_decay.apply(self.fatigue, dt);
+        self.intoxication = self.intoxication_decay.apply(self.intoxication, dt);
+    }
+
+    /// Returns a multiplier for how intensely the NPC feels an emotion.
+    ///
+    /// Pain feeds anger and sadness, fatigue feeds sadness and dulls joy and anticipation, and
+    /// intoxication feeds joy and anger and dulls fear.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::physiology::BodyState;
+    ///
+    /// let mut body = BodyState::default();
+    /// assert_eq!(body.emotion_gain(&Emotion::Anger), 1.0);
+    /// body.set_pain(1.0);
+    /// assert_eq!(body.emotion_gain(&Emotion::Anger), 1.5);
+    /// ```
+    pub fn emotion_gain(&self, emotion: &Emotion) -> f64 {
+        let gain = match emotion {
+            Emotion::Anger => 1.0 + 0.5 * self.pain + 0.5 * self.intoxication,
+            Emotion::Sadness => 1.0 + 0.3 * self.pain + 0.4 * self.fatigue,
+            Emotion::Joy => (1.0 - 0.4 * self.fatigue) * (1.0 + 0.5 * self.intoxication),
+            Emotion::Anticipation => 1.0 - 0.4 * self.fatigue,
+            Emotion::Fear => 1.0 - 0.5 * self.intoxication,
+            _ => 1.0,
+        };
+        gain.max(0.0)
+    }
+
+    /// Returns a multiplier for an action's utility in the current body state.
+    pub fn action_modifier(&self, action_name: &str) -> f64 {
+        match action_name {
+            "Rest" => 1.0 + self.fatigue + 0.5 * self.pain,
+            "Work" | "Explore" | "Prepare" => (1.0 - 0.5 * self.fatigue) * (1.0 - 0.4 * self.pain),
+            "Dance" | "Talk" => (1.0 - 0.4 * self.fatigue) * (1.0 + 0.5 * self.intoxication),
+            "Shout" => 1.0 + 0.5 * self.intoxication + 0.3 * self.pain,
+            "Investigate" => 1.0 - 0.5 * self.intoxication,
+            "Cry" => 1.0 + 0.5 * self.pain,
+            _ => 1.0,
+        }
+    }
+
+    /// Builds the prompt directives describing how the body state affects the NPC's speech.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::physiology::BodyState;
+    ///
+    /// let mut body = BodyState::default();
+    /// assert!(body.directives().is_empty());
+    /// body.set_fatigue(0.8);
+    /// assert_eq!(body.directives(), vec!["You are exhausted: answer tersely.".to_string()]);
+    /// ```
+    pub fn directives(&self) -> Vec<String> {
+        let mut directives = Vec::new();
+        if self.pain >= 0.5 {
+            directives.push("You are in pain: speak in short, strained sentences.".to_string());
+        }
+        if self.fatigue >= 0.6 {
+            directives.push("You are exhausted: answer tersely.".to_string());
+        }
+        if self.intoxication >= 0.5 {
+            directives.push("You are drunk: slur your words and ramble.".to_string());
+        }
+        directives
+    }
+
+    /// Builds the post-processing filters that enforce the body state's speech style: one
+    /// sentence when in heavy pain or exhausted, two when hurting or tired, and slurring when
+    /// drunk.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::physiology::BodyState;
+    /// use athena::postprocess::PostProcessor;
+    ///
+    /// let mut body = BodyState::default();
+    /// body.set_intoxication(0.9);
+    /// body.set_pain(0.6);
+    /// let speech = PostProcessor { filters: body.filters() };
+    /// assert_eq!(speech.apply("Yes, sir. Fresh ale. Shall I pour?"), "Yesh, shir. Fresh ale.");
+    /// ```
+    pub fn filters(&self) -> Vec<Filter> {
+        let mut filters = Vec::new();
+        let strain = self.pain.max(self.fatigue);
+        if strain >= 0.8 {
+            filters.push(Filter::MaxSentences(1));
+        } else if strain >= 0.5 {
+            filters.push(Filter::MaxSentences(2));
+        }
+        if self.intoxication >= 0.7 {
+            filters.push(Filter::Slur);
+        }
+        filters
+    }
+}
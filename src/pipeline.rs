@@ -0,0 +1,582 @@
+//! # Pipeline Module
+//!
+//! This module breaks dialogue generation into a composable pipeline: context gathering, prompt
+//! building, generation, validation, and post-processing. Games insert their own stages into any
+//! phase (a stage that pulls in lore, one that rejects lines mentioning spoilers, one that trims
+//! the reply to a single sentence) instead of wrapping one monolithic request function.
+//!
+//! Stages run in phase order, and within a phase in the order they were added. Any stage can
+//! abort the request by returning an error. A validation stage can instead return a `Regenerate`
+//! error to have the reply generated again with a correction added to the prompt (e.g. the facts
+//! a line got wrong), as long as the request has attempts left.
+//!
+//! A pipeline can keep an audit log of its requests, which QA can replay through a newer build
+//! and diff (see the `audit` module), and a report of their token usage (see the `cost` module).
+
+use std::collections::HashMap;
+
+use crate::audit::{AuditConfig, AuditLog, AuditRecord, ValidationDecision};
+use crate::cancellation::{CancellationToken, Interrupted};
+use crate::cost::{CostReport, UsageRecord};
+use crate::dialogue_generation::{
+    ApiResponse, DialogueClient, GenerationParams, RequestType, ResponseFormat,
+};
+use crate::experiment::Experiment;
+use crate::postprocess::{Filter, PostProcessor};
+use crate::prompt::{PromptBuilder, PromptContext};
+
+/// The error type of pipeline stages. It is `Send` so pipelines can run on background tasks.
+pub type StageError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The error a validation stage returns to have the reply generated again. If the request has
+/// no attempts left, it fails with this error instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regenerate {
+    /// The correction added to the prompt of the next attempt.
+    pub directive: String,
+}
+
+impl Regenerate {
+    /// Creates a request to regenerate the reply with a correction.
+    pub fn new(directive: &str) -> Self {
+        Regenerate {
+            directive: directive.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Regenerate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The reply must be regenerated: {}", self.directive)
+    }
+}
+
+impl std::error::Error for Regenerate {}
+
+/// Represents a phase of the pipeline, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Fills in the prompt context (mood, directives, history).
+    ContextGathering,
+    /// Renders the prompt context into the prompt.
+    PromptBuild,
+    /// Checks the generated reply, aborting the request if it is unacceptable.
+    Validation,
+    /// Rewrites the generated reply.
+    PostProcessing,
+}
+
+/// Represents a request as it moves through the pipeline.
+#[derive(Debug, Clone)]
+pub struct PipelineState {
+    pub request_type: RequestType,
+    /// The player's line or other input that triggered the request.
+    pub input: String,
+    /// The per-request part of the prompt, filled in by context gathering stages.
+    pub context: PromptContext,
+    /// The prompt sent to the model. It starts out as the input.
+    pub prompt: String,
+    /// The generated reply, empty until generation has run.
+    pub reply: String,
+    /// Generation parameter overrides for the request, merged over the client defaults.
+    pub params: GenerationParams,
+    /// The format the reply is asked for in. If the server rejects a structured format, the
+    /// request is retried as text.
+    pub format: ResponseFormat,
+    /// Free-form values stages can use to pass information along.
+    pub metadata: HashMap<String, String>,
+    /// The phase being run, so a stage added to several phases can tell them apart.
+    pub phase: Phase,
+    /// The number of the current attempt at generating the reply, starting at 0.
+    pub attempt: usize,
+    /// How many more times the reply can be regenerated after this attempt.
+    pub attempts_left: usize,
+    /// The line shown instead if generation or validation fails for good. The request then
+    /// succeeds with this line as its reply, and the audit record notes the fallback.
+    pub fallback: Option<String>,
+}
+
+impl PipelineState {
+    /// Creates a new state for a request.
+    pub fn new(request_type: RequestType, input: &str) -> Self {
+        PipelineState {
+            request_type,
+            input: input.to_string(),
+            context: PromptContext {
+                player_input: input.to_string(),
+                ..PromptContext::default()
+            },
+            prompt: input.to_string(),
+            reply: String::new(),
+            params: GenerationParams::default(),
+            format: ResponseFormat::Text,
+            metadata: HashMap::new(),
+            phase: Phase::ContextGathering,
+            attempt: 0,
+            attempts_left: 0,
+            fallback: None,
+        }
+    }
+}
+
+/// Represents a stage of the pipeline.
+///
+/// Closures taking `&mut PipelineState` are stages too.
+pub trait Stage: Send {
+    /// Processes the request.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), StageError>` - An error aborts the request.
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError>;
+
+    /// Gets the name the stage's decisions are audited under.
+    fn name(&self) -> &str {
+        "stage"
+    }
+}
+
+impl<F> Stage for F
+where
+    F: FnMut(&mut PipelineState) -> Result<(), StageError> + Send,
+{
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        self(state)
+    }
+}
+
+/// A prompt building stage that renders the prompt context with a `PromptBuilder`, and applies
+/// the persona's generation overrides for the request type.
+pub struct PromptStage {
+    pub builder: PromptBuilder,
+}
+
+impl Stage for PromptStage {
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        state.prompt = self.builder.build(&state.context);
+        let overrides = self.builder.get_persona().generation.params_for(state.request_type);
+        state.params = state.params.merge(&overrides);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "prompt"
+    }
+}
+
+/// A post-processing stage that runs the reply through a `PostProcessor`.
+///
+/// Placeholders are also filled from the state's metadata, so earlier stages can provide values
+/// such as `player_name`.
+impl Stage for PostProcessor {
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        let reply = Filter::ReplacePlaceholders(state.metadata.clone()).apply(&state.reply);
+        state.reply = self.apply(&reply);
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "postprocess"
+    }
+}
+
+/// A context gathering stage that applies the variant an experiment assigns to the request's
+/// NPC-player pair, read from the `npc_id` and `player_id` metadata values. The variant's name is
+/// stored as the `experiment:<name>` metadata value, so later stages and logs can refer to it.
+impl Stage for Experiment {
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        let npc_id = state.metadata.get("npc_id").cloned().unwrap_or_default();
+        let player_id = state.metadata.get("player_id").cloned().unwrap_or_default();
+        if let Some(variant) = self.assign(&npc_id, &player_id) {
+            variant.apply(&mut state.context, &mut state.params);
+            state.metadata.insert(format!("experiment:{}", self.name), variant.name.clone());
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &str {
+        "experiment"
+    }
+}
+
+/// Runs dialogue requests through the pipeline stages.
+pub struct Pipeline {
+    client: DialogueClient,
+    stages: Vec<(Phase, Box<dyn Stage>)>,
+    /// The log requests are audited to, if auditing is on.
+    audit: Option<AuditLog>,
+    /// The report token usage is recorded to, if cost reporting is on.
+    costs: Option<CostReport>,
+    /// The token generation runs under; cancelling it aborts requests in flight.
+    cancellation: CancellationToken,
+    /// How many replies a request may generate in total.
+    max_attempts: usize,
+}
+
+impl Pipeline {
+    /// Creates a new pipeline without stages, which sends the input to the model unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::DialogueClient;
+    /// use athena::pipeline::Pipeline;
+    /// let pipeline = Pipeline::new(DialogueClient::new("my-api-key"));
+    /// assert_eq!(pipeline.stage_count(), 0);
+    /// ```
+    pub fn new(client: DialogueClient) -> Self {
+        Pipeline {
+            client,
+            stages: Vec::new(),
+            audit: None,
+            costs: None,
+            cancellation: CancellationToken::new(),
+            max_attempts: 3,
+        }
+    }
+
+    /// Adds a stage to a phase, after the stages already in it.
+    pub fn add_stage(&mut self, phase: Phase, stage: impl Stage + 'static) {
+        let index = self.stages.partition_point(|(existing, _)| *existing <= phase);
+        self.stages.insert(index, (phase, Box::new(stage)));
+    }
+
+    /// Returns the number of stages.
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Gets the client used for generation.
+    pub fn get_client(&self) -> &DialogueClient {
+        &self.client
+    }
+
+    /// Turns auditing on, logging every request run from now on to the log. The speaker of a
+    /// record is the `npc_id` metadata value, if a stage sets one.
+    pub fn set_audit_log(&mut self, log: AuditLog) {
+        self.audit = Some(log);
+    }
+
+    /// Gets the audit log, if auditing is on.
+    pub fn get_audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    /// Turns auditing off, returning the log.
+    pub fn take_audit_log(&mut self) -> Option<AuditLog> {
+        self.audit.take()
+    }
+
+    /// Turns cost reporting on, recording the token usage of every request from now on. Usage
+    /// is attributed to the `npc_id`, `scene`, and `session` metadata values, if stages set them.
+    pub fn set_cost_report(&mut self, report: CostReport) {
+        self.costs = Some(report);
+    }
+
+    /// Gets the cost report, if cost reporting is on.
+    pub fn get_cost_report(&self) -> Option<&CostReport> {
+        self.costs.as_ref()
+    }
+
+    /// Turns cost reporting off, returning the report.
+    pub fn take_cost_report(&mut self) -> Option<CostReport> {
+        self.costs.take()
+    }
+
+    /// Sets the token generation runs under, e.g. an agent's or scene's token. Requests in
+    /// flight when it is cancelled fail with `Interrupted::Cancelled`.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Sets how many replies a request may generate in total when validation stages ask for
+    /// regeneration. Defaults to 3.
+    pub fn set_max_attempts(&mut self, max_attempts: usize) {
+        self.max_attempts = max_attempts.max(1);
+    }
+
+    /// Gets how many replies a request may generate in total.
+    pub fn get_max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// Runs the stages of the given phases in order, noting the decision of every validation
+    /// stage and of the stage that failed, if any.
+    fn run_phases(
+        &mut self,
+        state: &mut PipelineState,
+        phases: &[Phase],
+        decisions: &mut Vec<ValidationDecision>,
+    ) -> Result<(), StageError> {
+        for (phase, stage) in &mut self.stages {
+            if phases.contains(phase) {
+                state.phase = *phase;
+                let result = stage.process(state);
+                if *phase == Phase::Validation || result.is_err() {
+                    decisions.push(ValidationDecision {
+                        check: stage.name().to_string(),
+                        passed: result.is_ok(),
+                        detail: result.as_ref().err().map(|e| e.to_string()).unwrap_or_default(),
+                    });
+                }
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the context gathering and prompt building stages, leaving the prompt in the state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::{DialogueClient, RequestType};
+    /// use athena::pipeline::{Phase, Pipeline, PipelineState, PromptStage, StageError};
+    /// use athena::prompt::{PersonaCard, PromptBuilder};
+    ///
+    /// let mut pipeline = Pipeline::new(DialogueClient::new("my-api-key"));
+    /// let builder = PromptBuilder::new(PersonaCard::new("Brom", "The village blacksmith."));
+    /// pipeline.add_stage(Phase::PromptBuild, PromptStage { builder });
+    /// let gather_mood = |state: &mut PipelineState| -> Result<(), StageError> {
+    ///     state.context.mood.push("cheerful".to_string());
+    ///     Ok(())
+    /// };
+    /// pipeline.add_stage(Phase::ContextGathering, gather_mood);
+    ///
+    /// let mut state = PipelineState::new(RequestType::Conversation, "Hello!");
+    /// pipeline.prepare(&mut state).unwrap();
+    /// assert!(state.prompt.contains("Current mood: cheerful"));
+    /// assert!(state.prompt.contains("Player: Hello!"));
+    /// ```
+    pub fn prepare(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        self.run_phases(state, &[Phase::ContextGathering, Phase::PromptBuild], &mut Vec::new())
+    }
+
+    /// Runs the validation and post-processing stages on the reply in the state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::{DialogueClient, RequestType};
+    /// use athena::pipeline::{Phase, Pipeline, PipelineState, StageError};
+    ///
+    /// let mut pipeline = Pipeline::new(DialogueClient::new("my-api-key"));
+    /// let no_spoilers = |state: &mut PipelineState| -> Result<(), StageError> {
+    ///     if state.reply.contains("dragon") {
+    ///         return Err("The dragon is a spoiler".into());
+    ///     }
+    ///     Ok(())
+    /// };
+    /// let trim = |state: &mut PipelineState| -> Result<(), StageError> {
+    ///     state.reply = state.reply.trim().to_string();
+    ///     Ok(())
+    /// };
+    /// pipeline.add_stage(Phase::Validation, no_spoilers);
+    /// pipeline.add_stage(Phase::PostProcessing, trim);
+    ///
+    /// let mut state = PipelineState::new(RequestType::Conversation, "Hello!");
+    /// state.reply = "  Well met!  ".to_string();
+    /// pipeline.finish(&mut state).unwrap();
+    /// assert_eq!(state.reply, "Well met!");
+    ///
+    /// state.reply = "Beware the dragon.".to_string();
+    /// assert!(pipeline.finish(&mut state).is_err());
+    /// ```
+    pub fn finish(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        self.run_phases(state, &[Phase::Validation, Phase::PostProcessing], &mut Vec::new())
+    }
+
+    /// Runs a request through every phase: context gathering, prompt building, generation,
+    /// validation, and post-processing.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_type` - The kind of request, used to pick the model.
+    /// * `input` - The player's line or other input that triggered the request.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PipelineState, StageError>` - The final state, with the reply, or the error of
+    ///   the stage or request that failed.
+    pub async fn run(
+        &mut self,
+        request_type: RequestType,
+        input: &str,
+    ) -> Result<PipelineState, StageError> {
+        self.run_state(PipelineState::new(request_type, input)).await
+    }
+
+    /// Runs a request through every phase, like `run`, starting from a prepared state, e.g. one
+    /// whose context already carries directives for the request.
+    ///
+    /// When a validation stage asks for regeneration and the request has attempts left, every
+    /// phase runs again from the given state, with the corrections asked for so far added to the
+    /// prompt. Each attempt is audited, with the decision of every validation stage. If the
+    /// request fails for good and the state has a fallback line, it succeeds with that line.
+    pub async fn run_state(&mut self, state: PipelineState) -> Result<PipelineState, StageError> {
+        let max_attempts = self.max_attempts.max(1);
+        let mut corrections: Vec<String> = Vec::new();
+        let mut attempt = 0;
+        loop {
+            let mut attempt_state = state.clone();
+            attempt_state.attempt = attempt;
+            attempt_state.attempts_left = max_attempts - attempt - 1;
+            match self.attempt(attempt_state, &corrections).await {
+                Err(error) if attempt + 1 < max_attempts => match error.downcast::<Regenerate>() {
+                    Ok(regenerate) => corrections.push(regenerate.directive),
+                    Err(error) => return Err(error),
+                },
+                result => return result,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Runs one attempt at a request through every phase, adding the corrections to the prompt.
+    async fn attempt(
+        &mut self,
+        mut state: PipelineState,
+        corrections: &[String],
+    ) -> Result<PipelineState, StageError> {
+        self.prepare(&mut state)?;
+        for correction in corrections {
+            state.prompt = format!("{}\n{}", state.prompt, correction);
+        }
+        let mut sent = self.send(&state).await;
+        if sent.as_ref().is_err_and(|error| !error.is::<Interrupted>())
+            && state.format != ResponseFormat::Text
+        {
+            state.format = ResponseFormat::Text;
+            sent = self.send(&state).await;
+        }
+        let response = match sent {
+            Ok(response) => response,
+            Err(error) => {
+                let decision = ValidationDecision {
+                    check: "request".to_string(),
+                    passed: false,
+                    detail: error.to_string(),
+                };
+                return self.conclude(state, None, vec![decision], Err(error));
+            }
+        };
+        if let Some(costs) = &mut self.costs {
+            let tag = |key: &str| state.metadata.get(key).map_or("", |value| value.as_str());
+            let (npc_id, scene, session) = (tag("npc_id"), tag("scene"), tag("session"));
+            let model = &response.model;
+            costs.record(UsageRecord::new(npc_id, scene, session, model, &response.usage));
+        }
+        state.reply = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .unwrap_or_default();
+        let raw = state.reply.clone();
+        let mut decisions = Vec::new();
+        let phases = [Phase::Validation, Phase::PostProcessing];
+        let result = self.run_phases(&mut state, &phases, &mut decisions);
+        self.conclude(state, Some(&raw), decisions, result)
+    }
+
+    /// Audits an attempt and settles its outcome, falling back to the state's fallback line if
+    /// the attempt failed and will not be retried.
+    fn conclude(
+        &mut self,
+        mut state: PipelineState,
+        response: Option<&str>,
+        decisions: Vec<ValidationDecision>,
+        result: Result<(), StageError>,
+    ) -> Result<PipelineState, StageError> {
+        let error = match result {
+            Ok(()) => {
+                self.audit(&state, response, decisions, None);
+                return Ok(state);
+            }
+            Err(error) => error,
+        };
+        let retried = error.is::<Regenerate>() && state.attempts_left > 0;
+        match state.fallback.clone() {
+            Some(fallback) if !retried => {
+                self.audit(&state, response, decisions, Some(&fallback));
+                state.reply = fallback;
+                Ok(state)
+            }
+            _ => {
+                self.audit(&state, response, decisions, None);
+                Err(error)
+            }
+        }
+    }
+
+    /// Sends the prompt of a request in its format, under the pipeline's cancellation token.
+    async fn send(&self, state: &PipelineState) -> Result<ApiResponse, StageError> {
+        let (prompt, format) = (&state.prompt, &state.format);
+        let sent = self.client.send_request(state.request_type, prompt, format, &state.params);
+        self.cancellation.run(sent).await.unwrap_or_else(|interrupted| Err(interrupted.into()))
+    }
+
+    /// Runs the requests of an audit log through the pipeline again, e.g. in a newer build,
+    /// logging them to a new log with the same redaction and no sampling.
+    ///
+    /// Each request runs with the input, metadata, and fallback line it was logged with, with
+    /// the redacted terms put back, so the baseline's config must still list them.
+    ///
+    /// # Returns
+    ///
+    /// * `AuditLog` - The replayed requests, ready to `diff` against the original log.
+    pub async fn replay(&mut self, baseline: &AuditLog) -> AuditLog {
+        let replay = AuditLog::new(AuditConfig {
+            sample_rate: 1.0,
+            ..baseline.config.clone()
+        });
+        let previous = self.audit.replace(replay);
+        let restore = |text: &String| baseline.config.restore(text);
+        for record in baseline.records() {
+            let mut state = PipelineState::new(record.request_type, &restore(&record.input));
+            state.metadata = record
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), restore(value)))
+                .collect();
+            state.fallback = record.fallback.as_ref().map(restore);
+            // Failures are part of the replayed log.
+            let _ = self.run_state(state).await;
+        }
+        std::mem::replace(&mut self.audit, previous).unwrap_or_default()
+    }
+
+    /// Logs a request to the audit log, if auditing is on.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The request.
+    /// * `response` - The raw response of the model, or `None` if the request failed.
+    /// * `decisions` - The decisions of the validation stages and of the stage that failed.
+    /// * `fallback` - The fallback line shown instead of the reply, if any.
+    fn audit(
+        &mut self,
+        state: &PipelineState,
+        response: Option<&str>,
+        decisions: Vec<ValidationDecision>,
+        fallback: Option<&str>,
+    ) {
+        let Some(log) = &mut self.audit else {
+            return;
+        };
+        let speaker = state.metadata.get("npc_id").map_or("", |id| id.as_str());
+        let mut record = AuditRecord::new(speaker, state.request_type, &state.input)
+            .with_prompt(&state.prompt)
+            .with_metadata(&state.metadata);
+        if let Some(response) = response {
+            record = record.with_response(response);
+        }
+        if let Some(fallback) = fallback {
+            record = record.with_fallback(fallback);
+        } else if decisions.iter().all(|decision| decision.passed) {
+            record = record.with_reply(&state.reply);
+        }
+        record.validation = decisions;
+        log.record(record);
+    }
+}
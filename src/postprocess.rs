@@ -0,0 +1,213 @@
+//! # Postprocess Module
+//!
+//! This module cleans up generated lines before they reach the game. Models wrap dialogue in
+//! markdown, narrate stage directions ("*wipes the counter*"), prefix the speaker's name, use
+//! typographic quotes the game font may lack, ramble past the length a speech bubble fits, and
+//! leave placeholders such as `{player_name}` unfilled. A `PostProcessor` runs a configurable
+//! chain of filters that fix each of these, in order.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Represents one post-processing step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Filter {
+    /// Removes markdown emphasis, headings, bullets, inline code, and code fences.
+    StripMarkdown,
+    /// Removes stage directions in asterisks, parentheses, or square brackets.
+    RemoveStageDirections,
+    /// Removes a leading "Name:" speaker prefix.
+    RemoveSpeakerPrefix,
+    /// Replaces typographic quotes, apostrophes, and ellipses with plain ASCII ones.
+    NormalizeQuotes,
+    /// Removes quotes wrapping the whole line.
+    Unquote,
+    /// Keeps at most this many sentences.
+    MaxSentences(usize),
+    /// Replaces `{key}` placeholders with their values. Unknown placeholders are left as is.
+    ReplacePlaceholders(HashMap<String, String>),
+    /// Collapses runs of whitespace into single spaces and trims the line.
+    CollapseWhitespace,
+    /// Slurs the line like a drunk speaker ("Yes, sir" becomes "Yesh, shir").
+    Slur,
+}
+
+/// Removes every span from `open` to the next `close`, including both.
+fn remove_spans(line: &str, open: char, close: char) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut depth = 0;
+    for c in line.chars() {
+        if c == open && (open != close || depth == 0) {
+            depth += 1;
+        } else if c == close && depth > 0 {
+            depth -= 1;
+        } else if depth == 0 {
+            result.push(c);
+        }
+    }
+    // An unclosed span was not a span after all.
+    if depth > 0 {
+        return line.to_string();
+    }
+    result
+}
+
+/// Splits a line into sentences, keeping their terminators and closing quotes.
+fn sentences(line: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        if matches!(c, '.' | '!' | '?') {
+            while let Some(next) = chars.peek().copied() {
+                if matches!(next, '.' | '!' | '?' | '"' | '\'') {
+                    current.push(next);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            if chars.peek().is_none_or(|next| next.is_whitespace()) {
+                result.push(current.trim().to_string());
+                current.clear();
+            }
+        }
+    }
+    if !current.trim().is_empty() {
+        result.push(current.trim().to_string());
+    }
+    result
+}
+
+impl Filter {
+    /// Applies the filter to a line.
+    pub fn apply(&self, line: &str) -> String {
+        match self {
+            Filter::StripMarkdown => line
+                .lines()
+                .filter(|l| !l.trim_start().starts_with("```"))
+                .map(|l| {
+                    l.trim_start()
+                        .trim_start_matches('#')
+                        .trim_start_matches(['-', '>'])
+                        .trim_start()
+                        .replace("**", "")
+                        .replace("__", "")
+                        .replace('`', "")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Filter::RemoveStageDirections => {
+                let line = remove_spans(line, '*', '*');
+                let line = remove_spans(&line, '(', ')');
+                remove_spans(&line, '[', ']')
+            }
+            Filter::RemoveSpeakerPrefix => match line.split_once(':') {
+                Some((prefix, rest))
+                    if !prefix.trim().is_empty()
+                        && prefix.split_whitespace().count() <= 3
+                        && prefix.chars().next().is_some_and(char::is_uppercase) =>
+                {
+                    rest.trim_start().to_string()
+                }
+                _ => line.to_string(),
+            },
+            Filter::NormalizeQuotes => line
+                .replace(['\u{201c}', '\u{201d}', '\u{201e}'], "\"")
+                .replace(['\u{2018}', '\u{2019}'], "'")
+                .replace('\u{2026}', "..."),
+            Filter::Unquote => {
+                let trimmed = line.trim();
+                let quoted = trimmed.len() >= 2
+                    && trimmed.starts_with('"')
+                    && trimmed.ends_with('"')
+                    && !trimmed[1..trimmed.len() - 1].contains('"');
+                if quoted {
+                    trimmed[1..trimmed.len() - 1].to_string()
+                } else {
+                    line.to_string()
+                }
+            }
+            Filter::MaxSentences(max) => {
+                sentences(line).into_iter().take(*max).collect::<Vec<_>>().join(" ")
+            }
+            Filter::ReplacePlaceholders(values) => {
+                values.iter().fold(line.to_string(), |line, (key, value)| {
+                    line.replace(&format!("{{{}}}", key), value)
+                })
+            }
+            Filter::CollapseWhitespace => line.split_whitespace().collect::<Vec<_>>().join(" "),
+            Filter::Slur => {
+                let mut result = String::with_capacity(line.len());
+                let mut chars = line.chars().peekable();
+                while let Some(c) = chars.next() {
+                    result.push(c);
+                    if matches!(c, 's' | 'S') && chars.peek().is_none_or(|next| *next != 'h') {
+                        result.push('h');
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+/// Represents a chain of filters applied to every generated line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostProcessor {
+    pub filters: Vec<Filter>,
+}
+
+impl Default for PostProcessor {
+    /// The standard chain: strip markdown, stage directions, and the speaker prefix, normalize
+    /// and unwrap quotes, and collapse whitespace. It does not limit length or fill placeholders.
+    fn default() -> Self {
+        PostProcessor {
+            filters: vec![
+                Filter::StripMarkdown,
+                Filter::RemoveStageDirections,
+                Filter::RemoveSpeakerPrefix,
+                Filter::NormalizeQuotes,
+                Filter::Unquote,
+                Filter::CollapseWhitespace,
+            ],
+        }
+    }
+}
+
+impl PostProcessor {
+    /// Creates a new post-processor without filters.
+    pub fn new() -> Self {
+        PostProcessor {
+            filters: Vec::new(),
+        }
+    }
+
+    /// Adds a filter to the end of the chain.
+    pub fn with(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Runs a line through the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::postprocess::{Filter, PostProcessor};
+    ///
+    /// let names = HashMap::from([("player_name".to_string(), "Ayla".to_string())]);
+    /// let processor = PostProcessor::default()
+    ///     .with(Filter::ReplacePlaceholders(names))
+    ///     .with(Filter::MaxSentences(2));
+    /// let line = "Brom: \u{201c}*wipes the counter* **Welcome**, {player_name}! Ale\u{2019}s fresh. \
+    ///             We also have stew.\u{201d}";
+    /// assert_eq!(processor.apply(line), "Welcome, Ayla! Ale's fresh.");
+    /// ```
+    pub fn apply(&self, line: &str) -> String {
+        self.filters.iter().fold(line.to_string(), |line, filter| filter.apply(&line))
+    }
+}
@@ -0,0 +1,222 @@
+//! # Prewarm Module
+//!
+//! This module pre-generates the lines an NPC is likely to need first (greetings, barks, and
+//! quest briefings) while the game is loading, so that the first interaction with each NPC in an
+//! upcoming scene is served from the cache without waiting on the language model.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bark::BarkContext;
+#[cfg(feature = "network")]
+use crate::bark::BarkService;
+#[cfg(feature = "network")]
+use crate::dialogue_generation::DialogueClient;
+use crate::dialogue_generation::RequestType;
+use crate::prompt::PersonaCard;
+
+/// Represents the kind of line stored in the response cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LineKind {
+    /// The first thing an NPC says when the player approaches.
+    Greeting,
+    /// An NPC explaining a quest to the player.
+    QuestBriefing,
+}
+
+/// Identifies a cached line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CacheKey {
+    /// The ID of the NPC who says the line. NPCs are keyed by ID, not by name, so two NPCs who
+    /// share a name do not share lines.
+    pub npc: String,
+    pub kind: LineKind,
+    /// What the line is about (e.g. the quest ID); empty for greetings.
+    pub subject: String,
+}
+
+impl CacheKey {
+    /// Creates a new cache key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::prewarm::{CacheKey, LineKind};
+    /// let key = CacheKey::new("brom", LineKind::QuestBriefing, "lost_hammer");
+    /// ```
+    pub fn new(npc: &str, kind: LineKind, subject: &str) -> Self {
+        CacheKey {
+            npc: npc.to_string(),
+            kind,
+            subject: subject.to_string(),
+        }
+    }
+}
+
+/// Stores pre-generated greetings and quest briefings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseCache {
+    lines: HashMap<CacheKey, String>,
+}
+
+impl ResponseCache {
+    /// Creates a new, empty response cache.
+    pub fn new() -> Self {
+        ResponseCache::default()
+    }
+
+    /// Stores a line, replacing any previous line for the key.
+    pub fn insert(&mut self, key: CacheKey, line: &str) {
+        self.lines.insert(key, line.to_string());
+    }
+
+    /// Gets the cached line for a key.
+    pub fn get(&self, key: &CacheKey) -> Option<&str> {
+        self.lines.get(key).map(|line| line.as_str())
+    }
+
+    /// Returns true if a line is cached for the key.
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.lines.contains_key(key)
+    }
+
+    /// Returns the number of cached lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+/// Represents an NPC the player is likely to meet in an upcoming scene.
+#[derive(Debug, Clone, Default)]
+pub struct ScenePlan {
+    /// The ID of the NPC.
+    pub npc_id: String,
+    pub persona: PersonaCard,
+    /// Situations the NPC is likely to bark in.
+    pub barks: Vec<BarkContext>,
+    /// Quests the NPC may brief the player on, as `(quest ID, summary)` pairs.
+    pub quests: Vec<(String, String)>,
+}
+
+impl ScenePlan {
+    /// Creates a new plan for an NPC.
+    pub fn new(npc_id: &str, persona: PersonaCard) -> Self {
+        ScenePlan {
+            npc_id: npc_id.to_string(),
+            persona,
+            ..ScenePlan::default()
+        }
+    }
+
+    /// Lists the greeting and quest briefing requests needed for this NPC.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `(key, request type, prompt)` triples.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::prewarm::{LineKind, ScenePlan};
+    /// use athena::prompt::PersonaCard;
+    ///
+    /// let mut plan = ScenePlan::new("brom", PersonaCard::new("Brom", "The village blacksmith."));
+    /// plan.quests.push(("lost_hammer".to_string(), "Find Brom's hammer in the mine.".to_string()));
+    /// let tasks = plan.tasks();
+    /// assert_eq!(tasks.len(), 2);
+    /// assert_eq!(tasks[0].0.npc, "brom");
+    /// assert_eq!(tasks[1].0.kind, LineKind::QuestBriefing);
+    /// assert!(tasks[1].2.contains("Find Brom's hammer"));
+    /// ```
+    pub fn tasks(&self) -> Vec<(CacheKey, RequestType, String)> {
+        let persona = self.persona.render();
+        let mut tasks = vec![(
+            CacheKey::new(&self.npc_id, LineKind::Greeting, ""),
+            RequestType::Conversation,
+            format!(
+                "{}\n\nThe player approaches you for the first time. Greet them in one or two \
+                 sentences. Reply in character.",
+                persona
+            ),
+        )];
+        for (quest_id, summary) in &self.quests {
+            tasks.push((
+                CacheKey::new(&self.npc_id, LineKind::QuestBriefing, quest_id),
+                RequestType::Story,
+                format!(
+                    "{}\n\nBrief the player on a task you need help with: {}\nReply in character.",
+                    persona, summary
+                ),
+            ));
+        }
+        tasks
+    }
+}
+
+/// Summarizes the outcome of a prewarm pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrewarmReport {
+    /// Greetings and briefings generated and cached.
+    pub lines: usize,
+    /// Barks generated and cached.
+    pub barks: usize,
+    /// Lines that were already cached and skipped.
+    pub skipped: usize,
+    /// Requests that failed.
+    pub failed: usize,
+}
+
+/// Pre-generates greetings, barks, and quest briefings for the NPCs of upcoming scenes.
+///
+/// Lines already in the cache are not requested again, so calling this on every loading screen
+/// only pays for NPCs and quests that are new.
+///
+/// # Arguments
+///
+/// * `client` - The client used for greetings and briefings.
+/// * `cache` - The cache greetings and briefings are stored in.
+/// * `barks` - The bark service whose cache is filled with barks.
+/// * `plans` - The NPCs of the upcoming scenes.
+///
+/// # Returns
+///
+/// A `PrewarmReport` counting what was generated.
+#[cfg(feature = "network")]
+pub async fn prewarm(
+    client: &DialogueClient,
+    cache: &mut ResponseCache,
+    barks: &mut BarkService,
+    plans: &[ScenePlan],
+) -> PrewarmReport {
+    let mut report = PrewarmReport::default();
+    for plan in plans {
+        for (key, request_type, prompt) in plan.tasks() {
+            if cache.contains(&key) {
+                report.skipped += 1;
+                continue;
+            }
+            let reply = client.send(request_type, &prompt).await.ok().and_then(|response| {
+                response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .map(|choice| choice.message.content)
+            });
+            match reply {
+                Some(line) => {
+                    cache.insert(key, line.trim());
+                    report.lines += 1;
+                }
+                None => report.failed += 1,
+            }
+        }
+        report.barks += barks.pregenerate(&plan.barks).await;
+    }
+    report
+}
@@ -0,0 +1,185 @@
+//! # Profiling Module
+//!
+//! This module accounts for the time NPC simulation takes each frame and keeps it within a
+//! budget. A `FrameProfiler` times each module of the update (needs, emotions, schedules,
+//! consolidation, and whatever the game adds, such as ambient generation) and, once a frame's
+//! budget is spent, turns away low-priority work instead of letting the frame run long. Turned
+//! away work is listed in the frame's profile. The `AgentManager` runs its own turned away
+//! phases first on the next frame; work the game hands in is not kept, so the game submits it
+//! again on a later frame.
+//!
+//! Essential work always runs, so a frame can still go over budget; the profile reports by how
+//! much, and which modules took the time.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Represents how important a piece of per-frame work is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WorkPriority {
+    /// Runs every frame, whatever the budget.
+    Essential,
+    /// Deferred to a later frame once the budget is spent (e.g. reflection, ambient chatter).
+    Low,
+}
+
+/// Represents work that was deferred to a later frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeferredWork {
+    /// The module the work belongs to (e.g. "consolidation").
+    pub module: String,
+    /// The agent the work was for, if any.
+    pub agent: Option<String>,
+}
+
+/// Represents where the time of one frame went.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameProfile {
+    /// The time spent in each module, in the order the modules first ran.
+    pub timings: Vec<(String, Duration)>,
+    /// The work deferred to a later frame.
+    pub deferred: Vec<DeferredWork>,
+    /// The budget of the frame, if it had one.
+    pub budget: Option<Duration>,
+}
+
+impl FrameProfile {
+    /// Gets the time spent in a module.
+    pub fn get_time(&self, module: &str) -> Duration {
+        self.timings
+            .iter()
+            .find(|(name, _)| name == module)
+            .map_or(Duration::ZERO, |(_, time)| *time)
+    }
+
+    /// Gets the time spent in all modules.
+    pub fn total(&self) -> Duration {
+        self.timings.iter().map(|(_, time)| *time).sum()
+    }
+
+    /// Returns true if the frame took longer than its budget.
+    pub fn is_over_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.total() > budget)
+    }
+
+    /// Gets the modules that took the most time, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<(&str, Duration)> {
+        let mut timings: Vec<(&str, Duration)> =
+            self.timings.iter().map(|(name, time)| (name.as_str(), *time)).collect();
+        timings.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+        timings.truncate(count);
+        timings
+    }
+}
+
+/// Times the modules of one frame and enforces its budget.
+#[derive(Debug, Clone)]
+pub struct FrameProfiler {
+    started: Instant,
+    profile: FrameProfile,
+}
+
+impl FrameProfiler {
+    /// Starts profiling a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget` - How long the frame's work may take, or `None` for no limit.
+    pub fn start(budget: Option<Duration>) -> Self {
+        FrameProfiler {
+            started: Instant::now(),
+            profile: FrameProfile {
+                budget,
+                ..FrameProfile::default()
+            },
+        }
+    }
+
+    /// Gets the time since the frame started.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Returns true if the frame's budget is spent.
+    pub fn is_exhausted(&self) -> bool {
+        self.profile.budget.is_some_and(|budget| self.elapsed() >= budget)
+    }
+
+    /// Returns true if work of a priority may run now.
+    pub fn allows(&self, priority: WorkPriority) -> bool {
+        priority == WorkPriority::Essential || !self.is_exhausted()
+    }
+
+    /// Adds time to a module.
+    pub fn record(&mut self, module: &str, time: Duration) {
+        match self.profile.timings.iter_mut().find(|(name, _)| name == module) {
+            Some((_, total)) => *total += time,
+            None => self.profile.timings.push((module.to_string(), time)),
+        }
+    }
+
+    /// Runs work and adds the time it took to a module.
+    pub fn time<T>(&mut self, module: &str, work: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = work();
+        self.record(module, started.elapsed());
+        result
+    }
+
+    /// Runs work of a priority if the budget allows it, timing it under a module; otherwise
+    /// records it as deferred.
+    ///
+    /// # Returns
+    ///
+    /// The result of the work, or `None` if it was deferred.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use athena::profiling::{FrameProfiler, WorkPriority};
+    ///
+    /// let mut frame = FrameProfiler::start(Some(Duration::ZERO));
+    /// assert_eq!(frame.run("needs", None, WorkPriority::Essential, || 1), Some(1));
+    /// assert_eq!(frame.run("chatter", Some("bard"), WorkPriority::Low, || 2), None);
+    ///
+    /// let profile = frame.finish();
+    /// assert_eq!(profile.deferred[0].module, "chatter");
+    /// assert_eq!(profile.timings.len(), 1);
+    /// ```
+    pub fn run<T>(
+        &mut self,
+        module: &str,
+        agent: Option<&str>,
+        priority: WorkPriority,
+        work: impl FnOnce() -> T,
+    ) -> Option<T> {
+        if !self.allows(priority) {
+            self.defer(module, agent);
+            return None;
+        }
+        Some(self.time(module, work))
+    }
+
+    /// Records work as deferred to a later frame, once per frame.
+    pub fn defer(&mut self, module: &str, agent: Option<&str>) {
+        let work = DeferredWork {
+            module: module.to_string(),
+            agent: agent.map(str::to_string),
+        };
+        if !self.profile.deferred.contains(&work) {
+            self.profile.deferred.push(work);
+        }
+    }
+
+    /// Gets the profile of the frame so far.
+    pub fn get_profile(&self) -> &FrameProfile {
+        &self.profile
+    }
+
+    /// Finishes the frame.
+    pub fn finish(self) -> FrameProfile {
+        self.profile
+    }
+}
@@ -0,0 +1,422 @@
+//! # Promises Module
+//!
+//! This module lets NPCs remember what the player promised them, and by when. Promises are
+//! detected in conversation, either with a language model returning structured output or with
+//! a quick offline scan for phrases such as "I promise to..." and "you have my word". Each
+//! promise is kept in a book with its deadline until it is kept or the deadline passes, at which
+//! point it counts as broken. The book only remembers the most recently settled promises, so it
+//! does not grow without bound over a long game.
+//!
+//! The book supplies what NPCs need to follow up: a reminder while the promise is pending, a
+//! line for when it is kept or broken, and prompt directives so generated dialogue can bring it
+//! up.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::dialogue_act::normalize;
+#[cfg(feature = "network")]
+use crate::dialogue_generation::{DialogueClient, RequestType};
+#[cfg(feature = "network")]
+use crate::structured;
+
+/// How many kept or broken promises a book remembers. The earliest settled are forgotten first.
+const MAX_SETTLED: usize = 16;
+
+/// The phrases that mark a line as a promise.
+const PROMISE_MARKERS: [&str; 5] =
+    ["i promise", "i swear", "you have my word", "i give you my word", "i vow"];
+
+/// The phrases that introduce what is promised, most specific first.
+const COMMITMENT_PHRASES: [&str; 8] = [
+    "i promise to ",
+    "i promise i'll ",
+    "i promise i will ",
+    "i swear to ",
+    "i swear i'll ",
+    "i vow to ",
+    "i'll ",
+    "i will ",
+];
+
+/// The number words understood in deadlines.
+const NUMBER_WORDS: [&str; 10] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+];
+
+/// Represents the state of a promise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PromiseStatus {
+    Pending,
+    Kept,
+    Broken,
+}
+
+/// Represents a promise made to an NPC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Promise {
+    /// Who made the promise.
+    pub promiser: String,
+    /// What was promised (e.g. "bring back the lantern").
+    pub what: String,
+    /// When it was made, in seconds of game time.
+    pub made_at: f64,
+    /// When it must be kept by, in seconds of game time, or `None` if there is no deadline.
+    pub deadline: Option<f64>,
+    pub status: PromiseStatus,
+    /// Whether the NPC has already reminded the promiser.
+    pub reminded: bool,
+}
+
+impl Promise {
+    /// Returns true if the promise is pending and its deadline has passed by a game time.
+    pub fn is_overdue(&self, now: f64) -> bool {
+        self.status == PromiseStatus::Pending
+            && self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Returns a line the NPC can say to the promiser about the promise.
+    pub fn follow_up(&self) -> String {
+        match self.status {
+            PromiseStatus::Pending => format!("Don't forget, you promised to {}.", self.what),
+            PromiseStatus::Kept => {
+                format!("You promised to {}, and you did. Thank you.", self.what)
+            }
+            PromiseStatus::Broken => format!("You promised to {}. I trusted you.", self.what),
+        }
+    }
+}
+
+/// Represents a promise found in a line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedPromise {
+    /// What was promised, phrased so it follows "you promised to".
+    pub what: String,
+    /// How many hours the promiser has to keep it, if they said.
+    #[serde(default)]
+    pub within_hours: Option<f64>,
+}
+
+/// Parses a deadline at the start of a phrase (e.g. "by tomorrow", "within 3 days"), as hours.
+fn parse_deadline(phrase: &str) -> Option<f64> {
+    let words: Vec<&str> = phrase.split_whitespace().collect();
+    match words.as_slice() {
+        ["tonight", ..] | ["by", "tonight", ..] | ["by", "nightfall", ..] => Some(12.0),
+        ["tomorrow", ..] | ["by", "tomorrow", ..] => Some(24.0),
+        ["by", "the", "end", "of", "the", "week", ..] | ["this", "week", ..] => Some(168.0),
+        ["in" | "within", count, unit, ..] => {
+            let count = count.parse::<f64>().ok().or_else(|| {
+                let position = NUMBER_WORDS.iter().position(|word| word == count)?;
+                Some(position as f64 + 1.0)
+            })?;
+            let hours = match unit.trim_end_matches('s') {
+                "hour" => 1.0,
+                "day" => 24.0,
+                "week" => 168.0,
+                _ => return None,
+            };
+            Some(count * hours)
+        }
+        _ => None,
+    }
+}
+
+/// Finds promises in a line without a language model, by looking for promise markers and the
+/// commitment they go with.
+///
+/// # Examples
+///
+/// ```
+/// use athena::promises::detect_promises;
+///
+/// let found = detect_promises("I promise to bring back your lantern by tomorrow.");
+/// assert_eq!(found[0].what, "bring back your lantern");
+/// assert_eq!(found[0].within_hours, Some(24.0));
+///
+/// let found = detect_promises("I'll find your brother within three days, you have my word!");
+/// assert_eq!(found[0].what, "find your brother");
+/// assert_eq!(found[0].within_hours, Some(72.0));
+///
+/// assert!(detect_promises("I'll have the stew, please.").is_empty());
+/// ```
+pub fn detect_promises(line: &str) -> Vec<ExtractedPromise> {
+    let lower = line.to_lowercase().replace('’', "'");
+    let mut found = Vec::new();
+    for sentence in lower.split(['.', '!', '?', ';']) {
+        if !PROMISE_MARKERS.iter().any(|marker| sentence.contains(marker)) {
+            continue;
+        }
+        let Some(start) = COMMITMENT_PHRASES
+            .iter()
+            .find_map(|phrase| sentence.find(phrase).map(|index| index + phrase.len()))
+        else {
+            continue;
+        };
+        let clause = sentence[start..].split(',').next().unwrap_or("").trim();
+        let mut what = clause;
+        let mut within_hours = None;
+        for (index, _) in clause.match_indices(' ') {
+            if let Some(hours) = parse_deadline(&clause[index + 1..]) {
+                what = &clause[..index];
+                within_hours = Some(hours);
+                break;
+            }
+        }
+        if !what.is_empty() {
+            found.push(ExtractedPromise {
+                what: what.to_string(),
+                within_hours,
+            });
+        }
+    }
+    found
+}
+
+/// Gets the JSON schema of the structured output expected when extracting promises.
+pub fn extraction_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "promises": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "what": { "type": "string" },
+                        "within_hours": { "type": ["number", "null"] }
+                    },
+                    "required": ["what"]
+                }
+            }
+        },
+        "required": ["promises"]
+    })
+}
+
+/// Builds the prompt asking a language model for the promises in a line.
+pub fn extraction_prompt(line: &str) -> String {
+    format!(
+        "List the promises the speaker makes in this line, if any. Phrase each so it follows \
+         \"you promised to\", and give the hours they have to keep it if they said.\n\nLine: {}",
+        line
+    )
+}
+
+/// The structured output of promise extraction.
+#[cfg(feature = "network")]
+#[derive(Debug, Deserialize)]
+struct Extraction {
+    promises: Vec<ExtractedPromise>,
+}
+
+/// Finds promises in a line with a language model, as structured output.
+///
+/// # Returns
+///
+/// * `Result<Vec<ExtractedPromise>, Box<dyn std::error::Error>>` - The promises, or an error if
+///   the model did not produce a valid answer.
+#[cfg(feature = "network")]
+pub async fn extract_promises(
+    client: &DialogueClient,
+    line: &str,
+) -> Result<Vec<ExtractedPromise>, Box<dyn std::error::Error>> {
+    let schema = extraction_schema();
+    let prompt = extraction_prompt(line);
+    let extraction: Extraction = structured::generate_structured(
+        client,
+        RequestType::Classification,
+        &prompt,
+        Some(&schema),
+        2,
+    )
+    .await?;
+    Ok(extraction.promises)
+}
+
+/// Represents the promises made to an NPC.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromiseBook {
+    promises: Vec<Promise>,
+}
+
+impl PromiseBook {
+    /// Creates a new, empty book.
+    pub fn new() -> Self {
+        PromiseBook::default()
+    }
+
+    /// Records a promise. A pending promise with the same promiser and subject is replaced.
+    pub fn record(&mut self, promise: Promise) {
+        self.promises.retain(|existing| {
+            existing.status != PromiseStatus::Pending
+                || existing.promiser != promise.promiser
+                || existing.what != promise.what
+        });
+        self.promises.push(promise);
+    }
+
+    /// Marks the pending promise of a promiser about a subject as kept. The subject matches if
+    /// either contains the other as whole words, ignoring case; an empty subject matches
+    /// nothing.
+    ///
+    /// # Returns
+    ///
+    /// The kept promise, or `None` if there is no such promise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::promises::{Promise, PromiseBook, PromiseStatus};
+    ///
+    /// let mut book = PromiseBook::new();
+    /// book.record(Promise {
+    ///     promiser: "alice".to_string(),
+    ///     what: "bring back your lantern".to_string(),
+    ///     made_at: 0.0,
+    ///     deadline: Some(3_600.0),
+    ///     status: PromiseStatus::Pending,
+    ///     reminded: false,
+    /// });
+    /// assert_eq!(book.pending_with("alice").len(), 1);
+    /// assert!(book.keep("alice", "").is_none());
+    /// assert!(book.keep("alice", "lant").is_none());
+    /// assert!(book.keep("alice", "lantern").is_some());
+    /// assert!(book.pending_with("alice").is_empty());
+    /// assert!(book.expire(7_200.0).is_empty());
+    /// ```
+    pub fn keep(&mut self, promiser: &str, what: &str) -> Option<Promise> {
+        if what.trim().is_empty() {
+            return None;
+        }
+        let what = normalize(what);
+        let index = self.promises.iter().position(|promise| {
+            let subject = normalize(&promise.what);
+            promise.status == PromiseStatus::Pending
+                && promise.promiser == promiser
+                && (subject.contains(&what) || what.contains(&subject))
+        })?;
+        let mut promise = self.promises.remove(index);
+        promise.status = PromiseStatus::Kept;
+        self.settle(promise.clone());
+        Some(promise)
+    }
+
+    /// Marks the pending promises whose deadline has passed by a game time as broken.
+    ///
+    /// # Returns
+    ///
+    /// The promises just broken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::promises::{Promise, PromiseBook, PromiseStatus};
+    ///
+    /// let mut book = PromiseBook::new();
+    /// for day in 0..20 {
+    ///     book.record(Promise {
+    ///         promiser: "alice".to_string(),
+    ///         what: format!("pay back debt {}", day),
+    ///         made_at: 0.0,
+    ///         deadline: Some(86_400.0 * day as f64),
+    ///         status: PromiseStatus::Pending,
+    ///         reminded: false,
+    ///     });
+    /// }
+    /// assert_eq!(book.expire(86_400.0 * 20.0).len(), 20);
+    /// // Only the most recently settled promises are remembered.
+    /// assert_eq!(book.len(), 16);
+    /// ```
+    pub fn expire(&mut self, now: f64) -> Vec<Promise> {
+        let (mut broken, promises): (Vec<Promise>, Vec<Promise>) =
+            self.promises.drain(..).partition(|promise| promise.is_overdue(now));
+        self.promises = promises;
+        for promise in &mut broken {
+            promise.status = PromiseStatus::Broken;
+            self.settle(promise.clone());
+        }
+        broken
+    }
+
+    /// Moves a promise that was just kept or broken to the end of the book, forgetting the
+    /// earliest settled promises beyond `MAX_SETTLED`.
+    fn settle(&mut self, promise: Promise) {
+        self.promises.push(promise);
+        let settled = self.promises.iter().filter(|p| p.status != PromiseStatus::Pending);
+        let mut excess = settled.count().saturating_sub(MAX_SETTLED);
+        self.promises.retain(|promise| {
+            if excess > 0 && promise.status != PromiseStatus::Pending {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Gets the pending promises of a promiser, earliest deadline first.
+    pub fn pending_with(&self, promiser: &str) -> Vec<&Promise> {
+        let mut pending: Vec<&Promise> = self
+            .promises
+            .iter()
+            .filter(|promise| promise.promiser == promiser)
+            .filter(|promise| promise.status == PromiseStatus::Pending)
+            .collect();
+        pending.sort_by(|a, b| {
+            let a = a.deadline.unwrap_or(f64::INFINITY);
+            a.total_cmp(&b.deadline.unwrap_or(f64::INFINITY))
+        });
+        pending
+    }
+
+    /// Takes the reminder for the pending promise of a promiser with the earliest deadline that
+    /// they have not been reminded of yet, marking it as reminded.
+    pub fn remind(&mut self, promiser: &str) -> Option<String> {
+        let what = self.pending_with(promiser).into_iter().find(|p| !p.reminded)?.what.clone();
+        let promise = self.promises.iter_mut().find(|promise| {
+            promise.status == PromiseStatus::Pending
+                && promise.promiser == promiser
+                && promise.what == what
+        })?;
+        promise.reminded = true;
+        Some(promise.follow_up())
+    }
+
+    /// Builds the prompt directives reminding the NPC of a promiser's promises, pending and
+    /// broken, at a game time.
+    pub fn directives(&self, promiser: &str, now: f64) -> Vec<String> {
+        self.promises
+            .iter()
+            .filter(|promise| promise.promiser == promiser)
+            .filter_map(|promise| match promise.status {
+                PromiseStatus::Pending => Some(match promise.deadline {
+                    Some(deadline) => format!(
+                        "They promised to {}; {:.0} hours remain.",
+                        promise.what,
+                        ((deadline - now) / 3_600.0).max(0.0)
+                    ),
+                    None => format!("They promised to {}.", promise.what),
+                }),
+                PromiseStatus::Broken => Some(format!(
+                    "They broke their promise to {}; you are disappointed.",
+                    promise.what
+                )),
+                PromiseStatus::Kept => None,
+            })
+            .collect()
+    }
+
+    /// Iterates over all promises, kept and broken ones included.
+    pub fn promises(&self) -> impl Iterator<Item = &Promise> {
+        self.promises.iter()
+    }
+
+    /// Returns the number of promises.
+    pub fn len(&self) -> usize {
+        self.promises.len()
+    }
+
+    /// Returns true if the book has no promises.
+    pub fn is_empty(&self) -> bool {
+        self.promises.is_empty()
+    }
+}
@@ -0,0 +1,240 @@
+//! # Python Module
+//!
+//! This module exposes NPC agents, knowledge graphs, and the dialogue client to Python through
+//! PyO3, so narrative tools and prototype pipelines written in Python drive the same NPC brains
+//! as the shipped game.
+//!
+//! The module is only built with the `python` feature. Build the extension with maturin, e.g.
+//! `maturin develop --features python,pyo3/extension-module`, then `import athena`.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::agent::NpcAgent;
+use crate::dialogue_generation::{DialogueClient, RequestType};
+use crate::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+
+/// Parses a request type from its lowercase name.
+fn parse_request_type(name: &str) -> PyResult<RequestType> {
+    match name {
+        "bark" => Ok(RequestType::Bark),
+        "classification" => Ok(RequestType::Classification),
+        "conversation" => Ok(RequestType::Conversation),
+        "story" => Ok(RequestType::Story),
+        _ => Err(PyValueError::new_err(format!("Unknown request type: {}", name))),
+    }
+}
+
+/// Adds a relationship to a graph, creating its endpoints if they are not known yet.
+fn add_fact(
+    graph: &mut KnowledgeGraph,
+    source: &str,
+    relation: &str,
+    target: &str,
+    properties: HashMap<String, String>,
+) {
+    for id in [source, target] {
+        if graph.get_entity(id).is_none() {
+            graph.add_entity(Entity::new(id, HashMap::new()));
+        }
+    }
+    graph.add_relationship(Relationship::new(source, target, relation, properties));
+}
+
+/// A knowledge graph, exposed to Python as `athena.KnowledgeGraph`.
+#[pyclass(name = "KnowledgeGraph", unsendable)]
+pub struct PyKnowledgeGraph {
+    inner: KnowledgeGraph,
+}
+
+#[pymethods]
+impl PyKnowledgeGraph {
+    #[new]
+    fn new() -> Self {
+        PyKnowledgeGraph {
+            inner: KnowledgeGraph::new(),
+        }
+    }
+
+    /// Adds an entity with optional properties.
+    #[pyo3(signature = (id, properties = None))]
+    fn add_entity(&mut self, id: &str, properties: Option<HashMap<String, String>>) {
+        self.inner.add_entity(Entity::new(id, properties.unwrap_or_default()));
+    }
+
+    /// Adds a relationship, creating its endpoints if needed.
+    #[pyo3(signature = (source, relation, target, properties = None))]
+    fn add_relationship(
+        &mut self,
+        source: &str,
+        relation: &str,
+        target: &str,
+        properties: Option<HashMap<String, String>>,
+    ) {
+        add_fact(&mut self.inner, source, relation, target, properties.unwrap_or_default());
+    }
+
+    /// Gets the properties of an entity, or `None` if it is not known.
+    fn get_entity(&self, id: &str) -> Option<HashMap<String, String>> {
+        self.inner.get_entity(id).map(|entity| entity.properties.clone())
+    }
+
+    /// Gets the relationships of an entity as `(source, relation, target)` tuples.
+    fn get_relationships(&self, id: &str) -> Vec<(String, String, String)> {
+        self.inner
+            .get_relationships(id)
+            .into_iter()
+            .map(|r| (r.source.to_string(), r.relation_type.to_string(), r.target.to_string()))
+            .collect()
+    }
+
+    fn entity_count(&self) -> usize {
+        self.inner.entity_count()
+    }
+
+    fn relationship_count(&self) -> usize {
+        self.inner.relationship_count()
+    }
+}
+
+/// An NPC agent, exposed to Python as `athena.NpcAgent`.
+#[pyclass(name = "NpcAgent", unsendable)]
+pub struct PyNpcAgent {
+    inner: NpcAgent,
+}
+
+#[pymethods]
+impl PyNpcAgent {
+    #[new]
+    fn new(id: &str) -> Self {
+        PyNpcAgent {
+            inner: NpcAgent::new(id, vec![]),
+        }
+    }
+
+    #[getter]
+    fn id(&self) -> String {
+        self.inner.id.to_string()
+    }
+
+    /// Advances the agent by the given amount of game time.
+    fn tick(&mut self, dt: f64) {
+        self.inner.tick(dt);
+    }
+
+    /// Chooses the agent's next action.
+    fn decide(&mut self) -> String {
+        self.inner.decide()
+    }
+
+    /// Processes one line said by a partner, returning the agent's reaction as a dict with
+    /// `engaged`, `action`, `dialogue`, `end_reason`, and `violations`.
+    fn process_turn<'py>(
+        &mut self,
+        py: Python<'py>,
+        partner_id: &str,
+        line: &str,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let output = self.inner.process_turn(partner_id, line, &[]);
+        let reaction = PyDict::new(py);
+        reaction.set_item("engaged", output.engaged)?;
+        reaction.set_item("action", output.action)?;
+        reaction.set_item("dialogue", output.dialogue)?;
+        let end_reason = output.conversation_end.map(|end| format!("{:?}", end.reason));
+        reaction.set_item("end_reason", end_reason)?;
+        let violations: Vec<String> = output.violations.into_iter().map(|v| v.norm_id).collect();
+        reaction.set_item("violations", violations)?;
+        Ok(reaction)
+    }
+
+    /// Gets the intensity of every emotion, keyed by name.
+    fn emotions(&self) -> HashMap<String, f64> {
+        self.inner
+            .emotions
+            .get_intensities()
+            .into_iter()
+            .map(|(emotion, intensity)| (format!("{:?}", emotion), intensity))
+            .collect()
+    }
+
+    /// Gets the agent's disposition towards a partner as `(warmth, fear, suspicion)`.
+    fn disposition_towards(&self, partner_id: &str) -> (f64, f64, f64) {
+        let disposition = self.inner.disposition_towards(partner_id);
+        (disposition.warmth, disposition.fear, disposition.suspicion)
+    }
+
+    /// Teaches the agent a fact, creating the entities involved if needed.
+    #[pyo3(signature = (source, relation, target, properties = None))]
+    fn learn(
+        &mut self,
+        source: &str,
+        relation: &str,
+        target: &str,
+        properties: Option<HashMap<String, String>>,
+    ) {
+        let properties = properties.unwrap_or_default();
+        add_fact(&mut self.inner.knowledge, source, relation, target, properties);
+    }
+
+    /// Gets the agent's relationships about an entity as `(source, relation, target)` tuples.
+    fn recall(&self, id: &str) -> Vec<(String, String, String)> {
+        self.inner
+            .knowledge
+            .get_relationships(id)
+            .into_iter()
+            .map(|r| (r.source.to_string(), r.relation_type.to_string(), r.target.to_string()))
+            .collect()
+    }
+}
+
+/// The dialogue client, exposed to Python as `athena.DialogueClient`. Calls block until the
+/// response arrives, without holding the GIL.
+#[pyclass(name = "DialogueClient")]
+pub struct PyDialogueClient {
+    inner: DialogueClient,
+    runtime: Runtime,
+}
+
+#[pymethods]
+impl PyDialogueClient {
+    #[new]
+    fn new(api_key: &str) -> PyResult<Self> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|error| PyRuntimeError::new_err(error.to_string()))?;
+        Ok(PyDialogueClient {
+            inner: DialogueClient::new(api_key),
+            runtime,
+        })
+    }
+
+    /// Sends a message and returns the generated text. The request type is one of "bark",
+    /// "classification", "conversation", or "story".
+    #[pyo3(signature = (input, request_type = "conversation"))]
+    fn send(&self, py: Python<'_>, input: &str, request_type: &str) -> PyResult<String> {
+        let request_type = parse_request_type(request_type)?;
+        let response = py
+            .allow_threads(|| self.runtime.block_on(self.inner.send(request_type, input)))
+            .map_err(|error| PyRuntimeError::new_err(error.to_string()))?;
+        Ok(response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .unwrap_or_default())
+    }
+}
+
+/// The `athena` Python module.
+#[pymodule]
+fn athena(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyNpcAgent>()?;
+    module.add_class::<PyKnowledgeGraph>()?;
+    module.add_class::<PyDialogueClient>()?;
+    Ok(())
+}
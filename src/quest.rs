@@ -0,0 +1,1546 @@
+//! # Quest Module
+//!
+//! This module describes the quests a game hands out and lets NPCs talk about them without
+//! knowing more than they should. A quest lists its clues: facts that lead toward its solution,
+//! such as where the stolen relic was taken. An NPC can only hint at a clue whose fact is in its
+//! own knowledge graph, so it cannot give away a solution it has no way of knowing.
+//!
+//! Hints say how the NPC came to know the clue, read from the fact's `source` property (the
+//! same one crimes use): "witnessed" if the NPC saw it, and `gossip:<id>` if someone told it.
+//! The informant is named by their relationship to the NPC, as in "My cousin Tam told me that
+//! the wolves were heading to the old mill." Clues the NPC is unsure of are hedged, and secret
+//! ones are kept from askers it does not trust (see `knowledge_query::SharingPolicy`).
+//!
+//! A quest's objectives are advanced by what happens in the game rather than by the game's own
+//! bookkeeping. Each objective counts events of a kind caused by the assignee (such as
+//! "combat.kill.wolf", matched like appraisal rules) or facts the NPCs come to know, and a
+//! `QuestLog` fed those events and checking those facts keeps count ("3/5 wolves slain"). Once
+//! a quest's objectives are all done, the log completes it and applies its effects: NPCs
+//! remember it, warm to whoever did it, or learn facts, and the rewards are handed back for the
+//! game to grant.
+//!
+//! A quest can have several resolutions, such as sparing or killing the bandit leader, reached
+//! by an event or chosen by the game. Each writes its own facts, deeds, and memories, and the
+//! NPCs concerned learn how the quest ended, so their dialogue can bring it up later (see
+//! `outcome_directive`).
+//!
+//! Quests can also be lost: a fail condition is met (the merchant to escort is killed), the time
+//! limit runs out, or the assignee gives up. The giver reacts in kind, and thinks less of them.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{AgentManager, NpcAgent};
+use crate::appraisal::{kind_matches, AppraisalEvent};
+use crate::emotional_response::Emotion;
+use crate::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+use crate::knowledge_query::{Disclosure, SharingPolicy};
+use crate::knowledge_summary::display_name;
+use crate::reputation::DeedCategory;
+
+#[cfg(feature = "network")]
+use crate::dialogue_generation::RequestType;
+#[cfg(feature = "network")]
+use crate::pipeline::{Pipeline, PipelineState, StageError};
+
+/// How relationships to an informant are phrased, keyed by relation type from the NPC to them.
+const KIN_NOUNS: [(&str, &str); 8] = [
+    ("married_to", "spouse"),
+    ("friend", "friend"),
+    ("rival", "rival"),
+    ("sibling", "sibling"),
+    ("cousin", "cousin"),
+    ("parent_of", "child"),
+    ("child_of", "parent"),
+    ("works_for", "master"),
+];
+
+/// Represents a fact that leads toward a quest's solution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clue {
+    /// The fact as a `(source, relation, target)` triple an NPC must know to hint at the clue.
+    pub source: String,
+    pub relation: String,
+    pub target: String,
+    /// How the clue is put into words, as a clause that can follow "I heard that" (e.g. "the
+    /// wolves were heading to the old mill"); if empty, the fact is phrased from its triple.
+    #[serde(default)]
+    pub statement: String,
+    /// Whether the clue gives the solution away rather than pointing toward it.
+    #[serde(default)]
+    pub solution: bool,
+}
+
+impl Clue {
+    /// Creates a new clue about a fact.
+    pub fn new(source: &str, relation: &str, target: &str, statement: &str) -> Self {
+        Clue {
+            source: source.to_string(),
+            relation: relation.to_string(),
+            target: target.to_string(),
+            statement: statement.to_string(),
+            solution: false,
+        }
+    }
+
+    /// Marks the clue as giving the solution away.
+    pub fn solving(mut self) -> Self {
+        self.solution = true;
+        self
+    }
+
+    /// Finds the clue's fact in a knowledge graph.
+    pub(crate) fn fact_in<'a>(&self, graph: &'a KnowledgeGraph) -> Option<&'a Relationship> {
+        graph.outgoing(&self.source).find(|relationship| {
+            relationship.target == self.target.as_str()
+                && relationship.relation_type == self.relation.as_str()
+        })
+    }
+
+    /// Puts the clue into words, phrasing the fact from its triple if it has no statement.
+    fn phrase(&self, graph: &KnowledgeGraph) -> String {
+        if !self.statement.is_empty() {
+            return self.statement.clone();
+        }
+        format!(
+            "{} {} {}",
+            display_name(graph, &self.source),
+            self.relation.replace('_', " "),
+            display_name(graph, &self.target)
+        )
+    }
+}
+
+/// Represents what advances an objective.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ObjectiveTrigger {
+    /// An event whose kind matches a pattern, as in appraisal rules (e.g. "combat.kill.wolf" or
+    /// "combat.kill.*"). Each matching event counts once.
+    Event {
+        pattern: String,
+        /// Who must cause the event: an ID, `ASSIGNEE` for the quest's assignee, or `None` for
+        /// anyone.
+        actor: Option<String>,
+    },
+    /// A fact in the knowledge graphs of the managed NPCs, as a `(source, relation, target)`
+    /// triple where "*" matches anything. Each distinct matching fact counts once.
+    Fact {
+        /// Whose knowledge graph the fact must reach, or `None` for anyone's.
+        knower: Option<String>,
+        source: String,
+        relation: String,
+        target: String,
+    },
+}
+
+impl ObjectiveTrigger {
+    /// Returns whether an event fires the trigger for a quest's assignee.
+    fn fired_by(&self, event: &AppraisalEvent, assignee: &str) -> bool {
+        match self {
+            ObjectiveTrigger::Event { pattern, actor } => {
+                let actor = actor
+                    .as_deref()
+                    .map(|actor| if actor == ASSIGNEE { assignee } else { actor });
+                kind_matches(pattern, &event.kind)
+                    && actor.is_none_or(|actor| event.actor.as_deref() == Some(actor))
+            }
+            ObjectiveTrigger::Fact { .. } => false,
+        }
+    }
+
+    /// Counts the distinct facts in the knowledge graphs of the managed NPCs (or the knower's
+    /// alone) that fire the trigger.
+    fn facts_known(&self, agents: &AgentManager) -> usize {
+        let ObjectiveTrigger::Fact { knower, source, relation, target } = self else {
+            return 0;
+        };
+        let part = |pattern: &str, value: &str| pattern == "*" || pattern == value;
+        let mut facts = HashSet::new();
+        let knowers: Box<dyn Iterator<Item = &NpcAgent>> = match knower {
+            Some(knower) => Box::new(agents.get_agent(knower).into_iter()),
+            None => Box::new(agents.agents()),
+        };
+        for agent in knowers {
+            for fact in candidate_facts(&agent.knowledge, source, target) {
+                let triple =
+                    (fact.source.as_str(), fact.relation_type.as_str(), fact.target.as_str());
+                if part(source, triple.0) && part(relation, triple.1) && part(target, triple.2) {
+                    facts.insert(triple);
+                }
+            }
+        }
+        facts.len()
+    }
+}
+
+/// Iterates over the relationships of a graph that can match a fact pattern, looking them up by
+/// source or target when the pattern names one instead of scanning the whole graph.
+fn candidate_facts<'a>(
+    graph: &'a KnowledgeGraph,
+    source: &'a str,
+    target: &'a str,
+) -> Box<dyn Iterator<Item = &'a Relationship> + 'a> {
+    if source != "*" {
+        Box::new(graph.outgoing(source))
+    } else if target != "*" {
+        Box::new(graph.incoming(target))
+    } else {
+        Box::new(graph.relationships())
+    }
+}
+
+/// Represents a step of a quest, such as slaying five wolves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Objective {
+    /// A unique ID for the objective within the quest.
+    pub id: String,
+    /// What is counted, as it follows the count in "3/5 wolves slain".
+    pub description: String,
+    pub trigger: ObjectiveTrigger,
+    /// How many times the trigger must fire.
+    pub required: usize,
+    /// How many times it has.
+    #[serde(default)]
+    pub progress: usize,
+}
+
+impl Objective {
+    /// Creates an objective advanced by events of a kind caused by the quest's assignee (see
+    /// `Objective::by` and `Objective::by_anyone` to count others).
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique ID for the objective within the quest.
+    /// * `description` - What is counted (e.g. "wolves slain").
+    /// * `pattern` - The event kinds that advance it.
+    /// * `required` - How many events are needed.
+    pub fn event(id: &str, description: &str, pattern: &str, required: usize) -> Self {
+        Objective {
+            id: id.to_string(),
+            description: description.to_string(),
+            trigger: ObjectiveTrigger::Event {
+                pattern: pattern.to_string(),
+                actor: Some(ASSIGNEE.to_string()),
+            },
+            required: required.max(1),
+            progress: 0,
+        }
+    }
+
+    /// Creates an objective advanced by facts the managed NPCs come to know.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique ID for the objective within the quest.
+    /// * `description` - What is counted (e.g. "herbs found").
+    /// * `fact` - The facts that advance it, as a triple where "*" matches anything.
+    /// * `required` - How many facts are needed.
+    pub fn fact(id: &str, description: &str, fact: (&str, &str, &str), required: usize) -> Self {
+        Objective {
+            id: id.to_string(),
+            description: description.to_string(),
+            trigger: ObjectiveTrigger::Fact {
+                knower: None,
+                source: fact.0.to_string(),
+                relation: fact.1.to_string(),
+                target: fact.2.to_string(),
+            },
+            required: required.max(1),
+            progress: 0,
+        }
+    }
+
+    /// Restricts the objective to events caused by an actor, or to facts reaching the knowledge
+    /// graph of a knower.
+    pub fn by(mut self, id: &str) -> Self {
+        match &mut self.trigger {
+            ObjectiveTrigger::Event { actor, .. } => *actor = Some(id.to_string()),
+            ObjectiveTrigger::Fact { knower, .. } => *knower = Some(id.to_string()),
+        }
+        self
+    }
+
+    /// Lets events caused by anyone advance the objective.
+    pub fn by_anyone(mut self) -> Self {
+        if let ObjectiveTrigger::Event { actor, .. } = &mut self.trigger {
+            *actor = None;
+        }
+        self
+    }
+
+    /// Returns whether the objective is done.
+    pub fn is_complete(&self) -> bool {
+        self.progress >= self.required
+    }
+
+    /// Describes the objective's progress.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::quest::Objective;
+    ///
+    /// let mut wolves = Objective::event("wolves", "wolves slain", "combat.kill.wolf", 5);
+    /// wolves.progress = 3;
+    /// assert_eq!(wolves.progress_text(), "3/5 wolves slain");
+    /// ```
+    pub fn progress_text(&self) -> String {
+        format!("{}/{} {}", self.progress, self.required, self.description)
+    }
+
+    /// Counts an event towards the objective, returning whether it advanced.
+    fn observe_event(&mut self, event: &AppraisalEvent, assignee: &str) -> bool {
+        if self.is_complete() || !self.trigger.fired_by(event, assignee) {
+            return false;
+        }
+        self.progress += 1;
+        true
+    }
+
+    /// Counts the facts the managed NPCs know towards the objective, returning whether it
+    /// advanced.
+    fn observe_knowledge(&mut self, agents: &AgentManager) -> bool {
+        let known = self.trigger.facts_known(agents).min(self.required);
+        if known <= self.progress {
+            return false;
+        }
+        self.progress = known;
+        true
+    }
+}
+
+/// Represents one way a quest can end, such as sparing or killing the bandit leader.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Resolution {
+    /// A unique ID for the resolution within the quest.
+    pub id: String,
+    /// What the assignee did, as it follows their name (e.g. "spared the bandit leader").
+    pub description: String,
+    /// The event or fact that resolves the quest this way once its objectives are done, if it is
+    /// not chosen by the game (see `QuestLog::resolve`).
+    #[serde(default)]
+    pub trigger: Option<ObjectiveTrigger>,
+    /// What happens when the quest is resolved this way, on top of its completion effects.
+    #[serde(default)]
+    pub effects: Vec<QuestEffect>,
+}
+
+impl Resolution {
+    /// Creates a new resolution without effects, chosen by the game.
+    pub fn new(id: &str, description: &str) -> Self {
+        Resolution {
+            id: id.to_string(),
+            description: description.to_string(),
+            trigger: None,
+            effects: Vec::new(),
+        }
+    }
+
+    /// Sets the event or fact that resolves the quest this way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::AgentManager;
+    /// use athena::appraisal::AppraisalEvent;
+    /// use athena::quest::{Objective, ObjectiveTrigger, Quest, QuestLog, Resolution};
+    ///
+    /// let event = |kind: &str| ObjectiveTrigger::Event { pattern: kind.to_string(), actor: None };
+    /// let quest = Quest::new("thief", "The Pilfered Purse", "")
+    ///     .with_objective(Objective::event("catch", "thieves caught", "catch.thief", 1))
+    ///     .with_resolution(Resolution::new("reported", "turned the thief in")
+    ///         .triggered_by(event("report.thief")))
+    ///     .with_resolution(Resolution::new("covered", "covered for the thief")
+    ///         .triggered_by(event("release.thief")));
+    /// let mut agents = AgentManager::new(1.0);
+    /// let mut log = QuestLog::new();
+    /// log.accept(quest, "alice", 0.0);
+    ///
+    /// // Letting the thief go before catching them resolves nothing.
+    /// let release = AppraisalEvent::new("release.thief", "").by("alice");
+    /// log.handle_event(&release, &mut agents);
+    /// log.handle_event(&AppraisalEvent::new("catch.thief", "").by("alice"), &mut agents);
+    /// assert_eq!(log.get_quest("thief").unwrap().resolution, None);
+    /// log.handle_event(&release, &mut agents);
+    /// assert_eq!(log.get_quest("thief").unwrap().resolution.as_deref(), Some("covered"));
+    /// ```
+    pub fn triggered_by(mut self, trigger: ObjectiveTrigger) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    /// Adds an effect.
+    pub fn with_effect(mut self, effect: QuestEffect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+}
+
+/// Represents where a quest stands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuestStatus {
+    #[default]
+    Active,
+    Completed,
+    /// A fail condition was met.
+    Failed,
+    /// The time limit ran out.
+    Expired,
+    /// The assignee gave up on the quest.
+    Abandoned,
+}
+
+impl QuestStatus {
+    /// Returns whether the quest ended without being completed.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, QuestStatus::Failed | QuestStatus::Expired | QuestStatus::Abandoned)
+    }
+}
+
+/// Represents the kind of task a quest sets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum QuestKind {
+    #[default]
+    General,
+    /// Seeing someone safely somewhere.
+    Escort,
+    /// Hunting down a dangerous creature or person.
+    Bounty,
+    /// Bringing something to someone.
+    Delivery,
+    /// Finding out what happened.
+    Investigation,
+    /// Helping someone out.
+    Favor,
+}
+
+/// Represents a quest.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quest {
+    /// A unique ID for the quest.
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    #[serde(default)]
+    pub kind: QuestKind,
+    /// Where the quest takes place, if anywhere in particular.
+    #[serde(default)]
+    pub location: Option<(f64, f64)>,
+    #[serde(default)]
+    pub clues: Vec<Clue>,
+    /// The NPC who gave the quest, if any.
+    #[serde(default)]
+    pub giver: Option<String>,
+    /// Who took the quest on, set when it is accepted into a `QuestLog`.
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// The steps that complete the quest once all are done.
+    #[serde(default)]
+    pub objectives: Vec<Objective>,
+    /// What happens when the quest is completed.
+    #[serde(default)]
+    pub on_complete: Vec<QuestEffect>,
+    /// The ways the quest can end. A quest with resolutions is only completed once one is
+    /// reached.
+    #[serde(default)]
+    pub resolutions: Vec<Resolution>,
+    /// The ID of the resolution the quest was completed with.
+    #[serde(default)]
+    pub resolution: Option<String>,
+    /// Events or facts that fail the quest, such as the death of someone to escort.
+    #[serde(default)]
+    pub fail_conditions: Vec<ObjectiveTrigger>,
+    /// How long the quest may take, in seconds of game time, or `None` for no limit.
+    #[serde(default)]
+    pub time_limit: Option<f64>,
+    /// The game time the quest was accepted at, in seconds.
+    #[serde(default)]
+    pub accepted_at: f64,
+    /// What happens when the quest fails, expires, or is abandoned. If empty, consequences for
+    /// the giver are generated (see `Quest::failure_effects`).
+    #[serde(default)]
+    pub on_fail: Vec<QuestEffect>,
+    #[serde(default)]
+    pub status: QuestStatus,
+}
+
+impl Quest {
+    /// Creates a new quest without clues.
+    pub fn new(id: &str, title: &str, description: &str) -> Self {
+        Quest {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            ..Quest::default()
+        }
+    }
+
+    /// Adds a clue.
+    pub fn with_clue(mut self, clue: Clue) -> Self {
+        self.clues.push(clue);
+        self
+    }
+
+    /// Sets the kind of task the quest sets.
+    pub fn with_kind(mut self, kind: QuestKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets where the quest takes place.
+    pub fn at(mut self, location: (f64, f64)) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Sets the NPC who gave the quest.
+    pub fn given_by(mut self, giver: &str) -> Self {
+        self.giver = Some(giver.to_string());
+        self
+    }
+
+    /// Adds an objective.
+    pub fn with_objective(mut self, objective: Objective) -> Self {
+        self.objectives.push(objective);
+        self
+    }
+
+    /// Adds an effect of completing the quest.
+    pub fn on_complete(mut self, effect: QuestEffect) -> Self {
+        self.on_complete.push(effect);
+        self
+    }
+
+    /// Adds a way the quest can end.
+    pub fn with_resolution(mut self, resolution: Resolution) -> Self {
+        self.resolutions.push(resolution);
+        self
+    }
+
+    /// Gets a resolution by its ID.
+    pub fn get_resolution(&self, id: &str) -> Option<&Resolution> {
+        self.resolutions.iter().find(|resolution| resolution.id == id)
+    }
+
+    /// Sets how long the quest may take, in seconds of game time.
+    pub fn with_time_limit(mut self, seconds: f64) -> Self {
+        self.time_limit = Some(seconds.max(0.0));
+        self
+    }
+
+    /// Adds a fail condition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::AgentManager;
+    /// use athena::appraisal::AppraisalEvent;
+    /// use athena::quest::{ObjectiveTrigger, Quest, QuestLog, QuestStatus};
+    ///
+    /// let mut agents = AgentManager::new(1.0);
+    /// let death = ObjectiveTrigger::Event { pattern: "death.merchant".to_string(), actor: None };
+    /// let quest = Quest::new("escort", "Safe Passage", "").fails_on(death);
+    /// let mut log = QuestLog::new();
+    /// log.accept(quest, "alice", 0.0);
+    ///
+    /// log.handle_event(&AppraisalEvent::new("death.merchant", "the merchant died"), &mut agents);
+    /// assert_eq!(log.get_quest("escort").unwrap().status, QuestStatus::Failed);
+    /// ```
+    pub fn fails_on(mut self, condition: ObjectiveTrigger) -> Self {
+        self.fail_conditions.push(condition);
+        self
+    }
+
+    /// Adds an effect of failing the quest, replacing the generated consequences.
+    pub fn on_fail(mut self, effect: QuestEffect) -> Self {
+        self.on_fail.push(effect);
+        self
+    }
+
+    /// Gets the game time the quest expires at, if it has a time limit.
+    pub fn get_deadline(&self) -> Option<f64> {
+        self.time_limit.map(|limit| self.accepted_at + limit)
+    }
+
+    /// Returns whether any objective has advanced.
+    pub fn has_progress(&self) -> bool {
+        self.objectives.iter().any(|objective| objective.progress > 0)
+    }
+
+    /// Gets the effects of the quest ending in failure: the authored `on_fail` effects, or else
+    /// the giver's reaction. A giver is saddened by a failure, let down by a quest left to run out
+    /// of time (and angered if it was ignored altogether), and angered by one given up on; each
+    /// costs the assignee some of the giver's affinity and trust.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - How the quest ended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::quest::{Quest, QuestEffect, QuestStatus};
+    ///
+    /// let quest = Quest::new("herbs", "Healing Herbs", "").given_by("healer");
+    /// let effects = quest.failure_effects(QuestStatus::Expired);
+    /// assert!(matches!(&effects[0], QuestEffect::Memory { emotion: Emotion::Anger, .. }));
+    /// assert!(Quest::new("herbs", "Healing Herbs", "").failure_effects(QuestStatus::Failed).is_empty());
+    /// ```
+    pub fn failure_effects(&self, status: QuestStatus) -> Vec<QuestEffect> {
+        if !self.on_fail.is_empty() {
+            return self.on_fail.clone();
+        }
+        let Some(giver) = &self.giver else {
+            return Vec::new();
+        };
+        let (emotion, intensity, affinity, trust, what) = match status {
+            QuestStatus::Failed => (Emotion::Sadness, 0.5, -0.1, -0.1, "failed"),
+            QuestStatus::Expired if self.has_progress() => {
+                (Emotion::Sadness, 0.4, -0.05, -0.15, "ran out of time on")
+            }
+            QuestStatus::Expired => (Emotion::Anger, 0.4, -0.15, -0.2, "ignored"),
+            QuestStatus::Abandoned => (Emotion::Anger, 0.5, -0.1, -0.25, "gave up on"),
+            QuestStatus::Active | QuestStatus::Completed => return Vec::new(),
+        };
+        let assignee = self.assignee.as_deref().unwrap_or("someone");
+        vec![
+            QuestEffect::Memory {
+                npc: giver.clone(),
+                emotion,
+                intensity,
+                description: format!("{} {} {}", assignee, what, self.title),
+            },
+            QuestEffect::Relationship {
+                npc: giver.clone(),
+                affinity,
+                trust,
+            },
+        ]
+    }
+
+    /// Gets an objective by its ID.
+    pub fn get_objective(&self, id: &str) -> Option<&Objective> {
+        self.objectives.iter().find(|objective| objective.id == id)
+    }
+
+    /// Returns whether every objective is done. A quest without objectives is only completed
+    /// by the game (see `QuestLog::complete`).
+    pub fn objectives_done(&self) -> bool {
+        !self.objectives.is_empty() && self.objectives.iter().all(Objective::is_complete)
+    }
+}
+
+/// Represents how an NPC came to know a clue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HintProvenance {
+    /// The NPC knows it, without saying how.
+    Known,
+    /// The NPC saw it.
+    Witnessed,
+    /// Someone told the NPC.
+    HeardFrom(String),
+}
+
+impl HintProvenance {
+    /// Reads the provenance of a fact from its `source` property.
+    fn of(relationship: &Relationship) -> Self {
+        let source = relationship.properties.get("source").map_or("", |source| source.as_str());
+        if source == "witnessed" {
+            HintProvenance::Witnessed
+        } else if let Some(informant) = source.strip_prefix("gossip:") {
+            HintProvenance::HeardFrom(informant.to_string())
+        } else {
+            HintProvenance::Known
+        }
+    }
+}
+
+/// Represents a hint an NPC can give about a quest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuestHint {
+    /// The index of the clue in the quest.
+    pub clue: usize,
+    /// The hint as the NPC would say it.
+    pub text: String,
+    pub provenance: HintProvenance,
+    pub confidence: f64,
+    /// Whether the hint gives the solution away.
+    pub solution: bool,
+}
+
+/// Names an informant by their relationship to an NPC (e.g. "my cousin Tam"), or by name alone
+/// if the NPC's knowledge graph does not relate them.
+fn informant_phrase(graph: &KnowledgeGraph, npc_id: &str, informant: &str) -> String {
+    let name = display_name(graph, informant);
+    let noun = graph
+        .outgoing(npc_id)
+        .filter(|relationship| relationship.target == informant)
+        .find_map(|relationship| {
+            let relation = relationship.relation_type.as_str();
+            KIN_NOUNS.iter().find(|(r, _)| *r == relation).map(|(_, noun)| noun.to_string())
+        });
+    match noun {
+        Some(noun) => format!("my {} {}", noun, name),
+        None => name,
+    }
+}
+
+/// Words that start a clue's statement only because it is a sentence of its own, lowercased
+/// when the statement is embedded in a hint.
+const SENTENCE_STARTS: [&str; 10] =
+    ["the", "a", "an", "they", "it", "he", "she", "there", "someone", "some"];
+
+/// Prepares a clue's statement to follow a lead-in such as "I saw that": trailing punctuation
+/// is dropped, and the first word is lowercased unless it may be a name.
+fn embed(statement: &str) -> String {
+    let statement = statement.trim().trim_end_matches(['.', '!', '?']);
+    let first = statement.split(' ').next().unwrap_or("");
+    if SENTENCE_STARTS.contains(&first.to_lowercase().as_str()) {
+        format!("{}{}", first.to_lowercase(), &statement[first.len()..])
+    } else {
+        statement.to_string()
+    }
+}
+
+/// Capitalizes the first letter of a sentence.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Gathers the hints a knowledge graph's owner can give about a quest.
+///
+/// # Arguments
+///
+/// * `graph` - The knowledge graph of the NPC giving hints.
+/// * `npc_id` - The ID of the NPC, used to name informants by their relationship to it.
+/// * `quest` - The quest hinted at.
+/// * `trust` - How much the NPC trusts the asker (between -1.0 and 1.0).
+/// * `policy` - Which facts are shared.
+///
+/// # Returns
+///
+/// A hint for each clue the NPC knows and is willing to share, in the quest's order.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use athena::knowledge_graph::{Entity, KnowledgeGraph, Relationship};
+/// use athena::knowledge_query::SharingPolicy;
+/// use athena::quest::{self, Clue, HintProvenance, Quest};
+///
+/// let quest = Quest::new("wolves", "The Wolf Problem", "Wolves are killing the sheep.")
+///     .with_clue(Clue::new("wolves", "headed_to", "old_mill",
+///                          "the wolves were heading to the old mill"))
+///     .with_clue(Clue::new("wolves", "led_by", "werewolf", "the pack is led by a werewolf")
+///         .solving());
+///
+/// let mut graph = KnowledgeGraph::new();
+/// let name = HashMap::from([("name".to_string(), "Tam".to_string())]);
+/// graph.add_entity(Entity::new("tam", name));
+/// graph.add_relationship(Relationship::new("oda", "tam", "cousin", HashMap::new()));
+/// let heard = HashMap::from([("source".to_string(), "gossip:tam".to_string())]);
+/// graph.add_relationship(Relationship::new("wolves", "old_mill", "headed_to", heard));
+///
+/// let hints = quest::hints(&graph, "oda", &quest, 0.0, &SharingPolicy::default());
+/// // Oda knows nothing of the werewolf, so cannot give the solution away.
+/// assert_eq!(hints.len(), 1);
+/// assert_eq!(hints[0].provenance, HintProvenance::HeardFrom("tam".to_string()));
+/// assert_eq!(
+///     hints[0].text,
+///     "My cousin Tam told me that the wolves were heading to the old mill."
+/// );
+///
+/// // Authored statements are sentences of their own, but read on within a hint.
+/// let relic = Quest::new("relic", "The Lost Relic", "")
+///     .with_clue(Clue::new("relic", "hidden_in", "crypt", "The relic is in the crypt."));
+/// let rumor = HashMap::from([("confidence".to_string(), "0.5".to_string())]);
+/// graph.add_relationship(Relationship::new("relic", "crypt", "hidden_in", rumor));
+/// let hints = quest::hints(&graph, "oda", &relic, 0.0, &SharingPolicy::default());
+/// assert_eq!(hints[0].text, "I'm not sure, but the relic is in the crypt.");
+/// ```
+pub fn hints(
+    graph: &KnowledgeGraph,
+    npc_id: &str,
+    quest: &Quest,
+    trust: f64,
+    policy: &SharingPolicy,
+) -> Vec<QuestHint> {
+    let mut hints = Vec::new();
+    for (index, clue) in quest.clues.iter().enumerate() {
+        let Some(fact) = clue.fact_in(graph) else {
+            continue;
+        };
+        let Disclosure::Shared { confidence, hearsay } = policy.disclosure(fact, trust) else {
+            continue;
+        };
+        let statement = embed(&clue.phrase(graph));
+        let provenance = HintProvenance::of(fact);
+        let mut text = match &provenance {
+            HintProvenance::Known => statement,
+            HintProvenance::Witnessed => format!("I saw with my own eyes that {}", statement),
+            HintProvenance::HeardFrom(informant) => {
+                format!("{} told me that {}", informant_phrase(graph, npc_id, informant), statement)
+            }
+        };
+        if hearsay {
+            text = format!("I'm not sure, but {}", text);
+        }
+        hints.push(QuestHint {
+            clue: index,
+            text: format!("{}.", capitalize(&text)),
+            provenance,
+            confidence,
+            solution: clue.solution,
+        });
+    }
+    hints
+}
+
+/// Builds the prompt directive asking the model to hint at a quest, or `None` if the NPC knows
+/// no clue it is willing to share.
+///
+/// # Examples
+///
+/// ```
+/// use athena::quest::{self, HintProvenance, QuestHint};
+///
+/// let hint = QuestHint {
+///     clue: 0,
+///     text: "I saw with my own eyes that the relic is in the crypt.".to_string(),
+///     provenance: HintProvenance::Witnessed,
+///     confidence: 1.0,
+///     solution: true,
+/// };
+/// let directive = quest::hint_directive(&[hint]).unwrap();
+/// assert!(directive.ends_with("- I saw with my own eyes that the relic is in the crypt."));
+/// assert!(quest::hint_directive(&[]).is_none());
+/// ```
+pub fn hint_directive(hints: &[QuestHint]) -> Option<String> {
+    if hints.is_empty() {
+        return None;
+    }
+    let mut directive = "Drop a hint, in your own words and voice, from what you know below. \
+        Say how you know it, and add nothing you do not know:"
+        .to_string();
+    for hint in hints {
+        directive.push_str(&format!("\n- {}", hint.text));
+    }
+    Some(directive)
+}
+
+/// Stands for a quest's assignee in the facts of effects, which are written before anyone takes
+/// the quest on.
+pub const ASSIGNEE: &str = "{assignee}";
+
+/// Stands for every managed NPC in the effects of a quest.
+pub const EVERYONE: &str = "*";
+
+/// The relation type of the facts recording how a quest was resolved, from the quest to the
+/// resolution.
+pub(crate) const RESOLVED_AS: &str = "resolved_as";
+
+/// Represents something the game grants the assignee of a quest, such as gold or an item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reward {
+    pub item: String,
+    pub amount: f64,
+}
+
+/// Represents a consequence of a quest.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QuestEffect {
+    /// The game grants the assignee a reward; quest logs pass it on without granting it.
+    Reward(Reward),
+    /// An NPC feels an emotion about the quest, and remembers the quest with it (under the
+    /// trigger `quest:<id>`).
+    Memory {
+        npc: String,
+        emotion: Emotion,
+        intensity: f64,
+        description: String,
+    },
+    /// An NPC's affinity and trust towards the assignee change.
+    Relationship {
+        npc: String,
+        affinity: f64,
+        trust: f64,
+    },
+    /// An NPC remembers a deed of the assignee.
+    Deed {
+        npc: String,
+        category: DeedCategory,
+        magnitude: f64,
+        description: String,
+    },
+    /// An NPC learns a fact; `ASSIGNEE` in it stands for the assignee.
+    Fact {
+        npc: String,
+        source: String,
+        relation: String,
+        target: String,
+    },
+}
+
+impl QuestEffect {
+    /// Creates a reward effect.
+    pub fn reward(item: &str, amount: f64) -> Self {
+        QuestEffect::Reward(Reward {
+            item: item.to_string(),
+            amount,
+        })
+    }
+
+    /// Applies the effect to the NPC it concerns, or to every managed NPC but the assignee if
+    /// it concerns `EVERYONE` (e.g. to spread word of a deed).
+    ///
+    /// # Arguments
+    ///
+    /// * `quest_id` - The ID of the quest the effect belongs to.
+    /// * `assignee` - The ID of who took the quest on.
+    /// * `agents` - The managed NPCs.
+    ///
+    /// # Returns
+    ///
+    /// Whether the effect was applied; rewards and effects on NPCs that are not managed are not.
+    pub fn apply(&self, quest_id: &str, assignee: &str, agents: &mut AgentManager) -> bool {
+        let Some(npc) = self.get_npc() else {
+            return false;
+        };
+        let ids = if npc == EVERYONE {
+            agents
+                .agents()
+                .map(|agent| agent.id.to_string())
+                .filter(|id| id != assignee)
+                .collect()
+        } else {
+            vec![npc.to_string()]
+        };
+        let mut applied = false;
+        for id in ids {
+            if let Some(agent) = agents.get_agent_mut(&id) {
+                self.apply_to(agent, quest_id, assignee);
+                applied = true;
+            }
+        }
+        applied
+    }
+
+    /// Gets the ID of the NPC the effect concerns, or `EVERYONE`; `None` for rewards.
+    pub fn get_npc(&self) -> Option<&str> {
+        match self {
+            QuestEffect::Reward(_) => None,
+            QuestEffect::Memory { npc, .. }
+            | QuestEffect::Relationship { npc, .. }
+            | QuestEffect::Deed { npc, .. }
+            | QuestEffect::Fact { npc, .. } => Some(npc),
+        }
+    }
+
+    /// Applies the effect to an NPC.
+    fn apply_to(&self, agent: &mut NpcAgent, quest_id: &str, assignee: &str) {
+        match self {
+            QuestEffect::Reward(_) => {}
+            QuestEffect::Memory { emotion, intensity, description, .. } => {
+                agent.appraise(emotion.clone(), *intensity, description);
+                agent.emotions.record_memory(&format!("quest:{}", quest_id), emotion.clone());
+            }
+            QuestEffect::Relationship { affinity, trust, .. } => {
+                let context = agent.interlocutor(assignee);
+                context.adjust_affinity(*affinity);
+                context.adjust_trust(*trust);
+            }
+            QuestEffect::Deed { category, magnitude, description, .. } => {
+                agent.record_deed(assignee, *category, *magnitude, description);
+            }
+            QuestEffect::Fact { source, relation, target, .. } => {
+                let source = if source == ASSIGNEE { assignee } else { source };
+                let target = if target == ASSIGNEE { assignee } else { target };
+                agent.learn_fact(source, target, relation);
+            }
+        }
+    }
+}
+
+/// Represents a change to a quest reported by a `QuestLog`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum QuestUpdate {
+    /// An objective advanced.
+    Progressed {
+        quest: String,
+        objective: String,
+        /// The objective's progress in words (e.g. "3/5 wolves slain").
+        text: String,
+    },
+    /// A quest was completed and its effects applied.
+    Completed {
+        quest: String,
+        /// The ID of the resolution the quest was completed with, if any.
+        resolution: Option<String>,
+        /// The rewards the game should grant the assignee.
+        rewards: Vec<Reward>,
+    },
+    /// A quest failed, expired, or was abandoned, and its failure effects were applied.
+    Failed { quest: String, status: QuestStatus },
+}
+
+/// Tracks the quests taken on, advancing their objectives as events happen.
+///
+/// The game feeds the log the events of its world and the changes to NPCs' knowledge graphs;
+/// the log updates each active quest's objectives, and when a quest's objectives are all done,
+/// completes it and applies its effects to the NPCs concerned. Quests also end in failure, with
+/// consequences for the NPCs, when a fail condition is met, when their time limit runs out (see
+/// `QuestLog::update`), or when they are abandoned.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuestLog {
+    /// The quests, keyed by ID.
+    quests: BTreeMap<String, Quest>,
+}
+
+impl QuestLog {
+    /// Creates an empty quest log.
+    pub fn new() -> Self {
+        QuestLog::default()
+    }
+
+    /// Takes a quest on, replacing any quest with the same ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `quest` - The quest.
+    /// * `assignee` - Who takes it on (usually the player).
+    /// * `game_time` - The current game time in seconds, from which the time limit runs.
+    pub fn accept(&mut self, mut quest: Quest, assignee: &str, game_time: f64) {
+        quest.assignee = Some(assignee.to_string());
+        quest.accepted_at = game_time;
+        quest.status = QuestStatus::Active;
+        self.quests.insert(quest.id.clone(), quest);
+    }
+
+    /// Gets a quest by its ID.
+    pub fn get_quest(&self, id: &str) -> Option<&Quest> {
+        self.quests.get(id)
+    }
+
+    /// Iterates over the quests, in ID order.
+    pub fn quests(&self) -> impl Iterator<Item = &Quest> {
+        self.quests.values()
+    }
+
+    /// Advances the active quests' objectives with an event of the game world.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - What happened.
+    /// * `agents` - The managed NPCs, affected by the quests it completes.
+    ///
+    /// # Returns
+    ///
+    /// The objectives advanced and quests completed, in that order for each quest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::appraisal::AppraisalEvent;
+    /// use athena::emotional_response::Emotion;
+    /// use athena::quest::{Objective, Quest, QuestEffect, QuestLog, QuestStatus, QuestUpdate};
+    ///
+    /// let mut agents = AgentManager::new(1.0);
+    /// agents.add_agent(NpcAgent::new("oda", vec![]));
+    /// let quest = Quest::new("wolves", "The Wolf Problem", "Wolves are killing the sheep.")
+    ///     .given_by("oda")
+    ///     .with_objective(Objective::event("wolves", "wolves slain", "combat.kill.wolf", 5))
+    ///     .on_complete(QuestEffect::reward("gold", 50.0))
+    ///     .on_complete(QuestEffect::Relationship {
+    ///         npc: "oda".to_string(),
+    ///         affinity: 0.3,
+    ///         trust: 0.2,
+    ///     })
+    ///     .on_complete(QuestEffect::Memory {
+    ///         npc: "oda".to_string(),
+    ///         emotion: Emotion::Joy,
+    ///         intensity: 0.8,
+    ///         description: "the wolves are gone".to_string(),
+    ///     });
+    ///
+    /// let mut log = QuestLog::new();
+    /// log.accept(quest, "alice", 0.0);
+    /// // Wolves slain by others do not count towards alice's quest.
+    /// let other = AppraisalEvent::new("combat.kill.wolf", "bob slew a wolf").by("bob");
+    /// assert!(log.handle_event(&other, &mut agents).is_empty());
+    /// let kill = AppraisalEvent::new("combat.kill.wolf", "alice slew a wolf").by("alice");
+    /// for _ in 0..2 {
+    ///     log.handle_event(&kill, &mut agents);
+    /// }
+    /// let updates = log.handle_event(&kill, &mut agents);
+    /// assert!(matches!(&updates[0], QuestUpdate::Progressed { text, .. } if text == "3/5 wolves slain"));
+    ///
+    /// log.handle_event(&kill, &mut agents);
+    /// let updates = log.handle_event(&kill, &mut agents);
+    /// assert!(matches!(&updates[1], QuestUpdate::Completed { rewards, .. } if rewards[0].amount == 50.0));
+    /// assert_eq!(log.get_quest("wolves").unwrap().status, QuestStatus::Completed);
+    ///
+    /// let oda = agents.get_agent("oda").unwrap();
+    /// assert_eq!(oda.get_interlocutor("alice").unwrap().trust, 0.2);
+    /// assert_eq!(oda.emotions.get_memory("quest:wolves"), Some(&Emotion::Joy));
+    ///
+    /// // Completed quests no longer count events.
+    /// assert!(log.handle_event(&kill, &mut agents).is_empty());
+    /// ```
+    pub fn handle_event(
+        &mut self,
+        event: &AppraisalEvent,
+        agents: &mut AgentManager,
+    ) -> Vec<QuestUpdate> {
+        self.advance(
+            agents,
+            |trigger, assignee, _| trigger.fired_by(event, assignee),
+            |objective, assignee, _| objective.observe_event(event, assignee),
+        )
+    }
+
+    /// Advances the active quests' objectives with what the managed NPCs know, however they
+    /// came to know it (witnessing, gossip, quest effects, ...). `QuestLog::update` does this
+    /// too.
+    ///
+    /// # Arguments
+    ///
+    /// * `agents` - The managed NPCs, whose knowledge graphs are checked and who are affected by
+    ///   the quests completed.
+    ///
+    /// # Returns
+    ///
+    /// The objectives advanced and quests completed, in that order for each quest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::knowledge_graph::Relationship;
+    /// use athena::quest::{Objective, Quest, QuestLog, QuestStatus};
+    ///
+    /// let mut agents = AgentManager::new(1.0);
+    /// agents.add_agent(NpcAgent::new("captain", vec![]));
+    /// agents.add_agent(NpcAgent::new("innkeeper", vec![]));
+    /// let report = Objective::fact("report", "reports made", ("bandits", "camp_at", "*"), 1)
+    ///     .by("captain");
+    /// let mut log = QuestLog::new();
+    /// log.accept(Quest::new("bandits", "Bandit Trouble", "").with_objective(report), "alice", 0.0);
+    ///
+    /// let fact = Relationship::new("bandits", "ridge", "camp_at", HashMap::new());
+    /// agents.get_agent_mut("innkeeper").unwrap().knowledge.add_relationship(fact.clone());
+    /// // Only the captain's knowledge counts.
+    /// assert!(log.check_knowledge(&mut agents).is_empty());
+    /// agents.get_agent_mut("captain").unwrap().knowledge.add_relationship(fact);
+    /// assert_eq!(log.check_knowledge(&mut agents).len(), 2);
+    /// assert_eq!(log.get_quest("bandits").unwrap().status, QuestStatus::Completed);
+    /// ```
+    pub fn check_knowledge(&mut self, agents: &mut AgentManager) -> Vec<QuestUpdate> {
+        self.advance(
+            agents,
+            |trigger, _, agents| trigger.facts_known(agents) > 0,
+            |objective, _, agents| objective.observe_knowledge(agents),
+        )
+    }
+
+    /// Completes an active quest regardless of its objectives, e.g. one the game tracks itself,
+    /// applying its effects.
+    ///
+    /// # Returns
+    ///
+    /// The update, or `None` if there is no such active quest.
+    pub fn complete(&mut self, id: &str, agents: &mut AgentManager) -> Option<QuestUpdate> {
+        let quest = self.quests.get_mut(id)?;
+        if quest.status != QuestStatus::Active {
+            return None;
+        }
+        Some(finish(quest, None, agents))
+    }
+
+    /// Fails the active quests whose time limit has run out, applying their failure effects, and
+    /// advances the others with what the managed NPCs know (see `QuestLog::check_knowledge`).
+    /// Call it every frame or so.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_time` - The current game time in seconds.
+    /// * `agents` - The managed NPCs.
+    ///
+    /// # Returns
+    ///
+    /// An update for each quest that expired, then those of the knowledge check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::quest::{Objective, Quest, QuestLog, QuestStatus, QuestUpdate};
+    ///
+    /// let mut agents = AgentManager::new(1.0);
+    /// agents.add_agent(NpcAgent::new("healer", vec![]));
+    /// let quest = Quest::new("herbs", "Healing Herbs", "The healer needs herbs.")
+    ///     .given_by("healer")
+    ///     .with_objective(Objective::event("herbs", "herbs picked", "gather.herb", 3))
+    ///     .with_time_limit(3600.0);
+    /// let mut log = QuestLog::new();
+    /// log.accept(quest, "alice", 100.0);
+    ///
+    /// assert!(log.update(3000.0, &mut agents).is_empty());
+    /// let updates = log.update(3700.0, &mut agents);
+    /// assert_eq!(updates[0], QuestUpdate::Failed {
+    ///     quest: "herbs".to_string(),
+    ///     status: QuestStatus::Expired,
+    /// });
+    ///
+    /// // The healer takes being ignored badly.
+    /// let healer = agents.get_agent("healer").unwrap();
+    /// assert!(healer.get_interlocutor("alice").unwrap().trust < 0.0);
+    /// assert!(healer.emotions.get_memory("quest:herbs").is_some());
+    /// ```
+    pub fn update(&mut self, game_time: f64, agents: &mut AgentManager) -> Vec<QuestUpdate> {
+        let mut updates = Vec::new();
+        for quest in self.quests.values_mut() {
+            let overdue = quest.get_deadline().is_some_and(|deadline| game_time >= deadline);
+            if quest.status == QuestStatus::Active && overdue {
+                updates.push(end_in_failure(quest, QuestStatus::Expired, agents));
+            }
+        }
+        updates.extend(self.check_knowledge(agents));
+        updates
+    }
+
+    /// Gives up on an active quest, applying its failure effects.
+    ///
+    /// # Returns
+    ///
+    /// The update, or `None` if there is no such active quest.
+    pub fn abandon(&mut self, id: &str, agents: &mut AgentManager) -> Option<QuestUpdate> {
+        let quest = self.quests.get_mut(id)?;
+        if quest.status != QuestStatus::Active {
+            return None;
+        }
+        Some(end_in_failure(quest, QuestStatus::Abandoned, agents))
+    }
+
+    /// Fails an active quest regardless of its fail conditions, e.g. on a condition the game
+    /// checks itself, applying its failure effects.
+    ///
+    /// # Returns
+    ///
+    /// The update, or `None` if there is no such active quest.
+    pub fn fail(&mut self, id: &str, agents: &mut AgentManager) -> Option<QuestUpdate> {
+        let quest = self.quests.get_mut(id)?;
+        if quest.status != QuestStatus::Active {
+            return None;
+        }
+        Some(end_in_failure(quest, QuestStatus::Failed, agents))
+    }
+
+    /// Completes an active quest with one of its resolutions, chosen by the game (e.g. from a
+    /// dialogue choice), applying its completion effects and the resolution's. The quest's
+    /// objectives need not be done.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the quest.
+    /// * `resolution` - The ID of the resolution.
+    /// * `agents` - The managed NPCs.
+    ///
+    /// # Returns
+    ///
+    /// The update, or `None` if there is no such active quest or resolution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::emotional_response::Emotion;
+    /// use athena::quest::{self, Quest, QuestEffect, QuestLog, Resolution, ASSIGNEE, EVERYONE};
+    /// use athena::reputation::DeedCategory;
+    ///
+    /// let spare = Resolution::new("spared", "spared the bandit leader")
+    ///     .with_effect(QuestEffect::Deed {
+    ///         npc: EVERYONE.to_string(),
+    ///         category: DeedCategory::Generosity,
+    ///         magnitude: 1.0,
+    ///         description: "spared the bandit leader".to_string(),
+    ///     })
+    ///     .with_effect(QuestEffect::Memory {
+    ///         npc: "captain".to_string(),
+    ///         emotion: Emotion::Anger,
+    ///         intensity: 0.6,
+    ///         description: "the bandit leader walks free".to_string(),
+    ///     });
+    /// let kill = Resolution::new("killed", "killed the bandit leader").with_effect(QuestEffect::Fact {
+    ///     npc: "captain".to_string(),
+    ///     source: ASSIGNEE.to_string(),
+    ///     relation: "killed".to_string(),
+    ///     target: "bandit_leader".to_string(),
+    /// });
+    /// let quest = Quest::new("bandits", "Bandit Trouble", "")
+    ///     .given_by("captain")
+    ///     .with_resolution(spare)
+    ///     .with_resolution(kill);
+    ///
+    /// let mut agents = AgentManager::new(1.0);
+    /// agents.add_agent(NpcAgent::new("captain", vec![]));
+    /// agents.add_agent(NpcAgent::new("innkeeper", vec![]));
+    /// let mut log = QuestLog::new();
+    /// log.accept(quest, "alice", 0.0);
+    /// log.resolve("bandits", "spared", &mut agents).unwrap();
+    /// assert_eq!(log.get_quest("bandits").unwrap().resolution.as_deref(), Some("spared"));
+    ///
+    /// // Word spreads, and everyone can bring it up.
+    /// let innkeeper = agents.get_agent("innkeeper").unwrap();
+    /// assert!(innkeeper.disposition_towards("alice").warmth > 0.0);
+    /// let outcomes = innkeeper.quest_outcomes();
+    /// assert_eq!(outcomes[0].resolution, "spared");
+    /// assert!(innkeeper.knowledge.get_entity("bandits:spared").is_some());
+    /// let directive = quest::outcome_directive(&innkeeper.knowledge, &outcomes).unwrap();
+    /// assert!(directive.ends_with("- alice spared the bandit leader (Bandit Trouble)."));
+    /// ```
+    pub fn resolve(
+        &mut self,
+        id: &str,
+        resolution: &str,
+        agents: &mut AgentManager,
+    ) -> Option<QuestUpdate> {
+        let quest = self.quests.get_mut(id)?;
+        let index = quest.resolutions.iter().position(|r| r.id == resolution)?;
+        if quest.status != QuestStatus::Active {
+            return None;
+        }
+        Some(finish(quest, Some(index), agents))
+    }
+
+    /// Advances the objectives of the active quests with an observation, completing the quests
+    /// whose objectives are all done (and, for quests with resolutions, resolving those whose
+    /// trigger the observation fires). Quests whose fail conditions the observation meets fail
+    /// instead.
+    fn advance(
+        &mut self,
+        agents: &mut AgentManager,
+        fires: impl Fn(&ObjectiveTrigger, &str, &AgentManager) -> bool,
+        mut observe: impl FnMut(&mut Objective, &str, &AgentManager) -> bool,
+    ) -> Vec<QuestUpdate> {
+        let mut updates = Vec::new();
+        for quest in self.quests.values_mut() {
+            if quest.status != QuestStatus::Active {
+                continue;
+            }
+            let assignee = quest.assignee.clone().unwrap_or_default();
+            if quest.fail_conditions.iter().any(|trigger| fires(trigger, &assignee, agents)) {
+                updates.push(end_in_failure(quest, QuestStatus::Failed, agents));
+                continue;
+            }
+            for objective in &mut quest.objectives {
+                if observe(objective, &assignee, agents) {
+                    updates.push(QuestUpdate::Progressed {
+                        quest: quest.id.clone(),
+                        objective: objective.id.clone(),
+                        text: objective.progress_text(),
+                    });
+                }
+            }
+            if quest.resolutions.is_empty() {
+                if quest.objectives_done() {
+                    updates.push(finish(quest, None, agents));
+                }
+            } else if quest.objectives.iter().all(Objective::is_complete) {
+                let reached = quest.resolutions.iter().position(|resolution| {
+                    let trigger = resolution.trigger.as_ref();
+                    trigger.is_some_and(|trigger| fires(trigger, &assignee, agents))
+                });
+                if let Some(index) = reached {
+                    updates.push(finish(quest, Some(index), agents));
+                }
+            }
+        }
+        updates
+    }
+}
+
+/// Marks a quest completed, with the resolution at an index if any, and applies its effects.
+/// The giver and the NPCs the resolution's effects concern learn how the quest was resolved.
+fn finish(quest: &mut Quest, resolution: Option<usize>, agents: &mut AgentManager) -> QuestUpdate {
+    quest.status = QuestStatus::Completed;
+    let assignee = quest.assignee.clone().unwrap_or_default();
+    let resolution = resolution.and_then(|index| quest.resolutions.get(index));
+    let resolution_effects = resolution.map_or(&[][..], |resolution| &resolution.effects);
+    let mut rewards = Vec::new();
+    for effect in quest.on_complete.iter().chain(resolution_effects) {
+        match effect {
+            QuestEffect::Reward(reward) => rewards.push(reward.clone()),
+            effect => {
+                effect.apply(&quest.id, &assignee, agents);
+            }
+        }
+    }
+    if let Some(resolution) = resolution {
+        let mut witnesses: Vec<String> = quest.giver.iter().cloned().collect();
+        for npc in resolution.effects.iter().filter_map(QuestEffect::get_npc) {
+            if npc == EVERYONE {
+                witnesses.extend(agents.agents().map(|agent| agent.id.to_string()));
+            } else {
+                witnesses.push(npc.to_string());
+            }
+        }
+        witnesses.sort();
+        witnesses.dedup();
+        for id in witnesses.iter().filter(|id| **id != assignee) {
+            if let Some(agent) = agents.get_agent_mut(id) {
+                record_outcome(&mut agent.knowledge, quest, resolution, &assignee);
+            }
+        }
+    }
+    quest.resolution = resolution.map(|resolution| resolution.id.clone());
+    QuestUpdate::Completed {
+        quest: quest.id.clone(),
+        resolution: quest.resolution.clone(),
+        rewards,
+    }
+}
+
+/// Records how a quest was resolved in a knowledge graph, as a `resolved_as` fact from the quest
+/// to the resolution. The resolution's entity is named `<quest>:<resolution>`, so resolutions of
+/// different quests sharing an ID (e.g. "spared") stay apart.
+fn record_outcome(
+    graph: &mut KnowledgeGraph,
+    quest: &Quest,
+    resolution: &Resolution,
+    assignee: &str,
+) {
+    let outcome = format!("{}:{}", quest.id, resolution.id);
+    for (id, name) in [(&quest.id, &quest.title), (&outcome, &resolution.description)] {
+        if graph.get_entity(id).is_none() {
+            let properties = HashMap::from([("name".to_string(), name.to_string())]);
+            graph.add_entity(Entity::new(id, properties));
+        }
+    }
+    let properties = HashMap::from([
+        ("assignee".to_string(), assignee.to_string()),
+        ("description".to_string(), resolution.description.clone()),
+    ]);
+    graph.add_relationship(Relationship::new(&quest.id, &outcome, RESOLVED_AS, properties));
+}
+
+/// Ends a quest in failure and applies its failure effects.
+fn end_in_failure(
+    quest: &mut Quest,
+    status: QuestStatus,
+    agents: &mut AgentManager,
+) -> QuestUpdate {
+    quest.status = status;
+    let assignee = quest.assignee.clone().unwrap_or_default();
+    for effect in quest.failure_effects(status) {
+        effect.apply(&quest.id, &assignee, agents);
+    }
+    QuestUpdate::Failed {
+        quest: quest.id.clone(),
+        status,
+    }
+}
+
+/// Represents how a quest was resolved, as an NPC knows it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuestOutcome {
+    /// The ID of the quest.
+    pub quest: String,
+    /// The ID of the resolution.
+    pub resolution: String,
+    /// The ID of who resolved the quest.
+    pub assignee: String,
+    /// What they did (e.g. "spared the bandit leader").
+    pub description: String,
+}
+
+/// Gathers the quest outcomes recorded in a knowledge graph, so dialogue can bring up how past
+/// quests ended.
+pub fn outcomes(graph: &KnowledgeGraph) -> Vec<QuestOutcome> {
+    graph
+        .relationships()
+        .filter(|relationship| relationship.relation_type == RESOLVED_AS)
+        .map(|relationship| {
+            let property = |key: &str| {
+                relationship.properties.get(key).cloned().unwrap_or_default()
+            };
+            let quest = relationship.source.as_str();
+            let outcome = relationship.target.as_str();
+            let resolution = outcome.strip_prefix(&format!("{}:", quest)).unwrap_or(outcome);
+            QuestOutcome {
+                quest: quest.to_string(),
+                resolution: resolution.to_string(),
+                assignee: property("assignee"),
+                description: property("description"),
+            }
+        })
+        .collect()
+}
+
+/// Builds the prompt directive reminding an NPC how quests it knows of ended, or `None` if it
+/// knows of none.
+///
+/// # Arguments
+///
+/// * `graph` - The knowledge graph of the NPC, used to name quests and assignees.
+/// * `outcomes` - The outcomes to mention.
+pub fn outcome_directive(graph: &KnowledgeGraph, outcomes: &[QuestOutcome]) -> Option<String> {
+    if outcomes.is_empty() {
+        return None;
+    }
+    let mut directive =
+        "You know how these quests ended, and may bring them up when it fits:".to_string();
+    for outcome in outcomes {
+        directive.push_str(&format!(
+            "\n- {} {} ({}).",
+            display_name(graph, &outcome.assignee),
+            outcome.description,
+            display_name(graph, &outcome.quest)
+        ));
+    }
+    Some(directive)
+}
+
+/// Has an NPC hint at a quest in character through a dialogue pipeline.
+///
+/// The hints' directive is added to the prompt context, so the pipeline's prompt stage must
+/// render it (a `PromptStage` does).
+///
+/// # Arguments
+///
+/// * `pipeline` - The pipeline generating the NPC's lines.
+/// * `agent` - The NPC asked.
+/// * `asker` - The ID of who asked.
+/// * `quest` - The quest hinted at.
+/// * `policy` - Which facts are shared.
+///
+/// # Returns
+///
+/// * `Result<Option<String>, StageError>` - The NPC's line, `None` if it knows no clue it is
+///   willing to share, or the error of the stage or request that failed.
+#[cfg(feature = "network")]
+pub async fn generate_hint(
+    pipeline: &mut Pipeline,
+    agent: &NpcAgent,
+    asker: &str,
+    quest: &Quest,
+    policy: &SharingPolicy,
+) -> Result<Option<String>, StageError> {
+    let hints = agent.quest_hints(asker, quest, policy);
+    let Some(directive) = hint_directive(&hints) else {
+        return Ok(None);
+    };
+    let input = format!("Do you know anything that could help with {}?", quest.title);
+    let mut state = PipelineState::new(RequestType::Conversation, &input);
+    state.context.directives.push(directive);
+    state.metadata.insert("npc_id".to_string(), agent.id.to_string());
+    Ok(Some(pipeline.run_state(state).await?.reply))
+}
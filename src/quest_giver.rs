@@ -0,0 +1,233 @@
+//! # Quest Giver Module
+//!
+//! This module picks who hands out a generated quest. Every managed NPC is scored on four
+//! grounds, and the best fit becomes the quest's giver:
+//!
+//! - Knowledge: how many of the quest's clues the NPC knows, since a giver should be able to
+//!   say something about the task.
+//! - Relationship: how much the NPC likes and trusts the player.
+//! - Proximity: how close the NPC is to where the quest takes place.
+//! - Personality: how well the kind of quest suits the NPC. Conscientious NPCs give escort
+//!   quests, bold ones bounties, curious ones investigations, patient ones deliveries, and
+//!   agreeable ones favors.
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{distance, AgentManager, NpcAgent};
+use crate::personality::Trait;
+use crate::quest::{Quest, QuestKind};
+
+/// Represents how well an NPC fits as the giver of a quest. Every score is between 0.0 and 1.0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GiverScore {
+    /// The ID of the NPC.
+    pub npc: String,
+    /// The share of the quest's clues the NPC knows, or 0.0 if the quest has none.
+    pub knowledge: f64,
+    /// How much the NPC likes and trusts the player; 0.5 if they have not met.
+    pub relationship: f64,
+    /// How close the NPC is to the quest's location; 0.5 if the quest has none.
+    pub proximity: f64,
+    /// How well the kind of quest suits the NPC's personality.
+    pub personality: f64,
+    /// The weighted sum of the scores.
+    pub total: f64,
+}
+
+/// Configures how quest givers are picked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GiverSelector {
+    pub knowledge_weight: f64,
+    pub relationship_weight: f64,
+    pub proximity_weight: f64,
+    pub personality_weight: f64,
+    /// The distance from the quest's location at which proximity reaches 0.0.
+    pub max_distance: f64,
+    /// NPCs who like and trust the player less than this (between 0.0 and 1.0, see
+    /// `GiverScore::relationship`) are never picked.
+    pub min_relationship: f64,
+    /// Whether NPCs the quest is about (named in its clues) are left out, so that a quest
+    /// about a missing smith is not handed out by the smith.
+    pub exclude_subjects: bool,
+}
+
+impl Default for GiverSelector {
+    fn default() -> Self {
+        GiverSelector {
+            knowledge_weight: 0.4,
+            relationship_weight: 0.25,
+            proximity_weight: 0.15,
+            personality_weight: 0.2,
+            max_distance: 500.0,
+            min_relationship: 0.2,
+            exclude_subjects: true,
+        }
+    }
+}
+
+impl GiverSelector {
+    /// Creates a selector with the default weights.
+    pub fn new() -> Self {
+        GiverSelector::default()
+    }
+
+    /// Scores an NPC as the giver of a quest.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - The NPC.
+    /// * `quest` - The quest to hand out.
+    /// * `player` - The ID of the player the quest is for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::quest::{Quest, QuestKind};
+    /// use athena::quest_giver::GiverSelector;
+    ///
+    /// let bounty = Quest::new("bandits", "Bandit Trouble", "").with_kind(QuestKind::Bounty);
+    /// let mut captain = NpcAgent::new("captain", vec![]);
+    /// captain.personality.set_extraversion(0.9);
+    /// captain.personality.set_neuroticism(0.1);
+    /// let mut clerk = NpcAgent::new("clerk", vec![]);
+    /// clerk.personality.set_neuroticism(0.9);
+    ///
+    /// let selector = GiverSelector::new();
+    /// let bold = selector.score(&captain, &bounty, "alice");
+    /// let timid = selector.score(&clerk, &bounty, "alice");
+    /// assert!(bold.personality > timid.personality);
+    /// assert_eq!(bold.relationship, 0.5);
+    /// ```
+    pub fn score(&self, agent: &NpcAgent, quest: &Quest, player: &str) -> GiverScore {
+        let knowledge = if quest.clues.is_empty() {
+            0.0
+        } else {
+            let known = quest.clues.iter().filter(|clue| clue.fact_in(&agent.knowledge).is_some());
+            known.count() as f64 / quest.clues.len() as f64
+        };
+        let relationship = agent.get_interlocutor(player).map_or(0.5, |context| {
+            ((context.affinity + context.trust) / 2.0 + 1.0) / 2.0
+        });
+        let proximity = quest.location.map_or(0.5, |location| {
+            let distance = distance(agent.position, location);
+            (1.0 - distance / self.max_distance.max(f64::EPSILON)).clamp(0.0, 1.0)
+        });
+        let personality = personality_fit(agent, quest.kind);
+        let total = self.knowledge_weight * knowledge
+            + self.relationship_weight * relationship
+            + self.proximity_weight * proximity
+            + self.personality_weight * personality;
+        GiverScore {
+            npc: agent.id.to_string(),
+            knowledge,
+            relationship,
+            proximity,
+            personality,
+            total,
+        }
+    }
+
+    /// Ranks the managed NPCs as givers of a quest, best first. NPCs the player is on too bad
+    /// terms with are left out, as are NPCs the quest is about unless `exclude_subjects` is
+    /// off; ties are broken by ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `agents` - The managed NPCs.
+    /// * `quest` - The quest to hand out.
+    /// * `player` - The ID of the player the quest is for.
+    pub fn rank(&self, agents: &AgentManager, quest: &Quest, player: &str) -> Vec<GiverScore> {
+        let mut scores: Vec<GiverScore> = agents
+            .agents()
+            .filter(|agent| {
+                !self.exclude_subjects
+                    || !quest.clues.iter().any(|clue| {
+                        agent.id == clue.source.as_str() || agent.id == clue.target.as_str()
+                    })
+            })
+            .map(|agent| self.score(agent, quest, player))
+            .filter(|score| score.relationship >= self.min_relationship)
+            .collect();
+        scores.sort_by(|a, b| b.total.total_cmp(&a.total));
+        scores
+    }
+
+    /// Picks the best giver for a quest and makes them its giver.
+    ///
+    /// # Arguments
+    ///
+    /// * `quest` - The quest to hand out.
+    /// * `agents` - The managed NPCs.
+    /// * `player` - The ID of the player the quest is for.
+    ///
+    /// # Returns
+    ///
+    /// The score of the giver picked, or `None` if no NPC fits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use athena::agent::{AgentManager, NpcAgent};
+    /// use athena::knowledge_graph::Relationship;
+    /// use athena::quest::{Clue, Quest, QuestKind};
+    /// use athena::quest_giver::GiverSelector;
+    ///
+    /// let mut quest = Quest::new("wolves", "The Wolf Problem", "Wolves are killing the sheep.")
+    ///     .with_kind(QuestKind::Bounty)
+    ///     .at((100.0, 0.0))
+    ///     .with_clue(Clue::new("wolves", "headed_to", "old_mill", ""));
+    ///
+    /// let mut agents = AgentManager::new(1.0);
+    /// let mut shepherd = NpcAgent::new("shepherd", vec![]);
+    /// shepherd.position = (120.0, 0.0);
+    /// let seen = Relationship::new("wolves", "old_mill", "headed_to", HashMap::new());
+    /// shepherd.knowledge.add_relationship(seen);
+    /// agents.add_agent(shepherd);
+    /// let mut baker = NpcAgent::new("baker", vec![]);
+    /// baker.position = (110.0, 0.0);
+    /// agents.add_agent(baker);
+    /// // The player is on bad terms with the hunter.
+    /// let mut hunter = NpcAgent::new("hunter", vec![]);
+    /// hunter.interlocutor("alice").adjust_trust(-1.0);
+    /// hunter.interlocutor("alice").adjust_affinity(-1.0);
+    /// agents.add_agent(hunter);
+    ///
+    /// let selector = GiverSelector::new();
+    /// let giver = selector.assign(&mut quest, &agents, "alice").unwrap();
+    /// assert_eq!(giver.npc, "shepherd");
+    /// assert_eq!(quest.giver.as_deref(), Some("shepherd"));
+    /// assert_eq!(selector.rank(&agents, &quest, "alice").len(), 2);
+    ///
+    /// // The old mill's keeper is named in a clue, so only gives the quest if allowed to.
+    /// agents.add_agent(NpcAgent::new("old_mill", vec![]));
+    /// assert_eq!(selector.rank(&agents, &quest, "alice").len(), 2);
+    /// let inclusive = GiverSelector { exclude_subjects: false, ..GiverSelector::new() };
+    /// assert_eq!(inclusive.rank(&agents, &quest, "alice").len(), 3);
+    /// ```
+    pub fn assign(
+        &self,
+        quest: &mut Quest,
+        agents: &AgentManager,
+        player: &str,
+    ) -> Option<GiverScore> {
+        let best = self.rank(agents, quest, player).into_iter().next()?;
+        quest.giver = Some(best.npc.clone());
+        Some(best)
+    }
+}
+
+/// Computes how well a kind of quest suits an NPC's personality (between 0.0 and 1.0).
+fn personality_fit(agent: &NpcAgent, kind: QuestKind) -> f64 {
+    let personality = &agent.personality;
+    let parameters = personality.decision_parameters();
+    match kind {
+        QuestKind::General => 0.5,
+        QuestKind::Escort => personality.get_trait(Trait::Conscientiousness),
+        QuestKind::Bounty => parameters.risk_tolerance,
+        QuestKind::Delivery => parameters.patience,
+        QuestKind::Investigation => parameters.novelty_seeking,
+        QuestKind::Favor => personality.get_trait(Trait::Agreeableness),
+    }
+}
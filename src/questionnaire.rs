@@ -0,0 +1,292 @@
+//! # Questionnaire Module
+//!
+//! This module lets writers author personalities without picking numbers for the Big Five
+//! traits. A personality can be derived from answers to a short questionnaire, from descriptive
+//! tags such as "bookish" or "hot-tempered", or from both.
+//!
+//! The questionnaire has two statements per trait, modeled on the ten-item Big Five Inventory
+//! (BFI-10). Each is answered on a five-point scale from "strongly disagree" to "strongly agree",
+//! and one statement of each pair is reversed. A trait is the average of its answers, mapped
+//! onto 0.0 to 1.0.
+//!
+//! Tags then shift traits away from the questionnaire's result (or from the neutral 0.5 when
+//! there is none). The shifts of several tags add up, and the result is clamped. The mapping:
+//!
+//! | Tag | Shifts |
+//! | --- | --- |
+//! | `bookish` | openness +0.25, extraversion -0.15 |
+//! | `curious` | openness +0.3 |
+//! | `artistic` | openness +0.3, conscientiousness -0.1 |
+//! | `traditional` | openness -0.3, conscientiousness +0.1 |
+//! | `incurious` | openness -0.25 |
+//! | `meticulous` | conscientiousness +0.3 |
+//! | `disciplined` | conscientiousness +0.25, neuroticism -0.1 |
+//! | `lazy` | conscientiousness -0.3 |
+//! | `reckless` | conscientiousness -0.25, extraversion +0.1 |
+//! | `outgoing` | extraversion +0.3 |
+//! | `cheerful` | extraversion +0.15, neuroticism -0.2 |
+//! | `shy` | extraversion -0.3, neuroticism +0.1 |
+//! | `loner` | extraversion -0.3, agreeableness -0.1 |
+//! | `kind` | agreeableness +0.3 |
+//! | `people-pleaser` | agreeableness +0.3, neuroticism +0.1 |
+//! | `grumpy` | agreeableness -0.2, neuroticism +0.1 |
+//! | `cruel` | agreeableness -0.4 |
+//! | `hot-tempered` | neuroticism +0.3, agreeableness -0.2 |
+//! | `anxious` | neuroticism +0.3 |
+//! | `calm` | neuroticism -0.3 |
+//! | `stoic` | neuroticism -0.25, extraversion -0.1 |
+//!
+//! Tags are matched case-insensitively, with spaces and underscores read as hyphens.
+
+use serde::{Deserialize, Serialize};
+
+use crate::personality::{Personality, Trait};
+
+/// Represents an answer on the questionnaire's five-point scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Answer {
+    StronglyDisagree,
+    Disagree,
+    Neutral,
+    Agree,
+    StronglyAgree,
+}
+
+impl Answer {
+    /// Gets how much the answer agrees with its statement (between 0.0 and 1.0).
+    pub fn agreement(&self) -> f64 {
+        match self {
+            Answer::StronglyDisagree => 0.0,
+            Answer::Disagree => 0.25,
+            Answer::Neutral => 0.5,
+            Answer::Agree => 0.75,
+            Answer::StronglyAgree => 1.0,
+        }
+    }
+}
+
+/// Represents a statement of the questionnaire, answered from the NPC's point of view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Question {
+    pub statement: &'static str,
+    /// The trait the statement measures.
+    pub measures: Trait,
+    /// Whether agreeing with the statement lowers the trait.
+    pub reversed: bool,
+}
+
+/// The questionnaire, in the order its answers are given.
+pub const QUESTIONS: [Question; 10] = [
+    Question {
+        statement: "I am reserved.",
+        measures: Trait::Extraversion,
+        reversed: true,
+    },
+    Question {
+        statement: "I am generally trusting.",
+        measures: Trait::Agreeableness,
+        reversed: false,
+    },
+    Question {
+        statement: "I tend to be lazy.",
+        measures: Trait::Conscientiousness,
+        reversed: true,
+    },
+    Question {
+        statement: "I am relaxed and handle stress well.",
+        measures: Trait::Neuroticism,
+        reversed: true,
+    },
+    Question {
+        statement: "I have few artistic interests.",
+        measures: Trait::Openness,
+        reversed: true,
+    },
+    Question {
+        statement: "I am outgoing and sociable.",
+        measures: Trait::Extraversion,
+        reversed: false,
+    },
+    Question {
+        statement: "I tend to find fault with others.",
+        measures: Trait::Agreeableness,
+        reversed: true,
+    },
+    Question {
+        statement: "I do a thorough job.",
+        measures: Trait::Conscientiousness,
+        reversed: false,
+    },
+    Question {
+        statement: "I get nervous easily.",
+        measures: Trait::Neuroticism,
+        reversed: false,
+    },
+    Question {
+        statement: "I have an active imagination.",
+        measures: Trait::Openness,
+        reversed: false,
+    },
+];
+
+/// The descriptive tags and the trait shifts they apply (see the module documentation).
+pub const TAGS: &[(&str, &[(Trait, f64)])] = &[
+    ("bookish", &[(Trait::Openness, 0.25), (Trait::Extraversion, -0.15)]),
+    ("curious", &[(Trait::Openness, 0.3)]),
+    ("artistic", &[(Trait::Openness, 0.3), (Trait::Conscientiousness, -0.1)]),
+    ("traditional", &[(Trait::Openness, -0.3), (Trait::Conscientiousness, 0.1)]),
+    ("incurious", &[(Trait::Openness, -0.25)]),
+    ("meticulous", &[(Trait::Conscientiousness, 0.3)]),
+    ("disciplined", &[(Trait::Conscientiousness, 0.25), (Trait::Neuroticism, -0.1)]),
+    ("lazy", &[(Trait::Conscientiousness, -0.3)]),
+    ("reckless", &[(Trait::Conscientiousness, -0.25), (Trait::Extraversion, 0.1)]),
+    ("outgoing", &[(Trait::Extraversion, 0.3)]),
+    ("cheerful", &[(Trait::Extraversion, 0.15), (Trait::Neuroticism, -0.2)]),
+    ("shy", &[(Trait::Extraversion, -0.3), (Trait::Neuroticism, 0.1)]),
+    ("loner", &[(Trait::Extraversion, -0.3), (Trait::Agreeableness, -0.1)]),
+    ("kind", &[(Trait::Agreeableness, 0.3)]),
+    ("people-pleaser", &[(Trait::Agreeableness, 0.3), (Trait::Neuroticism, 0.1)]),
+    ("grumpy", &[(Trait::Agreeableness, -0.2), (Trait::Neuroticism, 0.1)]),
+    ("cruel", &[(Trait::Agreeableness, -0.4)]),
+    ("hot-tempered", &[(Trait::Neuroticism, 0.3), (Trait::Agreeableness, -0.2)]),
+    ("anxious", &[(Trait::Neuroticism, 0.3)]),
+    ("calm", &[(Trait::Neuroticism, -0.3)]),
+    ("stoic", &[(Trait::Neuroticism, -0.25), (Trait::Extraversion, -0.1)]),
+];
+
+/// Gets the trait shifts of a tag, or `None` if the tag is unknown.
+///
+/// # Examples
+///
+/// ```
+/// use athena::personality::Trait;
+/// use athena::questionnaire::tag_shifts;
+///
+/// assert_eq!(tag_shifts("Hot tempered").unwrap()[0], (Trait::Neuroticism, 0.3));
+/// assert!(tag_shifts("sneaky").is_none());
+/// ```
+pub fn tag_shifts(tag: &str) -> Option<&'static [(Trait, f64)]> {
+    let tag = tag.trim().to_lowercase().replace([' ', '_'], "-");
+    TAGS.iter().find(|(name, _)| *name == tag).map(|(_, shifts)| *shifts)
+}
+
+/// Represents a personality described the way a writer would describe it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PersonalitySketch {
+    /// The answers to the questionnaire, in the order of `QUESTIONS`, or empty to skip it.
+    #[serde(default)]
+    pub answers: Vec<Answer>,
+    /// Descriptive tags such as "bookish" or "hot-tempered".
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl PersonalitySketch {
+    /// Creates a sketch from tags alone.
+    pub fn from_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Self {
+        PersonalitySketch {
+            answers: Vec::new(),
+            tags: tags.into_iter().map(str::to_string).collect(),
+        }
+    }
+
+    /// Creates a sketch from questionnaire answers alone.
+    pub fn from_answers(answers: &[Answer]) -> Self {
+        PersonalitySketch {
+            answers: answers.to_vec(),
+            tags: Vec::new(),
+        }
+    }
+
+    /// Describes what is wrong with the sketch: a questionnaire with the wrong number of answers
+    /// and tags that are not in the mapping.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.answers.is_empty() && self.answers.len() != QUESTIONS.len() {
+            problems.push(format!(
+                "the questionnaire has {} questions, but {} answers were given",
+                QUESTIONS.len(),
+                self.answers.len()
+            ));
+        }
+        for tag in &self.tags {
+            if tag_shifts(tag).is_none() {
+                problems.push(format!("\"{}\" is not a known personality tag", tag));
+            }
+        }
+        problems
+    }
+
+    /// Derives the personality, skipping an incomplete questionnaire and unknown tags.
+    pub fn personality(&self) -> Personality {
+        let mut personality = Personality::new();
+        if self.answers.len() == QUESTIONS.len() {
+            for personality_trait in Trait::ALL {
+                let scores: Vec<f64> = QUESTIONS
+                    .iter()
+                    .zip(&self.answers)
+                    .filter(|(question, _)| question.measures == personality_trait)
+                    .map(|(question, answer)| {
+                        if question.reversed {
+                            1.0 - answer.agreement()
+                        } else {
+                            answer.agreement()
+                        }
+                    })
+                    .collect();
+                let score = scores.iter().sum::<f64>() / scores.len() as f64;
+                personality.set_trait(personality_trait, score);
+            }
+        }
+        // The shifts are summed before clamping, so the order of the tags does not matter.
+        let shifts: Vec<&(Trait, f64)> =
+            self.tags.iter().filter_map(|tag| tag_shifts(tag)).flatten().collect();
+        for personality_trait in Trait::ALL {
+            let shift: f64 = shifts
+                .iter()
+                .filter(|(shifted, _)| *shifted == personality_trait)
+                .map(|(_, shift)| shift)
+                .sum();
+            let value = personality.get_trait(personality_trait) + shift;
+            personality.set_trait(personality_trait, value);
+        }
+        personality
+    }
+
+    /// Derives the personality.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Personality, Box<dyn std::error::Error>>` - The personality, or an error listing
+    ///   the sketch's problems.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::questionnaire::{Answer, PersonalitySketch};
+    ///
+    /// let scholar = PersonalitySketch::from_tags(["bookish", "shy"]).to_personality().unwrap();
+    /// assert_eq!(scholar.openness, 0.75);
+    /// assert!((scholar.extraversion - 0.05).abs() < 1e-9);
+    ///
+    /// // Strongly agreeing with every statement cancels out each reversed pair.
+    /// let answers = [Answer::StronglyAgree; 10];
+    /// let sketch = PersonalitySketch::from_answers(&answers);
+    /// assert_eq!(sketch.to_personality().unwrap().openness, 0.5);
+    ///
+    /// // Tags add up before the result is clamped, whatever their order.
+    /// let cruel_first = PersonalitySketch::from_tags(["cruel", "cruel", "kind"]).to_personality();
+    /// let kind_first = PersonalitySketch::from_tags(["kind", "cruel", "cruel"]).to_personality();
+    /// assert_eq!(cruel_first.unwrap().agreeableness, kind_first.unwrap().agreeableness);
+    ///
+    /// let typo = PersonalitySketch::from_tags(["hot-tempred"]);
+    /// assert!(typo.to_personality().is_err());
+    /// ```
+    pub fn to_personality(&self) -> Result<Personality, Box<dyn std::error::Error>> {
+        let problems = self.problems();
+        if !problems.is_empty() {
+            return Err(problems.join("; ").into());
+        }
+        Ok(self.personality())
+    }
+}
@@ -0,0 +1,167 @@
+//! # Quota Module
+//!
+//! This module caps how often NPCs may call the language model, so a player spamming an NPC
+//! cannot run up the API bill. Each NPC gets a quota of generations per minute and per session;
+//! once either is used up, its lines should come from authored fallback dialogue instead until
+//! the minute rolls over or a new session starts.
+//!
+//! Quotas are enforced by the `AgentManager` (see `AgentManager::generate_or_fallback`), which
+//! measures minutes in wall-clock time: players spam in real time, whatever the game's clock
+//! is doing.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::symbol::Symbol;
+
+/// Represents how many generations an NPC may request. `None` means no limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quota {
+    /// The generations allowed in any sixty seconds.
+    pub per_minute: Option<usize>,
+    /// The generations allowed until the session is reset.
+    pub per_session: Option<usize>,
+}
+
+impl Quota {
+    /// Creates a quota without limits.
+    pub fn unlimited() -> Self {
+        Quota::default()
+    }
+
+    /// Limits the generations in any sixty seconds.
+    pub fn with_per_minute(mut self, limit: usize) -> Self {
+        self.per_minute = Some(limit);
+        self
+    }
+
+    /// Limits the generations until the session is reset.
+    pub fn with_per_session(mut self, limit: usize) -> Self {
+        self.per_session = Some(limit);
+        self
+    }
+}
+
+/// Represents whether a generation may go ahead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuotaStatus {
+    Allowed,
+    /// The per-minute limit is used up; a slot frees up after the given number of seconds.
+    RateLimited { retry_after: f64 },
+    /// The per-session limit is used up.
+    SessionExhausted,
+}
+
+impl QuotaStatus {
+    /// Returns true if the generation may go ahead.
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, QuotaStatus::Allowed)
+    }
+}
+
+/// Represents an NPC's use of its quota.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    /// The generations allowed this session.
+    pub session: usize,
+    /// The generations refused this session, whose lines came from fallback dialogue.
+    pub refused: usize,
+    /// When the generations of the last minute were allowed, in seconds.
+    recent: VecDeque<f64>,
+}
+
+/// Tracks the quota use of many NPCs.
+#[derive(Debug, Clone, Default)]
+pub struct QuotaTracker {
+    /// The quota of NPCs without one of their own.
+    pub default: Quota,
+    quotas: HashMap<Symbol, Quota>,
+    usage: HashMap<Symbol, QuotaUsage>,
+}
+
+impl QuotaTracker {
+    /// Creates a new tracker where every NPC has the given quota.
+    pub fn new(default: Quota) -> Self {
+        QuotaTracker {
+            default,
+            ..QuotaTracker::default()
+        }
+    }
+
+    /// Gives an NPC a quota of its own, e.g. a more generous one for a main character.
+    pub fn set_quota(&mut self, id: &str, quota: Quota) {
+        self.quotas.insert(Symbol::new(id), quota);
+    }
+
+    /// Gets the quota of an NPC.
+    pub fn get_quota(&self, id: &str) -> Quota {
+        self.quotas.get(id).copied().unwrap_or(self.default)
+    }
+
+    /// Gets an NPC's use of its quota, if it has requested a generation this session.
+    pub fn get_usage(&self, id: &str) -> Option<&QuotaUsage> {
+        self.usage.get(id)
+    }
+
+    /// Checks an NPC's quota and, if a generation is allowed, counts it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the NPC requesting a generation.
+    /// * `now` - The current time in seconds, from any fixed starting point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::quota::{Quota, QuotaStatus, QuotaTracker};
+    ///
+    /// let mut tracker = QuotaTracker::new(Quota::unlimited().with_per_minute(2));
+    /// assert!(tracker.try_acquire("brom", 0.0).is_allowed());
+    /// assert!(tracker.try_acquire("brom", 10.0).is_allowed());
+    /// assert_eq!(
+    ///     tracker.try_acquire("brom", 20.0),
+    ///     QuotaStatus::RateLimited { retry_after: 40.0 }
+    /// );
+    /// // Other NPCs have quotas of their own.
+    /// assert!(tracker.try_acquire("mira", 20.0).is_allowed());
+    /// // A minute after the first generation, a slot is free again.
+    /// assert!(tracker.try_acquire("brom", 60.0).is_allowed());
+    /// assert_eq!(tracker.get_usage("brom").unwrap().refused, 1);
+    /// ```
+    pub fn try_acquire(&mut self, id: &str, now: f64) -> QuotaStatus {
+        let quota = self.get_quota(id);
+        let usage = self.usage.entry(Symbol::new(id)).or_default();
+        while usage.recent.front().is_some_and(|time| now - time >= 60.0) {
+            usage.recent.pop_front();
+        }
+        let status = if quota.per_session.is_some_and(|limit| usage.session >= limit) {
+            QuotaStatus::SessionExhausted
+        } else if quota.per_minute.is_some_and(|limit| usage.recent.len() >= limit) {
+            // The oldest generations of the minute have to expire before one more fits.
+            let limit = quota.per_minute.unwrap_or(0);
+            let freed_by = usage.recent.len() - limit;
+            let retry_after = usage.recent.get(freed_by).map_or(60.0, |time| time + 60.0 - now);
+            QuotaStatus::RateLimited { retry_after }
+        } else {
+            QuotaStatus::Allowed
+        };
+        if status.is_allowed() {
+            usage.session += 1;
+            usage.recent.push_back(now);
+        } else {
+            usage.refused += 1;
+        }
+        status
+    }
+
+    /// Starts a new session for an NPC, e.g. when the player loads a save.
+    pub fn reset_session(&mut self, id: &str) {
+        self.usage.remove(id);
+    }
+
+    /// Starts a new session for every NPC.
+    pub fn reset_all(&mut self) {
+        self.usage.clear();
+    }
+}
@@ -0,0 +1,296 @@
+//! # Recall Module
+//!
+//! This module picks which of an NPC's memories go into a prompt. Every candidate memory is
+//! scored with a fast heuristic: keyword overlap with what is being talked about, whether it
+//! involves the entities in the conversation, how recent it is, and how important it was.
+//! Memories are then chosen greedily under a token budget, penalizing near-duplicates of what
+//! was already chosen so the prompt covers several memories instead of one repeated many times.
+//!
+//! `gather` collects the candidates from an agent: its memories, beliefs, emotional memories,
+//! and journal, and its memories of and deeds by the conversation partner.
+
+use std::collections::HashSet;
+
+use crate::agent::NpcAgent;
+use crate::knowledge_summary::estimate_tokens;
+use crate::lore::keywords;
+
+/// Represents a memory that may be recalled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryCandidate {
+    /// The memory as it would appear in the prompt.
+    pub text: String,
+    /// The IDs of the entities the memory involves.
+    pub entities: Vec<String>,
+    /// The game time the memory was formed at, if known.
+    pub game_time: Option<f64>,
+    /// How important the memory is (between 0.0 and 1.0).
+    pub importance: f64,
+}
+
+impl MemoryCandidate {
+    /// Creates a new undated candidate of medium importance.
+    pub fn new(text: &str) -> Self {
+        MemoryCandidate {
+            text: text.to_string(),
+            entities: Vec::new(),
+            game_time: None,
+            importance: 0.5,
+        }
+    }
+
+    /// Adds an entity the memory involves.
+    pub fn with_entity(mut self, id: &str) -> Self {
+        self.entities.push(id.to_string());
+        self
+    }
+
+    /// Sets when the memory was formed.
+    pub fn at(mut self, game_time: f64) -> Self {
+        self.game_time = Some(game_time);
+        self
+    }
+
+    /// Sets how important the memory is.
+    pub fn with_importance(mut self, importance: f64) -> Self {
+        self.importance = importance.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Represents how much each signal counts towards a memory's score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecallWeights {
+    pub keywords: f64,
+    pub entities: f64,
+    pub recency: f64,
+    pub importance: f64,
+}
+
+impl Default for RecallWeights {
+    fn default() -> Self {
+        RecallWeights {
+            keywords: 2.0,
+            entities: 1.5,
+            recency: 1.0,
+            importance: 1.0,
+        }
+    }
+}
+
+/// Represents what memories are being recalled for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecallQuery {
+    /// What is being talked about, e.g. the player's latest line.
+    pub text: String,
+    /// The IDs of the entities in the conversation, e.g. the partner.
+    pub entities: Vec<String>,
+    /// The current game time, used for recency.
+    pub game_time: f64,
+    /// How long it takes a memory's recency to halve, in seconds of game time.
+    pub half_life: f64,
+    /// The maximum estimated tokens of the chosen memories.
+    pub token_budget: usize,
+    /// How strongly near-duplicates of chosen memories are penalized (0.0 disables it).
+    pub diversity: f64,
+    pub weights: RecallWeights,
+}
+
+impl Default for RecallQuery {
+    fn default() -> Self {
+        RecallQuery {
+            text: String::new(),
+            entities: Vec::new(),
+            game_time: 0.0,
+            half_life: 86_400.0,
+            token_budget: 150,
+            diversity: 1.0,
+            weights: RecallWeights::default(),
+        }
+    }
+}
+
+/// Scores how relevant a memory is to a query.
+///
+/// Keyword overlap is the fraction of the query's keywords the memory contains, the entity
+/// signal is 1.0 if the memory involves a queried entity, recency halves every `half_life`
+/// (undated memories count as half recent), and importance is taken as is.
+pub fn score(candidate: &MemoryCandidate, query: &RecallQuery) -> f64 {
+    let query_words: HashSet<String> = keywords(&query.text).into_iter().collect();
+    score_with(candidate, query, &query_words)
+}
+
+fn score_with(candidate: &MemoryCandidate, query: &RecallQuery, words: &HashSet<String>) -> f64 {
+    let overlap = if words.is_empty() {
+        0.0
+    } else {
+        let candidate_words: HashSet<String> = keywords(&candidate.text).into_iter().collect();
+        words.intersection(&candidate_words).count() as f64 / words.len() as f64
+    };
+    let involved = candidate.entities.iter().any(|id| query.entities.contains(id));
+    let recency = candidate.game_time.map_or(0.5, |time| {
+        let age = (query.game_time - time).max(0.0);
+        0.5_f64.powf(age / query.half_life.max(f64::EPSILON))
+    });
+    let weights = &query.weights;
+    weights.keywords * overlap
+        + weights.entities * if involved { 1.0 } else { 0.0 }
+        + weights.recency * recency
+        + weights.importance * candidate.importance
+}
+
+/// Measures how similar two memories are, as the Jaccard similarity of their keywords.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Chooses the memories to put in a prompt.
+///
+/// Memories are chosen one at a time: the best-scoring memory after subtracting `diversity`
+/// times its similarity to the most similar memory already chosen, as long as it fits the
+/// remaining token budget.
+///
+/// # Returns
+///
+/// The chosen memories, best first.
+///
+/// # Examples
+///
+/// ```
+/// use athena::recall::{select, MemoryCandidate, RecallQuery};
+///
+/// let candidates = vec![
+///     MemoryCandidate::new("The player bought a sword from me.").with_entity("player").at(190_000.0),
+///     MemoryCandidate::new("The player bought a second sword from me.").with_entity("player").at(195_000.0),
+///     MemoryCandidate::new("The mill burned down last winter.").with_importance(0.9),
+///     MemoryCandidate::new("Wolves were seen near the mill.").at(0.0),
+/// ];
+/// let query = RecallQuery {
+///     text: "Got any more swords?".to_string(),
+///     entities: vec!["player".to_string()],
+///     game_time: 200_000.0,
+///     token_budget: 25,
+///     diversity: 2.0,
+///     ..Default::default()
+/// };
+/// let chosen: Vec<&str> = select(&candidates, &query).iter().map(|c| c.text.as_str()).collect();
+/// assert_eq!(chosen, vec![
+///     "The player bought a second sword from me.",
+///     "The mill burned down last winter.",
+/// ]);
+/// ```
+pub fn select<'a>(
+    candidates: &'a [MemoryCandidate],
+    query: &RecallQuery,
+) -> Vec<&'a MemoryCandidate> {
+    let words: HashSet<String> = keywords(&query.text).into_iter().collect();
+    let mut remaining: Vec<(&MemoryCandidate, f64, HashSet<String>, usize)> = candidates
+        .iter()
+        .map(|candidate| {
+            let candidate_words = keywords(&candidate.text).into_iter().collect();
+            let tokens = estimate_tokens(&candidate.text);
+            (candidate, score_with(candidate, query, &words), candidate_words, tokens)
+        })
+        .collect();
+    let mut chosen: Vec<(&MemoryCandidate, HashSet<String>)> = Vec::new();
+    let mut budget = query.token_budget;
+    loop {
+        let best = remaining
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, _, _, tokens))| *tokens <= budget)
+            .map(|(i, (_, score, candidate_words, _))| {
+                let redundancy = chosen
+                    .iter()
+                    .map(|(_, chosen_words)| similarity(candidate_words, chosen_words))
+                    .fold(0.0_f64, f64::max);
+                (i, score - query.diversity * redundancy)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| b.0.cmp(&a.0)));
+        let Some((index, _)) = best else {
+            break;
+        };
+        let (candidate, _, candidate_words, tokens) = remaining.swap_remove(index);
+        budget -= tokens;
+        chosen.push((candidate, candidate_words));
+    }
+    chosen.into_iter().map(|(candidate, _)| candidate).collect()
+}
+
+/// Builds a prompt directive from the chosen memories, or `None` if none were chosen.
+pub fn directive(candidates: &[MemoryCandidate], query: &RecallQuery) -> Option<String> {
+    let chosen = select(candidates, query);
+    if chosen.is_empty() {
+        return None;
+    }
+    let lines: Vec<&str> = chosen.iter().map(|candidate| candidate.text.as_str()).collect();
+    Some(format!("Things you remember: {}", lines.join(" ")))
+}
+
+/// Collects an agent's memories as recall candidates.
+///
+/// # Arguments
+///
+/// * `agent` - The agent remembering.
+/// * `partner_id` - The conversation partner, whose memories and deeds are included.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::emotional_response::Emotion;
+/// use athena::recall::gather;
+///
+/// let mut agent = NpcAgent::new("brom", vec![]);
+/// agent.intelligence.record_memory("belief:guards", "The guards cannot be trusted.");
+/// agent.emotions.record_memory("attacked_by_wolves", Emotion::Fear);
+/// agent.interlocutor("player").record_memory("name", "The player is called Ayla.");
+/// let candidates = gather(&agent, Some("player"));
+/// assert_eq!(candidates.len(), 3);
+/// assert!(candidates.iter().any(|c| c.text == "attacked by wolves made me feel fear."));
+/// ```
+pub fn gather(agent: &NpcAgent, partner_id: Option<&str>) -> Vec<MemoryCandidate> {
+    let mut candidates = Vec::new();
+    for (key, value) in agent.intelligence.memories() {
+        let candidate = match key.split_once(':') {
+            Some(("belief", subject)) => {
+                MemoryCandidate::new(value).with_entity(subject).with_importance(0.7)
+            }
+            _ => MemoryCandidate::new(value).with_importance(0.4),
+        };
+        candidates.push(candidate);
+    }
+    for (trigger, emotion) in agent.emotions.memories() {
+        let text = format!(
+            "{} made me feel {}.",
+            trigger.replace('_', " "),
+            format!("{:?}", emotion).to_lowercase()
+        );
+        let strength = agent.emotions.get_memory_strength(trigger);
+        candidates.push(MemoryCandidate::new(&text).with_importance(0.3 + 0.5 * strength));
+    }
+    for entry in &agent.reflector.get_journal().entries {
+        candidates.push(MemoryCandidate::new(&entry.text).with_importance(0.6));
+    }
+    let Some(partner_id) = partner_id else {
+        return candidates;
+    };
+    let Some(context) = agent.get_interlocutor(partner_id) else {
+        return candidates;
+    };
+    for (_, value) in context.memories() {
+        candidates.push(MemoryCandidate::new(value).with_entity(partner_id));
+    }
+    for deed in context.reputation.deeds() {
+        let candidate = MemoryCandidate::new(&deed.description)
+            .with_entity(partner_id)
+            .at(deed.game_time)
+            .with_importance(deed.magnitude / 5.0);
+        candidates.push(candidate);
+    }
+    candidates
+}
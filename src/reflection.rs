@@ -0,0 +1,288 @@
+//! # Reflection Module
+//!
+//! This module implements a periodic self-reflection step for NPCs, in the style of generative
+//! agents. Recent experiences are summarized into higher-level beliefs ("I no longer trust the
+//! guards"), which are stored in the NPC's memory and written to a journal. The journal is
+//! useful to designers for debugging and can be shown to players as a readable NPC diary.
+//!
+//! Reflection works offline with a heuristic summarizer; `reflect_with_llm` optionally asks the
+//! language model to write the journal entry in the NPC's own voice.
+
+use serde::{Deserialize, Serialize};
+
+use crate::adaptive_intelligence::AdaptiveIntelligence;
+#[cfg(feature = "network")]
+use crate::dialogue_generation::send_message;
+use crate::emotional_response::Emotion;
+
+/// Represents a single experience awaiting reflection.
+#[derive(Debug, Clone)]
+pub struct Experience {
+    /// The entity or topic the experience was about (e.g. "the guards").
+    pub subject: String,
+    /// A short description of what happened.
+    pub description: String,
+    /// The emotion the experience caused.
+    pub emotion: Emotion,
+}
+
+/// Represents one journal entry produced by a reflection step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// The sequence number of the reflection that produced this entry.
+    pub index: usize,
+    /// The diary text of the entry.
+    pub text: String,
+    /// The beliefs formed during this reflection.
+    pub beliefs: Vec<String>,
+}
+
+/// Represents an NPC's journal of reflections.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Renders the journal as readable diary text, one entry per paragraph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::reflection::Journal;
+    /// let journal = Journal::default();
+    /// assert_eq!(journal.render(), "");
+    /// ```
+    pub fn render(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("Entry {}: {}", entry.index + 1, entry.text))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Represents the reflection system of an NPC.
+#[derive(Clone)]
+pub struct Reflector {
+    /// Experiences recorded since the last reflection.
+    experiences: Vec<Experience>,
+    /// The number of experiences that triggers a reflection.
+    threshold: usize,
+    /// The journal of past reflections.
+    journal: Journal,
+    /// How the NPC has been feeling lately, noted in the next journal entry.
+    mood: Option<String>,
+}
+
+impl Reflector {
+    /// Creates a new Reflector that reflects after the given number of experiences.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - The number of experiences after which `should_reflect` returns true.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::reflection::Reflector;
+    /// let reflector = Reflector::new(5);
+    /// ```
+    pub fn new(threshold: usize) -> Self {
+        Reflector {
+            experiences: Vec::new(),
+            threshold: threshold.max(1),
+            journal: Journal::default(),
+            mood: None,
+        }
+    }
+
+    /// Records an experience to be considered in the next reflection.
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The entity or topic the experience was about.
+    /// * `description` - A short description of what happened.
+    /// * `emotion` - The emotion the experience caused.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::reflection::Reflector;
+    /// use athena::emotional_response::Emotion;
+    /// let mut reflector = Reflector::new(2);
+    /// reflector.record_experience("the guards", "was searched without cause", Emotion::Anger);
+    /// assert!(!reflector.should_reflect());
+    /// ```
+    pub fn record_experience(&mut self, subject: &str, description: &str, emotion: Emotion) {
+        self.experiences.push(Experience {
+            subject: subject.to_string(),
+            description: description.to_string(),
+            emotion,
+        });
+    }
+
+    /// Sets how the NPC has been feeling lately (e.g. "I've been on edge lately."), which the next
+    /// journal entry mentions.
+    pub fn set_mood(&mut self, mood: Option<String>) {
+        self.mood = mood;
+    }
+
+    /// Returns true when enough experiences have accumulated to warrant a reflection.
+    pub fn should_reflect(&self) -> bool {
+        self.experiences.len() >= self.threshold
+    }
+
+    /// Summarizes recent experiences into beliefs, one per subject with a clear emotional lean.
+    fn form_beliefs(&self) -> Vec<(String, String)> {
+        let mut subjects: Vec<&str> = self.experiences.iter().map(|e| e.subject.as_str()).collect();
+        subjects.sort();
+        subjects.dedup();
+
+        subjects
+            .into_iter()
+            .filter_map(|subject| {
+                let (positive, negative) = self
+                    .experiences
+                    .iter()
+                    .filter(|e| e.subject == subject)
+                    .fold((0, 0), |(p, n), e| match e.emotion {
+                        Emotion::Joy | Emotion::Trust | Emotion::Anticipation => (p + 1, n),
+                        Emotion::Fear | Emotion::Sadness | Emotion::Disgust | Emotion::Anger => {
+                            (p, n + 1)
+                        }
+                        _ => (p, n),
+                    });
+                if negative >= 2 && negative > positive {
+                    Some((subject.to_string(), format!("I no longer trust {}", subject)))
+                } else if positive >= 2 && positive > negative {
+                    Some((subject.to_string(), format!("I have come to trust {}", subject)))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Performs a reflection step using the heuristic summarizer.
+    ///
+    /// Beliefs are stored in the NPC's memory under `belief:<subject>` and the experiences are
+    /// cleared. A journal entry is written even if no new belief was formed.
+    ///
+    /// # Arguments
+    ///
+    /// * `ai` - The adaptive intelligence whose memory receives the beliefs.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the new journal entry, or `None` if there was nothing to reflect on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::adaptive_intelligence::AdaptiveIntelligence;
+    /// use athena::emotional_response::Emotion;
+    /// use athena::reflection::Reflector;
+    ///
+    /// let mut ai = AdaptiveIntelligence::new(vec![]);
+    /// let mut reflector = Reflector::new(2);
+    /// reflector.record_experience("the guards", "was searched without cause", Emotion::Anger);
+    /// reflector.record_experience("the guards", "was fined unfairly", Emotion::Disgust);
+    /// reflector.set_mood(Some("I've been short-tempered lately.".to_string()));
+    /// let entry = reflector.reflect(&mut ai).unwrap();
+    /// assert!(entry.text.ends_with("the guards. I've been short-tempered lately."));
+    /// assert_eq!(ai.get_memory("belief:the guards").unwrap(), "I no longer trust the guards");
+    /// ```
+    pub fn reflect(&mut self, ai: &mut AdaptiveIntelligence) -> Option<&JournalEntry> {
+        if self.experiences.is_empty() {
+            return None;
+        }
+        let beliefs = self.form_beliefs();
+        let happenings: Vec<String> = self
+            .experiences
+            .iter()
+            .map(|e| format!("{} ({})", e.description, e.subject))
+            .collect();
+        let mut text = format!("Lately I {}.", happenings.join("; "));
+        for (_, belief) in &beliefs {
+            text.push_str(&format!(" {}.", belief));
+        }
+        if let Some(mood) = &self.mood {
+            text.push_str(&format!(" {}", mood));
+        }
+        Some(self.commit(ai, text, beliefs))
+    }
+
+    /// Performs a reflection step, asking the language model to write the journal entry.
+    ///
+    /// Beliefs are still formed by the heuristic summarizer so that they stay predictable; the
+    /// model only provides the diary text in the NPC's voice.
+    ///
+    /// # Arguments
+    ///
+    /// * `ai` - The adaptive intelligence whose memory receives the beliefs.
+    /// * `persona` - A short description of the NPC, used to set the voice of the entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<&JournalEntry>, Box<dyn std::error::Error>>` - The new entry, `None` if
+    ///   there was nothing to reflect on, or an error if the request failed.
+    #[cfg(feature = "network")]
+    pub async fn reflect_with_llm(
+        &mut self,
+        ai: &mut AdaptiveIntelligence,
+        persona: &str,
+    ) -> Result<Option<&JournalEntry>, Box<dyn std::error::Error>> {
+        if self.experiences.is_empty() {
+            return Ok(None);
+        }
+        let beliefs = self.form_beliefs();
+        let happenings: Vec<String> = self
+            .experiences
+            .iter()
+            .map(|e| format!("- {} ({}, felt {:?})", e.description, e.subject, e.emotion))
+            .collect();
+        let conclusions: Vec<&str> = beliefs.iter().map(|(_, b)| b.as_str()).collect();
+        let prompt = format!(
+            "You are {}. Write a short first-person diary entry (at most 4 sentences) reflecting on these recent experiences:\n{}\nConclusions you have reached: {}",
+            persona,
+            happenings.join("\n"),
+            if conclusions.is_empty() { "none".to_string() } else { conclusions.join("; ") }
+        );
+        let prompt = match &self.mood {
+            Some(mood) => format!("{}\nYour mood lately: {}", prompt, mood),
+            None => prompt,
+        };
+        let response = send_message(&prompt).await?;
+        let text = response
+            .choices
+            .first()
+            .map(|choice| choice.message.content.trim().to_string())
+            .unwrap_or_default();
+        Ok(Some(self.commit(ai, text, beliefs)))
+    }
+
+    /// Stores beliefs, writes the journal entry, and clears the processed experiences.
+    fn commit(
+        &mut self,
+        ai: &mut AdaptiveIntelligence,
+        text: String,
+        beliefs: Vec<(String, String)>,
+    ) -> &JournalEntry {
+        for (subject, belief) in &beliefs {
+            ai.record_memory(&format!("belief:{}", subject), belief);
+        }
+        self.experiences.clear();
+        self.journal.entries.push(JournalEntry {
+            index: self.journal.entries.len(),
+            text,
+            beliefs: beliefs.into_iter().map(|(_, belief)| belief).collect(),
+        });
+        self.journal.entries.last().expect("entry was just pushed")
+    }
+
+    /// Gets the NPC's journal.
+    pub fn get_journal(&self) -> &Journal {
+        &self.journal
+    }
+}
@@ -0,0 +1,152 @@
+//! # Relationship Module
+//!
+//! This module turns an NPC's relationship metrics with someone (affinity, trust, and
+//! familiarity) into a label such as "acquaintance", "rival", or "sworn enemy". Labels are
+//! derived from ordered rules over ranges of the metrics rather than stored, so they change on
+//! their own as the metrics move, and games can replace the rules with their own vocabulary.
+//!
+//! The label is what prompts and authored templates refer to, through a prompt directive or the
+//! `{relationship}` placeholder.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interlocutor::InterlocutorContext;
+
+/// Represents the metrics a relationship label is derived from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RelationshipMetrics {
+    /// Between -1.0 and 1.0.
+    pub affinity: f64,
+    /// Between -1.0 and 1.0.
+    pub trust: f64,
+    /// Between 0.0 and 1.0.
+    pub familiarity: f64,
+}
+
+impl RelationshipMetrics {
+    /// Reads the metrics of a conversation partner.
+    pub fn of(context: &InterlocutorContext) -> Self {
+        RelationshipMetrics {
+            affinity: context.affinity,
+            trust: context.trust,
+            familiarity: context.familiarity,
+        }
+    }
+}
+
+/// Represents a rule assigning a label to a range of metrics. Each bound is inclusive, and
+/// every metric must be within its bounds for the rule to apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelRule {
+    pub label: String,
+    pub affinity: (f64, f64),
+    pub trust: (f64, f64),
+    pub familiarity: (f64, f64),
+}
+
+impl LabelRule {
+    /// Creates a rule that applies to any metrics; narrow it with the `with_*` methods.
+    pub fn new(label: &str) -> Self {
+        LabelRule {
+            label: label.to_string(),
+            affinity: (-1.0, 1.0),
+            trust: (-1.0, 1.0),
+            familiarity: (0.0, 1.0),
+        }
+    }
+
+    /// Restricts the affinity range.
+    pub fn with_affinity(mut self, min: f64, max: f64) -> Self {
+        self.affinity = (min, max);
+        self
+    }
+
+    /// Restricts the trust range.
+    pub fn with_trust(mut self, min: f64, max: f64) -> Self {
+        self.trust = (min, max);
+        self
+    }
+
+    /// Restricts the familiarity range.
+    pub fn with_familiarity(mut self, min: f64, max: f64) -> Self {
+        self.familiarity = (min, max);
+        self
+    }
+
+    /// Returns true if the metrics are within every range of the rule.
+    pub fn matches(&self, metrics: &RelationshipMetrics) -> bool {
+        let within = |value: f64, (min, max): (f64, f64)| value >= min && value <= max;
+        within(metrics.affinity, self.affinity)
+            && within(metrics.trust, self.trust)
+            && within(metrics.familiarity, self.familiarity)
+    }
+}
+
+/// Derives relationship labels from metrics with ordered rules; the first matching rule wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationshipLabels {
+    pub rules: Vec<LabelRule>,
+    /// The label used when no rule matches.
+    pub fallback: String,
+}
+
+impl Default for RelationshipLabels {
+    /// Sworn enemies, enemies, rivals, close friends, friends, and acquaintances, falling back
+    /// to strangers.
+    fn default() -> Self {
+        RelationshipLabels {
+            rules: vec![
+                LabelRule::new("sworn enemy")
+                    .with_affinity(-1.0, -0.7)
+                    .with_trust(-1.0, -0.3),
+                LabelRule::new("enemy").with_affinity(-1.0, -0.5),
+                LabelRule::new("rival").with_affinity(-1.0, -0.2),
+                LabelRule::new("close friend")
+                    .with_affinity(0.6, 1.0)
+                    .with_trust(0.4, 1.0)
+                    .with_familiarity(0.5, 1.0),
+                LabelRule::new("friend").with_affinity(0.3, 1.0).with_familiarity(0.2, 1.0),
+                LabelRule::new("acquaintance").with_familiarity(0.2, 1.0),
+            ],
+            fallback: "stranger".to_string(),
+        }
+    }
+}
+
+impl RelationshipLabels {
+    /// Derives the label for a set of metrics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::relationship::{RelationshipLabels, RelationshipMetrics};
+    ///
+    /// let labels = RelationshipLabels::default();
+    /// let mut metrics = RelationshipMetrics::default();
+    /// assert_eq!(labels.label(&metrics), "stranger");
+    /// metrics.familiarity = 0.4;
+    /// assert_eq!(labels.label(&metrics), "acquaintance");
+    /// metrics.affinity = -0.8;
+    /// metrics.trust = -0.5;
+    /// assert_eq!(labels.label(&metrics), "sworn enemy");
+    /// ```
+    pub fn label(&self, metrics: &RelationshipMetrics) -> &str {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(metrics))
+            .map_or(self.fallback.as_str(), |rule| rule.label.as_str())
+    }
+
+    /// Returns the template placeholders for a set of metrics (`{relationship}`).
+    pub fn placeholders(&self, metrics: &RelationshipMetrics) -> HashMap<String, String> {
+        HashMap::from([("relationship".to_string(), self.label(metrics).to_string())])
+    }
+}
+
+/// Prefixes a label with "a" or "an" (e.g. "an acquaintance").
+pub fn with_article(label: &str) -> String {
+    let vowel = label.starts_with(['a', 'e', 'i', 'o', 'u', 'A', 'E', 'I', 'O', 'U']);
+    format!("{} {}", if vowel { "an" } else { "a" }, label)
+}
@@ -0,0 +1,217 @@
+//! # Reputation Module
+//!
+//! This module tracks what one NPC has seen or heard a player do. Deeds are categorized (e.g.
+//! violence, generosity, theft) and carry a magnitude that fades with a configurable half-life,
+//! so old misdeeds are slowly forgiven. The decayed deeds aggregate into a `Disposition` that
+//! feeds both prompt building (as directives) and action selection (as utility modifiers).
+//!
+//! Reputation here is personal: each NPC keeps its own record per player. Faction-wide standing
+//! is a separate concern.
+
+use serde::{Deserialize, Serialize};
+
+use crate::schedule::SECONDS_PER_DAY;
+
+/// Deeds whose decayed magnitude falls below this level are forgotten.
+const FORGOTTEN_MAGNITUDE: f64 = 0.01;
+
+/// Represents the kind of deed a player can be remembered for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeedCategory {
+    Violence,
+    Generosity,
+    Theft,
+    Helpfulness,
+    Deception,
+    /// Breaches of etiquette and social norms.
+    Disrespect,
+}
+
+/// Represents a single remembered deed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deed {
+    pub category: DeedCategory,
+    /// How significant the deed was when it happened (e.g. 1.0 for a punch, 5.0 for a murder).
+    pub magnitude: f64,
+    /// The game time the deed happened at, in seconds.
+    pub game_time: f64,
+    pub description: String,
+}
+
+/// Represents how an NPC is disposed towards a player because of their deeds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Disposition {
+    /// Goodwill from kind deeds minus ill will from harmful ones (between -1.0 and 1.0).
+    pub warmth: f64,
+    /// Fear of the player's violence (between 0.0 and 1.0).
+    pub fear: f64,
+    /// Suspicion from theft and deception (between 0.0 and 1.0).
+    pub suspicion: f64,
+}
+
+impl Disposition {
+    /// Computes the utility multiplier the disposition applies to an action.
+    ///
+    /// Warmth favours social actions and disfavours hostile ones, fear favours fleeing, and
+    /// suspicion favours keeping an eye on the player.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::reputation::Disposition;
+    /// let disposition = Disposition { warmth: 0.0, fear: 0.8, suspicion: 0.0 };
+    /// assert!(disposition.action_modifier("Run") > 1.0);
+    /// assert_eq!(disposition.action_modifier("Work"), 1.0);
+    /// ```
+    pub fn action_modifier(&self, action: &str) -> f64 {
+        match action {
+            "Talk" | "Collaborate" | "Trade" => 1.0 + 0.5 * self.warmth,
+            "Shout" | "Reject" => 1.0 - 0.5 * self.warmth,
+            "Run" | "Hide" => 1.0 + self.fear,
+            "Investigate" => 1.0 + self.suspicion,
+            _ => 1.0,
+        }
+    }
+
+    /// Renders the disposition as prompt directives.
+    ///
+    /// # Returns
+    ///
+    /// One directive per notable aspect of the disposition; empty if the NPC is indifferent.
+    pub fn directives(&self) -> Vec<String> {
+        let mut directives = Vec::new();
+        if self.warmth >= 0.3 {
+            directives.push("You are grateful to the player for past kindness.".to_string());
+        } else if self.warmth <= -0.3 {
+            directives.push("You resent the player for past wrongs.".to_string());
+        }
+        if self.fear >= 0.3 {
+            directives.push("You are afraid the player may turn violent.".to_string());
+        }
+        if self.suspicion >= 0.3 {
+            directives.push("You suspect the player is a thief or a liar.".to_string());
+        }
+        directives
+    }
+}
+
+/// Maps a sum onto -1.0..1.0, approaching the bounds as the sum grows.
+fn squash(value: f64) -> f64 {
+    value / (1.0 + value.abs())
+}
+
+/// Tracks the deeds one NPC remembers about one player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reputation {
+    deeds: Vec<Deed>,
+    /// The game time after which a deed's magnitude has halved, in seconds.
+    half_life: f64,
+}
+
+impl Default for Reputation {
+    fn default() -> Self {
+        Self::new(3.0 * SECONDS_PER_DAY)
+    }
+}
+
+impl Reputation {
+    /// Creates a new, empty reputation whose deeds fade with the given half-life.
+    ///
+    /// # Arguments
+    ///
+    /// * `half_life` - The game time after which a deed's magnitude has halved, in seconds.
+    pub fn new(half_life: f64) -> Self {
+        Reputation {
+            deeds: Vec::new(),
+            half_life: half_life.max(f64::EPSILON),
+        }
+    }
+
+    /// Records a deed.
+    ///
+    /// # Arguments
+    ///
+    /// * `category` - The kind of deed.
+    /// * `magnitude` - How significant the deed was.
+    /// * `game_time` - When the deed happened, in seconds.
+    /// * `description` - A short description (e.g. "paid for the broken window").
+    pub fn record(
+        &mut self,
+        category: DeedCategory,
+        magnitude: f64,
+        game_time: f64,
+        description: &str,
+    ) {
+        self.deeds.push(Deed {
+            category,
+            magnitude,
+            game_time,
+            description: description.to_string(),
+        });
+    }
+
+    /// Iterates over the remembered deeds, oldest first.
+    pub fn deeds(&self) -> impl Iterator<Item = &Deed> {
+        self.deeds.iter()
+    }
+
+    /// Computes the decayed magnitude of a deed at the given time.
+    fn decayed(&self, deed: &Deed, now: f64) -> f64 {
+        let age = (now - deed.game_time).max(0.0);
+        deed.magnitude * 0.5_f64.powf(age / self.half_life)
+    }
+
+    /// Sums the decayed magnitudes of the deeds in a category.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::reputation::{DeedCategory, Reputation};
+    /// let mut reputation = Reputation::new(100.0);
+    /// reputation.record(DeedCategory::Theft, 2.0, 0.0, "stole a loaf of bread");
+    /// assert_eq!(reputation.score(DeedCategory::Theft, 0.0), 2.0);
+    /// assert_eq!(reputation.score(DeedCategory::Theft, 100.0), 1.0);
+    /// ```
+    pub fn score(&self, category: DeedCategory, now: f64) -> f64 {
+        self.deeds
+            .iter()
+            .filter(|deed| deed.category == category)
+            .map(|deed| self.decayed(deed, now))
+            .sum()
+    }
+
+    /// Aggregates the deeds into a disposition at the given time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::reputation::{DeedCategory, Reputation};
+    /// let mut reputation = Reputation::default();
+    /// reputation.record(DeedCategory::Generosity, 1.0, 0.0, "tipped generously");
+    /// reputation.record(DeedCategory::Violence, 3.0, 0.0, "started a bar fight");
+    /// let disposition = reputation.disposition(0.0);
+    /// assert!(disposition.warmth < 0.0);
+    /// assert_eq!(disposition.fear, 0.75);
+    /// ```
+    pub fn disposition(&self, now: f64) -> Disposition {
+        let score = |category| self.score(category, now);
+        let kind = score(DeedCategory::Generosity) + score(DeedCategory::Helpfulness);
+        let violence = score(DeedCategory::Violence);
+        let dishonesty = score(DeedCategory::Theft) + score(DeedCategory::Deception);
+        let disrespect = score(DeedCategory::Disrespect);
+        Disposition {
+            warmth: squash(kind - violence - dishonesty - disrespect),
+            fear: squash(violence),
+            suspicion: squash(dishonesty),
+        }
+    }
+
+    /// Forgets deeds whose decayed magnitude has become negligible.
+    pub fn prune(&mut self, now: f64) {
+        let half_life = self.half_life;
+        self.deeds.retain(|deed| {
+            let age = (now - deed.game_time).max(0.0);
+            deed.magnitude.abs() * 0.5_f64.powf(age / half_life) >= FORGOTTEN_MAGNITUDE
+        });
+    }
+}
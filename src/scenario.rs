@@ -0,0 +1,583 @@
+//! # Scenario Module
+//!
+//! This module runs behavioral regression tests written by designers. A scenario is a plain text
+//! file: each line is either something the player says, or a `:` command that causes an event or
+//! asserts something about the NPC's state.
+//!
+//! ```text
+//! # The blacksmith should take insults badly.
+//! :mock Hmph. What do you want?
+//! Hello there!
+//! :expect reply contains What do you want
+//! You idiot.
+//! :expect affinity < 0
+//! :deed Violence 1.0 punched the apprentice
+//! :expect emotion Anger >= 0
+//! ```
+//!
+//! Replies come from a `ReplyProvider`. Tests use the `MockProvider`, which returns scripted
+//! replies and records the prompts it was sent, so scenarios run without a language model.
+//!
+//! Sample scenarios live in the `scenarios/` folder at the root of the crate, and `run_dir` runs
+//! every `.scenario` file in a folder. The crate's own tests run the samples, so a scenario added
+//! there is checked by `cargo test`.
+//!
+//! Commands:
+//!
+//! - `:tick <seconds>` advances game time.
+//! - `:emotion <Emotion> <intensity>` sets an emotion's intensity.
+//! - `:deed <DeedCategory> <magnitude> <description>` records a deed by the player.
+//! - `:learn <source> <relation> <target>` adds a fact to the NPC's knowledge.
+//! - `:mock <reply>` queues the next reply of the mock provider.
+//! - `:expect <expectation>` asserts something (see `Expectation::parse`).
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::agent::{AgentOutput, NpcAgent};
+use crate::emotional_response::Emotion;
+use crate::knowledge_graph::{Entity, Relationship};
+use crate::postprocess::PostProcessor;
+use crate::prompt::{PromptBuilder, PromptContext};
+use crate::reputation::DeedCategory;
+
+/// The ID the player speaks as in scenarios.
+pub const PLAYER_ID: &str = "player";
+
+/// Parses a unit enum variant from its name (e.g. "Anger").
+fn parse_variant<T: serde::de::DeserializeOwned>(name: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(name.to_string()))
+        .map_err(|_| format!("unknown name: {}", name))
+}
+
+/// Parses a number.
+fn parse_number(text: &str) -> Result<f64, String> {
+    text.parse().map_err(|_| format!("not a number: {}", text))
+}
+
+/// Represents a comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    /// Parses an operator such as `>=`.
+    pub fn parse(op: &str) -> Result<Self, String> {
+        match op {
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            "=" | "==" => Ok(Comparison::Equal),
+            _ => Err(format!("unknown operator: {}", op)),
+        }
+    }
+
+    /// Returns whether `actual` compares to `expected` this way.
+    pub fn holds(&self, actual: f64, expected: f64) -> bool {
+        match self {
+            Comparison::Greater => actual > expected,
+            Comparison::GreaterOrEqual => actual >= expected,
+            Comparison::Less => actual < expected,
+            Comparison::LessOrEqual => actual <= expected,
+            Comparison::Equal => (actual - expected).abs() < 1e-6,
+        }
+    }
+}
+
+/// Represents something a scenario asserts about the NPC.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expectation {
+    Emotion(Emotion, Comparison, f64),
+    Affinity(Comparison, f64),
+    Trust(Comparison, f64),
+    /// The last action chosen.
+    Action(String),
+    /// A fact in the NPC's knowledge, as `(source, relation, target)`.
+    Fact(String, String, String),
+    /// The last turn ended the conversation.
+    Ended,
+    /// The last reply contains the text.
+    ReplyContains(String),
+    /// The last prompt sent to the provider contains the text.
+    PromptContains(String),
+}
+
+impl Expectation {
+    /// Parses an expectation. The forms are:
+    ///
+    /// - `emotion <Emotion> <op> <value>`
+    /// - `affinity <op> <value>` and `trust <op> <value>`, about the relationship with the player
+    /// - `action <name>`
+    /// - `fact <source> <relation> <target>`
+    /// - `ended`
+    /// - `reply contains <text>` and `prompt contains <text>`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::scenario::{Comparison, Expectation};
+    ///
+    /// assert_eq!(
+    ///     Expectation::parse("emotion Anger > 0.5"),
+    ///     Ok(Expectation::Emotion(Emotion::Anger, Comparison::Greater, 0.5))
+    /// );
+    /// assert!(Expectation::parse("weather sunny").is_err());
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        if let Some(rest) = text.strip_prefix("reply contains ") {
+            return Ok(Expectation::ReplyContains(rest.trim().to_string()));
+        }
+        if let Some(rest) = text.strip_prefix("prompt contains ") {
+            return Ok(Expectation::PromptContains(rest.trim().to_string()));
+        }
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["emotion", emotion, op, value] => Ok(Expectation::Emotion(
+                parse_variant(emotion)?,
+                Comparison::parse(op)?,
+                parse_number(value)?,
+            )),
+            ["affinity", op, value] => {
+                Ok(Expectation::Affinity(Comparison::parse(op)?, parse_number(value)?))
+            }
+            ["trust", op, value] => {
+                Ok(Expectation::Trust(Comparison::parse(op)?, parse_number(value)?))
+            }
+            ["action", name] => Ok(Expectation::Action(name.to_string())),
+            ["fact", source, relation, target] => Ok(Expectation::Fact(
+                source.to_string(),
+                relation.to_string(),
+                target.to_string(),
+            )),
+            ["ended"] => Ok(Expectation::Ended),
+            _ => Err(format!("unknown expectation: {}", text)),
+        }
+    }
+}
+
+/// Represents one line of a scenario.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// The player says a line.
+    Say(String),
+    Tick(f64),
+    SetEmotion(Emotion, f64),
+    Deed(DeedCategory, f64, String),
+    Learn(String, String, String),
+    /// Queues the next reply of the mock provider.
+    MockReply(String),
+    Expect(Expectation),
+}
+
+impl Step {
+    /// Parses a line. Returns `Ok(None)` for blank lines and `#` comments.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::scenario::Step;
+    ///
+    /// assert_eq!(Step::parse("Hello!"), Ok(Some(Step::Say("Hello!".to_string()))));
+    /// assert_eq!(Step::parse(":tick 60"), Ok(Some(Step::Tick(60.0))));
+    /// assert_eq!(Step::parse("# a comment"), Ok(None));
+    /// ```
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let Some(command) = line.strip_prefix(':') else {
+            return Ok(Some(Step::Say(line.to_string())));
+        };
+        let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+        let argument = argument.trim();
+        let words: Vec<&str> = argument.split_whitespace().collect();
+        let step = match (name, words.as_slice()) {
+            ("tick", [seconds]) => Step::Tick(parse_number(seconds)?),
+            ("emotion", [emotion, intensity]) => {
+                Step::SetEmotion(parse_variant(emotion)?, parse_number(intensity)?)
+            }
+            ("deed", [category, magnitude, ..]) => {
+                let description = words[2..].join(" ");
+                Step::Deed(parse_variant(category)?, parse_number(magnitude)?, description)
+            }
+            ("learn", [source, relation, target]) => {
+                Step::Learn(source.to_string(), relation.to_string(), target.to_string())
+            }
+            ("mock", _) => Step::MockReply(argument.to_string()),
+            ("expect", _) => Step::Expect(Expectation::parse(argument)?),
+            _ => return Err(format!("unknown command: {}", line)),
+        };
+        Ok(Some(step))
+    }
+}
+
+/// Represents a source of NPC replies.
+pub trait ReplyProvider {
+    /// Generates a reply to a prompt, or `None` if it could not.
+    fn reply(&mut self, prompt: &str) -> Option<String>;
+
+    /// Queues a scripted reply. Providers that do not script replies ignore it.
+    fn queue_reply(&mut self, _reply: &str) {}
+}
+
+/// A provider that returns scripted replies and records the prompts it was sent.
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    replies: VecDeque<String>,
+    /// The reply used when no scripted reply is queued.
+    pub default_reply: String,
+    prompts: Vec<String>,
+}
+
+impl MockProvider {
+    /// Creates a new mock provider that replies "..." unless replies are queued.
+    pub fn new() -> Self {
+        MockProvider {
+            default_reply: "...".to_string(),
+            ..MockProvider::default()
+        }
+    }
+
+    /// Gets the prompts the provider was sent, oldest first.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+}
+
+impl ReplyProvider for MockProvider {
+    fn reply(&mut self, prompt: &str) -> Option<String> {
+        self.prompts.push(prompt.to_string());
+        Some(self.replies.pop_front().unwrap_or_else(|| self.default_reply.clone()))
+    }
+
+    fn queue_reply(&mut self, reply: &str) {
+        self.replies.push_back(reply.to_string());
+    }
+}
+
+/// Runs scenario steps against an agent.
+pub struct ScenarioRunner {
+    pub agent: NpcAgent,
+    pub prompt: PromptBuilder,
+    provider: Box<dyn ReplyProvider>,
+    last_output: AgentOutput,
+    last_reply: Option<String>,
+    last_prompt: Option<String>,
+}
+
+impl ScenarioRunner {
+    /// Creates a new runner for an agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - The agent under test.
+    /// * `prompt` - The prompt builder used for the agent's replies.
+    /// * `provider` - Where replies come from.
+    pub fn new(agent: NpcAgent, prompt: PromptBuilder, provider: Box<dyn ReplyProvider>) -> Self {
+        ScenarioRunner {
+            agent,
+            prompt,
+            provider,
+            last_output: AgentOutput::default(),
+            last_reply: None,
+            last_prompt: None,
+        }
+    }
+
+    /// Gets the agent's reaction to the last line the player said.
+    pub fn get_last_output(&self) -> &AgentOutput {
+        &self.last_output
+    }
+
+    /// Gets the agent's last reply, if any.
+    pub fn get_last_reply(&self) -> Option<&str> {
+        self.last_reply.as_deref()
+    }
+
+    /// Has the player say a line. If the agent engages without ending the conversation, its
+    /// reply is generated by the provider.
+    pub fn say(&mut self, line: &str) -> &AgentOutput {
+        let output = self.agent.process_turn(PLAYER_ID, line, &[]);
+        self.last_reply = match &output.dialogue {
+            Some(dialogue) => Some(dialogue.clone()),
+            None if output.engaged => {
+                let mut mood = vec![format!("{:?}", self.agent.emotions.get_emotion())];
+                mood.extend(self.agent.emotions.get_dyad_labels(0.3));
+                let mut directives = vec![self.agent.relationship_directive(PLAYER_ID)];
+                directives.extend(self.agent.disposition_towards(PLAYER_ID).directives());
+                directives.extend(self.agent.mood_directives());
+                directives.extend(self.agent.conversation_directives(PLAYER_ID));
+                directives.extend(self.agent.ledger_directives(PLAYER_ID));
+                directives.extend(self.agent.promise_directives(PLAYER_ID));
+                directives.extend(self.agent.morals.directives());
+                directives.extend(self.agent.norms.directives());
+                directives.extend(self.agent.annoyance_directive(PLAYER_ID));
+                let context = PromptContext {
+                    mood,
+                    directives,
+                    player_input: line.to_string(),
+                    ..PromptContext::default()
+                };
+                let prompt = self.prompt.build(&context);
+                let speech = PostProcessor {
+                    filters: self.agent.speech_filters(PLAYER_ID),
+                };
+                let reply = self.provider.reply(&prompt).map(|reply| speech.apply(&reply));
+                self.last_prompt = Some(prompt);
+                reply
+            }
+            None => None,
+        };
+        self.last_output = output;
+        &self.last_output
+    }
+
+    /// Applies a step.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - An error describing the failure if the step is an expectation
+    ///   that does not hold.
+    pub fn apply(&mut self, step: &Step) -> Result<(), String> {
+        match step {
+            Step::Say(line) => {
+                self.say(line);
+            }
+            Step::Tick(dt) => self.agent.tick(*dt),
+            Step::SetEmotion(emotion, intensity) => {
+                self.agent.emotions.set_intensity(emotion.clone(), *intensity);
+            }
+            Step::Deed(category, magnitude, description) => {
+                self.agent.record_deed(PLAYER_ID, *category, *magnitude, description);
+            }
+            Step::Learn(source, relation, target) => {
+                let knowledge = &mut self.agent.knowledge;
+                for id in [source, target] {
+                    if knowledge.get_entity(id).is_none() {
+                        knowledge.add_entity(Entity::new(id, HashMap::new()));
+                    }
+                }
+                let relationship = Relationship::new(source, target, relation, HashMap::new());
+                knowledge.add_relationship(relationship);
+            }
+            Step::MockReply(reply) => self.provider.queue_reply(reply),
+            Step::Expect(expectation) => return self.check(expectation),
+        }
+        Ok(())
+    }
+
+    /// Checks an expectation against the current state.
+    pub fn check(&self, expectation: &Expectation) -> Result<(), String> {
+        let context = self.agent.get_interlocutor(PLAYER_ID);
+        let compare = |actual: f64, comparison: &Comparison, expected: f64| {
+            if comparison.holds(actual, expected) {
+                Ok(())
+            } else {
+                Err(format!("actual value is {:.3}", actual))
+            }
+        };
+        let contains = |actual: Option<&str>, text: &str| match actual {
+            Some(actual) if actual.contains(text) => Ok(()),
+            other => Err(format!("actual text is {:?}", other)),
+        };
+        match expectation {
+            Expectation::Emotion(emotion, comparison, value) => {
+                compare(self.agent.emotions.get_intensity(emotion), comparison, *value)
+            }
+            Expectation::Affinity(comparison, value) => {
+                compare(context.map_or(0.0, |c| c.affinity), comparison, *value)
+            }
+            Expectation::Trust(comparison, value) => {
+                compare(context.map_or(0.0, |c| c.trust), comparison, *value)
+            }
+            Expectation::Action(name) => match &self.last_output.action {
+                Some(action) if action == name => Ok(()),
+                other => Err(format!("last action was {:?}", other)),
+            },
+            Expectation::Fact(source, relation, target) => {
+                let known = self
+                    .agent
+                    .knowledge
+                    .outgoing(source)
+                    .any(|r| r.relation_type == relation.as_str() && r.target == target.as_str());
+                if known {
+                    Ok(())
+                } else {
+                    Err("not known".to_string())
+                }
+            }
+            Expectation::Ended => match &self.last_output.conversation_end {
+                Some(_) => Ok(()),
+                None => Err("the conversation is still going".to_string()),
+            },
+            Expectation::ReplyContains(text) => contains(self.last_reply.as_deref(), text),
+            Expectation::PromptContains(text) => contains(self.last_prompt.as_deref(), text),
+        }
+    }
+}
+
+/// Represents a failed expectation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// The line number in the scenario, starting at 1.
+    pub line: usize,
+    pub expectation: Expectation,
+    pub message: String,
+}
+
+/// Represents the result of running a scenario.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScenarioReport {
+    pub steps_run: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl ScenarioReport {
+    /// Returns whether every expectation held.
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Represents a parsed scenario.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    /// The steps with their line numbers, starting at 1.
+    steps: Vec<(usize, Step)>,
+}
+
+impl Scenario {
+    /// Parses a scenario.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Scenario, String>` - The scenario, or an error naming the first bad line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut steps = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let step = Step::parse(line).map_err(|error| format!("line {}: {}", index + 1, error))?;
+            if let Some(step) = step {
+                steps.push((index + 1, step));
+            }
+        }
+        Ok(Scenario { steps })
+    }
+
+    /// Loads a scenario from a file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&fs::read_to_string(path)?)?)
+    }
+
+    /// Iterates over the steps with their line numbers.
+    pub fn steps(&self) -> impl Iterator<Item = &(usize, Step)> {
+        self.steps.iter()
+    }
+
+    /// Runs every step, collecting the expectations that did not hold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::prompt::{PersonaCard, PromptBuilder};
+    /// use athena::scenario::{MockProvider, Scenario, ScenarioRunner};
+    ///
+    /// let scenario = Scenario::parse(
+    ///     "# The blacksmith should take insults badly.
+    ///      :mock Hmph. What do you want?
+    ///      Hello there!
+    ///      :expect reply contains What do you want
+    ///      :expect prompt contains Player: Hello there!
+    ///      You idiot.
+    ///      :expect affinity < 0
+    ///      :learn brom lives_in riverside
+    ///      :expect fact brom lives_in riverside
+    ///      :expect trust > 0.5",
+    /// )
+    /// .unwrap();
+    /// let prompt = PromptBuilder::new(PersonaCard::new("Brom", "The village blacksmith."));
+    /// let agent = NpcAgent::new("brom", vec![]);
+    /// let mut runner = ScenarioRunner::new(agent, prompt, Box::new(MockProvider::new()));
+    ///
+    /// let report = scenario.run(&mut runner);
+    /// assert_eq!(report.failures.len(), 1);
+    /// assert_eq!(report.failures[0].line, 10);
+    /// ```
+    pub fn run(&self, runner: &mut ScenarioRunner) -> ScenarioReport {
+        let mut report = ScenarioReport::default();
+        for (line, step) in &self.steps {
+            report.steps_run += 1;
+            if let (Err(message), Step::Expect(expectation)) = (runner.apply(step), step) {
+                report.failures.push(Failure {
+                    line: *line,
+                    expectation: expectation.clone(),
+                    message,
+                });
+            }
+        }
+        report
+    }
+}
+
+/// Runs every `.scenario` file in a folder, in file name order.
+///
+/// # Arguments
+///
+/// * `dir` - The folder of scenario files.
+/// * `new_runner` - Creates the runner for a scenario file, so each starts from a fresh NPC.
+///
+/// # Returns
+///
+/// * `Result<Vec<(PathBuf, ScenarioReport)>, Box<dyn std::error::Error>>` - The report of each
+///   file, or an error if the folder or a file cannot be read or parsed.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::prompt::{PersonaCard, PromptBuilder};
+/// use athena::scenario::{self, MockProvider, ScenarioRunner};
+///
+/// // Runs the crate's sample scenarios.
+/// let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/scenarios");
+/// let reports = scenario::run_dir(dir, |_| {
+///     let prompt = PromptBuilder::new(PersonaCard::new("Brom", "The village blacksmith."));
+///     let agent = NpcAgent::new("brom", vec![]);
+///     ScenarioRunner::new(agent, prompt, Box::new(MockProvider::new()))
+/// })
+/// .unwrap();
+/// assert!(!reports.is_empty());
+/// for (path, report) in &reports {
+///     assert!(report.passed(), "{}: {:?}", path.display(), report.failures);
+/// }
+/// ```
+pub fn run_dir(
+    dir: impl AsRef<Path>,
+    mut new_runner: impl FnMut(&Path) -> ScenarioRunner,
+) -> Result<Vec<(PathBuf, ScenarioReport)>, Box<dyn std::error::Error>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "scenario") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let mut reports = Vec::new();
+    for path in paths {
+        let scenario = Scenario::load(&path)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+        let report = scenario.run(&mut new_runner(&path));
+        reports.push((path, report));
+    }
+    Ok(reports)
+}
@@ -0,0 +1,339 @@
+//! # Schedule Module
+//!
+//! This module manages the daily routine of an NPC as a list of schedule blocks (open the shop
+//! at 8, eat at noon, sleep at 22). The schedule keeps its own clock, advanced with game time,
+//! and reports which block is currently active.
+//!
+//! Events can disrupt the routine: when a location becomes unusable (the shop burned down), every
+//! block that takes place there is re-planned, first to an authored alternative for the activity
+//! and otherwise to a fallback, so the NPC keeps doing something sensible instead of getting
+//! stuck. The original routine is kept and comes back once the location is restored.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The number of game seconds in one day.
+pub const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Represents one block of an NPC's daily routine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleBlock {
+    /// The hour of the day (0.0 to 24.0) at which the block starts.
+    pub start_hour: f64,
+    /// The hour of the day (0.0 to 24.0) at which the block ends. May wrap past midnight.
+    pub end_hour: f64,
+    /// The activity performed during the block (e.g. "work").
+    pub activity: String,
+    /// Where the activity takes place (e.g. "smithy").
+    pub location: String,
+}
+
+impl ScheduleBlock {
+    /// Creates a new schedule block.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_hour` - The hour at which the block starts.
+    /// * `end_hour` - The hour at which the block ends. If smaller than `start_hour`, the block wraps past midnight.
+    /// * `activity` - The activity performed during the block.
+    /// * `location` - Where the activity takes place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::schedule::ScheduleBlock;
+    /// let block = ScheduleBlock::new(22.0, 6.0, "sleep", "home");
+    /// assert!(block.contains(2.0));
+    /// ```
+    pub fn new(start_hour: f64, end_hour: f64, activity: &str, location: &str) -> Self {
+        ScheduleBlock {
+            start_hour,
+            end_hour,
+            activity: activity.to_string(),
+            location: location.to_string(),
+        }
+    }
+
+    /// Returns true if the given hour of the day falls within this block.
+    pub fn contains(&self, hour: f64) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Represents a block of the routine that was moved because of a disruption.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replan {
+    /// The block as planned in the routine.
+    pub original: ScheduleBlock,
+    /// The block the NPC follows instead.
+    pub replacement: ScheduleBlock,
+    /// Why the original location cannot be used (e.g. "the shop burned down").
+    pub reason: String,
+}
+
+impl Replan {
+    /// Describes the change as a memory of the NPC.
+    pub fn memory(&self) -> String {
+        format!(
+            "I couldn't {} at the {} because {}, so I had to {} at the {} instead.",
+            self.original.activity,
+            self.original.location,
+            self.reason,
+            self.replacement.activity,
+            self.replacement.location
+        )
+    }
+
+    /// Returns a line the NPC can say about the change.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::schedule::{Replan, ScheduleBlock};
+    ///
+    /// let replan = Replan {
+    ///     original: ScheduleBlock::new(8.0, 18.0, "work", "smithy"),
+    ///     replacement: ScheduleBlock::new(8.0, 18.0, "work", "market stall"),
+    ///     reason: "the smithy burned down".to_string(),
+    /// };
+    /// assert_eq!(
+    ///     replan.remark(),
+    ///     "Ever since the smithy burned down, I've had to work at the market stall."
+    /// );
+    /// ```
+    pub fn remark(&self) -> String {
+        format!(
+            "Ever since {}, I've had to {} at the {}.",
+            self.reason, self.replacement.activity, self.replacement.location
+        )
+    }
+}
+
+/// Represents the daily routine of an NPC.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// The blocks followed, in priority order: the routine with disrupted blocks re-planned.
+    blocks: Vec<ScheduleBlock>,
+    /// The routine as planned, in priority order.
+    routine: Vec<ScheduleBlock>,
+    /// Alternatives per activity as `(activity, location)`, in order of preference.
+    alternatives: HashMap<String, Vec<(String, String)>>,
+    /// The `(activity, location)` used when an activity has no usable alternative.
+    fallback: (String, String),
+    /// The locations that cannot be used, with the reason.
+    disruptions: HashMap<String, String>,
+    /// The current game time of day, in seconds since midnight.
+    clock: f64,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::new()
+    }
+}
+
+impl Schedule {
+    /// Creates a new, empty schedule starting at midnight.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::schedule::Schedule;
+    /// let schedule = Schedule::new();
+    /// ```
+    pub fn new() -> Self {
+        Schedule {
+            blocks: Vec::new(),
+            routine: Vec::new(),
+            alternatives: HashMap::new(),
+            fallback: ("wander".to_string(), "town".to_string()),
+            disruptions: HashMap::new(),
+            clock: 0.0,
+        }
+    }
+
+    /// Adds a block to the routine. Earlier blocks take priority where blocks overlap.
+    ///
+    /// # Arguments
+    ///
+    /// * `block` - The schedule block to add.
+    pub fn add_block(&mut self, block: ScheduleBlock) {
+        self.routine.push(block);
+        self.replan();
+    }
+
+    /// Gets all blocks followed, with disrupted blocks re-planned.
+    pub fn get_blocks(&self) -> &[ScheduleBlock] {
+        &self.blocks
+    }
+
+    /// Gets all blocks of the routine as planned.
+    pub fn get_routine(&self) -> &[ScheduleBlock] {
+        &self.routine
+    }
+
+    /// Adds an alternative for an activity, used when its location is disrupted. Alternatives
+    /// are tried in the order they were added.
+    ///
+    /// # Arguments
+    ///
+    /// * `for_activity` - The activity of the routine the alternative replaces (e.g. "work").
+    /// * `activity` - What the NPC does instead (e.g. "work", or "help at the tavern").
+    /// * `location` - Where it does it.
+    pub fn add_alternative(&mut self, for_activity: &str, activity: &str, location: &str) {
+        self.alternatives
+            .entry(for_activity.to_string())
+            .or_default()
+            .push((activity.to_string(), location.to_string()));
+        self.replan();
+    }
+
+    /// Sets what the NPC does when an activity has no usable alternative. Defaults to
+    /// wandering around town.
+    pub fn set_fallback(&mut self, activity: &str, location: &str) {
+        self.fallback = (activity.to_string(), location.to_string());
+        self.replan();
+    }
+
+    /// Returns true if a location is disrupted.
+    pub fn is_disrupted(&self, location: &str) -> bool {
+        self.disruptions.contains_key(location)
+    }
+
+    /// Marks a location as unusable and re-plans the blocks that take place there.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - The location that cannot be used.
+    /// * `reason` - Why (e.g. "the smithy burned down").
+    ///
+    /// # Returns
+    ///
+    /// The blocks that were moved.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::schedule::{Schedule, ScheduleBlock};
+    ///
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_block(ScheduleBlock::new(8.0, 18.0, "work", "smithy"));
+    /// schedule.add_block(ScheduleBlock::new(18.0, 20.0, "eat", "smithy"));
+    /// schedule.add_alternative("work", "work", "market stall");
+    /// schedule.set_time_of_day(9.0);
+    ///
+    /// let replans = schedule.disrupt("smithy", "the smithy burned down");
+    /// assert_eq!(replans.len(), 2);
+    /// assert_eq!(schedule.current_block().unwrap().location, "market stall");
+    /// assert_eq!(replans[1].replacement.activity, "wander");
+    ///
+    /// schedule.restore("smithy");
+    /// assert_eq!(schedule.current_block().unwrap().location, "smithy");
+    /// ```
+    pub fn disrupt(&mut self, location: &str, reason: &str) -> Vec<Replan> {
+        self.disruptions.insert(location.to_string(), reason.to_string());
+        let before = self.blocks.clone();
+        self.replan();
+        self.routine
+            .iter()
+            .zip(before.iter().zip(&self.blocks))
+            .filter(|(_, (before, after))| before != after)
+            .map(|(original, (_, replacement))| Replan {
+                original: original.clone(),
+                replacement: replacement.clone(),
+                reason: self
+                    .disruptions
+                    .get(&original.location)
+                    .cloned()
+                    .unwrap_or_else(|| reason.to_string()),
+            })
+            .collect()
+    }
+
+    /// Makes a disrupted location usable again, returning the routine to it.
+    ///
+    /// # Returns
+    ///
+    /// True if the location was disrupted.
+    pub fn restore(&mut self, location: &str) -> bool {
+        let restored = self.disruptions.remove(location).is_some();
+        self.replan();
+        restored
+    }
+
+    /// Rebuilds the blocks followed from the routine and the disruptions.
+    fn replan(&mut self) {
+        let blocks = self
+            .routine
+            .iter()
+            .map(|block| {
+                if !self.is_disrupted(&block.location) {
+                    return block.clone();
+                }
+                let (activity, location) = self
+                    .alternatives
+                    .get(&block.activity)
+                    .into_iter()
+                    .flatten()
+                    .find(|(_, location)| !self.is_disrupted(location))
+                    .unwrap_or(&self.fallback);
+                ScheduleBlock::new(block.start_hour, block.end_hour, activity, location)
+            })
+            .collect();
+        self.blocks = blocks;
+    }
+
+    /// Sets the time of day.
+    ///
+    /// # Arguments
+    ///
+    /// * `hour` - The hour of the day (0.0 to 24.0).
+    pub fn set_time_of_day(&mut self, hour: f64) {
+        self.clock = (hour * 3600.0).rem_euclid(SECONDS_PER_DAY);
+    }
+
+    /// Gets the current hour of the day (0.0 to 24.0).
+    pub fn get_hour(&self) -> f64 {
+        self.clock / 3600.0
+    }
+
+    /// Advances the schedule clock by the given amount of game time.
+    ///
+    /// # Arguments
+    ///
+    /// * `dt` - The elapsed game time in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::schedule::Schedule;
+    /// let mut schedule = Schedule::new();
+    /// schedule.set_time_of_day(23.0);
+    /// schedule.update(7200.0);
+    /// assert_eq!(schedule.get_hour(), 1.0);
+    /// ```
+    pub fn update(&mut self, dt: f64) {
+        self.clock = (self.clock + dt).rem_euclid(SECONDS_PER_DAY);
+    }
+
+    /// Returns the block active at the current time of day, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::schedule::{Schedule, ScheduleBlock};
+    /// let mut schedule = Schedule::new();
+    /// schedule.add_block(ScheduleBlock::new(8.0, 18.0, "work", "smithy"));
+    /// schedule.set_time_of_day(9.5);
+    /// assert_eq!(schedule.current_block().unwrap().activity, "work");
+    /// ```
+    pub fn current_block(&self) -> Option<&ScheduleBlock> {
+        let hour = self.get_hour();
+        self.blocks.iter().find(|block| block.contains(hour))
+    }
+}
@@ -0,0 +1,300 @@
+//! # Simulation Module
+//!
+//! This module runs an NPC through many randomized play sessions at authoring time and reports
+//! how it behaved, so designers can spot degenerate behavior before a playtest: an NPC that
+//! picks the same action nine times out of ten, one stuck in anger, or one whose fleeing state
+//! nothing ever leads to.
+//!
+//! Each run starts from a fresh agent and, step by step, has the player say random lines,
+//! stirs random emotions and deeds, advances time, and asks the agent to decide. Replies come
+//! from a `MockProvider`, so no language model is needed. Runs are seeded, so the same seed
+//! always produces the same report and a regression can be reproduced.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::adaptive_intelligence::STATE_ACTIONS;
+use crate::definition::NpcDefinition;
+use crate::emotional_response::Emotion;
+use crate::prompt::PromptBuilder;
+use crate::reputation::DeedCategory;
+use crate::scenario::{MockProvider, ScenarioRunner, Step};
+
+/// The deeds a simulation has the player commit.
+const DEEDS: [DeedCategory; 6] = [
+    DeedCategory::Violence,
+    DeedCategory::Generosity,
+    DeedCategory::Theft,
+    DeedCategory::Helpfulness,
+    DeedCategory::Deception,
+    DeedCategory::Disrespect,
+];
+
+/// A small deterministic random number generator (SplitMix64), so reports are reproducible
+/// without a dependency.
+pub(crate) struct SplitMix(pub(crate) u64);
+
+impl SplitMix {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Gets a fraction between 0.0 (inclusive) and 1.0 (exclusive).
+    pub(crate) fn fraction(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Picks an index below `len`.
+    pub(crate) fn pick(&mut self, len: usize) -> usize {
+        (self.fraction() * len as f64) as usize
+    }
+}
+
+/// Represents how simulations are run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    pub runs: usize,
+    /// The number of steps in each run.
+    pub steps: usize,
+    /// The game time each step advances, in seconds.
+    pub step_seconds: f64,
+    /// The chance that something happens in a step (the player speaks, an emotion is stirred,
+    /// or the player commits a deed).
+    pub event_chance: f64,
+    /// The lines the player says, picked at random.
+    pub lines: Vec<String>,
+    /// The states the NPC is expected to reach; those it never does are reported.
+    pub states: Vec<String>,
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        let lines = [
+            "Hello there!",
+            "Can you help me?",
+            "Thank you so much!",
+            "You idiot.",
+            "Hand over your money.",
+            "What news from the village?",
+            "Goodbye.",
+        ];
+        SimulationConfig {
+            runs: 20,
+            steps: 50,
+            step_seconds: 10.0,
+            event_chance: 0.5,
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+            states: STATE_ACTIONS.iter().map(|(state, _)| state.to_string()).collect(),
+            seed: 0,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// Picks a random step, stirring one of the given emotions.
+    fn random_step(&self, rng: &mut SplitMix, emotions: &[Emotion]) -> Option<Step> {
+        if rng.fraction() >= self.event_chance {
+            return None;
+        }
+        let strength = 0.2 + 0.8 * rng.fraction();
+        match rng.pick(3) {
+            0 if !self.lines.is_empty() => {
+                Some(Step::Say(self.lines[rng.pick(self.lines.len())].clone()))
+            }
+            1 if !emotions.is_empty() => {
+                let emotion = emotions[rng.pick(emotions.len())].clone();
+                Some(Step::SetEmotion(emotion, strength))
+            }
+            _ => {
+                let deed = DEEDS[rng.pick(DEEDS.len())];
+                Some(Step::Deed(deed, strength, format!("{:?} (simulated)", deed)))
+            }
+        }
+    }
+}
+
+/// Represents the behavior of an NPC over a set of simulations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub runs: usize,
+    /// The number of decisions made.
+    pub decisions: usize,
+    /// How many times each action was chosen.
+    pub actions: BTreeMap<String, usize>,
+    /// The game time spent with each emotion dominant, in seconds, longest first.
+    pub emotion_time: Vec<(Emotion, f64)>,
+    /// The game time spent in each state, in seconds.
+    pub state_time: BTreeMap<String, f64>,
+    /// The expected states the NPC never reached.
+    pub unreached_states: Vec<String>,
+    /// The emotions that were never dominant.
+    pub unreached_emotions: Vec<Emotion>,
+    /// The actions the NPC has or could be proposed that it never chose.
+    pub unchosen_actions: Vec<String>,
+    /// The number of conversations the NPC ended.
+    pub conversations_ended: usize,
+}
+
+impl SimulationReport {
+    /// Gets the share of decisions that chose an action (between 0.0 and 1.0).
+    pub fn action_share(&self, action: &str) -> f64 {
+        let count = self.actions.get(action).copied().unwrap_or(0);
+        count as f64 / self.decisions.max(1) as f64
+    }
+
+    /// Gets the share of game time an emotion was dominant (between 0.0 and 1.0).
+    pub fn emotion_residency(&self, emotion: &Emotion) -> f64 {
+        let total: f64 = self.emotion_time.iter().map(|(_, time)| time).sum();
+        let time = self
+            .emotion_time
+            .iter()
+            .find(|(e, _)| e == emotion)
+            .map_or(0.0, |(_, time)| *time);
+        if total > 0.0 {
+            time / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Describes behavior that looks degenerate: one action chosen in most decisions, one
+    /// emotion dominant most of the time, and expected states never reached.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for action in self.actions.keys() {
+            let share = self.action_share(action);
+            if share >= 0.8 && self.decisions >= 10 {
+                warnings.push(format!("chooses {} in {:.0}% of decisions", action, share * 100.0));
+            }
+        }
+        for (emotion, _) in &self.emotion_time {
+            let residency = self.emotion_residency(emotion);
+            if *emotion != Emotion::Neutral && residency >= 0.8 {
+                warnings.push(format!("feels {:?} {:.0}% of the time", emotion, residency * 100.0));
+            }
+        }
+        for state in &self.unreached_states {
+            warnings.push(format!("never reaches the {} state", state));
+        }
+        warnings
+    }
+}
+
+/// Runs simulations and reports on them.
+///
+/// # Arguments
+///
+/// * `config` - How the simulations are run.
+/// * `make_runner` - Creates a runner with a fresh agent for each run.
+///
+/// # Examples
+///
+/// ```
+/// use athena::agent::NpcAgent;
+/// use athena::prompt::{PersonaCard, PromptBuilder};
+/// use athena::scenario::{MockProvider, ScenarioRunner};
+/// use athena::simulation::{simulate, SimulationConfig};
+///
+/// let config = SimulationConfig { runs: 3, steps: 20, ..SimulationConfig::default() };
+/// let make_runner = || {
+///     let prompt = PromptBuilder::new(PersonaCard::new("Brom", "The village blacksmith."));
+///     let agent = NpcAgent::new("brom", vec![]);
+///     ScenarioRunner::new(agent, prompt, Box::new(MockProvider::new()))
+/// };
+///
+/// let report = simulate(&config, make_runner);
+/// assert_eq!(report.decisions, 60);
+/// assert_eq!(report, simulate(&config, make_runner));
+/// // Nothing in the core moves the NPC out of its idle state.
+/// assert!(report.warnings().contains(&"never reaches the Fleeing state".to_string()));
+/// ```
+pub fn simulate(
+    config: &SimulationConfig,
+    mut make_runner: impl FnMut() -> ScenarioRunner,
+) -> SimulationReport {
+    let mut report = SimulationReport {
+        runs: config.runs,
+        ..SimulationReport::default()
+    };
+    let mut emotion_time: Vec<(Emotion, f64)> =
+        Emotion::ALL.iter().map(|emotion| (emotion.clone(), 0.0)).collect();
+    let mut known_actions: Vec<String> = Vec::new();
+    let mut rng = SplitMix(config.seed);
+    for _ in 0..config.runs {
+        let mut runner = make_runner();
+        let agent = &runner.agent;
+        let palette: Vec<Emotion> = agent.emotions.get_palette().emotions().cloned().collect();
+        for emotion in &palette {
+            if !emotion_time.iter().any(|(e, _)| e == emotion) {
+                emotion_time.push((emotion.clone(), 0.0));
+            }
+        }
+        let proposed = agent.emotions.get_action_registry().actions().map(str::to_string);
+        let own = agent.intelligence.get_actions().iter().map(|action| action.name.clone());
+        for action in proposed.chain(own) {
+            if !known_actions.contains(&action) {
+                known_actions.push(action);
+            }
+        }
+        for _ in 0..config.steps {
+            if let Some(step) = config.random_step(&mut rng, &palette) {
+                let is_line = matches!(step, Step::Say(_));
+                // Random steps are never expectations, so applying them cannot fail.
+                let _ = runner.apply(&step);
+                if is_line && runner.get_last_output().conversation_end.is_some() {
+                    report.conversations_ended += 1;
+                }
+            }
+            runner.agent.tick(config.step_seconds);
+
+            let agent = &mut runner.agent;
+            let emotion = agent.emotions.get_emotion().clone();
+            if let Some((_, time)) = emotion_time.iter_mut().find(|(e, _)| *e == emotion) {
+                *time += config.step_seconds;
+            }
+            let state = agent.intelligence.get_current_state().to_string();
+            *report.state_time.entry(state).or_default() += config.step_seconds;
+            let action = agent.decide();
+            *report.actions.entry(action).or_default() += 1;
+            report.decisions += 1;
+        }
+    }
+
+    report.unreached_emotions = emotion_time
+        .iter()
+        .filter(|(_, time)| *time == 0.0)
+        .map(|(emotion, _)| emotion.clone())
+        .collect();
+    emotion_time.retain(|(_, time)| *time > 0.0);
+    emotion_time.sort_by(|a, b| b.1.total_cmp(&a.1));
+    report.emotion_time = emotion_time;
+    report.unreached_states = config
+        .states
+        .iter()
+        .filter(|state| !report.state_time.contains_key(state.as_str()))
+        .cloned()
+        .collect();
+    known_actions.sort();
+    report.unchosen_actions = known_actions
+        .into_iter()
+        .filter(|action| !report.actions.contains_key(action))
+        .collect();
+    report
+}
+
+/// Runs simulations of an authored NPC, built fresh from its definition for each run.
+pub fn simulate_definition(
+    definition: &NpcDefinition,
+    config: &SimulationConfig,
+) -> SimulationReport {
+    simulate(config, || {
+        let prompt = PromptBuilder::new(definition.persona.clone());
+        ScenarioRunner::new(definition.build(), prompt, Box::new(MockProvider::new()))
+    })
+}
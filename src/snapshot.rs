@@ -0,0 +1,368 @@
+//! # Snapshot Module
+//!
+//! This module saves and loads anything persistable in a compact form. JSON is readable but
+//! slow and large for big agents, so snapshots can also be written as bincode and, with the
+//! `compression` feature, compressed with zstd.
+//!
+//! Every snapshot starts with a small header: a magic number, the version of the snapshot
+//! format, how the payload is encoded and compressed, and the schema version of the data (see
+//! the `migration` module). Loading reads the header, so a game can switch formats without
+//! converting old saves, and plain JSON written before snapshots had headers still loads.
+//! Snapshots written by a newer format version are rejected rather than misread.
+//!
+//! Types are persisted through the `Persist` trait, which stamps each snapshot with the schema
+//! version of the type's `Migrator` and upgrades older snapshots on load. Athena's own persisted
+//! types implement it; a game's types can too, with migrations of their own. Snapshots written
+//! with the bare `encode` are stamped `UNVERSIONED`, so they are never mistaken for old data.
+
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::DebugReport;
+use crate::conversation::Conversation;
+use crate::definition::NpcDefinition;
+use crate::knowledge_graph::KnowledgeGraph;
+use crate::migration::Migrator;
+use crate::quest::QuestLog;
+
+/// The bytes every snapshot starts with.
+pub const MAGIC: [u8; 4] = *b"ATHN";
+
+/// The version of the snapshot format written by this build.
+pub const FORMAT_VERSION: u16 = 2;
+
+/// The schema version stamped into snapshots written without one (by `encode`).
+pub const UNVERSIONED: u32 = u32::MAX;
+
+/// The length of a version 1 header, in bytes: the magic number, the format version, the
+/// encoding, and the compression. Version 2 adds the schema version.
+const V1_HEADER_LEN: usize = 8;
+const HEADER_LEN: usize = 12;
+
+/// Represents how the payload of a snapshot is serialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Readable, and tolerant of added and removed fields.
+    #[default]
+    Json,
+    /// Compact and fast, but only readable by the same type definitions.
+    Binary,
+}
+
+/// Represents how the payload of a snapshot is compressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    #[default]
+    None,
+    /// zstd at a level from 1 (fastest) to 22 (smallest). Needs the `compression` feature.
+    Zstd(i32),
+}
+
+/// Represents how snapshots are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotOptions {
+    pub encoding: Encoding,
+    pub compression: Compression,
+}
+
+impl SnapshotOptions {
+    /// Writes uncompressed JSON.
+    pub fn json() -> Self {
+        SnapshotOptions::default()
+    }
+
+    /// Writes uncompressed bincode.
+    pub fn binary() -> Self {
+        SnapshotOptions {
+            encoding: Encoding::Binary,
+            compression: Compression::None,
+        }
+    }
+
+    /// Writes bincode compressed with zstd at its default level.
+    pub fn compact() -> Self {
+        SnapshotOptions {
+            encoding: Encoding::Binary,
+            compression: Compression::Zstd(3),
+        }
+    }
+}
+
+/// Represents the header of a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotHeader {
+    /// The version of the snapshot format it was written with.
+    pub version: u16,
+    pub encoding: Encoding,
+    /// Whether the payload is compressed with zstd.
+    pub compressed: bool,
+    /// The schema version of the data: 0 for snapshots written before schemas were stamped, or
+    /// `UNVERSIONED` for snapshots written without one.
+    pub schema: u32,
+}
+
+impl SnapshotHeader {
+    /// Reads the header at the start of a snapshot.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Self>, Box<dyn std::error::Error>>` - The header, `None` if the bytes
+    ///   have none (such as JSON written without one), or an error if the header is truncated
+    ///   or names an unknown encoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::snapshot::SnapshotHeader;
+    ///
+    /// assert!(SnapshotHeader::read(b"{}").unwrap().is_none());
+    /// let unknown = [b'A', b'T', b'H', b'N', 2, 0, 7, 0, 0, 0, 0, 0];
+    /// assert!(SnapshotHeader::read(&unknown).is_err());
+    /// ```
+    pub fn read(bytes: &[u8]) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if bytes.len() < V1_HEADER_LEN || bytes[..4] != MAGIC {
+            return Ok(None);
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        let schema = match version {
+            1 => 0,
+            _ => {
+                let schema = bytes.get(8..HEADER_LEN).ok_or("the snapshot header is truncated")?;
+                u32::from_le_bytes(schema.try_into()?)
+            }
+        };
+        let encoding = match bytes[6] {
+            0 => Encoding::Json,
+            1 => Encoding::Binary,
+            other => return Err(format!("unknown snapshot encoding {}", other).into()),
+        };
+        Ok(Some(SnapshotHeader {
+            version,
+            encoding,
+            compressed: bytes[7] != 0,
+            schema,
+        }))
+    }
+
+    /// Gets the size of the header, in bytes.
+    pub fn size(&self) -> usize {
+        match self.version {
+            1 => V1_HEADER_LEN,
+            _ => HEADER_LEN,
+        }
+    }
+
+    /// Writes the header as bytes.
+    fn to_bytes(self) -> [u8; HEADER_LEN] {
+        let encoding = match self.encoding {
+            Encoding::Json => 0,
+            Encoding::Binary => 1,
+        };
+        let mut bytes = [0; HEADER_LEN];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6] = encoding;
+        bytes[7] = u8::from(self.compressed);
+        bytes[8..].copy_from_slice(&self.schema.to_le_bytes());
+        bytes
+    }
+}
+
+/// Serializes a value into a snapshot, stamped `UNVERSIONED`. Types persisted across game
+/// versions should be saved through `Persist`, which stamps their schema version.
+///
+/// # Arguments
+///
+/// * `value` - The value to save.
+/// * `options` - How to encode and compress it.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, Box<dyn std::error::Error>>` - The snapshot, or an error if the value does
+///   not serialize or compression was asked for without the `compression` feature.
+///
+/// # Examples
+///
+/// ```
+/// use athena::conversation::Conversation;
+/// use athena::snapshot::{decode, encode, SnapshotHeader, SnapshotOptions};
+///
+/// let mut conversation = Conversation::new("blacksmith", "player_1");
+/// conversation.add_turn("player_1", "Hello!");
+///
+/// let json = encode(&conversation, SnapshotOptions::json()).unwrap();
+/// let binary = encode(&conversation, SnapshotOptions::binary()).unwrap();
+/// assert!(binary.len() < json.len());
+/// assert!(!SnapshotHeader::read(&binary).unwrap().unwrap().compressed);
+///
+/// let loaded: Conversation = decode(&binary).unwrap();
+/// assert_eq!(loaded, conversation);
+/// ```
+pub fn encode<T: Serialize + ?Sized>(
+    value: &T,
+    options: SnapshotOptions,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    encode_versioned(value, options, UNVERSIONED)
+}
+
+/// Serializes a value into a snapshot like `encode`, stamping it with the schema version of
+/// its data.
+pub fn encode_versioned<T: Serialize + ?Sized>(
+    value: &T,
+    options: SnapshotOptions,
+    schema: u32,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let payload = match options.encoding {
+        Encoding::Json => serde_json::to_vec(value)?,
+        Encoding::Binary => bincode::serialize(value)?,
+    };
+    let header = SnapshotHeader {
+        version: FORMAT_VERSION,
+        encoding: options.encoding,
+        compressed: options.compression != Compression::None,
+        schema,
+    };
+    let mut bytes = header.to_bytes().to_vec();
+    match options.compression {
+        Compression::None => bytes.extend_from_slice(&payload),
+        Compression::Zstd(level) => bytes.extend_from_slice(&compress(&payload, level)?),
+    }
+    Ok(bytes)
+}
+
+/// Deserializes a value from a snapshot written by `encode` with any options, or from plain
+/// JSON without a header.
+///
+/// # Returns
+///
+/// * `Result<T, Box<dyn std::error::Error>>` - The value, or an error if the snapshot is
+///   corrupt, was written by a newer format version, or is compressed and the `compression`
+///   feature is off.
+///
+/// # Examples
+///
+/// ```
+/// use athena::conversation::Conversation;
+/// use athena::snapshot::decode;
+///
+/// let legacy = br#"{"npc_id": "blacksmith", "player_id": "player_1", "turns": []}"#;
+/// let conversation: Conversation = decode(legacy).unwrap();
+/// assert_eq!(conversation.npc_id, "blacksmith");
+/// ```
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+    let (header, payload) = unpack(bytes)?;
+    match header.map_or(Encoding::Json, |header| header.encoding) {
+        Encoding::Json => Ok(serde_json::from_slice(&payload)?),
+        Encoding::Binary => Ok(bincode::deserialize(&payload)?),
+    }
+}
+
+/// Splits a snapshot into its header, if it has one, and its decompressed payload.
+///
+/// # Returns
+///
+/// * `Result<(Option<SnapshotHeader>, Vec<u8>), Box<dyn std::error::Error>>` - The header and
+///   payload, or an error if the snapshot was written by a newer format version or cannot be
+///   decompressed.
+pub fn unpack(
+    bytes: &[u8],
+) -> Result<(Option<SnapshotHeader>, Vec<u8>), Box<dyn std::error::Error>> {
+    let Some(header) = SnapshotHeader::read(bytes)? else {
+        return Ok((None, bytes.to_vec()));
+    };
+    if header.version > FORMAT_VERSION {
+        return Err(format!(
+            "snapshot format version {} is newer than the supported version {}",
+            header.version, FORMAT_VERSION
+        )
+        .into());
+    }
+    let payload = &bytes[header.size()..];
+    let payload = if header.compressed { decompress(payload)? } else { payload.to_vec() };
+    Ok((Some(header), payload))
+}
+
+#[cfg(feature = "compression")]
+fn compress(payload: &[u8], level: i32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(zstd::encode_all(payload, level)?)
+}
+
+#[cfg(not(feature = "compression"))]
+fn compress(_payload: &[u8], _level: i32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Err("zstd compression needs the `compression` feature".into())
+}
+
+#[cfg(feature = "compression")]
+fn decompress(payload: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(zstd::decode_all(payload)?)
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress(_payload: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Err("the snapshot is compressed with zstd, which needs the `compression` feature".into())
+}
+
+/// Saves and loads a type as snapshots, through the migrator of its schema.
+pub trait Persist: Serialize + DeserializeOwned {
+    /// Gets the migrator of the type's schema, without migrations (schema version 0) by default.
+    /// Whenever the type's shape changes, a migration is added here.
+    fn migrator() -> Migrator {
+        Migrator::new()
+    }
+
+    /// Serializes the value into a snapshot stamped with its schema version (see
+    /// `Migrator::encode`).
+    fn to_snapshot(&self, options: SnapshotOptions) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Self::migrator().encode(self, options)
+    }
+
+    /// Deserializes a value from a snapshot, upgrading it if it has an older schema version (see
+    /// `Migrator::decode`).
+    fn from_snapshot(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::migrator().decode(bytes)
+    }
+
+    /// Writes the value to a file as a snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::snapshot::{Persist, SnapshotOptions};
+    ///
+    /// let agent = NpcAgent::new("guard", vec![]);
+    /// let report = agent.debug_report();
+    /// let path = std::env::temp_dir().join("athena_snapshot_doctest.bin");
+    /// report.save_snapshot(&path, SnapshotOptions::binary()).unwrap();
+    ///
+    /// let loaded = athena::agent::DebugReport::load_snapshot(&path).unwrap();
+    /// assert_eq!(loaded.id, report.id);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    fn save_snapshot(
+        &self,
+        path: impl AsRef<Path>,
+        options: SnapshotOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, self.to_snapshot(options)?)?;
+        Ok(())
+    }
+
+    /// Reads a value from a snapshot file.
+    fn load_snapshot(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::from_snapshot(&fs::read(path)?)
+    }
+}
+
+impl Persist for Conversation {}
+
+impl Persist for DebugReport {}
+
+impl Persist for KnowledgeGraph {}
+
+impl Persist for NpcDefinition {}
+
+impl Persist for QuestLog {}
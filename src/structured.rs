@@ -0,0 +1,448 @@
+//! # Structured Module
+//!
+//! This module gets structured (JSON) output from the language model reliably. Where the server
+//! supports it, generation is constrained natively: to a schema if the server accepts JSON
+//! schemas, or to any JSON object in JSON mode. Otherwise the expected format is spelled out in
+//! the prompt, and the reply is repaired before parsing: code fences and surrounding prose are
+//! stripped, trailing commas and typographic quotes are fixed, and output cut off mid-object is
+//! closed.
+//!
+//! In a pipeline, a `JsonStage` asks for JSON and repairs the reply, having it regenerated if it
+//! cannot be parsed. `generate_json` does the same for a client outside a pipeline.
+//!
+//! Parsed values can be checked against a schema with `schema_errors`, e.g. the arguments of
+//! tool calls before they are run (see the `tools` module).
+
+use serde_json::Value;
+
+#[cfg(feature = "network")]
+use serde::de::DeserializeOwned;
+
+#[cfg(feature = "network")]
+use crate::dialogue_generation::{Capabilities, DialogueClient, RequestType, ResponseFormat};
+#[cfg(feature = "network")]
+use crate::pipeline::{Phase, PipelineState, Regenerate, Stage, StageError};
+
+/// Builds the prompt instruction asking for JSON output.
+///
+/// # Arguments
+///
+/// * `schema` - The JSON schema the output must match, if any.
+pub fn format_instruction(schema: Option<&Value>) -> String {
+    match schema {
+        Some(schema) => format!(
+            "Respond only with a JSON object matching this JSON schema, without other text:\n{}",
+            schema
+        ),
+        None => "Respond only with a JSON object, without any other text.".to_string(),
+    }
+}
+
+/// Cuts the JSON value out of a reply, closing it if the reply was cut off.
+fn extract(text: &str) -> Option<String> {
+    let start = text.find(['{', '['])?;
+    let mut closers = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut result = String::new();
+    for c in text[start..].chars() {
+        result.push(c);
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' => {
+                closers.pop();
+                if closers.is_empty() {
+                    return Some(result);
+                }
+            }
+            _ => {}
+        }
+    }
+    // The reply was cut off: close the open string, drop a dangling separator, and close the
+    // open containers.
+    if in_string {
+        result.push('"');
+    }
+    let trimmed = result.trim_end().trim_end_matches([',', ':']).len();
+    result.truncate(trimmed);
+    result.extend(closers.iter().rev());
+    Some(result)
+}
+
+/// Removes commas directly followed by a closing bracket, outside strings.
+fn remove_trailing_commas(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut result = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if *c == '"' {
+            in_string = true;
+        } else if *c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']') | None) {
+                continue;
+            }
+        }
+        result.push(*c);
+    }
+    result
+}
+
+/// Parses the JSON value in a model reply, repairing common defects.
+///
+/// # Arguments
+///
+/// * `text` - The model's reply.
+///
+/// # Returns
+///
+/// The parsed value, or `None` if no JSON could be recovered.
+///
+/// # Examples
+///
+/// ```
+/// use athena::structured::repair_json;
+///
+/// let reply = "Sure! Here it is:\n```json\n{\u{201c}mood\u{201d}: \"wary\", \"items\": [1, 2,],}\n```";
+/// assert_eq!(repair_json(reply).unwrap()["items"][1], 2);
+///
+/// // A reply cut off by the token limit is closed.
+/// let truncated = r#"{"line": "Welcome to the Rusty Anchor", "tags": ["greet"#;
+/// assert_eq!(repair_json(truncated).unwrap()["tags"][0], "greet");
+///
+/// assert!(repair_json("I don't know.").is_none());
+/// ```
+pub fn repair_json(text: &str) -> Option<Value> {
+    if let Ok(value) = serde_json::from_str(text.trim()) {
+        return Some(value);
+    }
+    let normalized = text.replace(['\u{201c}', '\u{201d}'], "\"");
+    let extracted = extract(&normalized)?;
+    serde_json::from_str(&remove_trailing_commas(&extracted)).ok()
+}
+
+/// Checks a value against a JSON schema, returning what does not match.
+///
+/// Only the common subset of JSON Schema is understood: `type`, `enum`, `minimum`, `maximum`,
+/// `maxLength`, `properties`, `required`, `additionalProperties: false`, `items`, and
+/// `maxItems`. Other keywords are ignored.
+///
+/// # Arguments
+///
+/// * `value` - The value to check.
+/// * `schema` - The JSON schema it must match.
+///
+/// # Returns
+///
+/// A description of each mismatch, with the path to the offending value; empty if it matches.
+///
+/// # Examples
+///
+/// ```
+/// use athena::structured::schema_errors;
+/// use serde_json::json;
+///
+/// let schema = json!({
+///     "type": "object",
+///     "properties": { "amount": { "type": "integer", "minimum": 1, "maximum": 100 } },
+///     "required": ["amount"],
+///     "additionalProperties": false
+/// });
+/// assert!(schema_errors(&json!({ "amount": 20 }), &schema).is_empty());
+/// assert_eq!(
+///     schema_errors(&json!({ "amount": 999999, "vip": true }), &schema),
+///     ["amount: 999999 is above the maximum of 100", "vip: unexpected property"]
+/// );
+/// ```
+pub fn schema_errors(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check_schema(value, schema, "", &mut errors);
+    errors
+}
+
+/// Gets the name of a value's JSON Schema type.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Checks a value against a schema, adding mismatches under `path` to `errors`.
+fn check_schema(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = |message: String| {
+        if path.is_empty() {
+            message
+        } else {
+            format!("{}: {}", path, message)
+        }
+    };
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let actual = type_name(value);
+        if actual != expected && !(expected == "number" && actual == "integer") {
+            errors.push(at(format!("expected {}, found {}", expected, actual)));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(at(format!("{} is not one of the allowed values", value)));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                errors.push(at(format!("{} is below the minimum of {}", value, minimum)));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                errors.push(at(format!("{} is above the maximum of {}", value, maximum)));
+            }
+        }
+    }
+    if let (Some(text), Some(max)) = (value.as_str(), schema.get("maxLength")) {
+        if max.as_u64().is_some_and(|max| text.chars().count() as u64 > max) {
+            errors.push(at(format!("longer than {} characters", max)));
+        }
+    }
+    if let Some(items) = value.as_array() {
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if items.len() as u64 > max {
+                errors.push(at(format!("more than {} items", max)));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                check_schema(item, item_schema, &format!("{}[{}]", path, i), errors);
+            }
+        }
+    }
+    if let Some(object) = value.as_object() {
+        let child = |key: &str| {
+            if path.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", path, key)
+            }
+        };
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let required = schema.get("required").and_then(Value::as_array);
+        for key in required.into_iter().flatten().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                errors.push(format!("{}: missing required property", child(key)));
+            }
+        }
+        let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+        for (key, property) in object {
+            match properties.and_then(|properties| properties.get(key)) {
+                Some(property_schema) => {
+                    check_schema(property, property_schema, &child(key), errors)
+                }
+                None if closed => errors.push(format!("{}: unexpected property", child(key))),
+                None => {}
+            }
+        }
+    }
+}
+
+/// A stage that gets JSON output. Added to the prompt building phase (after the stage that
+/// renders the prompt), it asks for JSON in the prompt and in the request's format; added to the
+/// validation phase, it repairs the reply into compact JSON, having it regenerated if it cannot
+/// be parsed and failing the request once the attempts run out.
+///
+/// # Examples
+///
+/// ```
+/// use athena::dialogue_generation::{RequestType, ResponseFormat};
+/// use athena::pipeline::{Phase, PipelineState, Regenerate, Stage};
+/// use athena::structured::JsonStage;
+///
+/// let mut stage = JsonStage::new(None, None);
+/// let mut state = PipelineState::new(RequestType::Story, "Name a tavern.");
+/// state.phase = Phase::PromptBuild;
+/// stage.process(&mut state).unwrap();
+/// assert!(state.prompt.ends_with("Respond only with a JSON object, without any other text."));
+/// assert_eq!(state.format, ResponseFormat::JsonObject);
+///
+/// state.phase = Phase::Validation;
+/// state.reply = "Sure! ```json\n{\"name\": \"The Prancing Pony\",}\n```".to_string();
+/// stage.process(&mut state).unwrap();
+/// assert_eq!(state.reply, r#"{"name":"The Prancing Pony"}"#);
+///
+/// state.reply = "The Prancing Pony".to_string();
+/// state.attempts_left = 1;
+/// assert!(stage.process(&mut state).unwrap_err().is::<Regenerate>());
+/// ```
+#[cfg(feature = "network")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonStage {
+    /// The JSON schema the output must match, if any.
+    pub schema: Option<Value>,
+    /// The format the reply is asked for in.
+    pub format: ResponseFormat,
+}
+
+#[cfg(feature = "network")]
+impl JsonStage {
+    /// Creates a stage, picking the native format from a server's probed capabilities: a JSON
+    /// schema if the server supports it and a schema is given, else JSON mode. Unprobed servers
+    /// are assumed to support JSON mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The JSON schema the output must match, if any.
+    /// * `capabilities` - What the server supports (see `DialogueClient::get_capabilities`).
+    pub fn new(schema: Option<Value>, capabilities: Option<&Capabilities>) -> Self {
+        let format = match (capabilities, &schema) {
+            (Some(c), Some(schema)) if c.json_schema => ResponseFormat::JsonSchema {
+                name: "response".to_string(),
+                schema: schema.clone(),
+            },
+            (Some(c), _) if !c.json_mode => ResponseFormat::Text,
+            _ => ResponseFormat::JsonObject,
+        };
+        JsonStage { schema, format }
+    }
+}
+
+#[cfg(feature = "network")]
+impl Stage for JsonStage {
+    fn process(&mut self, state: &mut PipelineState) -> Result<(), StageError> {
+        let instruction = format_instruction(self.schema.as_ref());
+        match state.phase {
+            Phase::PromptBuild => {
+                // JSON mode requires the prompt to ask for JSON, so the instruction is always
+                // part of it.
+                state.prompt = format!("{}\n\n{}", state.prompt, instruction);
+                state.format = self.format.clone();
+                Ok(())
+            }
+            Phase::Validation => match repair_json(&state.reply) {
+                Some(value) => {
+                    state.reply = value.to_string();
+                    Ok(())
+                }
+                None if state.attempts_left > 0 => {
+                    let directive = format!("Your last reply was not valid JSON. {}", instruction);
+                    Err(Regenerate::new(&directive).into())
+                }
+                None => Err(format!("The reply is not JSON: {}", state.reply).into()),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "json"
+    }
+}
+
+/// Generates a JSON value with a client outside a pipeline, the way a `JsonStage` does in one.
+///
+/// If a native request fails, it is retried asking for JSON in the prompt only, without counting
+/// as an attempt.
+///
+/// # Arguments
+///
+/// * `client` - The client used for generation.
+/// * `request_type` - The kind of request.
+/// * `prompt` - The prompt to send.
+/// * `schema` - The JSON schema the output must match, if any.
+/// * `max_attempts` - How many replies may be generated in total.
+///
+/// # Returns
+///
+/// * `Result<Value, Box<dyn std::error::Error>>` - The value, or an error if no attempt produced
+///   parseable JSON.
+#[cfg(feature = "network")]
+pub async fn generate_json(
+    client: &DialogueClient,
+    request_type: RequestType,
+    prompt: &str,
+    schema: Option<&Value>,
+    max_attempts: usize,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut stage = JsonStage::new(schema.cloned(), client.get_capabilities());
+    let mut state = PipelineState::new(request_type, prompt);
+    state.phase = Phase::PromptBuild;
+    stage.process(&mut state).map_err(|error| error as Box<dyn std::error::Error>)?;
+    let prompt = state.prompt.clone();
+    let max_attempts = max_attempts.max(1);
+    for attempt in 0..max_attempts {
+        let sent = client.send_with_format(request_type, &state.prompt, &state.format).await;
+        let response = match sent {
+            Ok(response) => response,
+            Err(_) if state.format != ResponseFormat::Text => {
+                state.format = ResponseFormat::Text;
+                client
+                    .send_with_format(request_type, &state.prompt, &state.format)
+                    .await
+                    .map_err(|error| error as Box<dyn std::error::Error>)?
+            }
+            Err(error) => return Err(error as Box<dyn std::error::Error>),
+        };
+        state.reply = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .unwrap_or_default();
+        state.phase = Phase::Validation;
+        state.attempts_left = max_attempts - attempt - 1;
+        match stage.process(&mut state) {
+            Ok(()) => return Ok(serde_json::from_str(&state.reply)?),
+            Err(error) => match error.downcast::<Regenerate>() {
+                Ok(regenerate) => state.prompt = format!("{}\n{}", prompt, regenerate.directive),
+                Err(error) => return Err(error as Box<dyn std::error::Error>),
+            },
+        }
+    }
+    Err("No attempt was made".into())
+}
+
+/// Generates JSON and deserializes it into a type.
+///
+/// See `generate_json`; a reply that parses but does not deserialize counts as a failed attempt.
+#[cfg(feature = "network")]
+pub async fn generate_structured<T: DeserializeOwned>(
+    client: &DialogueClient,
+    request_type: RequestType,
+    prompt: &str,
+    schema: Option<&Value>,
+    max_attempts: usize,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let mut last_error: Box<dyn std::error::Error> = "No attempt was made".into();
+    for _ in 0..max_attempts.max(1) {
+        let value = generate_json(client, request_type, prompt, schema, 1).await?;
+        match serde_json::from_value(value) {
+            Ok(parsed) => return Ok(parsed),
+            Err(error) => last_error = error.into(),
+        }
+    }
+    Err(last_error)
+}
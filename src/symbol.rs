@@ -0,0 +1,200 @@
+//! # Symbol Module
+//!
+//! This module provides `Symbol`, an interned string used for identifiers that are compared and
+//! cloned often: entity IDs, relation types, and NPC states. Interning means every distinct
+//! string is allocated once; cloning a symbol is a reference-count increment, and comparing two
+//! symbols is a single pointer comparison.
+//!
+//! Strings no symbol refers to any more are dropped from the table once it has doubled in size
+//! since it was last pruned, so interning runtime IDs (players, gossip, quest outcomes) on a
+//! long-running server does not grow memory without bound.
+
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The size the table of interned strings is first pruned at.
+const MIN_PRUNE_AT: usize = 1024;
+
+/// Represents the table of interned strings.
+struct Interner {
+    strings: HashSet<Arc<str>>,
+    /// The size at which strings no symbol refers to are dropped.
+    prune_at: usize,
+}
+
+impl Interner {
+    /// Drops the strings only the table refers to, once the table has grown enough since it was
+    /// last pruned for the pass to pay off. A string only the table holds has no symbol, and
+    /// symbols are only created under the table's lock, so no live symbol loses its string.
+    fn prune(&mut self) {
+        if self.strings.len() < self.prune_at {
+            return;
+        }
+        self.strings.retain(|string| Arc::strong_count(string) > 1);
+        self.prune_at = (2 * self.strings.len()).max(MIN_PRUNE_AT);
+    }
+}
+
+/// The global table of interned strings.
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(|| {
+        Mutex::new(Interner {
+            strings: HashSet::new(),
+            prune_at: MIN_PRUNE_AT,
+        })
+    })
+}
+
+/// Represents an interned, immutable string.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// Interns a string, returning the shared symbol for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The string to intern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::symbol::Symbol;
+    /// let a = Symbol::new("guard");
+    /// let b = Symbol::new("guard");
+    /// assert_eq!(a, b);
+    /// assert_eq!(a, "guard");
+    ///
+    /// // Interning many short-lived IDs prunes the dead ones, but never a live symbol.
+    /// for visitor in 0..5_000 {
+    ///     Symbol::new(&format!("visitor_{}", visitor));
+    /// }
+    /// assert_eq!(Symbol::new("guard"), a);
+    /// ```
+    pub fn new(value: &str) -> Self {
+        let mut table = interner().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(existing) = table.strings.get(value) {
+            return Symbol(existing.clone());
+        }
+        table.prune();
+        let interned: Arc<str> = Arc::from(value);
+        table.strings.insert(interned.clone());
+        Symbol(interned)
+    }
+
+    /// Returns the symbol as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    /// Compares the interned pointers. Symbols are only created through the interner, which
+    /// keeps a string for as long as any symbol refers to it, so equal strings always share one
+    /// pointer.
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        &*self.0 == *other
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&*self.0, f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Self {
+        Symbol::new(value)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Self {
+        Symbol::new(&value)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(value: &String) -> Self {
+        Symbol::new(value)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Symbol::new(&value))
+    }
+}
@@ -0,0 +1,377 @@
+//! # Tools Module
+//!
+//! This module lets the language model act on the game through tool calls, without trusting it.
+//! The model may ask for any tool with any arguments, including ones it made up, so every call
+//! passes through a `ToolSandbox` before the game runs it:
+//!
+//! - The tool must be registered, and allowed for the NPC making the call. NPCs are allowed no
+//!   tools until the game says otherwise.
+//! - The arguments must match the tool's JSON schema (see `structured::schema_errors`), so a
+//!   hallucinated call cannot grant the player 999999 gold.
+//! - Each NPC may call a tool only so often per minute, if the tool has a rate limit.
+//! - In dry-run mode, calls are checked but never run, for testing prompts safely.
+//!
+//! Every call is logged with its outcome, whether it ran or not.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::dialogue_generation::ApiToolCall;
+use crate::quota::{Quota, QuotaStatus, QuotaTracker};
+use crate::structured::schema_errors;
+use crate::symbol::Symbol;
+
+/// Represents a call of a tool requested by the language model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// The ID the API gave the call, if any.
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+}
+
+impl TryFrom<ApiToolCall> for ToolCall {
+    type Error = serde_json::Error;
+
+    /// Parses the arguments. They are never repaired: a call whose arguments are not valid JSON
+    /// is rejected rather than guessed at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::ApiToolCall;
+    /// use athena::tools::ToolCall;
+    /// use serde_json::json;
+    ///
+    /// let api_call = |arguments: &str| -> ApiToolCall {
+    ///     serde_json::from_value(json!({
+    ///         "id": "call_1",
+    ///         "type": "function",
+    ///         "function": { "name": "give_gold", "arguments": arguments }
+    ///     })).unwrap()
+    /// };
+    /// let call = ToolCall::try_from(api_call(r#"{"amount": 5}"#)).unwrap();
+    /// assert_eq!(call.arguments, json!({ "amount": 5 }));
+    /// assert!(ToolCall::try_from(api_call(r#"{"amount": 5"#)).is_err());
+    /// assert!(ToolCall::try_from(api_call("")).is_err());
+    /// ```
+    fn try_from(call: ApiToolCall) -> Result<Self, Self::Error> {
+        Ok(ToolCall {
+            arguments: serde_json::from_str(&call.function.arguments)?,
+            id: call.id,
+            name: call.function.name,
+        })
+    }
+}
+
+impl ToolCall {
+    /// Creates a new call, e.g. for testing.
+    pub fn new(name: &str, arguments: Value) -> Self {
+        ToolCall {
+            id: String::new(),
+            name: name.to_string(),
+            arguments,
+        }
+    }
+}
+
+/// Represents a tool the language model may call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    /// The JSON schema of the arguments.
+    pub parameters: Value,
+    /// How many times an NPC may call the tool per minute, or `None` for no limit.
+    #[serde(default)]
+    pub per_minute: Option<usize>,
+}
+
+impl ToolSpec {
+    /// Creates a new tool without a rate limit.
+    pub fn new(name: &str, description: &str, parameters: Value) -> Self {
+        ToolSpec {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+            per_minute: None,
+        }
+    }
+
+    /// Limits how many times an NPC may call the tool per minute.
+    pub fn with_rate_limit(mut self, per_minute: usize) -> Self {
+        self.per_minute = Some(per_minute);
+        self
+    }
+
+    /// Describes the tool in the chat completions API's format, for the request's `tools`.
+    pub fn to_request_field(&self) -> Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            }
+        })
+    }
+}
+
+/// Represents why a tool call was not run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ToolRejection {
+    /// No tool of that name is registered.
+    UnknownTool,
+    /// The NPC is not allowed the tool.
+    NotAllowed,
+    /// The arguments do not match the tool's schema.
+    InvalidArguments(Vec<String>),
+    /// The NPC has called the tool too often; a call is allowed again after the given number of
+    /// seconds.
+    RateLimited { retry_after: f64 },
+}
+
+/// Represents what became of a tool call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ToolOutcome {
+    /// The call was run, and the game's handler returned this result.
+    Executed(Result<Value, String>),
+    /// The call passed every check, but was not run because the sandbox is in dry-run mode.
+    DryRun,
+    Rejected(ToolRejection),
+}
+
+/// Represents a logged tool call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    /// The ID of the NPC the call was made for.
+    pub npc_id: String,
+    pub call: ToolCall,
+    pub outcome: ToolOutcome,
+    /// When the call was made, in seconds.
+    pub time: f64,
+}
+
+/// Checks, runs, and logs the tool calls of NPCs.
+#[derive(Debug, Clone, Default)]
+pub struct ToolSandbox {
+    tools: HashMap<String, ToolSpec>,
+    /// The tools each NPC is allowed.
+    allowed: HashMap<Symbol, HashSet<String>>,
+    /// Whether calls are checked but not run.
+    dry_run: bool,
+    /// The rate limits, tracked per NPC and tool.
+    limits: QuotaTracker,
+    log: Vec<ToolInvocation>,
+}
+
+impl ToolSandbox {
+    /// Creates a new sandbox without tools.
+    pub fn new() -> Self {
+        ToolSandbox::default()
+    }
+
+    /// Registers a tool, replacing one with the same name.
+    pub fn register(&mut self, tool: ToolSpec) {
+        self.tools.insert(tool.name.clone(), tool);
+    }
+
+    /// Gets a registered tool.
+    pub fn get_tool(&self, name: &str) -> Option<&ToolSpec> {
+        self.tools.get(name)
+    }
+
+    /// Allows an NPC the given tools.
+    pub fn allow<'a>(&mut self, npc_id: &str, tools: impl IntoIterator<Item = &'a str>) {
+        let allowed = self.allowed.entry(Symbol::new(npc_id)).or_default();
+        allowed.extend(tools.into_iter().map(str::to_string));
+    }
+
+    /// Withdraws a tool from an NPC.
+    pub fn forbid(&mut self, npc_id: &str, tool: &str) {
+        if let Some(allowed) = self.allowed.get_mut(npc_id) {
+            allowed.remove(tool);
+        }
+    }
+
+    /// Returns true if an NPC is allowed a tool.
+    pub fn is_allowed(&self, npc_id: &str, tool: &str) -> bool {
+        self.allowed.get(npc_id).is_some_and(|allowed| allowed.contains(tool))
+    }
+
+    /// Describes the registered tools an NPC is allowed, in the chat completions API's format,
+    /// sorted by name. Offering only these keeps the model from asking for the others. They are
+    /// sent with a request as its `GenerationParams::tools`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::GenerationParams;
+    /// use athena::tools::{ToolSandbox, ToolSpec};
+    /// use serde_json::json;
+    ///
+    /// let mut sandbox = ToolSandbox::new();
+    /// sandbox.register(ToolSpec::new("open_gate", "Open the town gate.", json!({})));
+    /// sandbox.register(ToolSpec::new("give_gold", "Give the player gold.", json!({})));
+    /// sandbox.allow("hale", ["open_gate"]);
+    ///
+    /// let params = GenerationParams {
+    ///     tools: Some(sandbox.request_tools("hale")),
+    ///     ..Default::default()
+    /// };
+    /// let tools = params.tools.unwrap();
+    /// assert_eq!(tools.len(), 1);
+    /// assert_eq!(tools[0]["function"]["name"], "open_gate");
+    /// ```
+    pub fn request_tools(&self, npc_id: &str) -> Vec<Value> {
+        let mut tools: Vec<&ToolSpec> =
+            self.tools.values().filter(|tool| self.is_allowed(npc_id, &tool.name)).collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools.iter().map(|tool| tool.to_request_field()).collect()
+    }
+
+    /// Sets whether calls are only checked and logged, but never run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::dialogue_generation::ApiToolCall;
+    /// use athena::tools::{ToolCall, ToolOutcome, ToolRejection, ToolSandbox, ToolSpec};
+    /// use serde_json::json;
+    ///
+    /// let mut sandbox = ToolSandbox::new();
+    /// let open = ToolSpec::new("open_gate", "Open the town gate.", json!({ "type": "object" }));
+    /// sandbox.register(open.with_rate_limit(1));
+    /// sandbox.allow("hale", ["open_gate"]);
+    /// sandbox.set_dry_run(true);
+    ///
+    /// // Tool calls come from the API with their arguments as a string.
+    /// let api_call: ApiToolCall = serde_json::from_value(json!({
+    ///     "id": "call_1",
+    ///     "type": "function",
+    ///     "function": { "name": "open_gate", "arguments": "{}" }
+    /// })).unwrap();
+    /// let call = ToolCall::try_from(api_call).unwrap();
+    ///
+    /// let outcome = sandbox.invoke("hale", call.clone(), 0.0, |_| panic!("never run"));
+    /// assert_eq!(outcome, ToolOutcome::DryRun);
+    /// let outcome = sandbox.invoke("hale", call, 15.0, |_| panic!("never run"));
+    /// assert_eq!(
+    ///     outcome,
+    ///     ToolOutcome::Rejected(ToolRejection::RateLimited { retry_after: 45.0 })
+    /// );
+    /// ```
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Returns true if calls are only checked and logged, but never run.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Checks a tool call and, if it passes, runs it with the game's handler. The call is logged
+    /// either way.
+    ///
+    /// # Arguments
+    ///
+    /// * `npc_id` - The ID of the NPC the call was made for.
+    /// * `call` - The call requested by the language model.
+    /// * `now` - The current time in seconds, from any fixed starting point.
+    /// * `handler` - Runs the checked call in the game, returning its result or an error.
+    ///
+    /// # Returns
+    ///
+    /// What became of the call, to be sent back to the model as the tool's result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::tools::{ToolCall, ToolOutcome, ToolRejection, ToolSandbox, ToolSpec};
+    /// use serde_json::json;
+    ///
+    /// let mut sandbox = ToolSandbox::new();
+    /// let schema = json!({
+    ///     "type": "object",
+    ///     "properties": { "amount": { "type": "integer", "minimum": 1, "maximum": 50 } },
+    ///     "required": ["amount"]
+    /// });
+    /// sandbox.register(ToolSpec::new("give_gold", "Give the player gold.", schema));
+    /// sandbox.allow("brom", ["give_gold"]);
+    ///
+    /// let mut gold = 0;
+    /// let mut give = |call: &ToolCall| {
+    ///     gold += call.arguments["amount"].as_i64().unwrap();
+    ///     Ok(json!("done"))
+    /// };
+    /// let outcome = sandbox.invoke("brom", ToolCall::new("give_gold", json!({ "amount": 20 })),
+    ///                              0.0, &mut give);
+    /// assert_eq!(outcome, ToolOutcome::Executed(Ok(json!("done"))));
+    ///
+    /// let greedy = ToolCall::new("give_gold", json!({ "amount": 999999 }));
+    /// let outcome = sandbox.invoke("brom", greedy, 1.0, &mut give);
+    /// assert!(matches!(outcome, ToolOutcome::Rejected(ToolRejection::InvalidArguments(_))));
+    ///
+    /// let stranger = ToolCall::new("give_gold", json!({ "amount": 5 }));
+    /// let outcome = sandbox.invoke("mira", stranger, 2.0, &mut give);
+    /// assert_eq!(outcome, ToolOutcome::Rejected(ToolRejection::NotAllowed));
+    ///
+    /// assert_eq!(gold, 20);
+    /// assert_eq!(sandbox.get_log().len(), 3);
+    /// ```
+    pub fn invoke(
+        &mut self,
+        npc_id: &str,
+        call: ToolCall,
+        now: f64,
+        handler: impl FnOnce(&ToolCall) -> Result<Value, String>,
+    ) -> ToolOutcome {
+        let outcome = match self.check(npc_id, &call, now) {
+            Err(rejection) => ToolOutcome::Rejected(rejection),
+            Ok(()) if self.dry_run => ToolOutcome::DryRun,
+            Ok(()) => ToolOutcome::Executed(handler(&call)),
+        };
+        self.log.push(ToolInvocation {
+            npc_id: npc_id.to_string(),
+            call,
+            outcome: outcome.clone(),
+            time: now,
+        });
+        outcome
+    }
+
+    /// Checks a call against the allowlist, the tool's schema, and its rate limit, counting it
+    /// toward the rate limit if it passes.
+    fn check(&mut self, npc_id: &str, call: &ToolCall, now: f64) -> Result<(), ToolRejection> {
+        let tool = self.tools.get(&call.name).ok_or(ToolRejection::UnknownTool)?;
+        if !self.is_allowed(npc_id, &call.name) {
+            return Err(ToolRejection::NotAllowed);
+        }
+        let errors = schema_errors(&call.arguments, &tool.parameters);
+        if !errors.is_empty() {
+            return Err(ToolRejection::InvalidArguments(errors));
+        }
+        if let Some(per_minute) = tool.per_minute {
+            let key = format!("{}/{}", npc_id, call.name);
+            self.limits.set_quota(&key, Quota::unlimited().with_per_minute(per_minute));
+            if let QuotaStatus::RateLimited { retry_after } =
+                self.limits.try_acquire(&key, now)
+            {
+                return Err(ToolRejection::RateLimited { retry_after });
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the log of every call, oldest first.
+    pub fn get_log(&self) -> &[ToolInvocation] {
+        &self.log
+    }
+
+    /// Clears the log.
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+}
@@ -0,0 +1,266 @@
+//! # Topic Module
+//!
+//! This module tracks what a conversation is about. Conversations are tagged with active topics
+//! as lines mention their keywords, and each NPC has an interest level per topic derived from
+//! its personality and how much it knows about the topic. Topics that have been talked about
+//! too often become exhausted, which lets an NPC suggest a switch to something it cares about
+//! or decide that the conversation has run its course.
+
+use std::collections::HashMap;
+
+use crate::knowledge_graph::KnowledgeGraph;
+use crate::personality::Personality;
+use crate::symbol::Symbol;
+
+/// Represents the broad kind of a topic, which decides which personality trait drives interest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TopicKind {
+    /// The speakers themselves: family, feelings, history. Driven by agreeableness.
+    Personal,
+    /// Other people and gossip. Driven by extraversion.
+    Social,
+    /// Trade, craft, and duties. Driven by conscientiousness.
+    Work,
+    /// History, legends, and ideas. Driven by openness.
+    Lore,
+    /// Threats and troubles. Driven by neuroticism.
+    Danger,
+    /// Anything else. Neutral interest.
+    Other,
+}
+
+/// Represents a conversation topic.
+#[derive(Debug, Clone)]
+pub struct Topic {
+    pub id: Symbol,
+    pub kind: TopicKind,
+    /// Lowercase keywords whose mention tags a line with this topic.
+    pub keywords: Vec<String>,
+    /// The knowledge graph entity the topic is about, if any.
+    pub entity: Option<Symbol>,
+}
+
+impl Topic {
+    /// Creates a new topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique identifier for the topic.
+    /// * `kind` - The broad kind of the topic.
+    /// * `keywords` - Keywords whose mention tags a line with this topic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::topic::{Topic, TopicKind};
+    /// let topic = Topic::new("dragon", TopicKind::Danger, &["dragon", "wyrm"]);
+    /// assert!(topic.matches("I saw a DRAGON!"));
+    /// ```
+    pub fn new(id: &str, kind: TopicKind, keywords: &[&str]) -> Self {
+        Topic {
+            id: Symbol::new(id),
+            kind,
+            keywords: keywords.iter().map(|k| k.to_lowercase()).collect(),
+            entity: None,
+        }
+    }
+
+    /// Links the topic to a knowledge graph entity, so knowledge about it raises interest.
+    pub fn with_entity(mut self, entity_id: &str) -> Self {
+        self.entity = Some(Symbol::new(entity_id));
+        self
+    }
+
+    /// Returns true if the line mentions any of the topic's keywords.
+    pub fn matches(&self, line: &str) -> bool {
+        let lowered = line.to_lowercase();
+        self.keywords.iter().any(|keyword| lowered.contains(keyword.as_str()))
+    }
+
+    /// Computes how interested an NPC is in this topic (between 0.0 and 1.0).
+    ///
+    /// Interest is driven by the personality trait matching the topic's kind and, for topics
+    /// linked to an entity, by how many facts the NPC knows about it.
+    ///
+    /// # Arguments
+    ///
+    /// * `personality` - The personality of the NPC.
+    /// * `knowledge` - The knowledge graph of the NPC.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::knowledge_graph::KnowledgeGraph;
+    /// use athena::personality::Personality;
+    /// use athena::topic::{Topic, TopicKind};
+    ///
+    /// let mut personality = Personality::new();
+    /// personality.set_openness(0.9);
+    /// let lore = Topic::new("old_kings", TopicKind::Lore, &["king"]);
+    /// let work = Topic::new("prices", TopicKind::Work, &["price"]);
+    /// let knowledge = KnowledgeGraph::new();
+    /// assert!(lore.interest(&personality, &knowledge) > work.interest(&personality, &knowledge));
+    /// ```
+    pub fn interest(&self, personality: &Personality, knowledge: &KnowledgeGraph) -> f64 {
+        let trait_interest = match self.kind {
+            TopicKind::Personal => personality.agreeableness,
+            TopicKind::Social => personality.extraversion,
+            TopicKind::Work => personality.conscientiousness,
+            TopicKind::Lore => personality.openness,
+            TopicKind::Danger => personality.neuroticism,
+            TopicKind::Other => 0.5,
+        };
+        match &self.entity {
+            Some(entity) => {
+                let facts = knowledge.relationships_of(entity).count() as f64;
+                let knowledge_interest = 1.0 - (-facts / 5.0).exp();
+                0.6 * trait_interest + 0.4 * knowledge_interest
+            }
+            None => trait_interest,
+        }
+    }
+}
+
+/// Tracks the topics of an ongoing conversation.
+#[derive(Debug, Clone)]
+pub struct TopicTracker {
+    /// Topics mentioned in the most recent line.
+    active: Vec<Symbol>,
+    /// How many lines mentioned each topic.
+    mentions: HashMap<Symbol, usize>,
+    /// The number of mentions after which a topic is exhausted.
+    exhaustion_threshold: usize,
+}
+
+impl Default for TopicTracker {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl TopicTracker {
+    /// Creates a new tracker in which topics are exhausted after the given number of mentions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::topic::TopicTracker;
+    /// let tracker = TopicTracker::new(3);
+    /// ```
+    pub fn new(exhaustion_threshold: usize) -> Self {
+        TopicTracker {
+            active: Vec::new(),
+            mentions: HashMap::new(),
+            exhaustion_threshold: exhaustion_threshold.max(1),
+        }
+    }
+
+    /// Tags a conversation line with the topics it mentions.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line that was said.
+    /// * `topics` - The known topics.
+    ///
+    /// # Returns
+    ///
+    /// The IDs of the topics the line mentions, which become the active topics. If the line
+    /// mentions no known topic, the previous active topics are kept.
+    pub fn observe(&mut self, line: &str, topics: &[Topic]) -> &[Symbol] {
+        let mentioned: Vec<Symbol> = topics
+            .iter()
+            .filter(|topic| topic.matches(line))
+            .map(|topic| topic.id.clone())
+            .collect();
+        if !mentioned.is_empty() {
+            for id in &mentioned {
+                *self.mentions.entry(id.clone()).or_insert(0) += 1;
+            }
+            self.active = mentioned;
+        }
+        &self.active
+    }
+
+    /// Forgets the topics talked about, e.g. when a new conversation starts. The exhaustion
+    /// threshold is kept.
+    pub fn reset(&mut self) {
+        self.active.clear();
+        self.mentions.clear();
+    }
+
+    /// Gets the active topics.
+    pub fn get_active(&self) -> &[Symbol] {
+        &self.active
+    }
+
+    /// Returns true if a topic has been talked about enough to be exhausted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::topic::{Topic, TopicKind, TopicTracker};
+    /// let topics = vec![Topic::new("weather", TopicKind::Other, &["rain"])];
+    /// let mut tracker = TopicTracker::new(2);
+    /// tracker.observe("Looks like rain.", &topics);
+    /// tracker.observe("Rain again?", &topics);
+    /// assert!(tracker.is_exhausted("weather"));
+    /// ```
+    pub fn is_exhausted(&self, topic_id: &str) -> bool {
+        self.mentions.get(topic_id).copied().unwrap_or(0) >= self.exhaustion_threshold
+    }
+
+    /// Suggests a topic to switch to: the most interesting one that is neither active nor
+    /// exhausted, provided the NPC finds it interesting enough (at least 0.5).
+    ///
+    /// # Arguments
+    ///
+    /// * `topics` - The known topics.
+    /// * `personality` - The personality of the NPC.
+    /// * `knowledge` - The knowledge graph of the NPC.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::knowledge_graph::KnowledgeGraph;
+    /// use athena::personality::Personality;
+    /// use athena::topic::{Topic, TopicKind, TopicTracker};
+    ///
+    /// let mut personality = Personality::new();
+    /// personality.set_extraversion(0.9);
+    /// let topics = vec![
+    ///     Topic::new("weather", TopicKind::Other, &["rain"]),
+    ///     Topic::new("neighbours", TopicKind::Social, &["neighbour"]),
+    /// ];
+    /// let mut tracker = TopicTracker::new(1);
+    /// tracker.observe("Looks like rain.", &topics);
+    /// let suggestion = tracker.suggest_switch(&topics, &personality, &KnowledgeGraph::new());
+    /// assert_eq!(suggestion.unwrap().id, "neighbours");
+    /// ```
+    pub fn suggest_switch<'a>(
+        &self,
+        topics: &'a [Topic],
+        personality: &Personality,
+        knowledge: &KnowledgeGraph,
+    ) -> Option<&'a Topic> {
+        topics
+            .iter()
+            .filter(|topic| !self.active.contains(&topic.id) && !self.is_exhausted(&topic.id))
+            .map(|topic| (topic, topic.interest(personality, knowledge)))
+            .filter(|(_, interest)| *interest >= 0.5)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(topic, _)| topic)
+    }
+
+    /// Returns true if the conversation has run its course: every active topic is exhausted and
+    /// there is nothing interesting left to switch to.
+    pub fn should_end(
+        &self,
+        topics: &[Topic],
+        personality: &Personality,
+        knowledge: &KnowledgeGraph,
+    ) -> bool {
+        !self.active.is_empty()
+            && self.active.iter().all(|id| self.is_exhausted(id))
+            && self.suggest_switch(topics, personality, knowledge).is_none()
+    }
+}
@@ -0,0 +1,306 @@
+//! # Trading Module
+//!
+//! This module gives merchant NPCs knowledge about prices. Each merchant keeps a price belief per
+//! item in its knowledge graph: a base price plus its sense of current supply and demand. Prices
+//! follow supply and demand, so a player who dumps goods on a merchant or buys up its stock moves
+//! the price, and the merchant can explain its prices in dialogue.
+//!
+//! Haggling is built on top of the price beliefs: the merchant opens above its price, concedes
+//! according to its personality, and never sells below a floor it considers fair.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::knowledge_graph::{Entity, KnowledgeGraph};
+use crate::personality::Personality;
+
+/// The prefix of the knowledge graph entity IDs used for price beliefs (e.g. "item:iron").
+const ITEM_PREFIX: &str = "item:";
+
+/// How far supply and demand can push a price away from its base price, as a factor.
+const MAX_PRICE_FACTOR: f64 = 3.0;
+
+/// How much each unit traded by the player shifts the merchant's sense of supply.
+const SUPPLY_PER_UNIT: f64 = 0.05;
+
+/// Represents what a merchant believes about the market for one item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceBelief {
+    pub item: String,
+    /// The price under normal conditions.
+    pub base_price: f64,
+    /// How plentiful the item is (1.0 is normal).
+    pub supply: f64,
+    /// How sought-after the item is (1.0 is normal).
+    pub demand: f64,
+}
+
+impl PriceBelief {
+    /// Creates a belief for an item under normal market conditions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::trading::PriceBelief;
+    /// let belief = PriceBelief::new("iron", 10.0);
+    /// assert_eq!(belief.price(), 10.0);
+    /// ```
+    pub fn new(item: &str, base_price: f64) -> Self {
+        PriceBelief {
+            item: item.to_string(),
+            base_price,
+            supply: 1.0,
+            demand: 1.0,
+        }
+    }
+
+    /// Computes the current price from the base price, supply, and demand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::trading::PriceBelief;
+    /// let mut belief = PriceBelief::new("iron", 10.0);
+    /// belief.supply = 0.5;
+    /// assert_eq!(belief.price(), 20.0);
+    /// ```
+    pub fn price(&self) -> f64 {
+        let factor = self.demand / self.supply.max(f64::EPSILON);
+        self.base_price * factor.clamp(1.0 / MAX_PRICE_FACTOR, MAX_PRICE_FACTOR)
+    }
+
+    /// Explains the current price in words a shopkeeper could say.
+    pub fn justification(&self) -> String {
+        let item = &self.item;
+        let factor = self.price() / self.base_price.max(f64::EPSILON);
+        if factor >= 1.2 {
+            if self.supply < 1.0 {
+                format!("{} is scarce these days, so it costs more.", item)
+            } else {
+                format!("Everyone wants {} right now, so the price is up.", item)
+            }
+        } else if factor <= 0.8 {
+            if self.supply > 1.0 {
+                format!("I've more {} than I can sell, so it's cheap.", item)
+            } else {
+                format!("Nobody's buying {} lately, so I'll let it go cheap.", item)
+            }
+        } else {
+            format!("That's the usual price for {}.", item)
+        }
+    }
+}
+
+/// Gets a merchant's price belief for an item.
+pub fn price_belief(knowledge: &KnowledgeGraph, item: &str) -> Option<PriceBelief> {
+    let entity = knowledge.get_entity(&format!("{}{}", ITEM_PREFIX, item))?;
+    let property = |key: &str| entity.properties.get(key)?.parse().ok();
+    Some(PriceBelief {
+        item: item.to_string(),
+        base_price: property("base_price")?,
+        supply: property("supply")?,
+        demand: property("demand")?,
+    })
+}
+
+/// Stores a merchant's price belief for an item, replacing any previous belief.
+///
+/// # Examples
+///
+/// ```
+/// use athena::knowledge_graph::KnowledgeGraph;
+/// use athena::trading::{self, PriceBelief};
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// trading::set_price_belief(&mut knowledge, &PriceBelief::new("iron", 10.0));
+/// assert_eq!(trading::price_belief(&knowledge, "iron").unwrap().price(), 10.0);
+/// ```
+pub fn set_price_belief(knowledge: &mut KnowledgeGraph, belief: &PriceBelief) {
+    let mut properties = HashMap::new();
+    properties.insert("base_price".to_string(), belief.base_price.to_string());
+    properties.insert("supply".to_string(), belief.supply.to_string());
+    properties.insert("demand".to_string(), belief.demand.to_string());
+    knowledge.add_entity(Entity::new(format!("{}{}", ITEM_PREFIX, belief.item), properties));
+}
+
+/// Adjusts a merchant's price belief for an item, if it has one.
+fn update_belief(
+    knowledge: &mut KnowledgeGraph,
+    item: &str,
+    update: impl FnOnce(&mut PriceBelief),
+) {
+    if let Some(mut belief) = price_belief(knowledge, item) {
+        update(&mut belief);
+        set_price_belief(knowledge, &belief);
+    }
+}
+
+/// Records a supply fact (e.g. "the mine has flooded"), setting how plentiful an item is.
+pub fn record_supply(knowledge: &mut KnowledgeGraph, item: &str, supply: f64) {
+    update_belief(knowledge, item, |belief| belief.supply = supply.max(0.0));
+}
+
+/// Records a demand fact (e.g. "the army is buying swords"), setting how sought-after an item is.
+pub fn record_demand(knowledge: &mut KnowledgeGraph, item: &str, demand: f64) {
+    update_belief(knowledge, item, |belief| belief.demand = demand.max(0.0));
+}
+
+/// Records that the player sold units of an item to the merchant, which raises its supply.
+///
+/// Selling in bulk floods the merchant and drives the price down.
+///
+/// # Examples
+///
+/// ```
+/// use athena::knowledge_graph::KnowledgeGraph;
+/// use athena::trading::{self, PriceBelief};
+///
+/// let mut knowledge = KnowledgeGraph::new();
+/// trading::set_price_belief(&mut knowledge, &PriceBelief::new("iron", 10.0));
+/// trading::record_player_sale(&mut knowledge, "iron", 20);
+/// let belief = trading::price_belief(&knowledge, "iron").unwrap();
+/// assert_eq!(belief.price(), 5.0);
+/// assert!(belief.justification().contains("cheap"));
+/// ```
+pub fn record_player_sale(knowledge: &mut KnowledgeGraph, item: &str, quantity: u32) {
+    update_belief(knowledge, item, |belief| {
+        belief.supply += SUPPLY_PER_UNIT * quantity as f64;
+    });
+}
+
+/// Records that the player bought units of an item from the merchant, which lowers its supply.
+///
+/// Buying up stock makes the item scarce and drives the price up.
+pub fn record_player_purchase(knowledge: &mut KnowledgeGraph, item: &str, quantity: u32) {
+    update_belief(knowledge, item, |belief| {
+        belief.supply = (belief.supply - SUPPLY_PER_UNIT * quantity as f64).max(0.0);
+    });
+}
+
+/// Represents the merchant's answer to an offer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HaggleOutcome {
+    /// The merchant accepts the offer at this price.
+    Accept(f64),
+    /// The merchant makes a counter-offer, with a line justifying it.
+    Counter(f64, String),
+    /// The merchant refuses to keep haggling.
+    Refuse(String),
+}
+
+/// Represents a haggling session over one item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Haggle {
+    belief: PriceBelief,
+    /// The merchant's current asking price.
+    asking: f64,
+    /// The lowest price the merchant will accept.
+    floor: f64,
+    /// The fraction of the gap to the player's offer the merchant gives up per round.
+    concession: f64,
+    /// The number of offers made so far.
+    rounds: u32,
+    /// The number of offers after which the merchant loses patience.
+    max_rounds: u32,
+}
+
+impl Haggle {
+    /// Starts haggling over an item.
+    ///
+    /// Disagreeable merchants open higher, conscientious ones hold a firmer floor, and agreeable
+    /// ones concede more per round. Risk-averse merchants, afraid of losing the sale, concede
+    /// more too, and patient ones haggle for more rounds.
+    ///
+    /// # Arguments
+    ///
+    /// * `belief` - The merchant's price belief for the item.
+    /// * `personality` - The merchant's personality.
+    pub fn new(belief: PriceBelief, personality: &Personality) -> Self {
+        let price = belief.price();
+        let parameters = personality.decision_parameters();
+        let caution = 0.2 * (0.5 - parameters.risk_tolerance);
+        Haggle {
+            asking: price * (1.1 + 0.3 * (1.0 - personality.agreeableness)),
+            floor: price * (0.75 + 0.2 * personality.conscientiousness),
+            concession: (0.2 + 0.4 * personality.agreeableness + caution).clamp(0.0, 1.0),
+            rounds: 0,
+            max_rounds: 3 + (parameters.patience * 4.0).round() as u32,
+            belief,
+        }
+    }
+
+    /// Scales the merchant's asking price and floor, e.g. by `NpcAgent::price_factor` when the
+    /// player has annoyed it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::Personality;
+    /// use athena::trading::{Haggle, PriceBelief};
+    ///
+    /// let haggle = Haggle::new(PriceBelief::new("iron", 10.0), &Personality::new());
+    /// let asking = haggle.get_asking();
+    /// assert_eq!(haggle.with_surcharge(1.5).get_asking(), asking * 1.5);
+    /// ```
+    pub fn with_surcharge(mut self, factor: f64) -> Self {
+        let factor = factor.max(0.0);
+        self.asking *= factor;
+        self.floor *= factor;
+        self
+    }
+
+    /// Gets the merchant's current asking price.
+    pub fn get_asking(&self) -> f64 {
+        self.asking
+    }
+
+    /// Makes an offer.
+    ///
+    /// # Arguments
+    ///
+    /// * `offer` - The price the player offers.
+    ///
+    /// # Returns
+    ///
+    /// The merchant's answer: acceptance, a counter-offer, or a refusal if the offer is insulting
+    /// or the merchant has run out of patience.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::personality::Personality;
+    /// use athena::trading::{Haggle, HaggleOutcome, PriceBelief};
+    ///
+    /// let mut haggle = Haggle::new(PriceBelief::new("iron", 10.0), &Personality::new());
+    /// assert!(matches!(haggle.offer(2.0), HaggleOutcome::Refuse(_)));
+    ///
+    /// let mut haggle = Haggle::new(PriceBelief::new("iron", 10.0), &Personality::new());
+    /// let HaggleOutcome::Counter(counter, _) = haggle.offer(8.0) else { panic!() };
+    /// assert!(counter > 8.0);
+    /// assert_eq!(haggle.get_asking(), counter);
+    /// assert_eq!(haggle.offer(counter), HaggleOutcome::Accept(counter));
+    /// ```
+    pub fn offer(&mut self, offer: f64) -> HaggleOutcome {
+        self.rounds += 1;
+        if offer >= self.asking {
+            return HaggleOutcome::Accept(offer.min(self.asking));
+        }
+        if offer < self.floor * 0.6 {
+            return HaggleOutcome::Refuse(format!(
+                "That's an insult. {}",
+                self.belief.justification()
+            ));
+        }
+        if self.rounds > self.max_rounds {
+            let line = "We're going in circles. Take it or leave it.";
+            return HaggleOutcome::Refuse(line.to_string());
+        }
+        let counter = (self.asking - (self.asking - offer) * self.concession).max(self.floor);
+        if offer >= counter {
+            return HaggleOutcome::Accept(offer);
+        }
+        self.asking = counter;
+        HaggleOutcome::Counter(counter, self.belief.justification())
+    }
+}
@@ -0,0 +1,354 @@
+//! # Utility Module
+//!
+//! This module provides the shared utility-based selection system used across NPC subsystems.
+//! Each subsystem turns its options into scored candidates, and the selector picks the most
+//! useful one. Keeping the selection in one place means modifiers (emotions, personality,
+//! needs) compose the same way no matter which subsystem proposes the actions.
+
+use serde::{Deserialize, Serialize};
+
+/// Represents an action proposed for selection, together with its utility score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UtilityCandidate {
+    pub action: String,
+    pub score: f64,
+}
+
+impl UtilityCandidate {
+    /// Creates a new candidate with the given action name and score.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The name of the proposed action.
+    /// * `score` - The utility score of the action. Negative scores are treated as 0.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::utility::UtilityCandidate;
+    /// let candidate = UtilityCandidate::new("Dance", 0.8);
+    /// assert_eq!(candidate.action, "Dance");
+    /// ```
+    pub fn new(action: &str, score: f64) -> Self {
+        UtilityCandidate {
+            action: action.to_string(),
+            score: score.max(0.0),
+        }
+    }
+}
+
+/// Represents one factor that contributed to an action's score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Factor {
+    /// The subsystem the factor came from (e.g. "emotion", "personality", "state", "need").
+    pub source: String,
+    /// A human-readable description of the factor.
+    pub description: String,
+    /// The multiplier the factor applied to the score.
+    pub multiplier: f64,
+}
+
+/// Explains how an action's score was computed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    /// The action being explained.
+    pub action: String,
+    /// The score before any factor was applied.
+    pub base_score: f64,
+    /// Where the base score came from.
+    pub base_source: String,
+    /// The factors applied to the base score, in application order.
+    pub factors: Vec<Factor>,
+}
+
+impl Explanation {
+    /// Creates a new explanation with a base score and no factors.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The action being explained.
+    /// * `base_score` - The score before any factor was applied.
+    /// * `base_source` - Where the base score came from.
+    pub fn new(action: &str, base_score: f64, base_source: &str) -> Self {
+        Explanation {
+            action: action.to_string(),
+            base_score,
+            base_source: base_source.to_string(),
+            factors: Vec::new(),
+        }
+    }
+
+    /// Adds a factor to the explanation. Factors with a multiplier of exactly 1.0 are omitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The subsystem the factor came from.
+    /// * `description` - A human-readable description of the factor.
+    /// * `multiplier` - The multiplier the factor applies.
+    pub fn add_factor(&mut self, source: &str, description: &str, multiplier: f64) {
+        if multiplier != 1.0 {
+            self.factors.push(Factor {
+                source: source.to_string(),
+                description: description.to_string(),
+                multiplier,
+            });
+        }
+    }
+
+    /// Computes the final score: the base score multiplied by every factor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::utility::Explanation;
+    /// let mut explanation = Explanation::new("Hide", 1.0, "emotion Fear");
+    /// explanation.add_factor("personality", "neuroticism 0.9", 1.4);
+    /// assert_eq!(explanation.score(), 1.4);
+    /// ```
+    pub fn score(&self) -> f64 {
+        self.factors
+            .iter()
+            .fold(self.base_score, |score, factor| score * factor.multiplier)
+            .max(0.0)
+    }
+
+    /// Renders the explanation as a single line for logs and debug overlays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::utility::Explanation;
+    /// let mut explanation = Explanation::new("Hide", 1.0, "emotion Fear");
+    /// explanation.add_factor("personality", "neuroticism 0.9", 1.4);
+    /// assert_eq!(explanation.summary(), "Hide = 1.00 (emotion Fear) x1.40 [personality: neuroticism 0.9] = 1.40");
+    /// ```
+    pub fn summary(&self) -> String {
+        let mut summary = format!("{} = {:.2} ({})", self.action, self.base_score, self.base_source);
+        for factor in &self.factors {
+            summary.push_str(&format!(
+                " x{:.2} [{}: {}]",
+                factor.multiplier, factor.source, factor.description
+            ));
+        }
+        summary.push_str(&format!(" = {:.2}", self.score()));
+        summary
+    }
+
+    /// Converts the explanation into a scored candidate.
+    pub fn to_candidate(&self) -> UtilityCandidate {
+        UtilityCandidate::new(&self.action, self.score())
+    }
+}
+
+/// Multiplies each candidate's score by the modifier returned for its action.
+///
+/// # Arguments
+///
+/// * `candidates` - The candidates to modify in place.
+/// * `modifier` - A function returning a multiplier for an action name.
+///
+/// # Examples
+///
+/// ```
+/// use athena::utility::{apply_modifier, UtilityCandidate};
+/// let mut candidates = vec![UtilityCandidate::new("Hide", 1.0), UtilityCandidate::new("Shout", 1.0)];
+/// apply_modifier(&mut candidates, |action| if action == "Hide" { 0.5 } else { 1.0 });
+/// assert_eq!(candidates[0].score, 0.5);
+/// ```
+pub fn apply_modifier<F>(candidates: &mut [UtilityCandidate], modifier: F)
+where
+    F: Fn(&str) -> f64,
+{
+    for candidate in candidates.iter_mut() {
+        candidate.score = (candidate.score * modifier(&candidate.action)).max(0.0);
+    }
+}
+
+/// Selects the candidate with the highest score.
+///
+/// Ties are resolved in favour of the candidate listed first, so registries can express
+/// preference by ordering.
+///
+/// # Arguments
+///
+/// * `candidates` - The candidates to choose from.
+///
+/// # Returns
+///
+/// An `Option<&UtilityCandidate>` containing the best candidate, or `None` if there are none.
+///
+/// # Examples
+///
+/// ```
+/// use athena::utility::{select_best, UtilityCandidate};
+/// let candidates = vec![UtilityCandidate::new("Hide", 0.4), UtilityCandidate::new("Run", 0.9)];
+/// assert_eq!(select_best(&candidates).unwrap().action, "Run");
+/// ```
+pub fn select_best(candidates: &[UtilityCandidate]) -> Option<&UtilityCandidate> {
+    select_best_index(candidates).map(|index| &candidates[index])
+}
+
+/// Selects the index of the candidate with the highest score, breaking ties like `select_best`.
+/// Use it when the same action may be listed more than once and the pick must be told apart
+/// from its duplicates.
+///
+/// # Examples
+///
+/// ```
+/// use athena::utility::{select_best_index, UtilityCandidate};
+/// let candidates = vec![UtilityCandidate::new("Run", 0.4), UtilityCandidate::new("Run", 0.9)];
+/// assert_eq!(select_best_index(&candidates), Some(1));
+/// ```
+pub fn select_best_index(candidates: &[UtilityCandidate]) -> Option<usize> {
+    (0..candidates.len()).fold(None, |best: Option<usize>, index| match best {
+        Some(current) if candidates[current].score >= candidates[index].score => Some(current),
+        _ => Some(index),
+    })
+}
+
+/// Holds the candidates of many agents for scoring in bulk.
+///
+/// Candidates are laid out as columns (the agent, action, and score of every candidate, agent
+/// after agent) rather than one vector per agent, so scoring a crowd walks flat arrays. With
+/// the `parallel` feature, scores are modified and the best candidates selected in parallel.
+/// Actions are borrowed from the agents proposing them, so filling a batch every tick does not
+/// copy them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreBatch<'a> {
+    /// The agent index of each candidate.
+    agents: Vec<usize>,
+    actions: Vec<&'a str>,
+    scores: Vec<f64>,
+    /// The index of each agent's first candidate, followed by the number of candidates.
+    offsets: Vec<usize>,
+}
+
+impl Default for ScoreBatch<'_> {
+    fn default() -> Self {
+        ScoreBatch {
+            agents: Vec::new(),
+            actions: Vec::new(),
+            scores: Vec::new(),
+            offsets: vec![0],
+        }
+    }
+}
+
+impl<'a> ScoreBatch<'a> {
+    /// Creates a new, empty batch.
+    pub fn new() -> Self {
+        ScoreBatch::default()
+    }
+
+    /// Adds the next agent's candidates as `(action, base score)` pairs.
+    ///
+    /// # Returns
+    ///
+    /// The index of the agent in the batch.
+    pub fn push_agent(&mut self, candidates: impl IntoIterator<Item = (&'a str, f64)>) -> usize {
+        let agent = self.agent_count();
+        for (action, score) in candidates {
+            self.agents.push(agent);
+            self.actions.push(action);
+            self.scores.push(score.max(0.0));
+        }
+        self.offsets.push(self.scores.len());
+        agent
+    }
+
+    /// Returns the number of agents in the batch.
+    pub fn agent_count(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Returns the number of candidates of all agents.
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Returns true if the batch has no candidates.
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Gets the actions of an agent's candidates.
+    pub fn actions(&self, agent: usize) -> &[&'a str] {
+        &self.actions[self.offsets[agent]..self.offsets[agent + 1]]
+    }
+
+    /// Gets the scores of an agent's candidates.
+    pub fn scores(&self, agent: usize) -> &[f64] {
+        &self.scores[self.offsets[agent]..self.offsets[agent + 1]]
+    }
+
+    /// Gets an agent's candidates.
+    pub fn candidates(&self, agent: usize) -> Vec<UtilityCandidate> {
+        self.actions(agent)
+            .iter()
+            .zip(self.scores(agent))
+            .map(|(action, score)| UtilityCandidate::new(action, *score))
+            .collect()
+    }
+
+    /// Replaces every score with the one a function computes from the agent index, the action,
+    /// and the current score. Negative results are treated as 0.0.
+    pub fn apply<F>(&mut self, modifier: F)
+    where
+        F: Fn(usize, &str, f64) -> f64 + Sync,
+    {
+        let modify = |((score, action), agent): ((&mut f64, &&str), &usize)| {
+            *score = modifier(*agent, action, *score).max(0.0);
+        };
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            self.scores
+                .par_iter_mut()
+                .zip(self.actions.par_iter())
+                .zip(self.agents.par_iter())
+                .for_each(modify);
+        }
+        #[cfg(not(feature = "parallel"))]
+        self.scores
+            .iter_mut()
+            .zip(self.actions.iter())
+            .zip(self.agents.iter())
+            .for_each(modify);
+    }
+
+    /// Selects the best candidate of every agent, like `select_best`.
+    ///
+    /// # Returns
+    ///
+    /// The index of the best candidate within each agent's candidates, or `None` for agents
+    /// without candidates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::utility::ScoreBatch;
+    ///
+    /// let mut batch = ScoreBatch::new();
+    /// batch.push_agent(vec![("Hide", 0.4), ("Run", 0.9)]);
+    /// batch.push_agent(vec![("Hide", 0.8), ("Run", 0.3)]);
+    /// batch.push_agent(vec![]);
+    /// batch.apply(|agent, action, score| if agent == 0 && action == "Run" { 0.0 } else { score });
+    /// assert_eq!(batch.select_best(), vec![Some(0), Some(0), None]);
+    /// ```
+    pub fn select_best(&self) -> Vec<Option<usize>> {
+        let best = |agent: usize| {
+            let candidates = self.scores(agent);
+            (0..candidates.len()).fold(None, |best: Option<usize>, index| match best {
+                Some(current) if candidates[current] >= candidates[index] => Some(current),
+                _ => Some(index),
+            })
+        };
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            (0..self.agent_count()).into_par_iter().map(best).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        (0..self.agent_count()).map(best).collect()
+    }
+}
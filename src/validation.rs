@@ -0,0 +1,312 @@
+//! # Validation Module
+//!
+//! This module lints authored NPC definitions before they reach the game. Hand-written JSON goes
+//! wrong in quiet ways: a personality trait typed as 7 instead of 0.7 is clamped without a word,
+//! an action no state calls for is never taken, and a misspelled relation type is a fact no
+//! module will ever read. `validate` catches these and returns a structured report that editors
+//! and build pipelines can display or fail on.
+//!
+//! Errors are definitions that cannot mean what the author intended; warnings are definitions
+//! that are valid but probably mistaken. What counts as a known relation type or a referenced
+//! action depends on the game, so both can be extended through `ValidationRules`.
+//!
+//! Definitions carry no dialogue trees (NPC lines are generated, not authored as nodes), so
+//! there are no dialogue nodes whose reachability could be checked.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::adaptive_intelligence::STATE_ACTIONS;
+use crate::crime::ALERT_GUARDS_ACTION;
+use crate::definition::NpcDefinition;
+use crate::emotional_response::ActionRegistry;
+use crate::fact_check::FUNCTIONAL_RELATIONS;
+use crate::faction::FactionDecision;
+use crate::morality::MoralValues;
+use crate::perception::PlayerAction;
+use crate::quest::RESOLVED_AS;
+
+/// Represents how serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Represents a problem found in a definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// A stable identifier of the check (e.g. "out-of-range"), for filtering in tools.
+    pub code: String,
+    /// Where the problem is, as a path into the definition (e.g. "personality.openness").
+    pub path: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, code: &str, path: &str, message: String) -> Self {
+        Diagnostic {
+            severity,
+            code: code.to_string(),
+            path: path.to_string(),
+            message,
+        }
+    }
+
+    /// Describes the diagnostic in one line (e.g. "error[out-of-range] at personality.openness:
+    /// ...").
+    pub fn describe(&self) -> String {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        format!("{}[{}] at {}: {}", severity, self.code, self.path, self.message)
+    }
+}
+
+/// Represents the result of validating a definition.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationReport {
+    /// The ID of the definition validated.
+    pub definition: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Returns true if the definition has no errors. It may still have warnings.
+    pub fn is_valid(&self) -> bool {
+        !self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    /// Gets the diagnostics of a severity.
+    pub fn get_diagnostics(&self, severity: Severity) -> Vec<&Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == severity).collect()
+    }
+
+    fn push(&mut self, severity: Severity, code: &str, path: &str, message: String) {
+        self.diagnostics.push(Diagnostic::new(severity, code, path, message));
+    }
+}
+
+/// Represents what a game's definitions may refer to.
+#[derive(Debug, Clone)]
+pub struct ValidationRules {
+    /// The relation types facts may use.
+    pub relation_types: HashSet<String>,
+    /// Prefixes of families of relation types (e.g. "crime:").
+    pub relation_prefixes: Vec<String>,
+    /// Actions something calls for besides the built-in states and emotions, such as the game's
+    /// own states or needs.
+    pub referenced_actions: HashSet<String>,
+    /// The action registry proposing actions for emotions.
+    pub registry: ActionRegistry,
+}
+
+impl Default for ValidationRules {
+    /// Knows the relation types Athena's own modules read and write, and the actions of the
+    /// built-in states, the default action registry, crime reactions, and faction decisions.
+    fn default() -> Self {
+        let relation_types = [
+            "knows", "likes", "dislikes", "owns", "member_of", "friend", "rival", "trusts",
+            "discussed", "promised", "revealed", RESOLVED_AS,
+        ];
+        let relation_types = relation_types
+            .into_iter()
+            .chain(FUNCTIONAL_RELATIONS)
+            .chain(PlayerAction::RELATIONS);
+        let decisions = [
+            FactionDecision::DeclareRivalry(String::new()),
+            FactionDecision::RaisePrices,
+            FactionDecision::PostGuards,
+        ];
+        let actions = decisions.iter().filter_map(FactionDecision::action);
+        ValidationRules {
+            relation_types: relation_types.map(str::to_string).collect(),
+            relation_prefixes: vec!["crime:".to_string()],
+            referenced_actions: actions.chain([ALERT_GUARDS_ACTION]).map(str::to_string).collect(),
+            registry: ActionRegistry::default(),
+        }
+    }
+}
+
+impl ValidationRules {
+    /// Adds relation types facts may use.
+    pub fn with_relations<'a>(mut self, relations: impl IntoIterator<Item = &'a str>) -> Self {
+        self.relation_types.extend(relations.into_iter().map(str::to_string));
+        self
+    }
+
+    /// Adds actions the game's own states or needs call for.
+    pub fn with_actions<'a>(mut self, actions: impl IntoIterator<Item = &'a str>) -> Self {
+        self.referenced_actions.extend(actions.into_iter().map(str::to_string));
+        self
+    }
+
+    /// Returns true if facts may use a relation type.
+    pub fn knows_relation(&self, relation: &str) -> bool {
+        self.relation_types.contains(relation)
+            || self.relation_prefixes.iter().any(|prefix| relation.starts_with(prefix.as_str()))
+    }
+
+    /// Returns true if a state, an emotion, or the game calls for an action.
+    pub fn is_referenced(&self, action: &str) -> bool {
+        STATE_ACTIONS.iter().any(|(_, called)| *called == action)
+            || self.registry.actions().any(|proposed| proposed == action)
+            || self.referenced_actions.contains(action)
+    }
+}
+
+/// Reports values outside 0.0 to 1.0.
+fn check_range<'a>(
+    report: &mut ValidationReport,
+    section: &str,
+    values: impl IntoIterator<Item = (&'a str, f64)>,
+) {
+    for (name, value) in values {
+        if !(0.0..=1.0).contains(&value) {
+            report.push(
+                Severity::Error,
+                "out-of-range",
+                &format!("{}.{}", section, name),
+                format!("{} is outside 0.0 to 1.0", value),
+            );
+        }
+    }
+}
+
+/// Lists the values of a set of moral values by name.
+fn moral_values(morals: &MoralValues) -> [(&'static str, f64); 5] {
+    [
+        ("care", morals.care),
+        ("fairness", morals.fairness),
+        ("loyalty", morals.loyalty),
+        ("authority", morals.authority),
+        ("liberty", morals.liberty),
+    ]
+}
+
+/// Validates a definition.
+///
+/// # Arguments
+///
+/// * `definition` - The definition to lint.
+/// * `rules` - What the game's definitions may refer to.
+///
+/// # Examples
+///
+/// ```
+/// use athena::definition::NpcDefinition;
+/// use athena::validation::{validate, Severity, ValidationRules};
+///
+/// let definition = NpcDefinition::from_json(r#"{
+///     "id": "brom",
+///     "personality": { "openness": 7, "conscientiousness": 0.8, "extraversion": 0.4,
+///                      "agreeableness": 0.6, "neuroticism": 0.2 },
+///     "actions": [{ "name": "Talk", "description": "Chat." },
+///                 { "name": "Juggle", "description": "Show off." },
+///                 { "name": "AlertGuards", "description": "Call the watch." }],
+///     "facts": [["brom", "lives_in", "riverside"], ["brom", "wroks_at", "forge"],
+///               ["alice", "stole", "hammer"]]
+/// }"#).unwrap();
+///
+/// let report = validate(&definition, &ValidationRules::default());
+/// assert!(!report.is_valid());
+/// assert_eq!(report.get_diagnostics(Severity::Error)[0].path, "personality.openness");
+/// let warnings: Vec<&str> = report
+///     .get_diagnostics(Severity::Warning)
+///     .iter()
+///     .map(|d| d.code.as_str())
+///     .collect();
+/// assert_eq!(warnings, vec!["unreferenced-action", "unknown-relation"]);
+///
+/// let rules = ValidationRules::default().with_actions(["Juggle"]).with_relations(["wroks_at"]);
+/// assert_eq!(validate(&definition, &rules).diagnostics.len(), 1);
+/// ```
+pub fn validate(definition: &NpcDefinition, rules: &ValidationRules) -> ValidationReport {
+    let mut report = ValidationReport {
+        definition: definition.id.clone(),
+        diagnostics: Vec::new(),
+    };
+    if definition.id.trim().is_empty() {
+        report.push(Severity::Error, "missing-id", "id", "the NPC has no ID".to_string());
+    }
+
+    if let Some(sketch) = &definition.personality_sketch {
+        for problem in sketch.problems() {
+            report.push(Severity::Error, "invalid-sketch", "personality_sketch", problem);
+        }
+    }
+
+    let personality = &definition.personality;
+    check_range(
+        &mut report,
+        "personality",
+        [
+            ("openness", personality.openness),
+            ("conscientiousness", personality.conscientiousness),
+            ("extraversion", personality.extraversion),
+            ("agreeableness", personality.agreeableness),
+            ("neuroticism", personality.neuroticism),
+        ],
+    );
+    if let Some(morals) = &definition.morals {
+        check_range(&mut report, "morals", moral_values(morals));
+    }
+    if let Some(culture) = &definition.culture {
+        let values = culture.values.iter().map(|(name, value)| (name.as_str(), *value));
+        check_range(&mut report, "culture.values", values);
+        if let Some(morals) = &culture.morals {
+            check_range(&mut report, "culture.morals", moral_values(morals));
+        }
+    }
+
+    if let Some(palette) = &definition.palette {
+        let baselines = palette
+            .emotions()
+            .map(|emotion| (emotion.name(), palette.get_baseline(emotion)));
+        check_range(&mut report, "palette.baselines", baselines);
+    }
+
+    let mut names: HashSet<&str> = HashSet::new();
+    for (i, action) in definition.actions.iter().enumerate() {
+        let path = format!("actions[{}]", i);
+        if !names.insert(action.name.as_str()) {
+            report.push(
+                Severity::Warning,
+                "duplicate-action",
+                &path,
+                format!("\"{}\" is defined more than once", action.name),
+            );
+        } else if !rules.is_referenced(&action.name) {
+            report.push(
+                Severity::Warning,
+                "unreferenced-action",
+                &path,
+                format!("no state or emotion calls for \"{}\", so it is never taken", action.name),
+            );
+        }
+    }
+
+    for (i, (source, relation, target)) in definition.facts.iter().enumerate() {
+        let path = format!("facts[{}]", i);
+        if source.trim().is_empty() || target.trim().is_empty() {
+            report.push(
+                Severity::Error,
+                "empty-entity",
+                &path,
+                "the fact is missing its source or target".to_string(),
+            );
+        }
+        if !rules.knows_relation(relation) {
+            report.push(
+                Severity::Warning,
+                "unknown-relation",
+                &path,
+                format!("\"{}\" is not a known relation type", relation),
+            );
+        }
+    }
+    report
+}
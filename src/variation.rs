@@ -0,0 +1,162 @@
+//! # Variation Module
+//!
+//! This module tracks the lines an NPC has recently said, both generated and authored, so it can
+//! avoid repeating itself. Generated dialogue receives an "avoid repeating" directive listing
+//! recent lines for the topic, and authored dialogue rotates through its template variants,
+//! preferring the ones that were used least recently.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::dialogue_act::normalize;
+
+/// Tracks recently used lines per NPC, overall and per topic.
+#[derive(Debug, Clone)]
+pub struct RepetitionTracker {
+    /// How many recent lines are remembered, overall and per topic.
+    capacity: usize,
+    /// Recent lines across all topics, oldest first.
+    recent: VecDeque<String>,
+    /// Recent lines per topic, oldest first.
+    by_topic: HashMap<String, VecDeque<String>>,
+}
+
+impl Default for RepetitionTracker {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl RepetitionTracker {
+    /// Creates a new tracker remembering up to `capacity` lines overall and per topic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::variation::RepetitionTracker;
+    /// let tracker = RepetitionTracker::new(5);
+    /// ```
+    pub fn new(capacity: usize) -> Self {
+        RepetitionTracker {
+            capacity: capacity.max(1),
+            recent: VecDeque::new(),
+            by_topic: HashMap::new(),
+        }
+    }
+
+    /// Records that a line was said about a topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the line (e.g. "greeting").
+    /// * `line` - The line that was said.
+    pub fn record(&mut self, topic: &str, line: &str) {
+        push_bounded(&mut self.recent, line.to_string(), self.capacity);
+        let capacity = self.capacity;
+        push_bounded(
+            self.by_topic.entry(topic.to_string()).or_default(),
+            line.to_string(),
+            capacity,
+        );
+    }
+
+    /// Returns true if the line (ignoring case and punctuation) was said recently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::variation::RepetitionTracker;
+    /// let mut tracker = RepetitionTracker::new(5);
+    /// tracker.record("greeting", "Welcome, traveler!");
+    /// assert!(tracker.was_used_recently("welcome traveler"));
+    /// ```
+    pub fn was_used_recently(&self, line: &str) -> bool {
+        let normalized = normalize(line);
+        self.recent.iter().any(|used| normalize(used) == normalized)
+    }
+
+    /// Iterates over the recent lines for a topic, oldest first.
+    pub fn recent_for_topic<'a>(&'a self, topic: &str) -> impl Iterator<Item = &'a str> + use<'a> {
+        self.by_topic
+            .get(topic)
+            .into_iter()
+            .flat_map(|lines| lines.iter().map(|line| line.as_str()))
+    }
+
+    /// Builds a prompt directive asking the model not to repeat recent lines for a topic.
+    ///
+    /// # Returns
+    ///
+    /// The directive, or `None` if nothing was said about the topic recently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::variation::RepetitionTracker;
+    /// let mut tracker = RepetitionTracker::new(5);
+    /// tracker.record("greeting", "Welcome, traveler!");
+    /// assert!(tracker.avoid_directive("greeting").unwrap().contains("Welcome, traveler!"));
+    /// assert!(tracker.avoid_directive("farewell").is_none());
+    /// ```
+    pub fn avoid_directive(&self, topic: &str) -> Option<String> {
+        let lines: Vec<String> = self
+            .recent_for_topic(topic)
+            .map(|line| format!("\"{}\"", line))
+            .collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "Do not repeat or closely paraphrase these recent lines: {}",
+                lines.join(", ")
+            ))
+        }
+    }
+
+    /// Picks an authored variant for a topic, rotating away from recently used ones.
+    ///
+    /// The first variant not said recently is chosen; if all were said recently, the one said
+    /// longest ago is reused. The chosen variant is recorded.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic of the line.
+    /// * `variants` - The authored variants to choose from.
+    ///
+    /// # Returns
+    ///
+    /// The chosen variant, or `None` if `variants` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::variation::RepetitionTracker;
+    /// let mut tracker = RepetitionTracker::new(5);
+    /// let variants = ["Welcome!", "Good day!", "Hello there!"];
+    /// assert_eq!(tracker.pick_variant("greeting", &variants), Some("Welcome!"));
+    /// assert_eq!(tracker.pick_variant("greeting", &variants), Some("Good day!"));
+    /// assert_eq!(tracker.pick_variant("greeting", &variants), Some("Hello there!"));
+    /// assert_eq!(tracker.pick_variant("greeting", &variants), Some("Welcome!"));
+    /// ```
+    pub fn pick_variant<'a>(&mut self, topic: &str, variants: &[&'a str]) -> Option<&'a str> {
+        let recent: Vec<String> = self.recent_for_topic(topic).map(normalize).collect();
+        let last_used = |variant: &str| {
+            let normalized = normalize(variant);
+            recent.iter().rposition(|line| *line == normalized)
+        };
+        let chosen = variants
+            .iter()
+            .find(|variant| last_used(variant).is_none())
+            .or_else(|| variants.iter().min_by_key(|variant| last_used(variant)))
+            .copied()?;
+        self.record(topic, chosen);
+        Some(chosen)
+    }
+}
+
+/// Pushes a line, dropping the oldest if the queue is full.
+fn push_bounded(queue: &mut VecDeque<String>, line: String, capacity: usize) {
+    if queue.len() == capacity {
+        queue.pop_front();
+    }
+    queue.push_back(line);
+}
@@ -0,0 +1,264 @@
+//! # Voice Module
+//!
+//! This module selects authored voice-over lines. Lines are grouped by concept (e.g. "greeting",
+//! "farewell", "pain") and each line may require an emotion, an affinity range, and context tags.
+//! For a given situation the most specific matching line wins, and among equally specific lines
+//! the one heard least recently is played.
+//!
+//! The selection context is built from the same agent state the dialogue generator uses, so a game
+//! can play recorded audio whenever a matching line exists and fall back to generated dialogue
+//! when none does.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::NpcAgent;
+use crate::emotional_response::Emotion;
+use crate::variation::RepetitionTracker;
+
+/// Represents the situation a voice line is selected for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelectionContext {
+    /// What the NPC wants to express (e.g. "greeting").
+    pub concept: String,
+    pub emotion: Emotion,
+    /// The NPC's affinity towards the listener.
+    pub affinity: f64,
+    /// Facts about the situation (e.g. the current activity, state, topics, and dyads).
+    pub tags: Vec<String>,
+}
+
+impl SelectionContext {
+    /// Creates a new context for a concept with a neutral emotion and no tags.
+    pub fn new(concept: &str) -> Self {
+        SelectionContext {
+            concept: concept.to_string(),
+            emotion: Emotion::Neutral,
+            affinity: 0.0,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Builds the context from an agent's current state.
+    ///
+    /// The tags are the agent's decision state, its scheduled activity, the active conversation
+    /// topics, and the labels of its strong dyads.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent` - The speaking agent.
+    /// * `listener_id` - The ID of the listener, used for affinity.
+    /// * `concept` - What the NPC wants to express.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::agent::NpcAgent;
+    /// use athena::voice::SelectionContext;
+    ///
+    /// let mut agent = NpcAgent::new("innkeeper", vec![]);
+    /// agent.interlocutor("alice").adjust_affinity(0.4);
+    /// let context = SelectionContext::for_agent(&agent, "alice", "greeting");
+    /// assert_eq!(context.affinity, 0.4);
+    /// assert!(context.tags.contains(&"Idle".to_string()));
+    /// ```
+    pub fn for_agent(agent: &NpcAgent, listener_id: &str, concept: &str) -> Self {
+        let mut tags = vec![agent.intelligence.get_current_state().to_string()];
+        if let Some(block) = agent.schedule.current_block() {
+            tags.push(block.activity.clone());
+        }
+        if let Some(context) = agent.get_interlocutor(listener_id) {
+            tags.extend(context.topics.get_active().iter().map(|topic| topic.to_string()));
+        }
+        tags.extend(agent.emotions.get_dyad_labels(0.3));
+        SelectionContext {
+            concept: concept.to_string(),
+            emotion: agent.emotions.get_emotion().clone(),
+            affinity: agent
+                .get_interlocutor(listener_id)
+                .map_or(0.0, |context| context.affinity),
+            tags,
+        }
+    }
+}
+
+/// Represents an authored, recorded line and the conditions under which it may play.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceLine {
+    /// The audio asset to play.
+    pub asset: String,
+    /// The transcript of the line.
+    pub text: String,
+    /// The emotion the line was recorded with, if it requires one.
+    pub emotion: Option<Emotion>,
+    /// The minimum affinity towards the listener, if any.
+    pub min_affinity: Option<f64>,
+    /// The maximum affinity towards the listener, if any.
+    pub max_affinity: Option<f64>,
+    /// Tags that must all be present in the context.
+    pub tags: Vec<String>,
+}
+
+impl VoiceLine {
+    /// Creates a new line that may play in any situation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::voice::VoiceLine;
+    ///
+    /// let line = VoiceLine::new("vo/inn_greet_angry.ogg", "What do you want?")
+    ///     .with_emotion(Emotion::Anger)
+    ///     .with_affinity(None, Some(0.0));
+    /// ```
+    pub fn new(asset: &str, text: &str) -> Self {
+        VoiceLine {
+            asset: asset.to_string(),
+            text: text.to_string(),
+            emotion: None,
+            min_affinity: None,
+            max_affinity: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Requires the NPC to feel the given emotion.
+    pub fn with_emotion(mut self, emotion: Emotion) -> Self {
+        self.emotion = Some(emotion);
+        self
+    }
+
+    /// Requires the NPC's affinity towards the listener to be within a range.
+    pub fn with_affinity(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min_affinity = min;
+        self.max_affinity = max;
+        self
+    }
+
+    /// Requires a tag to be present in the context.
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Checks the line against a context.
+    ///
+    /// # Returns
+    ///
+    /// The number of conditions the line requires (its specificity), or `None` if any of them
+    /// is not met.
+    pub fn specificity(&self, context: &SelectionContext) -> Option<usize> {
+        let mut matched = 0;
+        if let Some(emotion) = &self.emotion {
+            if *emotion != context.emotion {
+                return None;
+            }
+            matched += 1;
+        }
+        if let Some(min) = self.min_affinity {
+            if context.affinity < min {
+                return None;
+            }
+            matched += 1;
+        }
+        if let Some(max) = self.max_affinity {
+            if context.affinity > max {
+                return None;
+            }
+            matched += 1;
+        }
+        for tag in &self.tags {
+            if !context.tags.contains(tag) {
+                return None;
+            }
+            matched += 1;
+        }
+        Some(matched)
+    }
+}
+
+/// Stores authored voice lines by concept.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VoiceBank {
+    lines: HashMap<String, Vec<VoiceLine>>,
+}
+
+impl VoiceBank {
+    /// Creates a new, empty voice bank.
+    pub fn new() -> Self {
+        VoiceBank::default()
+    }
+
+    /// Adds a line for a concept.
+    pub fn add_line(&mut self, concept: &str, line: VoiceLine) {
+        self.lines.entry(concept.to_string()).or_default().push(line);
+    }
+
+    /// Gets the lines for a concept.
+    pub fn get_lines(&self, concept: &str) -> &[VoiceLine] {
+        self.lines.get(concept).map_or(&[], |lines| lines.as_slice())
+    }
+
+    /// Selects the line to play for a context.
+    ///
+    /// The most specific matching line wins; ties are broken by playing the line heard least
+    /// recently. The chosen line is recorded in the tracker under the concept.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The situation to select a line for.
+    /// * `tracker` - The speaking NPC's repetition tracker.
+    ///
+    /// # Returns
+    ///
+    /// The chosen line, or `None` if no authored line matches (generate one instead).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use athena::emotional_response::Emotion;
+    /// use athena::variation::RepetitionTracker;
+    /// use athena::voice::{SelectionContext, VoiceBank, VoiceLine};
+    ///
+    /// let mut bank = VoiceBank::new();
+    /// bank.add_line("greeting", VoiceLine::new("greet_1.ogg", "Welcome!"));
+    /// bank.add_line("greeting", VoiceLine::new("greet_2.ogg", "Good day!"));
+    /// bank.add_line(
+    ///     "greeting",
+    ///     VoiceLine::new("greet_angry.ogg", "What do you want?").with_emotion(Emotion::Anger),
+    /// );
+    ///
+    /// let mut tracker = RepetitionTracker::default();
+    /// let mut context = SelectionContext::new("greeting");
+    /// assert_eq!(bank.select(&context, &mut tracker).unwrap().asset, "greet_1.ogg");
+    /// assert_eq!(bank.select(&context, &mut tracker).unwrap().asset, "greet_2.ogg");
+    ///
+    /// context.emotion = Emotion::Anger;
+    /// assert_eq!(bank.select(&context, &mut tracker).unwrap().asset, "greet_angry.ogg");
+    /// assert!(bank.select(&SelectionContext::new("farewell"), &mut tracker).is_none());
+    /// ```
+    pub fn select(
+        &self,
+        context: &SelectionContext,
+        tracker: &mut RepetitionTracker,
+    ) -> Option<&VoiceLine> {
+        let candidates: Vec<(&VoiceLine, usize)> = self
+            .get_lines(&context.concept)
+            .iter()
+            .filter_map(|line| line.specificity(context).map(|score| (line, score)))
+            .collect();
+        let best = candidates.iter().map(|(_, score)| *score).max()?;
+        let texts: Vec<&str> = candidates
+            .iter()
+            .filter(|(_, score)| *score == best)
+            .map(|(line, _)| line.text.as_str())
+            .collect();
+        let chosen = tracker.pick_variant(&context.concept, &texts)?;
+        candidates
+            .iter()
+            .find(|(line, score)| *score == best && line.text == chosen)
+            .map(|(line, _)| *line)
+    }
+}